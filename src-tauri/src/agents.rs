@@ -0,0 +1,276 @@
+//! Sub-agents
+//!
+//! The assistant can delegate a self-contained task, such as exploring the
+//! codebase, to a sub-agent with the `spawn_agent` tool. A sub-agent runs its
+//! own conversation with a dedicated system prompt, a restricted set of tools
+//! and its own budget, and its final answer is returned to the parent as the
+//! tool result, so the exploration itself never enters the parent's context.
+
+use futures::future::join_all;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::budget::{AgentRun, RunBudget, RunUsage};
+use crate::providers::scheduler::RequestScheduler;
+use crate::providers::{
+    pricing, ChatMessage, ContentBlock, Provider, ProviderError, Role, Tool, ToolCall,
+};
+use crate::tools::{
+    tool_result_is_error, RiskClass, ToolConcurrency, ToolContext, ToolDefinition, ToolRegistry,
+};
+
+/// Name of the tool that starts a sub-agent
+pub const SPAWN_AGENT_TOOL: &str = "spawn_agent";
+
+/// Sent when a sub-agent reaches its budget, so it reports instead of working on
+const BUDGET_REACHED_PROMPT: &str = "You have reached your budget. Do not call any more tools; \
+reply now with your report based on what you have found so far.";
+
+/// A kind of sub-agent the assistant can spawn
+#[derive(Debug, Clone, Copy)]
+pub struct AgentProfile {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub system_prompt: &'static str,
+    /// Risk classes of the tools the sub-agent may use; classes that require
+    /// approval are never given to a sub-agent
+    pub tools: &'static [RiskClass],
+    pub budget: RunBudget,
+}
+
+/// The sub-agents available to the assistant
+///
+/// Sub-agents only get tools that run without the user's approval, since
+/// nobody is watching their conversation to approve anything.
+pub const AGENT_PROFILES: &[AgentProfile] = &[
+    AgentProfile {
+        name: "explore",
+        description: "Explores the project with read-only tools and reports on its structure, \
+                      where things are implemented and how they fit together",
+        system_prompt: "You are a sub-agent exploring a codebase on behalf of another AI \
+assistant. Use the read-only tools to investigate the task you are given, then reply with a \
+concise, well-organized report: relevant files and directories with their purpose, key types and \
+functions, and how they connect. Cite file paths. Your reply is all the other assistant will see, \
+so include every finding it needs.",
+        tools: &[RiskClass::Read],
+        budget: RunBudget {
+            max_tool_calls: Some(40),
+            max_tokens: Some(400_000),
+            max_cost: None,
+            max_duration_secs: Some(300),
+        },
+    },
+    AgentProfile {
+        name: "research",
+        description: "Answers a question from the project's files, and reports what it found \
+                      with sources",
+        system_prompt: "You are a sub-agent researching a question on behalf of another AI \
+assistant. Use the read-only tools to find the answer in the project's files, then reply with a \
+concise report of your findings, citing file paths. Your reply is all the other assistant will \
+see, so include every finding it needs.",
+        tools: &[RiskClass::Read],
+        budget: RunBudget {
+            max_tool_calls: Some(30),
+            max_tokens: Some(300_000),
+            max_cost: None,
+            max_duration_secs: Some(300),
+        },
+    },
+];
+
+/// Find a sub-agent profile by name
+pub fn agent_profile(name: &str) -> Option<&'static AgentProfile> {
+    AGENT_PROFILES.iter().find(|p| p.name == name)
+}
+
+/// Definition of the `spawn_agent` tool
+pub fn spawn_agent_definition() -> ToolDefinition {
+    let agents: Vec<&str> = AGENT_PROFILES.iter().map(|p| p.name).collect();
+    let descriptions: Vec<String> = AGENT_PROFILES
+        .iter()
+        .map(|p| format!("{}: {}", p.name, p.description))
+        .collect();
+
+    ToolDefinition {
+        name: SPAWN_AGENT_TOOL.to_string(),
+        description: format!(
+            "Delegate a self-contained task to a sub-agent, which works on it with its own \
+             tools and returns a report. Use it for broad investigations whose details you \
+             don't need to see. Agents: {}",
+            descriptions.join("; ")
+        ),
+        parameters: json!({
+            "type": "object",
+            "properties": {
+                "agent": {
+                    "type": "string",
+                    "enum": agents,
+                    "description": "The kind of sub-agent to spawn"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "What the sub-agent should do and report back, with any context it needs"
+                }
+            },
+            "required": ["agent", "task"]
+        }),
+    }
+}
+
+/// The tools of `registry` a sub-agent may use
+pub fn restricted_registry(registry: &ToolRegistry, profile: &AgentProfile) -> ToolRegistry {
+    let mut restricted = registry.clone();
+    for definition in registry.definitions() {
+        let risk = registry.risk(&definition.name);
+        let allowed = profile.tools.contains(&risk)
+            && !risk.requires_approval()
+            && definition.name != SPAWN_AGENT_TOOL;
+        if !allowed {
+            restricted.unregister(&definition.name);
+        }
+    }
+    restricted
+}
+
+/// What a sub-agent reported back
+#[derive(Debug, Clone, Serialize)]
+pub struct SubAgentReport {
+    pub agent: String,
+    pub report: String,
+    /// Whether the sub-agent stopped because it reached its budget
+    pub budget_reached: bool,
+    pub usage: RunUsage,
+}
+
+/// Run a sub-agent on a task until it replies without calling tools
+///
+/// When the sub-agent reaches its budget, its tool calls are refused and it
+/// is asked for its report.
+///
+/// # Arguments
+/// * `provider` - Provider the sub-agent's requests are sent to
+/// * `registry` - Tools the sub-agent may use; see `restricted_registry`
+/// * `context` - Context the sub-agent's tools run in
+pub async fn run_sub_agent(
+    provider: &dyn Provider,
+    scheduler: &RequestScheduler,
+    registry: &ToolRegistry,
+    context: &ToolContext,
+    limits: &ToolConcurrency,
+    profile: &AgentProfile,
+    task: &str,
+) -> Result<SubAgentReport, ProviderError> {
+    let tools: Vec<Tool> = registry
+        .definitions()
+        .into_iter()
+        .map(|td| Tool::new(td.name, td.description, td.parameters))
+        .collect();
+    let mut run = AgentRun::new(profile.budget);
+    let mut messages = vec![
+        ChatMessage::system(profile.system_prompt),
+        ChatMessage::user(task),
+    ];
+    let mut budget_reached = false;
+
+    loop {
+        let response = {
+            let _permit = scheduler.acquire(provider.name(), |_| {}).await;
+            provider.chat(messages.clone(), Some(tools.clone())).await?
+        };
+        let cost = pricing::estimate_cost(&response.model, &response.usage);
+        run.record_response(&response.usage, cost);
+
+        let tool_calls = response.tool_calls();
+        if tool_calls.is_empty() || budget_reached {
+            return Ok(SubAgentReport {
+                agent: profile.name.to_string(),
+                report: response.text(),
+                budget_reached,
+                usage: run.usage(),
+            });
+        }
+        messages.push(ChatMessage::blocks(Role::Assistant, response.content));
+
+        budget_reached = run.exceeded(tool_calls.len() as u32).is_some();
+        let results = if budget_reached {
+            refuse_tool_calls(&tool_calls)
+        } else {
+            run.record_tool_calls(tool_calls.len() as u32);
+            run_tool_calls(registry, context, limits, tool_calls).await
+        };
+        messages.push(ChatMessage::blocks(Role::User, results));
+        if budget_reached {
+            messages.push(ChatMessage::user(BUDGET_REACHED_PROMPT));
+        }
+    }
+}
+
+/// Run a sub-agent's tool calls concurrently; its tools only read
+async fn run_tool_calls(
+    registry: &ToolRegistry,
+    context: &ToolContext,
+    limits: &ToolConcurrency,
+    tool_calls: Vec<ToolCall>,
+) -> Vec<ContentBlock> {
+    let outputs = join_all(tool_calls.into_iter().map(|tool_call| async move {
+        let id = tool_call.id.clone();
+        let output = registry
+            .execute_async(tool_call, context.clone(), limits)
+            .await;
+        (id, output)
+    }))
+    .await;
+
+    let mut blocks = Vec::new();
+    for (id, output) in outputs {
+        let is_error = tool_result_is_error(&output.content);
+        blocks.push(ContentBlock::ToolResult {
+            tool_use_id: id,
+            content: output.content,
+            is_error: if is_error { Some(true) } else { None },
+        });
+        blocks.extend(output.images);
+    }
+    blocks
+}
+
+/// Error results for tool calls made after the budget was reached
+fn refuse_tool_calls(tool_calls: &[ToolCall]) -> Vec<ContentBlock> {
+    tool_calls
+        .iter()
+        .map(|tool_call| ContentBlock::ToolResult {
+            tool_use_id: tool_call.id.clone(),
+            content: json!({ "success": false, "error": "Budget reached" }).to_string(),
+            is_error: Some(true),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restricted_registry_keeps_allowed_tools() {
+        let registry = ToolRegistry::with_builtin_tools();
+        let explore = agent_profile("explore").unwrap();
+        let restricted = restricted_registry(&registry, explore);
+
+        assert!(restricted.get("read_file").is_some());
+        assert!(restricted.get("write_file").is_none());
+        assert!(restricted
+            .definitions()
+            .iter()
+            .all(|td| restricted.risk(&td.name) == RiskClass::Read));
+
+        let research = restricted_registry(&registry, agent_profile("research").unwrap());
+        assert!(research.get("fetch_url").is_none());
+        assert!(research.get("create_pull_request").is_none());
+
+        let definition = spawn_agent_definition();
+        assert_eq!(
+            definition.parameters["properties"]["agent"]["enum"],
+            json!(["explore", "research"])
+        );
+    }
+}
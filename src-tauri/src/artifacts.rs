@@ -0,0 +1,199 @@
+//! File artifacts in assistant replies
+//!
+//! When the assistant shows a whole file as a fenced code block and says which
+//! file it is, the block is reported as an artifact the user can apply to the
+//! file in one click. The path can be given in the fence's info string
+//! (```` ```rust src/main.rs ````, ```` ```rust:src/main.rs ```` or
+//! ```` ```rust title="src/main.rs" ````) or on the line just before the fence
+//! (`` `src/main.rs`: ``, `**src/main.rs**` or `File: src/main.rs`).
+
+use serde::Serialize;
+
+/// A code block in a reply that holds the content of a file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileArtifact {
+    pub path: String,
+    pub language: Option<String>,
+    pub content: String,
+}
+
+/// Whether a string looks like a relative or absolute file path
+fn is_path_hint(hint: &str) -> bool {
+    !hint.is_empty()
+        && !hint.contains(char::is_whitespace)
+        && !hint.contains("://")
+        && (hint.contains('/') || hint.contains('.'))
+        && !hint.ends_with('.')
+}
+
+/// A file path given on its own line before a code block, if any
+fn path_from_line(line: &str) -> Option<String> {
+    let line = line.trim().trim_end_matches(':').trim();
+    let line = ["File:", "file:", "Filename:", "Path:"]
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .unwrap_or(line)
+        .trim();
+    let hint = line
+        .trim_matches(|c| c == '*' || c == '`' || c == '_')
+        .trim_end_matches(':');
+    is_path_hint(hint).then(|| hint.to_string())
+}
+
+/// The language and file path given in a fence's info string
+fn parse_info_string(info: &str) -> (Option<String>, Option<String>) {
+    let mut language = None;
+    let mut path = None;
+
+    for (i, token) in info.split_whitespace().enumerate() {
+        if let Some((key, value)) = token.split_once('=') {
+            if matches!(key, "title" | "file" | "filename" | "path") {
+                let value = value.trim_matches(|c| c == '"' || c == '\'');
+                path = path.or(is_path_hint(value).then(|| value.to_string()));
+            }
+        } else if let Some((lang, hint)) = token.split_once(':').filter(|_| i == 0) {
+            language = (!lang.is_empty()).then(|| lang.to_string());
+            path = is_path_hint(hint).then(|| hint.to_string());
+        } else if i == 0 && !is_path_hint(token) {
+            language = Some(token.to_string());
+        } else if path.is_none() && is_path_hint(token) {
+            path = Some(token.to_string());
+        }
+    }
+    (language, path)
+}
+
+/// A code block being read
+struct OpenBlock {
+    /// The fence that opened it, which must also close it
+    fence: String,
+    path: Option<String>,
+    language: Option<String>,
+    content: String,
+}
+
+/// Finds file artifacts in text as it streams in
+///
+/// Text is processed a line at a time, so an artifact is reported as soon as
+/// its closing fence arrives.
+#[derive(Default)]
+pub struct ArtifactExtractor {
+    /// Text of the current, incomplete line
+    pending: String,
+    /// The last non-empty line outside a code block
+    previous_line: String,
+    block: Option<OpenBlock>,
+}
+
+impl ArtifactExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add streamed text, returning the artifacts it completes
+    pub fn push(&mut self, text: &str) -> Vec<FileArtifact> {
+        self.pending.push_str(text);
+        let mut artifacts = Vec::new();
+        while let Some(end) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=end).collect();
+            artifacts.extend(self.process_line(line.trim_end_matches(['\n', '\r'])));
+        }
+        artifacts
+    }
+
+    /// End the text, returning an artifact completed by its last line
+    ///
+    /// A code block that is never closed isn't reported. The extractor is
+    /// ready for a new text afterwards.
+    pub fn finish(&mut self) -> Option<FileArtifact> {
+        let line = std::mem::take(&mut self.pending);
+        let artifact = self.process_line(&line);
+        *self = Self::default();
+        artifact
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<FileArtifact> {
+        let trimmed = line.trim_start();
+
+        if let Some(block) = &mut self.block {
+            // A closing fence uses the same character, at least as many times
+            let fence_char = block.fence.chars().next();
+            let closes = trimmed.trim_end().chars().all(|c| Some(c) == fence_char)
+                && trimmed.trim_end().len() >= block.fence.len();
+            if !closes {
+                block.content.push_str(line);
+                block.content.push('\n');
+                return None;
+            }
+            let block = self.block.take()?;
+            self.previous_line.clear();
+            return block.path.map(|path| FileArtifact {
+                path,
+                language: block.language,
+                content: block.content,
+            });
+        }
+
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let fence_len = fence_char.map_or(0, |c| trimmed.chars().take_while(|&x| x == c).count());
+        if fence_len >= 3 {
+            let (fence, info) = trimmed.split_at(fence_len);
+            let (language, path) = parse_info_string(info);
+            self.block = Some(OpenBlock {
+                fence: fence.to_string(),
+                path: path.or_else(|| path_from_line(&self.previous_line)),
+                language,
+                content: String::new(),
+            });
+        } else if !trimmed.is_empty() {
+            self.previous_line = trimmed.to_string();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_artifacts_from_streamed_text() {
+        let reply = "Here is the fix.\n\n**src/main.rs**:\n```rust\nfn main() {\n    \
+                     println!(\"```\");\n}\n```\n\nAnd an example:\n```sh\ncargo run\n```\n\
+                     ```toml Cargo.toml\n[package]\n```\n```python:scripts/run.py\nprint(1)\n```";
+
+        // Split the reply into small pieces as a stream would
+        let mut extractor = ArtifactExtractor::new();
+        let mut artifacts = Vec::new();
+        let chars: Vec<char> = reply.chars().collect();
+        for piece in chars.chunks(7) {
+            artifacts.extend(extractor.push(&piece.iter().collect::<String>()));
+        }
+        artifacts.extend(extractor.finish());
+
+        assert_eq!(
+            artifacts,
+            vec![
+                FileArtifact {
+                    path: "src/main.rs".to_string(),
+                    language: Some("rust".to_string()),
+                    content: "fn main() {\n    println!(\"```\");\n}\n".to_string(),
+                },
+                FileArtifact {
+                    path: "Cargo.toml".to_string(),
+                    language: Some("toml".to_string()),
+                    content: "[package]\n".to_string(),
+                },
+                FileArtifact {
+                    path: "scripts/run.py".to_string(),
+                    language: Some("python".to_string()),
+                    content: "print(1)\n".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            parse_info_string(" ts title=\"web/app.ts\""),
+            (Some("ts".to_string()), Some("web/app.ts".to_string()))
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Budgets for agent runs
+//!
+//! An agent run is the sequence of requests and tool calls the assistant makes
+//! while working on one task. A run can be given limits on tool calls, tokens,
+//! cost and time. When a limit is reached the run pauses until the user either
+//! lets it continue, which grants another budget of the same size, or stops it.
+
+use std::ops::Add;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::providers::Usage;
+
+/// Limits on an agent run; `None` means no limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunBudget {
+    pub max_tool_calls: Option<u32>,
+    /// Input plus output tokens across all requests
+    pub max_tokens: Option<u64>,
+    /// Estimated cost in US dollars
+    pub max_cost: Option<f64>,
+    /// Wall-clock time since the run started
+    pub max_duration_secs: Option<u64>,
+}
+
+/// The limit a run reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetLimit {
+    ToolCalls,
+    Tokens,
+    Cost,
+    Duration,
+}
+
+impl BudgetLimit {
+    /// Human-readable name of the limit
+    pub fn label(&self) -> &'static str {
+        match self {
+            BudgetLimit::ToolCalls => "tool call",
+            BudgetLimit::Tokens => "token",
+            BudgetLimit::Cost => "cost",
+            BudgetLimit::Duration => "time",
+        }
+    }
+}
+
+/// What a run has used so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunUsage {
+    pub tool_calls: u32,
+    pub tokens: u64,
+    /// Estimated cost in US dollars of the requests whose model price is known
+    pub cost: f64,
+    pub elapsed_secs: u64,
+}
+
+/// An agent run in progress
+#[derive(Debug)]
+pub struct AgentRun {
+    /// The budget the run was started with
+    budget: RunBudget,
+    /// Current limits; grow by `budget` each time the user lets the run continue
+    limits: RunBudget,
+    started: Instant,
+    tool_calls: u32,
+    tokens: u64,
+    cost: f64,
+}
+
+impl AgentRun {
+    /// Start a run with the given budget
+    pub fn new(budget: RunBudget) -> Self {
+        Self {
+            budget,
+            limits: budget,
+            started: Instant::now(),
+            tool_calls: 0,
+            tokens: 0,
+            cost: 0.0,
+        }
+    }
+
+    /// The run's current limits
+    pub fn limits(&self) -> RunBudget {
+        self.limits
+    }
+
+    /// What the run has used so far
+    pub fn usage(&self) -> RunUsage {
+        RunUsage {
+            tool_calls: self.tool_calls,
+            tokens: self.tokens,
+            cost: self.cost,
+            elapsed_secs: self.started.elapsed().as_secs(),
+        }
+    }
+
+    /// Count the tokens and cost of a response
+    pub fn record_response(&mut self, usage: &Usage, cost: Option<f64>) {
+        self.tokens += (usage.input_tokens + usage.output_tokens) as u64;
+        self.cost += cost.unwrap_or(0.0);
+    }
+
+    /// Count what a sub-agent used as part of this run
+    pub fn record_sub_agent(&mut self, usage: &RunUsage) {
+        self.tool_calls += usage.tool_calls;
+        self.tokens += usage.tokens;
+        self.cost += usage.cost;
+    }
+
+    /// Count tool calls that are about to run
+    pub fn record_tool_calls(&mut self, count: u32) {
+        self.tool_calls += count;
+    }
+
+    /// The first limit the run has reached, counting `pending_tool_calls`
+    /// that have not run yet
+    pub fn exceeded(&self, pending_tool_calls: u32) -> Option<BudgetLimit> {
+        self.exceeded_after(pending_tool_calls, self.started.elapsed())
+    }
+
+    fn exceeded_after(&self, pending_tool_calls: u32, elapsed: Duration) -> Option<BudgetLimit> {
+        let limits = &self.limits;
+        if limits
+            .max_tool_calls
+            .is_some_and(|max| self.tool_calls + pending_tool_calls > max)
+        {
+            Some(BudgetLimit::ToolCalls)
+        } else if limits.max_tokens.is_some_and(|max| self.tokens >= max) {
+            Some(BudgetLimit::Tokens)
+        } else if limits.max_cost.is_some_and(|max| self.cost >= max) {
+            Some(BudgetLimit::Cost)
+        } else if limits
+            .max_duration_secs
+            .is_some_and(|max| elapsed.as_secs() >= max)
+        {
+            Some(BudgetLimit::Duration)
+        } else {
+            None
+        }
+    }
+
+    /// Let the run continue past a limit by granting another budget
+    ///
+    /// Every limit is raised by the size of the original budget, so the run
+    /// pauses again after doing as much work once more.
+    pub fn extend(&mut self) {
+        let (limits, budget) = (&mut self.limits, &self.budget);
+        raise(&mut limits.max_tool_calls, budget.max_tool_calls);
+        raise(&mut limits.max_tokens, budget.max_tokens);
+        raise(&mut limits.max_cost, budget.max_cost);
+        raise(&mut limits.max_duration_secs, budget.max_duration_secs);
+    }
+}
+
+/// Raise a limit, if there is one, by `by`
+fn raise<T: Add<Output = T> + Copy>(limit: &mut Option<T>, by: Option<T>) {
+    if let (Some(limit), Some(by)) = (limit.as_mut(), by) {
+        *limit = *limit + by;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_pauses_at_limits_and_extends() {
+        let mut run = AgentRun::new(RunBudget {
+            max_tool_calls: Some(3),
+            max_tokens: Some(1000),
+            ..Default::default()
+        });
+
+        run.record_tool_calls(2);
+        assert_eq!(run.exceeded(1), None);
+        assert_eq!(run.exceeded(2), Some(BudgetLimit::ToolCalls));
+
+        run.record_response(
+            &Usage {
+                input_tokens: 900,
+                output_tokens: 100,
+            },
+            Some(0.01),
+        );
+        assert_eq!(run.exceeded(0), Some(BudgetLimit::Tokens));
+
+        run.extend();
+        assert_eq!(run.limits().max_tool_calls, Some(6));
+        assert_eq!(run.exceeded(4), None);
+        assert_eq!(run.usage().tokens, 1000);
+
+        let timed = AgentRun::new(RunBudget {
+            max_duration_secs: Some(60),
+            ..Default::default()
+        });
+        assert_eq!(timed.exceeded_after(0, Duration::from_secs(59)), None);
+        assert_eq!(
+            timed.exceeded_after(0, Duration::from_secs(60)),
+            Some(BudgetLimit::Duration)
+        );
+    }
+}
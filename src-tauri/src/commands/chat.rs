@@ -3,14 +3,51 @@
 //! This module provides Tauri commands for sending messages to AI providers
 //! and handling streaming responses.
 
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter, State};
 use futures::StreamExt;
 
-use crate::providers::{ChatChunk, ChatMessage, ChatResponse, ContentBlock, Role, Tool};
+use crate::agents::{self, SPAWN_AGENT_TOOL};
+use crate::artifacts::{ArtifactExtractor, FileArtifact};
+use crate::budget::{BudgetLimit, RunBudget, RunUsage};
+use crate::context::{self, CompactionInfo, ContextManager, InstructionFile};
+use crate::providers::{
+    self, pricing, scheduler::QueueStatus, ChatChunk, ChatMessage, ChatResponse, Citation, ContentBlock, ContentDelta,
+    ImageSource, MessageContent, Provider, Role, Tool, ToolCall, Usage,
+};
+use crate::slash::{self, SlashCommand, SlashCommandInfo};
 use crate::state::AppState;
-use crate::tools::{execute_tool_as_string, get_tool_definitions, tool_result_is_error};
+use crate::tools::{
+    self, read_file_content, tool_result_is_error, Checkpoint, FileContent, AuditEntry, EditRecord, PermissionDecision, PermissionRequest, RiskClass,
+    MemoryEntry, TodoItem, ToolContext, ToolOutput, ToolRegistry,
+};
+
+/// Event emitted when a tool call needs the user's approval
+pub const TOOL_PERMISSION_EVENT: &str = "tool-permission-request";
+
+/// Event emitted with a session's task list whenever the model changes it
+pub const TODOS_UPDATED_EVENT: &str = "todos-updated";
+
+/// Event emitted when an agent run reaches its budget and waits for the user
+pub const BUDGET_EXCEEDED_EVENT: &str = "budget-exceeded";
+
+/// Event emitted when an agent turn that changed files is checkpointed
+pub const CHECKPOINT_CREATED_EVENT: &str = "checkpoint-created";
+
+/// Event emitted while a non-streaming request waits for its provider's limits
+pub const PROVIDER_QUEUE_EVENT: &str = "provider-queue";
+
+/// Session used for "always allow" rules when the frontend doesn't pass one
+const DEFAULT_SESSION_ID: &str = "default";
+
+/// Shortest time between usage events while a response streams
+const USAGE_EVENT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait for the user to answer a permission request before denying it
+const PERMISSION_TIMEOUT: Duration = Duration::from_secs(300);
 
 /// Request payload for sending a chat message
 #[derive(Debug, Deserialize)]
@@ -26,25 +63,245 @@ pub struct SendMessageRequest {
     pub provider: Option<String>,
     #[serde(default)]
     pub model: Option<String>,
+    /// Project files to include with the last user message
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Most tokens to generate, for this request only
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Saved session whose provider, model, temperature and system prompt
+    /// apply unless the request gives its own
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 /// Input message format from frontend
 #[derive(Debug, Deserialize)]
 pub struct ChatMessageInput {
     pub role: String,
-    pub content: String,
+    pub content: MessageInputContent,
+}
+
+/// Message content from the frontend: plain text or content blocks
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum MessageInputContent {
+    Text(String),
+    Blocks(Vec<InputContentBlock>),
 }
 
-impl From<ChatMessageInput> for ChatMessage {
-    fn from(input: ChatMessageInput) -> Self {
-        let role = match input.role.as_str() {
+/// A content block from the frontend
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputContentBlock {
+    Text { text: String },
+    /// An image the frontend already has, e.g. a pasted screenshot
+    Image { source: ImageSource },
+    /// An image file, read and encoded here; relative paths are resolved
+    /// against the project root
+    ImageFile { path: String },
+}
+
+impl ChatMessageInput {
+    /// Convert to a provider message, reading any image files it refers to
+    fn into_message(self, project_root: Option<&Path>) -> Result<ChatMessage, String> {
+        let role = match self.role.as_str() {
             "system" => Role::System,
             "user" => Role::User,
             "assistant" => Role::Assistant,
             "tool" => Role::Tool,
             _ => Role::User,
         };
-        ChatMessage::text(role, input.content)
+        let blocks = match self.content {
+            MessageInputContent::Text(text) => return Ok(ChatMessage::text(role, text)),
+            MessageInputContent::Blocks(blocks) => blocks,
+        };
+
+        let blocks = blocks
+            .into_iter()
+            .map(|block| match block {
+                InputContentBlock::Text { text } => Ok(ContentBlock::Text { text }),
+                InputContentBlock::Image { source } => Ok(ContentBlock::Image { source }),
+                InputContentBlock::ImageFile { path } => {
+                    let path = match project_root {
+                        Some(root) => root.join(&path),
+                        None => Path::new(&path).to_path_buf(),
+                    };
+                    let path = path.to_string_lossy();
+                    match read_file_content(&path).map_err(|e| e.to_string())? {
+                        FileContent::Image {
+                            media_type, data, ..
+                        } => Ok(ContentBlock::Image {
+                            source: ImageSource::Base64 { media_type, data },
+                        }),
+                        _ => Err(format!(
+                            "{} is not a PNG, JPEG, GIF or WebP image under 5 MB",
+                            path
+                        )),
+                    }
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(ChatMessage::blocks(role, blocks))
+    }
+}
+
+/// Convert messages from the frontend, resolving image files against the project
+async fn convert_messages(
+    state: &AppState,
+    messages: Vec<ChatMessageInput>,
+) -> Result<Vec<ChatMessage>, String> {
+    let project_root = state.get_project_path().await;
+    messages
+        .into_iter()
+        .map(|m| m.into_message(project_root.as_deref()))
+        .collect()
+}
+
+/// Get the provider for a request, configured as its session is pinned to
+///
+/// Values in the request take precedence over the session's. The shared
+/// provider is never changed: when the model, temperature or max tokens has
+/// to change, the request gets its own copy, so concurrent requests can't
+/// affect each other's configuration.
+///
+/// # Returns
+/// The provider and the system prompt to use, if one was given or pinned
+async fn resolve_provider(
+    state: &AppState,
+    request: &SendMessageRequest,
+) -> Result<(Arc<dyn Provider>, Option<String>), String> {
+    let session = match (&request.session_id, state.session_store().await) {
+        (Some(id), Ok(store)) => store.load_session(id).ok(),
+        _ => None,
+    };
+    let pinned = session.as_ref();
+    let provider_name = request
+        .provider
+        .clone()
+        .or_else(|| pinned.and_then(|s| s.summary.provider.clone()));
+    let model = request
+        .model
+        .clone()
+        .or_else(|| pinned.and_then(|s| s.summary.model.clone()));
+    let temperature = request.temperature.or_else(|| pinned.and_then(|s| s.temperature));
+    let system_prompt = request
+        .system_prompt
+        .clone()
+        .or_else(|| pinned.and_then(|s| s.system_prompt.clone()));
+
+    let provider = match &provider_name {
+        Some(name) => state.get_provider(name).await,
+        None => state.get_active_provider().await,
+    };
+    let provider = provider.ok_or_else(|| "No AI provider configured".to_string())?;
+
+    let model = model.filter(|m| m != provider.model());
+    let temperature = temperature.filter(|t| *t != provider.temperature());
+    let max_tokens = request.max_tokens.filter(|m| *m != provider.max_tokens());
+    if model.is_none() && temperature.is_none() && max_tokens.is_none() {
+        return Ok((provider, system_prompt));
+    }
+
+    let mut configured = provider.boxed_clone();
+    if let Some(model) = model {
+        configured.set_model(&model);
+    }
+    if let Some(temperature) = temperature {
+        configured.set_temperature(temperature);
+    }
+    if let Some(max_tokens) = max_tokens {
+        configured.set_max_tokens(max_tokens);
+    }
+    Ok((Arc::from(configured), system_prompt))
+}
+
+/// The system prompt for a chat: the project's instruction files, then the
+/// requested prompt or the provider's default
+async fn build_system_prompt(
+    state: &AppState,
+    provider: &dyn Provider,
+    requested: Option<String>,
+) -> Option<String> {
+    let system_prompt = match requested {
+        Some(system) => Some(system),
+        None => state.default_system_prompt(provider.name(), provider.model()).await,
+    };
+    let instructions = state.project_instructions(false).await;
+    let system_prompt = context::system_prompt_with_instructions(&instructions, system_prompt);
+
+    // Facts the model saved about the project in earlier sessions
+    let memory = match state.project_memory().await {
+        Some(memory) => tools::memory_prompt(&memory.entries()),
+        None => None,
+    };
+    match (system_prompt, memory) {
+        (Some(system), Some(memory)) => Some(format!("{}\n\n{}", system, memory)),
+        (system, memory) => system.or(memory),
+    }
+}
+
+/// Read attached files and add them to the last user message
+async fn attach_files(
+    state: &AppState,
+    messages: &mut Vec<ChatMessage>,
+    attachments: &[String],
+) -> Result<(), String> {
+    if attachments.is_empty() {
+        return Ok(());
+    }
+    let project_root = state.get_project_path().await;
+    let blocks = context::read_attachments(project_root.as_deref(), attachments)
+        .map_err(|e| format!("Failed to attach file: {}", e))?;
+    context::inject_attachments(messages, blocks);
+    Ok(())
+}
+
+/// The tools to offer the model: the registry's, plus `spawn_agent`
+async fn enabled_tools(state: &AppState, enable_tools: bool) -> Option<Vec<Tool>> {
+    if !enable_tools {
+        return None;
+    }
+    let mut tool_defs = state.tool_registry.read().await.definitions();
+    tool_defs.push(agents::spawn_agent_definition());
+    Some(
+        tool_defs
+            .into_iter()
+            .map(|td| Tool::new(td.name, td.description, td.parameters))
+            .collect(),
+    )
+}
+
+/// Add the workspace preamble to the last user message, if enabled
+///
+/// Names the files open in the editor and those the AI edited recently in
+/// the session, with their diffs if the settings ask for them.
+async fn add_workspace_context(
+    state: &AppState,
+    messages: &mut Vec<ChatMessage>,
+    session_id: Option<&str>,
+) {
+    let settings = state.get_settings().await;
+    if !settings.workspace_context {
+        return;
+    }
+
+    let session_id = session_id.unwrap_or(DEFAULT_SESSION_ID);
+    let project_root = state.session_project_path(session_id).await;
+    let open_files = state.open_files.read().await.clone();
+    let edits = state.snapshot_store(session_id).await.edits();
+    let edited_files = context::recently_edited_files(&edits, project_root.as_deref());
+    let diffs = match &project_root {
+        Some(root) if settings.workspace_context_diffs => {
+            context::workspace_diffs(root, &edited_files)
+        }
+        _ => Vec::new(),
+    };
+
+    if let Some(preamble) = context::workspace_preamble(&open_files, &edited_files, &diffs) {
+        context::inject_attachments(messages, vec![ContentBlock::Text { text: preamble }]);
     }
 }
 
@@ -53,10 +310,129 @@ impl From<ChatMessageInput> for ChatMessage {
 pub struct ChatResponseOutput {
     pub id: String,
     pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
     pub tool_calls: Vec<ToolCallOutput>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub citations: Vec<Citation>,
     pub stop_reason: Option<String>,
     pub usage: UsageOutput,
     pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compaction: Option<CompactionInfo>,
+    /// The slash command that was run instead of sending the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slash_command: Option<SlashCommandOutcome>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<ResponseMetrics>,
+}
+
+/// How a response was served, for comparing providers
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseMetrics {
+    /// URL the request was sent to
+    pub endpoint: String,
+    /// Milliseconds from sending the request to the first streamed content;
+    /// `None` for responses that aren't streamed
+    pub time_to_first_token_ms: Option<u64>,
+    /// Milliseconds from sending the request to the end of the response,
+    /// including any retries
+    pub duration_ms: u64,
+    /// How many times the request was sent again after failing
+    pub retries: u32,
+}
+
+impl ChatResponseOutput {
+    /// The response to a message that was a slash command
+    fn for_slash_command(outcome: SlashCommandOutcome, model: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: String::new(),
+            thinking: None,
+            tool_calls: Vec::new(),
+            citations: Vec::new(),
+            stop_reason: None,
+            usage: UsageOutput {
+                input_tokens: 0,
+                output_tokens: 0,
+            },
+            model: model.to_string(),
+            compaction: None,
+            slash_command: Some(outcome),
+            metrics: None,
+        }
+    }
+}
+
+/// Result of a slash command the backend carried out
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum SlashCommandOutcome {
+    /// The frontend should replace the first `replaced_count` messages with
+    /// the summary; `None` if there was too little to compact
+    Compact { compaction: Option<CompactionInfo> },
+    /// The frontend should start the conversation over
+    Clear,
+    /// The model to send later messages with (as `SendMessageRequest::model`)
+    Model {
+        provider: String,
+        model: String,
+        available_models: Vec<String>,
+    },
+}
+
+/// Run a slash command in the last user message
+///
+/// Prompt templates are expanded in place and the message is sent as
+/// usual. Other commands are carried out here and their outcome returned;
+/// the message is then not sent to the provider.
+async fn run_slash_command(
+    state: &AppState,
+    provider: &dyn Provider,
+    messages: &mut Vec<ChatMessage>,
+) -> Result<Option<SlashCommandOutcome>, String> {
+    let Some(last) = messages.last_mut().filter(|m| m.role == Role::User) else {
+        return Ok(None);
+    };
+    let MessageContent::Text { content } = &mut last.content else {
+        return Ok(None);
+    };
+    let project_root = state.get_project_path().await;
+    let Some(command) = slash::parse_slash_command(content, project_root.as_deref())? else {
+        return Ok(None);
+    };
+
+    let outcome = match command {
+        SlashCommand::Prompt(prompt) => {
+            *content = prompt;
+            return Ok(None);
+        }
+        SlashCommand::Clear => SlashCommandOutcome::Clear,
+        SlashCommand::Compact => {
+            messages.pop();
+            let _permit = state.request_scheduler.acquire(provider.name(), |_| {}).await;
+            let compaction = context::compact_messages(
+                provider,
+                std::mem::take(messages),
+                context::DEFAULT_KEEP_RECENT,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            SlashCommandOutcome::Compact {
+                compaction: compaction.as_ref().map(CompactionInfo::from),
+            }
+        }
+        SlashCommand::Model { model } => SlashCommandOutcome::Model {
+            provider: provider.name().to_string(),
+            model: model.unwrap_or_else(|| provider.model().to_string()),
+            available_models: provider
+                .available_models()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        },
+    };
+    Ok(Some(outcome))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +451,8 @@ pub struct UsageOutput {
 impl From<ChatResponse> for ChatResponseOutput {
     fn from(response: ChatResponse) -> Self {
         let content = response.text();
+        let thinking = Some(response.thinking()).filter(|t| !t.is_empty());
+        let citations = response.citations();
         let tool_calls = response
             .tool_calls()
             .into_iter()
@@ -88,60 +466,186 @@ impl From<ChatResponse> for ChatResponseOutput {
         ChatResponseOutput {
             id: response.id,
             content,
+            thinking,
             tool_calls,
+            citations,
             stop_reason: response.stop_reason.map(|r| format!("{:?}", r)),
             usage: UsageOutput {
                 input_tokens: response.usage.input_tokens,
                 output_tokens: response.usage.output_tokens,
             },
             model: response.model,
+            compaction: None,
+            slash_command: None,
+            metrics: None,
         }
     }
 }
 
+/// Compact the conversation if it is approaching the provider's context window
+///
+/// Compaction failures are logged and the original messages are used, so a
+/// failed summary never blocks the request itself.
+async fn auto_compact(
+    state: &AppState,
+    provider: &dyn Provider,
+    messages: Vec<ChatMessage>,
+) -> (Vec<ChatMessage>, Option<CompactionInfo>) {
+    let settings = state.get_settings().await;
+    if !settings.auto_compact
+        || !context::should_compact(
+            &messages,
+            provider.context_window(),
+            settings.auto_compact_threshold,
+        )
+    {
+        return (messages, None);
+    }
+
+    let _permit = state.request_scheduler.acquire(provider.name(), |_| {}).await;
+    match context::compact_messages(provider, messages.clone(), context::DEFAULT_KEEP_RECENT).await {
+        Ok(Some(compaction)) => {
+            log::info!(
+                "Compacted conversation from ~{} to ~{} tokens",
+                compaction.tokens_before,
+                compaction.tokens_after
+            );
+            let info = CompactionInfo::from(&compaction);
+            (compaction.messages, Some(info))
+        }
+        Ok(None) => (messages, None),
+        Err(e) => {
+            log::warn!("Automatic compaction failed: {}", e);
+            (messages, None)
+        }
+    }
+}
+
+/// Truncate tool results and drop old exchanges until the messages fit the
+/// provider's context window
+fn fit_to_context(
+    provider: &dyn Provider,
+    messages: Vec<ChatMessage>,
+    tools: Option<&[Tool]>,
+) -> Vec<ChatMessage> {
+    let (messages, report) = ContextManager::for_provider(provider, tools).fit(messages);
+    if report.modified() {
+        log::info!(
+            "Fitted conversation to context: truncated {} tool results, dropped {} messages (~{} tokens)",
+            report.truncated_tool_results,
+            report.dropped_messages,
+            report.tokens
+        );
+    }
+    messages
+}
+
+/// Pause a session's agent run if it has reached its budget
+///
+/// The user is asked whether to continue; if they agree the run gets
+/// another budget of the same size, otherwise an error stops the request.
+///
+/// # Arguments
+/// * `pending_tool_calls` - Tool calls about to run, counted against the budget
+async fn enforce_run_budget(
+    app: &AppHandle,
+    state: &AppState,
+    session_id: &str,
+    pending_tool_calls: u32,
+) -> Result<(), String> {
+    let Some((limit, limits, usage)) = state.check_agent_run(session_id, pending_tool_calls).await
+    else {
+        return Ok(());
+    };
+
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let rx = state.register_budget_confirmation(&request_id).await;
+    let event = BudgetExceeded {
+        request_id: request_id.clone(),
+        session_id: session_id.to_string(),
+        limit,
+        limits,
+        usage,
+    };
+
+    let proceed = if app.emit(BUDGET_EXCEEDED_EVENT, &event).is_ok() {
+        matches!(tokio::time::timeout(PERMISSION_TIMEOUT, rx).await, Ok(Ok(true)))
+    } else {
+        false
+    };
+    if !proceed {
+        state.cancel_budget_confirmation(&request_id).await;
+        return Err(format!("Agent run stopped: {} budget reached", limit.label()));
+    }
+
+    state.extend_agent_run(session_id).await;
+    Ok(())
+}
+
 /// Send a message to the AI provider (non-streaming)
 #[tauri::command]
 pub async fn send_message(
+    app: AppHandle,
     state: State<'_, Arc<AppState>>,
     request: SendMessageRequest,
 ) -> Result<ChatResponseOutput, String> {
-    // Get the provider
-    let provider = if let Some(provider_name) = &request.provider {
-        state.get_provider(provider_name).await
-    } else {
-        state.get_active_provider().await
-    };
-
-    let provider = provider.ok_or_else(|| "No AI provider configured".to_string())?;
+    // Get the provider, configured as the session is pinned to
+    let (provider, system_prompt) = resolve_provider(&state, &request).await?;
 
     // Convert messages
-    let mut messages: Vec<ChatMessage> = request.messages.into_iter().map(|m| m.into()).collect();
+    let mut messages = convert_messages(&state, request.messages).await?;
 
-    // Add system prompt if provided
-    if let Some(system) = request.system_prompt {
+    // Commands other than prompt templates are handled without the provider
+    if let Some(outcome) = run_slash_command(&state, provider.as_ref(), &mut messages).await? {
+        return Ok(ChatResponseOutput::for_slash_command(outcome, provider.model()));
+    }
+    attach_files(&state, &mut messages, &request.attachments).await?;
+    add_workspace_context(&state, &mut messages, request.session_id.as_deref()).await;
+
+    // Add project instructions and the system prompt, falling back to the provider's default
+    if let Some(system) = build_system_prompt(&state, provider.as_ref(), system_prompt).await {
         messages.insert(0, ChatMessage::system(system));
     }
 
     // Get tools if enabled
-    let tools = if request.enable_tools {
-        let tool_defs = get_tool_definitions();
-        Some(
-            tool_defs
-                .into_iter()
-                .map(|td| Tool::new(td.name, td.description, td.parameters))
-                .collect(),
-        )
-    } else {
-        None
+    let tools = enabled_tools(&state, request.enable_tools).await;
+
+    // Compact older turns if the conversation is close to the context limit
+    let (messages, compaction) = auto_compact(&state, provider.as_ref(), messages).await;
+
+    // Trim whatever still doesn't fit so the API doesn't reject the request
+    let messages = fit_to_context(provider.as_ref(), messages, tools.as_deref());
+
+    let session_id = request.session_id.as_deref().unwrap_or(DEFAULT_SESSION_ID);
+    enforce_run_budget(&app, &state, session_id, 0).await?;
+
+    // Wait for the provider's concurrency and rate limits, then send the request
+    let _permit = state
+        .request_scheduler
+        .acquire(provider.name(), |status| {
+            let _ = app.emit(PROVIDER_QUEUE_EVENT, &status);
+        })
+        .await;
+    let started = Instant::now();
+    let (response, retries) =
+        providers::with_retries(|| provider.chat(messages.clone(), tools.clone())).await;
+    let response = response.map_err(|e| e.to_string())?;
+    let metrics = ResponseMetrics {
+        endpoint: provider.endpoint().to_string(),
+        time_to_first_token_ms: None,
+        duration_ms: started.elapsed().as_millis() as u64,
+        retries,
     };
 
-    // Send request
-    let response = provider
-        .chat(messages, tools)
-        .await
-        .map_err(|e| e.to_string())?;
+    let cost = pricing::estimate_cost(&response.model, &response.usage);
+    state
+        .record_run_response(session_id, &response.usage, cost)
+        .await;
 
-    Ok(response.into())
+    let mut output: ChatResponseOutput = response.into();
+    output.compaction = compaction;
+    output.metrics = Some(metrics);
+    Ok(output)
 }
 
 /// Send a message with streaming response
@@ -152,67 +656,244 @@ pub async fn send_message_stream(
     request: SendMessageRequest,
     stream_id: String,
 ) -> Result<(), String> {
-    // Get the provider
-    let provider = if let Some(provider_name) = &request.provider {
-        state.get_provider(provider_name).await
-    } else {
-        state.get_active_provider().await
-    };
-
-    let provider = provider.ok_or_else(|| "No AI provider configured".to_string())?;
+    // Get the provider, configured as the session is pinned to
+    let (provider, system_prompt) = resolve_provider(&state, &request).await?;
 
     // Convert messages
-    let mut messages: Vec<ChatMessage> = request.messages.into_iter().map(|m| m.into()).collect();
+    let mut messages = convert_messages(&state, request.messages).await?;
+
+    // Commands other than prompt templates are handled without the provider
+    if let Some(outcome) = run_slash_command(&state, provider.as_ref(), &mut messages).await? {
+        let event_name = format!("chat-stream-{}", stream_id);
+        let _ = app.emit(&event_name, &StreamEvent::SlashCommand(outcome));
+        let _ = app.emit(&event_name, &StreamEvent::Done);
+        return Ok(());
+    }
+    attach_files(&state, &mut messages, &request.attachments).await?;
+    add_workspace_context(&state, &mut messages, request.session_id.as_deref()).await;
 
-    // Add system prompt if provided
-    if let Some(system) = request.system_prompt {
+    // Add project instructions and the system prompt, falling back to the provider's default
+    if let Some(system) = build_system_prompt(&state, provider.as_ref(), system_prompt).await {
         messages.insert(0, ChatMessage::system(system));
     }
 
     // Get tools if enabled
-    let tools = if request.enable_tools {
-        let tool_defs = get_tool_definitions();
-        Some(
-            tool_defs
-                .into_iter()
-                .map(|td| Tool::new(td.name, td.description, td.parameters))
-                .collect(),
-        )
-    } else {
-        None
-    };
-
-    // Start streaming
-    let mut stream = provider
-        .chat_stream(messages, tools)
-        .await
-        .map_err(|e| e.to_string())?;
+    let tools = enabled_tools(&state, request.enable_tools).await;
 
-    // Process stream and emit events
     let event_name = format!("chat-stream-{}", stream_id);
 
-    while let Some(result) = stream.next().await {
-        match result {
-            Ok(chunk) => {
-                let event = StreamEvent::from_chunk(chunk);
-                if app.emit(&event_name, &event).is_err() {
+    // Compact older turns if the conversation is close to the context limit
+    let (messages, compaction) = auto_compact(&state, provider.as_ref(), messages).await;
+
+    // Trim whatever still doesn't fit so the API doesn't reject the request
+    let messages = fit_to_context(provider.as_ref(), messages, tools.as_deref());
+    if let Some(info) = compaction {
+        let _ = app.emit(&event_name, &StreamEvent::Compacted(info));
+    }
+
+    let mut usage = UsageTracker::new(
+        provider.model(),
+        context::estimate_conversation_tokens(&messages),
+    );
+
+    let session_id = request.session_id.as_deref().unwrap_or(DEFAULT_SESSION_ID);
+    if let Err(e) = enforce_run_budget(&app, &state, session_id, 0).await {
+        let _ = app.emit(&event_name, &StreamEvent::Error { message: e.clone() });
+        return Err(e);
+    }
+
+    // Register the stream so it can be cancelled without affecting others
+    let mut cancelled = state.register_stream(&stream_id).await?;
+    let result: Result<(), String> = async {
+        // Wait for the provider's limits; the slot is held until the stream ends
+        let _permit = tokio::select! {
+            permit = state.request_scheduler.acquire(provider.name(), |status| {
+                let _ = app.emit(&event_name, &StreamEvent::Queued(status));
+            }) => permit,
+            _ = &mut cancelled => {
+                let _ = app.emit(&event_name, &StreamEvent::Cancelled);
+                let _ = app.emit(&event_name, &StreamEvent::Done);
+                return Ok(());
+            }
+        };
+
+        // Start streaming, retrying if the request fails before the stream starts
+        let started = Instant::now();
+        let (stream, retries) = tokio::select! {
+            result = providers::with_retries(|| {
+                provider.chat_stream(messages.clone(), tools.clone())
+            }) => result,
+            _ = &mut cancelled => {
+                let _ = app.emit(&event_name, &StreamEvent::Cancelled);
+                let _ = app.emit(&event_name, &StreamEvent::Done);
+                return Ok(());
+            }
+        };
+        let mut stream = stream.map_err(|e| e.to_string())?;
+        let mut first_token: Option<Duration> = None;
+
+        // Process stream and emit events
+        let mut artifacts = ArtifactExtractor::new();
+
+        loop {
+            let result = tokio::select! {
+                next = stream.next() => match next {
+                    Some(result) => result,
+                    None => break,
+                },
+                _ = &mut cancelled => {
+                    let _ = app.emit(&event_name, &StreamEvent::Cancelled);
+                    break;
+                }
+            };
+            match result {
+                Ok(chunk) => {
+                    usage.observe(&chunk);
+                    if first_token.is_none()
+                        && matches!(chunk, ChatChunk::ContentBlockDelta { .. })
+                    {
+                        first_token = Some(started.elapsed());
+                    }
+                    let found = match &chunk {
+                        ChatChunk::ContentBlockDelta {
+                            delta: ContentDelta::TextDelta { text },
+                            ..
+                        } => artifacts.push(text),
+                        ChatChunk::ContentBlockStop { .. } => {
+                            artifacts.finish().into_iter().collect()
+                        }
+                        _ => Vec::new(),
+                    };
+                    let event = StreamEvent::from_chunk(chunk);
+                    if app.emit(&event_name, &event).is_err() {
+                        break;
+                    }
+                    for artifact in found {
+                        let _ = app.emit(&event_name, &StreamEvent::FileArtifact(artifact));
+                    }
+                    if let Some(event) = usage.periodic_event() {
+                        let _ = app.emit(&event_name, &event);
+                    }
+                }
+                Err(e) => {
+                    let event = StreamEvent::Error {
+                        message: e.to_string(),
+                    };
+                    let _ = app.emit(&event_name, &event);
                     break;
                 }
             }
-            Err(e) => {
-                let event = StreamEvent::Error {
-                    message: e.to_string(),
-                };
-                let _ = app.emit(&event_name, &event);
-                break;
+        }
+
+        // Send the final totals, then the completion event
+        let cost = pricing::estimate_cost(&usage.model, &usage.usage);
+        state.record_run_response(session_id, &usage.usage, cost).await;
+        let _ = app.emit(&event_name, &usage.event());
+        let metrics = ResponseMetrics {
+            endpoint: provider.endpoint().to_string(),
+            time_to_first_token_ms: first_token.map(|d| d.as_millis() as u64),
+            duration_ms: started.elapsed().as_millis() as u64,
+            retries,
+        };
+        let _ = app.emit(&event_name, &StreamEvent::Metrics(metrics));
+        let _ = app.emit(&event_name, &StreamEvent::Done);
+
+        Ok(())
+    }
+    .await;
+
+    state.end_stream(&stream_id).await;
+    result
+}
+
+/// Cancel a streaming response
+///
+/// Other streams are unaffected. The stream sends a `cancelled` event, then
+/// its usage so far and `done`.
+#[tauri::command]
+pub async fn cancel_stream(state: State<'_, Arc<AppState>>, stream_id: String) -> Result<(), String> {
+    state.cancel_stream(&stream_id).await
+}
+
+/// Keeps a running count of a streamed response's tokens for usage events
+///
+/// Output tokens are estimated from the streamed text until the provider
+/// reports real counts, which usually come only at the end.
+struct UsageTracker {
+    model: String,
+    usage: Usage,
+    estimated_output_tokens: u32,
+    reported_output_tokens: Option<u32>,
+    last_emitted: Instant,
+    changed: bool,
+}
+
+impl UsageTracker {
+    fn new(model: &str, estimated_input_tokens: u32) -> Self {
+        Self {
+            model: model.to_string(),
+            usage: Usage {
+                input_tokens: estimated_input_tokens,
+                output_tokens: 0,
+            },
+            estimated_output_tokens: 0,
+            reported_output_tokens: None,
+            last_emitted: Instant::now(),
+            changed: false,
+        }
+    }
+
+    fn observe(&mut self, chunk: &ChatChunk) {
+        let (reported, delta_text) = match chunk {
+            ChatChunk::MessageStart { model, usage, .. } => {
+                self.model = model.clone();
+                (usage.as_ref(), None)
             }
+            ChatChunk::MessageDelta { usage, .. } => (usage.as_ref(), None),
+            ChatChunk::ContentBlockDelta { delta, .. } => match delta {
+                ContentDelta::TextDelta { text } => (None, Some(text)),
+                ContentDelta::InputJsonDelta { partial_json } => (None, Some(partial_json)),
+                ContentDelta::ThinkingDelta { thinking } => (None, Some(thinking)),
+                _ => (None, None),
+            },
+            _ => (None, None),
+        };
+
+        if let Some(text) = delta_text {
+            self.estimated_output_tokens += context::estimate_tokens(text);
+            self.changed = true;
         }
+        if let Some(reported) = reported {
+            if reported.input_tokens > 0 {
+                self.usage.input_tokens = reported.input_tokens;
+            }
+            if reported.output_tokens > 0 {
+                self.reported_output_tokens = Some(reported.output_tokens);
+            }
+            self.changed = true;
+        }
+        self.usage.output_tokens = self
+            .reported_output_tokens
+            .unwrap_or(self.estimated_output_tokens);
     }
 
-    // Send completion event
-    let _ = app.emit(&event_name, &StreamEvent::Done);
+    fn event(&self) -> StreamEvent {
+        StreamEvent::Usage {
+            input_tokens: self.usage.input_tokens,
+            output_tokens: self.usage.output_tokens,
+            estimated_cost: pricing::estimate_cost(&self.model, &self.usage),
+        }
+    }
 
-    Ok(())
+    /// A usage event, if the counts changed and one hasn't been sent recently
+    fn periodic_event(&mut self) -> Option<StreamEvent> {
+        if !self.changed || self.last_emitted.elapsed() < USAGE_EVENT_INTERVAL {
+            return None;
+        }
+        self.changed = false;
+        self.last_emitted = Instant::now();
+        Some(self.event())
+    }
 }
 
 /// Stream event sent to frontend
@@ -223,8 +904,30 @@ pub enum StreamEvent {
     ContentBlockStart { index: usize, block_type: String },
     TextDelta { index: usize, text: String },
     ToolUseDelta { index: usize, partial_json: String },
+    ThinkingDelta { index: usize, thinking: String },
+    SignatureDelta { index: usize, signature: String },
+    Citation { index: usize, citation: Citation },
     ContentBlockStop { index: usize },
     MessageDelta { stop_reason: Option<String> },
+    /// Older turns were replaced with a summary before sending
+    Compacted(CompactionInfo),
+    /// The message was a slash command, run instead of sending it
+    SlashCommand(SlashCommandOutcome),
+    /// The request is waiting for the provider's concurrency or rate limits
+    Queued(QueueStatus),
+    /// A code block in the reply holds the content of a file
+    FileArtifact(FileArtifact),
+    /// Latency and retries of the response, sent when it ends
+    Metrics(ResponseMetrics),
+    /// The stream was cancelled; usage so far and `done` follow
+    Cancelled,
+    /// Tokens used so far; estimated until the provider reports its counts
+    Usage {
+        input_tokens: u32,
+        output_tokens: u32,
+        /// US dollars, if the model's price is known
+        estimated_cost: Option<f64>,
+    },
     Error { message: String },
     Done,
 }
@@ -232,13 +935,16 @@ pub enum StreamEvent {
 impl StreamEvent {
     fn from_chunk(chunk: ChatChunk) -> Self {
         match chunk {
-            ChatChunk::MessageStart { id, model } => StreamEvent::MessageStart { id, model },
+            ChatChunk::MessageStart { id, model, .. } => StreamEvent::MessageStart { id, model },
             ChatChunk::ContentBlockStart { index, content_block } => {
                 let block_type = match content_block {
                     ContentBlock::Text { .. } => "text",
                     ContentBlock::ToolUse { .. } => "tool_use",
                     ContentBlock::Image { .. } => "image",
                     ContentBlock::ToolResult { .. } => "tool_result",
+                    ContentBlock::Thinking { .. } => "thinking",
+                    ContentBlock::RedactedThinking { .. } => "redacted_thinking",
+                    ContentBlock::Citation(_) => "citation",
                 };
                 StreamEvent::ContentBlockStart {
                     index,
@@ -246,12 +952,21 @@ impl StreamEvent {
                 }
             }
             ChatChunk::ContentBlockDelta { index, delta } => match delta {
-                crate::providers::ContentDelta::TextDelta { text } => {
+                ContentDelta::TextDelta { text } => {
                     StreamEvent::TextDelta { index, text }
                 }
-                crate::providers::ContentDelta::InputJsonDelta { partial_json } => {
+                ContentDelta::InputJsonDelta { partial_json } => {
                     StreamEvent::ToolUseDelta { index, partial_json }
                 }
+                ContentDelta::ThinkingDelta { thinking } => {
+                    StreamEvent::ThinkingDelta { index, thinking }
+                }
+                ContentDelta::SignatureDelta { signature } => {
+                    StreamEvent::SignatureDelta { index, signature }
+                }
+                ContentDelta::CitationDelta { citation } => {
+                    StreamEvent::Citation { index, citation }
+                }
             },
             ChatChunk::ContentBlockStop { index } => StreamEvent::ContentBlockStop { index },
             ChatChunk::MessageDelta { stop_reason, .. } => StreamEvent::MessageDelta {
@@ -264,31 +979,461 @@ impl StreamEvent {
     }
 }
 
+/// Ask the frontend to approve a tool call and wait for the decision
+///
+/// The call is denied if the event can't be delivered or nobody answers in time.
+async fn request_tool_permission(
+    app: &AppHandle,
+    state: &AppState,
+    session_id: &str,
+    tool_call: &ToolCall,
+    risk: RiskClass,
+) -> PermissionDecision {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let rx = state.register_permission_request(&request_id).await;
+
+    let request = PermissionRequest {
+        request_id: request_id.clone(),
+        session_id: session_id.to_string(),
+        tool_use_id: tool_call.id.clone(),
+        tool_name: tool_call.name.clone(),
+        arguments: tool_call.arguments.clone(),
+        risk,
+    };
+
+    if let Err(e) = app.emit(TOOL_PERMISSION_EVENT, &request) {
+        log::error!("Failed to request permission for {}: {}", tool_call.name, e);
+        state.cancel_permission_request(&request_id).await;
+        return PermissionDecision::Deny;
+    }
+
+    match tokio::time::timeout(PERMISSION_TIMEOUT, rx).await {
+        Ok(Ok(decision)) => decision,
+        _ => {
+            log::warn!("Permission request for {} was not answered", tool_call.name);
+            state.cancel_permission_request(&request_id).await;
+            PermissionDecision::Deny
+        }
+    }
+}
+
 /// Execute tool calls from an AI response
+///
+/// Write and execute tools need the user's approval unless they were
+/// "always allowed" earlier in the session; see `respond_tool_permission`.
+/// Approval is asked for every call up front. Consecutive read and network
+/// calls then run concurrently, while writes and commands run one at a time
+/// in order, so each sees the effects of the calls before it.
+///
+/// If the calls changed any files, they are saved as a checkpoint the
+/// workspace can later be restored to.
 #[tauri::command]
 pub async fn execute_tool_calls(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
     tool_calls: Vec<ToolCallOutput>,
+    session_id: Option<String>,
 ) -> Result<Vec<ToolResultOutput>, String> {
-    let mut results = Vec::new();
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+    enforce_run_budget(&app, &state, &session_id, tool_calls.len() as u32).await?;
+    state
+        .record_run_tool_calls(&session_id, tool_calls.len() as u32)
+        .await;
+
+    let snapshots = state.snapshot_store(&session_id).await;
+    let settings = state.get_settings().await;
+    // Tools may use every workspace root; relative paths resolve against the
+    // first, or against the session's worktree, which replaces it
+    let mut roots = state.workspace_roots().await.into_iter();
+    let project = roots.next();
+    let project = state.session_worktree(&session_id).await.or(project);
+    let allowed_paths = roots.chain(settings.tool_allowed_paths).collect();
+    let context = ToolContext::jailed(project, allowed_paths)
+        .with_snapshots(snapshots.clone())
+        .with_format_on_write(settings.format_on_ai_write)
+        .with_pre_commit_command(settings.pre_commit_command)
+        .with_todos(state.todo_list(&session_id).await);
+    let context = match state.project_memory().await {
+        Some(memory) => context.with_memory(memory),
+        None => context,
+    };
+    // Clone so the lock isn't held while tools run
+    let registry = state.tool_registry.read().await.clone();
+
+    let mut results: Vec<Option<ToolResultOutput>> = Vec::new();
+    let mut approved = Vec::new();
 
     for tc in tool_calls {
-        let tool_call = crate::providers::ToolCall {
-            id: tc.id.clone(),
+        let tool_call = ToolCall {
+            id: tc.id,
             name: tc.name,
             arguments: tc.arguments,
         };
 
-        let result = execute_tool_as_string(&tool_call);
-        let is_error = tool_result_is_error(&result);
+        // Sub-agents only get tools that need no approval
+        let risk = if tool_call.name == SPAWN_AGENT_TOOL {
+            RiskClass::Read
+        } else {
+            registry.risk(&tool_call.name)
+        };
+        if risk.requires_approval() && !state.is_tool_allowed(&session_id, &tool_call.name).await {
+            match request_tool_permission(&app, &state, &session_id, &tool_call, risk).await {
+                PermissionDecision::AllowOnce => {}
+                PermissionDecision::AlwaysAllow => {
+                    state.allow_tool(&session_id, &tool_call.name).await;
+                }
+                PermissionDecision::Deny => {
+                    results.push(Some(ToolResultOutput {
+                        tool_use_id: tool_call.id,
+                        content: serde_json::json!({
+                            "success": false,
+                            "error": format!("The user denied permission to run {}", tool_call.name)
+                        })
+                        .to_string(),
+                        is_error: true,
+                        images: Vec::new(),
+                    }));
+                    continue;
+                }
+            }
+        }
 
-        results.push(ToolResultOutput {
-            tool_use_id: tc.id,
-            content: result,
-            is_error,
-        });
+        approved.push((results.len(), tool_call));
+        results.push(None);
+    }
+
+    let mut pending = approved.into_iter().peekable();
+    while let Some((index, tool_call)) = pending.next() {
+        if !registry.runs_concurrently(&tool_call.name) {
+            // Each call is one undoable edit
+            snapshots.begin(&tool_call.id, &tool_call.name);
+            let output = run_tool_call(&app, &state, &registry, &context, &session_id, tool_call).await;
+            snapshots.finish();
+            results[index] = Some(output);
+            continue;
+        }
+
+        let mut batch = vec![(index, tool_call)];
+        while let Some((index, tool_call)) =
+            pending.next_if(|(_, next)| registry.runs_concurrently(&next.name))
+        {
+            batch.push((index, tool_call));
+        }
+
+        let outputs = futures::future::join_all(batch.into_iter().map(|(index, tool_call)| {
+            let (app, state, registry, context) = (&app, &state, &registry, &context);
+            let session_id = &session_id;
+            async move {
+                let output = run_tool_call(app, state, registry, context, session_id, tool_call).await;
+                (index, output)
+            }
+        }))
+        .await;
+        for (index, output) in outputs {
+            results[index] = Some(output);
+        }
+    }
+
+    if let Some(checkpoint) = snapshots.checkpoint() {
+        let created = CheckpointCreated {
+            session_id: session_id.clone(),
+            checkpoint,
+        };
+        let _ = app.emit(CHECKPOINT_CREATED_EVENT, &created);
+    }
+
+    Ok(results.into_iter().flatten().collect())
+}
+
+/// Run one approved tool call, recording it in the audit log
+async fn run_tool_call(
+    app: &AppHandle,
+    state: &AppState,
+    registry: &ToolRegistry,
+    context: &ToolContext,
+    session_id: &str,
+    tool_call: ToolCall,
+) -> ToolResultOutput {
+    let (id, tool_name, arguments) = (
+        tool_call.id.clone(),
+        tool_call.name.clone(),
+        tool_call.arguments.clone(),
+    );
+    let (started, timer) = (SystemTime::now(), Instant::now());
+
+    let output = if tool_name == SPAWN_AGENT_TOOL {
+        spawn_sub_agent(state, registry, context, session_id, &arguments).await
+    } else {
+        registry
+            .execute_async(tool_call, context.clone(), &state.tool_concurrency)
+            .await
+    };
+    let is_error = tool_result_is_error(&output.content);
+
+    let entry = AuditEntry::new(
+        &id,
+        &tool_name,
+        &arguments,
+        started,
+        timer.elapsed(),
+        &output.content,
+        !is_error,
+    );
+    state.record_tool_call(session_id, &entry).await;
+
+    if tool_name == "manage_todos" && !is_error {
+        let update = TodosUpdated {
+            session_id: session_id.to_string(),
+            todos: state.todo_list(session_id).await.items(),
+        };
+        let _ = app.emit(TODOS_UPDATED_EVENT, &update);
     }
 
-    Ok(results)
+    ToolResultOutput {
+        tool_use_id: id,
+        content: output.content,
+        is_error,
+        images: output.images,
+    }
+}
+
+/// Run a `spawn_agent` call and return the sub-agent's report as the result
+///
+/// The sub-agent uses the session's provider, and what it uses counts
+/// against the session's agent run.
+async fn spawn_sub_agent(
+    state: &AppState,
+    registry: &ToolRegistry,
+    context: &ToolContext,
+    session_id: &str,
+    arguments: &serde_json::Value,
+) -> ToolOutput {
+    let error = |message: String| ToolOutput {
+        content: serde_json::json!({ "success": false, "error": message }).to_string(),
+        images: Vec::new(),
+    };
+
+    let agent = arguments.get("agent").and_then(|v| v.as_str()).unwrap_or("");
+    let Some(profile) = agents::agent_profile(agent) else {
+        return error(format!("Unknown agent: {}", agent));
+    };
+    let Some(task) = arguments.get("task").and_then(|v| v.as_str()) else {
+        return error("Missing required argument: task".to_string());
+    };
+    let pinned = match state.session_store().await {
+        Ok(store) => store
+            .load_session(session_id)
+            .ok()
+            .and_then(|s| s.summary.provider),
+        Err(_) => None,
+    };
+    let provider = match pinned {
+        Some(name) => state.get_provider(&name).await,
+        None => state.get_active_provider().await,
+    };
+    let Some(provider) = provider else {
+        return error("No AI provider configured".to_string());
+    };
+
+    let tools = agents::restricted_registry(registry, profile);
+    let result = agents::run_sub_agent(
+        provider.as_ref(),
+        &state.request_scheduler,
+        &tools,
+        context,
+        &state.tool_concurrency,
+        profile,
+        task,
+    )
+    .await;
+    match result {
+        Ok(report) => {
+            state.record_run_sub_agent(session_id, &report.usage).await;
+            let content = serde_json::json!({
+                "success": true,
+                "agent": report.agent,
+                "report": report.report,
+                "budget_reached": report.budget_reached,
+                "usage": report.usage,
+            });
+            ToolOutput {
+                content: content.to_string(),
+                images: Vec::new(),
+            }
+        }
+        Err(e) => error(format!("Sub-agent failed: {}", e)),
+    }
+}
+
+/// Answer a pending tool permission request
+#[tauri::command]
+pub async fn respond_tool_permission(
+    state: State<'_, Arc<AppState>>,
+    request_id: String,
+    decision: PermissionDecision,
+) -> Result<(), String> {
+    state.resolve_permission_request(&request_id, decision).await
+}
+
+/// Start an agent run in a session with limits on its tool calls, tokens,
+/// cost and time
+///
+/// Requests and tool calls in the session count against the budget until
+/// `end_agent_run`. When a limit is reached, a `budget-exceeded` event is sent
+/// and the run waits for `respond_budget_exceeded`.
+#[tauri::command]
+pub async fn start_agent_run(
+    state: State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+    budget: RunBudget,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+    state.start_agent_run(&session_id, budget).await;
+    Ok(())
+}
+
+/// End a session's agent run, returning what it used
+#[tauri::command]
+pub async fn end_agent_run(
+    state: State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+) -> Result<Option<RunUsage>, String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+    Ok(state.end_agent_run(&session_id).await)
+}
+
+/// Continue or stop an agent run that reached its budget
+#[tauri::command]
+pub async fn respond_budget_exceeded(
+    state: State<'_, Arc<AppState>>,
+    request_id: String,
+    proceed: bool,
+) -> Result<(), String> {
+    state.resolve_budget_confirmation(&request_id, proceed).await
+}
+
+/// Forget the "always allow" rules of a session
+#[tauri::command]
+pub async fn clear_tool_permissions(
+    state: State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+    state.clear_tool_permissions(&session_id).await;
+    Ok(())
+}
+
+/// List the undoable edits the AI made in a session, oldest first
+#[tauri::command]
+pub async fn list_session_edits(
+    state: State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+) -> Result<Vec<EditRecord>, String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+    Ok(state.snapshot_store(&session_id).await.edits())
+}
+
+/// Undo the most recent file edit the AI made in a session
+///
+/// Returns the undone edit, or `None` if there is nothing left to undo.
+#[tauri::command]
+pub async fn undo_last_edit(
+    state: State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+) -> Result<Option<EditRecord>, String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+    let snapshots = state.snapshot_store(&session_id).await;
+    snapshots.undo_last().map_err(|e| e.to_string())
+}
+
+/// Undo every file edit the AI made in a session, returning the restored paths
+#[tauri::command]
+pub async fn undo_all_session_edits(
+    state: State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+) -> Result<Vec<String>, String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+    let snapshots = state.snapshot_store(&session_id).await;
+    let restored = snapshots.undo_all().map_err(|e| e.to_string())?;
+    Ok(restored
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+/// List the checkpoints of a session's agent turns that changed files, oldest first
+#[tauri::command]
+pub async fn list_checkpoints(
+    state: State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+) -> Result<Vec<Checkpoint>, String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+    Ok(state.snapshot_store(&session_id).await.checkpoints())
+}
+
+/// Rewind the workspace to how it was after a checkpointed turn
+///
+/// Every AI edit made since is undone. Returns the restored paths.
+#[tauri::command]
+pub async fn restore_checkpoint(
+    state: State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+    checkpoint_id: String,
+) -> Result<Vec<String>, String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+    let snapshots = state.snapshot_store(&session_id).await;
+    let restored = snapshots
+        .restore_checkpoint(&checkpoint_id)
+        .map_err(|e| e.to_string())?;
+    Ok(restored
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+/// Get the audit log of tool calls executed in a session, oldest first
+#[tauri::command]
+pub async fn get_tool_audit_log(
+    state: State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+) -> Result<Vec<AuditEntry>, String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+    state.tool_audit_log(&session_id).await
+}
+
+/// Get the model's task list for a session
+#[tauri::command]
+pub async fn get_todos(
+    state: State<'_, Arc<AppState>>,
+    session_id: Option<String>,
+) -> Result<Vec<TodoItem>, String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_SESSION_ID.to_string());
+    Ok(state.todo_list(&session_id).await.items())
+}
+
+/// Payload of the event sent when the model changes its task list
+#[derive(Debug, Clone, Serialize)]
+pub struct TodosUpdated {
+    pub session_id: String,
+    pub todos: Vec<TodoItem>,
+}
+
+/// Payload of the event sent when an agent run reaches its budget
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetExceeded {
+    /// ID to pass to `respond_budget_exceeded`
+    pub request_id: String,
+    pub session_id: String,
+    pub limit: BudgetLimit,
+    pub limits: RunBudget,
+    pub usage: RunUsage,
+}
+
+/// Payload of the event sent when an agent turn is checkpointed
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckpointCreated {
+    pub session_id: String,
+    pub checkpoint: Checkpoint,
 }
 
 /// Tool result to send back to the AI
@@ -297,6 +1442,10 @@ pub struct ToolResultOutput {
     pub tool_use_id: String,
     pub content: String,
     pub is_error: bool,
+    /// Images the tool returned (e.g. from reading an image file), to send
+    /// as image blocks alongside the tool result
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ContentBlock>,
 }
 
 /// Get available providers
@@ -364,3 +1513,86 @@ pub async fn set_provider_model(
 
     Ok(())
 }
+
+/// List the slash commands available in the current project, for autocomplete
+#[tauri::command]
+pub async fn list_slash_commands(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<SlashCommandInfo>, String> {
+    let project_root = state.get_project_path().await;
+    Ok(slash::list_slash_commands(project_root.as_deref()))
+}
+
+/// Get the project instruction files added to every chat's system prompt
+///
+/// Pass `rediscover` to search the project again for new or removed files.
+#[tauri::command]
+pub async fn get_loaded_instructions(
+    state: State<'_, Arc<AppState>>,
+    rediscover: Option<bool>,
+) -> Result<Vec<InstructionFile>, String> {
+    Ok(state.project_instructions(rediscover.unwrap_or(false)).await)
+}
+
+/// Report the files open in the editor, relative to the project root
+///
+/// They are named in the workspace context of later requests.
+#[tauri::command]
+pub async fn set_open_files(
+    state: State<'_, Arc<AppState>>,
+    paths: Vec<String>,
+) -> Result<(), String> {
+    *state.open_files.write().await = paths;
+    Ok(())
+}
+
+/// List the facts remembered about the current project
+#[tauri::command]
+pub async fn list_memories(state: State<'_, Arc<AppState>>) -> Result<Vec<MemoryEntry>, String> {
+    Ok(state
+        .project_memory()
+        .await
+        .map(|memory| memory.entries())
+        .unwrap_or_default())
+}
+
+/// Forget a fact remembered about the current project
+#[tauri::command]
+pub async fn delete_memory(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    let memory = state
+        .project_memory()
+        .await
+        .ok_or_else(|| "No project is open".to_string())?;
+    memory.forget(&id).map_err(|e| e.to_string())
+}
+
+/// Summarize older turns of a conversation to free up context
+///
+/// The frontend should replace the first `replaced_count` non-system messages
+/// with the returned summary.
+#[tauri::command]
+pub async fn compact_conversation(
+    state: State<'_, Arc<AppState>>,
+    messages: Vec<ChatMessageInput>,
+    provider: Option<String>,
+    keep_recent: Option<usize>,
+) -> Result<Option<CompactionInfo>, String> {
+    let provider = if let Some(provider_name) = &provider {
+        state.get_provider(provider_name).await
+    } else {
+        state.get_active_provider().await
+    };
+
+    let provider = provider.ok_or_else(|| "No AI provider configured".to_string())?;
+
+    let messages = convert_messages(&state, messages).await?;
+    let keep_recent = keep_recent.unwrap_or(context::DEFAULT_KEEP_RECENT);
+
+    let _permit = state.request_scheduler.acquire(provider.name(), |_| {}).await;
+
+    let compaction = context::compact_messages(provider.as_ref(), messages, keep_recent)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(compaction.as_ref().map(CompactionInfo::from))
+}
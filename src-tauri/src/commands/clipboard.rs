@@ -0,0 +1,18 @@
+//! Clipboard commands
+//!
+//! This module provides Tauri commands for reading and writing the system
+//! clipboard.
+
+use crate::tools::clipboard;
+
+/// Read the text on the clipboard
+#[tauri::command]
+pub async fn read_clipboard() -> Result<String, String> {
+    clipboard::read_clipboard().map_err(|e| e.to_string())
+}
+
+/// Replace the clipboard with text
+#[tauri::command]
+pub async fn write_clipboard(text: String) -> Result<(), String> {
+    clipboard::write_clipboard(&text).map_err(|e| e.to_string())
+}
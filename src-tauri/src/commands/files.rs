@@ -1,22 +1,78 @@
 //! File operation commands
 //!
 //! This module provides Tauri commands for file operations including
-//! reading, writing, listing directories, and searching.
+//! reading, writing, listing directories, and searching. Paths are confined
+//! to the project unless a command is called with `allow_outside_project`.
 
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use serde::Serialize;
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
 
+use crate::recent::{FileActivity, RecentFile};
 use crate::state::AppState;
-use crate::tools::{file_ops, search, FileEntry, GlobMatch, SearchResult};
+use crate::commands::git::{GitStatusChanged, GIT_STATUS_CHANGED_EVENT};
+use crate::watcher::{ProjectWatcher, FS_CHANGE_EVENT};
+use crate::tools::executor::resolve_path;
+use crate::tools::{
+    archive, batch, checksum, diff, file_ops, fuzzy, loc, patch, search, DuplicateGroup, FileDiff, FileEntry, FileRange, FileVersion, FuzzyMatch, GlobMatch, LocReport, PatchResult, SearchLimits,
+    SearchPage, SearchResult, ToolContext, BatchResult, FileOp, LARGE_FILE_BYTES, LARGE_TREE_ENTRIES, READ_CHUNK_BYTES,
+};
+
+/// Resolve a path passed to a file command
+///
+/// Relative paths resolve against the project. Unless `allow_outside_project`
+/// is set, the path must lie inside a workspace root once `..` and symlinks
+/// are resolved, so a command can't be pointed at the rest of the filesystem.
+/// The path itself is returned unresolved, so deleting or moving a symlink
+/// acts on the link rather than its target.
+pub(crate) async fn sandboxed_path(
+    state: &AppState,
+    path: &str,
+    allow_outside_project: Option<bool>,
+) -> Result<String, String> {
+    let mut roots = state.workspace_roots().await.into_iter();
+    let project = roots.next();
+    if !allow_outside_project.unwrap_or(false) {
+        let jail = ToolContext::jailed(project.clone(), roots.collect());
+        resolve_path(&jail, path).map_err(|e| e.to_string())?;
+    }
+    let context = ToolContext {
+        working_dir: project,
+        ..Default::default()
+    };
+    resolve_path(&context, path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .map_err(|e| e.to_string())
+}
 
 /// Read the contents of a file
+///
+/// Only the start of a file larger than `LARGE_FILE_BYTES` is returned,
+/// marked as truncated; read the rest with `read_file_range` or `stream_file`.
 #[tauri::command]
-pub async fn read_file(path: String) -> Result<FileReadResult, String> {
+pub async fn read_file(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    allow_outside_project: Option<bool>,
+) -> Result<FileReadResult, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
+    let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    if size > LARGE_FILE_BYTES {
+        let range = file_ops::read_file_range(&path, 0, READ_CHUNK_BYTES).map_err(|e| e.to_string())?;
+        return Ok(FileReadResult {
+            content: range.content,
+            version: file_ops::file_version(&path).ok(),
+            path,
+            truncated: true,
+        });
+    }
+
     let content = file_ops::read_file(&path).map_err(|e| e.to_string())?;
 
     Ok(FileReadResult {
         content,
+        version: file_ops::file_version(&path).ok(),
         path,
         truncated: false,
     })
@@ -27,28 +83,149 @@ pub struct FileReadResult {
     pub content: String,
     pub path: String,
     pub truncated: bool,
+    /// The version read, to pass back to `write_file` to detect conflicts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<FileVersion>,
 }
 
 /// Read a file with a line limit
 #[tauri::command]
-pub async fn read_file_lines(path: String, max_lines: usize) -> Result<FileReadResult, String> {
+pub async fn read_file_lines(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    max_lines: usize,
+    allow_outside_project: Option<bool>,
+) -> Result<FileReadResult, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
     let (content, truncated) = file_ops::read_file_lines(&path, max_lines).map_err(|e| e.to_string())?;
 
     Ok(FileReadResult {
         content,
+        version: file_ops::file_version(&path).ok(),
         path,
         truncated,
     })
 }
 
+/// Read a byte range of a file
+#[tauri::command]
+pub async fn read_file_range(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    offset_bytes: u64,
+    length: u64,
+    allow_outside_project: Option<bool>,
+) -> Result<FileRange, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
+    file_ops::read_file_range(&path, offset_bytes, length).map_err(|e| e.to_string())
+}
+
+/// Event sent while streaming a file
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FileStreamEvent {
+    Chunk(FileRange),
+    Done,
+    Error { message: String },
+}
+
+/// Stream a file to the frontend in chunks
+///
+/// Chunks of `READ_CHUNK_BYTES` are emitted as `file-stream-{stream_id}`
+/// events, so a large file never goes through IPC in one piece. The command
+/// returns once the last chunk has been sent.
+#[tauri::command]
+pub async fn stream_file(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    stream_id: String,
+    allow_outside_project: Option<bool>,
+) -> Result<(), String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
+    let event_name = format!("file-stream-{}", stream_id);
+    let mut offset = 0;
+
+    loop {
+        let chunk_path = path.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            file_ops::read_file_range(&chunk_path, offset, READ_CHUNK_BYTES)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        match result {
+            Ok(range) => {
+                let eof = range.eof || range.next_offset == offset;
+                offset = range.next_offset;
+                app.emit(&event_name, &FileStreamEvent::Chunk(range))
+                    .map_err(|e| e.to_string())?;
+                if eof {
+                    break;
+                }
+            }
+            Err(e) => {
+                let message = e.to_string();
+                let _ = app.emit(&event_name, &FileStreamEvent::Error { message: message.clone() });
+                return Err(message);
+            }
+        }
+    }
+
+    let _ = app.emit(&event_name, &FileStreamEvent::Done);
+    Ok(())
+}
+
 /// Write content to a file
+///
+/// The write is atomic; with `backup` set, the previous content is kept as
+/// `<path>.bak`. Passing the `version` from `read_file` as `expected_hash` or
+/// `expected_mtime` rejects the write if the file changed on disk since.
+#[tauri::command]
+pub async fn write_file(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    content: String,
+    backup: Option<bool>,
+    expected_hash: Option<String>,
+    expected_mtime: Option<u64>,
+    allow_outside_project: Option<bool>,
+) -> Result<WriteResult, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
+    file_ops::check_file_version(&path, expected_hash.as_deref(), expected_mtime)
+        .map_err(|e| e.to_string())?;
+    file_ops::write_file_atomic(&path, &content, backup.unwrap_or(false))
+        .map_err(|e| e.to_string())?;
+    state.recent_files().await.record(&path, FileActivity::Edit);
+
+    Ok(WriteResult {
+        success: true,
+        version: file_ops::file_version(&path).ok(),
+        path,
+    })
+}
+
+/// Replace lines `start_line` to `end_line` of a file, numbered from 1
+///
+/// Lets the editor update a region of a file without sending its whole
+/// contents.
 #[tauri::command]
-pub async fn write_file(path: String, content: String) -> Result<WriteResult, String> {
-    file_ops::write_file(&path, &content).map_err(|e| e.to_string())?;
+pub async fn write_file_lines(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    start_line: usize,
+    end_line: usize,
+    new_content: String,
+    allow_outside_project: Option<bool>,
+) -> Result<WriteResult, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
+    file_ops::write_file_lines(&path, start_line, end_line, &new_content).map_err(|e| e.to_string())?;
+    state.recent_files().await.record(&path, FileActivity::Edit);
 
     Ok(WriteResult {
         success: true,
         path,
+        version: None,
     })
 }
 
@@ -56,61 +233,238 @@ pub async fn write_file(path: String, content: String) -> Result<WriteResult, St
 pub struct WriteResult {
     pub success: bool,
     pub path: String,
+    /// The file's version after the write, for `write_file`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<FileVersion>,
 }
 
 /// List the contents of a directory
+///
+/// Without a `path`, the workspace roots themselves are listed. With
+/// `details`, entries include their git status, media type and line count.
 #[tauri::command]
-pub async fn list_directory(path: String) -> Result<Vec<FileEntry>, String> {
-    file_ops::list_directory(&path).map_err(|e| e.to_string())
+pub async fn list_directory(
+    state: State<'_, Arc<AppState>>,
+    path: Option<String>,
+    details: Option<bool>,
+    allow_outside_project: Option<bool>,
+) -> Result<Vec<FileEntry>, String> {
+    match path {
+        Some(path) => {
+            let path = sandboxed_path(&state, &path, allow_outside_project).await?;
+            let mut entries = file_ops::list_directory(&path).map_err(|e| e.to_string())?;
+            if details.unwrap_or(false) {
+                file_ops::add_file_details(&path, &mut entries);
+            }
+            Ok(entries)
+        }
+        None => {
+            let mut entries = Vec::new();
+            for root in search_bases(&state, None, None).await? {
+                let mut entry = file_ops::get_file_info(&root).map_err(|e| e.to_string())?;
+                if details.unwrap_or(false) {
+                    file_ops::add_file_details(&root, std::slice::from_mut(&mut entry));
+                }
+                entries.push(entry);
+            }
+            Ok(entries)
+        }
+    }
 }
 
 /// List a directory recursively
+///
+/// Without a `path`, every workspace root is listed.
 #[tauri::command]
 pub async fn list_directory_recursive(
-    path: String,
+    state: State<'_, Arc<AppState>>,
+    path: Option<String>,
     max_depth: Option<usize>,
+    details: Option<bool>,
+    allow_outside_project: Option<bool>,
 ) -> Result<Vec<FileEntry>, String> {
-    file_ops::list_directory_recursive(&path, max_depth).map_err(|e| e.to_string())
+    let mut entries = Vec::new();
+    for base in search_bases(&state, path, allow_outside_project).await? {
+        let mut listed = file_ops::list_directory_recursive(&base, max_depth).map_err(|e| e.to_string())?;
+        if details.unwrap_or(false) {
+            file_ops::add_file_details(&base, &mut listed);
+        }
+        entries.extend(listed);
+    }
+    Ok(entries)
+}
+
+/// The directories a search runs in: `path`, or every workspace root if it
+/// isn't given
+async fn search_bases(
+    state: &AppState,
+    path: Option<String>,
+    allow_outside_project: Option<bool>,
+) -> Result<Vec<String>, String> {
+    match path {
+        Some(path) => Ok(vec![sandboxed_path(state, &path, allow_outside_project).await?]),
+        None => {
+            let roots = state.workspace_roots().await;
+            if roots.is_empty() {
+                return Err("No project path set".to_string());
+            }
+            Ok(roots.iter().map(|p| p.to_string_lossy().to_string()).collect())
+        }
+    }
+}
+
+/// Build search limits from optional command arguments
+fn search_limits(
+    max_results: Option<usize>,
+    offset: Option<usize>,
+    max_per_file: Option<usize>,
+) -> SearchLimits {
+    let defaults = SearchLimits::default();
+    SearchLimits {
+        max_results: max_results.unwrap_or(defaults.max_results),
+        offset: offset.unwrap_or(defaults.offset),
+        max_per_file,
+    }
 }
 
 /// Search for files matching a glob pattern
+///
+/// Without a `path`, every workspace root is searched.
 #[tauri::command]
-pub async fn search_files(pattern: String, path: String) -> Result<Vec<GlobMatch>, String> {
-    search::search_files(&pattern, &path).map_err(|e| e.to_string())
+pub async fn search_files(
+    state: State<'_, Arc<AppState>>,
+    pattern: String,
+    path: Option<String>,
+    max_results: Option<usize>,
+    offset: Option<usize>,
+    allow_outside_project: Option<bool>,
+) -> Result<SearchPage<GlobMatch>, String> {
+    let bases = search_bases(&state, path, allow_outside_project).await?;
+    let frecency = state.recent_files().await.scores();
+    let limits = search_limits(max_results, offset, None);
+    let per_base = SearchLimits {
+        max_results: limits.offset + limits.max_results,
+        offset: 0,
+        ..limits
+    };
+
+    let mut pages = Vec::with_capacity(bases.len());
+    for base in &bases {
+        pages.push(
+            search::search_files_ranked(&pattern, base, &per_base, &frecency)
+                .map_err(|e| e.to_string())?,
+        );
+    }
+    Ok(SearchPage::merge(pages, &limits))
 }
 
-/// Search for text in files using a regex pattern
+/// Find files by fuzzy-matching their paths, for the quick-open palette
+///
+/// Without a `path`, every workspace root is searched.
 #[tauri::command]
-pub async fn grep_files(
+pub async fn fuzzy_find_files(
+    state: State<'_, Arc<AppState>>,
     query: String,
+    path: Option<String>,
+    limit: Option<usize>,
+    allow_outside_project: Option<bool>,
+) -> Result<Vec<FuzzyMatch>, String> {
+    let bases = search_bases(&state, path, allow_outside_project).await?;
+    let frecency = state.recent_files().await.scores();
+    let limit = limit.unwrap_or(fuzzy::DEFAULT_FUZZY_RESULTS);
+
+    let mut matches = Vec::new();
+    for base in &bases {
+        matches.extend(
+            fuzzy::fuzzy_find_files_ranked(&query, base, limit, &frecency)
+                .map_err(|e| e.to_string())?,
+        );
+    }
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+/// Record that the user opened a file, for recent files and ranking
+#[tauri::command]
+pub async fn record_file_open(
+    state: State<'_, Arc<AppState>>,
     path: String,
-    file_pattern: Option<String>,
-) -> Result<GrepResult, String> {
-    let results = search::grep_files(&query, &path, file_pattern.as_deref())
-        .map_err(|e| e.to_string())?;
+    allow_outside_project: Option<bool>,
+) -> Result<(), String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
+    state.recent_files().await.record(&path, FileActivity::Open);
+    Ok(())
+}
 
-    Ok(GrepResult {
-        results: results.clone(),
-        count: results.len(),
-    })
+/// Get the files the user opened or edited most recently, most recent first
+#[tauri::command]
+pub async fn get_recent_files(
+    state: State<'_, Arc<AppState>>,
+    limit: Option<usize>,
+) -> Result<Vec<RecentFile>, String> {
+    Ok(state.recent_files().await.recent(limit.unwrap_or(20)))
 }
 
-#[derive(Debug, Serialize)]
-pub struct GrepResult {
-    pub results: Vec<SearchResult>,
-    pub count: usize,
+/// Search for text in files using a regex pattern
+///
+/// Without a `path`, every workspace root is searched. Paths covered by the
+/// text index, once built, are searched without walking the filesystem.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn grep_files(
+    state: State<'_, Arc<AppState>>,
+    query: String,
+    path: Option<String>,
+    file_pattern: Option<String>,
+    max_results: Option<usize>,
+    offset: Option<usize>,
+    max_per_file: Option<usize>,
+    allow_outside_project: Option<bool>,
+) -> Result<SearchPage<SearchResult>, String> {
+    let bases = search_bases(&state, path, allow_outside_project).await?;
+    let limits = search_limits(max_results, offset, max_per_file);
+    let per_base = SearchLimits {
+        max_results: limits.offset + limits.max_results,
+        offset: 0,
+        ..limits
+    };
+
+    // Use the text index where it covers the search, rather than walking
+    let text_index = state.text_index.read().await.clone();
+    let mut pages = Vec::with_capacity(bases.len());
+    for base in &bases {
+        let page = match &text_index {
+            Some(index) if index.covers(Path::new(base)) => {
+                index.grep(&query, base, file_pattern.as_deref(), &per_base)
+            }
+            _ => search::grep_files(&query, base, file_pattern.as_deref(), &per_base),
+        };
+        pages.push(page.map_err(|e| e.to_string())?);
+    }
+    Ok(SearchPage::merge(pages, &limits))
 }
 
 /// Search with context lines
+///
+/// Without a `path`, every workspace root is searched.
 #[tauri::command]
 pub async fn grep_files_with_context(
+    state: State<'_, Arc<AppState>>,
     query: String,
-    path: String,
+    path: Option<String>,
     file_pattern: Option<String>,
     context_lines: usize,
+    allow_outside_project: Option<bool>,
 ) -> Result<GrepWithContextResult, String> {
-    let results = search::grep_files_with_context(&query, &path, file_pattern.as_deref(), context_lines)
-        .map_err(|e| e.to_string())?;
+    let bases = search_bases(&state, path, allow_outside_project).await?;
+    let mut results = Vec::new();
+    for base in &bases {
+        results.extend(
+            search::grep_files_with_context(&query, base, file_pattern.as_deref(), context_lines)
+                .map_err(|e| e.to_string())?,
+        );
+    }
 
     Ok(GrepWithContextResult {
         results: results.clone(),
@@ -126,72 +480,391 @@ pub struct GrepWithContextResult {
 
 /// Check if a path exists
 #[tauri::command]
-pub async fn path_exists(path: String) -> Result<bool, String> {
+pub async fn path_exists(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    allow_outside_project: Option<bool>,
+) -> Result<bool, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
     Ok(file_ops::path_exists(&path))
 }
 
 /// Check if a path is a file
 #[tauri::command]
-pub async fn is_file(path: String) -> Result<bool, String> {
+pub async fn is_file(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    allow_outside_project: Option<bool>,
+) -> Result<bool, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
     Ok(file_ops::is_file(&path))
 }
 
 /// Check if a path is a directory
 #[tauri::command]
-pub async fn is_directory(path: String) -> Result<bool, String> {
+pub async fn is_directory(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    allow_outside_project: Option<bool>,
+) -> Result<bool, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
     Ok(file_ops::is_directory(&path))
 }
 
 /// Get file metadata
+///
+/// With `details`, the entry includes its git status, media type and line
+/// count.
 #[tauri::command]
-pub async fn get_file_info(path: String) -> Result<FileEntry, String> {
-    file_ops::get_file_info(&path).map_err(|e| e.to_string())
+pub async fn get_file_info(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    details: Option<bool>,
+    allow_outside_project: Option<bool>,
+) -> Result<FileEntry, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
+    let mut entry = file_ops::get_file_info(&path).map_err(|e| e.to_string())?;
+    if details.unwrap_or(false) {
+        // Run git in the file's directory; a directory is its own
+        let dir = if entry.is_dir {
+            PathBuf::from(&path)
+        } else {
+            Path::new(&path).parent().map(Path::to_path_buf).unwrap_or_default()
+        };
+        file_ops::add_file_details(&dir.to_string_lossy(), std::slice::from_mut(&mut entry));
+    }
+    Ok(entry)
 }
 
 /// Create a directory
 #[tauri::command]
-pub async fn create_directory(path: String) -> Result<WriteResult, String> {
+pub async fn create_directory(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    allow_outside_project: Option<bool>,
+) -> Result<WriteResult, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
     file_ops::create_directory(&path).map_err(|e| e.to_string())?;
 
     Ok(WriteResult {
         success: true,
         path,
+        version: None,
     })
 }
 
 /// Delete a file
 #[tauri::command]
-pub async fn delete_file(path: String) -> Result<WriteResult, String> {
+pub async fn delete_file(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    allow_outside_project: Option<bool>,
+) -> Result<WriteResult, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
     file_ops::delete_file(&path).map_err(|e| e.to_string())?;
 
     Ok(WriteResult {
         success: true,
         path,
+        version: None,
+    })
+}
+
+/// Delete a directory
+///
+/// Workspace roots and their parents can't be deleted. Deleting more than
+/// `LARGE_TREE_ENTRIES` files and directories recursively fails unless
+/// `confirm` is set, so the user can be asked first.
+#[tauri::command]
+pub async fn delete_directory(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    recursive: bool,
+    confirm: Option<bool>,
+    allow_outside_project: Option<bool>,
+) -> Result<WriteResult, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
+    let target = std::path::Path::new(&path);
+    // Resolve the parent only, so a symlink is compared as itself
+    let canonical = match (target.parent(), target.file_name()) {
+        (Some(parent), Some(name)) => parent.canonicalize().map(|parent| parent.join(name)),
+        _ => target.canonicalize(),
+    };
+    if let Ok(canonical) = canonical {
+        for root in state.workspace_roots().await {
+            let root = root.canonicalize().unwrap_or(root);
+            if root.starts_with(&canonical) {
+                return Err(format!("Refusing to delete {}, which contains the project", path));
+            }
+        }
+    }
+
+    if recursive && !confirm.unwrap_or(false) {
+        let entries = file_ops::count_entries(target, LARGE_TREE_ENTRIES);
+        if entries > LARGE_TREE_ENTRIES {
+            return Err(format!(
+                "{} contains more than {} files and directories; confirm to delete it",
+                path, LARGE_TREE_ENTRIES
+            ));
+        }
+    }
+
+    file_ops::delete_directory(&path, recursive).map_err(|e| e.to_string())?;
+
+    Ok(WriteResult {
+        success: true,
+        path,
+        version: None,
     })
 }
 
 /// Copy a file
 #[tauri::command]
-pub async fn copy_file(from: String, to: String) -> Result<WriteResult, String> {
+pub async fn copy_file(
+    state: State<'_, Arc<AppState>>,
+    from: String,
+    to: String,
+    allow_outside_project: Option<bool>,
+) -> Result<WriteResult, String> {
+    let from = sandboxed_path(&state, &from, allow_outside_project).await?;
+    let to = sandboxed_path(&state, &to, allow_outside_project).await?;
     file_ops::copy_file(&from, &to).map_err(|e| e.to_string())?;
 
     Ok(WriteResult {
         success: true,
         path: to,
+        version: None,
+    })
+}
+
+/// Copy a directory and everything in it
+#[tauri::command]
+pub async fn copy_directory(
+    state: State<'_, Arc<AppState>>,
+    from: String,
+    to: String,
+    allow_outside_project: Option<bool>,
+) -> Result<WriteResult, String> {
+    let from = sandboxed_path(&state, &from, allow_outside_project).await?;
+    let to = sandboxed_path(&state, &to, allow_outside_project).await?;
+    file_ops::copy_directory(&from, &to).map_err(|e| e.to_string())?;
+
+    Ok(WriteResult {
+        success: true,
+        path: to,
+        version: None,
     })
 }
 
 /// Move/rename a file
 #[tauri::command]
-pub async fn move_file(from: String, to: String) -> Result<WriteResult, String> {
+pub async fn move_file(
+    state: State<'_, Arc<AppState>>,
+    from: String,
+    to: String,
+    allow_outside_project: Option<bool>,
+) -> Result<WriteResult, String> {
+    let from = sandboxed_path(&state, &from, allow_outside_project).await?;
+    let to = sandboxed_path(&state, &to, allow_outside_project).await?;
     file_ops::move_file(&from, &to).map_err(|e| e.to_string())?;
 
     Ok(WriteResult {
         success: true,
         path: to,
+        version: None,
     })
 }
 
+/// Diff two text files, returning numbered hunks for the diff viewer
+#[tauri::command]
+pub async fn diff_files(
+    state: State<'_, Arc<AppState>>,
+    a: String,
+    b: String,
+    context: Option<usize>,
+    allow_outside_project: Option<bool>,
+) -> Result<FileDiff, String> {
+    let a = sandboxed_path(&state, &a, allow_outside_project).await?;
+    let b = sandboxed_path(&state, &b, allow_outside_project).await?;
+    diff::diff_files(&a, &b, context.unwrap_or(diff::DEFAULT_DIFF_CONTEXT)).map_err(|e| e.to_string())
+}
+
+/// Diff two texts, such as a file and an AI edit of it
+#[tauri::command]
+pub async fn diff_content(
+    original: String,
+    modified: String,
+    context: Option<usize>,
+) -> Result<FileDiff, String> {
+    Ok(diff::diff_text(
+        &original,
+        &modified,
+        context.unwrap_or(diff::DEFAULT_DIFF_CONTEXT),
+    ))
+}
+
+/// Hash a file's content
+///
+/// `algo` is one of sha224, sha256 (the default), sha384 or sha512.
+#[tauri::command]
+pub async fn hash_file(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    algo: Option<String>,
+    allow_outside_project: Option<bool>,
+) -> Result<String, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
+    let algorithm = match algo {
+        Some(algo) => algo.parse().map_err(|e: crate::tools::ToolError| e.to_string())?,
+        None => Default::default(),
+    };
+    tokio::task::spawn_blocking(move || checksum::hash_file(&path, algorithm))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Find files with identical content under a directory
+#[tauri::command]
+pub async fn find_duplicate_files(
+    state: State<'_, Arc<AppState>>,
+    dir: String,
+    allow_outside_project: Option<bool>,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let dir = sandboxed_path(&state, &dir, allow_outside_project).await?;
+    tokio::task::spawn_blocking(move || checksum::find_duplicate_files(&dir))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Count lines of code per language under a directory
+///
+/// Defaults to the project root. Files ignored by `.gitignore` are skipped.
+#[tauri::command]
+pub async fn count_loc(
+    state: State<'_, Arc<AppState>>,
+    path: Option<String>,
+    allow_outside_project: Option<bool>,
+) -> Result<LocReport, String> {
+    let dir = match path {
+        Some(path) => PathBuf::from(sandboxed_path(&state, &path, allow_outside_project).await?),
+        None => state
+            .get_project_path()
+            .await
+            .ok_or_else(|| "No project is open".to_string())?,
+    };
+    tokio::task::spawn_blocking(move || loc::count_loc(&dir))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Extract a zip or tar.gz archive into a directory
+///
+/// # Returns
+/// The number of files extracted
+#[tauri::command]
+pub async fn extract_archive(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    dest: String,
+    allow_outside_project: Option<bool>,
+) -> Result<usize, String> {
+    let path = sandboxed_path(&state, &path, allow_outside_project).await?;
+    let dest = sandboxed_path(&state, &dest, allow_outside_project).await?;
+    tokio::task::spawn_blocking(move || archive::extract_archive(&path, &dest))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Create a zip or tar.gz archive of files and directories
+///
+/// `format` is `zip` or `tar.gz`; it defaults to the one `dest` is named for.
+#[tauri::command]
+pub async fn create_archive(
+    state: State<'_, Arc<AppState>>,
+    paths: Vec<String>,
+    dest: String,
+    format: Option<String>,
+    allow_outside_project: Option<bool>,
+) -> Result<WriteResult, String> {
+    let mut resolved = Vec::with_capacity(paths.len());
+    for path in &paths {
+        resolved.push(sandboxed_path(&state, path, allow_outside_project).await?);
+    }
+    let paths = resolved;
+    let dest = sandboxed_path(&state, &dest, allow_outside_project).await?;
+    let format = match format {
+        Some(format) => format.parse().map_err(|e: crate::tools::ToolError| e.to_string())?,
+        None => archive::ArchiveFormat::from_path(std::path::Path::new(&dest))
+            .ok_or_else(|| format!("Can't tell the archive format of {}; pass a format", dest))?,
+    };
+    let path = dest.clone();
+    tokio::task::spawn_blocking(move || archive::create_archive(&paths, &dest, format))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    Ok(WriteResult {
+        success: true,
+        path,
+        version: None,
+    })
+}
+
+/// Run a list of reads, writes, deletes and moves in one call
+///
+/// The operations run in order as a transaction: if one fails, the files
+/// changed before it are restored. Every path is checked against the project
+/// before anything runs.
+#[tauri::command]
+pub async fn batch_file_ops(
+    state: State<'_, Arc<AppState>>,
+    mut ops: Vec<FileOp>,
+    allow_outside_project: Option<bool>,
+) -> Result<BatchResult, String> {
+    for op in &mut ops {
+        for path in op.paths_mut() {
+            *path = sandboxed_path(&state, path, allow_outside_project).await?;
+        }
+    }
+
+    tokio::task::spawn_blocking(move || batch::batch_file_ops(&ops))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Apply a unified diff to the workspace
+///
+/// Paths in the diff are resolved against `base_path`, defaulting to the
+/// current project path, and each must be inside the project like any other
+/// path passed to a file command.
+#[tauri::command]
+pub async fn apply_patch(
+    state: State<'_, Arc<AppState>>,
+    patch: String,
+    base_path: Option<String>,
+    dry_run: Option<bool>,
+    allow_outside_project: Option<bool>,
+) -> Result<PatchResult, String> {
+    let base = match base_path {
+        Some(base) => sandboxed_path(&state, &base, allow_outside_project).await?,
+        None => state
+            .get_project_path()
+            .await
+            .map(|p| p.to_string_lossy().to_string())
+            .ok_or_else(|| "No project path set".to_string())?,
+    };
+    for file in patch::patch_paths(&patch).map_err(|e| e.to_string())? {
+        let target = Path::new(&base).join(file);
+        sandboxed_path(&state, &target.to_string_lossy(), allow_outside_project).await?;
+    }
+
+    patch::apply_patch(&patch, &base, dry_run.unwrap_or(false)).map_err(|e| e.to_string())
+}
+
 /// Set the project path
 #[tauri::command]
 pub async fn set_project_path(
@@ -219,6 +892,109 @@ pub async fn get_project_path(state: State<'_, Arc<AppState>>) -> Result<Option<
     Ok(path.map(|p| p.to_string_lossy().to_string()))
 }
 
+/// Watch the project for file changes
+///
+/// Every workspace root is watched. Debounced changes are emitted as
+/// `fs-change` events, and files in the current project that changed are
+/// dropped from the semantic index until it is rebuilt and re-read into the
+/// text index, if one was built. A `git-status-changed` event follows any
+/// change, including to the repository's index or refs. Watching stops when
+/// `stop_watching` is called or the project changes; call this again after
+/// adding a root to watch it too.
+#[tauri::command]
+pub async fn start_watching(app: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    let roots = state.workspace_roots().await;
+    if roots.is_empty() {
+        return Err("No project path set".to_string());
+    }
+
+    let mut watchers = Vec::with_capacity(roots.len());
+    for (i, root) in roots.iter().enumerate() {
+        // The indexes only cover the current project, the first root
+        let indexed = i == 0;
+        // The state owns the watcher, so the callback mustn't keep it alive
+        let app_state = Arc::downgrade(state.inner());
+        let app = app.clone();
+        let watcher = ProjectWatcher::start(root, move |change| {
+            // Runs on the watcher's own thread, outside the async runtime
+            let app_state = app_state.upgrade();
+            if let Some(state) = &app_state {
+                state.git_status.invalidate(Path::new(&change.root));
+            }
+            let status_changed = GitStatusChanged {
+                root: change.root.clone(),
+            };
+            let _ = app.emit(GIT_STATUS_CHANGED_EVENT, &status_changed);
+            if !change.has_files() {
+                return;
+            }
+
+            let state = app_state.filter(|_| indexed);
+            let paths: Vec<String> = change.changed.iter().chain(&change.removed).cloned().collect();
+            if let Some(index) = state.as_ref().and_then(|s| s.semantic_index.blocking_read().clone()) {
+                if let Err(e) = index.invalidate(&paths) {
+                    log::warn!("Failed to invalidate semantic index: {}", e);
+                }
+            }
+            if let Some(index) = state.as_ref().and_then(|s| s.text_index.blocking_read().clone()) {
+                index.update(&paths);
+            }
+            let _ = app.emit(FS_CHANGE_EVENT, &change);
+        })
+        .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+        watchers.push(watcher);
+    }
+
+    *state.watchers.lock().await = watchers;
+    // Changes made while nothing was watching were missed
+    state.git_status.clear();
+    Ok(())
+}
+
+/// Stop watching the project for file changes
+#[tauri::command]
+pub async fn stop_watching(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    state.watchers.lock().await.clear();
+    state.git_status.clear();
+    Ok(())
+}
+
+/// Get the workspace roots, the current project first
+#[tauri::command]
+pub async fn get_workspace_roots(state: State<'_, Arc<AppState>>) -> Result<Vec<String>, String> {
+    Ok(state
+        .workspace_roots()
+        .await
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+/// Add a directory to the workspace
+///
+/// If no project is open, the directory becomes the project.
+#[tauri::command]
+pub async fn add_workspace_root(state: State<'_, Arc<AppState>>, path: String) -> Result<(), String> {
+    if state.get_project_path().await.is_none() {
+        return set_project_path(state, path).await;
+    }
+    state
+        .add_workspace_root(std::path::PathBuf::from(path))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Remove a directory from the workspace
+///
+/// Removing the current project makes the next root the project.
+#[tauri::command]
+pub async fn remove_workspace_root(state: State<'_, Arc<AppState>>, path: String) -> Result<(), String> {
+    state
+        .remove_workspace_root(std::path::Path::new(&path))
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// Open a file dialog to select a directory
 #[tauri::command]
 pub async fn select_directory(app: tauri::AppHandle) -> Result<Option<String>, String> {
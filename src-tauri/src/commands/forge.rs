@@ -0,0 +1,74 @@
+//! Pull request commands
+//!
+//! This module provides Tauri commands for the pull requests (or GitLab merge
+//! requests) of the repository a project's `origin` remote points to. Each
+//! takes an optional token; without one the token is read from the
+//! environment, and a missing or rejected token fails with `auth_required`.
+
+use crate::commands::git::RemoteError;
+use crate::tools::forge::{ForgeClient, PullRequest};
+use crate::tools::ToolResult;
+
+/// Run a forge request off the async runtime, since the client blocks
+async fn with_client<T: Send + 'static>(
+    path: String,
+    token: Option<String>,
+    request: impl FnOnce(&ForgeClient) -> ToolResult<T> + Send + 'static,
+) -> Result<T, RemoteError> {
+    tokio::task::spawn_blocking(move || request(&ForgeClient::for_repository(&path, token)?))
+        .await
+        .map_err(|e| RemoteError::Failed {
+            message: e.to_string(),
+        })?
+        .map_err(RemoteError::from)
+}
+
+/// List pull requests: "open" (the default), "closed", "merged" or "all"
+#[tauri::command]
+pub async fn forge_list_prs(
+    path: String,
+    state: Option<String>,
+    token: Option<String>,
+) -> Result<Vec<PullRequest>, RemoteError> {
+    with_client(path, token, move |client| client.list_prs(state.as_deref())).await
+}
+
+/// Open a pull request from `head` into `base`
+#[tauri::command]
+pub async fn forge_create_pr(
+    path: String,
+    title: String,
+    body: String,
+    base: String,
+    head: String,
+    token: Option<String>,
+) -> Result<PullRequest, RemoteError> {
+    with_client(path, token, move |client| {
+        client.create_pr(&title, &body, &base, &head)
+    })
+    .await
+}
+
+/// Get a pull request's diff
+#[tauri::command]
+pub async fn forge_get_pr_diff(
+    path: String,
+    number: u64,
+    token: Option<String>,
+) -> Result<String, RemoteError> {
+    with_client(path, token, move |client| client.get_pr_diff(number)).await
+}
+
+/// Comment on a pull request
+#[tauri::command]
+pub async fn forge_comment_on_pr(
+    path: String,
+    number: u64,
+    body: String,
+    token: Option<String>,
+) -> Result<(), RemoteError> {
+    with_client(path, token, move |client| {
+        client.comment_on_pr(number, &body)
+    })
+    .await
+}
@@ -1,390 +1,700 @@
 //! Git commands
 //!
 //! This module provides Tauri commands for Git operations including
-//! status, diff, log, stage, and commit.
+//! status, diff, log, stage, and commit. They use libgit2 rather than the
+//! `git` binary.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use std::process::Command;
 use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::commit_message::{self, CommitMessage};
+use crate::state::AppState;
+use crate::tools::git_hooks::{self, HookResult};
+use crate::tools::{git, ToolError};
+pub use crate::tools::git::{
+    CloneOptions, ConflictResolution, FileStatus, GitBranch, GitCommit, GitConflict, GitCredentials,
+    GitRefLabel, GitStatus, GitWorktree, GraphCommit, IgnoreCheck, LogFilter, MergeResult,
+    RebaseAction, RebaseResult, RebaseStep, ReflogEntry, ResetMode, UndoResult,
+    HARD_RESET_CONFIRMATION,
+};
+
+/// Commits `git_search_commits` returns when no count is given
+const SEARCH_COMMITS_COUNT: u32 = 50;
+
+/// Event carrying a line of git hook output while a commit runs its hooks
+pub const GIT_HOOK_OUTPUT_EVENT: &str = "git-hook-output";
+
+/// Event emitted when a watched repository's status may have changed, so the
+/// frontend can fetch it again instead of polling
+pub const GIT_STATUS_CHANGED_EVENT: &str = "git-status-changed";
+
+/// A watched root whose files or git metadata changed
+#[derive(Debug, Clone, Serialize)]
+pub struct GitStatusChanged {
+    pub root: String,
+}
 
-/// Git status result
+/// A line of git hook output
+#[derive(Debug, Clone, Serialize)]
+pub struct HookOutputLine {
+    /// "pre-commit" or "commit-msg"
+    pub hook: String,
+    pub line: String,
+}
+
+/// Error from committing
 #[derive(Debug, Serialize)]
-pub struct GitStatus {
-    pub branch: String,
-    pub ahead: u32,
-    pub behind: u32,
-    pub staged: Vec<FileStatus>,
-    pub unstaged: Vec<FileStatus>,
-    pub untracked: Vec<String>,
-    pub is_clean: bool,
-    pub has_conflicts: bool,
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommitError {
+    /// A hook rejected the commit; commit with `no_verify` to skip hooks
+    HookFailed(HookResult),
+    Failed { message: String },
 }
 
+impl From<ToolError> for CommitError {
+    fn from(error: ToolError) -> Self {
+        match error {
+            ToolError::HookFailed(result) => Self::HookFailed(*result),
+            error => Self::Failed {
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+/// Error from a command that talks to a remote
 #[derive(Debug, Serialize)]
-pub struct FileStatus {
-    pub path: String,
-    pub status: String, // "modified", "added", "deleted", "renamed", "copied"
-    pub old_path: Option<String>, // For renamed/copied files
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RemoteError {
+    /// The remote rejected every credential tried; ask the user for some and
+    /// call the command again with them
+    AuthRequired {
+        url: String,
+        /// "ssh" or "https"
+        method: String,
+        message: String,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<ToolError> for RemoteError {
+    fn from(error: ToolError) -> Self {
+        let message = error.to_string();
+        match error {
+            ToolError::AuthRequired { url, method } => Self::AuthRequired {
+                url,
+                method,
+                message,
+            },
+            _ => Self::Failed { message },
+        }
+    }
 }
 
 /// Get git status for a repository
+///
+/// Statuses of repositories under a watched root are cached until the
+/// watcher sees a change, which it reports as a `GIT_STATUS_CHANGED_EVENT`.
 #[tauri::command]
-pub async fn git_status(path: String) -> Result<GitStatus, String> {
-    // Get branch info
-    let branch_output = run_git_command(&path, &["branch", "--show-current"])?;
-    let branch = branch_output.trim().to_string();
+pub async fn git_status(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<GitStatus, String> {
+    let key = status_key(&path);
+    // Only watched repositories hear about changes, so only they are cached
+    let watched = state.watchers.lock().await.iter().any(|w| key.starts_with(w.root()));
+    if let Some(status) = state.git_status.get(&key).filter(|_| watched) {
+        return Ok(status);
+    }
+    let status = git::status(&path).map_err(|e| e.to_string())?;
+    if watched {
+        state.git_status.insert(key, status.clone());
+    }
+    Ok(status)
+}
 
-    // Get ahead/behind info
-    let (ahead, behind) = get_ahead_behind(&path).unwrap_or((0, 0));
+/// The key a repository's status is cached under
+fn status_key(path: &str) -> PathBuf {
+    Path::new(path).canonicalize().unwrap_or_else(|_| PathBuf::from(path))
+}
 
-    // Get status with porcelain format for easy parsing
-    let status_output = run_git_command(&path, &["status", "--porcelain=v1"])?;
+/// Forget the cached status of a repository a command changed, rather than
+/// wait for the watcher to notice
+fn forget_status(state: &AppState, path: &str) {
+    state.git_status.invalidate(&status_key(path));
+}
 
-    let mut staged = Vec::new();
-    let mut unstaged = Vec::new();
-    let mut untracked = Vec::new();
-    let mut has_conflicts = false;
+/// Get git diff
+#[tauri::command]
+pub async fn git_diff(path: String, staged: bool) -> Result<String, String> {
+    git::diff(&path, staged, None).map_err(|e| e.to_string())
+}
 
-    for line in status_output.lines() {
-        if line.len() < 3 {
-            continue;
-        }
+/// Get diff for a specific file
+#[tauri::command]
+pub async fn git_diff_file(path: String, file_path: String, staged: bool) -> Result<String, String> {
+    git::diff(&path, staged, Some(&file_path)).map_err(|e| e.to_string())
+}
 
-        let index_status = line.chars().next().unwrap_or(' ');
-        let worktree_status = line.chars().nth(1).unwrap_or(' ');
-        let file_path = line[3..].to_string();
+/// Get git log
+#[tauri::command]
+pub async fn git_log(
+    path: String,
+    count: u32,
+    filter: Option<LogFilter>,
+) -> Result<Vec<GitCommit>, String> {
+    git::log_filtered(&path, count, &filter.unwrap_or_default()).map_err(|e| e.to_string())
+}
 
-        // Check for conflicts
-        if index_status == 'U' || worktree_status == 'U' {
-            has_conflicts = true;
-        }
+/// Search history for commits whose message mentions `query`, or, with
+/// `search_patches`, whose changes add or remove it
+#[tauri::command]
+pub async fn git_search_commits(
+    path: String,
+    query: String,
+    search_patches: bool,
+    count: Option<u32>,
+) -> Result<Vec<GitCommit>, String> {
+    tokio::task::spawn_blocking(move || {
+        git::search_commits(&path, &query, search_patches, count.unwrap_or(SEARCH_COMMITS_COUNT))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
 
-        // Handle untracked files
-        if index_status == '?' && worktree_status == '?' {
-            untracked.push(file_path);
-            continue;
-        }
+/// Get recent commits on all branches with their parents and refs, for
+/// drawing a branch graph
+#[tauri::command]
+pub async fn git_log_graph(path: String, count: u32) -> Result<Vec<GraphCommit>, String> {
+    git::log_graph(&path, count).map_err(|e| e.to_string())
+}
 
-        // Handle staged changes
-        if index_status != ' ' && index_status != '?' {
-            let status = match index_status {
-                'M' => "modified",
-                'A' => "added",
-                'D' => "deleted",
-                'R' => "renamed",
-                'C' => "copied",
-                'U' => "conflict",
-                _ => "unknown",
-            };
-
-            let (path, old_path) = if status == "renamed" || status == "copied" {
-                // Parse "old -> new" format
-                if let Some(arrow_pos) = file_path.find(" -> ") {
-                    let old = file_path[..arrow_pos].to_string();
-                    let new = file_path[arrow_pos + 4..].to_string();
-                    (new, Some(old))
-                } else {
-                    (file_path.clone(), None)
-                }
-            } else {
-                (file_path.clone(), None)
-            };
-
-            staged.push(FileStatus {
-                path,
-                status: status.to_string(),
-                old_path,
-            });
-        }
+/// Stage files for commit
+#[tauri::command]
+pub async fn git_stage(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    files: Vec<String>,
+) -> Result<(), String> {
+    let result = git::stage(&path, &files).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
+}
 
-        // Handle unstaged changes
-        if worktree_status != ' ' && worktree_status != '?' {
-            let status = match worktree_status {
-                'M' => "modified",
-                'D' => "deleted",
-                'U' => "conflict",
-                _ => "unknown",
-            };
-
-            unstaged.push(FileStatus {
-                path: file_path,
-                status: status.to_string(),
-                old_path: None,
-            });
-        }
-    }
+/// Unstage files
+#[tauri::command]
+pub async fn git_unstage(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    files: Vec<String>,
+) -> Result<(), String> {
+    let result = git::unstage(&path, &files).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
+}
 
-    let is_clean = staged.is_empty() && unstaged.is_empty() && untracked.is_empty();
-
-    Ok(GitStatus {
-        branch,
-        ahead,
-        behind,
-        staged,
-        unstaged,
-        untracked,
-        is_clean,
-        has_conflicts,
-    })
+/// Stage all changes
+#[tauri::command]
+pub async fn git_stage_all(state: State<'_, Arc<AppState>>, path: String) -> Result<(), String> {
+    let result = git::stage_all(&path).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
 }
 
-/// Get ahead/behind counts relative to upstream
-fn get_ahead_behind(path: &str) -> Result<(u32, u32), String> {
-    let output = run_git_command(path, &["rev-list", "--left-right", "--count", "HEAD...@{upstream}"])?;
-    let parts: Vec<&str> = output.trim().split('\t').collect();
+/// Commit staged changes
+///
+/// The repository's pre-commit and commit-msg hooks run first, or the
+/// `pre_commit_command` setting in place of the pre-commit hook, unless
+/// `no_verify` is set. Their output is emitted line by line as
+/// `GIT_HOOK_OUTPUT_EVENT`s.
+#[tauri::command]
+pub async fn git_commit(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    message: String,
+    no_verify: Option<bool>,
+) -> Result<GitCommit, CommitError> {
+    let pre_commit_command = state.get_settings().await.pre_commit_command;
+    let repository = path.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        git_hooks::commit_with_hooks(
+            &path,
+            &message,
+            no_verify.unwrap_or(false),
+            pre_commit_command.as_deref(),
+            &mut |hook, line| {
+                let output = HookOutputLine {
+                    hook: hook.to_string(),
+                    line: line.to_string(),
+                };
+                let _ = app.emit(GIT_HOOK_OUTPUT_EVENT, &output);
+            },
+        )
+    })
+    .await
+    .map_err(|e| CommitError::Failed {
+        message: e.to_string(),
+    })?
+    .map_err(CommitError::from);
+    forget_status(&state, &repository);
+    result
+}
 
-    if parts.len() == 2 {
-        let ahead = parts[0].parse().unwrap_or(0);
-        let behind = parts[1].parse().unwrap_or(0);
-        Ok((ahead, behind))
-    } else {
-        Ok((0, 0))
+/// Generate a commit message for the staged changes with the active provider
+#[tauri::command]
+pub async fn generate_commit_message(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<CommitMessage, String> {
+    let diff = git::diff(&path, true, None).map_err(|e| e.to_string())?;
+    if diff.trim().is_empty() {
+        return Err("No staged changes".to_string());
     }
+    let stat = git::diff_stat(&path, true).map_err(|e| e.to_string())?;
+
+    let provider = state
+        .get_active_provider()
+        .await
+        .ok_or_else(|| "No AI provider configured".to_string())?;
+    commit_message::generate_commit_message(
+        provider.as_ref(),
+        &state.request_scheduler,
+        &diff,
+        &stat,
+    )
+    .await
+    .map_err(|e| e.to_string())
 }
 
-/// Get git diff
+/// Discard changes to a file
 #[tauri::command]
-pub async fn git_diff(path: String, staged: bool) -> Result<String, String> {
-    let args = if staged {
-        vec!["diff", "--cached"]
-    } else {
-        vec!["diff"]
-    };
-
-    run_git_command(&path, &args)
+pub async fn git_discard(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    file_path: String,
+) -> Result<(), String> {
+    let result = git::discard(&path, &file_path).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
 }
 
-/// Get diff for a specific file
+/// Get list of branches
 #[tauri::command]
-pub async fn git_diff_file(path: String, file_path: String, staged: bool) -> Result<String, String> {
-    let args = if staged {
-        vec!["diff", "--cached", "--", &file_path]
-    } else {
-        vec!["diff", "--", &file_path]
-    };
-
-    run_git_command(&path, &args.iter().map(|s| s.as_ref()).collect::<Vec<&str>>())
+pub async fn git_branches(path: String) -> Result<Vec<GitBranch>, String> {
+    git::branches(&path).map_err(|e| e.to_string())
 }
 
-/// Git commit info
-#[derive(Debug, Serialize)]
-pub struct GitCommit {
-    pub hash: String,
-    pub short_hash: String,
-    pub author: String,
-    pub email: String,
-    pub date: String,
-    pub message: String,
-    pub body: String,
+/// Checkout a branch
+#[tauri::command]
+pub async fn git_checkout(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    branch: String,
+) -> Result<(), String> {
+    let result = git::checkout(&path, &branch).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
 }
 
-/// Get git log
+/// Create a new branch
 #[tauri::command]
-pub async fn git_log(path: String, count: u32) -> Result<Vec<GitCommit>, String> {
-    // Use a format that's easy to parse
-    let format = "%H|%h|%an|%ae|%aI|%s|%b%x00";
-    let count_str = count.to_string();
-    let format_arg = format!("--format={}", format);
-    let args = vec!["log", &format_arg, "-n", &count_str];
+pub async fn git_create_branch(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    name: String,
+    checkout: bool,
+) -> Result<(), String> {
+    let result = git::create_branch(&path, &name, checkout).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
+}
 
-    let output = run_git_command(&path, &args)?;
+/// Delete a local branch; `force` deletes it even if it isn't merged
+#[tauri::command]
+pub async fn git_delete_branch(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    name: String,
+    force: bool,
+) -> Result<(), String> {
+    let result = git::delete_branch(&path, &name, force).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
+}
 
-    let mut commits = Vec::new();
+/// Rename a local branch
+#[tauri::command]
+pub async fn git_rename_branch(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    old_name: String,
+    new_name: String,
+) -> Result<(), String> {
+    let result = git::rename_branch(&path, &old_name, &new_name).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
+}
 
-    for entry in output.split('\0') {
-        let entry = entry.trim();
-        if entry.is_empty() {
-            continue;
-        }
+/// Set the remote branch a local branch tracks, or clear it with no
+/// `upstream`
+///
+/// The current branch is used if `branch` isn't given.
+#[tauri::command]
+pub async fn git_set_upstream(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    branch: Option<String>,
+    upstream: Option<String>,
+) -> Result<(), String> {
+    let result = git::set_upstream(&path, branch.as_deref(), upstream.as_deref())
+        .map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
+}
 
-        let parts: Vec<&str> = entry.splitn(7, '|').collect();
-        if parts.len() >= 6 {
-            commits.push(GitCommit {
-                hash: parts[0].to_string(),
-                short_hash: parts[1].to_string(),
-                author: parts[2].to_string(),
-                email: parts[3].to_string(),
-                date: parts[4].to_string(),
-                message: parts[5].to_string(),
-                body: parts.get(6).unwrap_or(&"").to_string(),
-            });
-        }
-    }
+/// Get the diff between two refs, optionally for one file
+///
+/// An empty `to_ref` compares against the working tree.
+#[tauri::command]
+pub async fn git_diff_refs(
+    path: String,
+    from_ref: String,
+    to_ref: String,
+    file: Option<String>,
+) -> Result<String, String> {
+    git::diff_refs(&path, &from_ref, &to_ref, file.as_deref()).map_err(|e| e.to_string())
+}
 
-    Ok(commits)
+/// List the files changed between two refs with their status
+#[tauri::command]
+pub async fn git_diff_refs_files(
+    path: String,
+    from_ref: String,
+    to_ref: String,
+) -> Result<Vec<FileStatus>, String> {
+    git::diff_refs_files(&path, &from_ref, &to_ref).map_err(|e| e.to_string())
 }
 
-/// Stage files for commit
+/// Reset the current branch to a ref
+///
+/// A hard reset fails unless `confirmation` is `HARD_RESET_CONFIRMATION`.
 #[tauri::command]
-pub async fn git_stage(path: String, files: Vec<String>) -> Result<(), String> {
-    if files.is_empty() {
-        return Ok(());
-    }
+pub async fn git_reset(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    git_ref: String,
+    mode: ResetMode,
+    confirmation: Option<String>,
+) -> Result<GitCommit, String> {
+    let result =
+        git::reset(&path, &git_ref, mode, confirmation.as_deref()).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
+}
 
-    let mut args = vec!["add"];
-    args.extend(files.iter().map(|s| s.as_str()));
+/// Get the most recent moves of HEAD
+#[tauri::command]
+pub async fn git_reflog(path: String, count: u32) -> Result<Vec<ReflogEntry>, String> {
+    git::reflog(&path, count).map_err(|e| e.to_string())
+}
 
-    run_git_command(&path, &args)?;
-    Ok(())
+/// Undo the last checkout, reset or other move of HEAD, using the reflog
+///
+/// The working tree is kept unless `confirmation` is `HARD_RESET_CONFIRMATION`.
+#[tauri::command]
+pub async fn git_undo_last_operation(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    confirmation: Option<String>,
+) -> Result<UndoResult, String> {
+    let result =
+        git::undo_last_operation(&path, confirmation.as_deref()).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
 }
 
-/// Unstage files
+/// Merge a branch into the current one
 #[tauri::command]
-pub async fn git_unstage(path: String, files: Vec<String>) -> Result<(), String> {
-    if files.is_empty() {
-        return Ok(());
-    }
+pub async fn git_merge(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    branch: String,
+) -> Result<MergeResult, String> {
+    let result = git::merge(&path, &branch).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
+}
 
-    let mut args = vec!["reset", "HEAD", "--"];
-    args.extend(files.iter().map(|s| s.as_str()));
+/// List conflicted files with their base, ours and theirs content
+#[tauri::command]
+pub async fn git_conflicts(path: String) -> Result<Vec<GitConflict>, String> {
+    git::conflicts(&path).map_err(|e| e.to_string())
+}
 
-    run_git_command(&path, &args)?;
-    Ok(())
+/// Get the base, ours and theirs content of a conflicted file together, for
+/// the three-way merge editor
+#[tauri::command]
+pub async fn git_merge_file_versions(path: String, file: String) -> Result<GitConflict, String> {
+    git::merge_file_versions(&path, &file).map_err(|e| e.to_string())
 }
 
-/// Stage all changes
+/// Resolve a conflicted file with ours, theirs or custom content
 #[tauri::command]
-pub async fn git_stage_all(path: String) -> Result<(), String> {
-    run_git_command(&path, &["add", "-A"])?;
-    Ok(())
+pub async fn git_resolve_conflict(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    file: String,
+    resolution: ConflictResolution,
+) -> Result<(), String> {
+    let result = git::resolve_conflict(&path, &file, resolution).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
 }
 
-/// Commit staged changes
+/// Start an interactive rebase of the current branch onto a revision
 #[tauri::command]
-pub async fn git_commit(path: String, message: String) -> Result<GitCommit, String> {
-    // Create the commit
-    run_git_command(&path, &["commit", "-m", &message])?;
+pub async fn git_rebase_start(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    onto: String,
+    instructions: Vec<RebaseStep>,
+) -> Result<RebaseResult, String> {
+    let result = git::rebase_start(&path, &onto, instructions).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
+}
 
-    // Get the commit info
-    let commits = git_log(path, 1).await?;
-    commits
-        .into_iter()
-        .next()
-        .ok_or_else(|| "Failed to get commit info".to_string())
+/// Continue a rebase after resolving conflicts
+#[tauri::command]
+pub async fn git_rebase_continue(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<RebaseResult, String> {
+    let result = git::rebase_continue(&path).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
 }
 
-/// Discard changes to a file
+/// Skip the commit a rebase stopped on
 #[tauri::command]
-pub async fn git_discard(path: String, file_path: String) -> Result<(), String> {
-    run_git_command(&path, &["checkout", "--", &file_path])?;
-    Ok(())
+pub async fn git_rebase_skip(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+) -> Result<RebaseResult, String> {
+    let result = git::rebase_skip(&path).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
 }
 
-/// Get list of branches
+/// Abort a rebase, restoring the branch
 #[tauri::command]
-pub async fn git_branches(path: String) -> Result<Vec<GitBranch>, String> {
-    let output = run_git_command(&path, &["branch", "-a", "-v", "--format=%(refname:short)|%(objectname:short)|%(upstream:short)|%(HEAD)"])?;
-
-    let mut branches = Vec::new();
-
-    for line in output.lines() {
-        let parts: Vec<&str> = line.split('|').collect();
-        if parts.len() >= 4 {
-            branches.push(GitBranch {
-                name: parts[0].to_string(),
-                commit: parts[1].to_string(),
-                upstream: if parts[2].is_empty() {
-                    None
-                } else {
-                    Some(parts[2].to_string())
-                },
-                is_current: parts[3] == "*",
-                is_remote: parts[0].starts_with("remotes/") || parts[0].starts_with("origin/"),
-            });
-        }
-    }
+pub async fn git_rebase_abort(state: State<'_, Arc<AppState>>, path: String) -> Result<(), String> {
+    let result = git::rebase_abort(&path).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
+}
 
-    Ok(branches)
+/// List the repository's worktrees, the main one first
+#[tauri::command]
+pub async fn git_worktree_list(path: String) -> Result<Vec<GitWorktree>, String> {
+    git::worktrees(&path).map_err(|e| e.to_string())
 }
 
-#[derive(Debug, Serialize)]
-pub struct GitBranch {
-    pub name: String,
-    pub commit: String,
-    pub upstream: Option<String>,
-    pub is_current: bool,
-    pub is_remote: bool,
+/// Create a worktree, checking out `branch` (default: `name`) there
+#[tauri::command]
+pub async fn git_worktree_add(
+    path: String,
+    name: String,
+    worktree_path: String,
+    branch: Option<String>,
+) -> Result<GitWorktree, String> {
+    git::add_worktree(&path, &name, &worktree_path, branch.as_deref()).map_err(|e| e.to_string())
 }
 
-/// Checkout a branch
+/// Delete a worktree; sessions working in it go back to the project
 #[tauri::command]
-pub async fn git_checkout(path: String, branch: String) -> Result<(), String> {
-    run_git_command(&path, &["checkout", &branch])?;
+pub async fn git_worktree_remove(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    name: String,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let worktree = git::worktrees(&path)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|w| w.name.as_deref() == Some(name.as_str()));
+    git::remove_worktree(&path, &name, force.unwrap_or(false)).map_err(|e| e.to_string())?;
+
+    if let Some(worktree) = worktree {
+        let worktree = std::path::PathBuf::from(worktree.path);
+        state.session_worktrees.write().await.retain(|_, w| *w != worktree);
+    }
     Ok(())
 }
 
-/// Create a new branch
+/// Make a session's tools work in a worktree of the current project, or back
+/// in the project when `worktree_path` is `None`
 #[tauri::command]
-pub async fn git_create_branch(path: String, name: String, checkout: bool) -> Result<(), String> {
-    if checkout {
-        run_git_command(&path, &["checkout", "-b", &name])?;
-    } else {
-        run_git_command(&path, &["branch", &name])?;
-    }
+pub async fn set_session_worktree(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    worktree_path: Option<String>,
+) -> Result<(), String> {
+    let Some(worktree_path) = worktree_path else {
+        state.set_session_worktree(&session_id, None).await;
+        return Ok(());
+    };
+
+    let project = state
+        .get_project_path()
+        .await
+        .ok_or_else(|| "No project is open".to_string())?;
+    let wanted = std::path::Path::new(&worktree_path)
+        .canonicalize()
+        .map_err(|e| e.to_string())?;
+    let worktree = git::worktrees(&project.to_string_lossy())
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|w| std::path::PathBuf::from(w.path))
+        .find(|w| w.canonicalize().is_ok_and(|w| w == wanted))
+        .ok_or_else(|| format!("{} is not a worktree of the project", worktree_path))?;
+    state.set_session_worktree(&session_id, Some(worktree)).await;
     Ok(())
 }
 
 /// Pull changes
 #[tauri::command]
-pub async fn git_pull(path: String) -> Result<String, String> {
-    run_git_command(&path, &["pull"])
+pub async fn git_pull(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    credentials: Option<GitCredentials>,
+) -> Result<String, RemoteError> {
+    let result = git::pull(&path, &credentials.unwrap_or_default());
+    forget_status(&state, &path);
+    Ok(result?)
 }
 
 /// Push changes
 #[tauri::command]
-pub async fn git_push(path: String, set_upstream: bool) -> Result<String, String> {
-    if set_upstream {
-        // Get current branch
-        let branch = run_git_command(&path, &["branch", "--show-current"])?;
-        let branch = branch.trim();
-        run_git_command(&path, &["push", "-u", "origin", branch])
-    } else {
-        run_git_command(&path, &["push"])
-    }
+pub async fn git_push(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    set_upstream: bool,
+    credentials: Option<GitCredentials>,
+) -> Result<String, RemoteError> {
+    let result = git::push(&path, set_upstream, &credentials.unwrap_or_default());
+    forget_status(&state, &path);
+    Ok(result?)
 }
 
 /// Fetch from remote
+///
+/// A `depth` only fetches that many commits of each branch's history.
 #[tauri::command]
-pub async fn git_fetch(path: String) -> Result<String, String> {
-    run_git_command(&path, &["fetch", "--all", "--prune"])
+pub async fn git_fetch(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    credentials: Option<GitCredentials>,
+    depth: Option<u32>,
+) -> Result<String, RemoteError> {
+    let result = git::fetch(&path, &credentials.unwrap_or_default(), depth);
+    forget_status(&state, &path);
+    Ok(result?)
+}
+
+/// Fetch the rest of a shallow clone's history
+#[tauri::command]
+pub async fn git_unshallow(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    credentials: Option<GitCredentials>,
+) -> Result<String, RemoteError> {
+    let repository = path.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        git::unshallow(&path, &credentials.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| RemoteError::Failed {
+        message: e.to_string(),
+    })?;
+    forget_status(&state, &repository);
+    Ok(result?)
+}
+
+/// Clone a repository into `destination`, optionally shallow or of one branch
+///
+/// Partial clones aren't supported; use a `depth` and `git_unshallow` later.
+#[tauri::command]
+pub async fn git_clone(
+    url: String,
+    destination: String,
+    options: Option<CloneOptions>,
+    credentials: Option<GitCredentials>,
+) -> Result<(), RemoteError> {
+    tokio::task::spawn_blocking(move || {
+        git::clone(
+            &url,
+            &destination,
+            &options.unwrap_or_default(),
+            &credentials.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| RemoteError::Failed {
+        message: e.to_string(),
+    })?
+    .map_err(RemoteError::from)
+}
+
+/// Check whether paths are ignored, and which ignore file rule decides it
+#[tauri::command]
+pub async fn git_check_ignore(
+    path: String,
+    paths: Vec<String>,
+) -> Result<Vec<IgnoreCheck>, String> {
+    git::check_ignore(&path, &paths).map_err(|e| e.to_string())
+}
+
+/// Add a pattern to the repository's root `.gitignore`
+///
+/// Returns `false` if the pattern was already there.
+#[tauri::command]
+pub async fn add_to_gitignore(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    pattern: String,
+) -> Result<bool, String> {
+    let result = git::add_to_gitignore(&path, &pattern).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
 }
 
 /// Check if a directory is a git repository
 #[tauri::command]
 pub async fn is_git_repository(path: String) -> Result<bool, String> {
-    match run_git_command(&path, &["rev-parse", "--git-dir"]) {
-        Ok(_) => Ok(true),
-        Err(_) => Ok(false),
-    }
+    Ok(git::is_repository(&path))
 }
 
 /// Initialize a git repository
 #[tauri::command]
-pub async fn git_init(path: String) -> Result<(), String> {
-    run_git_command(&path, &["init"])?;
-    Ok(())
+pub async fn git_init(state: State<'_, Arc<AppState>>, path: String) -> Result<(), String> {
+    let result = git::init(&path).map_err(|e| e.to_string());
+    forget_status(&state, &path);
+    result
 }
 
 /// Show file content at a specific ref (HEAD, commit hash, :0 for index, etc.)
+///
+/// During a merge, `:1`, `:2` and `:3` give the base, ours and theirs
+/// versions of a conflicted file.
 #[tauri::command]
 pub async fn git_show_file(path: String, file_path: String, git_ref: String) -> Result<String, String> {
-    // Format: git show <ref>:<file_path>
-    let spec = format!("{}:{}", git_ref, file_path);
-    run_git_command(&path, &["show", &spec])
-}
-
-/// Run a git command and return the output
-fn run_git_command(path: &str, args: &[&str]) -> Result<String, String> {
-    let output = Command::new("git")
-        .args(args)
-        .current_dir(path)
-        .output()
-        .map_err(|e| format!("Failed to execute git: {}", e))?;
-
-    if output.status.success() {
-        String::from_utf8(output.stdout)
-            .map_err(|e| format!("Invalid UTF-8 in git output: {}", e))
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(stderr.to_string())
-    }
+    git::show_file(&path, &git_ref, &file_path).map_err(|e| e.to_string())
 }
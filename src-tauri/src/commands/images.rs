@@ -0,0 +1,155 @@
+//! Image commands
+//!
+//! This module provides Tauri commands for generating images with an image
+//! provider and saving them into the project, and for capturing screenshots
+//! to send to vision models.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use base64::Engine;
+use serde::Serialize;
+use tauri::State;
+
+use crate::commands::files::sandboxed_path;
+use crate::providers::images::ImageRequest;
+use crate::providers::ImageSource;
+use crate::state::AppState;
+use crate::tools::{screenshot, ScreenshotTarget};
+
+/// Directory (relative to the project root) where generated images are saved
+const GENERATED_IMAGES_DIR: &str = "generated/images";
+
+/// Result of an image generation
+#[derive(Debug, Serialize)]
+pub struct GenerateImageOutput {
+    pub path: String,
+    pub media_type: String,
+    pub size_bytes: usize,
+    pub provider: String,
+    pub revised_prompt: Option<String>,
+}
+
+/// Image provider info for the frontend
+#[derive(Debug, Serialize)]
+pub struct ImageProviderInfo {
+    pub name: String,
+    pub default_model: String,
+    pub supported_sizes: Vec<String>,
+}
+
+/// Generate an image and save it into the project
+///
+/// `output_path` may be absolute or relative to the project root, and must be
+/// inside the project unless `allow_outside_project` is set. When omitted,
+/// the image is saved under `generated/images/` with a name derived from the prompt.
+#[tauri::command]
+pub async fn generate_image(
+    state: State<'_, Arc<AppState>>,
+    prompt: String,
+    provider: Option<String>,
+    size: Option<String>,
+    model: Option<String>,
+    output_path: Option<String>,
+    allow_outside_project: Option<bool>,
+) -> Result<GenerateImageOutput, String> {
+    // Check the path before paying for the image
+    let output_path = match output_path {
+        Some(path) => Some(PathBuf::from(
+            sandboxed_path(&state, &path, allow_outside_project).await?,
+        )),
+        None => None,
+    };
+
+    let image_provider = state
+        .get_image_provider(provider.as_deref())
+        .await
+        .ok_or_else(|| "No image provider configured".to_string())?;
+
+    let request = ImageRequest {
+        prompt: prompt.clone(),
+        size,
+        model,
+    };
+
+    let image = image_provider
+        .generate(request)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let path = match output_path {
+        Some(path) => path,
+        None => state
+            .get_project_path()
+            .await
+            .ok_or_else(|| "No project open to save the image into".to_string())?
+            .join(GENERATED_IMAGES_DIR)
+            .join(format!("{}.{}", image_file_stem(&prompt), image.extension())),
+    };
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, &image.data).map_err(|e| e.to_string())?;
+
+    log::info!("Saved generated image to {}", path.display());
+
+    Ok(GenerateImageOutput {
+        path: path.to_string_lossy().to_string(),
+        media_type: image.media_type,
+        size_bytes: image.data.len(),
+        provider: image_provider.name().to_string(),
+        revised_prompt: image.revised_prompt,
+    })
+}
+
+/// Get available image providers
+#[tauri::command]
+pub async fn get_image_providers(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<ImageProviderInfo>, String> {
+    let image_providers = state.image_providers.read().await;
+
+    Ok(image_providers
+        .values()
+        .map(|p| ImageProviderInfo {
+            name: p.name().to_string(),
+            default_model: p.default_model().to_string(),
+            supported_sizes: p.supported_sizes().iter().map(|s| s.to_string()).collect(),
+        })
+        .collect())
+}
+
+/// Build a file name from the first few words of a prompt
+fn image_file_stem(prompt: &str) -> String {
+    let slug = prompt
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .take(6)
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    let id = uuid::Uuid::new_v4().to_string();
+    if slug.is_empty() {
+        format!("image-{}", &id[..8])
+    } else {
+        format!("{}-{}", slug, &id[..8])
+    }
+}
+
+/// Capture the screen or a window as a base64 PNG image source
+///
+/// The result can be sent as an image content block to vision models.
+#[tauri::command]
+pub async fn capture_screenshot(target: Option<ScreenshotTarget>) -> Result<ImageSource, String> {
+    let target = target.unwrap_or_default();
+    let png = tokio::task::spawn_blocking(move || screenshot::capture_screenshot(target))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    Ok(ImageSource::Base64 {
+        media_type: "image/png".to_string(),
+        data: base64::engine::general_purpose::STANDARD.encode(png),
+    })
+}
@@ -0,0 +1,104 @@
+//! Semantic index commands
+//!
+//! This module provides Tauri commands for building the project's semantic
+//! search index and making the `semantic_search` tool available, and for the
+//! optional text index that speeds up grep.
+
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::index::{content_hash, IndexStats, SemanticIndex};
+use crate::state::AppState;
+use crate::tools::{SemanticSearchTool, TextIndex};
+
+/// Directory (relative to the app data directory) holding project indexes
+const INDEXES_DIR: &str = "indexes";
+
+/// Index the current project for semantic search
+///
+/// Only files that changed since the last run are re-embedded. Once the
+/// index is built, the `semantic_search` tool is offered to the AI.
+#[tauri::command]
+pub async fn reindex_project(
+    state: State<'_, Arc<AppState>>,
+    provider: Option<String>,
+) -> Result<IndexStats, String> {
+    let project_path = state
+        .get_project_path()
+        .await
+        .ok_or_else(|| "No project directory is set".to_string())?;
+    let data_dir = state
+        .get_data_dir()
+        .await
+        .ok_or_else(|| "App data directory is not available".to_string())?;
+    let embedding_provider = state
+        .get_embedding_provider(provider.as_deref())
+        .await
+        .ok_or_else(|| "No embedding provider configured".to_string())?;
+
+    // One database per project, named after its path
+    let project_key = content_hash(&project_path.to_string_lossy());
+    let db_path = data_dir
+        .join(INDEXES_DIR)
+        .join(format!("{}.sqlite", &project_key[..16]));
+
+    let index = SemanticIndex::open(&project_path, &db_path).map_err(|e| e.to_string())?;
+    let stats = index
+        .reindex(embedding_provider.as_ref())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    log::info!(
+        "Indexed {}: {} files updated, {} chunks total",
+        project_path.display(),
+        stats.files_indexed,
+        stats.total_chunks
+    );
+
+    // The project may have changed while indexing
+    if state.get_project_path().await.as_ref() == Some(&project_path) {
+        let index = Arc::new(index);
+        *state.semantic_index.write().await = Some(index.clone());
+        state
+            .register_tool(Arc::new(SemanticSearchTool::new(index, embedding_provider)))
+            .await;
+    }
+
+    Ok(stats)
+}
+
+/// Build a text index of the current project, so grep only reads files that
+/// could match
+///
+/// The index is kept up to date while the project is watched.
+///
+/// # Returns
+/// The number of files indexed
+#[tauri::command]
+pub async fn build_text_index(state: State<'_, Arc<AppState>>) -> Result<usize, String> {
+    let project_path = state
+        .get_project_path()
+        .await
+        .ok_or_else(|| "No project directory is set".to_string())?;
+    let root = project_path.clone();
+    let index = tokio::task::spawn_blocking(move || TextIndex::build(&root))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    let files = index.len();
+    log::info!("Built text index of {}: {} files", project_path.display(), files);
+
+    // The project may have changed while indexing
+    if state.get_project_path().await.as_ref() == Some(&project_path) {
+        *state.text_index.write().await = Some(Arc::new(index));
+    }
+    Ok(files)
+}
+
+/// Drop the text index; grep walks the project again
+#[tauri::command]
+pub async fn drop_text_index(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    *state.text_index.write().await = None;
+    Ok(())
+}
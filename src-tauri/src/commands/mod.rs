@@ -4,11 +4,29 @@
 //! via Tauri's IPC mechanism.
 
 pub mod chat;
+pub mod clipboard;
 pub mod files;
+pub mod forge;
 pub mod git;
+pub mod images;
+pub mod index;
+pub mod prompts;
+pub mod review;
+pub mod scaffold;
+pub mod sessions;
+pub mod settings;
 pub mod terminal;
 
 pub use chat::*;
+pub use clipboard::*;
 pub use files::*;
+pub use forge::*;
 pub use git::*;
+pub use images::*;
+pub use index::*;
+pub use prompts::*;
+pub use review::*;
+pub use scaffold::*;
+pub use sessions::*;
+pub use settings::*;
 pub use terminal::*;
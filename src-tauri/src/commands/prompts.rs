@@ -0,0 +1,71 @@
+//! Prompt library commands
+//!
+//! This module provides Tauri commands for managing saved prompts and
+//! rendering them for insertion into a message.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::prompts::{self, PromptInput, SavedPrompt};
+use crate::state::AppState;
+
+/// List the saved prompts, sorted by name
+#[tauri::command]
+pub async fn list_prompts(state: State<'_, Arc<AppState>>) -> Result<Vec<SavedPrompt>, String> {
+    Ok(state.prompt_library().await.list())
+}
+
+/// Save a new prompt
+#[tauri::command]
+pub async fn create_prompt(
+    state: State<'_, Arc<AppState>>,
+    prompt: PromptInput,
+) -> Result<SavedPrompt, String> {
+    state
+        .prompt_library()
+        .await
+        .create(prompt)
+        .map_err(|e| e.to_string())
+}
+
+/// Replace a saved prompt's name, description and content
+#[tauri::command]
+pub async fn update_prompt(
+    state: State<'_, Arc<AppState>>,
+    id: String,
+    prompt: PromptInput,
+) -> Result<SavedPrompt, String> {
+    state
+        .prompt_library()
+        .await
+        .update(&id, prompt)
+        .map_err(|e| e.to_string())
+}
+
+/// Delete a saved prompt
+#[tauri::command]
+pub async fn delete_prompt(state: State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
+    state
+        .prompt_library()
+        .await
+        .delete(&id)
+        .map_err(|e| e.to_string())
+}
+
+/// Render a saved prompt by name, filling in its variables
+#[tauri::command]
+pub async fn render_prompt(
+    state: State<'_, Arc<AppState>>,
+    name: String,
+    variables: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    let prompt = state
+        .prompt_library()
+        .await
+        .get(&name)
+        .map_err(|e| e.to_string())?;
+    prompts::render_prompt(&prompt.content, &variables.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
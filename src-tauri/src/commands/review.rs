@@ -0,0 +1,45 @@
+//! Code review commands
+//!
+//! This module provides the Tauri command that asks the AI provider to review
+//! a git diff and returns its findings for display as annotations.
+
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::review::{self, ReviewFinding};
+use crate::state::AppState;
+use crate::tools::git;
+
+/// Review a diff and return the provider's findings
+///
+/// # Arguments
+/// * `path` - Repository to review
+/// * `range` - Ref range such as `main..HEAD`; the staged changes are reviewed if omitted
+/// * `provider` - Provider to use instead of the active one
+#[tauri::command]
+pub async fn review_diff(
+    state: State<'_, Arc<AppState>>,
+    path: String,
+    range: Option<String>,
+    provider: Option<String>,
+) -> Result<Vec<ReviewFinding>, String> {
+    let diff = match range.as_deref() {
+        Some(range) => git::diff_range(&path, range),
+        None => git::diff(&path, true, None),
+    }
+    .map_err(|e| e.to_string())?;
+    if diff.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let provider = match &provider {
+        Some(name) => state.get_provider(name).await,
+        None => state.get_active_provider().await,
+    }
+    .ok_or_else(|| "No AI provider configured".to_string())?;
+
+    review::review_diff(provider.as_ref(), &state.request_scheduler, &diff)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,48 @@
+//! Project scaffolding commands
+//!
+//! This module provides Tauri commands for listing project templates and
+//! creating new projects from them.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tauri::State;
+
+use crate::scaffold::{self, TemplateInfo, TEMPLATES_DIR};
+use crate::state::AppState;
+
+/// The directory holding user templates, if the app data directory is known
+async fn user_templates_dir(state: &AppState) -> Option<PathBuf> {
+    state.get_data_dir().await.map(|dir| dir.join(TEMPLATES_DIR))
+}
+
+/// List the built-in and user project templates
+#[tauri::command]
+pub async fn list_project_templates(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<TemplateInfo>, String> {
+    let user_dir = user_templates_dir(&state).await;
+    Ok(scaffold::list_templates(user_dir.as_deref()))
+}
+
+/// Create a new project from a template
+///
+/// # Returns
+/// The files created, relative to `dest`
+#[tauri::command]
+pub async fn scaffold_project(
+    state: State<'_, Arc<AppState>>,
+    template: String,
+    dest: String,
+    vars: Option<HashMap<String, String>>,
+) -> Result<Vec<String>, String> {
+    let user_dir = user_templates_dir(&state).await;
+    scaffold::scaffold_project(
+        &template,
+        &PathBuf::from(dest),
+        &vars.unwrap_or_default(),
+        user_dir.as_deref(),
+    )
+    .map_err(|e| e.to_string())
+}
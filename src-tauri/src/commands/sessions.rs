@@ -0,0 +1,265 @@
+//! Chat session commands
+//!
+//! This module provides Tauri commands for saving conversations and
+//! loading them again after a restart.
+
+use std::sync::Arc;
+
+use tauri::{AppHandle, Emitter, State};
+
+use crate::providers::{ChatMessage, Role, Usage};
+use crate::sessions::{
+    self, first_exchange, ExportFormat, NewSession, Session, SessionConfig, SessionSearchResult,
+    SessionSummary, StoredMessage, DEFAULT_SEARCH_LIMIT,
+};
+use crate::state::AppState;
+
+/// Event emitted with a session's summary when it changes in the background,
+/// e.g. when its title is generated
+pub const SESSION_UPDATED_EVENT: &str = "session-updated";
+
+/// Start a new saved session
+///
+/// The session is associated with the current project, and pinned to the
+/// active provider with its model, temperature and default system prompt,
+/// unless others are given.
+#[tauri::command]
+pub async fn create_session(
+    state: State<'_, Arc<AppState>>,
+    session: Option<NewSession>,
+) -> Result<SessionSummary, String> {
+    let mut session = session.unwrap_or_default();
+    if session.project_path.is_none() {
+        session.project_path = state
+            .get_project_path()
+            .await
+            .map(|p| p.to_string_lossy().to_string());
+    }
+
+    let provider = match &session.provider {
+        Some(name) => state.get_provider(name).await,
+        None => state.get_active_provider().await,
+    };
+    if let Some(provider) = provider {
+        session
+            .provider
+            .get_or_insert_with(|| provider.name().to_string());
+        session
+            .model
+            .get_or_insert_with(|| provider.model().to_string());
+        session.temperature.get_or_insert(provider.temperature());
+        if session.system_prompt.is_none() {
+            let model = session.model.as_deref().unwrap_or(provider.model());
+            session.system_prompt = state.default_system_prompt(provider.name(), model).await;
+        }
+    }
+    state
+        .session_store()
+        .await?
+        .create_session(session)
+        .map_err(|e| e.to_string())
+}
+
+/// Change the provider, model, temperature or system prompt a session is pinned to
+#[tauri::command]
+pub async fn update_session_config(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    config: SessionConfig,
+) -> Result<Session, String> {
+    let store = state.session_store().await?;
+    store
+        .update_config(&session_id, &config)
+        .and_then(|_| store.load_session(&session_id))
+        .map_err(|e| e.to_string())
+}
+
+/// Add a message (including tool calls and results) to a saved session
+///
+/// Once an untitled session has its first reply, a title and summary are
+/// generated in the background and announced with `SESSION_UPDATED_EVENT`.
+#[tauri::command]
+pub async fn append_message(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    message: ChatMessage,
+    usage: Option<Usage>,
+) -> Result<StoredMessage, String> {
+    let store = state.session_store().await?;
+    let stored = store
+        .append_message(&session_id, &message, usage.as_ref())
+        .map_err(|e| e.to_string())?;
+
+    if message.role == Role::Assistant {
+        let session = store.load_session(&session_id).map_err(|e| e.to_string())?;
+        let replies = session
+            .messages
+            .iter()
+            .filter(|m| m.message.role == Role::Assistant)
+            .count();
+        if session.summary.title.is_none() && replies == 1 {
+            tauri::async_runtime::spawn(title_session(app, state.inner().clone(), session));
+        }
+    }
+    Ok(stored)
+}
+
+/// Generate and save a session's title, then tell the frontend
+async fn title_session(app: AppHandle, state: Arc<AppState>, session: Session) {
+    let Some(provider) = state.get_utility_provider().await else {
+        return;
+    };
+    let messages: Vec<ChatMessage> = session.messages.iter().map(|m| m.message.clone()).collect();
+    let Some(exchange) = first_exchange(&messages) else {
+        return;
+    };
+
+    let permit = state
+        .request_scheduler
+        .acquire(provider.name(), |_| {})
+        .await;
+    let title = sessions::generate_title(provider.as_ref(), exchange).await;
+    drop(permit);
+    let title = match title {
+        Ok(title) => title,
+        Err(e) => {
+            log::warn!(
+                "Failed to generate a title for session {}: {}",
+                session.summary.id,
+                e
+            );
+            return;
+        }
+    };
+    let Ok(store) = state.session_store().await else {
+        return;
+    };
+    let result = store
+        .set_title(&session.summary.id, &title.title, title.summary.as_deref())
+        .and_then(|_| store.load_session(&session.summary.id));
+    match result {
+        Ok(updated) => {
+            let _ = app.emit(SESSION_UPDATED_EVENT, &updated.summary);
+        }
+        Err(e) => log::warn!(
+            "Failed to save title for session {}: {}",
+            session.summary.id,
+            e
+        ),
+    }
+}
+
+/// List saved sessions, most recently active first
+#[tauri::command]
+pub async fn list_sessions(state: State<'_, Arc<AppState>>) -> Result<Vec<SessionSummary>, String> {
+    state
+        .session_store()
+        .await?
+        .list_sessions()
+        .map_err(|e| e.to_string())
+}
+
+/// Find saved sessions whose messages contain every word of a query
+#[tauri::command]
+pub async fn search_sessions(
+    state: State<'_, Arc<AppState>>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SessionSearchResult>, String> {
+    state
+        .session_store()
+        .await?
+        .search_sessions(&query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+        .map_err(|e| e.to_string())
+}
+
+/// Load a saved session with all its messages
+#[tauri::command]
+pub async fn load_session(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Session, String> {
+    state
+        .session_store()
+        .await?
+        .load_session(&session_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Delete a saved session and its messages
+#[tauri::command]
+pub async fn delete_session(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<(), String> {
+    state
+        .session_store()
+        .await?
+        .delete_session(&session_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Export a saved session as Markdown or JSON
+///
+/// Markdown is for sharing, e.g. attaching to a PR; JSON keeps everything
+/// needed to import the session again.
+#[tauri::command]
+pub async fn export_session(
+    state: State<'_, Arc<AppState>>,
+    session_id: String,
+    format: Option<ExportFormat>,
+) -> Result<String, String> {
+    let session = state
+        .session_store()
+        .await?
+        .load_session(&session_id)
+        .map_err(|e| e.to_string())?;
+    sessions::export_session(&session, format.unwrap_or_default()).map_err(|e| e.to_string())
+}
+
+/// Import a session from a JSON export, saving it as a new session
+#[tauri::command]
+pub async fn import_session(
+    state: State<'_, Arc<AppState>>,
+    content: String,
+) -> Result<SessionSummary, String> {
+    let session = sessions::parse_session_export(&content).map_err(|e| e.to_string())?;
+    state
+        .session_store()
+        .await?
+        .import_session(&session)
+        .map_err(|e| e.to_string())
+}
+
+/// Fork a saved session after one of its messages
+///
+/// The new session contains the conversation up to and including the
+/// message, so it can continue in a different direction.
+#[tauri::command]
+pub async fn branch_session(
+    state: State<'_, Arc<AppState>>,
+    message_id: i64,
+) -> Result<SessionSummary, String> {
+    state
+        .session_store()
+        .await?
+        .fork_session(message_id, true)
+        .map_err(|e| e.to_string())
+}
+
+/// Fork a saved session just before one of its messages, to generate it again
+///
+/// Returns the new session, ending with the messages that led up to the
+/// given one; the frontend sends them again, e.g. with a different model.
+#[tauri::command]
+pub async fn regenerate_from(
+    state: State<'_, Arc<AppState>>,
+    message_id: i64,
+) -> Result<Session, String> {
+    let store = state.session_store().await?;
+    let branch = store
+        .fork_session(message_id, false)
+        .map_err(|e| e.to_string())?;
+    store.load_session(&branch.id).map_err(|e| e.to_string())
+}
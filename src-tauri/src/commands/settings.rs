@@ -0,0 +1,60 @@
+//! Settings commands
+//!
+//! This module provides Tauri commands for reading and updating the
+//! backend settings.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::State;
+
+use crate::settings::Settings;
+use crate::state::AppState;
+
+/// Get the current backend settings
+#[tauri::command]
+pub async fn get_settings(state: State<'_, Arc<AppState>>) -> Result<Settings, String> {
+    Ok(state.get_settings().await)
+}
+
+/// Update the backend settings
+///
+/// Out-of-range values are clamped; the effective settings are returned.
+#[tauri::command]
+pub async fn update_settings(
+    state: State<'_, Arc<AppState>>,
+    settings: Settings,
+) -> Result<Settings, String> {
+    state.set_settings(settings).await
+}
+
+/// Get all configured default system prompts, keyed by "provider" or "provider/model"
+#[tauri::command]
+pub async fn get_default_system_prompts(
+    state: State<'_, Arc<AppState>>,
+) -> Result<HashMap<String, String>, String> {
+    Ok(state.get_settings().await.system_prompts)
+}
+
+/// Set or clear the default system prompt for a provider, optionally scoped to a model
+///
+/// Passing `None` (or an empty prompt) removes the configured prompt.
+#[tauri::command]
+pub async fn set_default_system_prompt(
+    state: State<'_, Arc<AppState>>,
+    provider: String,
+    model: Option<String>,
+    prompt: Option<String>,
+) -> Result<(), String> {
+    let key = Settings::system_prompt_key(&provider, model.as_deref());
+    state
+        .update_settings(|settings| match prompt {
+            Some(prompt) if !prompt.trim().is_empty() => {
+                settings.system_prompts.insert(key, prompt);
+            }
+            _ => {
+                settings.system_prompts.remove(&key);
+            }
+        })
+        .await?;
+    Ok(())
+}
@@ -6,21 +6,41 @@
 
 use std::collections::HashMap;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use portable_pty::{native_pty_system, CommandBuilder, PtyPair, PtySize};
 use serde::Serialize;
 use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::{mpsc, Mutex, RwLock};
 
+use crate::saved_terminals::{
+    append_scrollback, terminal_updates, SavedTerminal, TerminalUpdates,
+};
+use crate::state::AppState;
+
+/// How often a terminal's latest output is saved for restoring it
+const SCROLLBACK_SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Terminal info returned to frontend
 #[derive(Debug, Clone, Serialize)]
 pub struct TerminalInfo {
     pub id: String,
     pub cols: u16,
     pub rows: u16,
+    /// The directory the shell is in, as last announced by it
     pub cwd: String,
+    pub shell: String,
+    pub title: Option<String>,
+}
+
+/// A relaunched terminal with the end of its output from before the restart
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoredTerminal {
+    #[serde(flatten)]
+    pub info: TerminalInfo,
+    pub scrollback: String,
 }
 
 /// PTY output event emitted to frontend
@@ -116,9 +136,81 @@ pub async fn spawn_terminal(
         .map(PathBuf::from)
         .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/")));
 
-    let terminal_id = uuid::Uuid::new_v4().to_string();
-    let cols = cols.unwrap_or(80);
-    let rows = rows.unwrap_or(24);
+    start_terminal(
+        &app,
+        TerminalInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            cols: cols.unwrap_or(80),
+            rows: rows.unwrap_or(24),
+            cwd: working_dir.to_string_lossy().to_string(),
+            shell: get_default_shell(),
+            title: None,
+        },
+        String::new(),
+    )
+    .await
+}
+
+/// Relaunch the terminals that were open when the app last quit
+///
+/// Each shell starts again in the directory it was last in, keeping its ID
+/// and title, and the end of its previous output is returned so it can be
+/// shown above the new prompt. Terminals that can't be started again are
+/// forgotten.
+#[tauri::command]
+pub async fn restore_terminals(app: AppHandle) -> Result<Vec<RestoredTerminal>, String> {
+    let terminal_state = app.try_state::<TerminalState>().ok_or_else(|| {
+        "Terminal state not initialized".to_string()
+    })?;
+    let saved = app.state::<Arc<AppState>>().saved_terminals().await;
+
+    let mut restored = Vec::new();
+    for terminal in saved.all() {
+        // Already running, e.g. if the frontend reloaded
+        if terminal_state.get_session(&terminal.id).await.is_some() {
+            continue;
+        }
+        let cwd = if Path::new(&terminal.cwd).is_dir() {
+            terminal.cwd
+        } else {
+            std::env::current_dir()
+                .map(|dir| dir.to_string_lossy().to_string())
+                .unwrap_or_else(|_| "/".to_string())
+        };
+        let info = TerminalInfo {
+            id: terminal.id.clone(),
+            cols: terminal.cols,
+            rows: terminal.rows,
+            cwd,
+            shell: terminal.shell,
+            title: terminal.title,
+        };
+        match start_terminal(&app, info, terminal.scrollback.clone()).await {
+            Ok(info) => restored.push(RestoredTerminal {
+                info,
+                scrollback: terminal.scrollback,
+            }),
+            Err(e) => {
+                log::warn!("Failed to restore terminal {}: {}", terminal.id, e);
+                saved.remove(&terminal.id);
+            }
+        }
+    }
+    Ok(restored)
+}
+
+/// Start a shell in a new PTY and remember it so it can be restored
+///
+/// # Arguments
+/// * `scrollback` - Output from before a restart, which the new output is
+///   saved after
+async fn start_terminal(
+    app: &AppHandle,
+    terminal_info: TerminalInfo,
+    scrollback: String,
+) -> Result<TerminalInfo, String> {
+    let terminal_id = terminal_info.id.clone();
+    let working_dir = PathBuf::from(&terminal_info.cwd);
 
     // Create the PTY system
     let pty_system = native_pty_system();
@@ -126,15 +218,15 @@ pub async fn spawn_terminal(
     // Create PTY with specified size
     let pair = pty_system
         .openpty(PtySize {
-            rows,
-            cols,
+            rows: terminal_info.rows,
+            cols: terminal_info.cols,
             pixel_width: 0,
             pixel_height: 0,
         })
         .map_err(|e| format!("Failed to open PTY: {}", e))?;
 
     // Build the shell command
-    let mut cmd = CommandBuilder::new(get_default_shell());
+    let mut cmd = CommandBuilder::new(&terminal_info.shell);
     cmd.cwd(&working_dir);
 
     // Set environment variables for proper terminal behavior
@@ -162,13 +254,6 @@ pub async fn spawn_terminal(
     // Create shutdown channel
     let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
 
-    let terminal_info = TerminalInfo {
-        id: terminal_id.clone(),
-        cols,
-        rows,
-        cwd: working_dir.to_string_lossy().to_string(),
-    };
-
     // Create the PTY session
     let session = PtySession {
         info: terminal_info.clone(),
@@ -186,6 +271,18 @@ pub async fn spawn_terminal(
         .add_session(terminal_id.clone(), session)
         .await;
 
+    // Remember the terminal so it can be restored after a restart
+    let saved = app.state::<Arc<AppState>>().saved_terminals().await;
+    saved.insert(SavedTerminal {
+        id: terminal_id.clone(),
+        cwd: terminal_info.cwd.clone(),
+        shell: terminal_info.shell.clone(),
+        title: terminal_info.title.clone(),
+        cols: terminal_info.cols,
+        rows: terminal_info.rows,
+        scrollback: scrollback.clone(),
+    });
+
     // Spawn a task to read PTY output and emit events
     let app_handle = app.clone();
     let tid = terminal_id.clone();
@@ -221,17 +318,31 @@ pub async fn spawn_terminal(
     // Async task to receive data and emit events
     let tid = terminal_id.clone();
     tokio::spawn(async move {
+        // Output is saved now and then rather than on every write
+        let mut scrollback = scrollback;
+        let mut unsaved = false;
+        let mut save_timer = tokio::time::interval(SCROLLBACK_SAVE_INTERVAL);
         loop {
             tokio::select! {
                 _ = shutdown_rx.recv() => {
                     log::info!("PTY async handler shutdown requested for terminal {}", tid);
                     break;
                 }
+                _ = save_timer.tick(), if unsaved => {
+                    saved.update(&tid, |terminal| terminal.scrollback = scrollback.clone());
+                    unsaved = false;
+                }
                 result = output_rx.recv() => {
                     match result {
                         Some(data) => {
                             // Got data, emit to frontend
                             let data_str = String::from_utf8_lossy(&data).to_string();
+                            append_scrollback(&mut scrollback, &data_str);
+                            unsaved = true;
+                            let updates = terminal_updates(&data_str);
+                            if updates != TerminalUpdates::default() {
+                                update_terminal_info(&app_handle, &tid, updates).await;
+                            }
                             let event = PtyOutputEvent {
                                 terminal_id: tid.clone(),
                                 data: data_str,
@@ -249,6 +360,9 @@ pub async fn spawn_terminal(
                 }
             }
         }
+        if unsaved {
+            saved.update(&tid, |terminal| terminal.scrollback = scrollback);
+        }
 
         // Wait for child process to exit and get exit code
         let exit_code = match child.wait() {
@@ -276,6 +390,49 @@ pub async fn spawn_terminal(
     Ok(terminal_info)
 }
 
+/// Record a working directory or title a terminal's shell announced
+async fn update_terminal_info(app: &AppHandle, terminal_id: &str, updates: TerminalUpdates) {
+    if let Some(session) = app.state::<TerminalState>().get_session(terminal_id).await {
+        let mut session = session.lock().await;
+        if let Some(cwd) = &updates.cwd {
+            session.info.cwd = cwd.clone();
+        }
+        if let Some(title) = &updates.title {
+            session.info.title = Some(title.clone());
+        }
+    }
+    let saved = app.state::<Arc<AppState>>().saved_terminals().await;
+    saved.update(terminal_id, |terminal| {
+        if let Some(cwd) = updates.cwd {
+            terminal.cwd = cwd;
+        }
+        if let Some(title) = updates.title {
+            terminal.title = Some(title);
+        }
+    });
+}
+
+/// Set the title a terminal is shown and restored with
+#[tauri::command]
+pub async fn set_terminal_title(
+    app: AppHandle,
+    terminal_id: String,
+    title: String,
+) -> Result<(), String> {
+    let terminal_state = app.try_state::<TerminalState>().ok_or_else(|| {
+        "Terminal state not initialized".to_string()
+    })?;
+    if terminal_state.get_session(&terminal_id).await.is_none() {
+        return Err(format!("Terminal {} not found", terminal_id));
+    }
+    let updates = TerminalUpdates {
+        title: Some(title),
+        ..Default::default()
+    };
+    update_terminal_info(&app, &terminal_id, updates).await;
+    Ok(())
+}
+
 /// Write data to a terminal PTY
 #[tauri::command]
 pub async fn write_terminal(
@@ -326,6 +483,11 @@ pub async fn resize_terminal(
     session.resize(cols, rows)?;
     session.info.cols = cols;
     session.info.rows = rows;
+    let saved = app.state::<Arc<AppState>>().saved_terminals().await;
+    saved.update(&terminal_id, |terminal| {
+        terminal.cols = cols;
+        terminal.rows = rows;
+    });
 
     log::debug!("Resized terminal {} to {}x{}", terminal_id, cols, rows);
 
@@ -333,6 +495,8 @@ pub async fn resize_terminal(
 }
 
 /// Close a terminal session
+///
+/// A closed terminal isn't restored after a restart.
 #[tauri::command]
 pub async fn close_terminal(app: AppHandle, terminal_id: String) -> Result<(), String> {
     let terminal_state = app.try_state::<TerminalState>().ok_or_else(|| {
@@ -345,6 +509,10 @@ pub async fn close_terminal(app: AppHandle, terminal_id: String) -> Result<(), S
         let _ = session.shutdown_tx.send(()).await;
         log::info!("Closed terminal {}", terminal_id);
     }
+    app.state::<Arc<AppState>>()
+        .saved_terminals()
+        .await
+        .remove(&terminal_id);
 
     Ok(())
 }
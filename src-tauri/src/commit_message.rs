@@ -0,0 +1,150 @@
+//! AI commit message generation
+//!
+//! The staged diff is sent to the provider, which is asked for a
+//! conventional-commit style message. Diffs too large to send whole are
+//! summarized as a file list with the start of each file's changes.
+
+use serde::Serialize;
+
+use crate::providers::scheduler::RequestScheduler;
+use crate::providers::{ChatMessage, Provider, ProviderError};
+
+/// Most characters of diff sent when generating a commit message
+pub const MAX_COMMIT_DIFF_CHARS: usize = 30_000;
+
+const COMMIT_MESSAGE_SYSTEM_PROMPT: &str = "You write git commit messages. Given a staged diff, \
+reply with only a commit message in the conventional commit style:
+- a title line of the form `type(scope): summary`, where type is one of feat, fix, docs, style, \
+refactor, perf, test, build, ci or chore, the scope is optional, and the summary is in the \
+imperative mood, lowercase, without a trailing period and at most 72 characters long
+- if the change needs explaining, a blank line followed by a body wrapped at 72 characters that \
+says what changed and why, not how
+Don't wrap the message in a code block.";
+
+/// A generated commit message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommitMessage {
+    pub title: String,
+    /// Empty if the change needs no explanation
+    pub body: String,
+}
+
+/// Shorten a diff to about `max_chars`
+///
+/// Diffs that fit are returned unchanged. Otherwise the result starts with
+/// `stat` (the output of `git diff --stat`) and includes the start of each
+/// file's changes, giving every file an equal share of the space.
+pub fn summarize_diff(diff: &str, stat: &str, max_chars: usize) -> String {
+    if diff.len() <= max_chars {
+        return diff.to_string();
+    }
+
+    let mut files: Vec<&str> = Vec::new();
+    let mut start = 0;
+    for (i, _) in diff.match_indices("diff --git ") {
+        if i > start && diff[..i].ends_with('\n') {
+            files.push(&diff[start..i]);
+            start = i;
+        }
+    }
+    files.push(&diff[start..]);
+
+    let share = max_chars.saturating_sub(stat.len()) / files.len();
+    let mut summary = format!(
+        "The diff is too large to show in full. Changed files:\n{}\n",
+        stat.trim_end()
+    );
+    for file in files {
+        if file.len() <= share {
+            summary.push_str(file);
+            continue;
+        }
+        // Cut at a line boundary within the file's share
+        let mut end = share;
+        while !file.is_char_boundary(end) {
+            end -= 1;
+        }
+        let cut = file[..end].rfind('\n').map_or(0, |i| i + 1);
+        summary.push_str(&file[..cut]);
+        summary.push_str("[... diff truncated ...]\n");
+    }
+    summary
+}
+
+/// Split a reply into a commit message's title and body
+pub fn parse_commit_message(reply: &str) -> CommitMessage {
+    let message = reply.trim();
+    let message = message
+        .strip_prefix("```")
+        .and_then(|m| m.strip_suffix("```"))
+        // Skip the fence's language tag, if any
+        .map(|m| m.split_once('\n').map_or(m, |(_, rest)| rest))
+        .unwrap_or(message)
+        .trim();
+
+    let (title, body) = message.split_once('\n').unwrap_or((message, ""));
+    CommitMessage {
+        title: title.trim().to_string(),
+        body: body.trim().to_string(),
+    }
+}
+
+/// Ask the provider for a commit message for a diff
+///
+/// # Arguments
+/// * `diff` - The staged diff
+/// * `stat` - Its `git diff --stat` summary, used if the diff is too large
+pub async fn generate_commit_message(
+    provider: &dyn Provider,
+    scheduler: &RequestScheduler,
+    diff: &str,
+    stat: &str,
+) -> Result<CommitMessage, ProviderError> {
+    let messages = vec![
+        ChatMessage::system(COMMIT_MESSAGE_SYSTEM_PROMPT),
+        ChatMessage::user(format!(
+            "Write a commit message for this diff:\n\n{}",
+            summarize_diff(diff, stat, MAX_COMMIT_DIFF_CHARS)
+        )),
+    ];
+    let response = {
+        let _permit = scheduler.acquire(provider.name(), |_| {}).await;
+        provider.chat(messages, None).await?
+    };
+
+    let message = parse_commit_message(&response.text());
+    if message.title.is_empty() {
+        return Err(ProviderError::InvalidResponse(
+            "The provider returned an empty commit message".to_string(),
+        ));
+    }
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_diff_and_parse_message() {
+        let diff = format!(
+            "diff --git a/a.rs b/a.rs\n+{}\n+end\ndiff --git a/b.rs b/b.rs\n+short\n",
+            "x".repeat(100)
+        );
+        assert_eq!(summarize_diff(&diff, "", 1000), diff);
+
+        let summary = summarize_diff(&diff, " a.rs | 2 ++\n b.rs | 1 +\n", 120);
+        assert!(summary.contains(" b.rs | 1 +\n"));
+        assert!(summary.contains("diff --git a/a.rs b/a.rs\n[... diff truncated ...]\n"));
+        assert!(summary.ends_with("diff --git a/b.rs b/b.rs\n+short\n"));
+
+        assert_eq!(
+            parse_commit_message("```text\nfeat(git): add thing\n\nBecause.\n```"),
+            CommitMessage {
+                title: "feat(git): add thing".to_string(),
+                body: "Because.".to_string(),
+            }
+        );
+        assert_eq!(parse_commit_message("fix: typo").body, "");
+    }
+}
@@ -0,0 +1,182 @@
+//! File attachments
+//!
+//! Files the user attaches to a message are read here and added to it as
+//! context blocks: text files with line numbers, images as image blocks.
+//! Large files are cut off so one attachment can't take over the context
+//! window.
+
+use std::path::Path;
+
+use crate::providers::{ChatMessage, ContentBlock, ImageSource, MessageContent, Role};
+use crate::tools::{read_file_content, FileContent, ToolResult};
+
+/// Most lines of a text file included in an attachment
+pub const MAX_ATTACHMENT_LINES: usize = 2000;
+
+/// Most characters of a text file included in an attachment
+pub const MAX_ATTACHMENT_CHARS: usize = 100_000;
+
+/// Number the lines of a text file, stopping at the attachment limits
+///
+/// # Returns
+/// The numbered text and whether anything was left out
+fn numbered_lines(content: &str) -> (String, bool) {
+    let total_lines = content.lines().count();
+    let width = total_lines.max(1).to_string().len();
+    let mut out = String::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let numbered = format!("{:>width$}\t{}\n", i + 1, line, width = width);
+        if i == MAX_ATTACHMENT_LINES || out.len() + numbered.len() > MAX_ATTACHMENT_CHARS {
+            return (out, true);
+        }
+        out.push_str(&numbered);
+    }
+    (out, false)
+}
+
+/// Read attached files into content blocks
+///
+/// # Arguments
+/// * `root` - Project root that relative paths are resolved against
+/// * `paths` - The attached files, as given by the frontend
+pub fn read_attachments(root: Option<&Path>, paths: &[String]) -> ToolResult<Vec<ContentBlock>> {
+    let mut blocks = Vec::new();
+
+    for path in paths {
+        let full_path = match root {
+            Some(root) => root.join(path),
+            None => Path::new(path).to_path_buf(),
+        };
+
+        match read_file_content(&full_path.to_string_lossy())? {
+            FileContent::Text { content, .. } => {
+                let (numbered, truncated) = numbered_lines(&content);
+                let note = if truncated {
+                    format!(
+                        "[... truncated; the file has {} lines ...]\n",
+                        content.lines().count()
+                    )
+                } else {
+                    String::new()
+                };
+                blocks.push(ContentBlock::Text {
+                    text: format!(
+                        "<attached_file path=\"{}\">\n{}{}</attached_file>",
+                        path, numbered, note
+                    ),
+                });
+            }
+            FileContent::Image {
+                media_type, data, ..
+            } => {
+                blocks.push(ContentBlock::Text {
+                    text: format!(
+                        "<attached_file path=\"{}\">[image follows]</attached_file>",
+                        path
+                    ),
+                });
+                blocks.push(ContentBlock::Image {
+                    source: ImageSource::Base64 { media_type, data },
+                });
+            }
+            FileContent::Binary { media_type, size } => {
+                blocks.push(ContentBlock::Text {
+                    text: format!(
+                        "<attached_file path=\"{}\">[binary file, {}, {} bytes; contents not included]</attached_file>",
+                        path,
+                        media_type.as_deref().unwrap_or("unknown type"),
+                        size
+                    ),
+                });
+            }
+        }
+    }
+
+    Ok(blocks)
+}
+
+/// Add attachment blocks to the start of the last user message
+///
+/// A new user message is added if the conversation doesn't have one.
+pub fn inject_attachments(messages: &mut Vec<ChatMessage>, attachments: Vec<ContentBlock>) {
+    if attachments.is_empty() {
+        return;
+    }
+
+    let Some(message) = messages.iter_mut().rev().find(|m| m.role == Role::User) else {
+        messages.push(ChatMessage::blocks(Role::User, attachments));
+        return;
+    };
+
+    let existing = match std::mem::replace(
+        &mut message.content,
+        MessageContent::Blocks {
+            content: Vec::new(),
+        },
+    ) {
+        MessageContent::Text { content } => vec![ContentBlock::Text { text: content }],
+        MessageContent::Blocks { content } => content,
+    };
+    let mut blocks = attachments;
+    blocks.extend(existing);
+    message.content = MessageContent::Blocks { content: blocks };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_attachments_are_numbered_and_injected() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {\n    run();\n}\n").unwrap();
+        let long: String = (0..MAX_ATTACHMENT_LINES + 10)
+            .map(|i| format!("line {}\n", i))
+            .collect();
+        fs::write(dir.path().join("long.txt"), long).unwrap();
+
+        let blocks = read_attachments(
+            Some(dir.path()),
+            &["main.rs".to_string(), "long.txt".to_string()],
+        )
+        .unwrap();
+        let texts: Vec<&str> = blocks
+            .iter()
+            .map(|b| match b {
+                ContentBlock::Text { text } => text.as_str(),
+                _ => panic!("expected text"),
+            })
+            .collect();
+        assert_eq!(
+            texts[0],
+            "<attached_file path=\"main.rs\">\n1\tfn main() {\n2\t    run();\n3\t}\n</attached_file>"
+        );
+        assert!(texts[1].contains(&format!(
+            "{}\tline {}\n",
+            MAX_ATTACHMENT_LINES,
+            MAX_ATTACHMENT_LINES - 1
+        )));
+        assert!(texts[1].contains("[... truncated; the file has 2010 lines ...]"));
+        assert!(read_attachments(Some(dir.path()), &["missing.rs".to_string()]).is_err());
+
+        let mut messages = vec![
+            ChatMessage::user("first"),
+            ChatMessage::assistant("reply"),
+            ChatMessage::user("What does main do?"),
+        ];
+        inject_attachments(&mut messages, blocks);
+        match &messages[2].content {
+            MessageContent::Blocks { content } => {
+                assert_eq!(content.len(), 3);
+                assert!(
+                    matches!(&content[2], ContentBlock::Text { text } if text == "What does main do?")
+                );
+            }
+            _ => panic!("expected blocks"),
+        }
+        assert!(matches!(messages[0].content, MessageContent::Text { .. }));
+    }
+}
@@ -0,0 +1,280 @@
+//! Automatic context compaction
+//!
+//! When a conversation approaches the model's context window, older turns are
+//! summarized by the provider and replaced with a compact summary message so the
+//! conversation can continue without hitting the context limit.
+
+use serde::Serialize;
+
+use super::{estimate_conversation_tokens, is_user_turn};
+use crate::providers::{ChatMessage, ContentBlock, MessageContent, Provider, ProviderError, Role};
+
+/// Number of most recent messages kept verbatim by default
+pub const DEFAULT_KEEP_RECENT: usize = 6;
+
+/// Prefix used for the summary message that replaces compacted turns
+pub const SUMMARY_PREFIX: &str = "[Summary of earlier conversation]";
+
+/// Maximum characters of a single tool result included in the transcript
+const MAX_TOOL_RESULT_CHARS: usize = 2000;
+
+const COMPACTION_SYSTEM_PROMPT: &str = "You are summarizing the earlier part of a conversation \
+between a user and an AI coding assistant so it can be continued with less context. \
+Write a concise but complete summary that preserves the user's goals, decisions made, \
+files and code that were read or changed, important tool results, errors encountered, \
+and any open tasks. Do not add commentary or continue the conversation.";
+
+/// Result of compacting a conversation
+#[derive(Debug, Clone)]
+pub struct Compaction {
+    /// The compacted conversation, ready to send to the provider
+    pub messages: Vec<ChatMessage>,
+    /// Summary text that replaced the older turns
+    pub summary: String,
+    /// Number of non-system messages that were replaced by the summary
+    pub replaced_count: usize,
+    /// Estimated token count before compaction
+    pub tokens_before: u32,
+    /// Estimated token count after compaction
+    pub tokens_after: u32,
+}
+
+/// Compaction details reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactionInfo {
+    pub summary: String,
+    pub replaced_count: usize,
+    pub tokens_before: u32,
+    pub tokens_after: u32,
+}
+
+impl From<&Compaction> for CompactionInfo {
+    fn from(compaction: &Compaction) -> Self {
+        Self {
+            summary: compaction.summary.clone(),
+            replaced_count: compaction.replaced_count,
+            tokens_before: compaction.tokens_before,
+            tokens_after: compaction.tokens_after,
+        }
+    }
+}
+
+/// Check whether a conversation should be compacted
+///
+/// # Arguments
+/// * `messages` - The conversation to check
+/// * `context_window` - The model's context window in tokens
+/// * `threshold` - Fraction of the context window at which to compact
+pub fn should_compact(messages: &[ChatMessage], context_window: u32, threshold: f32) -> bool {
+    let limit = (context_window as f64 * threshold as f64) as u32;
+    estimate_conversation_tokens(messages) >= limit
+}
+
+/// Find the index in `messages` where the verbatim recent portion begins
+///
+/// The split never lands on a tool result, so tool calls are never separated
+/// from their results. Returns `None` if there is nothing worth compacting.
+fn find_split_point(messages: &[ChatMessage], keep_recent: usize) -> Option<usize> {
+    let conversation: Vec<usize> = messages
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| m.role != Role::System)
+        .map(|(i, _)| i)
+        .collect();
+
+    if conversation.len() <= keep_recent {
+        return None;
+    }
+
+    let mut split = conversation.len() - keep_recent;
+
+    // Move the split back until the recent portion starts with a plain user turn
+    while split > 0 && !is_user_turn(&messages[conversation[split]]) {
+        split -= 1;
+    }
+
+    if split == 0 {
+        return None;
+    }
+
+    Some(conversation[split])
+}
+
+/// Render messages as a plain-text transcript for summarization
+pub(crate) fn render_transcript(messages: &[ChatMessage]) -> String {
+    let mut transcript = String::new();
+
+    for message in messages {
+        let role = match message.role {
+            Role::System => "System",
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+            Role::Tool => "Tool",
+        };
+
+        match &message.content {
+            MessageContent::Text { content } => {
+                transcript.push_str(&format!("{}: {}\n\n", role, content));
+            }
+            MessageContent::Blocks { content } => {
+                for block in content {
+                    let line = match block {
+                        ContentBlock::Text { text } => format!("{}: {}", role, text),
+                        ContentBlock::Image { .. } => format!("{}: [image]", role),
+                        ContentBlock::ToolUse { name, input, .. } => {
+                            format!("{} called tool {} with {}", role, name, input)
+                        }
+                        ContentBlock::ToolResult {
+                            content, is_error, ..
+                        } => {
+                            let label = if is_error.unwrap_or(false) {
+                                "Tool error"
+                            } else {
+                                "Tool result"
+                            };
+                            format!("{}: {}", label, truncate_chars(content, MAX_TOOL_RESULT_CHARS))
+                        }
+                        // Reasoning is not part of the conversation the summary preserves
+                        ContentBlock::Thinking { .. } | ContentBlock::RedactedThinking { .. } => {
+                            continue;
+                        }
+                        ContentBlock::Citation(citation) => match &citation.url {
+                            Some(url) => format!("{}: [source: {}]", role, url),
+                            None => continue,
+                        },
+                    };
+                    transcript.push_str(&line);
+                    transcript.push_str("\n\n");
+                }
+            }
+        }
+    }
+
+    transcript
+}
+
+/// Truncate a string to at most `max_chars` characters
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => format!("{}... [truncated]", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// Compact a conversation by summarizing its older turns
+///
+/// System messages are kept as-is, the oldest non-system messages are replaced
+/// with a summary, and the `keep_recent` most recent messages are kept verbatim.
+///
+/// # Arguments
+/// * `provider` - The provider used to generate the summary
+/// * `messages` - The conversation to compact
+/// * `keep_recent` - Number of recent messages to keep verbatim
+///
+/// # Returns
+/// The compaction result, or `None` if the conversation is too short to compact
+pub async fn compact_messages(
+    provider: &dyn Provider,
+    messages: Vec<ChatMessage>,
+    keep_recent: usize,
+) -> Result<Option<Compaction>, ProviderError> {
+    let split = match find_split_point(&messages, keep_recent) {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+
+    let tokens_before = estimate_conversation_tokens(&messages);
+
+    let (older, recent) = messages.split_at(split);
+    let system_messages: Vec<ChatMessage> = older
+        .iter()
+        .filter(|m| m.role == Role::System)
+        .cloned()
+        .collect();
+    let to_summarize: Vec<ChatMessage> = older
+        .iter()
+        .filter(|m| m.role != Role::System)
+        .cloned()
+        .collect();
+
+    let request = vec![
+        ChatMessage::system(COMPACTION_SYSTEM_PROMPT),
+        ChatMessage::user(format!(
+            "Summarize this conversation:\n\n{}",
+            render_transcript(&to_summarize)
+        )),
+    ];
+
+    let response = provider.chat(request, None).await?;
+    let summary = response.text().trim().to_string();
+
+    if summary.is_empty() {
+        return Err(ProviderError::InvalidResponse(
+            "Provider returned an empty summary".to_string(),
+        ));
+    }
+
+    let mut compacted = system_messages;
+    compacted.push(ChatMessage::user(format!("{}\n{}", SUMMARY_PREFIX, summary)));
+    compacted.push(ChatMessage::assistant(
+        "Understood. I'll continue from this summary.",
+    ));
+    compacted.extend(recent.iter().cloned());
+
+    let tokens_after = estimate_conversation_tokens(&compacted);
+
+    Ok(Some(Compaction {
+        messages: compacted,
+        summary,
+        replaced_count: to_summarize.len(),
+        tokens_before,
+        tokens_after,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_point_keeps_recent_messages() {
+        let messages = vec![
+            ChatMessage::system("be helpful"),
+            ChatMessage::user("one"),
+            ChatMessage::assistant("two"),
+            ChatMessage::user("three"),
+            ChatMessage::assistant("four"),
+        ];
+
+        assert_eq!(find_split_point(&messages, 2), Some(3));
+        assert_eq!(find_split_point(&messages, 4), None);
+    }
+
+    #[test]
+    fn test_split_point_does_not_orphan_tool_results() {
+        let messages = vec![
+            ChatMessage::user("read the file"),
+            ChatMessage::blocks(
+                Role::Assistant,
+                vec![ContentBlock::ToolUse {
+                    id: "t1".to_string(),
+                    name: "read_file".to_string(),
+                    input: serde_json::json!({ "path": "a.rs" }),
+                }],
+            ),
+            ChatMessage::tool_result("t1", "fn main() {}", false),
+            ChatMessage::assistant("done"),
+        ];
+
+        // Keeping two messages would start on the tool result, so the split
+        // moves back to the user turn that started the exchange
+        assert_eq!(find_split_point(&messages, 2), None);
+    }
+
+    #[test]
+    fn test_should_compact() {
+        let messages = vec![ChatMessage::user("x".repeat(4000))];
+        assert!(should_compact(&messages, 1000, 0.8));
+        assert!(!should_compact(&messages, 10_000, 0.8));
+    }
+}
@@ -0,0 +1,145 @@
+//! Project instruction files
+//!
+//! Projects can describe their conventions for AI assistants in an
+//! `OPENSESH.md`, `AGENTS.md` or `CLAUDE.md` file, at the root or in any
+//! subdirectory. The files found are added to the start of the system
+//! prompt of every chat in the project.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::tools::project_walker;
+
+/// Instruction file names, in the order they are loaded within a directory
+pub const INSTRUCTION_FILES: &[&str] = &["OPENSESH.md", "AGENTS.md", "CLAUDE.md"];
+
+/// Deepest subdirectory searched for instruction files
+const MAX_INSTRUCTION_DEPTH: usize = 4;
+
+/// Most characters loaded from one instruction file
+const MAX_INSTRUCTION_CHARS: usize = 20_000;
+
+/// An instruction file loaded into the system prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstructionFile {
+    /// Path relative to the project root
+    pub path: String,
+    pub content: String,
+    /// Whether the file was cut off at the size limit
+    pub truncated: bool,
+}
+
+/// Find the instruction files in a project
+///
+/// Ignored directories are skipped. The root's files come first, then
+/// those of subdirectories in path order.
+pub fn find_instruction_files(root: &Path) -> Vec<PathBuf> {
+    let mut found: Vec<PathBuf> = project_walker(root, false)
+        .max_depth(Some(MAX_INSTRUCTION_DEPTH + 1))
+        .build()
+        .flatten()
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| INSTRUCTION_FILES.contains(&name))
+        })
+        .map(|entry| entry.into_path())
+        .collect();
+
+    let rank = |path: &Path| {
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let order = INSTRUCTION_FILES.iter().position(|f| *f == name);
+        (dir.components().count(), dir, order)
+    };
+    found.sort_by_key(|path| rank(path));
+    found
+}
+
+/// Read instruction files, skipping any that can't be read
+pub fn load_instruction_files(root: &Path, paths: &[PathBuf]) -> Vec<InstructionFile> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let content = fs::read_to_string(path).ok()?;
+            let truncated = content.chars().count() > MAX_INSTRUCTION_CHARS;
+            let content = if truncated {
+                content.chars().take(MAX_INSTRUCTION_CHARS).collect()
+            } else {
+                content
+            };
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            Some(InstructionFile {
+                path: relative.to_string_lossy().replace('\\', "/"),
+                content,
+                truncated,
+            })
+        })
+        .filter(|file| !file.content.trim().is_empty())
+        .collect()
+}
+
+/// Combine instruction files and the chat's own system prompt
+pub fn system_prompt_with_instructions(
+    instructions: &[InstructionFile],
+    system_prompt: Option<String>,
+) -> Option<String> {
+    if instructions.is_empty() {
+        return system_prompt;
+    }
+
+    let mut prompt = String::from(
+        "The project has instruction files for AI assistants. Follow them; \
+         instructions in a subdirectory apply to files under it.\n\n",
+    );
+    for file in instructions {
+        prompt.push_str(&format!(
+            "<project_instructions path=\"{}\">\n{}\n</project_instructions>\n\n",
+            file.path,
+            file.content.trim_end()
+        ));
+    }
+    match system_prompt {
+        Some(system) => prompt.push_str(&system),
+        None => prompt.truncate(prompt.trim_end().len()),
+    }
+    Some(prompt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_instruction_files_are_found_and_prepended() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("crates/core")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::write(root.join("CLAUDE.md"), "Use tabs.").unwrap();
+        fs::write(root.join("OPENSESH.md"), "Run cargo test.").unwrap();
+        fs::write(root.join("crates/core/AGENTS.md"), "No unsafe.").unwrap();
+        fs::write(root.join("target/AGENTS.md"), "Ignored.").unwrap();
+        fs::write(root.join("README.md"), "Not instructions.").unwrap();
+
+        let paths = find_instruction_files(root);
+        let files = load_instruction_files(root, &paths);
+        let names: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["OPENSESH.md", "CLAUDE.md", "crates/core/AGENTS.md"]
+        );
+
+        let prompt =
+            system_prompt_with_instructions(&files, Some("Be brief.".to_string())).unwrap();
+        assert!(prompt.contains("<project_instructions path=\"OPENSESH.md\">\nRun cargo test.\n"));
+        assert!(prompt.ends_with("</project_instructions>\n\nBe brief."));
+        assert_eq!(system_prompt_with_instructions(&[], None), None);
+    }
+}
@@ -0,0 +1,240 @@
+//! Token-budget aware message truncation
+//!
+//! The context manager measures a conversation against the model's context
+//! limit and, when it doesn't fit, truncates the oldest tool results first
+//! (they're usually the bulkiest) before dropping whole older exchanges.
+
+use super::{estimate_block_tokens, estimate_conversation_tokens, estimate_tokens, is_user_turn};
+use crate::providers::{ChatMessage, ContentBlock, MessageContent, Provider, Role, Tool};
+
+/// Tool results at or below this many tokens are never truncated
+const MIN_TRUNCATABLE_TOKENS: u32 = 200;
+
+/// Characters kept from the start of a truncated tool result
+const TRUNCATED_HEAD_CHARS: usize = 500;
+
+/// Safety margin to absorb token estimation error
+const SAFETY_MARGIN_TOKENS: u32 = 1024;
+
+/// Summary of what the context manager changed to fit the budget
+#[derive(Debug, Clone, Default)]
+pub struct FitReport {
+    /// Number of tool results that were truncated
+    pub truncated_tool_results: usize,
+    /// Number of messages that were dropped entirely
+    pub dropped_messages: usize,
+    /// Estimated token count after fitting
+    pub tokens: u32,
+    /// Whether the conversation fits within the budget
+    pub fits: bool,
+}
+
+impl FitReport {
+    /// Check whether any messages were modified
+    pub fn modified(&self) -> bool {
+        self.truncated_tool_results > 0 || self.dropped_messages > 0
+    }
+}
+
+/// Fits conversations into a provider's context window
+#[derive(Debug, Clone)]
+pub struct ContextManager {
+    budget: u32,
+}
+
+impl ContextManager {
+    /// Create a context manager with an explicit token budget
+    pub fn new(budget: u32) -> Self {
+        Self { budget }
+    }
+
+    /// Create a context manager for a provider
+    ///
+    /// The budget is the context window minus the space reserved for the
+    /// response and the tool definitions sent alongside the messages.
+    pub fn for_provider(provider: &dyn Provider, tools: Option<&[Tool]>) -> Self {
+        let tool_tokens: u32 = tools
+            .unwrap_or_default()
+            .iter()
+            .map(|t| {
+                estimate_tokens(&t.name)
+                    + estimate_tokens(&t.description)
+                    + estimate_tokens(&t.input_schema.to_string())
+            })
+            .sum();
+
+        let budget = provider
+            .context_window()
+            .saturating_sub(provider.max_tokens())
+            .saturating_sub(tool_tokens)
+            .saturating_sub(SAFETY_MARGIN_TOKENS);
+
+        Self::new(budget)
+    }
+
+    /// Get the token budget for messages
+    pub fn budget(&self) -> u32 {
+        self.budget
+    }
+
+    /// Fit messages within the token budget
+    ///
+    /// Oldest tool results are truncated first; if that isn't enough, the
+    /// oldest exchanges are dropped. System messages and the latest user turn
+    /// are always kept.
+    pub fn fit(&self, mut messages: Vec<ChatMessage>) -> (Vec<ChatMessage>, FitReport) {
+        let mut report = FitReport::default();
+        let mut tokens = estimate_conversation_tokens(&messages);
+
+        if tokens <= self.budget {
+            report.tokens = tokens;
+            report.fits = true;
+            return (messages, report);
+        }
+
+        // Phase 1: truncate tool results, oldest first
+        'truncate: for message in messages.iter_mut() {
+            if let MessageContent::Blocks { content } = &mut message.content {
+                for block in content.iter_mut() {
+                    if tokens <= self.budget {
+                        break 'truncate;
+                    }
+                    let before = estimate_block_tokens(block);
+                    if before > MIN_TRUNCATABLE_TOKENS && truncate_tool_result(block) {
+                        tokens = tokens - before + estimate_block_tokens(block);
+                        report.truncated_tool_results += 1;
+                    }
+                }
+            }
+        }
+
+        // Phase 2: drop the oldest exchanges
+        while tokens > self.budget {
+            let Some(range) = oldest_exchange(&messages) else {
+                break;
+            };
+            let dropped: Vec<ChatMessage> = messages.drain(range).collect();
+            tokens -= estimate_conversation_tokens(&dropped);
+            report.dropped_messages += dropped.len();
+        }
+
+        report.tokens = tokens;
+        report.fits = tokens <= self.budget;
+
+        if !report.fits {
+            log::warn!(
+                "Conversation still exceeds context budget after truncation (~{} > {} tokens)",
+                tokens,
+                self.budget
+            );
+        }
+
+        (messages, report)
+    }
+}
+
+/// Truncate a tool result block in place, returning true if it was changed
+fn truncate_tool_result(block: &mut ContentBlock) -> bool {
+    let ContentBlock::ToolResult { content, .. } = block else {
+        return false;
+    };
+
+    let cut = match content.char_indices().nth(TRUNCATED_HEAD_CHARS) {
+        Some((idx, _)) => idx,
+        None => return false,
+    };
+
+    let omitted = content.len() - cut;
+    *content = format!(
+        "{}\n[... {} bytes omitted to fit the context window ...]",
+        &content[..cut],
+        omitted
+    );
+    true
+}
+
+/// Find the range of the oldest droppable exchange
+///
+/// An exchange runs from the first non-system message up to (but not
+/// including) the next plain user turn, so tool calls stay paired with their
+/// results. Returns `None` if only the final exchange remains.
+fn oldest_exchange(messages: &[ChatMessage]) -> Option<std::ops::Range<usize>> {
+    let start = messages.iter().position(|m| m.role != Role::System)?;
+
+    let end = messages[start + 1..]
+        .iter()
+        .position(|m| m.role != Role::System && is_user_turn(m))
+        .map(|offset| start + 1 + offset)?;
+
+    Some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_exchange(id: &str, result: &str) -> Vec<ChatMessage> {
+        vec![
+            ChatMessage::blocks(
+                Role::Assistant,
+                vec![ContentBlock::ToolUse {
+                    id: id.to_string(),
+                    name: "read_file".to_string(),
+                    input: serde_json::json!({ "path": "big.txt" }),
+                }],
+            ),
+            ChatMessage::tool_result(id, result, false),
+        ]
+    }
+
+    #[test]
+    fn test_fit_leaves_small_conversations_alone() {
+        let messages = vec![ChatMessage::user("hello"), ChatMessage::assistant("hi")];
+        let (fitted, report) = ContextManager::new(1000).fit(messages);
+
+        assert_eq!(fitted.len(), 2);
+        assert!(report.fits);
+        assert!(!report.modified());
+    }
+
+    #[test]
+    fn test_fit_truncates_oldest_tool_results_first() {
+        let mut messages = vec![ChatMessage::user("read both files")];
+        messages.extend(tool_exchange("t1", &"a".repeat(8000)));
+        messages.extend(tool_exchange("t2", &"b".repeat(8000)));
+
+        // Enough room for one full result but not two
+        let (fitted, report) = ContextManager::new(2600).fit(messages);
+
+        assert!(report.fits);
+        assert_eq!(report.truncated_tool_results, 1);
+        assert_eq!(report.dropped_messages, 0);
+
+        match &fitted[2].content {
+            MessageContent::Blocks { content } => match &content[0] {
+                ContentBlock::ToolResult { content, .. } => {
+                    assert!(content.contains("bytes omitted"))
+                }
+                _ => panic!("expected tool result"),
+            },
+            _ => panic!("expected blocks"),
+        }
+    }
+
+    #[test]
+    fn test_fit_drops_oldest_exchanges() {
+        let messages = vec![
+            ChatMessage::system("be helpful"),
+            ChatMessage::user("x".repeat(4000)),
+            ChatMessage::assistant("y".repeat(4000)),
+            ChatMessage::user("latest question"),
+        ];
+
+        let (fitted, report) = ContextManager::new(100).fit(messages);
+
+        assert!(report.fits);
+        assert_eq!(report.dropped_messages, 2);
+        assert_eq!(fitted.len(), 2);
+        assert_eq!(fitted[0].role, Role::System);
+    }
+}
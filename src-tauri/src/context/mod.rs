@@ -0,0 +1,77 @@
+//! Conversation context management
+//!
+//! This module keeps conversations within the limits of a provider's context
+//! window, including token estimation, automatic compaction of older turns,
+//! and budget-aware truncation. It also builds the project context added to
+//! requests: attachments, instruction files and the workspace preamble.
+
+pub mod attachments;
+pub mod compaction;
+pub mod instructions;
+pub mod manager;
+pub mod workspace;
+
+pub use attachments::*;
+pub use compaction::*;
+pub use instructions::*;
+pub use manager::*;
+pub use workspace::*;
+
+use crate::providers::{ChatMessage, ContentBlock, MessageContent, Role};
+
+/// Rough number of characters per token for English text and code
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Fixed overhead per message for role markers and formatting
+const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+
+/// Approximate token cost of an image block
+const IMAGE_TOKENS: u32 = 1600;
+
+/// Estimate the number of tokens in a piece of text
+pub fn estimate_tokens(text: &str) -> u32 {
+    text.len().div_ceil(CHARS_PER_TOKEN) as u32
+}
+
+/// Estimate the number of tokens in a single content block
+pub fn estimate_block_tokens(block: &ContentBlock) -> u32 {
+    match block {
+        ContentBlock::Text { text } => estimate_tokens(text),
+        ContentBlock::Image { .. } => IMAGE_TOKENS,
+        ContentBlock::ToolUse { name, input, .. } => {
+            estimate_tokens(name) + estimate_tokens(&input.to_string())
+        }
+        ContentBlock::ToolResult { content, .. } => estimate_tokens(content),
+        ContentBlock::Thinking { thinking, .. } => estimate_tokens(thinking),
+        ContentBlock::RedactedThinking { data } => estimate_tokens(data),
+        // Citations are never sent back to providers
+        ContentBlock::Citation(_) => 0,
+    }
+}
+
+/// Estimate the number of tokens in a message
+pub fn estimate_message_tokens(message: &ChatMessage) -> u32 {
+    let content_tokens = match &message.content {
+        MessageContent::Text { content } => estimate_tokens(content),
+        MessageContent::Blocks { content } => content.iter().map(estimate_block_tokens).sum(),
+    };
+    content_tokens + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// Estimate the number of tokens in a conversation
+pub fn estimate_conversation_tokens(messages: &[ChatMessage]) -> u32 {
+    messages.iter().map(estimate_message_tokens).sum()
+}
+
+/// Check whether a message is a user turn that isn't carrying tool results
+pub(crate) fn is_user_turn(message: &ChatMessage) -> bool {
+    if message.role != Role::User {
+        return false;
+    }
+    match &message.content {
+        MessageContent::Text { .. } => true,
+        MessageContent::Blocks { content } => !content
+            .iter()
+            .any(|b| matches!(b, ContentBlock::ToolResult { .. })),
+    }
+}
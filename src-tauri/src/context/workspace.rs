@@ -0,0 +1,138 @@
+//! Workspace context
+//!
+//! Each request can start with a short preamble naming the files open in the
+//! editor and the files the assistant edited recently, optionally with their
+//! uncommitted diffs, so the model knows what the user is looking at without
+//! being told.
+
+use std::path::Path;
+
+use crate::tools::{git, EditRecord};
+
+/// Most recently edited files named in the preamble
+pub const MAX_RECENT_EDITED_FILES: usize = 10;
+
+/// Most characters of diffs included in the preamble
+pub const MAX_WORKSPACE_DIFF_CHARS: usize = 20_000;
+
+/// The files the assistant edited most recently, newest first
+///
+/// Paths inside `root` are made relative to it.
+pub fn recently_edited_files(edits: &[EditRecord], root: Option<&Path>) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+
+    for file in edits.iter().rev().flat_map(|edit| edit.files.iter().rev()) {
+        let path = root
+            .and_then(|root| file.path.strip_prefix(root).ok())
+            .unwrap_or(&file.path)
+            .to_string_lossy()
+            .to_string();
+        if !files.contains(&path) {
+            files.push(path);
+        }
+        if files.len() == MAX_RECENT_EDITED_FILES {
+            break;
+        }
+    }
+    files
+}
+
+/// Uncommitted changes to files, as `(path, diff)` pairs
+///
+/// Files without changes, or outside a git repository, are skipped. Diffs
+/// stop being added once `MAX_WORKSPACE_DIFF_CHARS` is reached.
+pub fn workspace_diffs(root: &Path, files: &[String]) -> Vec<(String, String)> {
+    let root = root.to_string_lossy();
+    let mut diffs = Vec::new();
+    let mut total = 0;
+
+    for file in files {
+        let Ok(diff) = git::diff(&root, false, Some(file)) else {
+            continue;
+        };
+        if diff.trim().is_empty() {
+            continue;
+        }
+        total += diff.len();
+        if total > MAX_WORKSPACE_DIFF_CHARS {
+            break;
+        }
+        diffs.push((file.clone(), diff));
+    }
+    diffs
+}
+
+/// Build the workspace preamble for a request
+///
+/// # Returns
+/// The preamble, or `None` if there is nothing to report
+pub fn workspace_preamble(
+    open_files: &[String],
+    edited_files: &[String],
+    diffs: &[(String, String)],
+) -> Option<String> {
+    if open_files.is_empty() && edited_files.is_empty() {
+        return None;
+    }
+
+    let mut preamble = String::from("<workspace_context>\n");
+    if !open_files.is_empty() {
+        preamble.push_str("Files open in the editor:\n");
+        for file in open_files {
+            preamble.push_str(&format!("- {}\n", file));
+        }
+    }
+    if !edited_files.is_empty() {
+        preamble.push_str("Files you edited recently, newest first:\n");
+        for file in edited_files {
+            preamble.push_str(&format!("- {}\n", file));
+        }
+    }
+    for (path, diff) in diffs {
+        preamble.push_str(&format!(
+            "<diff path=\"{}\">\n{}\n</diff>\n",
+            path,
+            diff.trim_end()
+        ));
+    }
+    preamble.push_str("</workspace_context>");
+    Some(preamble)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::SnapshotStore;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_workspace_preamble_lists_recent_edits() {
+        let dir = tempdir().unwrap();
+        let store = SnapshotStore::new();
+        for (id, file) in [("call-1", "a.rs"), ("call-2", "b.rs"), ("call-3", "a.rs")] {
+            let path = dir.path().join(file);
+            store.begin(id, "write_file");
+            store.snapshot(&path).unwrap();
+            fs::write(&path, id).unwrap();
+            store.finish();
+        }
+
+        let edited = recently_edited_files(&store.edits(), Some(dir.path()));
+        assert_eq!(edited, vec!["a.rs", "b.rs"]);
+
+        let preamble = workspace_preamble(
+            &["src/main.rs".to_string()],
+            &edited,
+            &[("a.rs".to_string(), "-old\n+new\n".to_string())],
+        )
+        .unwrap();
+        assert_eq!(
+            preamble,
+            "<workspace_context>\nFiles open in the editor:\n- src/main.rs\n\
+             Files you edited recently, newest first:\n- a.rs\n- b.rs\n\
+             <diff path=\"a.rs\">\n-old\n+new\n</diff>\n</workspace_context>"
+        );
+        assert_eq!(workspace_preamble(&[], &[], &[]), None);
+    }
+}
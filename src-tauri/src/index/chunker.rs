@@ -0,0 +1,132 @@
+//! File scanning and chunking for the semantic index
+
+use std::fs;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use crate::tools::project_walker;
+
+/// Lines per chunk
+pub const CHUNK_LINES: usize = 60;
+
+/// Lines shared between consecutive chunks, so definitions that straddle a
+/// boundary appear whole in at least one chunk
+pub const CHUNK_OVERLAP: usize = 10;
+
+/// Characters kept from a chunk, to stay within embedding input limits
+const MAX_CHUNK_CHARS: usize = 6_000;
+
+/// Files larger than this are skipped (generated code, data, bundles)
+pub const MAX_INDEXED_FILE_BYTES: u64 = 512 * 1024;
+
+/// A chunk of a source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// 1-based line range
+    pub start_line: usize,
+    pub end_line: usize,
+    pub content: String,
+}
+
+/// A text file found while scanning a project
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    /// Path relative to the project root, with `/` separators
+    pub path: String,
+    pub hash: String,
+    pub content: String,
+}
+
+/// Split text into overlapping line chunks, skipping blank chunks
+pub fn chunk_text(content: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let step = CHUNK_LINES - CHUNK_OVERLAP;
+    let mut chunks = Vec::new();
+
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + CHUNK_LINES).min(lines.len());
+        let text = lines[start..end].join("\n");
+        if !text.trim().is_empty() {
+            chunks.push(Chunk {
+                start_line: start + 1,
+                end_line: end,
+                content: text.chars().take(MAX_CHUNK_CHARS).collect(),
+            });
+        }
+        if end == lines.len() {
+            break;
+        }
+        start += step;
+    }
+
+    chunks
+}
+
+/// Hash file content to detect changes between index runs
+pub fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Read a file if it's a reasonably sized text file
+fn read_text_file(path: &Path) -> Option<String> {
+    let metadata = fs::metadata(path).ok()?;
+    if metadata.len() > MAX_INDEXED_FILE_BYTES {
+        return None;
+    }
+    let content = fs::read_to_string(path).ok()?;
+    if content.contains('\0') {
+        return None;
+    }
+    Some(content)
+}
+
+/// Find the text files of a project, honoring ignore files and skipping hidden files
+pub fn scan_project(root: &Path) -> Vec<SourceFile> {
+    let mut files = Vec::new();
+
+    for entry in project_walker(root, false).build().flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Some(content) = read_text_file(entry.path()) else {
+            continue;
+        };
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+
+        files.push(SourceFile {
+            path: relative.to_string_lossy().replace('\\', "/"),
+            hash: content_hash(&content),
+            content,
+        });
+    }
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_text_overlaps() {
+        let content = (1..=120)
+            .map(|n| format!("line {}", n))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let chunks = chunk_text(&content);
+
+        let ranges: Vec<(usize, usize)> =
+            chunks.iter().map(|c| (c.start_line, c.end_line)).collect();
+        assert_eq!(ranges, vec![(1, 60), (51, 110), (101, 120)]);
+        assert!(chunks[1].content.starts_with("line 51\n"));
+        assert!(chunk_text("\n\n  \n").is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+//! Semantic code search
+//!
+//! This module indexes a project for semantic search: files are split into
+//! overlapping line chunks, embedded with an embedding provider, and stored
+//! in a per-project SQLite database. Queries are embedded the same way and
+//! ranked by cosine similarity.
+
+pub mod chunker;
+pub mod store;
+
+pub use chunker::*;
+pub use store::*;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::providers::ProviderError;
+
+/// Errors that can occur while indexing or searching
+#[derive(Debug, Error)]
+pub enum IndexError {
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Embedding failed: {0}")]
+    Embedding(#[from] ProviderError),
+
+    #[error("Indexing task failed: {0}")]
+    Task(String),
+}
+
+/// Summary of a reindex run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexStats {
+    /// Files whose chunks were (re)embedded
+    pub files_indexed: usize,
+    /// Files skipped because they hadn't changed
+    pub files_unchanged: usize,
+    /// Files removed from the index because they no longer exist
+    pub files_removed: usize,
+    pub chunks_embedded: usize,
+    pub total_chunks: usize,
+}
+
+/// A chunk returned by a semantic search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticMatch {
+    /// Path relative to the project root
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub content: String,
+    /// Cosine similarity to the query, from -1 to 1
+    pub score: f32,
+}
+
+/// Cosine similarity of two vectors; 0 if either is empty or zero
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a.sqrt() * norm_b.sqrt())
+    }
+}
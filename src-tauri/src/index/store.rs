@@ -0,0 +1,353 @@
+//! SQLite storage and search for the semantic index
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::{chunk_text, cosine_similarity, scan_project, IndexError, IndexStats, SemanticMatch};
+use crate::providers::embeddings::EmbeddingProvider;
+
+/// Text prepended to each chunk before embedding, so the file path
+/// contributes to the match
+fn embedding_input(path: &str, content: &str) -> String {
+    format!("{}\n{}", path, content)
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// A chunk that has been split out of a file but not yet stored
+struct PendingChunk {
+    start_line: usize,
+    end_line: usize,
+    content: String,
+    embedding: Vec<f32>,
+}
+
+/// A semantic index of one project, stored in a SQLite database
+///
+/// Vectors are compared by brute force, which is fast enough for the tens
+/// of thousands of chunks a typical project produces.
+pub struct SemanticIndex {
+    root: PathBuf,
+    conn: Mutex<Connection>,
+}
+
+impl SemanticIndex {
+    /// Open (or create) the index database for a project
+    pub fn open(root: &Path, db_path: &Path) -> Result<Self, IndexError> {
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(db_path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS meta (
+                 key TEXT PRIMARY KEY,
+                 value TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS files (
+                 path TEXT PRIMARY KEY,
+                 hash TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS chunks (
+                 id INTEGER PRIMARY KEY,
+                 path TEXT NOT NULL,
+                 start_line INTEGER NOT NULL,
+                 end_line INTEGER NOT NULL,
+                 content TEXT NOT NULL,
+                 embedding BLOB NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS chunks_path ON chunks (path);",
+        )?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// The project root this index covers
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        // A panic mid-query leaves nothing half-written outside a transaction
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Drop all stored vectors if they came from a different embedding model
+    fn reset_if_model_changed(&self, model: &str) -> Result<(), IndexError> {
+        let conn = self.conn();
+        let stored: Option<String> = conn
+            .query_row("SELECT value FROM meta WHERE key = 'model'", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+
+        if stored.as_deref() != Some(model) {
+            conn.execute_batch("DELETE FROM chunks; DELETE FROM files;")?;
+            conn.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES ('model', ?1)",
+                params![model],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Drop what is stored for files that changed since they were indexed
+    ///
+    /// `paths` are relative to the project root; a directory drops every file
+    /// under it. The files are embedded again by the next `reindex`.
+    ///
+    /// # Returns
+    /// How many files were dropped
+    pub fn invalidate(&self, paths: &[String]) -> Result<usize, IndexError> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        let mut dropped = 0;
+        for path in paths {
+            let under = format!("{}/%", path.trim_end_matches('/'));
+            tx.execute(
+                "DELETE FROM chunks WHERE path = ?1 OR path LIKE ?2",
+                params![path, under],
+            )?;
+            dropped += tx.execute(
+                "DELETE FROM files WHERE path = ?1 OR path LIKE ?2",
+                params![path, under],
+            )?;
+        }
+        tx.commit()?;
+        Ok(dropped)
+    }
+
+    /// Bring the index up to date with the project files
+    ///
+    /// Only files whose content changed since the last run are re-embedded;
+    /// files that no longer exist are removed.
+    pub async fn reindex(
+        &self,
+        provider: &dyn EmbeddingProvider,
+    ) -> Result<IndexStats, IndexError> {
+        self.reset_if_model_changed(provider.model())?;
+
+        let root = self.root.clone();
+        let files = tokio::task::spawn_blocking(move || scan_project(&root))
+            .await
+            .map_err(|e| IndexError::Task(e.to_string()))?;
+
+        let known: HashMap<String, String> = {
+            let conn = self.conn();
+            let mut stmt = conn.prepare("SELECT path, hash FROM files")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_, _>>()?
+        };
+
+        let mut stats = IndexStats::default();
+        let seen: HashSet<&str> = files.iter().map(|f| f.path.as_str()).collect();
+
+        // Remove files that were deleted or are now ignored
+        {
+            let conn = self.conn();
+            for path in known.keys().filter(|p| !seen.contains(p.as_str())) {
+                conn.execute("DELETE FROM chunks WHERE path = ?1", params![path])?;
+                conn.execute("DELETE FROM files WHERE path = ?1", params![path])?;
+                stats.files_removed += 1;
+            }
+        }
+
+        let batch_size = provider.max_batch_size().max(1);
+        for file in &files {
+            if known.get(&file.path) == Some(&file.hash) {
+                stats.files_unchanged += 1;
+                continue;
+            }
+
+            let chunks = chunk_text(&file.content);
+            let mut pending = Vec::with_capacity(chunks.len());
+            for batch in chunks.chunks(batch_size) {
+                let inputs: Vec<String> = batch
+                    .iter()
+                    .map(|c| embedding_input(&file.path, &c.content))
+                    .collect();
+                let embeddings = provider.embed(&inputs).await?;
+                for (chunk, embedding) in batch.iter().zip(embeddings) {
+                    pending.push(PendingChunk {
+                        start_line: chunk.start_line,
+                        end_line: chunk.end_line,
+                        content: chunk.content.clone(),
+                        embedding,
+                    });
+                }
+            }
+
+            // Replace the file's chunks in one transaction so an interrupted
+            // run never leaves a file half-indexed
+            let mut conn = self.conn();
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM chunks WHERE path = ?1", params![file.path])?;
+            for chunk in &pending {
+                tx.execute(
+                    "INSERT INTO chunks (path, start_line, end_line, content, embedding)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        file.path,
+                        chunk.start_line as i64,
+                        chunk.end_line as i64,
+                        chunk.content,
+                        encode_vector(&chunk.embedding)
+                    ],
+                )?;
+            }
+            tx.execute(
+                "INSERT OR REPLACE INTO files (path, hash) VALUES (?1, ?2)",
+                params![file.path, file.hash],
+            )?;
+            tx.commit()?;
+
+            stats.files_indexed += 1;
+            stats.chunks_embedded += pending.len();
+        }
+
+        stats.total_chunks = self.chunk_count()?;
+        Ok(stats)
+    }
+
+    /// Number of chunks in the index
+    pub fn chunk_count(&self) -> Result<usize, IndexError> {
+        let count: i64 = self
+            .conn()
+            .query_row("SELECT COUNT(*) FROM chunks", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    /// Find the chunks most similar to a query
+    pub async fn search(
+        &self,
+        provider: &dyn EmbeddingProvider,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SemanticMatch>, IndexError> {
+        let query_vector = provider
+            .embed(&[query.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        let conn = self.conn();
+        let mut stmt =
+            conn.prepare("SELECT path, start_line, end_line, content, embedding FROM chunks")?;
+        let mut rows = stmt.query([])?;
+
+        let mut matches: Vec<SemanticMatch> = Vec::new();
+        while let Some(row) = rows.next()? {
+            let embedding: Vec<u8> = row.get(4)?;
+            let score = cosine_similarity(&query_vector, &decode_vector(&embedding));
+
+            // Keep only the best `limit` matches seen so far
+            if matches.len() >= limit && matches.last().is_some_and(|m| m.score >= score) {
+                continue;
+            }
+            matches.push(SemanticMatch {
+                path: row.get(0)?,
+                start_line: row.get::<_, i64>(1)? as usize,
+                end_line: row.get::<_, i64>(2)? as usize,
+                content: row.get(3)?,
+                score,
+            });
+            matches.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+            matches.truncate(limit);
+        }
+
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::ProviderError;
+    use async_trait::async_trait;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    use tempfile::tempdir;
+
+    /// Embeds text as counts of a few keywords
+    struct KeywordEmbedder {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for KeywordEmbedder {
+        async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, ProviderError> {
+            self.calls.fetch_add(inputs.len(), AtomicOrdering::SeqCst);
+            Ok(inputs
+                .iter()
+                .map(|text| {
+                    ["database", "network", "parser"]
+                        .iter()
+                        .map(|word| text.matches(word).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+
+        fn name(&self) -> &str {
+            "keyword"
+        }
+
+        fn model(&self) -> &str {
+            "keyword-v1"
+        }
+
+        fn max_batch_size(&self) -> usize {
+            8
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reindex_and_search() {
+        let project = tempdir().unwrap();
+        let data = tempdir().unwrap();
+        fs::write(
+            project.path().join("db.rs"),
+            "// database connection pool\n",
+        )
+        .unwrap();
+        fs::write(project.path().join("net.rs"), "// network client\n").unwrap();
+
+        let embedder = KeywordEmbedder {
+            calls: AtomicUsize::new(0),
+        };
+        let index = SemanticIndex::open(project.path(), &data.path().join("index.sqlite")).unwrap();
+
+        let stats = index.reindex(&embedder).await.unwrap();
+        assert_eq!(stats.files_indexed, 2);
+        assert_eq!(stats.total_chunks, 2);
+
+        let results = index.search(&embedder, "network", 1).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "net.rs");
+
+        // Unchanged files are not re-embedded; deleted files are dropped
+        fs::remove_file(project.path().join("net.rs")).unwrap();
+        let calls_before = embedder.calls.load(AtomicOrdering::SeqCst);
+        let stats = index.reindex(&embedder).await.unwrap();
+        assert_eq!(stats.files_unchanged, 1);
+        assert_eq!(stats.files_removed, 1);
+        assert_eq!(stats.total_chunks, 1);
+        assert_eq!(embedder.calls.load(AtomicOrdering::SeqCst), calls_before);
+    }
+}
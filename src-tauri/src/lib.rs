@@ -3,12 +3,29 @@
 //! This is the main library for the Tauri backend, providing AI provider integrations,
 //! file operations, git integration, and terminal support.
 
+pub mod agents;
+pub mod artifacts;
+pub mod budget;
 pub mod commands;
+pub mod commit_message;
+pub mod context;
+pub mod index;
+pub mod prompts;
 pub mod providers;
+pub mod recent;
+pub mod saved_terminals;
+pub mod review;
+pub mod scaffold;
+pub mod sessions;
+pub mod settings;
+pub mod slash;
 pub mod state;
 pub mod tools;
+pub mod watcher;
+pub mod workspace;
 
 use std::sync::Arc;
+use tauri::Manager;
 use state::AppState;
 use commands::terminal::TerminalState;
 
@@ -37,10 +54,16 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .manage(app_state.clone())
         .manage(terminal_state)
-        .setup(move |_app| {
-            // Initialize providers asynchronously
+        .setup(move |app| {
+            let data_dir = app.path().app_data_dir().ok();
+
+            // Load persisted state and initialize providers asynchronously
             let state = app_state.clone();
             tauri::async_runtime::spawn(async move {
+                match data_dir {
+                    Some(dir) => state.init_data_dir(dir).await,
+                    None => log::warn!("Could not resolve app data directory; settings won't persist"),
+                }
                 state.init_providers().await;
             });
 
@@ -52,16 +75,44 @@ pub fn run() {
             commands::chat::send_message,
             commands::chat::send_message_stream,
             commands::chat::execute_tool_calls,
+            commands::chat::respond_tool_permission,
+            commands::chat::start_agent_run,
+            commands::chat::end_agent_run,
+            commands::chat::respond_budget_exceeded,
+            commands::chat::clear_tool_permissions,
+            commands::chat::list_session_edits,
+            commands::chat::undo_last_edit,
+            commands::chat::undo_all_session_edits,
+            commands::chat::list_checkpoints,
+            commands::chat::restore_checkpoint,
+            commands::chat::get_tool_audit_log,
+            commands::chat::get_todos,
             commands::chat::get_providers,
             commands::chat::set_active_provider,
             commands::chat::set_provider_model,
+            commands::chat::compact_conversation,
+            commands::chat::get_loaded_instructions,
+            commands::chat::cancel_stream,
+            commands::chat::set_open_files,
+            commands::chat::list_memories,
+            commands::chat::delete_memory,
+            commands::chat::list_slash_commands,
+            // Clipboard commands
+            commands::clipboard::read_clipboard,
+            commands::clipboard::write_clipboard,
             // File commands
             commands::files::read_file,
             commands::files::read_file_lines,
+            commands::files::read_file_range,
+            commands::files::stream_file,
             commands::files::write_file,
+            commands::files::write_file_lines,
             commands::files::list_directory,
             commands::files::list_directory_recursive,
             commands::files::search_files,
+            commands::files::fuzzy_find_files,
+            commands::files::record_file_open,
+            commands::files::get_recent_files,
             commands::files::grep_files,
             commands::files::grep_files_with_context,
             commands::files::path_exists,
@@ -70,36 +121,122 @@ pub fn run() {
             commands::files::get_file_info,
             commands::files::create_directory,
             commands::files::delete_file,
+            commands::files::delete_directory,
             commands::files::copy_file,
+            commands::files::copy_directory,
             commands::files::move_file,
+            commands::files::diff_files,
+            commands::files::diff_content,
+            commands::files::hash_file,
+            commands::files::count_loc,
+            commands::files::find_duplicate_files,
+            commands::files::extract_archive,
+            commands::files::create_archive,
+            commands::files::batch_file_ops,
+            commands::files::apply_patch,
             commands::files::set_project_path,
+            commands::files::get_workspace_roots,
+            commands::files::add_workspace_root,
+            commands::files::remove_workspace_root,
             commands::files::get_project_path,
             commands::files::select_directory,
+            commands::files::start_watching,
+            commands::files::stop_watching,
             // Git commands
             commands::git::git_status,
             commands::git::git_diff,
             commands::git::git_diff_file,
+            commands::git::git_diff_refs,
+            commands::git::git_diff_refs_files,
             commands::git::git_log,
+            commands::git::git_log_graph,
+            commands::git::git_search_commits,
             commands::git::git_stage,
             commands::git::git_unstage,
             commands::git::git_stage_all,
             commands::git::git_commit,
+            commands::git::generate_commit_message,
             commands::git::git_discard,
+            commands::git::git_reset,
+            commands::git::git_reflog,
+            commands::git::git_undo_last_operation,
             commands::git::git_branches,
             commands::git::git_checkout,
             commands::git::git_create_branch,
+            commands::git::git_delete_branch,
+            commands::git::git_rename_branch,
+            commands::git::git_set_upstream,
+            commands::git::git_merge,
+            commands::git::git_conflicts,
+            commands::git::git_merge_file_versions,
+            commands::git::git_resolve_conflict,
+            commands::git::git_rebase_start,
+            commands::git::git_rebase_continue,
+            commands::git::git_rebase_skip,
+            commands::git::git_rebase_abort,
+            commands::git::git_worktree_list,
+            commands::git::git_worktree_add,
+            commands::git::git_worktree_remove,
+            commands::git::set_session_worktree,
             commands::git::git_pull,
             commands::git::git_push,
             commands::git::git_fetch,
+            commands::git::git_unshallow,
+            commands::git::git_clone,
             commands::git::is_git_repository,
             commands::git::git_init,
             commands::git::git_show_file,
+            commands::git::git_check_ignore,
+            commands::git::add_to_gitignore,
+            // Pull request commands
+            commands::forge::forge_list_prs,
+            commands::forge::forge_create_pr,
+            commands::forge::forge_get_pr_diff,
+            commands::forge::forge_comment_on_pr,
+            // Image commands
+            commands::images::generate_image,
+            commands::images::get_image_providers,
+            commands::images::capture_screenshot,
+            // Index commands
+            commands::index::reindex_project,
+            commands::index::build_text_index,
+            commands::index::drop_text_index,
+            // Prompt library commands
+            commands::prompts::list_prompts,
+            commands::prompts::create_prompt,
+            commands::prompts::update_prompt,
+            commands::prompts::delete_prompt,
+            commands::prompts::render_prompt,
+            // Review commands
+            commands::review::review_diff,
+            // Scaffolding commands
+            commands::scaffold::list_project_templates,
+            commands::scaffold::scaffold_project,
+            // Session commands
+            commands::sessions::create_session,
+            commands::sessions::update_session_config,
+            commands::sessions::append_message,
+            commands::sessions::list_sessions,
+            commands::sessions::search_sessions,
+            commands::sessions::load_session,
+            commands::sessions::delete_session,
+            commands::sessions::export_session,
+            commands::sessions::import_session,
+            commands::sessions::branch_session,
+            commands::sessions::regenerate_from,
+            // Settings commands
+            commands::settings::get_settings,
+            commands::settings::update_settings,
+            commands::settings::get_default_system_prompts,
+            commands::settings::set_default_system_prompt,
             // Terminal commands
             commands::terminal::spawn_terminal,
             commands::terminal::write_terminal,
             commands::terminal::resize_terminal,
             commands::terminal::close_terminal,
             commands::terminal::list_terminals,
+            commands::terminal::restore_terminals,
+            commands::terminal::set_terminal_title,
             commands::terminal::send_terminal_signal,
             commands::terminal::execute_command,
             commands::terminal::execute_shell,
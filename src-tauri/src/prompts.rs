@@ -0,0 +1,333 @@
+//! Prompt library
+//!
+//! Reusable prompts the user saves once and inserts by name, such as "write
+//! tests in our style". A prompt may contain `{{variable}}` placeholders that
+//! are filled in when it is rendered. The library is saved as JSON in the app
+//! data directory.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// File (relative to the app data directory) holding the prompt library
+pub const PROMPTS_FILE: &str = "prompts.json";
+
+/// Errors that can occur while managing the prompt library
+#[derive(Debug, Error)]
+pub enum PromptError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Prompt not found: {0}")]
+    NotFound(String),
+
+    #[error("A prompt named '{0}' already exists")]
+    DuplicateName(String),
+
+    #[error("Invalid prompt name '{0}': use letters, digits, '-' and '_'")]
+    InvalidName(String),
+
+    #[error("Missing values for variables: {}", .0.join(", "))]
+    MissingVariables(Vec<String>),
+}
+
+/// A prompt as given by the frontend when creating or updating it
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptInput {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub content: String,
+}
+
+/// A prompt in the library
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPrompt {
+    pub id: String,
+    /// Unique name the prompt is inserted by
+    pub name: String,
+    pub description: Option<String>,
+    pub content: String,
+    /// Names of the `{{variable}}` placeholders in the content, in order
+    pub variables: Vec<String>,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+    /// Unix timestamp (seconds)
+    pub updated_at: u64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Find the `{{variable}}` placeholders in a prompt
+///
+/// # Returns
+/// The byte range of each placeholder with the variable's name
+fn placeholders(content: &str) -> Vec<(usize, usize, &str)> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+
+    while let Some(start) = content[offset..].find("{{").map(|i| offset + i) {
+        let Some(end) = content[start + 2..].find("}}").map(|i| start + 2 + i) else {
+            break;
+        };
+        let name = content[start + 2..end].trim();
+        if is_variable_name(name) {
+            found.push((start, end + 2, name));
+            offset = end + 2;
+        } else {
+            offset = start + 2;
+        }
+    }
+    found
+}
+
+/// Names of the variables in a prompt, each listed once
+pub fn prompt_variables(content: &str) -> Vec<String> {
+    let mut variables: Vec<String> = Vec::new();
+    for (_, _, name) in placeholders(content) {
+        if !variables.iter().any(|v| v == name) {
+            variables.push(name.to_string());
+        }
+    }
+    variables
+}
+
+/// Fill in a prompt's variables
+///
+/// Every variable must have a value; extra values are ignored.
+pub fn render_prompt(
+    content: &str,
+    values: &HashMap<String, String>,
+) -> Result<String, PromptError> {
+    let missing: Vec<String> = prompt_variables(content)
+        .into_iter()
+        .filter(|v| !values.contains_key(v))
+        .collect();
+    if !missing.is_empty() {
+        return Err(PromptError::MissingVariables(missing));
+    }
+
+    let mut rendered = String::with_capacity(content.len());
+    let mut last = 0;
+    for (start, end, name) in placeholders(content) {
+        rendered.push_str(&content[last..start]);
+        rendered.push_str(&values[name]);
+        last = end;
+    }
+    rendered.push_str(&content[last..]);
+    Ok(rendered)
+}
+
+/// The saved prompts
+#[derive(Debug, Default)]
+pub struct PromptLibrary {
+    prompts: Mutex<Vec<SavedPrompt>>,
+    /// Where the library is saved; `None` keeps it in memory only
+    path: Option<PathBuf>,
+}
+
+impl PromptLibrary {
+    /// Create an empty, in-memory library
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the library from its file, starting empty if it doesn't exist or is invalid
+    pub fn load(path: &Path) -> Self {
+        let prompts = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("Ignoring invalid prompt library {}: {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self {
+            prompts: Mutex::new(prompts),
+            path: Some(path.to_path_buf()),
+        }
+    }
+
+    fn prompts(&self) -> MutexGuard<'_, Vec<SavedPrompt>> {
+        self.prompts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, prompts: &[SavedPrompt]) -> Result<(), PromptError> {
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, serde_json::to_string_pretty(prompts)?)?;
+        }
+        Ok(())
+    }
+
+    /// Check a prompt's name is valid and not used by another prompt
+    fn check_name(
+        prompts: &[SavedPrompt],
+        name: &str,
+        id: Option<&str>,
+    ) -> Result<(), PromptError> {
+        if !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+            || name.is_empty()
+        {
+            return Err(PromptError::InvalidName(name.to_string()));
+        }
+        if prompts
+            .iter()
+            .any(|p| p.name == name && Some(p.id.as_str()) != id)
+        {
+            return Err(PromptError::DuplicateName(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// All prompts, sorted by name
+    pub fn list(&self) -> Vec<SavedPrompt> {
+        let mut prompts = self.prompts().clone();
+        prompts.sort_by(|a, b| a.name.cmp(&b.name));
+        prompts
+    }
+
+    /// Find a prompt by name
+    pub fn get(&self, name: &str) -> Result<SavedPrompt, PromptError> {
+        self.prompts()
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| PromptError::NotFound(name.to_string()))
+    }
+
+    /// Add a prompt to the library
+    pub fn create(&self, input: PromptInput) -> Result<SavedPrompt, PromptError> {
+        let mut prompts = self.prompts();
+        Self::check_name(&prompts, &input.name, None)?;
+
+        let created_at = now();
+        let prompt = SavedPrompt {
+            id: uuid::Uuid::new_v4().to_string(),
+            variables: prompt_variables(&input.content),
+            name: input.name,
+            description: input.description,
+            content: input.content,
+            created_at,
+            updated_at: created_at,
+        };
+        prompts.push(prompt.clone());
+        self.save(&prompts)?;
+        Ok(prompt)
+    }
+
+    /// Replace a prompt's name, description and content
+    pub fn update(&self, id: &str, input: PromptInput) -> Result<SavedPrompt, PromptError> {
+        let mut prompts = self.prompts();
+        Self::check_name(&prompts, &input.name, Some(id))?;
+
+        let prompt = prompts
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| PromptError::NotFound(id.to_string()))?;
+        prompt.variables = prompt_variables(&input.content);
+        prompt.name = input.name;
+        prompt.description = input.description;
+        prompt.content = input.content;
+        prompt.updated_at = now();
+        let prompt = prompt.clone();
+
+        self.save(&prompts)?;
+        Ok(prompt)
+    }
+
+    /// Remove a prompt from the library
+    pub fn delete(&self, id: &str) -> Result<(), PromptError> {
+        let mut prompts = self.prompts();
+        let index = prompts
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| PromptError::NotFound(id.to_string()))?;
+        prompts.remove(index);
+        self.save(&prompts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_prompt_library_persists_and_renders() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(PROMPTS_FILE);
+        let library = PromptLibrary::load(&path);
+
+        let tests = library
+            .create(PromptInput {
+                name: "tests".to_string(),
+                description: None,
+                content: "Write tests for {{ target }} using {{framework}}. Cover {{target}}'s \
+                          edge cases. {{not a variable}}"
+                    .to_string(),
+            })
+            .unwrap();
+        assert_eq!(tests.variables, vec!["target", "framework"]);
+        assert!(matches!(
+            library.create(PromptInput {
+                name: "tests".to_string(),
+                description: None,
+                content: String::new(),
+            }),
+            Err(PromptError::DuplicateName(_))
+        ));
+
+        let reloaded = PromptLibrary::load(&path);
+        let saved = reloaded.get("tests").unwrap();
+        assert_eq!(saved, tests);
+
+        let values = HashMap::from([
+            ("target".to_string(), "the parser".to_string()),
+            ("framework".to_string(), "insta".to_string()),
+        ]);
+        assert_eq!(
+            render_prompt(&saved.content, &values).unwrap(),
+            "Write tests for the parser using insta. Cover the parser's edge cases. \
+             {{not a variable}}"
+        );
+        assert!(matches!(
+            render_prompt(&saved.content, &HashMap::new()),
+            Err(PromptError::MissingVariables(v)) if v == vec!["target", "framework"]
+        ));
+
+        let renamed = reloaded
+            .update(
+                &tests.id,
+                PromptInput {
+                    name: "unit-tests".to_string(),
+                    description: Some("Our test style".to_string()),
+                    content: "Write unit tests.".to_string(),
+                },
+            )
+            .unwrap();
+        assert!(renamed.variables.is_empty());
+        reloaded.delete(&tests.id).unwrap();
+        assert!(PromptLibrary::load(&path).list().is_empty());
+    }
+}
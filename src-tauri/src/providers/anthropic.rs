@@ -10,15 +10,21 @@ use serde::{Deserialize, Serialize};
 use std::pin::Pin;
 
 use super::{
-    ChatChunk, ChatMessage, ChatResponse, ContentBlock, ContentDelta,
-    Provider, ProviderError, Role, StopReason, Tool, Usage,
+    build_client, send_with_deadline, ChatChunk, ChatMessage, ChatResponse, Citation,
+    ContentBlock, ContentDelta, Provider, ProviderError, ProviderTimeouts, Role, StopReason,
+    Tool, Usage,
 };
 
 const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
 const DEFAULT_MODEL: &str = "claude-sonnet-4-20250514";
+
+/// Small, fast model for background work such as titling sessions
+pub const UTILITY_MODEL: &str = "claude-3-5-haiku-20241022";
 const DEFAULT_MAX_TOKENS: u32 = 4096;
 const DEFAULT_TEMPERATURE: f32 = 0.7;
+const CONTEXT_WINDOW: u32 = 200_000;
+const MIN_THINKING_BUDGET: u32 = 1024;
 
 /// Anthropic API request body
 #[derive(Debug, Serialize)]
@@ -32,10 +38,19 @@ struct AnthropicRequest {
     tools: Option<Vec<AnthropicTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<AnthropicThinking>,
     #[serde(skip_serializing_if = "std::ops::Not::not")]
     stream: bool,
 }
 
+/// Extended thinking configuration
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicThinking {
+    Enabled { budget_tokens: u32 },
+}
+
 /// Anthropic message format
 #[derive(Debug, Serialize, Deserialize)]
 struct AnthropicMessage {
@@ -57,6 +72,8 @@ enum AnthropicContent {
 enum AnthropicContentBlock {
     Text {
         text: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        citations: Vec<AnthropicCitation>,
     },
     Image {
         source: AnthropicImageSource,
@@ -72,6 +89,41 @@ enum AnthropicContentBlock {
         #[serde(skip_serializing_if = "Option::is_none")]
         is_error: Option<bool>,
     },
+    Thinking {
+        thinking: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        signature: Option<String>,
+    },
+    RedactedThinking {
+        data: String,
+    },
+}
+
+/// Anthropic citation attached to a text block
+#[derive(Debug, Serialize, Deserialize)]
+struct AnthropicCitation {
+    #[serde(rename = "type")]
+    citation_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cited_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    document_title: Option<String>,
+}
+
+impl From<AnthropicCitation> for Citation {
+    fn from(citation: AnthropicCitation) -> Self {
+        Citation {
+            url: citation.url,
+            title: citation.title.or(citation.document_title),
+            cited_text: citation.cited_text,
+            start_index: None,
+            end_index: None,
+        }
+    }
 }
 
 /// Anthropic image source
@@ -104,7 +156,10 @@ struct AnthropicResponse {
 /// Anthropic usage stats
 #[derive(Debug, Deserialize)]
 struct AnthropicUsage {
+    // Stream events report only the counts that changed
+    #[serde(default)]
     input_tokens: u32,
+    #[serde(default)]
     output_tokens: u32,
 }
 
@@ -158,13 +213,18 @@ enum AnthropicStreamEvent {
 struct AnthropicStreamMessage {
     id: String,
     model: String,
+    usage: Option<AnthropicUsage>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)] // Variant names mirror the API's delta types
 enum AnthropicDelta {
     TextDelta { text: String },
     InputJsonDelta { partial_json: String },
+    ThinkingDelta { thinking: String },
+    SignatureDelta { signature: String },
+    CitationsDelta { citation: AnthropicCitation },
 }
 
 #[derive(Debug, Deserialize)]
@@ -173,6 +233,7 @@ struct AnthropicMessageDelta {
 }
 
 /// Anthropic Claude API provider
+#[derive(Clone)]
 pub struct AnthropicProvider {
     client: Client,
     api_key: String,
@@ -180,21 +241,73 @@ pub struct AnthropicProvider {
     system_prompt: Option<String>,
     max_tokens: u32,
     temperature: f32,
+    timeouts: ProviderTimeouts,
+    thinking_budget: Option<u32>,
 }
 
 impl AnthropicProvider {
     /// Create a new Anthropic provider with the given API key
     pub fn new(api_key: String) -> Self {
         Self {
-            client: Client::new(),
+            client: build_client(&ProviderTimeouts::default()),
             api_key,
             model: DEFAULT_MODEL.to_string(),
             system_prompt: None,
             max_tokens: DEFAULT_MAX_TOKENS,
             temperature: DEFAULT_TEMPERATURE,
+            timeouts: ProviderTimeouts::default(),
+            thinking_budget: None,
+        }
+    }
+
+    /// Enable extended thinking with the given token budget, or disable it with `None`
+    pub fn set_thinking_budget(&mut self, budget: Option<u32>) {
+        self.thinking_budget = budget.filter(|b| *b >= MIN_THINKING_BUDGET);
+    }
+
+    /// Get the extended thinking budget, if enabled
+    pub fn thinking_budget(&self) -> Option<u32> {
+        self.thinking_budget
+    }
+
+    /// Build an API request for the given conversation
+    fn build_request(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<Vec<Tool>>,
+        stream: bool,
+    ) -> AnthropicRequest {
+        // Extended thinking requires the default temperature and a max_tokens
+        // larger than the thinking budget
+        let (max_tokens, temperature, thinking) = match self.thinking_budget {
+            Some(budget) => (
+                self.max_tokens.max(budget + DEFAULT_MAX_TOKENS),
+                None,
+                Some(AnthropicThinking::Enabled {
+                    budget_tokens: budget,
+                }),
+            ),
+            None => (self.max_tokens, Some(self.temperature), None),
+        };
+
+        AnthropicRequest {
+            model: self.model.clone(),
+            max_tokens,
+            messages: self.convert_messages(messages),
+            system: self.extract_system_prompt(messages),
+            tools: tools.map(|t| self.convert_tools(&t)),
+            temperature,
+            thinking,
+            stream,
         }
     }
 
+    /// Set network timeouts, rebuilding the HTTP client
+    pub fn set_timeouts(&mut self, timeouts: ProviderTimeouts) {
+        self.timeouts = timeouts.validated();
+        self.client = build_client(&self.timeouts);
+    }
+
     /// Convert internal messages to Anthropic format
     fn convert_messages(&self, messages: &[ChatMessage]) -> Vec<AnthropicMessage> {
         messages
@@ -215,10 +328,11 @@ impl AnthropicProvider {
                         AnthropicContent::Blocks(
                             content
                                 .iter()
-                                .map(|b| match b {
-                                    ContentBlock::Text { text } => {
-                                        AnthropicContentBlock::Text { text: text.clone() }
-                                    }
+                                .filter_map(|b| Some(match b {
+                                    ContentBlock::Text { text } => AnthropicContentBlock::Text {
+                                        text: text.clone(),
+                                        citations: Vec::new(),
+                                    },
                                     ContentBlock::Image { source } => {
                                         match source {
                                             super::types::ImageSource::Base64 { media_type, data } => {
@@ -235,6 +349,7 @@ impl AnthropicProvider {
                                                 // would need to fetch and convert
                                                 AnthropicContentBlock::Text {
                                                     text: format!("[Image URL: {}]", url),
+                                                    citations: Vec::new(),
                                                 }
                                             }
                                         }
@@ -255,7 +370,21 @@ impl AnthropicProvider {
                                         content: content.clone(),
                                         is_error: *is_error,
                                     },
-                                })
+                                    ContentBlock::Thinking {
+                                        thinking,
+                                        signature,
+                                    } => AnthropicContentBlock::Thinking {
+                                        thinking: thinking.clone(),
+                                        signature: signature.clone(),
+                                    },
+                                    ContentBlock::RedactedThinking { data } => {
+                                        AnthropicContentBlock::RedactedThinking {
+                                            data: data.clone(),
+                                        }
+                                    }
+                                    // Citations are display-only and not sent back
+                                    ContentBlock::Citation(_) => return None,
+                                }))
                                 .collect(),
                         )
                     }
@@ -305,35 +434,64 @@ impl AnthropicProvider {
             .collect()
     }
 
+    /// Convert an Anthropic content block to internal format
+    ///
+    /// Citations on text blocks are not included; see `convert_response`.
+    fn convert_block(block: AnthropicContentBlock) -> ContentBlock {
+        match block {
+            AnthropicContentBlock::Text { text, .. } => ContentBlock::Text { text },
+            AnthropicContentBlock::Image { source } => ContentBlock::Image {
+                source: super::types::ImageSource::Base64 {
+                    media_type: source.media_type,
+                    data: source.data,
+                },
+            },
+            AnthropicContentBlock::ToolUse { id, name, input } => {
+                ContentBlock::ToolUse { id, name, input }
+            }
+            AnthropicContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            },
+            AnthropicContentBlock::Thinking {
+                thinking,
+                signature,
+            } => ContentBlock::Thinking {
+                thinking,
+                // Stream block starts carry an empty signature; it arrives later as a delta
+                signature: signature.filter(|s| !s.is_empty()),
+            },
+            AnthropicContentBlock::RedactedThinking { data } => {
+                ContentBlock::RedactedThinking { data }
+            }
+        }
+    }
+
     /// Convert Anthropic response to internal format
     fn convert_response(&self, response: AnthropicResponse) -> ChatResponse {
+        let mut content = Vec::new();
+        for mut block in response.content {
+            // Citations follow the text block they support
+            let citations = match &mut block {
+                AnthropicContentBlock::Text { citations, .. } => std::mem::take(citations),
+                _ => Vec::new(),
+            };
+            content.push(Self::convert_block(block));
+            content.extend(
+                citations
+                    .into_iter()
+                    .map(|c| ContentBlock::Citation(c.into())),
+            );
+        }
+
         ChatResponse {
             id: response.id,
-            content: response
-                .content
-                .into_iter()
-                .map(|b| match b {
-                    AnthropicContentBlock::Text { text } => ContentBlock::Text { text },
-                    AnthropicContentBlock::Image { source } => ContentBlock::Image {
-                        source: super::types::ImageSource::Base64 {
-                            media_type: source.media_type,
-                            data: source.data,
-                        },
-                    },
-                    AnthropicContentBlock::ToolUse { id, name, input } => {
-                        ContentBlock::ToolUse { id, name, input }
-                    }
-                    AnthropicContentBlock::ToolResult {
-                        tool_use_id,
-                        content,
-                        is_error,
-                    } => ContentBlock::ToolResult {
-                        tool_use_id,
-                        content,
-                        is_error,
-                    },
-                })
-                .collect(),
+            content,
             stop_reason: response.stop_reason.map(|r| match r.as_str() {
                 "end_turn" => StopReason::EndTurn,
                 "max_tokens" => StopReason::MaxTokens,
@@ -356,49 +514,38 @@ impl AnthropicProvider {
     }
 
     /// Convert Anthropic stream event to internal chunk format
-    #[allow(dead_code)]
-    fn convert_stream_event(&self, event: AnthropicStreamEvent) -> ChatChunk {
+    fn convert_stream_event(event: AnthropicStreamEvent) -> ChatChunk {
         match event {
             AnthropicStreamEvent::MessageStart { message } => ChatChunk::MessageStart {
                 id: message.id,
                 model: message.model,
+                usage: message.usage.map(|u| Usage {
+                    input_tokens: u.input_tokens,
+                    output_tokens: u.output_tokens,
+                }),
             },
             AnthropicStreamEvent::ContentBlockStart {
                 index,
                 content_block,
-            } => {
-                let block = match content_block {
-                    AnthropicContentBlock::Text { text } => ContentBlock::Text { text },
-                    AnthropicContentBlock::ToolUse { id, name, input } => {
-                        ContentBlock::ToolUse { id, name, input }
-                    }
-                    AnthropicContentBlock::Image { source } => ContentBlock::Image {
-                        source: super::types::ImageSource::Base64 {
-                            media_type: source.media_type,
-                            data: source.data,
-                        },
-                    },
-                    AnthropicContentBlock::ToolResult {
-                        tool_use_id,
-                        content,
-                        is_error,
-                    } => ContentBlock::ToolResult {
-                        tool_use_id,
-                        content,
-                        is_error,
-                    },
-                };
-                ChatChunk::ContentBlockStart {
-                    index,
-                    content_block: block,
-                }
-            }
+            } => ChatChunk::ContentBlockStart {
+                index,
+                content_block: Self::convert_block(content_block),
+            },
             AnthropicStreamEvent::ContentBlockDelta { index, delta } => {
                 let delta = match delta {
                     AnthropicDelta::TextDelta { text } => ContentDelta::TextDelta { text },
                     AnthropicDelta::InputJsonDelta { partial_json } => {
                         ContentDelta::InputJsonDelta { partial_json }
                     }
+                    AnthropicDelta::ThinkingDelta { thinking } => {
+                        ContentDelta::ThinkingDelta { thinking }
+                    }
+                    AnthropicDelta::SignatureDelta { signature } => {
+                        ContentDelta::SignatureDelta { signature }
+                    }
+                    AnthropicDelta::CitationsDelta { citation } => ContentDelta::CitationDelta {
+                        citation: citation.into(),
+                    },
                 };
                 ChatChunk::ContentBlockDelta { index, delta }
             }
@@ -434,15 +581,7 @@ impl Provider for AnthropicProvider {
         messages: Vec<ChatMessage>,
         tools: Option<Vec<Tool>>,
     ) -> Result<ChatResponse, ProviderError> {
-        let request = AnthropicRequest {
-            model: self.model.clone(),
-            max_tokens: self.max_tokens,
-            messages: self.convert_messages(&messages),
-            system: self.extract_system_prompt(&messages),
-            tools: tools.map(|t| self.convert_tools(&t)),
-            temperature: Some(self.temperature),
-            stream: false,
-        };
+        let request = self.build_request(&messages, tools, false);
 
         let response = self
             .client
@@ -451,6 +590,7 @@ impl Provider for AnthropicProvider {
             .header("anthropic-version", ANTHROPIC_VERSION)
             .header("content-type", "application/json")
             .json(&request)
+            .timeout(self.timeouts.request())
             .send()
             .await?;
 
@@ -482,25 +622,16 @@ impl Provider for AnthropicProvider {
         tools: Option<Vec<Tool>>,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<ChatChunk, ProviderError>> + Send>>, ProviderError>
     {
-        let request = AnthropicRequest {
-            model: self.model.clone(),
-            max_tokens: self.max_tokens,
-            messages: self.convert_messages(&messages),
-            system: self.extract_system_prompt(&messages),
-            tools: tools.map(|t| self.convert_tools(&t)),
-            temperature: Some(self.temperature),
-            stream: true,
-        };
+        let request = self.build_request(&messages, tools, true);
 
-        let response = self
+        let builder = self
             .client
             .post(ANTHROPIC_API_URL)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", ANTHROPIC_VERSION)
             .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+            .json(&request);
+        let response = send_with_deadline(builder, self.timeouts.request()).await?;
 
         let status = response.status();
         if !status.is_success() {
@@ -527,10 +658,7 @@ impl Provider for AnthropicProvider {
             .map(move |result| {
                 result
                     .map_err(|e| ProviderError::StreamError(e.to_string()))
-                    .and_then(|bytes| {
-                        let text = String::from_utf8_lossy(&bytes);
-                        Ok(text.to_string())
-                    })
+                    .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
             })
             .filter_map(|result| async move {
                 match result {
@@ -545,84 +673,7 @@ impl Provider for AnthropicProvider {
                                 if let Ok(event) =
                                     serde_json::from_str::<AnthropicStreamEvent>(data)
                                 {
-                                    let chunk = match event {
-                                        AnthropicStreamEvent::MessageStart { message } => {
-                                            ChatChunk::MessageStart {
-                                                id: message.id,
-                                                model: message.model,
-                                            }
-                                        }
-                                        AnthropicStreamEvent::ContentBlockStart {
-                                            index,
-                                            content_block,
-                                        } => {
-                                            let block = match content_block {
-                                                AnthropicContentBlock::Text { text } => {
-                                                    ContentBlock::Text { text }
-                                                }
-                                                AnthropicContentBlock::ToolUse { id, name, input } => {
-                                                    ContentBlock::ToolUse { id, name, input }
-                                                }
-                                                AnthropicContentBlock::Image { source } => {
-                                                    ContentBlock::Image {
-                                                        source: super::types::ImageSource::Base64 {
-                                                            media_type: source.media_type,
-                                                            data: source.data,
-                                                        },
-                                                    }
-                                                }
-                                                AnthropicContentBlock::ToolResult {
-                                                    tool_use_id,
-                                                    content,
-                                                    is_error,
-                                                } => ContentBlock::ToolResult {
-                                                    tool_use_id,
-                                                    content,
-                                                    is_error,
-                                                },
-                                            };
-                                            ChatChunk::ContentBlockStart {
-                                                index,
-                                                content_block: block,
-                                            }
-                                        }
-                                        AnthropicStreamEvent::ContentBlockDelta { index, delta } => {
-                                            let delta = match delta {
-                                                AnthropicDelta::TextDelta { text } => {
-                                                    ContentDelta::TextDelta { text }
-                                                }
-                                                AnthropicDelta::InputJsonDelta { partial_json } => {
-                                                    ContentDelta::InputJsonDelta { partial_json }
-                                                }
-                                            };
-                                            ChatChunk::ContentBlockDelta { index, delta }
-                                        }
-                                        AnthropicStreamEvent::ContentBlockStop { index } => {
-                                            ChatChunk::ContentBlockStop { index }
-                                        }
-                                        AnthropicStreamEvent::MessageDelta { delta, usage } => {
-                                            ChatChunk::MessageDelta {
-                                                stop_reason: delta.stop_reason.map(|r| {
-                                                    match r.as_str() {
-                                                        "end_turn" => StopReason::EndTurn,
-                                                        "max_tokens" => StopReason::MaxTokens,
-                                                        "stop_sequence" => StopReason::StopSequence,
-                                                        "tool_use" => StopReason::ToolUse,
-                                                        _ => StopReason::EndTurn,
-                                                    }
-                                                }),
-                                                usage: usage.map(|u| Usage {
-                                                    input_tokens: u.input_tokens,
-                                                    output_tokens: u.output_tokens,
-                                                }),
-                                            }
-                                        }
-                                        AnthropicStreamEvent::MessageStop => ChatChunk::MessageStop,
-                                        AnthropicStreamEvent::Ping => ChatChunk::Ping,
-                                        AnthropicStreamEvent::Error { error } => ChatChunk::Error {
-                                            message: error.message,
-                                        },
-                                    };
+                                    let chunk = AnthropicProvider::convert_stream_event(event);
                                     chunks.push(Ok(chunk));
                                 }
                             }
@@ -667,6 +718,10 @@ impl Provider for AnthropicProvider {
         &self.model
     }
 
+    fn context_window(&self) -> u32 {
+        CONTEXT_WINDOW
+    }
+
     fn set_system_prompt(&mut self, prompt: Option<String>) {
         self.system_prompt = prompt;
     }
@@ -690,4 +745,12 @@ impl Provider for AnthropicProvider {
     fn temperature(&self) -> f32 {
         self.temperature
     }
+
+    fn endpoint(&self) -> &str {
+        ANTHROPIC_API_URL
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Provider> {
+        Box::new(self.clone())
+    }
 }
@@ -0,0 +1,28 @@
+//! Embedding providers
+//!
+//! This module contains the EmbeddingProvider trait and implementations for
+//! text embedding APIs, used to build the semantic code search index.
+
+pub mod openai;
+
+pub use openai::OpenAIEmbeddingProvider;
+
+use async_trait::async_trait;
+
+use super::ProviderError;
+
+/// Trait for text embedding providers
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Embed a batch of texts, returning one vector per input in the same order
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, ProviderError>;
+
+    /// Get the provider name
+    fn name(&self) -> &str;
+
+    /// Get the embedding model; vectors from different models are not comparable
+    fn model(&self) -> &str;
+
+    /// Maximum number of inputs accepted in one request
+    fn max_batch_size(&self) -> usize;
+}
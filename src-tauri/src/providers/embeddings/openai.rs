@@ -0,0 +1,129 @@
+//! OpenAI Embeddings API Provider
+//!
+//! This module implements the EmbeddingProvider trait for OpenAI's
+//! Embeddings API.
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::EmbeddingProvider;
+use crate::providers::{build_client, send_with_deadline, ProviderError, ProviderTimeouts};
+
+const OPENAI_EMBEDDINGS_URL: &str = "https://api.openai.com/v1/embeddings";
+const DEFAULT_MODEL: &str = "text-embedding-3-small";
+
+/// OpenAI accepts up to 2048 inputs per request; smaller batches keep
+/// requests well under the token limit for code chunks
+const MAX_BATCH_SIZE: usize = 64;
+
+/// OpenAI embeddings request body
+#[derive(Debug, Serialize)]
+struct OpenAIEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+/// OpenAI embeddings response
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbeddingResponse {
+    data: Vec<OpenAIEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// OpenAI error response
+#[derive(Debug, Deserialize)]
+struct OpenAIError {
+    error: OpenAIErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIErrorDetail {
+    message: String,
+}
+
+/// OpenAI Embeddings API provider
+pub struct OpenAIEmbeddingProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    base_url: String,
+}
+
+impl OpenAIEmbeddingProvider {
+    /// Create a new OpenAI embedding provider with the given API key
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: build_client(&ProviderTimeouts::default()),
+            api_key,
+            model: DEFAULT_MODEL.to_string(),
+            base_url: OPENAI_EMBEDDINGS_URL.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAIEmbeddingProvider {
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, ProviderError> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body = OpenAIEmbeddingRequest {
+            model: &self.model,
+            input: inputs,
+        };
+
+        let request = self
+            .client
+            .post(&self.base_url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body);
+        let response = send_with_deadline(request, ProviderTimeouts::default().request()).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            if status.as_u16() == 429 {
+                return Err(ProviderError::RateLimited { retry_after: None });
+            }
+            let message = serde_json::from_str::<OpenAIError>(&error_text)
+                .map(|e| e.error.message)
+                .unwrap_or(error_text);
+            return Err(ProviderError::ApiError {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        let mut embeddings: OpenAIEmbeddingResponse = response.json().await?;
+        if embeddings.data.len() != inputs.len() {
+            return Err(ProviderError::InvalidResponse(format!(
+                "Expected {} embeddings, got {}",
+                inputs.len(),
+                embeddings.data.len()
+            )));
+        }
+
+        embeddings.data.sort_by_key(|e| e.index);
+        Ok(embeddings.data.into_iter().map(|e| e.embedding).collect())
+    }
+
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn max_batch_size(&self) -> usize {
+        MAX_BATCH_SIZE
+    }
+}
@@ -0,0 +1,118 @@
+//! Image generation providers
+//!
+//! This module contains the ImageProvider trait and implementations for
+//! image generation APIs (OpenAI Images, Stability AI), used to produce
+//! diagrams and mockups that get saved into the project.
+
+pub mod openai;
+pub mod stability;
+
+pub use openai::OpenAIImageProvider;
+pub use stability::StabilityImageProvider;
+
+use async_trait::async_trait;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use super::ProviderError;
+
+/// Default image size when none is requested
+pub const DEFAULT_IMAGE_SIZE: &str = "1024x1024";
+
+/// Parameters for an image generation request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageRequest {
+    pub prompt: String,
+    /// Image size as "WIDTHxHEIGHT" (e.g. "1024x1024")
+    #[serde(default)]
+    pub size: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl ImageRequest {
+    /// Create a request for a single image from a prompt
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            size: None,
+            model: None,
+        }
+    }
+
+    /// Get the requested size, falling back to the default
+    pub fn size(&self) -> &str {
+        self.size.as_deref().unwrap_or(DEFAULT_IMAGE_SIZE)
+    }
+
+    /// Parse the requested size into (width, height)
+    pub fn dimensions(&self) -> Result<(u32, u32), ProviderError> {
+        let size = self.size();
+        let parsed = size
+            .split_once('x')
+            .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)));
+
+        match parsed {
+            Some((w, h)) if w > 0 && h > 0 => Ok((w, h)),
+            _ => Err(ProviderError::InvalidResponse(format!(
+                "Invalid image size '{}', expected WIDTHxHEIGHT",
+                size
+            ))),
+        }
+    }
+}
+
+/// An image produced by a provider
+#[derive(Debug, Clone)]
+pub struct GeneratedImage {
+    /// Raw image bytes
+    pub data: Vec<u8>,
+    /// MIME type of the image (e.g. "image/png")
+    pub media_type: String,
+    /// The prompt as rewritten by the provider, if any
+    pub revised_prompt: Option<String>,
+}
+
+impl GeneratedImage {
+    /// Decode a base64 payload returned by an API into an image
+    pub fn from_base64(
+        encoded: &str,
+        media_type: impl Into<String>,
+        revised_prompt: Option<String>,
+    ) -> Result<Self, ProviderError> {
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| ProviderError::InvalidResponse(format!("Invalid image data: {}", e)))?;
+
+        Ok(Self {
+            data,
+            media_type: media_type.into(),
+            revised_prompt,
+        })
+    }
+
+    /// File extension matching the image's media type
+    pub fn extension(&self) -> &str {
+        match self.media_type.as_str() {
+            "image/jpeg" => "jpg",
+            "image/webp" => "webp",
+            _ => "png",
+        }
+    }
+}
+
+/// Trait for image generation providers
+#[async_trait]
+pub trait ImageProvider: Send + Sync {
+    /// Generate an image from a prompt
+    async fn generate(&self, request: ImageRequest) -> Result<GeneratedImage, ProviderError>;
+
+    /// Get the provider name
+    fn name(&self) -> &str;
+
+    /// Get the default model for this provider
+    fn default_model(&self) -> &str;
+
+    /// Get the image sizes this provider supports
+    fn supported_sizes(&self) -> Vec<&str>;
+}
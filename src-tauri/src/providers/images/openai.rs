@@ -0,0 +1,138 @@
+//! OpenAI Images API Provider
+//!
+//! This module implements the ImageProvider trait for OpenAI's Images API
+//! (DALL-E / gpt-image models).
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::{GeneratedImage, ImageProvider, ImageRequest};
+use crate::providers::{build_client, send_with_deadline, ProviderError, ProviderTimeouts};
+
+const OPENAI_IMAGES_URL: &str = "https://api.openai.com/v1/images/generations";
+const DEFAULT_MODEL: &str = "dall-e-3";
+
+/// OpenAI image generation request body
+#[derive(Debug, Serialize)]
+struct OpenAIImageRequest {
+    model: String,
+    prompt: String,
+    n: u32,
+    size: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<String>,
+}
+
+/// OpenAI image generation response
+#[derive(Debug, Deserialize)]
+struct OpenAIImageResponse {
+    data: Vec<OpenAIImageData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIImageData {
+    b64_json: Option<String>,
+    revised_prompt: Option<String>,
+}
+
+/// OpenAI error response
+#[derive(Debug, Deserialize)]
+struct OpenAIError {
+    error: OpenAIErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIErrorDetail {
+    message: String,
+}
+
+/// OpenAI Images API provider
+pub struct OpenAIImageProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    base_url: String,
+}
+
+impl OpenAIImageProvider {
+    /// Create a new OpenAI image provider with the given API key
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: build_client(&ProviderTimeouts::default()),
+            api_key,
+            model: DEFAULT_MODEL.to_string(),
+            base_url: OPENAI_IMAGES_URL.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl ImageProvider for OpenAIImageProvider {
+    async fn generate(&self, request: ImageRequest) -> Result<GeneratedImage, ProviderError> {
+        let model = request.model.clone().unwrap_or_else(|| self.model.clone());
+
+        // gpt-image models always return base64 and reject response_format
+        let response_format = if model.starts_with("gpt-image") {
+            None
+        } else {
+            Some("b64_json".to_string())
+        };
+
+        let body = OpenAIImageRequest {
+            model,
+            prompt: request.prompt.clone(),
+            n: 1,
+            size: request.size().to_string(),
+            response_format,
+        };
+
+        let request = self
+            .client
+            .post(&self.base_url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body);
+        let response = send_with_deadline(request, ProviderTimeouts::default().request()).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            if status.as_u16() == 429 {
+                return Err(ProviderError::RateLimited { retry_after: None });
+            }
+            let message = serde_json::from_str::<OpenAIError>(&error_text)
+                .map(|e| e.error.message)
+                .unwrap_or(error_text);
+            return Err(ProviderError::ApiError {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        let image_response: OpenAIImageResponse = response.json().await?;
+        let image = image_response
+            .data
+            .into_iter()
+            .next()
+            .ok_or_else(|| ProviderError::InvalidResponse("No image returned".to_string()))?;
+
+        let encoded = image
+            .b64_json
+            .ok_or_else(|| ProviderError::InvalidResponse("Image has no data".to_string()))?;
+
+        GeneratedImage::from_base64(&encoded, "image/png", image.revised_prompt)
+    }
+
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn default_model(&self) -> &str {
+        DEFAULT_MODEL
+    }
+
+    fn supported_sizes(&self) -> Vec<&str> {
+        vec!["1024x1024", "1792x1024", "1024x1792"]
+    }
+}
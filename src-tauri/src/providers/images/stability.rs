@@ -0,0 +1,135 @@
+//! Stability AI Image Provider
+//!
+//! This module implements the ImageProvider trait for Stability AI's
+//! Stable Image generation API.
+
+use async_trait::async_trait;
+use reqwest::multipart::Form;
+use reqwest::Client;
+use serde::Deserialize;
+
+use super::{GeneratedImage, ImageProvider, ImageRequest};
+use crate::providers::{build_client, send_with_deadline, ProviderError, ProviderTimeouts};
+
+const STABILITY_API_URL: &str = "https://api.stability.ai/v2beta/stable-image/generate";
+const DEFAULT_MODEL: &str = "core";
+
+/// Aspect ratios accepted by the Stability API
+const ASPECT_RATIOS: &[(&str, f64)] = &[
+    ("21:9", 21.0 / 9.0),
+    ("16:9", 16.0 / 9.0),
+    ("3:2", 3.0 / 2.0),
+    ("5:4", 5.0 / 4.0),
+    ("1:1", 1.0),
+    ("4:5", 4.0 / 5.0),
+    ("2:3", 2.0 / 3.0),
+    ("9:16", 9.0 / 16.0),
+    ("9:21", 9.0 / 21.0),
+];
+
+/// Stability image generation response (JSON mode)
+#[derive(Debug, Deserialize)]
+struct StabilityResponse {
+    image: String,
+    finish_reason: Option<String>,
+}
+
+/// Stability error response
+#[derive(Debug, Deserialize)]
+struct StabilityError {
+    errors: Vec<String>,
+}
+
+/// Stability AI image provider
+pub struct StabilityImageProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+}
+
+impl StabilityImageProvider {
+    /// Create a new Stability image provider with the given API key
+    pub fn new(api_key: String) -> Self {
+        Self {
+            client: build_client(&ProviderTimeouts::default()),
+            api_key,
+            model: DEFAULT_MODEL.to_string(),
+        }
+    }
+
+    /// Pick the supported aspect ratio closest to the requested size
+    fn aspect_ratio(width: u32, height: u32) -> &'static str {
+        let ratio = width as f64 / height as f64;
+        ASPECT_RATIOS
+            .iter()
+            .min_by(|a, b| {
+                (a.1 - ratio)
+                    .abs()
+                    .partial_cmp(&(b.1 - ratio).abs())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|(name, _)| *name)
+            .unwrap_or("1:1")
+    }
+}
+
+#[async_trait]
+impl ImageProvider for StabilityImageProvider {
+    async fn generate(&self, request: ImageRequest) -> Result<GeneratedImage, ProviderError> {
+        let (width, height) = request.dimensions()?;
+        let model = request.model.clone().unwrap_or_else(|| self.model.clone());
+
+        let form = Form::new()
+            .text("prompt", request.prompt.clone())
+            .text("output_format", "png")
+            .text("aspect_ratio", Self::aspect_ratio(width, height));
+
+        let request = self
+            .client
+            .post(format!("{}/{}", STABILITY_API_URL, model))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Accept", "application/json")
+            .multipart(form);
+        let response = send_with_deadline(request, ProviderTimeouts::default().request()).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            if status.as_u16() == 429 {
+                return Err(ProviderError::RateLimited { retry_after: None });
+            }
+            if status.as_u16() == 401 || status.as_u16() == 403 {
+                return Err(ProviderError::AuthError(error_text));
+            }
+            let message = serde_json::from_str::<StabilityError>(&error_text)
+                .map(|e| e.errors.join("; "))
+                .unwrap_or(error_text);
+            return Err(ProviderError::ApiError {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        let stability_response: StabilityResponse = response.json().await?;
+
+        if stability_response.finish_reason.as_deref() == Some("CONTENT_FILTERED") {
+            return Err(ProviderError::InvalidResponse(
+                "Image was blocked by the content filter".to_string(),
+            ));
+        }
+
+        GeneratedImage::from_base64(&stability_response.image, "image/png", None)
+    }
+
+    fn name(&self) -> &str {
+        "stability"
+    }
+
+    fn default_model(&self) -> &str {
+        DEFAULT_MODEL
+    }
+
+    fn supported_sizes(&self) -> Vec<&str> {
+        vec!["1024x1024", "1536x640", "1344x768", "768x1344", "640x1536"]
+    }
+}
@@ -6,21 +6,31 @@
 pub mod types;
 pub mod anthropic;
 pub mod openai;
+pub mod images;
+pub mod embeddings;
+pub mod pricing;
+pub mod scheduler;
 
 pub use types::*;
 pub use anthropic::AnthropicProvider;
 pub use openai::OpenAIProvider;
 
 use async_trait::async_trait;
+use std::future::Future;
 use std::pin::Pin;
+use std::time::Duration;
 use futures::Stream;
+use reqwest::Client;
 use thiserror::Error;
 
 /// Errors that can occur when interacting with AI providers
 #[derive(Debug, Error)]
 pub enum ProviderError {
     #[error("HTTP request failed: {0}")]
-    RequestFailed(#[from] reqwest::Error),
+    RequestFailed(#[source] reqwest::Error),
+
+    #[error("Request timed out: {0}")]
+    Timeout(String),
 
     #[error("API error: {status} - {message}")]
     ApiError { status: u16, message: String },
@@ -47,6 +57,92 @@ pub enum ProviderError {
     Unsupported(String),
 }
 
+impl ProviderError {
+    /// Whether the request may succeed if it is sent again
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ProviderError::RequestFailed(e) => e.is_connect(),
+            ProviderError::RateLimited { .. } => true,
+            ProviderError::ApiError { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+/// Most times a failed request is sent again
+pub const MAX_REQUEST_RETRIES: u32 = 2;
+
+/// Longest wait before retrying a rate-limited request
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Send a request, retrying with exponential backoff while it fails with a
+/// retryable error
+///
+/// # Returns
+/// The result of the last attempt and how many times the request was retried
+pub async fn with_retries<T, F, Fut>(mut request: F) -> (Result<T, ProviderError>, u32)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ProviderError>>,
+{
+    let mut retries = 0;
+    loop {
+        match request().await {
+            Err(e) if e.is_retryable() && retries < MAX_REQUEST_RETRIES => {
+                let delay = match e {
+                    ProviderError::RateLimited {
+                        retry_after: Some(secs),
+                    } => Duration::from_secs(secs).min(MAX_RETRY_DELAY),
+                    _ => Duration::from_secs(1 << retries),
+                };
+                log::warn!("Retrying request in {}s after error: {}", delay.as_secs(), e);
+                tokio::time::sleep(delay).await;
+                retries += 1;
+            }
+            result => return (result, retries),
+        }
+    }
+}
+
+impl From<reqwest::Error> for ProviderError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            ProviderError::Timeout(e.to_string())
+        } else {
+            ProviderError::RequestFailed(e)
+        }
+    }
+}
+
+/// Build an HTTP client with the given connect and read timeouts
+pub fn build_client(timeouts: &ProviderTimeouts) -> Client {
+    Client::builder()
+        .connect_timeout(timeouts.connect())
+        .read_timeout(timeouts.read())
+        .build()
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to build HTTP client with timeouts: {}", e);
+            Client::new()
+        })
+}
+
+/// Send a request, failing if the response hasn't started within `deadline`
+///
+/// Only the wait for the response headers is bounded, so this is suitable for
+/// streaming requests whose bodies may legitimately take longer.
+pub(crate) async fn send_with_deadline(
+    request: reqwest::RequestBuilder,
+    deadline: Duration,
+) -> Result<reqwest::Response, ProviderError> {
+    match tokio::time::timeout(deadline, request.send()).await {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(ProviderError::Timeout(format!(
+            "no response after {} seconds",
+            deadline.as_secs()
+        ))),
+    }
+}
+
 /// Trait for AI providers
 ///
 /// This trait defines the interface that all AI providers must implement
@@ -71,6 +167,9 @@ pub trait Provider: Send + Sync {
     /// Get the provider name
     fn name(&self) -> &str;
 
+    /// Get the URL requests are sent to
+    fn endpoint(&self) -> &str;
+
     /// Check if this provider supports tool/function calling
     fn supports_tools(&self) -> bool;
 
@@ -86,6 +185,9 @@ pub trait Provider: Send + Sync {
     /// Get the current model
     fn model(&self) -> &str;
 
+    /// Get the context window size of the current model, in tokens
+    fn context_window(&self) -> u32;
+
     /// Set the system prompt
     fn set_system_prompt(&mut self, prompt: Option<String>);
 
@@ -103,6 +205,9 @@ pub trait Provider: Send + Sync {
 
     /// Get temperature
     fn temperature(&self) -> f32;
+
+    /// Copy this provider, e.g. to change its model for one conversation
+    fn boxed_clone(&self) -> Box<dyn Provider>;
 }
 
 /// Helper function to create a provider from configuration
@@ -110,6 +215,9 @@ pub fn create_provider(config: &ProviderConfig) -> Result<Box<dyn Provider>, Pro
     match config.name.as_str() {
         "anthropic" => {
             let mut provider = AnthropicProvider::new(config.api_key.clone());
+            if let Some(timeouts) = config.timeouts {
+                provider.set_timeouts(timeouts);
+            }
             if let Some(model) = &config.model {
                 provider.set_model(model);
             }
@@ -123,6 +231,9 @@ pub fn create_provider(config: &ProviderConfig) -> Result<Box<dyn Provider>, Pro
         }
         "openai" => {
             let mut provider = OpenAIProvider::new(config.api_key.clone());
+            if let Some(timeouts) = config.timeouts {
+                provider.set_timeouts(timeouts);
+            }
             if let Some(model) = &config.model {
                 provider.set_model(model);
             }
@@ -10,12 +10,16 @@ use serde::{Deserialize, Serialize};
 use std::pin::Pin;
 
 use super::{
-    ChatChunk, ChatMessage, ChatResponse, ContentBlock, ContentDelta,
-    Provider, ProviderError, Role, StopReason, Tool, Usage,
+    build_client, send_with_deadline, ChatChunk, ChatMessage, ChatResponse, Citation,
+    ContentBlock, ContentDelta, Provider, ProviderError, ProviderTimeouts, Role, StopReason,
+    Tool, Usage,
 };
 
 const OPENAI_API_URL: &str = "https://api.openai.com/v1/chat/completions";
 const DEFAULT_MODEL: &str = "gpt-4o";
+
+/// Small, fast model for background work such as titling sessions
+pub const UTILITY_MODEL: &str = "gpt-4o-mini";
 const DEFAULT_MAX_TOKENS: u32 = 4096;
 const DEFAULT_TEMPERATURE: f32 = 0.7;
 
@@ -131,6 +135,44 @@ struct OpenAIResponseMessage {
     role: String,
     content: Option<String>,
     tool_calls: Option<Vec<OpenAIToolCall>>,
+    #[serde(default)]
+    annotations: Vec<OpenAIAnnotation>,
+}
+
+/// OpenAI response annotation (e.g. web search citations)
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OpenAIAnnotation {
+    UrlCitation { url_citation: OpenAIUrlCitation },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIUrlCitation {
+    url: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    start_index: Option<usize>,
+    #[serde(default)]
+    end_index: Option<usize>,
+}
+
+impl OpenAIAnnotation {
+    /// Convert to an internal citation, if this annotation is one
+    fn into_citation(self) -> Option<Citation> {
+        match self {
+            OpenAIAnnotation::UrlCitation { url_citation } => Some(Citation {
+                url: Some(url_citation.url),
+                title: url_citation.title,
+                cited_text: None,
+                start_index: url_citation.start_index,
+                end_index: url_citation.end_index,
+            }),
+            OpenAIAnnotation::Other => None,
+        }
+    }
 }
 
 /// OpenAI usage stats
@@ -182,6 +224,8 @@ struct OpenAIStreamDelta {
     role: Option<String>,
     content: Option<String>,
     tool_calls: Option<Vec<OpenAIStreamToolCall>>,
+    #[serde(default)]
+    annotations: Option<Vec<OpenAIAnnotation>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -201,6 +245,7 @@ struct OpenAIStreamFunction {
 }
 
 /// OpenAI Chat Completions API provider
+#[derive(Clone)]
 pub struct OpenAIProvider {
     client: Client,
     api_key: String,
@@ -208,6 +253,7 @@ pub struct OpenAIProvider {
     system_prompt: Option<String>,
     max_tokens: u32,
     temperature: f32,
+    timeouts: ProviderTimeouts,
     base_url: String,
 }
 
@@ -215,12 +261,13 @@ impl OpenAIProvider {
     /// Create a new OpenAI provider with the given API key
     pub fn new(api_key: String) -> Self {
         Self {
-            client: Client::new(),
+            client: build_client(&ProviderTimeouts::default()),
             api_key,
             model: DEFAULT_MODEL.to_string(),
             system_prompt: None,
             max_tokens: DEFAULT_MAX_TOKENS,
             temperature: DEFAULT_TEMPERATURE,
+            timeouts: ProviderTimeouts::default(),
             base_url: OPENAI_API_URL.to_string(),
         }
     }
@@ -228,16 +275,36 @@ impl OpenAIProvider {
     /// Create a new OpenAI provider with a custom base URL (for OpenAI-compatible APIs)
     pub fn with_base_url(api_key: String, base_url: String) -> Self {
         Self {
-            client: Client::new(),
+            client: build_client(&ProviderTimeouts::default()),
             api_key,
             model: DEFAULT_MODEL.to_string(),
             system_prompt: None,
             max_tokens: DEFAULT_MAX_TOKENS,
             temperature: DEFAULT_TEMPERATURE,
+            timeouts: ProviderTimeouts::default(),
             base_url,
         }
     }
 
+    /// Set network timeouts, rebuilding the HTTP client
+    pub fn set_timeouts(&mut self, timeouts: ProviderTimeouts) {
+        self.timeouts = timeouts.validated();
+        self.client = build_client(&self.timeouts);
+    }
+
+    /// Convert an image source to an OpenAI image content part
+    fn convert_image(source: &super::types::ImageSource) -> OpenAIContentPart {
+        let url = match source {
+            super::types::ImageSource::Base64 { media_type, data } => {
+                format!("data:{};base64,{}", media_type, data)
+            }
+            super::types::ImageSource::Url { url } => url.clone(),
+        };
+        OpenAIContentPart::ImageUrl {
+            image_url: OpenAIImageUrl { url, detail: None },
+        }
+    }
+
     /// Convert internal messages to OpenAI format
     fn convert_messages(&self, messages: &[ChatMessage]) -> Vec<OpenAIMessage> {
         let mut result = Vec::new();
@@ -308,6 +375,27 @@ impl OpenAIProvider {
                                         name: None,
                                     });
                                 }
+
+                                // Tool messages can't hold images, so images a
+                                // tool returned follow as a user message
+                                let images: Vec<_> = content
+                                    .iter()
+                                    .filter_map(|b| match b {
+                                        ContentBlock::Image { source } => {
+                                            Some(Self::convert_image(source))
+                                        }
+                                        _ => None,
+                                    })
+                                    .collect();
+                                if !images.is_empty() {
+                                    result.push(OpenAIMessage {
+                                        role: "user".to_string(),
+                                        content: Some(OpenAIContent::Parts(images)),
+                                        tool_calls: None,
+                                        tool_call_id: None,
+                                        name: None,
+                                    });
+                                }
                                 continue; // Skip adding the user message
                             }
 
@@ -318,27 +406,9 @@ impl OpenAIProvider {
                                     ContentBlock::Text { text } => {
                                         Some(OpenAIContentPart::Text { text: text.clone() })
                                     }
-                                    ContentBlock::Image { source } => match source {
-                                        super::types::ImageSource::Base64 { media_type, data } => {
-                                            Some(OpenAIContentPart::ImageUrl {
-                                                image_url: OpenAIImageUrl {
-                                                    url: format!(
-                                                        "data:{};base64,{}",
-                                                        media_type, data
-                                                    ),
-                                                    detail: None,
-                                                },
-                                            })
-                                        }
-                                        super::types::ImageSource::Url { url } => {
-                                            Some(OpenAIContentPart::ImageUrl {
-                                                image_url: OpenAIImageUrl {
-                                                    url: url.clone(),
-                                                    detail: None,
-                                                },
-                                            })
-                                        }
-                                    },
+                                    ContentBlock::Image { source } => {
+                                        Some(Self::convert_image(source))
+                                    }
                                     _ => None,
                                 })
                                 .collect();
@@ -447,9 +517,9 @@ impl OpenAIProvider {
 
     /// Convert OpenAI response to internal format
     fn convert_response(&self, response: OpenAIResponse) -> ChatResponse {
-        let choice = response.choices.first();
-        let message = choice.map(|c| &c.message);
-        let finish_reason = choice.and_then(|c| c.finish_reason.as_ref());
+        let choice = response.choices.into_iter().next();
+        let finish_reason = choice.as_ref().and_then(|c| c.finish_reason.clone());
+        let message = choice.map(|c| c.message);
 
         let mut content = Vec::new();
 
@@ -461,6 +531,14 @@ impl OpenAIProvider {
                 }
             }
 
+            // Add citations
+            content.extend(
+                msg.annotations
+                    .into_iter()
+                    .filter_map(OpenAIAnnotation::into_citation)
+                    .map(ContentBlock::Citation),
+            );
+
             // Add tool calls
             if let Some(tool_calls) = &msg.tool_calls {
                 for tc in tool_calls {
@@ -520,6 +598,7 @@ impl Provider for OpenAIProvider {
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
             .json(&request)
+            .timeout(self.timeouts.request())
             .send()
             .await?;
 
@@ -561,14 +640,13 @@ impl Provider for OpenAIProvider {
             stream_options: Some(StreamOptions { include_usage: true }),
         };
 
-        let response = self
+        let builder = self
             .client
             .post(&self.base_url)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+            .json(&request);
+        let response = send_with_deadline(builder, self.timeouts.request()).await?;
 
         let status = response.status();
         if !status.is_success() {
@@ -608,16 +686,30 @@ impl Provider for OpenAIProvider {
                                     continue;
                                 }
 
-                                if let Ok(chunk) = serde_json::from_str::<OpenAIStreamChunk>(data) {
+                                if let Ok(mut chunk) = serde_json::from_str::<OpenAIStreamChunk>(data) {
                                     // First chunk - message start
                                     if chunks.is_empty() {
                                         chunks.push(Ok(ChatChunk::MessageStart {
                                             id: chunk.id.clone(),
                                             model: model.clone(),
+                                            usage: None,
                                         }));
                                     }
 
-                                    for choice in &chunk.choices {
+                                    // With include_usage, the totals arrive in a final chunk with no choices
+                                    if chunk.choices.is_empty() {
+                                        if let Some(u) = &chunk.usage {
+                                            chunks.push(Ok(ChatChunk::MessageDelta {
+                                                stop_reason: None,
+                                                usage: Some(Usage {
+                                                    input_tokens: u.prompt_tokens,
+                                                    output_tokens: u.completion_tokens,
+                                                }),
+                                            }));
+                                        }
+                                    }
+
+                                    for choice in &mut chunk.choices {
                                         // Handle text content
                                         if let Some(content) = &choice.delta.content {
                                             if !content.is_empty() {
@@ -630,6 +722,19 @@ impl Provider for OpenAIProvider {
                                             }
                                         }
 
+                                        // Handle citations on the text block
+                                        if let Some(annotations) = choice.delta.annotations.take() {
+                                            for citation in annotations
+                                                .into_iter()
+                                                .filter_map(OpenAIAnnotation::into_citation)
+                                            {
+                                                chunks.push(Ok(ChatChunk::ContentBlockDelta {
+                                                    index: 0,
+                                                    delta: ContentDelta::CitationDelta { citation },
+                                                }));
+                                            }
+                                        }
+
                                         // Handle tool calls
                                         if let Some(tool_calls) = &choice.delta.tool_calls {
                                             for tc in tool_calls {
@@ -740,6 +845,14 @@ impl Provider for OpenAIProvider {
         &self.model
     }
 
+    fn context_window(&self) -> u32 {
+        match self.model.as_str() {
+            "gpt-4" => 8_192,
+            "gpt-3.5-turbo" => 16_385,
+            _ => 128_000,
+        }
+    }
+
     fn set_system_prompt(&mut self, prompt: Option<String>) {
         self.system_prompt = prompt;
     }
@@ -763,4 +876,12 @@ impl Provider for OpenAIProvider {
     fn temperature(&self) -> f32 {
         self.temperature
     }
+
+    fn endpoint(&self) -> &str {
+        &self.base_url
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Provider> {
+        Box::new(self.clone())
+    }
 }
@@ -0,0 +1,64 @@
+//! Model pricing
+//!
+//! Approximate list prices for the models Open Sesh offers, used to show
+//! what a conversation costs. Prices change; treat the result as an
+//! estimate, not a bill.
+
+use super::Usage;
+
+/// Price of a model in US dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+/// Prices by model name prefix; more specific prefixes come first
+const MODEL_PRICES: &[(&str, f64, f64)] = &[
+    ("claude-opus-4-5", 5.0, 25.0),
+    ("claude-opus-4", 15.0, 75.0),
+    ("claude-3-opus", 15.0, 75.0),
+    ("claude-sonnet-4", 3.0, 15.0),
+    ("claude-3-7-sonnet", 3.0, 15.0),
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-haiku-4", 1.0, 5.0),
+    ("claude-3-5-haiku", 0.8, 4.0),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("gpt-4o-mini", 0.15, 0.6),
+    ("gpt-4o", 2.5, 10.0),
+    ("gpt-4.1-nano", 0.1, 0.4),
+    ("gpt-4.1-mini", 0.4, 1.6),
+    ("gpt-4.1", 2.0, 8.0),
+    ("gpt-4-turbo", 10.0, 30.0),
+    ("gpt-4", 30.0, 60.0),
+    ("gpt-3.5-turbo", 0.5, 1.5),
+    ("gpt-5-nano", 0.05, 0.4),
+    ("gpt-5-mini", 0.25, 2.0),
+    ("gpt-5", 1.25, 10.0),
+    ("o1-mini", 1.1, 4.4),
+    ("o1", 15.0, 60.0),
+    ("o3-mini", 1.1, 4.4),
+    ("o3", 2.0, 8.0),
+    ("o4-mini", 1.1, 4.4),
+];
+
+/// Look up the price of a model, if it is known
+pub fn model_pricing(model: &str) -> Option<ModelPricing> {
+    MODEL_PRICES
+        .iter()
+        .find(|(prefix, _, _)| model.starts_with(prefix))
+        .map(|&(_, input, output)| ModelPricing {
+            input_per_million: input,
+            output_per_million: output,
+        })
+}
+
+/// Estimate the cost of a response in US dollars, if the model's price is known
+pub fn estimate_cost(model: &str, usage: &Usage) -> Option<f64> {
+    let pricing = model_pricing(model)?;
+    Some(
+        (usage.input_tokens as f64 * pricing.input_per_million
+            + usage.output_tokens as f64 * pricing.output_per_million)
+            / 1_000_000.0,
+    )
+}
@@ -0,0 +1,305 @@
+//! Per-provider request scheduling
+//!
+//! Agentic loops can fire many requests in a short burst. The scheduler queues
+//! calls so each provider sees no more than its configured number of requests
+//! in flight or per minute, rather than answering the burst with 429s.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Window over which `requests_per_minute` is counted
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// How often a queued request checks whether its position changed
+const QUEUE_STATUS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Limits on how hard a single provider is called
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestLimits {
+    /// Maximum requests in flight at once
+    pub max_concurrent: usize,
+    /// Maximum requests started per minute; 0 for no limit
+    pub requests_per_minute: u32,
+}
+
+impl RequestLimits {
+    /// Ensure at least one request can run at a time
+    pub fn validated(self) -> Self {
+        Self {
+            max_concurrent: self.max_concurrent.max(1),
+            ..self
+        }
+    }
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 4,
+            requests_per_minute: 50,
+        }
+    }
+}
+
+/// Where a waiting request stands, reported while it is held back
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueueStatus {
+    pub provider: String,
+    /// Place in the provider's queue, starting at 1; 0 once the request is
+    /// waiting only for the rate limit
+    pub position: usize,
+    /// Seconds until the rate limit lets the request start
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limited_secs: Option<u64>,
+}
+
+/// A slot to send one request; the slot is released when this is dropped
+#[derive(Debug)]
+pub struct RequestPermit {
+    _slot: OwnedSemaphorePermit,
+}
+
+/// Requests for one provider
+#[derive(Debug)]
+struct ProviderQueue {
+    limits: RequestLimits,
+    slots: Arc<Semaphore>,
+    /// Tickets of requests waiting for a slot, oldest first
+    waiting: Mutex<VecDeque<u64>>,
+    next_ticket: Mutex<u64>,
+    /// Start times of requests within the last `RATE_WINDOW`
+    started: Mutex<VecDeque<Instant>>,
+}
+
+impl ProviderQueue {
+    fn new(limits: RequestLimits) -> Self {
+        Self {
+            limits,
+            slots: Arc::new(Semaphore::new(limits.max_concurrent)),
+            waiting: Mutex::new(VecDeque::new()),
+            next_ticket: Mutex::new(0),
+            started: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn enqueue(&self) -> u64 {
+        let mut next = self.next_ticket.lock().unwrap();
+        let ticket = *next;
+        *next += 1;
+        self.waiting.lock().unwrap().push_back(ticket);
+        ticket
+    }
+
+    fn dequeue(&self, ticket: u64) {
+        self.waiting.lock().unwrap().retain(|t| *t != ticket);
+    }
+
+    fn position(&self, ticket: u64) -> usize {
+        let waiting = self.waiting.lock().unwrap();
+        waiting
+            .iter()
+            .position(|t| *t == ticket)
+            .map_or(0, |i| i + 1)
+    }
+
+    /// Record a request starting at `now` if the rate limit allows it
+    ///
+    /// # Returns
+    /// `None` if the request may start, or how long to wait before trying again
+    fn try_start(&self, now: Instant) -> Option<Duration> {
+        let limit = self.limits.requests_per_minute as usize;
+        if limit == 0 {
+            return None;
+        }
+
+        let mut started = self.started.lock().unwrap();
+        while started
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= RATE_WINDOW)
+        {
+            started.pop_front();
+        }
+        if started.len() < limit {
+            started.push_back(now);
+            return None;
+        }
+        started
+            .front()
+            .map(|oldest| RATE_WINDOW.saturating_sub(now.duration_since(*oldest)))
+    }
+}
+
+/// Removes a request from the queue when it gets a slot or is cancelled
+struct QueueTicket<'a> {
+    queue: &'a ProviderQueue,
+    ticket: u64,
+}
+
+impl Drop for QueueTicket<'_> {
+    fn drop(&mut self) {
+        self.queue.dequeue(self.ticket);
+    }
+}
+
+/// Queues provider requests to keep within each provider's limits
+#[derive(Debug, Default)]
+pub struct RequestScheduler {
+    limits: Mutex<HashMap<String, RequestLimits>>,
+    queues: Mutex<HashMap<String, Arc<ProviderQueue>>>,
+}
+
+impl RequestScheduler {
+    /// Replace the limits, keyed by provider name
+    ///
+    /// Providers without an entry use the default limits. Requests already
+    /// running keep their slots; new ones are counted against the new limits.
+    pub fn set_limits(&self, limits: HashMap<String, RequestLimits>) {
+        let mut queues = self.queues.lock().unwrap();
+        queues.retain(|name, queue| {
+            queue.limits == limits.get(name).copied().unwrap_or_default().validated()
+        });
+        *self.limits.lock().unwrap() = limits;
+    }
+
+    fn queue(&self, provider: &str) -> Arc<ProviderQueue> {
+        let limits = self
+            .limits
+            .lock()
+            .unwrap()
+            .get(provider)
+            .copied()
+            .unwrap_or_default()
+            .validated();
+        self.queues
+            .lock()
+            .unwrap()
+            .entry(provider.to_string())
+            .or_insert_with(|| Arc::new(ProviderQueue::new(limits)))
+            .clone()
+    }
+
+    /// Wait until a request to `provider` may be sent
+    ///
+    /// Requests are let through in the order they arrive.
+    ///
+    /// # Arguments
+    /// * `provider` - Name of the provider the request is for
+    /// * `on_wait` - Called when the request is held back and whenever its
+    ///   queue position changes
+    pub async fn acquire(&self, provider: &str, on_wait: impl Fn(QueueStatus)) -> RequestPermit {
+        let queue = self.queue(provider);
+        let status = |position, rate_limited_secs| QueueStatus {
+            provider: provider.to_string(),
+            position,
+            rate_limited_secs,
+        };
+
+        let slot = match queue.slots.clone().try_acquire_owned() {
+            Ok(slot) => slot,
+            Err(_) => {
+                let ticket = QueueTicket {
+                    queue: &queue,
+                    ticket: queue.enqueue(),
+                };
+                let acquire = queue.slots.clone().acquire_owned();
+                tokio::pin!(acquire);
+
+                let mut position = queue.position(ticket.ticket);
+                on_wait(status(position, None));
+                loop {
+                    tokio::select! {
+                        slot = &mut acquire => {
+                            break slot.expect("provider semaphores are never closed");
+                        }
+                        _ = tokio::time::sleep(QUEUE_STATUS_INTERVAL) => {
+                            let current = queue.position(ticket.ticket);
+                            if current != position {
+                                position = current;
+                                on_wait(status(position, None));
+                            }
+                        }
+                    }
+                }
+            }
+        };
+
+        while let Some(wait) = queue.try_start(Instant::now()) {
+            on_wait(status(0, Some(wait.as_secs_f64().ceil() as u64)));
+            tokio::time::sleep(wait).await;
+        }
+
+        RequestPermit { _slot: slot }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_requests_queue_beyond_concurrency_limit() {
+        let scheduler = Arc::new(RequestScheduler::default());
+        scheduler.set_limits(HashMap::from([(
+            "anthropic".to_string(),
+            RequestLimits {
+                max_concurrent: 1,
+                requests_per_minute: 0,
+            },
+        )]));
+
+        let first = scheduler
+            .acquire("anthropic", |_| panic!("should not wait"))
+            .await;
+
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+        let waiter = tokio::spawn({
+            let scheduler = scheduler.clone();
+            let statuses = statuses.clone();
+            async move {
+                scheduler
+                    .acquire("anthropic", |status| statuses.lock().unwrap().push(status))
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        // Other providers are not held up
+        let _other = scheduler
+            .acquire("openai", |_| panic!("should not wait"))
+            .await;
+
+        drop(first);
+        waiter.await.unwrap();
+        assert_eq!(
+            statuses.lock().unwrap().as_slice(),
+            &[QueueStatus {
+                provider: "anthropic".to_string(),
+                position: 1,
+                rate_limited_secs: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_window() {
+        let queue = ProviderQueue::new(RequestLimits {
+            max_concurrent: 4,
+            requests_per_minute: 2,
+        });
+        let start = Instant::now();
+
+        assert_eq!(queue.try_start(start), None);
+        assert_eq!(queue.try_start(start + Duration::from_secs(10)), None);
+        assert_eq!(
+            queue.try_start(start + Duration::from_secs(20)),
+            Some(Duration::from_secs(40))
+        );
+        assert_eq!(queue.try_start(start + Duration::from_secs(60)), None);
+    }
+}
@@ -4,6 +4,7 @@
 //! including message structures, tool definitions, and response types.
 
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Role of a message in the conversation
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -36,6 +37,36 @@ pub enum ContentBlock {
         #[serde(skip_serializing_if = "Option::is_none")]
         is_error: Option<bool>,
     },
+    /// Model reasoning (extended thinking)
+    Thinking {
+        thinking: String,
+        /// Provider signature that must be sent back unchanged with the block
+        #[serde(skip_serializing_if = "Option::is_none")]
+        signature: Option<String>,
+    },
+    /// Reasoning the provider has encrypted; opaque but must be sent back as-is
+    RedactedThinking {
+        data: String,
+    },
+    /// A source the model cited for the preceding text (web search, grounding)
+    Citation(Citation),
+}
+
+/// A cited source attached to a response
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Citation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The passage of the source that supports the text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cited_text: Option<String>,
+    /// Character range of the response text the citation applies to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_index: Option<usize>,
 }
 
 /// Image source for multi-modal messages
@@ -198,6 +229,29 @@ impl ChatResponse {
             .collect()
     }
 
+    /// Extract thinking (reasoning) content from the response
+    pub fn thinking(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Thinking { thinking, .. } => Some(thinking.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("")
+    }
+
+    /// Extract citations from the response
+    pub fn citations(&self) -> Vec<Citation> {
+        self.content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Citation(citation) => Some(citation.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Check if the response contains tool calls
     pub fn has_tool_calls(&self) -> bool {
         self.content.iter().any(|block| matches!(block, ContentBlock::ToolUse { .. }))
@@ -212,6 +266,8 @@ pub enum ChatChunk {
     MessageStart {
         id: String,
         model: String,
+        /// Tokens counted so far, usually just the input
+        usage: Option<Usage>,
     },
     /// Content block started
     ContentBlockStart {
@@ -248,6 +304,9 @@ pub enum ChatChunk {
 pub enum ContentDelta {
     TextDelta { text: String },
     InputJsonDelta { partial_json: String },
+    ThinkingDelta { thinking: String },
+    SignatureDelta { signature: String },
+    CitationDelta { citation: Citation },
 }
 
 /// Provider configuration
@@ -259,6 +318,54 @@ pub struct ProviderConfig {
     pub base_url: Option<String>,
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
+    #[serde(default)]
+    pub timeouts: Option<ProviderTimeouts>,
+}
+
+/// Network timeouts for provider HTTP requests, in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderTimeouts {
+    /// Maximum time to establish a connection
+    pub connect_secs: u64,
+    /// Maximum time between bytes received; catches streams that stall
+    pub read_secs: u64,
+    /// Overall deadline for a request. For streaming requests this covers
+    /// the time until the response starts; the read timeout covers the rest.
+    pub request_secs: u64,
+}
+
+impl ProviderTimeouts {
+    pub fn connect(&self) -> Duration {
+        Duration::from_secs(self.connect_secs)
+    }
+
+    pub fn read(&self) -> Duration {
+        Duration::from_secs(self.read_secs)
+    }
+
+    pub fn request(&self) -> Duration {
+        Duration::from_secs(self.request_secs)
+    }
+
+    /// Ensure every timeout is at least one second
+    pub fn validated(self) -> Self {
+        Self {
+            connect_secs: self.connect_secs.max(1),
+            read_secs: self.read_secs.max(1),
+            request_secs: self.request_secs.max(1),
+        }
+    }
+}
+
+impl Default for ProviderTimeouts {
+    fn default() -> Self {
+        Self {
+            connect_secs: 10,
+            read_secs: 60,
+            request_secs: 300,
+        }
+    }
 }
 
 /// Chat request parameters
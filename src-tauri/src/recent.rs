@@ -0,0 +1,193 @@
+//! Recently used files
+//!
+//! Files the user opens and edits are remembered with how often and how
+//! recently they were used. The resulting frecency score lists recent files
+//! and ranks the quick-open and file search results, so the files someone
+//! works on come first. The list is saved as JSON in the app data directory.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// File (relative to the app data directory) holding the recent files
+pub const RECENT_FILES_FILE: &str = "recent_files.json";
+
+/// Most files remembered; the least frecent are forgotten first
+pub const MAX_RECENT_FILES: usize = 500;
+
+/// How a file was used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileActivity {
+    Open,
+    Edit,
+}
+
+/// A file that was used recently
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentFile {
+    pub path: String,
+    pub opens: u32,
+    pub edits: u32,
+    /// Unix timestamp (seconds)
+    pub last_used: u64,
+}
+
+impl RecentFile {
+    /// How often and how recently the file was used, as of `now`
+    ///
+    /// Uses count for more the more recent the last one was, and edits count
+    /// twice as much as opens.
+    pub fn frecency(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_used);
+        let recency = match age {
+            a if a < 60 * 60 => 100.0,
+            a if a < 24 * 60 * 60 => 70.0,
+            a if a < 7 * 24 * 60 * 60 => 50.0,
+            a if a < 30 * 24 * 60 * 60 => 30.0,
+            _ => 10.0,
+        };
+        recency * f64::from(self.opens + 2 * self.edits)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The recently used files
+#[derive(Debug, Default)]
+pub struct RecentFiles {
+    files: Mutex<Vec<RecentFile>>,
+    /// Where the list is saved; `None` keeps it in memory only
+    path: Option<PathBuf>,
+}
+
+impl RecentFiles {
+    /// Create an empty, in-memory list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the list from its file, starting empty if it doesn't exist or is invalid
+    pub fn load(path: &Path) -> Self {
+        let files = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("Ignoring invalid recent files {}: {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self {
+            files: Mutex::new(files),
+            path: Some(path.to_path_buf()),
+        }
+    }
+
+    fn files(&self) -> MutexGuard<'_, Vec<RecentFile>> {
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, files: &[RecentFile]) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(path, serde_json::to_string(files).unwrap_or_default()));
+        if let Err(e) = result {
+            log::warn!("Failed to save recent files: {}", e);
+        }
+    }
+
+    /// Record that a file was opened or edited
+    pub fn record(&self, path: &str, activity: FileActivity) {
+        let mut files = self.files();
+        let now = now();
+        let index = match files.iter().position(|f| f.path == path) {
+            Some(index) => index,
+            None => {
+                if files.len() >= MAX_RECENT_FILES {
+                    let least = files
+                        .iter()
+                        .enumerate()
+                        .min_by(|(_, a), (_, b)| a.frecency(now).total_cmp(&b.frecency(now)))
+                        .map(|(i, _)| i);
+                    if let Some(least) = least {
+                        files.remove(least);
+                    }
+                }
+                files.push(RecentFile {
+                    path: path.to_string(),
+                    opens: 0,
+                    edits: 0,
+                    last_used: now,
+                });
+                files.len() - 1
+            }
+        };
+
+        let file = &mut files[index];
+        match activity {
+            FileActivity::Open => file.opens = file.opens.saturating_add(1),
+            FileActivity::Edit => file.edits = file.edits.saturating_add(1),
+        }
+        file.last_used = now;
+        self.save(&files);
+    }
+
+    /// The most recently used files, most recent first
+    pub fn recent(&self, limit: usize) -> Vec<RecentFile> {
+        let mut files = self.files().clone();
+        files.sort_by_key(|f| std::cmp::Reverse(f.last_used));
+        files.truncate(limit);
+        files
+    }
+
+    /// Frecency of every remembered file, keyed by path
+    pub fn scores(&self) -> HashMap<String, f64> {
+        let now = now();
+        self.files()
+            .iter()
+            .map(|f| (f.path.clone(), f.frecency(now)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_recent_files_rank_by_frecency() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(RECENT_FILES_FILE);
+        let recent = RecentFiles::load(&path);
+        recent.record("/p/a.rs", FileActivity::Open);
+        recent.record("/p/b.rs", FileActivity::Open);
+        recent.record("/p/b.rs", FileActivity::Edit);
+
+        let reloaded = RecentFiles::load(&path);
+        let scores = reloaded.scores();
+        assert_eq!(scores["/p/a.rs"], 100.0);
+        assert_eq!(scores["/p/b.rs"], 300.0);
+        assert_eq!(reloaded.recent(10).len(), 2);
+
+        let old = RecentFile {
+            path: "/p/c.rs".to_string(),
+            opens: 1,
+            edits: 0,
+            last_used: 0,
+        };
+        assert_eq!(old.frecency(60 * 24 * 60 * 60), 10.0);
+    }
+}
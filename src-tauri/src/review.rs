@@ -0,0 +1,241 @@
+//! AI code review of diffs
+//!
+//! A diff is split into chunks that fit comfortably in one request, each
+//! chunk is sent to the provider with a review-focused system prompt, and the
+//! findings the model returns as JSON are collected so the frontend can show
+//! them as annotations on the changed lines.
+
+use serde::{Deserialize, Serialize};
+
+use crate::providers::scheduler::RequestScheduler;
+use crate::providers::{ChatMessage, Provider, ProviderError};
+
+/// Most characters of diff sent in one review request
+pub const MAX_REVIEW_CHUNK_CHARS: usize = 40_000;
+
+const REVIEW_SYSTEM_PROMPT: &str = "You are an experienced code reviewer. Review the diff you \
+are given for bugs, security problems, performance issues, unclear code and missing tests. \
+Only comment on the changed lines and their immediate effects; don't restate what the change does \
+and don't praise it. Each line of the diff starts with its line number in the new version of the \
+file, if it has one.
+
+Reply with only a JSON array of findings, each an object with these fields:
+- \"file\": path of the file, as in the diff
+- \"line\": line number in the new version of the file the finding is about, or null
+- \"severity\": \"error\" for bugs and security problems, \"warning\" for likely problems, \
+\"info\" for suggestions
+- \"comment\": the finding and how to fix it, in one or two sentences
+Reply with [] if there is nothing worth pointing out.";
+
+/// How serious a review finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewSeverity {
+    Error,
+    Warning,
+    #[serde(other)]
+    Info,
+}
+
+/// A comment on a changed line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewFinding {
+    pub file: String,
+    /// Line in the new version of the file, if the finding is about one line
+    #[serde(default)]
+    pub line: Option<u32>,
+    pub severity: ReviewSeverity,
+    pub comment: String,
+}
+
+/// Parse the start of the new file's line range from a hunk header
+/// (`@@ -12,5 +14,7 @@`)
+fn hunk_new_start(header: &str) -> Option<u32> {
+    let new_range = header
+        .split_whitespace()
+        .find(|part| part.starts_with('+'))?;
+    new_range[1..].split(',').next()?.parse().ok()
+}
+
+/// Prefix each line of a file's diff with its line number in the new version
+fn number_lines(file_diff: &str) -> String {
+    let mut numbered = String::with_capacity(file_diff.len() * 2);
+    let mut new_line: Option<u32> = None;
+
+    for line in file_diff.lines() {
+        if line.starts_with("@@") {
+            new_line = hunk_new_start(line);
+            numbered.push_str(line);
+        } else if let Some(number) = new_line.filter(|_| !line.starts_with('-')) {
+            numbered.push_str(&format!("{:>5} {}", number, line));
+            new_line = Some(number + 1);
+        } else if new_line.is_some() {
+            numbered.push_str(&format!("      {}", line));
+        } else {
+            numbered.push_str(line);
+        }
+        numbered.push('\n');
+    }
+    numbered
+}
+
+/// Split a file's diff at hunk boundaries into pieces of at most `max_chars`
+///
+/// Each piece repeats the file header so the reviewer knows which file it is.
+/// A single hunk larger than `max_chars` is kept whole.
+fn split_file_diff(file_diff: &str, max_chars: usize) -> Vec<String> {
+    if file_diff.len() <= max_chars {
+        return vec![file_diff.to_string()];
+    }
+
+    let header_end = file_diff.find("\n@@").map_or(file_diff.len(), |i| i + 1);
+    let (header, body) = file_diff.split_at(header_end);
+    let mut pieces = Vec::new();
+    let mut current = header.to_string();
+
+    for hunk in body
+        .split_inclusive('\n')
+        .fold(Vec::<String>::new(), |mut hunks, line| {
+            match hunks.last_mut() {
+                Some(hunk) if !line.starts_with("@@") => hunk.push_str(line),
+                _ => hunks.push(line.to_string()),
+            }
+            hunks
+        })
+    {
+        if current.len() > header.len() && current.len() + hunk.len() > max_chars {
+            pieces.push(std::mem::replace(&mut current, header.to_string()));
+        }
+        current.push_str(&hunk);
+    }
+    pieces.push(current);
+    pieces
+}
+
+/// Split a diff into chunks of at most about `max_chars`, with line numbers
+///
+/// Whole files are kept together where they fit; larger files are split
+/// between hunks.
+pub fn chunk_diff(diff: &str, max_chars: usize) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    for line in diff.split_inclusive('\n') {
+        match files.last_mut() {
+            Some(file) if !line.starts_with("diff --git ") => file.push_str(line),
+            _ => files.push(line.to_string()),
+        }
+    }
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for piece in files
+        .iter()
+        .flat_map(|file| split_file_diff(&number_lines(file), max_chars))
+    {
+        if !current.is_empty() && current.len() + piece.len() > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        current.push_str(&piece);
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Parse the JSON array of findings from a review reply
+///
+/// Text around the array, such as a Markdown code fence, is ignored.
+pub fn parse_findings(reply: &str) -> Result<Vec<ReviewFinding>, ProviderError> {
+    let (Some(start), Some(end)) = (reply.find('['), reply.rfind(']')) else {
+        return Err(ProviderError::InvalidResponse(
+            "Review reply contained no JSON array".to_string(),
+        ));
+    };
+    if end < start {
+        return Err(ProviderError::InvalidResponse(
+            "Review reply contained no JSON array".to_string(),
+        ));
+    }
+    Ok(serde_json::from_str(&reply[start..=end])?)
+}
+
+/// Review a diff, chunk by chunk
+///
+/// # Returns
+/// The findings of every chunk, in diff order
+pub async fn review_diff(
+    provider: &dyn Provider,
+    scheduler: &RequestScheduler,
+    diff: &str,
+) -> Result<Vec<ReviewFinding>, ProviderError> {
+    let mut findings = Vec::new();
+
+    for chunk in chunk_diff(diff, MAX_REVIEW_CHUNK_CHARS) {
+        let messages = vec![
+            ChatMessage::system(REVIEW_SYSTEM_PROMPT),
+            ChatMessage::user(format!("Review this diff:\n\n```diff\n{}```", chunk)),
+        ];
+        let response = {
+            let _permit = scheduler.acquire(provider.name(), |_| {}).await;
+            provider.chat(messages, None).await?
+        };
+        findings.extend(parse_findings(&response.text())?);
+    }
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/a.rs b/src/a.rs
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    run();
++    run(true);
+ }
+@@ -10,2 +10,3 @@
+ fn run() {
++    todo!();
+ }
+diff --git a/src/b.rs b/src/b.rs
+--- a/src/b.rs
++++ b/src/b.rs
+@@ -4 +4 @@
+-old
++new
+";
+
+    #[test]
+    fn test_chunk_diff_numbers_new_lines() {
+        let chunks = chunk_diff(DIFF, MAX_REVIEW_CHUNK_CHARS);
+        assert_eq!(chunks.len(), 1);
+        assert!(
+            chunks[0].contains("\n    1  fn main() {\n      -    run();\n    2 +    run(true);\n")
+        );
+        assert!(chunks[0].contains("\n   11 +    todo!();\n"));
+        assert!(chunks[0].contains("\n    4 +new\n"));
+
+        // Small chunks split between files, then between hunks
+        let chunks = chunk_diff(DIFF, 200);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[1].starts_with(
+            "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -10,2"
+        ));
+        assert!(chunks[2].starts_with("diff --git a/src/b.rs"));
+    }
+
+    #[test]
+    fn test_parse_findings() {
+        let reply = "```json\n[{\"file\": \"src/a.rs\", \"line\": 2, \"severity\": \"error\", \
+                     \"comment\": \"Missing check\"}, {\"file\": \"src/b.rs\", \"line\": null, \
+                     \"severity\": \"nit\", \"comment\": \"Rename\"}]\n```";
+        let findings = parse_findings(reply).unwrap();
+        assert_eq!(findings[0].line, Some(2));
+        assert_eq!(findings[0].severity, ReviewSeverity::Error);
+        assert_eq!(findings[1].severity, ReviewSeverity::Info);
+        assert!(parse_findings("Looks good to me").is_err());
+    }
+}
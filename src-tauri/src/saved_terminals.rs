@@ -0,0 +1,229 @@
+//! Terminals saved across restarts
+//!
+//! Each open terminal's working directory, shell, title and the tail of its
+//! output are remembered, so the terminals can be relaunched where they were
+//! after the app restarts. The working directory and title follow the
+//! escape sequences shells send when they change (OSC 7, and OSC 0 or 2). The
+//! list is saved as JSON in the app data directory.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+
+/// File (relative to the app data directory) holding the saved terminals
+pub const TERMINALS_FILE: &str = "terminals.json";
+
+/// Most output kept for each terminal, in bytes
+pub const MAX_SCROLLBACK_BYTES: usize = 64 * 1024;
+
+/// A terminal to relaunch after a restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedTerminal {
+    pub id: String,
+    pub cwd: String,
+    pub shell: String,
+    pub title: Option<String>,
+    pub cols: u16,
+    pub rows: u16,
+    /// The end of the terminal's output, escape sequences included
+    #[serde(default)]
+    pub scrollback: String,
+}
+
+/// Append terminal output to a scrollback, keeping only its last
+/// `MAX_SCROLLBACK_BYTES`
+pub fn append_scrollback(scrollback: &mut String, data: &str) {
+    scrollback.push_str(data);
+    if scrollback.len() > MAX_SCROLLBACK_BYTES {
+        let mut start = scrollback.len() - MAX_SCROLLBACK_BYTES;
+        while !scrollback.is_char_boundary(start) {
+            start += 1;
+        }
+        scrollback.drain(..start);
+    }
+}
+
+/// Decode `%XX` escapes in a URL path
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Working directory and title changes announced in terminal output
+#[derive(Debug, Default, PartialEq)]
+pub struct TerminalUpdates {
+    /// From OSC 7, `ESC ] 7 ; file://host/path BEL`
+    pub cwd: Option<String>,
+    /// From OSC 0 or 2, `ESC ] 0 ; title BEL`
+    pub title: Option<String>,
+}
+
+/// Find the last working directory and title a chunk of output announces
+///
+/// Sequences split across chunks are missed; the next prompt sends them again.
+pub fn terminal_updates(data: &str) -> TerminalUpdates {
+    let mut updates = TerminalUpdates::default();
+    for sequence in data.split("\x1b]").skip(1) {
+        // Ended by BEL or ST (`ESC \`)
+        let Some(end) = sequence.find(['\x07', '\x1b']) else {
+            continue;
+        };
+        let Some((code, value)) = sequence[..end].split_once(';') else {
+            continue;
+        };
+        match code {
+            "0" | "2" => updates.title = Some(value.to_string()),
+            "7" => {
+                let path = value
+                    .strip_prefix("file://")
+                    .and_then(|rest| rest.find('/').map(|slash| &rest[slash..]));
+                if let Some(path) = path {
+                    updates.cwd = Some(percent_decode(path));
+                }
+            }
+            _ => {}
+        }
+    }
+    updates
+}
+
+/// The terminals to relaunch after a restart
+#[derive(Debug, Default)]
+pub struct SavedTerminals {
+    terminals: Mutex<Vec<SavedTerminal>>,
+    /// Where the list is saved; `None` keeps it in memory only
+    path: Option<PathBuf>,
+}
+
+impl SavedTerminals {
+    /// Create an empty, in-memory list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the list from its file, starting empty if it doesn't exist or is invalid
+    pub fn load(path: &Path) -> Self {
+        let terminals = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("Ignoring invalid saved terminals {}: {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self {
+            terminals: Mutex::new(terminals),
+            path: Some(path.to_path_buf()),
+        }
+    }
+
+    fn terminals(&self) -> MutexGuard<'_, Vec<SavedTerminal>> {
+        self.terminals.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, terminals: &[SavedTerminal]) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(path, serde_json::to_string(terminals).unwrap_or_default()));
+        if let Err(e) = result {
+            log::warn!("Failed to save terminals: {}", e);
+        }
+    }
+
+    /// Every saved terminal, in the order they were opened
+    pub fn all(&self) -> Vec<SavedTerminal> {
+        self.terminals().clone()
+    }
+
+    /// Save a terminal, replacing any saved with the same ID
+    pub fn insert(&self, terminal: SavedTerminal) {
+        let mut terminals = self.terminals();
+        match terminals.iter_mut().find(|t| t.id == terminal.id) {
+            Some(saved) => *saved = terminal,
+            None => terminals.push(terminal),
+        }
+        self.save(&terminals);
+    }
+
+    /// Change a saved terminal; terminals that aren't saved are left alone
+    pub fn update(&self, id: &str, change: impl FnOnce(&mut SavedTerminal)) {
+        let mut terminals = self.terminals();
+        if let Some(terminal) = terminals.iter_mut().find(|t| t.id == id) {
+            change(terminal);
+            self.save(&terminals);
+        }
+    }
+
+    /// Forget a terminal that was closed
+    pub fn remove(&self, id: &str) {
+        let mut terminals = self.terminals();
+        let count = terminals.len();
+        terminals.retain(|t| t.id != id);
+        if terminals.len() != count {
+            self.save(&terminals);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_saved_terminals_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(TERMINALS_FILE);
+        let saved = SavedTerminals::load(&path);
+        saved.insert(SavedTerminal {
+            id: "t1".to_string(),
+            cwd: "/p".to_string(),
+            shell: "/bin/zsh".to_string(),
+            title: None,
+            cols: 80,
+            rows: 24,
+            scrollback: String::new(),
+        });
+        saved.update("t1", |t| append_scrollback(&mut t.scrollback, "$ ls\r\n"));
+        saved.update("gone", |t| t.cols = 1);
+
+        let reloaded = SavedTerminals::load(&path).all();
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded[0].scrollback, "$ ls\r\n");
+        saved.remove("t1");
+        assert!(SavedTerminals::load(&path).all().is_empty());
+
+        let mut scrollback = "é".repeat(MAX_SCROLLBACK_BYTES);
+        append_scrollback(&mut scrollback, "end");
+        assert!(scrollback.len() <= MAX_SCROLLBACK_BYTES && scrollback.ends_with("éend"));
+
+        let output = "\x1b]0;vim main.rs\x07text\x1b]7;file://host/home/me/My%20Project\x1b\\$ ";
+        assert_eq!(
+            terminal_updates(output),
+            TerminalUpdates {
+                cwd: Some("/home/me/My Project".to_string()),
+                title: Some("vim main.rs".to_string()),
+            }
+        );
+        assert_eq!(terminal_updates("\x1b]7;file://host"), TerminalUpdates::default());
+    }
+}
@@ -0,0 +1,330 @@
+//! Project templates
+//!
+//! New projects are created from a template: a set of files whose paths and
+//! contents may contain `{{variable}}` placeholders, filled in the same way
+//! as saved prompts. A few templates are built in; users add their own as
+//! directories under `templates/` in the app data directory.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::prompts::{prompt_variables, render_prompt, PromptError};
+
+/// Directory (relative to the app data directory) holding user templates
+pub const TEMPLATES_DIR: &str = "templates";
+
+/// Errors that can occur while creating a project from a template
+#[derive(Debug, Error)]
+pub enum ScaffoldError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Prompt(#[from] PromptError),
+
+    #[error("Template not found: {0}")]
+    TemplateNotFound(String),
+
+    #[error("Destination is not empty: {0}")]
+    DestinationNotEmpty(String),
+
+    #[error("Template file path must stay inside the project: {0}")]
+    UnsafePath(String),
+}
+
+/// A template a project can be created from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub description: Option<String>,
+    /// Variables used by the template, besides the defaults
+    pub variables: Vec<String>,
+    pub builtin: bool,
+}
+
+struct BuiltinTemplate {
+    name: &'static str,
+    description: &'static str,
+    files: &'static [(&'static str, &'static str)],
+}
+
+const BUILTIN_TEMPLATES: &[BuiltinTemplate] = &[
+    BuiltinTemplate {
+        name: "rust-bin",
+        description: "Rust command-line program",
+        files: &[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"{{name}}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+                 [dependencies]\n",
+            ),
+            (
+                "src/main.rs",
+                "fn main() {\n    println!(\"Hello from {{name}}!\");\n}\n",
+            ),
+            (".gitignore", "/target\n"),
+        ],
+    },
+    BuiltinTemplate {
+        name: "vite-react",
+        description: "React app built with Vite",
+        files: &[
+            (
+                "package.json",
+                "{\n  \"name\": \"{{name}}\",\n  \"private\": true,\n  \"version\": \"0.1.0\",\n  \
+                 \"type\": \"module\",\n  \"scripts\": {\n    \"dev\": \"vite\",\n    \
+                 \"build\": \"vite build\",\n    \"preview\": \"vite preview\"\n  },\n  \
+                 \"dependencies\": {\n    \"react\": \"^18.3.1\",\n    \"react-dom\": \"^18.3.1\"\n  \
+                 },\n  \"devDependencies\": {\n    \"@vitejs/plugin-react\": \"^4.3.1\",\n    \
+                 \"vite\": \"^5.4.0\"\n  }\n}\n",
+            ),
+            (
+                "index.html",
+                "<!doctype html>\n<html lang=\"en\">\n  <head>\n    <meta charset=\"UTF-8\" />\n    \
+                 <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\" />\n    \
+                 <title>{{name}}</title>\n  </head>\n  <body>\n    <div id=\"root\"></div>\n    \
+                 <script type=\"module\" src=\"/src/main.jsx\"></script>\n  </body>\n</html>\n",
+            ),
+            (
+                "vite.config.js",
+                "import { defineConfig } from 'vite'\nimport react from '@vitejs/plugin-react'\n\n\
+                 export default defineConfig({\n  plugins: [react()],\n})\n",
+            ),
+            (
+                "src/main.jsx",
+                "import { StrictMode } from 'react'\nimport { createRoot } from 'react-dom/client'\n\
+                 import App from './App.jsx'\n\ncreateRoot(document.getElementById('root')).render(\n  \
+                 <StrictMode>\n    <App />\n  </StrictMode>,\n)\n",
+            ),
+            (
+                "src/App.jsx",
+                "export default function App() {\n  return <h1>{{name}}</h1>\n}\n",
+            ),
+            (".gitignore", "node_modules\ndist\n"),
+        ],
+    },
+    BuiltinTemplate {
+        name: "python-package",
+        description: "Python package with pytest tests",
+        files: &[
+            (
+                "pyproject.toml",
+                "[project]\nname = \"{{name}}\"\nversion = \"0.1.0\"\nrequires-python = \">=3.9\"\n\
+                 dependencies = []\n\n[project.optional-dependencies]\ntest = [\"pytest\"]\n\n\
+                 [build-system]\nrequires = [\"hatchling\"]\nbuild-backend = \"hatchling.build\"\n",
+            ),
+            (
+                "src/{{module}}/__init__.py",
+                "\"\"\"{{name}}\"\"\"\n\n__version__ = \"0.1.0\"\n",
+            ),
+            (
+                "tests/test_{{module}}.py",
+                "import {{module}}\n\n\ndef test_version():\n    assert {{module}}.__version__\n",
+            ),
+            ("README.md", "# {{name}}\n"),
+            (".gitignore", "__pycache__/\n*.egg-info/\n.venv/\ndist/\n"),
+        ],
+    },
+];
+
+/// Variables every template can use without being given them
+const DEFAULT_VARIABLES: &[&str] = &["name", "module"];
+
+/// Fill in the default variables from the project's directory name
+fn with_defaults(dest: &Path, vars: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut vars = vars.clone();
+    let name = vars.entry("name".to_string()).or_insert_with(|| {
+        dest.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "project".to_string())
+    });
+    let module = name.to_lowercase().replace(['-', ' ', '.'], "_");
+    vars.entry("module".to_string()).or_insert(module);
+    vars
+}
+
+/// Read every file of a user template, with paths relative to its directory
+fn read_user_template(dir: &Path) -> Result<Vec<(String, Vec<u8>)>, ScaffoldError> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+            } else if let Ok(relative) = path.strip_prefix(dir) {
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                files.push((relative, fs::read(&path)?));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn user_template_dir(user_dir: Option<&Path>, name: &str) -> Option<PathBuf> {
+    let dir = user_dir?.join(name);
+    let plain_name = Path::new(name).components().count() == 1;
+    (plain_name && dir.is_dir()).then_some(dir)
+}
+
+/// List the built-in templates and those in `user_dir`
+pub fn list_templates(user_dir: Option<&Path>) -> Vec<TemplateInfo> {
+    let variables = |contents: Vec<&str>| {
+        let mut found: Vec<String> = Vec::new();
+        for content in contents {
+            for v in prompt_variables(content) {
+                if !DEFAULT_VARIABLES.contains(&v.as_str()) && !found.contains(&v) {
+                    found.push(v);
+                }
+            }
+        }
+        found
+    };
+
+    let mut templates: Vec<TemplateInfo> = BUILTIN_TEMPLATES
+        .iter()
+        .map(|t| TemplateInfo {
+            name: t.name.to_string(),
+            description: Some(t.description.to_string()),
+            variables: variables(t.files.iter().flat_map(|(p, c)| [*p, *c]).collect()),
+            builtin: true,
+        })
+        .collect();
+
+    let entries = user_dir.and_then(|dir| fs::read_dir(dir).ok());
+    let mut user_templates: Vec<TemplateInfo> = entries
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|e| {
+            let files = read_user_template(&e.path()).ok()?;
+            let texts: Vec<String> = files
+                .iter()
+                .flat_map(|(p, c)| [p.clone(), String::from_utf8_lossy(c).to_string()])
+                .collect();
+            Some(TemplateInfo {
+                name: e.file_name().to_string_lossy().to_string(),
+                description: None,
+                variables: variables(texts.iter().map(String::as_str).collect()),
+                builtin: false,
+            })
+        })
+        .collect();
+    user_templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates.extend(user_templates);
+    templates
+}
+
+/// Create a project from a template
+///
+/// A user template with the same name as a built-in one replaces it.
+/// `name` defaults to the destination's directory name and `module` to
+/// `name` as a Python-style identifier. Text files have their variables
+/// filled in; other files are copied as they are.
+///
+/// # Arguments
+/// * `template` - Name of the template
+/// * `dest` - Directory to create the project in; it must be empty or not exist
+/// * `vars` - Values for the template's variables
+/// * `user_dir` - Directory holding user templates, if any
+///
+/// # Returns
+/// The files created, relative to `dest`
+pub fn scaffold_project(
+    template: &str,
+    dest: &Path,
+    vars: &HashMap<String, String>,
+    user_dir: Option<&Path>,
+) -> Result<Vec<String>, ScaffoldError> {
+    let files: Vec<(String, Vec<u8>)> = match user_template_dir(user_dir, template) {
+        Some(dir) => read_user_template(&dir)?,
+        None => BUILTIN_TEMPLATES
+            .iter()
+            .find(|t| t.name == template)
+            .ok_or_else(|| ScaffoldError::TemplateNotFound(template.to_string()))?
+            .files
+            .iter()
+            .map(|(path, content)| (path.to_string(), content.as_bytes().to_vec()))
+            .collect(),
+    };
+
+    if dest.exists() && fs::read_dir(dest)?.next().is_some() {
+        return Err(ScaffoldError::DestinationNotEmpty(
+            dest.display().to_string(),
+        ));
+    }
+    let vars = with_defaults(dest, vars);
+
+    // Render everything before writing, so a missing variable writes nothing
+    let mut rendered = Vec::with_capacity(files.len());
+    for (path, content) in files {
+        let path = render_prompt(&path, &vars)?;
+        let safe = Path::new(&path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+        if !safe {
+            return Err(ScaffoldError::UnsafePath(path));
+        }
+        let content = match String::from_utf8(content) {
+            Ok(text) => render_prompt(&text, &vars)?.into_bytes(),
+            Err(e) => e.into_bytes(),
+        };
+        rendered.push((path, content));
+    }
+
+    for (path, content) in &rendered {
+        let target = dest.join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, content)?;
+    }
+    Ok(rendered.into_iter().map(|(path, _)| path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_scaffold_builtin_and_user_templates() {
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("my-lib");
+        let created = scaffold_project("python-package", &dest, &HashMap::new(), None).unwrap();
+        assert!(created.contains(&"src/my_lib/__init__.py".to_string()));
+        assert!(fs::read_to_string(dest.join("pyproject.toml"))
+            .unwrap()
+            .contains("name = \"my-lib\""));
+        assert!(matches!(
+            scaffold_project("rust-bin", &dest, &HashMap::new(), None),
+            Err(ScaffoldError::DestinationNotEmpty(_))
+        ));
+
+        let user_dir = dir.path().join(TEMPLATES_DIR);
+        fs::create_dir_all(user_dir.join("service/{{name}}")).unwrap();
+        fs::write(user_dir.join("service/{{name}}/main.go"), "// {{owner}}\n").unwrap();
+        let templates = list_templates(Some(&user_dir));
+        let service = templates.iter().find(|t| t.name == "service").unwrap();
+        assert_eq!(service.variables, vec!["owner".to_string()]);
+
+        let api = dir.path().join("api");
+        assert!(matches!(
+            scaffold_project("service", &api, &HashMap::new(), Some(&user_dir)),
+            Err(ScaffoldError::Prompt(PromptError::MissingVariables(_)))
+        ));
+        let vars = HashMap::from([("owner".to_string(), "platform".to_string())]);
+        scaffold_project("service", &api, &vars, Some(&user_dir)).unwrap();
+        assert_eq!(
+            fs::read_to_string(api.join("api/main.go")).unwrap(),
+            "// platform\n"
+        );
+    }
+}
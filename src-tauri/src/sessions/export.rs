@@ -0,0 +1,273 @@
+//! Session export and import
+//!
+//! Sessions can be exported as Markdown, for reading in a PR or issue, or as
+//! JSON, which keeps every message, tool call and usage figure so the
+//! session can be imported again on another machine.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Session, SessionError};
+use crate::providers::{ContentBlock, MessageContent, Role};
+
+/// Version of the JSON export format
+pub const SESSION_EXPORT_VERSION: u32 = 1;
+
+/// Format to export a session in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Markdown,
+    Json,
+}
+
+/// A session as written by a JSON export
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionExport {
+    version: u32,
+    session: Session,
+}
+
+/// Tool arguments that hold a diff, shown as one rather than as JSON
+const DIFF_ARGUMENTS: &[&str] = &["patch", "diff"];
+
+/// Wrap text in a code fence longer than any backtick run inside it
+fn fenced(language: &str, content: &str) -> String {
+    let longest_run = content
+        .split(|c| c != '`')
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!(
+        "{}{}\n{}\n{}\n\n",
+        fence,
+        language,
+        content.trim_end(),
+        fence
+    )
+}
+
+/// The diff in a tool call's arguments or a tool's JSON result, if any
+fn embedded_diff(value: &Value) -> Option<&str> {
+    DIFF_ARGUMENTS
+        .iter()
+        .find_map(|key| value.get(key).and_then(|v| v.as_str()))
+        .filter(|diff| !diff.is_empty())
+}
+
+fn render_block(out: &mut String, block: &ContentBlock) {
+    match block {
+        ContentBlock::Text { text } => {
+            out.push_str(text.trim_end());
+            out.push_str("\n\n");
+        }
+        ContentBlock::Image { .. } => out.push_str("_[image]_\n\n"),
+        ContentBlock::ToolUse { name, input, .. } => {
+            out.push_str(&format!("**Tool call:** `{}`\n\n", name));
+            match embedded_diff(input) {
+                Some(diff) => {
+                    let mut rest = input.clone();
+                    if let Some(args) = rest.as_object_mut() {
+                        args.retain(|key, _| !DIFF_ARGUMENTS.contains(&key.as_str()));
+                    }
+                    if rest.as_object().is_some_and(|args| !args.is_empty()) {
+                        out.push_str(&fenced("json", &pretty(&rest)));
+                    }
+                    out.push_str(&fenced("diff", diff));
+                }
+                None => out.push_str(&fenced("json", &pretty(input))),
+            }
+        }
+        ContentBlock::ToolResult {
+            content, is_error, ..
+        } => {
+            let label = if *is_error == Some(true) {
+                "**Tool error:**"
+            } else {
+                "**Tool result:**"
+            };
+            out.push_str(label);
+            out.push_str("\n\n");
+            let parsed: Option<Value> = serde_json::from_str(content).ok();
+            match parsed.as_ref().and_then(embedded_diff) {
+                Some(diff) => out.push_str(&fenced("diff", diff)),
+                None => out.push_str(&fenced("", content)),
+            }
+        }
+        ContentBlock::Thinking { thinking, .. } => {
+            out.push_str("<details><summary>Thinking</summary>\n\n");
+            out.push_str(thinking.trim_end());
+            out.push_str("\n\n</details>\n\n");
+        }
+        ContentBlock::RedactedThinking { .. } => {}
+        ContentBlock::Citation(citation) => {
+            let title = citation
+                .title
+                .as_deref()
+                .or(citation.url.as_deref())
+                .unwrap_or("source");
+            match &citation.url {
+                Some(url) => out.push_str(&format!("> Source: [{}]({})\n\n", title, url)),
+                None => out.push_str(&format!("> Source: {}\n\n", title)),
+            }
+        }
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// Render a session as Markdown
+pub fn session_to_markdown(session: &Session) -> String {
+    let summary = &session.summary;
+    let mut out = format!(
+        "# {}\n\n",
+        summary.title.as_deref().unwrap_or("Untitled session")
+    );
+    if let Some(model) = &summary.model {
+        match &summary.provider {
+            Some(provider) => out.push_str(&format!("- Model: {} ({})\n", model, provider)),
+            None => out.push_str(&format!("- Model: {}\n", model)),
+        }
+    }
+    if let Some(project) = &summary.project_path {
+        out.push_str(&format!("- Project: `{}`\n", project));
+    }
+    out.push_str(&format!(
+        "- Tokens: {} input, {} output\n\n",
+        summary.usage.input_tokens, summary.usage.output_tokens
+    ));
+
+    for stored in &session.messages {
+        let message = &stored.message;
+        let heading = match (&message.role, &message.content) {
+            (Role::User, MessageContent::Blocks { content })
+                if content
+                    .iter()
+                    .all(|b| matches!(b, ContentBlock::ToolResult { .. })) =>
+            {
+                "Tool"
+            }
+            (Role::System, _) => "System",
+            (Role::User, _) => "User",
+            (Role::Assistant, _) => "Assistant",
+            (Role::Tool, _) => "Tool",
+        };
+        out.push_str(&format!("## {}\n\n", heading));
+        match &message.content {
+            MessageContent::Text { content } => {
+                out.push_str(content.trim_end());
+                out.push_str("\n\n");
+            }
+            MessageContent::Blocks { content } => {
+                for block in content {
+                    render_block(&mut out, block);
+                }
+            }
+        }
+    }
+
+    format!("{}\n", out.trim_end())
+}
+
+/// Export a session in the given format
+pub fn export_session(session: &Session, format: ExportFormat) -> Result<String, SessionError> {
+    match format {
+        ExportFormat::Markdown => Ok(session_to_markdown(session)),
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(&SessionExport {
+            version: SESSION_EXPORT_VERSION,
+            session: session.clone(),
+        })?),
+    }
+}
+
+/// Parse a session from a JSON export
+///
+/// Markdown exports are meant for reading and can't be imported.
+pub fn parse_session_export(content: &str) -> Result<Session, SessionError> {
+    let export: SessionExport = serde_json::from_str(content)?;
+    if export.version > SESSION_EXPORT_VERSION {
+        return Err(SessionError::Import(format!(
+            "Export format version {} is newer than this app supports",
+            export.version
+        )));
+    }
+    Ok(export.session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{ChatMessage, Usage};
+    use crate::sessions::{NewSession, SessionStore};
+    use serde_json::json;
+
+    #[test]
+    fn test_export_and_import() {
+        let store = SessionStore::in_memory().unwrap();
+        let created = store
+            .create_session(NewSession {
+                title: Some("Patch the README".to_string()),
+                model: Some("claude-sonnet-4".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        store
+            .append_message(&created.id, &ChatMessage::user("Fix the typo"), None)
+            .unwrap();
+        let patch = "--- a/README.md\n+++ b/README.md\n@@ -1 +1 @@\n-Helo\n+Hello\n";
+        let tool_use = ChatMessage::blocks(
+            Role::Assistant,
+            vec![
+                ContentBlock::Text {
+                    text: "Use ```code``` here".to_string(),
+                },
+                ContentBlock::ToolUse {
+                    id: "call_1".to_string(),
+                    name: "apply_patch".to_string(),
+                    input: json!({ "patch": patch }),
+                },
+            ],
+        );
+        let usage = Usage {
+            input_tokens: 50,
+            output_tokens: 10,
+        };
+        store
+            .append_message(&created.id, &tool_use, Some(&usage))
+            .unwrap();
+        store
+            .append_message(
+                &created.id,
+                &ChatMessage::tool_result("call_1", r#"{"success":true}"#, false),
+                None,
+            )
+            .unwrap();
+        let session = store.load_session(&created.id).unwrap();
+
+        let markdown = export_session(&session, ExportFormat::Markdown).unwrap();
+        assert!(markdown.starts_with("# Patch the README\n"));
+        assert!(markdown.contains("## Tool\n\n**Tool result:**"));
+        assert!(markdown.contains(&format!(
+            "**Tool call:** `apply_patch`\n\n```diff\n{}```",
+            patch
+        )));
+        assert!(parse_session_export(&markdown).is_err());
+
+        let json = export_session(&session, ExportFormat::Json).unwrap();
+        let imported = store
+            .import_session(&parse_session_export(&json).unwrap())
+            .unwrap();
+        assert_ne!(imported.id, created.id);
+        assert_eq!(imported.message_count, 3);
+        assert_eq!(imported.usage.input_tokens, 50);
+        let reloaded = store.load_session(&imported.id).unwrap();
+        assert_eq!(
+            export_session(&reloaded, ExportFormat::Markdown).unwrap(),
+            markdown
+        );
+    }
+}
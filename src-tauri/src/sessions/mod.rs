@@ -0,0 +1,117 @@
+//! Conversation persistence
+//!
+//! This module stores chat sessions in a SQLite database in the app data
+//! directory, so conversations survive restarts. Each session keeps its
+//! messages in order, including tool calls and results, along with the
+//! token usage of each response and free-form metadata.
+
+pub mod export;
+pub mod search;
+pub mod store;
+pub mod title;
+
+pub use export::*;
+pub use search::*;
+pub use store::*;
+pub use title::*;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::providers::{ChatMessage, Usage};
+
+/// Database file (relative to the app data directory) holding sessions
+pub const SESSIONS_DB: &str = "sessions.sqlite";
+
+/// Errors that can occur while storing or loading sessions
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Session not found: {0}")]
+    NotFound(String),
+
+    #[error("Message not found: {0}")]
+    MessageNotFound(i64),
+
+    #[error("Import failed: {0}")]
+    Import(String),
+}
+
+/// Details for a new session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewSession {
+    pub title: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub project_path: Option<String>,
+    pub temperature: Option<f32>,
+    pub system_prompt: Option<String>,
+    /// Arbitrary frontend data kept with the session
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Changes to the configuration a session is pinned to; `None` keeps the
+/// current value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionConfig {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub system_prompt: Option<String>,
+}
+
+/// A session without its messages, for listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub id: String,
+    pub title: Option<String>,
+    /// One line on what the session is about, generated with the title
+    pub summary: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub project_path: Option<String>,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+    /// Unix timestamp (seconds) of the last message
+    pub updated_at: u64,
+    pub message_count: usize,
+    /// Tokens used by all responses in the session
+    pub usage: Usage,
+    /// The session this one was branched from, if any
+    pub parent_id: Option<String>,
+    /// ID of the message in the parent session where the branch was made
+    pub branched_at: Option<i64>,
+}
+
+/// A message as stored in a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredMessage {
+    pub id: i64,
+    #[serde(flatten)]
+    pub message: ChatMessage,
+    /// Tokens used by the response that produced this message
+    pub usage: Option<Usage>,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+}
+
+/// A session with all its messages, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    #[serde(flatten)]
+    pub summary: SessionSummary,
+    /// Temperature the session's requests use
+    pub temperature: Option<f32>,
+    /// System prompt the session's requests use
+    pub system_prompt: Option<String>,
+    pub metadata: serde_json::Value,
+    pub messages: Vec<StoredMessage>,
+}
@@ -0,0 +1,84 @@
+//! Full-text search over session messages
+//!
+//! Messages are indexed in an SQLite FTS5 table as they are stored. This
+//! module decides what text of a message is searchable and turns the user's
+//! query into an FTS5 query.
+
+use serde::{Deserialize, Serialize};
+
+use super::SessionSummary;
+use crate::providers::{ChatMessage, ContentBlock, MessageContent};
+
+/// Default number of sessions returned by a search
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// Markers placed around matched terms in result snippets
+pub const MATCH_START: &str = "**";
+pub const MATCH_END: &str = "**";
+
+/// A session that matched a search, with its best-matching message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSearchResult {
+    pub session: SessionSummary,
+    /// ID of the message that matched best
+    pub message_id: i64,
+    /// Text around the match, with matched terms between `MATCH_START` and `MATCH_END`
+    pub snippet: String,
+}
+
+/// The text of a message that search looks at
+///
+/// Covers text, tool calls and tool results. Reasoning and images are left out.
+pub fn searchable_text(message: &ChatMessage) -> String {
+    match &message.content {
+        MessageContent::Text { content } => content.clone(),
+        MessageContent::Blocks { content } => content
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text } => Some(text.clone()),
+                ContentBlock::ToolUse { name, input, .. } => Some(format!("{} {}", name, input)),
+                ContentBlock::ToolResult { content, .. } => Some(content.clone()),
+                ContentBlock::Citation(citation) => citation.title.clone(),
+                ContentBlock::Image { .. }
+                | ContentBlock::Thinking { .. }
+                | ContentBlock::RedactedThinking { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Turn a plain query into an FTS5 query matching messages with every word
+///
+/// Each word is quoted so punctuation in the query can't be read as FTS5
+/// syntax, and the last word matches as a prefix so results show up while
+/// the user is still typing. Returns `None` if the query has no words.
+pub fn fts_query(query: &str) -> Option<String> {
+    let words: Vec<&str> = query
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .collect();
+    let (last, rest) = words.split_last()?;
+
+    let mut terms: Vec<String> = rest.iter().map(|word| format!("\"{}\"", word)).collect();
+    terms.push(format!("\"{}\"*", last));
+    Some(terms.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(
+            fts_query("PTY resize-bug").as_deref(),
+            Some("\"PTY\" \"resize\" \"bug\"*")
+        );
+        assert_eq!(
+            fts_query("\"unbalanced OR (").as_deref(),
+            Some("\"unbalanced\" \"OR\"*")
+        );
+        assert_eq!(fts_query(" -- "), None);
+    }
+}
@@ -0,0 +1,730 @@
+//! SQLite storage for chat sessions
+
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use super::search::{self, SessionSearchResult, MATCH_END, MATCH_START};
+use super::{NewSession, Session, SessionConfig, SessionError, SessionSummary, StoredMessage};
+use crate::providers::{ChatMessage, Usage};
+
+/// Columns read by `summary_from_row`
+const SUMMARY_COLUMNS: &str = "s.id, s.title, s.provider, s.model, s.project_path,
+     s.created_at, s.updated_at,
+     (SELECT COUNT(*) FROM messages m WHERE m.session_id = s.id),
+     (SELECT COALESCE(SUM(m.input_tokens), 0) FROM messages m WHERE m.session_id = s.id),
+     (SELECT COALESCE(SUM(m.output_tokens), 0) FROM messages m WHERE m.session_id = s.id),
+     s.parent_id, s.branched_at, s.summary";
+
+/// Columns added to the sessions table after its first release
+const ADDED_SESSION_COLUMNS: &[(&str, &str)] = &[
+    (
+        "parent_id",
+        "TEXT REFERENCES sessions (id) ON DELETE SET NULL",
+    ),
+    ("branched_at", "INTEGER"),
+    ("summary", "TEXT"),
+    ("temperature", "REAL"),
+    ("system_prompt", "TEXT"),
+];
+
+/// Most matching messages looked at when picking each session's best match
+const MAX_SEARCH_MATCHES: usize = 1000;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn summary_from_row(row: &Row<'_>) -> rusqlite::Result<SessionSummary> {
+    Ok(SessionSummary {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        provider: row.get(2)?,
+        model: row.get(3)?,
+        project_path: row.get(4)?,
+        created_at: row.get::<_, i64>(5)? as u64,
+        updated_at: row.get::<_, i64>(6)? as u64,
+        message_count: row.get::<_, i64>(7)? as usize,
+        usage: Usage {
+            input_tokens: row.get::<_, i64>(8)? as u32,
+            output_tokens: row.get::<_, i64>(9)? as u32,
+        },
+        parent_id: row.get(10)?,
+        branched_at: row.get(11)?,
+        summary: row.get(12)?,
+    })
+}
+
+/// Add a message's text to the search index
+fn index_message(conn: &Connection, id: i64, message: &ChatMessage) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO message_search (rowid, text) VALUES (?1, ?2)",
+        params![id, search::searchable_text(message)],
+    )?;
+    Ok(())
+}
+
+/// Index the stored messages of one session, or of all sessions
+fn index_messages(conn: &Connection, session_id: Option<&str>) -> Result<(), SessionError> {
+    let mut stmt =
+        conn.prepare("SELECT id, message FROM messages WHERE ?1 IS NULL OR session_id = ?1")?;
+    let rows = stmt
+        .query_map(params![session_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (id, json) in rows {
+        index_message(conn, id, &serde_json::from_str(&json)?)?;
+    }
+    Ok(())
+}
+
+/// Chat sessions stored in a SQLite database
+pub struct SessionStore {
+    conn: Mutex<Connection>,
+}
+
+impl SessionStore {
+    /// Open (or create) the sessions database
+    pub fn open(db_path: &Path) -> Result<Self, SessionError> {
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::init(Connection::open(db_path)?)
+    }
+
+    /// Open a database that lives only as long as the store
+    pub fn in_memory() -> Result<Self, SessionError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, SessionError> {
+        conn.execute_batch(
+            "PRAGMA foreign_keys = ON;
+             CREATE TABLE IF NOT EXISTS sessions (
+                 id TEXT PRIMARY KEY,
+                 title TEXT,
+                 provider TEXT,
+                 model TEXT,
+                 project_path TEXT,
+                 metadata TEXT NOT NULL,
+                 created_at INTEGER NOT NULL,
+                 updated_at INTEGER NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS messages (
+                 id INTEGER PRIMARY KEY,
+                 session_id TEXT NOT NULL REFERENCES sessions (id) ON DELETE CASCADE,
+                 message TEXT NOT NULL,
+                 input_tokens INTEGER,
+                 output_tokens INTEGER,
+                 created_at INTEGER NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS messages_session ON messages (session_id, id);",
+        )?;
+
+        let existing: Vec<String> = conn
+            .prepare("SELECT name FROM pragma_table_info('sessions')")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        for (column, definition) in ADDED_SESSION_COLUMNS {
+            if !existing.iter().any(|name| name == column) {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE sessions ADD COLUMN {} {}",
+                    column, definition
+                ))?;
+            }
+        }
+
+        // Databases from before search was added have messages to index
+        let has_search_index = conn
+            .query_row(
+                "SELECT 1 FROM sqlite_master WHERE name = 'message_search'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS message_search
+                 USING fts5 (text, tokenize = 'porter unicode61');
+             CREATE TRIGGER IF NOT EXISTS messages_unindex AFTER DELETE ON messages BEGIN
+                 DELETE FROM message_search WHERE rowid = old.id;
+             END;",
+        )?;
+        if !has_search_index {
+            index_messages(&conn, None)?;
+        }
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        // A panic mid-query leaves nothing half-written outside a transaction
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start a new, empty session
+    pub fn create_session(&self, session: NewSession) -> Result<SessionSummary, SessionError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let metadata = session.metadata.unwrap_or_else(|| serde_json::json!({}));
+        let created_at = now();
+
+        self.conn().execute(
+            "INSERT INTO sessions (id, title, provider, model, project_path, metadata,
+                                   created_at, updated_at, temperature, system_prompt)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7, ?8, ?9)",
+            params![
+                id,
+                session.title,
+                session.provider,
+                session.model,
+                session.project_path,
+                serde_json::to_string(&metadata)?,
+                created_at as i64,
+                session.temperature,
+                session.system_prompt
+            ],
+        )?;
+
+        Ok(SessionSummary {
+            id,
+            title: session.title,
+            provider: session.provider,
+            model: session.model,
+            project_path: session.project_path,
+            created_at,
+            updated_at: created_at,
+            message_count: 0,
+            usage: Usage::default(),
+            parent_id: None,
+            branched_at: None,
+            summary: None,
+        })
+    }
+
+    /// Add a message to the end of a session
+    ///
+    /// # Arguments
+    /// * `usage` - Tokens used by the response that produced the message, if any
+    pub fn append_message(
+        &self,
+        session_id: &str,
+        message: &ChatMessage,
+        usage: Option<&Usage>,
+    ) -> Result<StoredMessage, SessionError> {
+        let json = serde_json::to_string(message)?;
+        let created_at = now();
+
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        let updated = tx.execute(
+            "UPDATE sessions SET updated_at = ?2 WHERE id = ?1",
+            params![session_id, created_at as i64],
+        )?;
+        if updated == 0 {
+            return Err(SessionError::NotFound(session_id.to_string()));
+        }
+        tx.execute(
+            "INSERT INTO messages (session_id, message, input_tokens, output_tokens, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                session_id,
+                json,
+                usage.map(|u| u.input_tokens),
+                usage.map(|u| u.output_tokens),
+                created_at as i64
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        index_message(&tx, id, message)?;
+        tx.commit()?;
+
+        Ok(StoredMessage {
+            id,
+            message: message.clone(),
+            usage: usage.cloned(),
+            created_at,
+        })
+    }
+
+    /// Save a copy of a session, e.g. one imported from an export
+    ///
+    /// The copy gets a new ID; its messages, usage and timestamps are kept.
+    pub fn import_session(&self, session: &Session) -> Result<SessionSummary, SessionError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let summary = &session.summary;
+
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO sessions (id, title, provider, model, project_path, metadata,
+                                   created_at, updated_at, summary, temperature, system_prompt)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                id,
+                summary.title,
+                summary.provider,
+                summary.model,
+                summary.project_path,
+                serde_json::to_string(&session.metadata)?,
+                summary.created_at as i64,
+                summary.updated_at as i64,
+                summary.summary,
+                session.temperature,
+                session.system_prompt
+            ],
+        )?;
+        for stored in &session.messages {
+            tx.execute(
+                "INSERT INTO messages (session_id, message, input_tokens, output_tokens, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    id,
+                    serde_json::to_string(&stored.message)?,
+                    stored.usage.as_ref().map(|u| u.input_tokens),
+                    stored.usage.as_ref().map(|u| u.output_tokens),
+                    stored.created_at as i64
+                ],
+            )?;
+            index_message(&tx, tx.last_insert_rowid(), &stored.message)?;
+        }
+        tx.commit()?;
+        drop(conn);
+
+        Ok(self.load_session(&id)?.summary)
+    }
+
+    /// List all sessions, most recently active first
+    pub fn list_sessions(&self) -> Result<Vec<SessionSummary>, SessionError> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM sessions s ORDER BY s.updated_at DESC, s.rowid DESC",
+            SUMMARY_COLUMNS
+        ))?;
+        let sessions = stmt
+            .query_map([], summary_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sessions)
+    }
+
+    /// Find sessions whose messages contain every word of a query
+    ///
+    /// Sessions are ordered by how well their best message matches.
+    ///
+    /// # Arguments
+    /// * `query` - Words to look for; the last may be the start of a word
+    /// * `limit` - Maximum number of sessions to return
+    pub fn search_sessions(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SessionSearchResult>, SessionError> {
+        let Some(fts_query) = search::fts_query(query) else {
+            return Ok(Vec::new());
+        };
+
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT m.session_id, m.id, snippet(message_search, 0, ?2, ?3, '...', 16)
+             FROM message_search JOIN messages m ON m.id = message_search.rowid
+             WHERE message_search MATCH ?1
+             ORDER BY message_search.rank
+             LIMIT ?4",
+        )?;
+        let matches = stmt
+            .query_map(
+                params![fts_query, MATCH_START, MATCH_END, MAX_SEARCH_MATCHES as i64],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut summary_stmt = conn.prepare(&format!(
+            "SELECT {} FROM sessions s WHERE s.id = ?1",
+            SUMMARY_COLUMNS
+        ))?;
+        let mut results: Vec<SessionSearchResult> = Vec::new();
+        for (session_id, message_id, snippet) in matches {
+            if results.len() >= limit {
+                break;
+            }
+            if results.iter().any(|r| r.session.id == session_id) {
+                continue;
+            }
+            let session = summary_stmt.query_row(params![session_id], summary_from_row)?;
+            results.push(SessionSearchResult {
+                session,
+                message_id,
+                snippet,
+            });
+        }
+        Ok(results)
+    }
+
+    /// Load a session with all its messages
+    pub fn load_session(&self, session_id: &str) -> Result<Session, SessionError> {
+        let conn = self.conn();
+        let found = conn
+            .query_row(
+                &format!(
+                    "SELECT {}, s.metadata, s.temperature, s.system_prompt
+                     FROM sessions s WHERE s.id = ?1",
+                    SUMMARY_COLUMNS
+                ),
+                params![session_id],
+                |row| {
+                    Ok((
+                        summary_from_row(row)?,
+                        row.get::<_, String>(13)?,
+                        row.get::<_, Option<f64>>(14)?,
+                        row.get::<_, Option<String>>(15)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let (summary, metadata, temperature, system_prompt) =
+            found.ok_or_else(|| SessionError::NotFound(session_id.to_string()))?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, message, input_tokens, output_tokens, created_at
+             FROM messages WHERE session_id = ?1 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map(params![session_id], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<u32>>(2)?,
+                    row.get::<_, Option<u32>>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let messages = rows
+            .into_iter()
+            .map(|(id, json, input_tokens, output_tokens, created_at)| {
+                let usage = match (input_tokens, output_tokens) {
+                    (None, None) => None,
+                    (input, output) => Some(Usage {
+                        input_tokens: input.unwrap_or(0),
+                        output_tokens: output.unwrap_or(0),
+                    }),
+                };
+                Ok(StoredMessage {
+                    id,
+                    message: serde_json::from_str(&json)?,
+                    usage,
+                    created_at: created_at as u64,
+                })
+            })
+            .collect::<Result<Vec<_>, SessionError>>()?;
+
+        Ok(Session {
+            summary,
+            temperature: temperature.map(|t| t as f32),
+            system_prompt,
+            metadata: serde_json::from_str(&metadata)?,
+            messages,
+        })
+    }
+
+    /// Copy a session up to one of its messages into a new branch
+    ///
+    /// The branch records the session and message it was forked from; the
+    /// original session is left as it was.
+    ///
+    /// # Arguments
+    /// * `message_id` - The message to fork at
+    /// * `include_message` - Whether the branch keeps that message or ends just before it
+    pub fn fork_session(
+        &self,
+        message_id: i64,
+        include_message: bool,
+    ) -> Result<SessionSummary, SessionError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let created_at = now();
+
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        let parent_id: String = tx
+            .query_row(
+                "SELECT session_id FROM messages WHERE id = ?1",
+                params![message_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(SessionError::MessageNotFound(message_id))?;
+
+        tx.execute(
+            "INSERT INTO sessions (id, title, provider, model, project_path, metadata,
+                                   created_at, updated_at, parent_id, branched_at, summary,
+                                   temperature, system_prompt)
+             SELECT ?2, title, provider, model, project_path, metadata, ?3, ?3, id, ?4, summary,
+                    temperature, system_prompt
+             FROM sessions WHERE id = ?1",
+            params![parent_id, id, created_at as i64, message_id],
+        )?;
+        let last_copied = if include_message {
+            message_id
+        } else {
+            message_id - 1
+        };
+        tx.execute(
+            "INSERT INTO messages (session_id, message, input_tokens, output_tokens, created_at)
+             SELECT ?2, message, input_tokens, output_tokens, created_at
+             FROM messages WHERE session_id = ?1 AND id <= ?3 ORDER BY id",
+            params![parent_id, id, last_copied],
+        )?;
+        index_messages(&tx, Some(&id))?;
+        tx.commit()?;
+        drop(conn);
+
+        Ok(self.load_session(&id)?.summary)
+    }
+
+    /// Change the provider, model, temperature or system prompt a session uses
+    pub fn update_config(
+        &self,
+        session_id: &str,
+        config: &SessionConfig,
+    ) -> Result<(), SessionError> {
+        let updated = self.conn().execute(
+            "UPDATE sessions SET provider = COALESCE(?2, provider),
+                                 model = COALESCE(?3, model),
+                                 temperature = COALESCE(?4, temperature),
+                                 system_prompt = COALESCE(?5, system_prompt)
+             WHERE id = ?1",
+            params![
+                session_id,
+                config.provider,
+                config.model,
+                config.temperature,
+                config.system_prompt
+            ],
+        )?;
+        if updated == 0 {
+            return Err(SessionError::NotFound(session_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Set a session's title and summary
+    pub fn set_title(
+        &self,
+        session_id: &str,
+        title: &str,
+        summary: Option<&str>,
+    ) -> Result<(), SessionError> {
+        let updated = self.conn().execute(
+            "UPDATE sessions SET title = ?2, summary = ?3 WHERE id = ?1",
+            params![session_id, title, summary],
+        )?;
+        if updated == 0 {
+            return Err(SessionError::NotFound(session_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Delete a session and its messages
+    pub fn delete_session(&self, session_id: &str) -> Result<(), SessionError> {
+        let deleted = self
+            .conn()
+            .execute("DELETE FROM sessions WHERE id = ?1", params![session_id])?;
+        if deleted == 0 {
+            return Err(SessionError::NotFound(session_id.to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{ContentBlock, Role};
+    use serde_json::json;
+
+    #[test]
+    fn test_session_round_trip() {
+        let store = SessionStore::in_memory().unwrap();
+        let first = store
+            .create_session(NewSession {
+                title: Some("Fix the parser".to_string()),
+                model: Some("gpt-4o".to_string()),
+                temperature: Some(0.2),
+                metadata: Some(json!({ "pinned": false })),
+                ..Default::default()
+            })
+            .unwrap();
+        let second = store.create_session(NewSession::default()).unwrap();
+
+        store
+            .append_message(
+                &first.id,
+                &ChatMessage::user("Why does parsing fail?"),
+                None,
+            )
+            .unwrap();
+        let tool_use = ChatMessage::blocks(
+            Role::Assistant,
+            vec![ContentBlock::ToolUse {
+                id: "call_1".to_string(),
+                name: "read_file".to_string(),
+                input: json!({ "path": "src/parser.rs" }),
+            }],
+        );
+        let usage = Usage {
+            input_tokens: 120,
+            output_tokens: 30,
+        };
+        store
+            .append_message(&first.id, &tool_use, Some(&usage))
+            .unwrap();
+        store
+            .append_message(
+                &first.id,
+                &ChatMessage::tool_result("call_1", "fn parse() {}", false),
+                None,
+            )
+            .unwrap();
+
+        let sessions = store.list_sessions().unwrap();
+        assert_eq!(sessions.len(), 2);
+        let listed = sessions.iter().find(|s| s.id == first.id).unwrap();
+        assert_eq!(listed.message_count, 3);
+        assert_eq!(listed.usage.input_tokens, 120);
+
+        let loaded = store.load_session(&first.id).unwrap();
+        assert_eq!(loaded.summary.title.as_deref(), Some("Fix the parser"));
+        assert_eq!(loaded.metadata, json!({ "pinned": false }));
+        assert_eq!(loaded.temperature, Some(0.2));
+
+        let config = SessionConfig {
+            model: Some("gpt-4.1".to_string()),
+            system_prompt: Some("Be brief.".to_string()),
+            ..Default::default()
+        };
+        store.update_config(&first.id, &config).unwrap();
+        let updated = store.load_session(&first.id).unwrap();
+        assert_eq!(updated.summary.model.as_deref(), Some("gpt-4.1"));
+        assert_eq!(updated.temperature, Some(0.2));
+        assert_eq!(updated.system_prompt.as_deref(), Some("Be brief."));
+        assert_eq!(loaded.messages.len(), 3);
+        assert_eq!(loaded.messages[1].usage.as_ref().unwrap().output_tokens, 30);
+        assert!(matches!(
+            &loaded.messages[1].message.content,
+            crate::providers::MessageContent::Blocks { content }
+                if matches!(&content[0], ContentBlock::ToolUse { name, .. } if name == "read_file")
+        ));
+
+        store.delete_session(&first.id).unwrap();
+        assert!(matches!(
+            store.load_session(&first.id),
+            Err(SessionError::NotFound(_))
+        ));
+        assert!(store
+            .append_message(&first.id, &ChatMessage::user("hello"), None)
+            .is_err());
+        assert_eq!(store.list_sessions().unwrap()[0].id, second.id);
+    }
+
+    #[test]
+    fn test_fork_session() {
+        let store = SessionStore::in_memory().unwrap();
+        let original = store.create_session(NewSession::default()).unwrap();
+        let question = store
+            .append_message(&original.id, &ChatMessage::user("Name a colour"), None)
+            .unwrap();
+        let answer = store
+            .append_message(&original.id, &ChatMessage::assistant("Blue"), None)
+            .unwrap();
+        store
+            .append_message(&original.id, &ChatMessage::user("Another"), None)
+            .unwrap();
+
+        let branch = store.fork_session(answer.id, true).unwrap();
+        assert_eq!(branch.parent_id.as_deref(), Some(original.id.as_str()));
+        assert_eq!(branch.branched_at, Some(answer.id));
+        assert_eq!(branch.message_count, 2);
+
+        // Regenerating the answer keeps only the question
+        let retry = store.fork_session(answer.id, false).unwrap();
+        let messages = store.load_session(&retry.id).unwrap().messages;
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(
+            &messages[0].message.content,
+            crate::providers::MessageContent::Text { content } if content == "Name a colour"
+        ));
+        assert_ne!(messages[0].id, question.id);
+
+        assert_eq!(store.load_session(&original.id).unwrap().messages.len(), 3);
+        assert!(matches!(
+            store.fork_session(9999, true),
+            Err(SessionError::MessageNotFound(9999))
+        ));
+
+        store.delete_session(&original.id).unwrap();
+        assert_eq!(
+            store.load_session(&branch.id).unwrap().summary.parent_id,
+            None
+        );
+    }
+
+    #[test]
+    fn test_search_sessions() {
+        let store = SessionStore::in_memory().unwrap();
+        let pty = store.create_session(NewSession::default()).unwrap();
+        let other = store.create_session(NewSession::default()).unwrap();
+        store
+            .append_message(
+                &pty.id,
+                &ChatMessage::user("The terminal breaks when I resize the window"),
+                None,
+            )
+            .unwrap();
+        let fix = store
+            .append_message(
+                &pty.id,
+                &ChatMessage::tool_result("call_1", "Fixed PTY resize handling", false),
+                None,
+            )
+            .unwrap();
+        store
+            .append_message(
+                &other.id,
+                &ChatMessage::user("Rename the parser module"),
+                None,
+            )
+            .unwrap();
+
+        let results = store.search_sessions("pty resiz", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].session.id, pty.id);
+        assert_eq!(results[0].message_id, fix.id);
+        assert!(results[0].snippet.contains("**PTY**"));
+
+        // Both messages match, but each session is listed once
+        assert_eq!(store.search_sessions("resize", 10).unwrap().len(), 1);
+        assert!(store.search_sessions("AND (", 10).unwrap().is_empty());
+
+        let branch = store.fork_session(fix.id, true).unwrap();
+        assert_eq!(store.search_sessions("resize", 10).unwrap().len(), 2);
+
+        store.delete_session(&pty.id).unwrap();
+        let results = store.search_sessions("resize", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].session.id, branch.id);
+    }
+}
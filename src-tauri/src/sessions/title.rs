@@ -0,0 +1,124 @@
+//! Session titles
+//!
+//! After the first exchange of a session, a small model is asked for a
+//! short title and a one-line summary, so saved sessions can be told apart
+//! in the history sidebar.
+
+use crate::context::render_transcript;
+use crate::providers::{ChatMessage, Provider, ProviderError, Role};
+
+/// Most characters of the first exchange shown to the titling model
+const MAX_TITLE_TRANSCRIPT_CHARS: usize = 8000;
+
+/// Longest title kept, in characters
+const MAX_TITLE_CHARS: usize = 80;
+
+const TITLE_SYSTEM_PROMPT: &str = "You name chat sessions between a developer and a coding \
+assistant. Reply with exactly two lines:\n\
+Title: <a title of at most six words, no quotes or trailing period>\n\
+Summary: <one sentence saying what the developer wants>";
+
+/// A generated title and summary
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionTitle {
+    pub title: String,
+    pub summary: Option<String>,
+}
+
+/// The first user message and the reply to it, if the session has both
+pub fn first_exchange(messages: &[ChatMessage]) -> Option<&[ChatMessage]> {
+    let user = messages.iter().position(|m| m.role == Role::User)?;
+    let reply = messages[user..]
+        .iter()
+        .position(|m| m.role == Role::Assistant)?;
+    Some(&messages[user..=user + reply])
+}
+
+/// Parse the titling model's reply
+fn parse_title(reply: &str) -> Option<SessionTitle> {
+    let field = |name: &str| {
+        reply.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim().eq_ignore_ascii_case(name)).then(|| value.trim().to_string())
+        })
+    };
+
+    // Fall back to the first line if the model ignored the format
+    let title = field("title").or_else(|| {
+        reply
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .map(String::from)
+    })?;
+    let title = title
+        .trim_matches(|c| c == '"' || c == '\'' || c == '*')
+        .trim_end_matches('.')
+        .trim();
+    if title.is_empty() {
+        return None;
+    }
+
+    Some(SessionTitle {
+        title: title.chars().take(MAX_TITLE_CHARS).collect(),
+        summary: field("summary").filter(|s| !s.is_empty()),
+    })
+}
+
+/// Ask a provider for a session's title and summary
+///
+/// # Arguments
+/// * `exchange` - The session's first exchange, from `first_exchange`
+pub async fn generate_title(
+    provider: &dyn Provider,
+    exchange: &[ChatMessage],
+) -> Result<SessionTitle, ProviderError> {
+    let transcript: String = render_transcript(exchange)
+        .chars()
+        .take(MAX_TITLE_TRANSCRIPT_CHARS)
+        .collect();
+    let request = vec![
+        ChatMessage::system(TITLE_SYSTEM_PROMPT),
+        ChatMessage::user(format!("Name this session:\n\n{}", transcript)),
+    ];
+
+    let response = provider.chat(request, None).await?;
+    parse_title(&response.text()).ok_or_else(|| {
+        ProviderError::InvalidResponse("Provider returned an empty title".to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_title() {
+        assert_eq!(
+            parse_title(
+                "Title: \"Fix flaky parser test.\"\nSummary: Make the parser test pass reliably."
+            ),
+            Some(SessionTitle {
+                title: "Fix flaky parser test".to_string(),
+                summary: Some("Make the parser test pass reliably.".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_title("\nRename config loader\n"),
+            Some(SessionTitle {
+                title: "Rename config loader".to_string(),
+                summary: None,
+            })
+        );
+        assert_eq!(parse_title("  \n"), None);
+
+        let messages = vec![
+            ChatMessage::system("Be brief."),
+            ChatMessage::user("Hi"),
+            ChatMessage::assistant("Hello"),
+            ChatMessage::user("More"),
+        ];
+        assert_eq!(first_exchange(&messages).unwrap().len(), 2);
+        assert!(first_exchange(&messages[..2]).is_none());
+    }
+}
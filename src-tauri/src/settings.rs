@@ -0,0 +1,133 @@
+//! Backend settings for Open Sesh
+//!
+//! This module defines the settings that tune chat and tool behaviour on the
+//! backend. Settings are held in `AppState`, persisted as JSON in the app data
+//! directory, and exposed to the frontend via the settings commands.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::providers::scheduler::RequestLimits;
+use crate::providers::ProviderTimeouts;
+
+/// File name of the persisted settings inside the app data directory
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Default fraction of the context window at which auto-compaction kicks in
+pub const DEFAULT_AUTO_COMPACT_THRESHOLD: f32 = 0.8;
+
+/// Backend settings shared across all Tauri commands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Automatically compact conversations that approach the context window
+    pub auto_compact: bool,
+
+    /// Fraction of the model's context window (0.1 - 1.0) at which to compact
+    pub auto_compact_threshold: f32,
+
+    /// Default system prompts keyed by "provider" or "provider/model"
+    pub system_prompts: HashMap<String, String>,
+
+    /// Network timeouts applied to AI provider requests
+    pub provider_timeouts: ProviderTimeouts,
+
+    /// Concurrency and rate limits keyed by provider name; providers without
+    /// an entry use the defaults
+    pub request_limits: HashMap<String, RequestLimits>,
+
+    /// Directories outside the project that AI tools may access
+    pub tool_allowed_paths: Vec<PathBuf>,
+
+    /// Run the language formatter on files the AI writes or edits
+    pub format_on_ai_write: bool,
+
+    /// Command run before commits in place of the repository's pre-commit
+    /// hook, e.g. `pre-commit run`
+    pub pre_commit_command: Option<String>,
+
+    /// Let the AI read the clipboard with the read_clipboard tool
+    pub clipboard_tool: bool,
+
+    /// Tell the AI which files are open and which it edited recently
+    pub workspace_context: bool,
+
+    /// Include the uncommitted diffs of recently edited files in that context
+    pub workspace_context_diffs: bool,
+}
+
+impl Settings {
+    /// Clamp settings into their valid ranges
+    pub fn validated(mut self) -> Self {
+        self.auto_compact_threshold = self.auto_compact_threshold.clamp(0.1, 1.0);
+        self.system_prompts.retain(|_, prompt| !prompt.trim().is_empty());
+        self.pre_commit_command = self.pre_commit_command.filter(|c| !c.trim().is_empty());
+        self.provider_timeouts = self.provider_timeouts.validated();
+        for limits in self.request_limits.values_mut() {
+            *limits = limits.validated();
+        }
+        self
+    }
+
+    /// Load settings from a JSON file, falling back to defaults
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(content) => match serde_json::from_str::<Settings>(&content) {
+                Ok(settings) => settings.validated(),
+                Err(e) => {
+                    log::warn!("Ignoring invalid settings file {}: {}", path.display(), e);
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Save settings to a JSON file
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)
+    }
+
+    /// Key used to store a system prompt for a provider and optional model
+    pub fn system_prompt_key(provider: &str, model: Option<&str>) -> String {
+        match model {
+            Some(model) => format!("{}/{}", provider, model),
+            None => provider.to_string(),
+        }
+    }
+
+    /// Get the default system prompt for a provider/model
+    ///
+    /// A model-specific prompt takes precedence over the provider-wide one.
+    pub fn default_system_prompt(&self, provider: &str, model: &str) -> Option<&str> {
+        self.system_prompts
+            .get(&Self::system_prompt_key(provider, Some(model)))
+            .or_else(|| self.system_prompts.get(provider))
+            .map(|s| s.as_str())
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            auto_compact: true,
+            auto_compact_threshold: DEFAULT_AUTO_COMPACT_THRESHOLD,
+            system_prompts: HashMap::new(),
+            provider_timeouts: ProviderTimeouts::default(),
+            request_limits: HashMap::new(),
+            tool_allowed_paths: Vec::new(),
+            format_on_ai_write: false,
+            pre_commit_command: None,
+            clipboard_tool: false,
+            workspace_context: true,
+            workspace_context_diffs: false,
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Slash commands
+//!
+//! A user message that starts with `/name` is a command rather than a
+//! prompt. Built-in commands act on the conversation (`/compact`, `/clear`,
+//! `/model`) or expand to a prompt (`/test`). Projects can add their own
+//! prompt templates as Markdown files in `.opensesh/commands/`, where
+//! `$ARGUMENTS` is replaced with whatever follows the command name.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+/// Directory (relative to the project root) holding project command templates
+pub const PROJECT_COMMANDS_DIR: &str = ".opensesh/commands";
+
+/// Placeholder in a template replaced with the command's arguments
+pub const ARGUMENTS_PLACEHOLDER: &str = "$ARGUMENTS";
+
+/// Prompt sent for `/test`
+const TEST_PROMPT: &str = "Run the project's test suite. If any tests fail, find the cause \
+and fix it, then run the tests again to confirm they pass. $ARGUMENTS";
+
+/// Built-in commands: name, description and argument hint
+const BUILTIN_COMMANDS: &[(&str, &str, Option<&str>)] = &[
+    ("clear", "Start the conversation over", None),
+    ("compact", "Summarize older turns to free up context", None),
+    (
+        "model",
+        "Show the current model, or switch to another",
+        Some("[model]"),
+    ),
+    (
+        "test",
+        "Run the project's tests and fix any failures",
+        Some("[focus]"),
+    ),
+];
+
+/// Where a slash command comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlashCommandSource {
+    Builtin,
+    Project,
+}
+
+/// A slash command offered for autocomplete
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlashCommandInfo {
+    /// Name without the leading slash
+    pub name: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub argument_hint: Option<String>,
+    pub source: SlashCommandSource,
+}
+
+/// What a slash command in a message asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum SlashCommand {
+    Compact,
+    Clear,
+    Model {
+        model: Option<String>,
+    },
+    /// Send this prompt in place of the command
+    Prompt(String),
+}
+
+/// Whether a command name is valid: lowercase letters, digits, `-` and `_`
+fn is_command_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// Project command templates by name, sorted
+fn project_templates(project_root: Option<&Path>) -> Vec<(String, PathBuf)> {
+    let Some(root) = project_root else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(root.join(PROJECT_COMMANDS_DIR)) else {
+        return Vec::new();
+    };
+
+    let mut templates: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            is_command_name(&name).then_some((name, path))
+        })
+        .filter(|(name, _)| {
+            !BUILTIN_COMMANDS
+                .iter()
+                .any(|(builtin, _, _)| builtin == name)
+        })
+        .collect();
+    templates.sort();
+    templates
+}
+
+/// Fill a template's argument placeholder, or append the arguments if it has none
+fn expand_template(template: &str, arguments: &str) -> String {
+    if template.contains(ARGUMENTS_PLACEHOLDER) {
+        template
+            .replace(ARGUMENTS_PLACEHOLDER, arguments)
+            .trim()
+            .to_string()
+    } else if arguments.is_empty() {
+        template.trim().to_string()
+    } else {
+        format!("{}\n\n{}", template.trim(), arguments)
+    }
+}
+
+/// List the built-in and project slash commands
+pub fn list_slash_commands(project_root: Option<&Path>) -> Vec<SlashCommandInfo> {
+    let builtins = BUILTIN_COMMANDS
+        .iter()
+        .map(|(name, description, hint)| SlashCommandInfo {
+            name: name.to_string(),
+            description: description.to_string(),
+            argument_hint: hint.map(|h| h.to_string()),
+            source: SlashCommandSource::Builtin,
+        });
+
+    let project = project_templates(project_root)
+        .into_iter()
+        .map(|(name, path)| {
+            // The first line of the template describes it
+            let description = fs::read_to_string(&path)
+                .ok()
+                .and_then(|t| {
+                    t.lines()
+                        .map(|l| l.trim_start_matches('#').trim().to_string())
+                        .find(|l| !l.is_empty())
+                })
+                .unwrap_or_default();
+            SlashCommandInfo {
+                name,
+                description,
+                argument_hint: None,
+                source: SlashCommandSource::Project,
+            }
+        });
+
+    builtins.chain(project).collect()
+}
+
+/// Parse a slash command at the start of a message
+///
+/// # Returns
+/// `None` if the message isn't a command (text like "/etc/hosts" isn't),
+/// or an error naming an unknown command
+pub fn parse_slash_command(
+    text: &str,
+    project_root: Option<&Path>,
+) -> Result<Option<SlashCommand>, String> {
+    let Some(rest) = text.trim_start().strip_prefix('/') else {
+        return Ok(None);
+    };
+    let (name, arguments) = match rest.split_once(char::is_whitespace) {
+        Some((name, arguments)) => (name, arguments.trim()),
+        None => (rest.trim_end(), ""),
+    };
+    if !is_command_name(name) {
+        return Ok(None);
+    }
+
+    let command = match name {
+        "compact" => SlashCommand::Compact,
+        "clear" => SlashCommand::Clear,
+        "model" => SlashCommand::Model {
+            model: Some(arguments.to_string()).filter(|m| !m.is_empty()),
+        },
+        "test" => SlashCommand::Prompt(expand_template(TEST_PROMPT, arguments)),
+        _ => {
+            let (_, path) = project_templates(project_root)
+                .into_iter()
+                .find(|(template, _)| template == name)
+                .ok_or_else(|| format!("Unknown command /{}", name))?;
+            let template = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            SlashCommand::Prompt(expand_template(&template, arguments))
+        }
+    };
+    Ok(Some(command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_slash_commands() {
+        let dir = tempdir().unwrap();
+        let commands = dir.path().join(PROJECT_COMMANDS_DIR);
+        fs::create_dir_all(&commands).unwrap();
+        fs::write(
+            commands.join("review.md"),
+            "# Review a file\nReview $ARGUMENTS for bugs.",
+        )
+        .unwrap();
+        fs::write(commands.join("clear.md"), "Shadowed by the builtin").unwrap();
+        let root = Some(dir.path());
+
+        assert_eq!(parse_slash_command("Fix /etc/hosts", root), Ok(None));
+        assert_eq!(parse_slash_command("/etc/hosts is broken", root), Ok(None));
+        assert_eq!(
+            parse_slash_command(" /clear ", root),
+            Ok(Some(SlashCommand::Clear))
+        );
+        assert_eq!(
+            parse_slash_command("/model gpt-4o", root),
+            Ok(Some(SlashCommand::Model {
+                model: Some("gpt-4o".to_string())
+            }))
+        );
+        assert_eq!(
+            parse_slash_command("/review src/main.rs", root),
+            Ok(Some(SlashCommand::Prompt(
+                "# Review a file\nReview src/main.rs for bugs.".to_string()
+            )))
+        );
+        assert!(parse_slash_command("/deploy", root).is_err());
+
+        let listed = list_slash_commands(root);
+        let names: Vec<&str> = listed.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["clear", "compact", "model", "test", "review"]);
+        assert_eq!(listed[4].description, "Review a file");
+        assert_eq!(listed[4].source, SlashCommandSource::Project);
+    }
+}
@@ -4,12 +4,30 @@
 //! all shared state across the application including terminal sessions,
 //! AI providers, and project configuration.
 
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{oneshot, Mutex, RwLock};
 
-use crate::providers::{Provider, AnthropicProvider, OpenAIProvider};
+use crate::budget::{AgentRun, BudgetLimit, RunBudget, RunUsage};
+use crate::context::{self, InstructionFile};
+use crate::index::SemanticIndex;
+use crate::providers::{anthropic, openai, Provider, AnthropicProvider, OpenAIProvider, Usage};
+use crate::providers::scheduler::RequestScheduler;
+use crate::providers::embeddings::{EmbeddingProvider, OpenAIEmbeddingProvider};
+use crate::providers::images::{ImageProvider, OpenAIImageProvider, StabilityImageProvider};
+use crate::prompts::{PromptLibrary, PROMPTS_FILE};
+use crate::recent::{RecentFiles, RECENT_FILES_FILE};
+use crate::saved_terminals::{SavedTerminals, TERMINALS_FILE};
+use crate::sessions::{SessionStore, SESSIONS_DB};
+use crate::settings::{Settings, SETTINGS_FILE};
+use crate::watcher::ProjectWatcher;
+use crate::workspace::{Workspace, WorkspaceError};
+use crate::tools::git::GitStatusCache;
+use crate::tools::{
+    self, AuditEntry, ClipboardTool, PermissionDecision, ProjectMemory, SnapshotStore, TodoList,
+    Tool, ToolConcurrency, ToolRegistry, TextIndex, CLIPBOARD_TOOL, SEMANTIC_SEARCH_TOOL,
+};
 
 /// Central application state shared across all Tauri commands
 pub struct AppState {
@@ -19,8 +37,95 @@ pub struct AppState {
     /// Current active provider name
     pub active_provider: RwLock<Option<String>>,
 
-    /// Current project root path
-    pub project_path: RwLock<Option<PathBuf>>,
+    /// Providers set to small, cheap models for background work such as
+    /// titling sessions, keyed like `providers`
+    pub utility_providers: RwLock<HashMap<String, Arc<dyn Provider>>>,
+
+    /// Queues provider requests to stay within each provider's limits
+    pub request_scheduler: RequestScheduler,
+
+    /// Available image generation providers
+    pub image_providers: RwLock<HashMap<String, Arc<dyn ImageProvider>>>,
+
+    /// Available text embedding providers
+    pub embedding_providers: RwLock<HashMap<String, Arc<dyn EmbeddingProvider>>>,
+
+    /// The open project roots; the first is the current project
+    pub workspace: RwLock<Workspace>,
+
+    /// Backend settings
+    pub settings: RwLock<Settings>,
+
+    /// Directory for persisted application data
+    pub data_dir: RwLock<Option<PathBuf>>,
+
+    /// Tools available to AI assistants
+    pub tool_registry: RwLock<ToolRegistry>,
+
+    /// Limits on how many tool calls use each resource at once
+    pub tool_concurrency: ToolConcurrency,
+
+    /// Tools the user has "always allowed", keyed by session ID
+    pub tool_allow_rules: RwLock<HashMap<String, HashSet<String>>>,
+
+    /// Tool permission requests awaiting a response from the frontend
+    pub pending_permissions: Mutex<HashMap<String, oneshot::Sender<PermissionDecision>>>,
+
+    /// Streaming responses in progress, keyed by stream ID, with the sender
+    /// that cancels each
+    pub active_streams: Mutex<HashMap<String, oneshot::Sender<()>>>,
+
+    /// Agent runs with a budget, keyed by session ID
+    pub agent_runs: Mutex<HashMap<String, AgentRun>>,
+
+    /// Paused agent runs awaiting the user's decision to continue, keyed by request ID
+    pub pending_budget_confirmations: Mutex<HashMap<String, oneshot::Sender<bool>>>,
+
+    /// Files changed by AI edits, keyed by session ID, for undo
+    pub edit_snapshots: RwLock<HashMap<String, Arc<SnapshotStore>>>,
+
+    /// The model's task lists, keyed by session ID
+    pub todo_lists: RwLock<HashMap<String, Arc<TodoList>>>,
+
+    /// Long-term memories, keyed by project root
+    pub project_memories: RwLock<HashMap<PathBuf, Arc<ProjectMemory>>>,
+
+    /// Files open in the editor, relative to the project root, as last
+    /// reported by the frontend
+    pub open_files: RwLock<Vec<String>>,
+
+    /// The current project's semantic index, once it has been built
+    pub semantic_index: RwLock<Option<Arc<SemanticIndex>>>,
+
+    /// The current project's text index, if one has been built for fast grep
+    pub text_index: RwLock<Option<Arc<TextIndex>>>,
+
+    /// Watch each workspace root for file changes, once started
+    pub watchers: Mutex<Vec<ProjectWatcher>>,
+
+    /// Git statuses of watched repositories, until their files change
+    pub git_status: GitStatusCache,
+
+    /// Instruction files found in the current project, discovered on first use
+    pub instruction_paths: RwLock<Option<Vec<PathBuf>>>,
+
+    /// Worktrees of the current project that sessions work in instead of
+    /// the project itself, keyed by session ID
+    pub session_worktrees: RwLock<HashMap<String, PathBuf>>,
+
+    /// Saved chat sessions, once the app data directory is known
+    pub sessions: RwLock<Option<Arc<SessionStore>>>,
+
+    /// Saved prompts; kept in memory until the app data directory is known
+    pub prompt_library: RwLock<Arc<PromptLibrary>>,
+
+    /// Files the user opened and edited recently; kept in memory until the
+    /// app data directory is known
+    pub recent_files: RwLock<Arc<RecentFiles>>,
+
+    /// Terminals to relaunch after a restart; kept in memory until the app
+    /// data directory is known
+    pub saved_terminals: RwLock<Arc<SavedTerminals>>,
 }
 
 impl AppState {
@@ -29,18 +134,105 @@ impl AppState {
         Self {
             providers: RwLock::new(HashMap::new()),
             active_provider: RwLock::new(None),
-            project_path: RwLock::new(None),
+            utility_providers: RwLock::new(HashMap::new()),
+            request_scheduler: RequestScheduler::default(),
+            image_providers: RwLock::new(HashMap::new()),
+            embedding_providers: RwLock::new(HashMap::new()),
+            workspace: RwLock::new(Workspace::default()),
+            settings: RwLock::new(Settings::default()),
+            data_dir: RwLock::new(None),
+            tool_registry: RwLock::new(ToolRegistry::with_builtin_tools()),
+            tool_concurrency: ToolConcurrency::default(),
+            tool_allow_rules: RwLock::new(HashMap::new()),
+            pending_permissions: Mutex::new(HashMap::new()),
+            active_streams: Mutex::new(HashMap::new()),
+            agent_runs: Mutex::new(HashMap::new()),
+            pending_budget_confirmations: Mutex::new(HashMap::new()),
+            edit_snapshots: RwLock::new(HashMap::new()),
+            todo_lists: RwLock::new(HashMap::new()),
+            project_memories: RwLock::new(HashMap::new()),
+            open_files: RwLock::new(Vec::new()),
+            semantic_index: RwLock::new(None),
+            text_index: RwLock::new(None),
+            watchers: Mutex::new(Vec::new()),
+            git_status: GitStatusCache::default(),
+            instruction_paths: RwLock::new(None),
+            session_worktrees: RwLock::new(HashMap::new()),
+            sessions: RwLock::new(None),
+            prompt_library: RwLock::new(Arc::new(PromptLibrary::new())),
+            recent_files: RwLock::new(Arc::new(RecentFiles::new())),
+            saved_terminals: RwLock::new(Arc::new(SavedTerminals::new())),
         }
     }
 
+    /// Set the app data directory and load persisted state from it
+    pub async fn init_data_dir(&self, dir: PathBuf) {
+        let settings = Settings::load(&dir.join(SETTINGS_FILE));
+        self.sync_optional_tools(&settings).await;
+        self.request_scheduler.set_limits(settings.request_limits.clone());
+        *self.settings.write().await = settings;
+        match SessionStore::open(&dir.join(SESSIONS_DB)) {
+            Ok(store) => *self.sessions.write().await = Some(Arc::new(store)),
+            Err(e) => log::warn!("Failed to open session database: {}", e),
+        }
+        *self.prompt_library.write().await = Arc::new(PromptLibrary::load(&dir.join(PROMPTS_FILE)));
+        *self.recent_files.write().await = Arc::new(RecentFiles::load(&dir.join(RECENT_FILES_FILE)));
+        *self.saved_terminals.write().await =
+            Arc::new(SavedTerminals::load(&dir.join(TERMINALS_FILE)));
+        *self.data_dir.write().await = Some(dir);
+    }
+
+    /// Get the app data directory, if it has been initialized
+    pub async fn get_data_dir(&self) -> Option<PathBuf> {
+        let data_dir = self.data_dir.read().await;
+        data_dir.clone()
+    }
+
+    /// Get the session store, if the app data directory has been initialized
+    pub async fn session_store(&self) -> Result<Arc<SessionStore>, String> {
+        self.sessions
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| "Session storage is not available".to_string())
+    }
+
+    /// Get the prompt library
+    pub async fn prompt_library(&self) -> Arc<PromptLibrary> {
+        self.prompt_library.read().await.clone()
+    }
+
+    /// Get the recently used files
+    pub async fn recent_files(&self) -> Arc<RecentFiles> {
+        self.recent_files.read().await.clone()
+    }
+
+    /// Get the terminals to relaunch after a restart
+    pub async fn saved_terminals(&self) -> Arc<SavedTerminals> {
+        self.saved_terminals.read().await.clone()
+    }
+
     /// Initialize providers from environment variables
     pub async fn init_providers(&self) {
+        let timeouts = self.get_settings().await.provider_timeouts;
         let mut providers = self.providers.write().await;
+        let mut utility_providers = self.utility_providers.write().await;
 
         // Try to initialize Anthropic provider
         if let Ok(api_key) = std::env::var("ANTHROPIC_API_KEY") {
             if !api_key.is_empty() {
-                let provider = AnthropicProvider::new(api_key);
+                let mut utility = AnthropicProvider::new(api_key.clone());
+                utility.set_timeouts(timeouts);
+                utility.set_model(anthropic::UTILITY_MODEL);
+                utility_providers.insert("anthropic".to_string(), Arc::new(utility) as Arc<dyn Provider>);
+
+                let mut provider = AnthropicProvider::new(api_key);
+                provider.set_timeouts(timeouts);
+                provider.set_thinking_budget(
+                    std::env::var("ANTHROPIC_THINKING_BUDGET")
+                        .ok()
+                        .and_then(|b| b.parse().ok()),
+                );
                 providers.insert("anthropic".to_string(), Arc::new(provider) as Arc<dyn Provider>);
                 log::info!("Initialized Anthropic provider");
 
@@ -55,7 +247,13 @@ impl AppState {
         // Try to initialize OpenAI provider
         if let Ok(api_key) = std::env::var("OPENAI_API_KEY") {
             if !api_key.is_empty() {
-                let provider = OpenAIProvider::new(api_key);
+                let mut utility = OpenAIProvider::new(api_key.clone());
+                utility.set_timeouts(timeouts);
+                utility.set_model(openai::UTILITY_MODEL);
+                utility_providers.insert("openai".to_string(), Arc::new(utility) as Arc<dyn Provider>);
+
+                let mut provider = OpenAIProvider::new(api_key);
+                provider.set_timeouts(timeouts);
                 providers.insert("openai".to_string(), Arc::new(provider) as Arc<dyn Provider>);
                 log::info!("Initialized OpenAI provider");
 
@@ -70,6 +268,66 @@ impl AppState {
         if providers.is_empty() {
             log::warn!("No AI providers configured. Set ANTHROPIC_API_KEY or OPENAI_API_KEY environment variables.");
         }
+
+        drop(providers);
+        drop(utility_providers);
+        self.init_image_providers().await;
+        self.init_embedding_providers().await;
+    }
+
+    /// Initialize image generation providers from environment variables
+    async fn init_image_providers(&self) {
+        let mut image_providers = self.image_providers.write().await;
+
+        if let Ok(api_key) = std::env::var("OPENAI_API_KEY") {
+            if !api_key.is_empty() {
+                let provider = OpenAIImageProvider::new(api_key);
+                image_providers.insert("openai".to_string(), Arc::new(provider) as Arc<dyn ImageProvider>);
+                log::info!("Initialized OpenAI image provider");
+            }
+        }
+
+        if let Ok(api_key) = std::env::var("STABILITY_API_KEY") {
+            if !api_key.is_empty() {
+                let provider = StabilityImageProvider::new(api_key);
+                image_providers.insert("stability".to_string(), Arc::new(provider) as Arc<dyn ImageProvider>);
+                log::info!("Initialized Stability image provider");
+            }
+        }
+    }
+
+    /// Get an image provider by name, or the first available one
+    pub async fn get_image_provider(&self, name: Option<&str>) -> Option<Arc<dyn ImageProvider>> {
+        let image_providers = self.image_providers.read().await;
+        match name {
+            Some(name) => image_providers.get(name).cloned(),
+            None => image_providers
+                .get("openai")
+                .or_else(|| image_providers.values().next())
+                .cloned(),
+        }
+    }
+
+    /// Initialize text embedding providers from environment variables
+    async fn init_embedding_providers(&self) {
+        let mut embedding_providers = self.embedding_providers.write().await;
+
+        if let Ok(api_key) = std::env::var("OPENAI_API_KEY") {
+            if !api_key.is_empty() {
+                let provider = OpenAIEmbeddingProvider::new(api_key);
+                embedding_providers.insert("openai".to_string(), Arc::new(provider) as Arc<dyn EmbeddingProvider>);
+                log::info!("Initialized OpenAI embedding provider");
+            }
+        }
+    }
+
+    /// Get an embedding provider by name, or the first available one
+    pub async fn get_embedding_provider(&self, name: Option<&str>) -> Option<Arc<dyn EmbeddingProvider>> {
+        let embedding_providers = self.embedding_providers.read().await;
+        match name {
+            Some(name) => embedding_providers.get(name).cloned(),
+            None => embedding_providers.values().next().cloned(),
+        }
     }
 
     /// Get a provider by name
@@ -88,6 +346,16 @@ impl AppState {
         }
     }
 
+    /// Get the utility provider for background work, falling back to the
+    /// active provider if it has none
+    pub async fn get_utility_provider(&self) -> Option<Arc<dyn Provider>> {
+        let active = self.active_provider.read().await.clone()?;
+        if let Some(utility) = self.utility_providers.read().await.get(&active) {
+            return Some(utility.clone());
+        }
+        self.get_provider(&active).await
+    }
+
     /// Set the active provider
     pub async fn set_active_provider(&self, name: &str) -> Result<(), String> {
         let providers = self.providers.read().await;
@@ -100,16 +368,411 @@ impl AppState {
         }
     }
 
-    /// Set the current project path
+    /// Set the current project path, replacing every workspace root
     pub async fn set_project_path(&self, path: PathBuf) {
-        let mut project_path = self.project_path.write().await;
-        *project_path = Some(path);
+        let mut workspace = self.workspace.write().await;
+        if workspace.primary() != Some(path.as_path()) {
+            self.reset_project().await;
+        }
+        if workspace.roots() != [path.clone()] {
+            self.watchers.lock().await.clear();
+        }
+        workspace.set_primary(path);
+    }
+
+    /// Forget state that belongs to the current project, when it changes
+    async fn reset_project(&self) {
+        // The semantic index belongs to the previous project
+        self.tool_registry.write().await.unregister(SEMANTIC_SEARCH_TOOL);
+        *self.semantic_index.write().await = None;
+        *self.text_index.write().await = None;
+        *self.instruction_paths.write().await = None;
+        self.session_worktrees.write().await.clear();
+        self.open_files.write().await.clear();
+    }
+
+    /// Get every workspace root, the current project first
+    pub async fn workspace_roots(&self) -> Vec<PathBuf> {
+        self.workspace.read().await.roots().to_vec()
+    }
+
+    /// Add a root to the workspace
+    ///
+    /// The root isn't watched until watching is started again.
+    pub async fn add_workspace_root(&self, path: PathBuf) -> Result<(), WorkspaceError> {
+        self.workspace.write().await.add_root(path)
+    }
+
+    /// Remove a root from the workspace and stop watching it
+    ///
+    /// Removing the current project makes the next root current.
+    pub async fn remove_workspace_root(&self, path: &Path) -> Result<(), WorkspaceError> {
+        let mut workspace = self.workspace.write().await;
+        let was_primary = workspace.primary() == Some(path);
+        workspace.remove_root(path)?;
+        if was_primary {
+            self.reset_project().await;
+        }
+        let watched = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.watchers.lock().await.retain(|w| w.root() != watched);
+        Ok(())
+    }
+
+    /// Load the current project's instruction files
+    ///
+    /// The files are found once per project, or again when `rediscover` is
+    /// set; their contents are read on every call so edits apply right away.
+    pub async fn project_instructions(&self, rediscover: bool) -> Vec<InstructionFile> {
+        let Some(root) = self.get_project_path().await else {
+            return Vec::new();
+        };
+
+        let cached = self.instruction_paths.read().await.clone();
+        let paths = match cached {
+            Some(paths) if !rediscover => paths,
+            _ => {
+                let paths = context::find_instruction_files(&root);
+                *self.instruction_paths.write().await = Some(paths.clone());
+                paths
+            }
+        };
+        context::load_instruction_files(&root, &paths)
     }
 
     /// Get the current project path
     pub async fn get_project_path(&self) -> Option<PathBuf> {
-        let project_path = self.project_path.read().await;
-        project_path.clone()
+        let workspace = self.workspace.read().await;
+        workspace.primary().map(|p| p.to_path_buf())
+    }
+
+    /// Make a session work in a worktree instead of the current project, or
+    /// back in the project with `None`
+    pub async fn set_session_worktree(&self, session_id: &str, worktree: Option<PathBuf>) {
+        let mut worktrees = self.session_worktrees.write().await;
+        match worktree {
+            Some(worktree) => worktrees.insert(session_id.to_string(), worktree),
+            None => worktrees.remove(session_id),
+        };
+    }
+
+    /// Get the worktree a session works in, if it has one
+    pub async fn session_worktree(&self, session_id: &str) -> Option<PathBuf> {
+        self.session_worktrees.read().await.get(session_id).cloned()
+    }
+
+    /// Get the directory a session works in: its worktree, or else the
+    /// current project
+    pub async fn session_project_path(&self, session_id: &str) -> Option<PathBuf> {
+        match self.session_worktree(session_id).await {
+            Some(worktree) => Some(worktree),
+            None => self.get_project_path().await,
+        }
+    }
+
+    /// Get a copy of the current settings
+    pub async fn get_settings(&self) -> Settings {
+        let settings = self.settings.read().await;
+        settings.clone()
+    }
+
+    /// Replace the current settings and persist them
+    ///
+    /// Providers are rebuilt if their timeouts changed.
+    pub async fn set_settings(&self, new_settings: Settings) -> Result<Settings, String> {
+        self.update_settings(|settings| *settings = new_settings).await
+    }
+
+    /// Change the current settings and persist them
+    ///
+    /// The settings stay locked from reading to writing, so concurrent
+    /// updates can't overwrite each other's changes.
+    pub async fn update_settings(
+        &self,
+        change: impl FnOnce(&mut Settings),
+    ) -> Result<Settings, String> {
+        let mut settings = self.settings.write().await;
+        let mut new_settings = settings.clone();
+        change(&mut new_settings);
+        let new_settings = new_settings.validated();
+        let timeouts_changed = settings.provider_timeouts != new_settings.provider_timeouts;
+        *settings = new_settings;
+
+        if let Some(dir) = self.get_data_dir().await {
+            settings
+                .save(&dir.join(SETTINGS_FILE))
+                .map_err(|e| format!("Failed to save settings: {}", e))?;
+        }
+
+        let result = settings.clone();
+        drop(settings);
+        self.sync_optional_tools(&result).await;
+        self.request_scheduler.set_limits(result.request_limits.clone());
+
+        if timeouts_changed {
+            self.init_providers().await;
+        }
+
+        Ok(result)
+    }
+
+    /// Register or remove the tools the user opts into in settings
+    async fn sync_optional_tools(&self, settings: &Settings) {
+        let mut registry = self.tool_registry.write().await;
+        if settings.clipboard_tool {
+            registry.register(Arc::new(ClipboardTool));
+        } else {
+            registry.unregister(CLIPBOARD_TOOL);
+        }
+    }
+
+    /// Register a tool, replacing any existing tool with the same name
+    pub async fn register_tool(&self, tool: Arc<dyn Tool>) {
+        self.tool_registry.write().await.register(tool);
+    }
+
+    /// Check whether a tool has been "always allowed" in a session
+    pub async fn is_tool_allowed(&self, session_id: &str, tool_name: &str) -> bool {
+        let rules = self.tool_allow_rules.read().await;
+        rules
+            .get(session_id)
+            .is_some_and(|tools| tools.contains(tool_name))
+    }
+
+    /// Always allow a tool for the rest of a session
+    pub async fn allow_tool(&self, session_id: &str, tool_name: &str) {
+        let mut rules = self.tool_allow_rules.write().await;
+        rules
+            .entry(session_id.to_string())
+            .or_default()
+            .insert(tool_name.to_string());
+    }
+
+    /// Forget the "always allow" rules of a session
+    pub async fn clear_tool_permissions(&self, session_id: &str) {
+        let mut rules = self.tool_allow_rules.write().await;
+        rules.remove(session_id);
+    }
+
+    /// Append an executed tool call to a session's audit log
+    pub async fn record_tool_call(&self, session_id: &str, entry: &AuditEntry) {
+        let Some(dir) = self.get_data_dir().await else {
+            return;
+        };
+        let path = tools::audit_log_path(&dir, session_id);
+        if let Err(e) = tools::append_audit_entry(&path, entry) {
+            log::warn!("Failed to write tool audit log {}: {}", path.display(), e);
+        }
+    }
+
+    /// Read a session's tool audit log, oldest entry first
+    pub async fn tool_audit_log(&self, session_id: &str) -> Result<Vec<AuditEntry>, String> {
+        let dir = self
+            .get_data_dir()
+            .await
+            .ok_or_else(|| "App data directory is not initialized".to_string())?;
+        tools::read_audit_log(&tools::audit_log_path(&dir, session_id))
+            .map_err(|e| format!("Failed to read tool audit log: {}", e))
+    }
+
+    /// Get the snapshot store recording a session's edits, creating it if needed
+    pub async fn snapshot_store(&self, session_id: &str) -> Arc<SnapshotStore> {
+        let mut stores = self.edit_snapshots.write().await;
+        stores
+            .entry(session_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Get a session's task list, loading it from the app data directory if needed
+    pub async fn todo_list(&self, session_id: &str) -> Arc<TodoList> {
+        if let Some(todos) = self.todo_lists.read().await.get(session_id) {
+            return todos.clone();
+        }
+
+        let todos = match self.get_data_dir().await {
+            Some(dir) => TodoList::load(tools::todo_list_path(&dir, session_id)),
+            None => TodoList::new(),
+        };
+        let mut lists = self.todo_lists.write().await;
+        lists
+            .entry(session_id.to_string())
+            .or_insert_with(|| Arc::new(todos))
+            .clone()
+    }
+
+    /// Get the current project's memory, loading it from the app data directory if needed
+    ///
+    /// # Returns
+    /// `None` if no project is open
+    pub async fn project_memory(&self) -> Option<Arc<ProjectMemory>> {
+        let project_root = self.get_project_path().await?;
+        if let Some(memory) = self.project_memories.read().await.get(&project_root) {
+            return Some(memory.clone());
+        }
+
+        let memory = match self.get_data_dir().await {
+            Some(dir) => ProjectMemory::load(tools::memory_path(&dir, &project_root)),
+            None => ProjectMemory::new(),
+        };
+        let mut memories = self.project_memories.write().await;
+        Some(
+            memories
+                .entry(project_root)
+                .or_insert_with(|| Arc::new(memory))
+                .clone(),
+        )
+    }
+
+    /// Register a pending permission request, returning the receiver for its decision
+    pub async fn register_permission_request(
+        &self,
+        request_id: &str,
+    ) -> oneshot::Receiver<PermissionDecision> {
+        let (tx, rx) = oneshot::channel();
+        let mut pending = self.pending_permissions.lock().await;
+        pending.insert(request_id.to_string(), tx);
+        rx
+    }
+
+    /// Drop a pending permission request that will not be answered
+    pub async fn cancel_permission_request(&self, request_id: &str) {
+        let mut pending = self.pending_permissions.lock().await;
+        pending.remove(request_id);
+    }
+
+    /// Deliver the user's decision for a pending permission request
+    pub async fn resolve_permission_request(
+        &self,
+        request_id: &str,
+        decision: PermissionDecision,
+    ) -> Result<(), String> {
+        let mut pending = self.pending_permissions.lock().await;
+        let tx = pending
+            .remove(request_id)
+            .ok_or_else(|| format!("No pending permission request '{}'", request_id))?;
+        tx.send(decision)
+            .map_err(|_| format!("Permission request '{}' is no longer waiting", request_id))
+    }
+
+    /// Register a streaming response, returning the receiver that signals its cancellation
+    pub async fn register_stream(&self, stream_id: &str) -> Result<oneshot::Receiver<()>, String> {
+        let mut streams = self.active_streams.lock().await;
+        if streams.contains_key(stream_id) {
+            return Err(format!("Stream '{}' is already running", stream_id));
+        }
+        let (tx, rx) = oneshot::channel();
+        streams.insert(stream_id.to_string(), tx);
+        Ok(rx)
+    }
+
+    /// Forget a streaming response that has ended
+    pub async fn end_stream(&self, stream_id: &str) {
+        self.active_streams.lock().await.remove(stream_id);
+    }
+
+    /// Cancel a streaming response
+    pub async fn cancel_stream(&self, stream_id: &str) -> Result<(), String> {
+        let tx = self
+            .active_streams
+            .lock()
+            .await
+            .remove(stream_id)
+            .ok_or_else(|| format!("No stream '{}' is running", stream_id))?;
+        // The stream may have ended in the meantime
+        let _ = tx.send(());
+        Ok(())
+    }
+
+    /// Start an agent run in a session, replacing any run already going
+    pub async fn start_agent_run(&self, session_id: &str, budget: RunBudget) {
+        let mut runs = self.agent_runs.lock().await;
+        runs.insert(session_id.to_string(), AgentRun::new(budget));
+    }
+
+    /// End a session's agent run, returning what it used
+    pub async fn end_agent_run(&self, session_id: &str) -> Option<RunUsage> {
+        let mut runs = self.agent_runs.lock().await;
+        runs.remove(session_id).map(|run| run.usage())
+    }
+
+    /// Check a session's agent run against its budget
+    ///
+    /// # Returns
+    /// The limit reached, with the run's limits and usage, or `None` if the
+    /// session has no run or the run is within its budget
+    pub async fn check_agent_run(
+        &self,
+        session_id: &str,
+        pending_tool_calls: u32,
+    ) -> Option<(BudgetLimit, RunBudget, RunUsage)> {
+        let runs = self.agent_runs.lock().await;
+        let run = runs.get(session_id)?;
+        let limit = run.exceeded(pending_tool_calls)?;
+        Some((limit, run.limits(), run.usage()))
+    }
+
+    /// Let a session's agent run continue past its limits
+    pub async fn extend_agent_run(&self, session_id: &str) {
+        if let Some(run) = self.agent_runs.lock().await.get_mut(session_id) {
+            run.extend();
+        }
+    }
+
+    /// Count a response against a session's agent run, if it has one
+    pub async fn record_run_response(&self, session_id: &str, usage: &Usage, cost: Option<f64>) {
+        if let Some(run) = self.agent_runs.lock().await.get_mut(session_id) {
+            run.record_response(usage, cost);
+        }
+    }
+
+    /// Count a sub-agent's usage against a session's agent run, if it has one
+    pub async fn record_run_sub_agent(&self, session_id: &str, usage: &RunUsage) {
+        if let Some(run) = self.agent_runs.lock().await.get_mut(session_id) {
+            run.record_sub_agent(usage);
+        }
+    }
+
+    /// Count tool calls against a session's agent run, if it has one
+    pub async fn record_run_tool_calls(&self, session_id: &str, count: u32) {
+        if let Some(run) = self.agent_runs.lock().await.get_mut(session_id) {
+            run.record_tool_calls(count);
+        }
+    }
+
+    /// Register a paused agent run and get a receiver for the user's decision
+    pub async fn register_budget_confirmation(&self, request_id: &str) -> oneshot::Receiver<bool> {
+        let (tx, rx) = oneshot::channel();
+        let mut pending = self.pending_budget_confirmations.lock().await;
+        pending.insert(request_id.to_string(), tx);
+        rx
+    }
+
+    /// Drop a budget confirmation that will not be answered
+    pub async fn cancel_budget_confirmation(&self, request_id: &str) {
+        let mut pending = self.pending_budget_confirmations.lock().await;
+        pending.remove(request_id);
+    }
+
+    /// Deliver the user's decision to continue or stop a paused agent run
+    pub async fn resolve_budget_confirmation(
+        &self,
+        request_id: &str,
+        proceed: bool,
+    ) -> Result<(), String> {
+        let mut pending = self.pending_budget_confirmations.lock().await;
+        let tx = pending
+            .remove(request_id)
+            .ok_or_else(|| format!("No paused agent run '{}'", request_id))?;
+        tx.send(proceed)
+            .map_err(|_| format!("Agent run '{}' is no longer waiting", request_id))
+    }
+
+    /// Get the default system prompt configured for a provider/model
+    pub async fn default_system_prompt(&self, provider: &str, model: &str) -> Option<String> {
+        let settings = self.settings.read().await;
+        settings
+            .default_system_prompt(provider, model)
+            .map(|s| s.to_string())
     }
 }
 
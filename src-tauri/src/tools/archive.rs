@@ -0,0 +1,288 @@
+//! Zip and tar.gz archives
+//!
+//! Archives are extracted and created in-process, so downloaded
+//! dependencies and exported session bundles can be handled without
+//! shelling out to `unzip` or `tar`. Entries whose paths would land outside
+//! the destination directory are refused.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::{ToolError, ToolResult};
+
+/// An archive format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Tell the format of an archive from its file name
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::str::FromStr for ArchiveFormat {
+    type Err = ToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().trim_start_matches('.') {
+            "zip" => Ok(ArchiveFormat::Zip),
+            "tar.gz" | "tgz" => Ok(ArchiveFormat::TarGz),
+            _ => Err(ToolError::InvalidArgument(format!(
+                "Unsupported archive format '{}'; use zip or tar.gz",
+                s
+            ))),
+        }
+    }
+}
+
+fn archive_error(e: impl std::fmt::Display) -> ToolError {
+    ToolError::InvalidArgument(format!("Invalid archive: {}", e))
+}
+
+/// Extract an archive into a directory, creating it if needed
+///
+/// The format is told from the archive's file name.
+///
+/// # Returns
+/// The number of files extracted
+pub fn extract_archive(path: &str, dest: &str) -> ToolResult<usize> {
+    let archive_path = Path::new(path);
+    if !archive_path.is_file() {
+        return Err(ToolError::PathNotFound(path.to_string()));
+    }
+    let format = ArchiveFormat::from_path(archive_path).ok_or_else(|| {
+        ToolError::InvalidArgument(format!("{} is not a zip or tar.gz archive", path))
+    })?;
+    let dest = Path::new(dest);
+    fs::create_dir_all(dest)?;
+    let file = fs::File::open(archive_path)?;
+
+    let mut extracted = 0;
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = ZipArchive::new(file).map_err(archive_error)?;
+            for i in 0..archive.len() {
+                let mut entry = archive.by_index(i).map_err(archive_error)?;
+                let relative = entry.enclosed_name().ok_or_else(|| {
+                    archive_error(format!("'{}' is outside the archive", entry.name()))
+                })?;
+                let target = dest.join(relative);
+                if entry.is_dir() {
+                    fs::create_dir_all(&target)?;
+                    continue;
+                }
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                io::copy(&mut entry, &mut fs::File::create(&target)?)?;
+                #[cfg(unix)]
+                if let Some(mode) = entry.unix_mode() {
+                    use std::os::unix::fs::PermissionsExt;
+                    fs::set_permissions(&target, fs::Permissions::from_mode(mode & 0o777))?;
+                }
+                extracted += 1;
+            }
+        }
+        ArchiveFormat::TarGz => {
+            let mut archive = tar::Archive::new(GzDecoder::new(file));
+            for entry in archive.entries().map_err(archive_error)? {
+                let mut entry = entry.map_err(archive_error)?;
+                let is_file = entry.header().entry_type().is_file();
+                // unpack_in skips entries that would escape the destination
+                if !entry.unpack_in(dest)? {
+                    let name = entry.path().map(|p| p.display().to_string());
+                    return Err(archive_error(format!(
+                        "'{}' is outside the archive",
+                        name.unwrap_or_default()
+                    )));
+                }
+                if is_file {
+                    extracted += 1;
+                }
+            }
+        }
+    }
+    Ok(extracted)
+}
+
+/// Every file under `path`, with its name in the archive
+fn files_to_archive(
+    path: &Path,
+    name: PathBuf,
+    files: &mut Vec<(PathBuf, String)>,
+) -> io::Result<()> {
+    if path.is_dir() {
+        let mut entries: Vec<_> = fs::read_dir(path)?.flatten().collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            files_to_archive(&entry.path(), name.join(entry.file_name()), files)?;
+        }
+    } else if path.is_file() {
+        files.push((
+            path.to_path_buf(),
+            name.to_string_lossy().replace('\\', "/"),
+        ));
+    }
+    Ok(())
+}
+
+/// Create an archive of files and directories
+///
+/// Each path is stored under its own name, with directories included
+/// recursively.
+///
+/// # Returns
+/// The number of files archived
+pub fn create_archive(paths: &[String], dest: &str, format: ArchiveFormat) -> ToolResult<usize> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = Path::new(path);
+        if !path.exists() {
+            return Err(ToolError::PathNotFound(path.display().to_string()));
+        }
+        let name = path
+            .canonicalize()?
+            .file_name()
+            .map(PathBuf::from)
+            .ok_or_else(|| {
+                ToolError::InvalidArgument(format!("Can't archive {}", path.display()))
+            })?;
+        files_to_archive(path, name, &mut files)?;
+    }
+
+    let dest = Path::new(dest);
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let file = fs::File::create(dest)?;
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut writer = ZipWriter::new(file);
+            for (path, name) in &files {
+                let mut options =
+                    SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    options = options.unix_permissions(fs::metadata(path)?.permissions().mode());
+                }
+                writer
+                    .start_file(name.as_str(), options)
+                    .map_err(archive_error)?;
+                io::copy(&mut fs::File::open(path)?, &mut writer)?;
+            }
+            writer.finish().map_err(archive_error)?;
+        }
+        ArchiveFormat::TarGz => {
+            let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+            for (path, name) in &files {
+                builder.append_path_with_name(path, name)?;
+            }
+            builder.into_inner()?.finish()?;
+        }
+    }
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_archives_round_trip() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(project.join("src/main.rs"), "fn main() {}").unwrap();
+        let readme = dir.path().join("README.md");
+        fs::write(&readme, "# Hi").unwrap();
+        let paths = vec![
+            project.to_string_lossy().to_string(),
+            readme.to_string_lossy().to_string(),
+        ];
+
+        for (file_name, format) in [("out.zip", "zip"), ("out.tar.gz", "tar.gz")] {
+            let archive = dir.path().join(file_name);
+            let archive = archive.to_str().unwrap();
+            assert_eq!(
+                create_archive(&paths, archive, format.parse().unwrap()).unwrap(),
+                2
+            );
+
+            let dest = dir.path().join(format!("extracted-{}", format));
+            assert_eq!(extract_archive(archive, dest.to_str().unwrap()).unwrap(), 2);
+            assert_eq!(
+                fs::read_to_string(dest.join("project/src/main.rs")).unwrap(),
+                "fn main() {}"
+            );
+            assert_eq!(fs::read_to_string(dest.join("README.md")).unwrap(), "# Hi");
+        }
+        assert!("rar".parse::<ArchiveFormat>().is_err());
+    }
+
+    #[test]
+    fn test_zip_entry_outside_destination_is_refused() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("evil.zip");
+        let mut zip = ZipWriter::new(fs::File::create(&archive).unwrap());
+        zip.start_file("../evil.txt", SimpleFileOptions::default())
+            .unwrap();
+        io::Write::write_all(&mut zip, b"evil").unwrap();
+        zip.finish().unwrap();
+
+        let dest = dir.path().join("dest");
+        let result = extract_archive(archive.to_str().unwrap(), dest.to_str().unwrap());
+        assert!(matches!(result, Err(ToolError::InvalidArgument(_))));
+        assert!(!dir.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_tar_absolute_entry_stays_in_destination() {
+        let dir = tempdir().unwrap();
+        let outside = dir.path().join("outside.txt");
+        let archive = dir.path().join("evil.tar.gz");
+        let mut builder = tar::Builder::new(GzEncoder::new(
+            fs::File::create(&archive).unwrap(),
+            Compression::default(),
+        ));
+        // set_path refuses absolute paths, so write the name directly
+        let mut header = tar::Header::new_gnu();
+        let name = outside.to_str().unwrap().as_bytes();
+        header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name);
+        header.set_size(4);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append(&header, &b"evil"[..]).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let dest = dir.path().join("dest");
+        assert_eq!(
+            extract_archive(archive.to_str().unwrap(), dest.to_str().unwrap()).unwrap(),
+            1
+        );
+        assert!(!outside.exists());
+        let relative = outside.strip_prefix("/").unwrap();
+        assert_eq!(fs::read_to_string(dest.join(relative)).unwrap(), "evil");
+    }
+}
@@ -0,0 +1,434 @@
+//! Code structure extraction for the tools system
+//!
+//! This module parses source files with tree-sitter and extracts their
+//! symbols (functions, types, classes, imports), so AI assistants can
+//! navigate large files and read single definitions instead of whole files.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Node, Parser, Tree};
+
+use super::{ToolError, ToolResult};
+
+/// Maximum characters kept from a symbol's first line
+const MAX_SIGNATURE_CHARS: usize = 200;
+
+/// Languages with symbol extraction support
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceLanguage {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl SourceLanguage {
+    /// Detect the language from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            "tsx" => Some(Self::Tsx),
+            "go" => Some(Self::Go),
+            _ => None,
+        }
+    }
+
+    fn grammar(self) -> tree_sitter::Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+}
+
+/// Kind of a symbol in a source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Struct,
+    Enum,
+    Trait,
+    Impl,
+    Class,
+    Interface,
+    Type,
+    Module,
+    Constant,
+    Import,
+}
+
+/// A symbol defined in a source file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The enclosing impl, trait, class or module, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// 1-based line range of the definition
+    pub start_line: usize,
+    pub end_line: usize,
+    /// First line of the definition
+    pub signature: String,
+    #[serde(skip)]
+    byte_range: (usize, usize),
+}
+
+impl Symbol {
+    /// Whether the symbol matches a name, optionally qualified by its parent
+    /// (e.g. `Parser::parse` or `Parser.parse`)
+    fn matches(&self, name: &str) -> bool {
+        if self.name == name {
+            return true;
+        }
+        match &self.parent {
+            Some(parent) => [
+                format!("{}::{}", parent, self.name),
+                format!("{}.{}", parent, self.name),
+            ]
+            .iter()
+            .any(|qualified| qualified == name),
+            None => false,
+        }
+    }
+}
+
+/// The source of a single symbol
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolSource {
+    #[serde(flatten)]
+    pub symbol: Symbol,
+    pub source: String,
+}
+
+fn node_text<'a>(node: Node, source: &'a str) -> &'a str {
+    &source[node.byte_range()]
+}
+
+fn field_text(node: Node, field: &str, source: &str) -> Option<String> {
+    node.child_by_field_name(field)
+        .map(|n| node_text(n, source).to_string())
+}
+
+/// Collapse a node's text onto one line, for imports and signatures
+fn one_line(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() > MAX_SIGNATURE_CHARS {
+        line.chars().take(MAX_SIGNATURE_CHARS).collect::<String>() + "..."
+    } else {
+        line
+    }
+}
+
+/// Classify a node as a symbol, returning its kind and name
+fn classify(
+    node: Node,
+    source: &str,
+    language: SourceLanguage,
+    in_container: bool,
+) -> Option<(SymbolKind, String)> {
+    let name = || field_text(node, "name", source);
+    let function_kind = if in_container {
+        SymbolKind::Method
+    } else {
+        SymbolKind::Function
+    };
+
+    match language {
+        SourceLanguage::Rust => match node.kind() {
+            "function_item" | "function_signature_item" => Some((function_kind, name()?)),
+            "struct_item" | "union_item" => Some((SymbolKind::Struct, name()?)),
+            "enum_item" => Some((SymbolKind::Enum, name()?)),
+            "trait_item" => Some((SymbolKind::Trait, name()?)),
+            "type_item" => Some((SymbolKind::Type, name()?)),
+            "mod_item" => Some((SymbolKind::Module, name()?)),
+            "const_item" | "static_item" => Some((SymbolKind::Constant, name()?)),
+            "macro_definition" => Some((SymbolKind::Function, name()?)),
+            "impl_item" => {
+                let ty = field_text(node, "type", source)?;
+                let name = match field_text(node, "trait", source) {
+                    Some(tr) => format!("{} for {}", tr, ty),
+                    None => ty,
+                };
+                Some((SymbolKind::Impl, name))
+            }
+            "use_declaration" => Some((
+                SymbolKind::Import,
+                one_line(&field_text(node, "argument", source)?),
+            )),
+            _ => None,
+        },
+        SourceLanguage::Python => match node.kind() {
+            "function_definition" => Some((function_kind, name()?)),
+            "class_definition" => Some((SymbolKind::Class, name()?)),
+            "import_statement" | "import_from_statement" => {
+                Some((SymbolKind::Import, one_line(node_text(node, source))))
+            }
+            _ => None,
+        },
+        SourceLanguage::JavaScript | SourceLanguage::TypeScript | SourceLanguage::Tsx => {
+            match node.kind() {
+                "function_declaration"
+                | "generator_function_declaration"
+                | "function_signature" => Some((SymbolKind::Function, name()?)),
+                "method_definition" | "method_signature" | "abstract_method_signature" => {
+                    Some((SymbolKind::Method, name()?))
+                }
+                "class_declaration" | "abstract_class_declaration" => {
+                    Some((SymbolKind::Class, name()?))
+                }
+                "interface_declaration" => Some((SymbolKind::Interface, name()?)),
+                "type_alias_declaration" => Some((SymbolKind::Type, name()?)),
+                "enum_declaration" => Some((SymbolKind::Enum, name()?)),
+                "internal_module" | "module" => Some((SymbolKind::Module, name()?)),
+                "import_statement" => Some((SymbolKind::Import, one_line(node_text(node, source)))),
+                // `const handler = () => {}` and `const fn = function () {}`
+                "variable_declarator" if !in_container => {
+                    let value = node.child_by_field_name("value")?;
+                    match value.kind() {
+                        "arrow_function" | "function_expression" | "function" => {
+                            Some((SymbolKind::Function, name()?))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        SourceLanguage::Go => match node.kind() {
+            "function_declaration" => Some((SymbolKind::Function, name()?)),
+            "method_declaration" => {
+                let receiver = node
+                    .child_by_field_name("receiver")
+                    .map(|r| one_line(node_text(r, source)))
+                    .unwrap_or_default();
+                Some((
+                    SymbolKind::Method,
+                    format!("{} {}", receiver, name()?).trim().to_string(),
+                ))
+            }
+            "type_spec" => {
+                let kind = match node.child_by_field_name("type").map(|t| t.kind()) {
+                    Some("struct_type") => SymbolKind::Struct,
+                    Some("interface_type") => SymbolKind::Interface,
+                    _ => SymbolKind::Type,
+                };
+                Some((kind, name()?))
+            }
+            "const_spec" => Some((SymbolKind::Constant, name()?)),
+            "import_spec" => Some((SymbolKind::Import, field_text(node, "path", source)?)),
+            _ => None,
+        },
+    }
+}
+
+/// Walk the syntax tree collecting symbols. Function bodies are not entered,
+/// so local helpers and variables are left out.
+fn collect_symbols(
+    node: Node,
+    source: &str,
+    language: SourceLanguage,
+    parent: Option<&str>,
+    symbols: &mut Vec<Symbol>,
+) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        match classify(child, source, language, parent.is_some()) {
+            Some((kind, name)) => {
+                let signature =
+                    one_line(node_text(child, source).lines().next().unwrap_or_default());
+                symbols.push(Symbol {
+                    name: name.clone(),
+                    kind,
+                    parent: parent.map(str::to_string),
+                    start_line: child.start_position().row + 1,
+                    end_line: child.end_position().row + 1,
+                    signature,
+                    byte_range: (child.start_byte(), child.end_byte()),
+                });
+
+                if matches!(
+                    kind,
+                    SymbolKind::Impl
+                        | SymbolKind::Trait
+                        | SymbolKind::Class
+                        | SymbolKind::Interface
+                        | SymbolKind::Module
+                ) {
+                    collect_symbols(child, source, language, Some(&name), symbols);
+                }
+            }
+            None => collect_symbols(child, source, language, parent, symbols),
+        }
+    }
+}
+
+/// Parse source text into a syntax tree
+pub fn parse_source(source: &str, language: SourceLanguage) -> ToolResult<Tree> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language.grammar())
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to load grammar: {}", e)))?;
+    parser
+        .parse(source, None)
+        .ok_or_else(|| ToolError::ExecutionFailed("Failed to parse source".to_string()))
+}
+
+/// Parse source text and extract its symbols
+pub fn extract_symbols(source: &str, language: SourceLanguage) -> ToolResult<Vec<Symbol>> {
+    let tree = parse_source(source, language)?;
+
+    let mut symbols = Vec::new();
+    collect_symbols(tree.root_node(), source, language, None, &mut symbols);
+    Ok(symbols)
+}
+
+fn read_source(path: &str) -> ToolResult<(String, SourceLanguage)> {
+    let path_ref = Path::new(path);
+    if !path_ref.is_file() {
+        return Err(ToolError::PathNotFound(path.to_string()));
+    }
+    let language = SourceLanguage::from_path(path_ref).ok_or_else(|| {
+        ToolError::InvalidArgument(format!(
+            "Unsupported language for {}; supported: Rust, Python, JavaScript, TypeScript, Go",
+            path
+        ))
+    })?;
+    Ok((fs::read_to_string(path_ref)?, language))
+}
+
+/// List the symbols defined in a source file
+///
+/// # Arguments
+/// * `path` - The path to the source file
+///
+/// # Returns
+/// The detected language and the symbols in source order
+pub fn list_symbols(path: &str) -> ToolResult<(SourceLanguage, Vec<Symbol>)> {
+    let (source, language) = read_source(path)?;
+    Ok((language, extract_symbols(&source, language)?))
+}
+
+/// Get the source of the symbols with a given name
+///
+/// # Arguments
+/// * `path` - The path to the source file
+/// * `name` - The symbol name, optionally qualified by its parent (`Type::method`)
+///
+/// # Returns
+/// Every matching definition with its source text
+pub fn get_symbol_source(path: &str, name: &str) -> ToolResult<Vec<SymbolSource>> {
+    let (source, language) = read_source(path)?;
+    let matches: Vec<SymbolSource> = extract_symbols(&source, language)?
+        .into_iter()
+        .filter(|symbol| symbol.matches(name))
+        .map(|symbol| SymbolSource {
+            source: source[symbol.byte_range.0..symbol.byte_range.1].to_string(),
+            symbol,
+        })
+        .collect();
+
+    if matches.is_empty() {
+        return Err(ToolError::InvalidArgument(format!(
+            "Symbol '{}' not found in {}",
+            name, path
+        )));
+    }
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const RUST_SOURCE: &str = r#"use std::fs;
+
+pub struct Parser {
+    input: String,
+}
+
+impl Parser {
+    pub fn new(input: String) -> Self {
+        fn helper() {}
+        Self { input }
+    }
+
+    pub fn parse(&self) -> usize {
+        self.input.len()
+    }
+}
+
+fn parse() {}
+"#;
+
+    #[test]
+    fn test_extract_rust_symbols() {
+        let symbols = extract_symbols(RUST_SOURCE, SourceLanguage::Rust).unwrap();
+        let summary: Vec<(SymbolKind, &str, Option<&str>)> = symbols
+            .iter()
+            .map(|s| (s.kind, s.name.as_str(), s.parent.as_deref()))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (SymbolKind::Import, "std::fs", None),
+                (SymbolKind::Struct, "Parser", None),
+                (SymbolKind::Impl, "Parser", None),
+                (SymbolKind::Method, "new", Some("Parser")),
+                (SymbolKind::Method, "parse", Some("Parser")),
+                (SymbolKind::Function, "parse", None),
+            ]
+        );
+        assert_eq!(symbols[1].start_line, 3);
+        assert_eq!(symbols[1].end_line, 5);
+    }
+
+    #[test]
+    fn test_get_symbol_source_qualified() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("parser.rs");
+        fs::write(&path, RUST_SOURCE).unwrap();
+        let path = path.to_str().unwrap();
+
+        assert_eq!(get_symbol_source(path, "parse").unwrap().len(), 2);
+        let method = get_symbol_source(path, "Parser::parse").unwrap();
+        assert_eq!(method.len(), 1);
+        assert!(method[0].source.starts_with("pub fn parse(&self)"));
+        assert!(method[0].source.ends_with('}'));
+    }
+
+    #[test]
+    fn test_extract_python_symbols() {
+        let source = "import os\n\nclass Greeter:\n    def greet(self):\n        pass\n\ndef main():\n    pass\n";
+        let symbols = extract_symbols(source, SourceLanguage::Python).unwrap();
+        let names: Vec<(&str, SymbolKind)> =
+            symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+
+        assert_eq!(
+            names,
+            vec![
+                ("import os", SymbolKind::Import),
+                ("Greeter", SymbolKind::Class),
+                ("greet", SymbolKind::Method),
+                ("main", SymbolKind::Function),
+            ]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Audit log of executed tool calls
+//!
+//! Every tool call the AI runs is appended to a per-session JSON Lines file,
+//! so the user can review exactly what an agent did. Arguments are stored as
+//! a hash rather than verbatim, since they can contain whole files.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::{session_file_stem, ToolResult};
+
+/// Subdirectory of the app data directory holding audit logs
+pub const AUDIT_DIR: &str = "audit";
+
+/// One executed tool call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// ID of the tool call
+    pub id: String,
+    pub tool: String,
+    /// SHA-256 of the call's JSON arguments
+    pub arguments_hash: String,
+    /// Unix timestamp (seconds) when the call started
+    pub timestamp: u64,
+    pub duration_ms: u64,
+    /// Size of the result returned to the model, in bytes
+    pub result_bytes: usize,
+    pub success: bool,
+}
+
+impl AuditEntry {
+    /// Describe a finished tool call
+    pub fn new(
+        id: &str,
+        tool: &str,
+        arguments: &Value,
+        started: SystemTime,
+        duration: Duration,
+        result: &str,
+        success: bool,
+    ) -> Self {
+        Self {
+            id: id.to_string(),
+            tool: tool.to_string(),
+            arguments_hash: hash_arguments(arguments),
+            timestamp: started
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            duration_ms: duration.as_millis() as u64,
+            result_bytes: result.len(),
+            success,
+        }
+    }
+}
+
+/// Hash tool arguments; object keys serialize sorted, so equal arguments hash equally
+pub fn hash_arguments(arguments: &Value) -> String {
+    Sha256::digest(arguments.to_string().as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Path of a session's audit log under the app data directory
+pub fn audit_log_path(data_dir: &Path, session_id: &str) -> PathBuf {
+    data_dir
+        .join(AUDIT_DIR)
+        .join(format!("{}.jsonl", session_file_stem(session_id)))
+}
+
+/// Append an entry to an audit log
+pub fn append_audit_entry(path: &Path, entry: &AuditEntry) -> ToolResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// Read an audit log, oldest entry first
+///
+/// A missing log is empty; lines that fail to parse are skipped.
+pub fn read_audit_log(path: &Path) -> ToolResult<Vec<AuditEntry>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_audit_log_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = audit_log_path(dir.path(), "../session 1");
+        assert_eq!(path, dir.path().join("audit").join("___session_1.jsonl"));
+        assert!(read_audit_log(&path).unwrap().is_empty());
+
+        let arguments = json!({ "path": "src/main.rs", "content": "fn main() {}" });
+        for (id, success) in [("call-1", true), ("call-2", false)] {
+            let entry = AuditEntry::new(
+                id,
+                "write_file",
+                &arguments,
+                SystemTime::now(),
+                Duration::from_millis(12),
+                "{\"success\": true}",
+                success,
+            );
+            append_audit_entry(&path, &entry).unwrap();
+        }
+
+        let entries = read_audit_log(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].id, "call-2");
+        assert!(!entries[1].success);
+        assert_eq!(entries[0].duration_ms, 12);
+        assert_eq!(entries[0].result_bytes, 17);
+        assert_eq!(
+            entries[0].arguments_hash,
+            hash_arguments(&json!({ "content": "fn main() {}", "path": "src/main.rs" }))
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Batched file operations
+//!
+//! The frontend applies a multi-file AI patch as one list of reads, writes,
+//! deletes and moves instead of one IPC call per file. The batch runs as a
+//! transaction: if any operation fails, the files already changed are put
+//! back the way they were.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{file_ops, ToolError, ToolResult};
+
+/// One operation in a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum FileOp {
+    Read { path: String },
+    Write { path: String, content: String },
+    Delete { path: String },
+    Move { from: String, to: String },
+}
+
+impl FileOp {
+    /// The paths the operation touches
+    pub fn paths_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            FileOp::Read { path } | FileOp::Write { path, .. } | FileOp::Delete { path } => {
+                vec![path]
+            }
+            FileOp::Move { from, to } => vec![from, to],
+        }
+    }
+}
+
+/// The outcome of one operation in a batch
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FileOpResult {
+    pub success: bool,
+    /// The file's content, for reads
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The outcome of a batch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchResult {
+    /// Whether every operation succeeded and the changes were kept
+    pub committed: bool,
+    /// One result per operation, up to and including the one that failed
+    pub results: Vec<FileOpResult>,
+}
+
+/// A file's content before the batch changed it; `None` if it didn't exist
+struct Original {
+    path: PathBuf,
+    content: Option<Vec<u8>>,
+}
+
+impl Original {
+    fn restore(&self) {
+        let result = match &self.content {
+            Some(content) => fs::write(&self.path, content),
+            None if self.path.exists() => fs::remove_file(&self.path),
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            log::error!("Failed to roll back {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Remember a file's content the first time the batch is about to change it
+fn record(originals: &mut Vec<Original>, path: &str) -> ToolResult<()> {
+    let path = Path::new(path);
+    if originals.iter().any(|o| o.path == path) {
+        return Ok(());
+    }
+    if path.is_dir() {
+        return Err(ToolError::InvalidArgument(format!(
+            "Path is a directory: {}",
+            path.display()
+        )));
+    }
+    let content = path.exists().then(|| fs::read(path)).transpose()?;
+    originals.push(Original {
+        path: path.to_path_buf(),
+        content,
+    });
+    Ok(())
+}
+
+fn run(op: &FileOp, originals: &mut Vec<Original>) -> ToolResult<Option<String>> {
+    match op {
+        FileOp::Read { path } => file_ops::read_file(path).map(Some),
+        FileOp::Write { path, content } => {
+            record(originals, path)?;
+            file_ops::write_file_atomic(path, content, false).map(|_| None)
+        }
+        FileOp::Delete { path } => {
+            record(originals, path)?;
+            file_ops::delete_file(path).map(|_| None)
+        }
+        FileOp::Move { from, to } => {
+            if Path::new(to).exists() {
+                return Err(ToolError::InvalidArgument(format!(
+                    "Destination already exists: {}",
+                    to
+                )));
+            }
+            record(originals, from)?;
+            record(originals, to)?;
+            file_ops::move_file(from, to).map(|_| None)
+        }
+    }
+}
+
+/// Run file operations in order as one transaction
+///
+/// Operations stop at the first failure, and every file changed before it is
+/// restored; directories created along the way are left in place.
+pub fn batch_file_ops(ops: &[FileOp]) -> BatchResult {
+    let mut originals = Vec::new();
+    let mut results = Vec::with_capacity(ops.len());
+
+    for op in ops {
+        match run(op, &mut originals) {
+            Ok(content) => results.push(FileOpResult {
+                success: true,
+                content,
+                error: None,
+            }),
+            Err(e) => {
+                results.push(FileOpResult {
+                    success: false,
+                    content: None,
+                    error: Some(e.to_string()),
+                });
+                for original in originals.iter().rev() {
+                    original.restore();
+                }
+                return BatchResult {
+                    committed: false,
+                    results,
+                };
+            }
+        }
+    }
+
+    BatchResult {
+        committed: true,
+        results,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_batch_rolls_back_on_failure() {
+        let dir = tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        fs::write(path("a.txt"), "a").unwrap();
+        fs::write(path("b.txt"), "b").unwrap();
+
+        let ops: Vec<FileOp> = serde_json::from_value(serde_json::json!([
+            { "op": "write", "path": path("a.txt"), "content": "A" },
+            { "op": "move", "from": path("b.txt"), "to": path("c.txt") },
+            { "op": "write", "path": path("new.txt"), "content": "new" },
+            { "op": "read", "path": path("missing.txt") },
+        ]))
+        .unwrap();
+        let result = batch_file_ops(&ops);
+        assert!(!result.committed);
+        assert_eq!(result.results.len(), 4);
+        assert!(result.results[3].error.is_some());
+        assert_eq!(fs::read_to_string(path("a.txt")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(path("b.txt")).unwrap(), "b");
+        assert!(!Path::new(&path("c.txt")).exists());
+        assert!(!Path::new(&path("new.txt")).exists());
+
+        let result = batch_file_ops(&ops[..3]);
+        assert!(result.committed);
+        assert_eq!(fs::read_to_string(path("c.txt")).unwrap(), "b");
+        let read = batch_file_ops(&[FileOp::Read {
+            path: path("a.txt"),
+        }]);
+        assert_eq!(read.results[0].content.as_deref(), Some("A"));
+    }
+}
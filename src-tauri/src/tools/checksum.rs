@@ -0,0 +1,177 @@
+//! File checksums and duplicate detection
+//!
+//! Hashes are computed by streaming the file, so large files aren't read
+//! into memory. Duplicates are found by grouping files by size first and
+//! only hashing files that share a size with another.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+
+use super::{project_walker, ToolError, ToolResult};
+
+/// A hash algorithm supported by `hash_file`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha224,
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = ToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "").as_str() {
+            "sha224" => Ok(HashAlgorithm::Sha224),
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "sha384" => Ok(HashAlgorithm::Sha384),
+            "sha512" => Ok(HashAlgorithm::Sha512),
+            _ => Err(ToolError::InvalidArgument(format!(
+                "Unsupported hash algorithm '{}'; use sha224, sha256, sha384 or sha512",
+                s
+            ))),
+        }
+    }
+}
+
+/// Files with the same content
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateGroup {
+    /// SHA-256 of the content, in hex
+    pub hash: String,
+    /// Size of each file in bytes
+    pub size: u64,
+    /// The files, relative to the searched directory
+    pub paths: Vec<String>,
+}
+
+fn digest_reader<D: Digest>(mut reader: impl Read) -> std::io::Result<String> {
+    let mut hasher = D::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Hash a file's content
+///
+/// # Returns
+/// The hash in lowercase hex
+pub fn hash_file(path: &str, algorithm: HashAlgorithm) -> ToolResult<String> {
+    let path = Path::new(path);
+    if !path.is_file() {
+        return Err(ToolError::PathNotFound(path.display().to_string()));
+    }
+    let file = fs::File::open(path)?;
+    let hash = match algorithm {
+        HashAlgorithm::Sha224 => digest_reader::<Sha224>(file),
+        HashAlgorithm::Sha256 => digest_reader::<Sha256>(file),
+        HashAlgorithm::Sha384 => digest_reader::<Sha384>(file),
+        HashAlgorithm::Sha512 => digest_reader::<Sha512>(file),
+    }?;
+    Ok(hash)
+}
+
+/// Find files with identical content under a directory
+///
+/// Files ignored by `.gitignore` and empty files are skipped.
+///
+/// # Returns
+/// Groups of two or more identical files, the ones wasting the most space
+/// first
+pub fn find_duplicate_files(dir: &str) -> ToolResult<Vec<DuplicateGroup>> {
+    let base = Path::new(dir);
+    if !base.is_dir() {
+        return Err(ToolError::PathNotFound(dir.to_string()));
+    }
+
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for entry in project_walker(base, false).build().flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+        if size > 0 {
+            by_size.entry(size).or_default().push(entry.into_path());
+        }
+    }
+
+    let mut groups = Vec::new();
+    for (size, paths) in by_size.into_iter().filter(|(_, paths)| paths.len() > 1) {
+        let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+        for path in paths {
+            let Ok(hash) = fs::File::open(&path).and_then(digest_reader::<Sha256>) else {
+                continue;
+            };
+            let relative = path.strip_prefix(base).unwrap_or(&path);
+            by_hash
+                .entry(hash)
+                .or_default()
+                .push(relative.to_string_lossy().replace('\\', "/"));
+        }
+        for (hash, mut paths) in by_hash.into_iter().filter(|(_, paths)| paths.len() > 1) {
+            paths.sort();
+            groups.push(DuplicateGroup { hash, size, paths });
+        }
+    }
+
+    groups.sort_by(|a, b| {
+        let wasted = |g: &DuplicateGroup| g.size * (g.paths.len() as u64 - 1);
+        wasted(b)
+            .cmp(&wasted(a))
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_hash_and_find_duplicates() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a.txt"), "hello").unwrap();
+        fs::write(root.join("sub/b.txt"), "hello").unwrap();
+        fs::write(root.join("c.txt"), "world").unwrap();
+        fs::write(root.join("d.txt"), "").unwrap();
+        fs::write(root.join("e.txt"), "").unwrap();
+
+        let path = root.join("a.txt");
+        assert_eq!(
+            hash_file(path.to_str().unwrap(), "SHA-256".parse().unwrap()).unwrap(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert!("md4".parse::<HashAlgorithm>().is_err());
+
+        let groups = find_duplicate_files(root.to_str().unwrap()).unwrap();
+        assert_eq!(
+            groups,
+            vec![DuplicateGroup {
+                hash: "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+                    .to_string(),
+                size: 5,
+                paths: vec!["a.txt".to_string(), "sub/b.txt".to_string()],
+            }]
+        );
+    }
+}
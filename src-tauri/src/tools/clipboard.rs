@@ -0,0 +1,132 @@
+//! System clipboard access
+//!
+//! This module reads and writes the clipboard through the platform's own
+//! utilities (`pbpaste`/`pbcopy`, PowerShell, `wl-paste`/`xclip`/`xsel`).
+//! It backs the clipboard commands and the opt-in `read_clipboard` tool,
+//! which lets an AI assistant see an error message the user just copied.
+
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+use serde_json::{json, Value};
+
+use super::{RiskClass, Tool, ToolContext, ToolDefinition, ToolError, ToolResult};
+
+/// Name of the clipboard tool
+pub const CLIPBOARD_TOOL: &str = "read_clipboard";
+
+/// Most clipboard characters returned to the model
+const MAX_CLIPBOARD_CHARS: usize = 20_000;
+
+/// Commands that print the clipboard, in order of preference
+fn paste_commands() -> &'static [&'static [&'static str]] {
+    if cfg!(target_os = "macos") {
+        &[&["pbpaste"]]
+    } else if cfg!(target_os = "windows") {
+        &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"]]
+    } else {
+        &[
+            &["wl-paste", "--no-newline"],
+            &["xclip", "-selection", "clipboard", "-out"],
+            &["xsel", "--clipboard", "--output"],
+        ]
+    }
+}
+
+/// Commands that set the clipboard from stdin, in order of preference
+fn copy_commands() -> &'static [&'static [&'static str]] {
+    if cfg!(target_os = "macos") {
+        &[&["pbcopy"]]
+    } else if cfg!(target_os = "windows") {
+        &[&["powershell", "-NoProfile", "-Command", "$input | Set-Clipboard"]]
+    } else {
+        &[
+            &["wl-copy"],
+            &["xclip", "-selection", "clipboard", "-in"],
+            &["xsel", "--clipboard", "--input"],
+        ]
+    }
+}
+
+fn no_clipboard_utility() -> ToolError {
+    ToolError::ExecutionFailed(
+        "No clipboard utility found (install wl-clipboard, xclip or xsel)".to_string(),
+    )
+}
+
+/// Read the text on the clipboard
+pub fn read_clipboard() -> ToolResult<String> {
+    for command in paste_commands() {
+        let output = match Command::new(command[0]).args(&command[1..]).output() {
+            Ok(output) => output,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if !output.status.success() {
+            // e.g. wl-paste outside a Wayland session; try the next utility
+            continue;
+        }
+        return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+    }
+    Err(no_clipboard_utility())
+}
+
+/// Replace the clipboard with text
+pub fn write_clipboard(text: &str) -> ToolResult<()> {
+    for command in copy_commands() {
+        let mut child = match Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+    Err(no_clipboard_utility())
+}
+
+/// Lets the model read the clipboard; registered only when the user enables it
+pub struct ClipboardTool;
+
+impl Tool for ClipboardTool {
+    fn name(&self) -> &str {
+        CLIPBOARD_TOOL
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: CLIPBOARD_TOOL.to_string(),
+            description: "Read the text on the user's clipboard, e.g. an error message or stack trace they just copied and refer to without pasting it".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {}
+            }),
+        }
+    }
+
+    fn risk(&self) -> RiskClass {
+        RiskClass::Read
+    }
+
+    fn execute(&self, _args: &Value, _context: &ToolContext) -> ToolResult<Value> {
+        let text = read_clipboard()?;
+        let truncated = text.chars().count() > MAX_CLIPBOARD_CHARS;
+        let content: String = text.chars().take(MAX_CLIPBOARD_CHARS).collect();
+
+        Ok(json!({
+            "success": true,
+            "content": content,
+            "truncated": truncated
+        }))
+    }
+}
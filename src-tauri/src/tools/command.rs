@@ -0,0 +1,288 @@
+//! Shell command execution for the tools system
+//!
+//! This module lets AI assistants run shell commands (builds, tests, linters)
+//! with a timeout, capturing stdout and stderr and truncating long output.
+
+use std::io::Read;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::{ToolError, ToolResult};
+
+/// Default time a command may run before it is killed
+pub const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 120;
+
+/// Upper bound for a caller-supplied timeout
+pub const MAX_COMMAND_TIMEOUT_SECS: u64 = 600;
+
+/// Maximum characters kept from each output stream
+const MAX_OUTPUT_CHARS: usize = 30_000;
+
+/// Time to wait for the output streams to close once the command has exited
+///
+/// Background processes the command started can keep them open; after this
+/// the command's process group is killed and the output read so far is used.
+pub(crate) const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Result of running a shell command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandResult {
+    pub command: String,
+    pub cwd: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// Exit code, or `None` if the process was killed
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub truncated: bool,
+    pub duration_ms: u64,
+}
+
+/// Keep the start and end of long output, where the useful parts usually are
+fn truncate_output(output: &str) -> (String, bool) {
+    let char_count = output.chars().count();
+    if char_count <= MAX_OUTPUT_CHARS {
+        return (output.to_string(), false);
+    }
+
+    let head_chars = MAX_OUTPUT_CHARS / 3;
+    let tail_chars = MAX_OUTPUT_CHARS - head_chars;
+    let head: String = output.chars().take(head_chars).collect();
+    let tail: String = output.chars().skip(char_count - tail_chars).collect();
+    let omitted = output.len() - head.len() - tail.len();
+
+    (
+        format!("{}\n[... {} bytes omitted ...]\n{}", head, omitted, tail),
+        true,
+    )
+}
+
+/// Build the platform shell invocation for a command string
+pub(crate) fn shell_command(command: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::process::CommandExt;
+
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        // Run in its own process group so the whole pipeline can be killed
+        cmd.process_group(0);
+        cmd
+    }
+}
+
+/// Kill a timed-out command along with any processes it started
+pub(crate) fn kill_tree(child: &mut Child) {
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = Command::new("kill")
+            .args(["-s", "KILL", "--", &format!("-{}", child.id())])
+            .status();
+    }
+    let _ = child.kill();
+}
+
+/// A child output stream being read on a background thread
+struct OutputReader {
+    thread: thread::JoinHandle<()>,
+    output: Arc<Mutex<Vec<u8>>>,
+}
+
+impl OutputReader {
+    /// Read a child output stream to completion on a background thread
+    fn spawn<R: Read + Send + 'static>(stream: Option<R>) -> Self {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let buffer = output.clone();
+        let thread = thread::spawn(move || {
+            let Some(mut stream) = stream else {
+                return;
+            };
+            let mut chunk = [0u8; 8192];
+            while let Ok(n @ 1..) = stream.read(&mut chunk) {
+                buffer.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(&chunk[..n]);
+            }
+        });
+        Self { thread, output }
+    }
+
+    /// The output read so far
+    fn text(&self) -> String {
+        let output = self.output.lock().unwrap_or_else(|e| e.into_inner());
+        String::from_utf8_lossy(&output).to_string()
+    }
+}
+
+/// Wait until the readers reach the end of their streams, or `timeout` passes
+fn wait_for_readers(readers: &[&OutputReader], timeout: Duration) -> bool {
+    let start = Instant::now();
+    while !readers.iter().all(|r| r.thread.is_finished()) {
+        if start.elapsed() >= timeout {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    true
+}
+
+/// Output of a command, before any truncation
+#[derive(Debug, Clone)]
+pub struct CapturedOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// Exit code, or `None` if the process was killed
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration: Duration,
+}
+
+/// Run a shell command and capture all of its output
+///
+/// Use this instead of `run_command` when the output is parsed rather than
+/// shown to the model, so truncation can't corrupt it.
+///
+/// # Arguments
+/// * `command` - The command line, run through `sh -c` (or `cmd /C` on Windows)
+/// * `cwd` - Directory to run the command in
+/// * `timeout` - Time after which the command and its children are killed
+pub fn capture_command(command: &str, cwd: &str, timeout: Duration) -> ToolResult<CapturedOutput> {
+    if command.trim().is_empty() {
+        return Err(ToolError::InvalidArgument("Command is empty".to_string()));
+    }
+
+    let cwd_path = Path::new(cwd);
+    if !cwd_path.is_dir() {
+        return Err(ToolError::PathNotFound(cwd.to_string()));
+    }
+
+    let start = Instant::now();
+    let mut child = shell_command(command)
+        .current_dir(cwd_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to start command: {}", e)))?;
+
+    let stdout_reader = OutputReader::spawn(child.stdout.take());
+    let stderr_reader = OutputReader::spawn(child.stderr.take());
+
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if start.elapsed() >= timeout {
+            kill_tree(&mut child);
+            let _ = child.wait();
+            timed_out = true;
+            break None;
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+
+    // Processes left running in the background may hold the streams open
+    let readers = [&stdout_reader, &stderr_reader];
+    if !wait_for_readers(&readers, OUTPUT_DRAIN_TIMEOUT) {
+        kill_tree(&mut child);
+        // Anything that left the process group is abandoned with its reader
+        wait_for_readers(&readers, OUTPUT_DRAIN_TIMEOUT);
+    }
+
+    Ok(CapturedOutput {
+        stdout: stdout_reader.text(),
+        stderr: stderr_reader.text(),
+        exit_code: status.and_then(|s| s.code()),
+        timed_out,
+        duration: start.elapsed(),
+    })
+}
+
+/// Run a shell command in a directory
+///
+/// # Arguments
+/// * `command` - The command line, run through `sh -c` (or `cmd /C` on Windows)
+/// * `cwd` - Directory to run the command in
+/// * `timeout` - Time after which the command and its children are killed
+///
+/// # Returns
+/// The captured output and exit status
+pub fn run_command(command: &str, cwd: &str, timeout: Duration) -> ToolResult<CommandResult> {
+    let output = capture_command(command, cwd, timeout)?;
+    let (stdout, stdout_truncated) = truncate_output(&output.stdout);
+    let (stderr, stderr_truncated) = truncate_output(&output.stderr);
+
+    Ok(CommandResult {
+        command: command.to_string(),
+        cwd: cwd.to_string(),
+        stdout,
+        stderr,
+        exit_code: output.exit_code,
+        timed_out: output.timed_out,
+        truncated: stdout_truncated || stderr_truncated,
+        duration_ms: output.duration.as_millis() as u64,
+    })
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_run_command_captures_output() {
+        let dir = tempdir().unwrap();
+        let result = run_command(
+            "echo out; echo err >&2; exit 3",
+            dir.path().to_str().unwrap(),
+            Duration::from_secs(10),
+        )
+        .unwrap();
+
+        assert_eq!(result.stdout.trim(), "out");
+        assert_eq!(result.stderr.trim(), "err");
+        assert_eq!(result.exit_code, Some(3));
+        assert!(!result.timed_out);
+    }
+
+    #[test]
+    fn test_run_command_times_out() {
+        let dir = tempdir().unwrap();
+        let result = run_command(
+            "sleep 5",
+            dir.path().to_str().unwrap(),
+            Duration::from_millis(200),
+        )
+        .unwrap();
+
+        assert!(result.timed_out);
+        assert_eq!(result.exit_code, None);
+    }
+
+    #[test]
+    fn test_run_command_returns_despite_background_process() {
+        let dir = tempdir().unwrap();
+        let start = Instant::now();
+        let result = run_command(
+            "echo started; sleep 30 &",
+            dir.path().to_str().unwrap(),
+            Duration::from_secs(60),
+        )
+        .unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(result.stdout.trim(), "started");
+        assert_eq!(result.exit_code, Some(0));
+    }
+}
@@ -0,0 +1,231 @@
+//! SQL queries against local databases for the tools system
+//!
+//! This module lets AI assistants inspect SQLite databases in a project:
+//! list the schema and run queries to sample data, e.g. while writing
+//! migrations. Connections are read-only unless the caller asks otherwise.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{ToolError, ToolResult};
+
+/// Default number of rows returned by a query
+pub const DEFAULT_QUERY_ROWS: usize = 100;
+
+/// Most rows a query may return
+pub const MAX_QUERY_ROWS: usize = 1000;
+
+/// Longest text value returned per cell
+const MAX_CELL_CHARS: usize = 1000;
+
+/// Every SQLite database starts with this header
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+/// A table, view, index or trigger in a database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaObject {
+    pub name: String,
+    /// "table", "view", "index" or "trigger"
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// The statement that created it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sql: Option<String>,
+}
+
+/// Result of running a SQL statement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// Whether more rows matched than were returned
+    pub truncated: bool,
+    /// Rows changed, for statements that don't return rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows_affected: Option<usize>,
+}
+
+fn db_error(e: rusqlite::Error) -> ToolError {
+    ToolError::ExecutionFailed(e.to_string())
+}
+
+/// Open a SQLite database, checking first that the file is one
+fn open_database(path: &str, read_only: bool) -> ToolResult<Connection> {
+    let mut header = [0u8; 16];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .map_err(|_| ToolError::InvalidArgument(format!("Not a SQLite database: {}", path)))?;
+    if header != SQLITE_HEADER {
+        return Err(ToolError::InvalidArgument(format!(
+            "Not a SQLite database: {}",
+            path
+        )));
+    }
+
+    let flags = if read_only {
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX
+    } else {
+        OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX
+    };
+    let conn = Connection::open_with_flags(Path::new(path), flags).map_err(db_error)?;
+    // The app using the database may hold a lock; wait for it briefly
+    conn.busy_timeout(Duration::from_secs(5)).map_err(db_error)?;
+    Ok(conn)
+}
+
+/// Convert a SQLite value to JSON
+fn cell_value(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(n) => Value::from(n),
+        ValueRef::Real(n) => Value::from(n),
+        ValueRef::Text(bytes) => {
+            let text = String::from_utf8_lossy(bytes);
+            if text.chars().count() > MAX_CELL_CHARS {
+                let truncated: String = text.chars().take(MAX_CELL_CHARS).collect();
+                Value::from(format!("{}...", truncated))
+            } else {
+                Value::from(text.into_owned())
+            }
+        }
+        ValueRef::Blob(bytes) => Value::from(format!("<blob {} bytes>", bytes.len())),
+    }
+}
+
+/// List the tables, views, indexes and triggers in a database
+///
+/// Internal `sqlite_` objects are left out.
+pub fn database_schema(path: &str) -> ToolResult<Vec<SchemaObject>> {
+    let conn = open_database(path, true)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, type, sql FROM sqlite_master
+             WHERE name NOT LIKE 'sqlite_%'
+             ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'view' THEN 1 ELSE 2 END, name",
+        )
+        .map_err(db_error)?;
+
+    let objects = stmt
+        .query_map([], |row| {
+            Ok(SchemaObject {
+                name: row.get(0)?,
+                kind: row.get(1)?,
+                sql: row.get(2)?,
+            })
+        })
+        .map_err(db_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(db_error)?;
+    Ok(objects)
+}
+
+/// Run a single SQL statement against a SQLite database
+///
+/// # Arguments
+/// * `path` - The database file
+/// * `sql` - One SQL statement
+/// * `max_rows` - Most rows to return
+/// * `read_only` - Reject statements that would modify the database
+///
+/// # Returns
+/// The columns and rows returned, or the number of rows changed
+pub fn query_database(
+    path: &str,
+    sql: &str,
+    max_rows: usize,
+    read_only: bool,
+) -> ToolResult<QueryResult> {
+    let conn = open_database(path, read_only)?;
+    let mut stmt = conn.prepare(sql).map_err(db_error)?;
+
+    if read_only && !stmt.readonly() {
+        return Err(ToolError::PermissionDenied(
+            "Only read-only statements are allowed on this database".to_string(),
+        ));
+    }
+
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    if columns.is_empty() {
+        let rows_affected = stmt.execute([]).map_err(db_error)?;
+        return Ok(QueryResult {
+            columns,
+            rows: Vec::new(),
+            truncated: false,
+            rows_affected: Some(rows_affected),
+        });
+    }
+
+    let max_rows = max_rows.clamp(1, MAX_QUERY_ROWS);
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    let mut result_rows = stmt.query([]).map_err(db_error)?;
+    while let Some(row) = result_rows.next().map_err(db_error)? {
+        if rows.len() == max_rows {
+            truncated = true;
+            break;
+        }
+        let values = (0..columns.len())
+            .map(|i| row.get_ref(i).map(cell_value))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_error)?;
+        rows.push(values);
+    }
+
+    Ok(QueryResult {
+        columns,
+        rows,
+        truncated,
+        rows_affected: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_schema_and_read_only_queries() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.db");
+        let path = path.to_str().unwrap();
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, avatar BLOB);
+             INSERT INTO users (name, avatar) VALUES ('ada', x'0102'), ('grace', NULL);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let schema = database_schema(path).unwrap();
+        assert_eq!(schema[0].name, "users");
+        assert_eq!(schema[0].kind, "table");
+
+        let result = query_database(path, "SELECT * FROM users ORDER BY id", 1, true).unwrap();
+        assert_eq!(result.columns, vec!["id", "name", "avatar"]);
+        assert_eq!(result.rows, vec![vec![json!(1), json!("ada"), json!("<blob 2 bytes>")]]);
+        assert!(result.truncated);
+
+        let delete = "DELETE FROM users WHERE name = 'grace'";
+        assert!(matches!(
+            query_database(path, delete, 10, true),
+            Err(ToolError::PermissionDenied(_))
+        ));
+        assert_eq!(query_database(path, delete, 10, false).unwrap().rows_affected, Some(1));
+
+        std::fs::write(dir.path().join("notes.txt"), "not a database").unwrap();
+        let notes = dir.path().join("notes.txt");
+        assert!(matches!(
+            query_database(notes.to_str().unwrap(), "SELECT 1", 10, true),
+            Err(ToolError::InvalidArgument(_))
+        ));
+    }
+}
@@ -0,0 +1,384 @@
+//! Compiler and linter diagnostics for the tools system
+//!
+//! This module runs a project's own checkers (`cargo check`, `tsc`,
+//! `go vet`) and parses their output into a common `Diagnostic` shape, so
+//! an AI assistant can verify that its edits compile. Checkers are
+//! `DiagnosticsRunner`s; more can be added without touching the tool.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{capture_command, CapturedOutput, ToolError, ToolResult};
+
+/// Default time each checker may run
+pub const DEFAULT_DIAGNOSTICS_TIMEOUT_SECS: u64 = 300;
+
+/// Most diagnostics returned at once; errors are kept before warnings
+pub const MAX_DIAGNOSTICS: usize = 200;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// A compiler or linter message about a location in a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Path relative to the project root
+    pub path: String,
+    pub line: u32,
+    pub column: u32,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// Name of the runner that reported it
+    pub source: String,
+}
+
+/// Outcome of one runner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunnerReport {
+    pub runner: String,
+    /// Directory the checker ran in, relative to the project root
+    pub dir: String,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub diagnostics: usize,
+}
+
+/// Diagnostics gathered from every runner that applies to a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsReport {
+    pub diagnostics: Vec<Diagnostic>,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub truncated: bool,
+    pub runners: Vec<RunnerReport>,
+}
+
+/// A checker that reports diagnostics for one kind of project
+pub trait DiagnosticsRunner: Send + Sync {
+    /// Short name shown in results
+    fn name(&self) -> &str;
+
+    /// File whose presence marks a directory as this runner's project root
+    fn marker(&self) -> &str;
+
+    /// Command line to run in the project root
+    fn command(&self) -> String;
+
+    /// Turn the command's output into diagnostics with paths relative to `dir`
+    fn parse(&self, output: &CapturedOutput) -> Vec<Diagnostic>;
+}
+
+/// `cargo check` for Rust crates
+pub struct CargoRunner;
+
+impl DiagnosticsRunner for CargoRunner {
+    fn name(&self) -> &str {
+        "cargo"
+    }
+
+    fn marker(&self) -> &str {
+        "Cargo.toml"
+    }
+
+    fn command(&self) -> String {
+        "cargo check --all-targets --message-format=json --quiet".to_string()
+    }
+
+    fn parse(&self, output: &CapturedOutput) -> Vec<Diagnostic> {
+        output
+            .stdout
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .filter(|msg| msg["reason"] == "compiler-message")
+            .filter_map(|msg| {
+                let message = &msg["message"];
+                let severity = match message["level"].as_str()? {
+                    "error" | "error: internal compiler error" => Severity::Error,
+                    "warning" => Severity::Warning,
+                    _ => Severity::Info,
+                };
+                let span = message["spans"]
+                    .as_array()?
+                    .iter()
+                    .find(|s| s["is_primary"] == true)?;
+
+                Some(Diagnostic {
+                    path: span["file_name"].as_str()?.to_string(),
+                    line: span["line_start"].as_u64().unwrap_or(0) as u32,
+                    column: span["column_start"].as_u64().unwrap_or(0) as u32,
+                    severity,
+                    message: message["message"].as_str().unwrap_or_default().to_string(),
+                    code: message["code"]["code"].as_str().map(str::to_string),
+                    source: self.name().to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// `tsc --noEmit` for TypeScript projects
+pub struct TscRunner;
+
+impl DiagnosticsRunner for TscRunner {
+    fn name(&self) -> &str {
+        "tsc"
+    }
+
+    fn marker(&self) -> &str {
+        "tsconfig.json"
+    }
+
+    fn command(&self) -> String {
+        // --no-install: only use the project's own compiler
+        "npx --no-install tsc --noEmit --pretty false".to_string()
+    }
+
+    fn parse(&self, output: &CapturedOutput) -> Vec<Diagnostic> {
+        // src/app.ts(12,5): error TS2322: Type 'string' is not assignable ...
+        output
+            .stdout
+            .lines()
+            .filter_map(|line| {
+                let (location, rest) = line.split_once("): ")?;
+                let (path, position) = location.rsplit_once('(')?;
+                let (line_no, column) = position.split_once(',')?;
+                let (kind, message) = rest.split_once(": ")?;
+                let (severity, code) = kind.split_once(' ')?;
+
+                Some(Diagnostic {
+                    path: path.to_string(),
+                    line: line_no.parse().ok()?,
+                    column: column.parse().ok()?,
+                    severity: if severity == "error" {
+                        Severity::Error
+                    } else {
+                        Severity::Warning
+                    },
+                    message: message.to_string(),
+                    code: Some(code.to_string()),
+                    source: self.name().to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// `go vet` for Go modules (also reports compile errors)
+pub struct GoVetRunner;
+
+impl DiagnosticsRunner for GoVetRunner {
+    fn name(&self) -> &str {
+        "go vet"
+    }
+
+    fn marker(&self) -> &str {
+        "go.mod"
+    }
+
+    fn command(&self) -> String {
+        "go vet ./...".to_string()
+    }
+
+    fn parse(&self, output: &CapturedOutput) -> Vec<Diagnostic> {
+        // ./main.go:12:5: undefined: foo  (optionally prefixed with "vet: ")
+        output
+            .stderr
+            .lines()
+            .filter_map(|line| {
+                let line = line.strip_prefix("vet: ").unwrap_or(line);
+                let mut parts = line.splitn(4, ':');
+                let path = parts.next()?;
+                let line_no = parts.next()?.parse().ok()?;
+                let column = parts.next()?.parse().ok()?;
+                let message = parts.next()?.trim();
+
+                Some(Diagnostic {
+                    path: path.trim_start_matches("./").to_string(),
+                    line: line_no,
+                    column,
+                    severity: Severity::Error,
+                    message: message.to_string(),
+                    code: None,
+                    source: self.name().to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The runners used by the `get_diagnostics` tool
+pub fn builtin_diagnostics_runners() -> Vec<Box<dyn DiagnosticsRunner>> {
+    vec![Box::new(CargoRunner), Box::new(TscRunner), Box::new(GoVetRunner)]
+}
+
+/// Find the directories a runner should check
+///
+/// For a file, that is the nearest ancestor (within the project) holding the
+/// runner's marker. For the whole project, it is the root and its immediate
+/// subdirectories, which covers layouts like a `src-tauri/` crate beside a
+/// web frontend.
+fn runner_dirs(runner: &dyn DiagnosticsRunner, root: &Path, file: Option<&Path>) -> Vec<PathBuf> {
+    let has_marker = |dir: &Path| dir.join(runner.marker()).is_file();
+
+    if let Some(file) = file {
+        return file
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(root))
+            .find(|dir| has_marker(dir))
+            .map(|dir| vec![dir.to_path_buf()])
+            .unwrap_or_default();
+    }
+
+    if has_marker(root) {
+        return vec![root.to_path_buf()];
+    }
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|dir| dir.is_dir() && has_marker(dir))
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Express a path relative to the project root, with forward slashes
+fn relative_to(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Run every applicable checker and collect its diagnostics
+///
+/// # Arguments
+/// * `root` - The project root
+/// * `file` - Only report diagnostics for this file (checkers still run on its whole project)
+/// * `runners` - The checkers to try
+/// * `timeout` - Time each checker may run
+///
+/// # Returns
+/// Diagnostics sorted by severity and location, with a report per checker run
+pub fn get_diagnostics(
+    root: &str,
+    file: Option<&str>,
+    runners: &[Box<dyn DiagnosticsRunner>],
+    timeout: Duration,
+) -> ToolResult<DiagnosticsReport> {
+    let root = Path::new(root);
+    if !root.is_dir() {
+        return Err(ToolError::PathNotFound(root.display().to_string()));
+    }
+    let file = file.map(|f| root.join(f));
+    if let Some(file) = &file {
+        if !file.is_file() {
+            return Err(ToolError::PathNotFound(file.display().to_string()));
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut reports = Vec::new();
+
+    for runner in runners {
+        for dir in runner_dirs(runner.as_ref(), root, file.as_deref()) {
+            let output = capture_command(&runner.command(), &dir.to_string_lossy(), timeout)?;
+            let found: Vec<Diagnostic> = runner
+                .parse(&output)
+                .into_iter()
+                .map(|mut d| {
+                    d.path = relative_to(root, &dir.join(&d.path));
+                    d
+                })
+                .filter(|d| file.as_ref().is_none_or(|f| root.join(&d.path) == *f))
+                .collect();
+
+            reports.push(RunnerReport {
+                runner: runner.name().to_string(),
+                dir: relative_to(root, &dir),
+                exit_code: output.exit_code,
+                timed_out: output.timed_out,
+                diagnostics: found.len(),
+            });
+            diagnostics.extend(found);
+        }
+    }
+
+    diagnostics.sort_by(|a, b| {
+        (a.severity, &a.path, a.line, a.column).cmp(&(b.severity, &b.path, b.line, b.column))
+    });
+    // Cargo repeats diagnostics for each target that includes a file
+    diagnostics.dedup_by(|a, b| {
+        a.path == b.path && a.line == b.line && a.column == b.column && a.message == b.message
+    });
+
+    let error_count = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warning_count = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Warning)
+        .count();
+    let truncated = diagnostics.len() > MAX_DIAGNOSTICS;
+    diagnostics.truncate(MAX_DIAGNOSTICS);
+
+    Ok(DiagnosticsReport {
+        diagnostics,
+        error_count,
+        warning_count,
+        truncated,
+        runners: reports,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(stdout: &str, stderr: &str) -> CapturedOutput {
+        CapturedOutput {
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+            exit_code: Some(1),
+            timed_out: false,
+            duration: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_parse_checker_output() {
+        let cargo = r#"{"reason":"compiler-artifact","target":{}}
+{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","code":{"code":"E0308"},"spans":[{"file_name":"src/lib.rs","line_start":4,"column_start":9,"is_primary":true}]}}"#;
+        let diagnostics = CargoRunner.parse(&output(cargo, ""));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "src/lib.rs");
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (4, 9));
+        assert_eq!(diagnostics[0].code.as_deref(), Some("E0308"));
+
+        let tsc = "src/app.ts(12,5): error TS2322: Type 'string' is not assignable to type 'number'.";
+        let diagnostics = TscRunner.parse(&output(tsc, ""));
+        assert_eq!(diagnostics[0].path, "src/app.ts");
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("TS2322"));
+
+        let vet = "# example.com/app\nvet: ./main.go:7:2: undefined: foo";
+        let diagnostics = GoVetRunner.parse(&output("", vet));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "main.go");
+        assert_eq!(diagnostics[0].message, "undefined: foo");
+    }
+}
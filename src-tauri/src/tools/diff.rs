@@ -0,0 +1,148 @@
+//! Structured line diffs
+//!
+//! Differences between two texts are returned as hunks of numbered lines,
+//! so the frontend can render an AI edit as a diff without parsing unified
+//! diff text.
+
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+
+use super::{read_file, ToolResult};
+
+/// Unchanged lines shown around each change by default
+pub const DEFAULT_DIFF_CONTEXT: usize = 3;
+
+/// Whether a diff line was added, removed or left unchanged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// A line of a diff hunk
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// Line number in the original text, from 1; `None` for added lines
+    pub old_line: Option<usize>,
+    /// Line number in the modified text, from 1; `None` for removed lines
+    pub new_line: Option<usize>,
+    /// The line without its line ending
+    pub content: String,
+}
+
+/// A run of changes with the unchanged lines around them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// The differences between two texts
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FileDiff {
+    pub hunks: Vec<DiffHunk>,
+    /// Number of lines added
+    pub added: usize,
+    /// Number of lines removed
+    pub removed: usize,
+}
+
+/// Diff two texts line by line
+///
+/// # Arguments
+/// * `original` - The text before the change
+/// * `modified` - The text after the change
+/// * `context` - Unchanged lines to include around each change
+pub fn diff_text(original: &str, modified: &str, context: usize) -> FileDiff {
+    let diff = TextDiff::from_lines(original, modified);
+    let mut result = FileDiff::default();
+
+    for group in diff.grouped_ops(context) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let old_range = first.old_range().start..last.old_range().end;
+        let new_range = first.new_range().start..last.new_range().end;
+
+        let mut lines = Vec::new();
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                let kind = match change.tag() {
+                    ChangeTag::Equal => DiffLineKind::Context,
+                    ChangeTag::Insert => {
+                        result.added += 1;
+                        DiffLineKind::Added
+                    }
+                    ChangeTag::Delete => {
+                        result.removed += 1;
+                        DiffLineKind::Removed
+                    }
+                };
+                lines.push(DiffLine {
+                    kind,
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                    content: change.value().trim_end_matches(['\n', '\r']).to_string(),
+                });
+            }
+        }
+
+        result.hunks.push(DiffHunk {
+            old_start: old_range.start + 1,
+            old_lines: old_range.len(),
+            new_start: new_range.start + 1,
+            new_lines: new_range.len(),
+            lines,
+        });
+    }
+    result
+}
+
+/// Diff two text files line by line
+pub fn diff_files(a: &str, b: &str, context: usize) -> ToolResult<FileDiff> {
+    Ok(diff_text(&read_file(a)?, &read_file(b)?, context))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_text_numbers_lines() {
+        let original = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let modified = "a\nB\nc\nd\ne\nf\ng\nh\ni\n";
+        let diff = diff_text(original, modified, 1);
+
+        assert_eq!((diff.added, diff.removed), (2, 1));
+        assert_eq!(diff.hunks.len(), 2);
+        let first = &diff.hunks[0];
+        assert_eq!(
+            (
+                first.old_start,
+                first.old_lines,
+                first.new_start,
+                first.new_lines
+            ),
+            (1, 3, 1, 3)
+        );
+        assert_eq!(
+            first.lines[1],
+            DiffLine {
+                kind: DiffLineKind::Removed,
+                old_line: Some(2),
+                new_line: None,
+                content: "b".to_string(),
+            }
+        );
+        assert_eq!(first.lines[2].new_line, Some(2));
+        assert_eq!(diff.hunks[1].lines.last().unwrap().content, "i");
+
+        assert!(diff_text(original, original, 3).hunks.is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+//! Transactional multi-file editing
+//!
+//! This module applies a batch of string-replacement edits across several
+//! files as a single transaction: every edit is validated in memory first,
+//! then all files are written, and any write failure restores the originals.
+//! Files keep their encoding, byte order mark and line endings.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{decode_text, encode_text, LineEnding, TextFormat, ToolError, ToolResult};
+
+/// A single string replacement in a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEdit {
+    pub path: String,
+    /// Text to replace; empty to create a new file with `new_string`
+    pub old_string: String,
+    pub new_string: String,
+    /// Replace every occurrence instead of requiring a unique match
+    #[serde(default)]
+    pub replace_all: bool,
+}
+
+/// Summary of a file changed by `multi_edit`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditedFile {
+    pub path: String,
+    pub edits: usize,
+    pub replacements: usize,
+    pub created: bool,
+}
+
+/// Original state of a file, used to roll back a failed transaction
+struct Snapshot {
+    path: PathBuf,
+    original: Option<Vec<u8>>,
+}
+
+impl Snapshot {
+    fn restore(&self) {
+        let result = match &self.original {
+            Some(content) => fs::write(&self.path, content),
+            None if self.path.exists() => fs::remove_file(&self.path),
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            log::error!("Failed to roll back {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Apply one edit to in-memory content, returning the number of replacements
+fn apply_edit(content: &mut String, edit: &FileEdit, index: usize) -> ToolResult<usize> {
+    // An empty string matches between every character
+    if edit.old_string.is_empty() {
+        return Err(ToolError::InvalidArgument(format!(
+            "Edit {} ({}): old_string is empty, which only creates a new file; the file exists",
+            index, edit.path
+        )));
+    }
+    if edit.old_string == edit.new_string {
+        return Err(ToolError::InvalidArgument(format!(
+            "Edit {} ({}): old_string and new_string are identical",
+            index, edit.path
+        )));
+    }
+
+    let count = content.matches(&edit.old_string).count();
+    if count == 0 {
+        return Err(ToolError::InvalidArgument(format!(
+            "Edit {} ({}): old_string not found",
+            index, edit.path
+        )));
+    }
+    if count > 1 && !edit.replace_all {
+        return Err(ToolError::InvalidArgument(format!(
+            "Edit {} ({}): old_string matches {} times; add more context or set replace_all",
+            index, edit.path, count
+        )));
+    }
+
+    *content = if edit.replace_all {
+        content.replace(&edit.old_string, &edit.new_string)
+    } else {
+        content.replacen(&edit.old_string, &edit.new_string, 1)
+    };
+    Ok(count)
+}
+
+/// Apply a list of edits across files atomically
+///
+/// Edits to the same file are applied in order, each seeing the result of
+/// the previous one. If any edit fails validation nothing is written; if a
+/// write fails, files already written are restored from snapshots.
+///
+/// # Arguments
+/// * `edits` - The edits to apply
+///
+/// # Returns
+/// A summary of each changed file
+pub fn multi_edit(edits: &[FileEdit]) -> ToolResult<Vec<EditedFile>> {
+    if edits.is_empty() {
+        return Err(ToolError::InvalidArgument("No edits provided".to_string()));
+    }
+
+    // Validate everything in memory, preserving first-seen file order
+    let mut order: Vec<PathBuf> = Vec::new();
+    let mut originals: HashMap<PathBuf, Option<Vec<u8>>> = HashMap::new();
+    let mut contents: HashMap<PathBuf, String> = HashMap::new();
+    let mut formats: HashMap<PathBuf, TextFormat> = HashMap::new();
+    let mut summaries: HashMap<PathBuf, EditedFile> = HashMap::new();
+
+    for (index, edit) in edits.iter().enumerate() {
+        let path = PathBuf::from(&edit.path);
+
+        if !contents.contains_key(&path) {
+            let original = if path.exists() {
+                if !path.is_file() {
+                    return Err(ToolError::InvalidArgument(format!(
+                        "Path is not a file: {}",
+                        edit.path
+                    )));
+                }
+                Some(fs::read(&path)?)
+            } else {
+                None
+            };
+            let (content, format) = match &original {
+                Some(bytes) => decode_text(bytes).ok_or_else(|| {
+                    ToolError::InvalidArgument(format!("Not a text file: {}", edit.path))
+                })?,
+                None => (
+                    String::new(),
+                    TextFormat {
+                        encoding: encoding_rs::UTF_8,
+                        bom: false,
+                        line_ending: LineEnding::Lf,
+                    },
+                ),
+            };
+            contents.insert(path.clone(), content);
+            formats.insert(path.clone(), format);
+            summaries.insert(
+                path.clone(),
+                EditedFile {
+                    path: edit.path.clone(),
+                    edits: 0,
+                    replacements: 0,
+                    created: original.is_none(),
+                },
+            );
+            originals.insert(path.clone(), original);
+            order.push(path.clone());
+        }
+
+        let content = contents.get_mut(&path).expect("content loaded above");
+        let summary = summaries.get_mut(&path).expect("summary created above");
+
+        let replacements = if summary.created && summary.edits == 0 {
+            if !edit.old_string.is_empty() {
+                return Err(ToolError::PathNotFound(edit.path.clone()));
+            }
+            *content = edit.new_string.clone();
+            1
+        } else if formats[&path].line_ending == LineEnding::Crlf {
+            // Match and insert text with the file's own line endings
+            let edit = FileEdit {
+                old_string: LineEnding::Crlf.apply(&edit.old_string),
+                new_string: LineEnding::Crlf.apply(&edit.new_string),
+                ..edit.clone()
+            };
+            apply_edit(content, &edit, index)?
+        } else {
+            apply_edit(content, edit, index)?
+        };
+
+        summary.edits += 1;
+        summary.replacements += replacements;
+    }
+
+    // Write all files, rolling back on the first failure
+    let mut written: Vec<Snapshot> = Vec::new();
+    for path in &order {
+        let result = encode_text(&contents[path], &formats[path])
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| write_with_parents(path, &bytes).map_err(|e| e.to_string()));
+        // Record the snapshot even on failure: a partial write must be undone too
+        written.push(Snapshot {
+            path: path.clone(),
+            original: originals[path].clone(),
+        });
+        if let Err(e) = result {
+            for snapshot in written.iter().rev() {
+                snapshot.restore();
+            }
+            return Err(ToolError::ExecutionFailed(format!(
+                "Failed to write {}: {}; all edits were rolled back",
+                path.display(),
+                e
+            )));
+        }
+    }
+
+    Ok(order
+        .iter()
+        .filter_map(|path| summaries.remove(path))
+        .collect())
+}
+
+fn write_with_parents(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    fs::write(path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn edit(path: &Path, old: &str, new: &str) -> FileEdit {
+        FileEdit {
+            path: path.to_str().unwrap().to_string(),
+            old_string: old.to_string(),
+            new_string: new.to_string(),
+            replace_all: false,
+        }
+    }
+
+    #[test]
+    fn test_multi_edit_applies_across_files() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.rs");
+        let b = dir.path().join("b.rs");
+        fs::write(&a, "fn old_name() {}\n").unwrap();
+        fs::write(&b, "old_name();\nold_name();\n").unwrap();
+
+        let mut rename_calls = edit(&b, "old_name", "new_name");
+        rename_calls.replace_all = true;
+        let result = multi_edit(&[edit(&a, "old_name", "new_name"), rename_calls]).unwrap();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[1].replacements, 2);
+        assert_eq!(fs::read_to_string(&a).unwrap(), "fn new_name() {}\n");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "new_name();\nnew_name();\n");
+    }
+
+    #[test]
+    fn test_multi_edit_validation_failure_writes_nothing() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        fs::write(&a, "alpha\n").unwrap();
+        fs::write(&b, "beta beta\n").unwrap();
+
+        let result = multi_edit(&[edit(&a, "alpha", "ALPHA"), edit(&b, "beta", "BETA")]);
+
+        assert!(matches!(result, Err(ToolError::InvalidArgument(_))));
+        assert_eq!(fs::read_to_string(&a).unwrap(), "alpha\n");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "beta beta\n");
+
+        let mut insert_everywhere = edit(&a, "", "x");
+        insert_everywhere.replace_all = true;
+        let result = multi_edit(&[insert_everywhere]);
+        assert!(matches!(result, Err(ToolError::InvalidArgument(_))));
+        assert_eq!(fs::read_to_string(&a).unwrap(), "alpha\n");
+    }
+
+    #[test]
+    fn test_multi_edit_keeps_line_endings_and_bom() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.cs");
+        fs::write(&a, "\u{feff}class A\r\n{\r\n}\r\n").unwrap();
+
+        multi_edit(&[edit(&a, "{\n}", "{\n    int x;\n}")]).unwrap();
+        assert_eq!(
+            fs::read(&a).unwrap(),
+            "\u{feff}class A\r\n{\r\n    int x;\r\n}\r\n".as_bytes()
+        );
+    }
+}
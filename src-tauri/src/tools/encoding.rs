@@ -0,0 +1,204 @@
+//! Text encodings and line endings
+//!
+//! Files are decoded to UTF-8 for display and editing, whatever encoding
+//! they were saved in, and encoded back the same way when written, so editing
+//! a Latin-1 or UTF-16 file with CRLF line endings doesn't change either.
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use serde::{Deserialize, Serialize};
+
+use super::{ToolError, ToolResult};
+
+/// Bytes inspected when guessing whether BOM-less text is UTF-16
+const UTF16_SNIFF_BYTES: usize = 1024;
+
+/// How lines end in a file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    /// The line ending used by most lines of `text`
+    pub fn detect(text: &str) -> Self {
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+        if crlf > lf {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// Convert every line ending in `text` to this one
+    pub fn apply(&self, text: &str) -> String {
+        let normalized = text.replace("\r\n", "\n");
+        match self {
+            LineEnding::Lf => normalized,
+            LineEnding::Crlf => normalized.replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// How a text file's bytes were encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextFormat {
+    pub encoding: &'static Encoding,
+    /// Whether the file starts with a byte order mark
+    pub bom: bool,
+    pub line_ending: LineEnding,
+}
+
+impl TextFormat {
+    /// Whether this is plain UTF-8 with LF line endings, which needs no conversion
+    pub fn is_plain_utf8(&self) -> bool {
+        self.encoding == UTF_8 && !self.bom && self.line_ending == LineEnding::Lf
+    }
+
+    /// Name of the encoding, e.g. `UTF-8`, `windows-1252` or `UTF-16LE`
+    pub fn encoding_name(&self) -> &'static str {
+        self.encoding.name()
+    }
+}
+
+/// Guess the byte order of UTF-16 text without a BOM from where its NULs are
+///
+/// Mostly-ASCII UTF-16 text has a NUL in every other byte.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(UTF16_SNIFF_BYTES) & !1];
+    if head.len() < 4 {
+        return None;
+    }
+    let pairs = head.len() / 2;
+    let even_nuls = head.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_nuls = head.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+
+    if odd_nuls * 10 >= pairs * 7 && even_nuls * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_nuls * 10 >= pairs * 7 && odd_nuls * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Decode a text file's bytes to UTF-8
+///
+/// The encoding is taken from a byte order mark if there is one; otherwise
+/// UTF-8 is assumed if the bytes are valid UTF-8, and guessed if not.
+///
+/// # Returns
+/// The text and its format, or `None` if the bytes don't look like text
+pub fn decode_text(bytes: &[u8]) -> Option<(String, TextFormat)> {
+    let (encoding, bom_len) = match Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) => (encoding, bom_len),
+        None => match sniff_utf16(bytes) {
+            Some(encoding) => (encoding, 0),
+            None if bytes.contains(&0) => return None,
+            None if std::str::from_utf8(bytes).is_ok() => (UTF_8, 0),
+            None => {
+                let mut detector = EncodingDetector::new();
+                detector.feed(bytes, true);
+                (detector.guess(None, true), 0)
+            }
+        },
+    };
+
+    let (text, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+    if had_errors {
+        return None;
+    }
+    let text = text.into_owned();
+    let format = TextFormat {
+        encoding,
+        bom: bom_len > 0,
+        line_ending: LineEnding::detect(&text),
+    };
+    Some((text, format))
+}
+
+/// Encode text in a file's format
+///
+/// Line endings are converted to the format's unless the text's own already
+/// match.
+pub fn encode_text(text: &str, format: &TextFormat) -> ToolResult<Vec<u8>> {
+    let text = if LineEnding::detect(text) == format.line_ending {
+        std::borrow::Cow::Borrowed(text)
+    } else {
+        std::borrow::Cow::Owned(format.line_ending.apply(text))
+    };
+
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+    if format.encoding == UTF_16LE || format.encoding == UTF_16BE {
+        let little_endian = format.encoding == UTF_16LE;
+        let units = std::iter::once('\u{feff}')
+            .filter(|_| format.bom)
+            .chain(text.chars())
+            .flat_map(|c| {
+                let mut buffer = [0u16; 2];
+                c.encode_utf16(&mut buffer).to_vec()
+            });
+        for unit in units {
+            bytes.extend(if little_endian {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            });
+        }
+        return Ok(bytes);
+    }
+
+    if format.bom && format.encoding == UTF_8 {
+        bytes.extend_from_slice(b"\xef\xbb\xbf");
+    }
+    let (encoded, _, had_errors) = format.encoding.encode(&text);
+    if had_errors {
+        return Err(ToolError::InvalidArgument(format!(
+            "The content has characters that can't be saved in the file's {} encoding",
+            format.encoding.name()
+        )));
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_latin1_and_utf16() {
+        // French text with CRLF line endings, saved as windows-1252
+        let latin1 = b"Caf\xe9 au lait, cr\xe8me br\xfbl\xe9e\r\nna\xefve\r\n";
+        let (text, format) = decode_text(latin1).unwrap();
+        assert_eq!(text, "Café au lait, crème brûlée\r\nnaïve\r\n");
+        assert_eq!(format.encoding_name(), "windows-1252");
+        assert_eq!(format.line_ending, LineEnding::Crlf);
+        // Edited text with LF line endings is written back as it was saved
+        let encoded = encode_text("Café au lait, crème brûlée\nnaïve\n", &format).unwrap();
+        assert_eq!(encoded, latin1);
+        assert!(encode_text("日本", &format).is_err());
+
+        let utf16: Vec<u8> = b"\xff\xfe"
+            .iter()
+            .copied()
+            .chain("héllo\n".encode_utf16().flat_map(|u| u.to_le_bytes()))
+            .collect();
+        let (text, format) = decode_text(&utf16).unwrap();
+        assert_eq!((text.as_str(), format.bom), ("héllo\n", true));
+        assert_eq!(encode_text(&text, &format).unwrap(), utf16);
+
+        let bomless: Vec<u8> = "plain ascii text"
+            .encode_utf16()
+            .flat_map(|u| u.to_be_bytes())
+            .collect();
+        assert_eq!(decode_text(&bomless).unwrap().1.encoding, UTF_16BE);
+
+        assert!(decode_text(b"plain\n").unwrap().1.is_plain_utf8());
+        assert!(decode_text(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0\x03\0").is_none());
+    }
+}
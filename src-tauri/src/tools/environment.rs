@@ -0,0 +1,124 @@
+//! Environment report for the tools system
+//!
+//! This module describes the machine a project runs on: operating system,
+//! architecture, shell and installed toolchains. It lets an AI assistant
+//! pick commands that will work here without asking the user.
+
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+/// Toolchains probed, as (name, candidate commands); the first that runs wins
+const TOOLCHAINS: &[(&str, &[&str])] = &[
+    ("rustc", &["rustc --version"]),
+    ("cargo", &["cargo --version"]),
+    ("node", &["node --version"]),
+    ("npm", &["npm --version"]),
+    ("python", &["python3 --version", "python --version"]),
+    ("go", &["go version"]),
+    ("git", &["git --version"]),
+];
+
+/// An installed toolchain and its version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolchainVersion {
+    pub name: String,
+    /// First line of the version output, or `None` if it isn't installed
+    pub version: Option<String>,
+}
+
+/// Description of the machine and project the tools run against
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentInfo {
+    /// Operating system, e.g. "macos", "linux" or "windows"
+    pub os: String,
+    /// CPU architecture, e.g. "aarch64" or "x86_64"
+    pub arch: String,
+    /// "unix" or "windows"
+    pub family: String,
+    /// The user's shell, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_root: Option<String>,
+    pub toolchains: Vec<ToolchainVersion>,
+}
+
+/// Run a version command and return the first line it prints
+fn probe_version(command: &str) -> Option<String> {
+    let mut parts = command.split_whitespace();
+    let output = Command::new(parts.next()?).args(parts).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // Older Pythons print their version to stderr
+    let text = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    String::from_utf8_lossy(&text)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+}
+
+/// The user's shell from the environment
+fn detect_shell() -> Option<String> {
+    let var = if cfg!(target_os = "windows") {
+        "COMSPEC"
+    } else {
+        "SHELL"
+    };
+    std::env::var(var).ok().filter(|s| !s.is_empty())
+}
+
+/// Describe the current environment
+///
+/// Toolchains are probed in parallel, so this takes about as long as the
+/// slowest version command.
+///
+/// # Arguments
+/// * `project_root` - The open project, if any
+pub fn environment_info(project_root: Option<&Path>) -> EnvironmentInfo {
+    let probes: Vec<_> = TOOLCHAINS
+        .iter()
+        .map(|&(name, commands)| {
+            thread::spawn(move || ToolchainVersion {
+                name: name.to_string(),
+                version: commands.iter().find_map(|command| probe_version(command)),
+            })
+        })
+        .collect();
+    let toolchains = probes
+        .into_iter()
+        .filter_map(|probe| probe.join().ok())
+        .collect();
+
+    EnvironmentInfo {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        family: std::env::consts::FAMILY.to_string(),
+        shell: detect_shell(),
+        project_root: project_root.map(|p| p.display().to_string()),
+        toolchains,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_info() {
+        let info = environment_info(Some(Path::new("/projects/app")));
+        assert_eq!(info.os, std::env::consts::OS);
+        assert_eq!(info.project_root.as_deref(), Some("/projects/app"));
+        assert_eq!(info.toolchains.len(), TOOLCHAINS.len());
+        assert!(info.toolchains.iter().any(|t| t.name == "git"));
+    }
+}
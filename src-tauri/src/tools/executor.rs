@@ -1,68 +1,1111 @@
 //! Tool executor - routes tool calls to their implementations
 //!
-//! This module provides the ToolExecutor which receives tool calls from AI providers
-//! and routes them to the appropriate tool implementations.
+//! This module implements the built-in tools on top of the `Tool` trait,
+//! along with the risk classes and path jail the chat commands rely on.
 
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-use super::{file_ops, search, ToolError, ToolResult};
-use crate::providers::ToolCall;
+use super::{
+    ast, command, database, diagnostics, edit, environment, file_ops, forge, format, fuzzy, git, git_hooks, notebook, patch, rename, replace, search, tree, web, FileContent, FileEdit, Tool, ToolContext, ToolDefinition,
+    CellEditMode, SearchLimits, TodoAction, DEFAULT_DIAGNOSTICS_TIMEOUT_SECS, DEFAULT_QUERY_ROWS, ToolError, TreeOptions, ToolRegistry, ToolResult, DEFAULT_MAX_SEARCH_RESULTS, TOOL_IMAGE_KEY,
+};
+use crate::providers::{ContentBlock, ImageSource, ToolCall};
 
-/// Execute a tool call and return the result as JSON
-pub fn execute_tool(tool_call: &ToolCall) -> ToolResult<Value> {
-    match tool_call.name.as_str() {
-        "read_file" => execute_read_file(&tool_call.arguments),
-        "write_file" => execute_write_file(&tool_call.arguments),
-        "list_directory" => execute_list_directory(&tool_call.arguments),
-        "search_files" => execute_search_files(&tool_call.arguments),
-        "grep_files" => execute_grep_files(&tool_call.arguments),
-        _ => Err(ToolError::ToolNotFound(tool_call.name.clone())),
+/// How much damage a tool can do, used to decide whether a call needs approval
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskClass {
+    /// Only reads the workspace
+    Read,
+    /// Modifies files
+    Write,
+    /// Makes requests to the network
+    Network,
+    /// Runs arbitrary commands
+    Execute,
+}
+
+impl RiskClass {
+    /// Whether calls of this class must be approved by the user
+    pub fn requires_approval(self) -> bool {
+        self != RiskClass::Read
+    }
+
+    /// Whether calls of this class may run alongside other calls
+    ///
+    /// Writes and commands run one at a time, in order, so a later call sees
+    /// the effects of earlier ones.
+    pub fn runs_concurrently(self) -> bool {
+        matches!(self, RiskClass::Read | RiskClass::Network)
+    }
+}
+
+/// The user's answer to a permission request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionDecision {
+    /// Run this call only
+    AllowOnce,
+    /// Run this call and every later call of the same tool in the session
+    AlwaysAllow,
+    Deny,
+}
+
+/// Payload of the event asking the frontend to approve a tool call
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionRequest {
+    pub request_id: String,
+    pub session_id: String,
+    pub tool_use_id: String,
+    pub tool_name: String,
+    pub arguments: Value,
+    pub risk: RiskClass,
+}
+
+/// Canonicalize a path that may not exist yet
+///
+/// The deepest existing ancestor is canonicalized (resolving symlinks) and the
+/// remaining components are appended. `..` is rejected in the missing part,
+/// since it can't be resolved against the real filesystem.
+fn canonicalize_lenient(path: &Path) -> ToolResult<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+
+    while !existing.exists() {
+        match (existing.file_name(), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => {
+                return Err(ToolError::PermissionDenied(format!(
+                    "Cannot resolve path: {}",
+                    path.display()
+                )))
+            }
+        }
+    }
+
+    let mut resolved = existing.canonicalize()?;
+    for name in missing.iter().rev() {
+        resolved.push(name);
     }
+    Ok(resolved)
+}
+
+/// Resolve a tool path argument, enforcing the context's path jail
+///
+/// Relative paths resolve against the working directory. When the context is
+/// jailed, the canonical path must lie inside the working directory or one of
+/// the allowed paths.
+pub fn resolve_path(context: &ToolContext, path: &str) -> ToolResult<PathBuf> {
+    let requested = Path::new(path);
+    let joined = match &context.working_dir {
+        Some(root) if requested.is_relative() => root.join(requested),
+        _ => requested.to_path_buf(),
+    };
+
+    if !context.jailed {
+        return Ok(joined);
+    }
+
+    if requested.is_relative() && context.working_dir.is_none() {
+        return Err(ToolError::PermissionDenied(
+            "No project directory is set; only absolute paths in allowed directories can be accessed"
+                .to_string(),
+        ));
+    }
+
+    let resolved = canonicalize_lenient(&joined)?;
+    let inside = context
+        .working_dir
+        .iter()
+        .chain(context.allowed_paths.iter())
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| resolved.starts_with(root));
+
+    if inside {
+        Ok(resolved)
+    } else {
+        Err(ToolError::PermissionDenied(format!(
+            "Path is outside the project directory: {}",
+            path
+        )))
+    }
+}
+
+/// A built-in tool backed by a handler function
+struct BuiltinTool {
+    definition: ToolDefinition,
+    risk: RiskClass,
+    handler: ToolHandler,
+}
+
+type ToolHandler = fn(&Value, &ToolContext) -> ToolResult<Value>;
+
+/// Read tools whose calls depend on the order they run in
+const SEQUENTIAL_TOOLS: &[&str] = &["manage_todos"];
+
+impl Tool for BuiltinTool {
+    fn name(&self) -> &str {
+        &self.definition.name
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        self.definition.clone()
+    }
+
+    fn risk(&self) -> RiskClass {
+        self.risk
+    }
+
+    fn runs_concurrently(&self) -> bool {
+        self.risk.runs_concurrently() && !SEQUENTIAL_TOOLS.contains(&self.name())
+    }
+
+    fn execute(&self, args: &Value, context: &ToolContext) -> ToolResult<Value> {
+        (self.handler)(args, context)
+    }
+}
+
+fn builtin(definition: ToolDefinition, risk: RiskClass, handler: ToolHandler) -> Arc<dyn Tool> {
+    Arc::new(BuiltinTool {
+        definition,
+        risk,
+        handler,
+    })
+}
+
+/// All built-in tools
+pub fn builtin_tools() -> Vec<Arc<dyn Tool>> {
+    vec![
+        builtin(
+            ToolDefinition {
+                name: "read_file".to_string(),
+                description: "Read the contents of a file at the given path. Images (PNG, JPEG, GIF, WebP) are returned so you can see them; other binary files return only their type and size".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The path to the file to read"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            RiskClass::Read,
+            execute_read_file,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "write_file".to_string(),
+                description: "Write content to a file at the given path".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The path to the file to write"
+                        },
+                        "content": {
+                            "type": "string",
+                            "description": "The content to write to the file"
+                        },
+                        "expected_hash": {
+                            "type": "string",
+                            "description": "The hash read_file returned for this file. The write fails if the file changed since, e.g. because the user edited it; read it again before retrying"
+                        }
+                    },
+                    "required": ["path", "content"]
+                }),
+            },
+            RiskClass::Write,
+            execute_write_file,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "list_directory".to_string(),
+                description: "List the contents of a directory".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The path to the directory to list"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            RiskClass::Read,
+            execute_list_directory,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "directory_tree".to_string(),
+                description: "Show a directory as an indented tree (like `tree -L 3`), skipping gitignored files. Deeper directories show their entry counts. Use this first to get oriented in a project".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Optional directory to show (defaults to the project directory)"
+                        },
+                        "max_depth": {
+                            "type": "integer",
+                            "description": "Optional number of levels to expand (default 3)"
+                        },
+                        "max_entries_per_dir": {
+                            "type": "integer",
+                            "description": "Optional number of entries shown per directory (default 50)"
+                        },
+                        "include_hidden": {
+                            "type": "boolean",
+                            "description": "Optional flag to include hidden files (default false)"
+                        },
+                        "respect_gitignore": {
+                            "type": "boolean",
+                            "description": "Optional flag to skip gitignored files (default true)"
+                        }
+                    }
+                }),
+            },
+            RiskClass::Read,
+            execute_directory_tree,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "environment_info".to_string(),
+                description: "Describe the environment: operating system, architecture, shell, project root and installed toolchain versions (rustc, cargo, node, npm, python, go, git). Use this to choose commands that will work on this machine".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+            RiskClass::Read,
+            execute_environment_info,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "manage_todos".to_string(),
+                description: "Keep a task list for multi-step work; the user sees it as your progress. Plan by adding tasks, mark one in_progress while working on it, and complete each as soon as it is done. Every action returns the current list".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "action": {
+                            "type": "string",
+                            "enum": ["list", "add", "update", "complete", "remove", "clear"],
+                            "description": "What to do with the list"
+                        },
+                        "items": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Tasks to add (add)"
+                        },
+                        "id": {
+                            "type": "string",
+                            "description": "ID of the task to change (update, complete, remove)"
+                        },
+                        "content": {
+                            "type": "string",
+                            "description": "New text for the task (update)"
+                        },
+                        "status": {
+                            "type": "string",
+                            "enum": ["pending", "in_progress", "completed"],
+                            "description": "New status for the task (update)"
+                        }
+                    },
+                    "required": ["action"]
+                }),
+            },
+            RiskClass::Read,
+            execute_manage_todos,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "save_memory".to_string(),
+                description: "Remember a durable fact about this project for future sessions, such as its package manager, how to run its tests or a convention the user asked you to follow. Saved facts are shown to you at the start of every session in this project. Save one short, self-contained fact per call; don't save anything specific to the current task".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "content": {
+                            "type": "string",
+                            "description": "The fact to remember, e.g. 'This repo uses pnpm, not npm'"
+                        }
+                    },
+                    "required": ["content"]
+                }),
+            },
+            // Saved memories go into every later session's system prompt
+            RiskClass::Write,
+            execute_save_memory,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "search_files".to_string(),
+                description: "Search for files matching a glob pattern".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "pattern": {
+                            "type": "string",
+                            "description": "The glob pattern to match (e.g., '**/*.rs')"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "The base directory to search in"
+                        },
+                        "max_results": {
+                            "type": "integer",
+                            "description": "Optional maximum number of matches to return (default 200)"
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Optional number of matches to skip, to fetch the next page"
+                        }
+                    },
+                    "required": ["pattern", "path"]
+                }),
+            },
+            RiskClass::Read,
+            execute_search_files,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "grep_files".to_string(),
+                description: "Search for text in files using a regex pattern".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "The regex pattern to search for"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "The directory to search in"
+                        },
+                        "file_pattern": {
+                            "type": "string",
+                            "description": "Optional glob pattern to filter files (e.g., '*.rs')"
+                        },
+                        "max_results": {
+                            "type": "integer",
+                            "description": "Optional maximum number of matches to return (default 200)"
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Optional number of matches to skip, to fetch the next page"
+                        },
+                        "max_per_file": {
+                            "type": "integer",
+                            "description": "Optional maximum number of matches per file (default 20)"
+                        }
+                    },
+                    "required": ["query", "path"]
+                }),
+            },
+            RiskClass::Read,
+            execute_grep_files,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "find_file".to_string(),
+                description: "Find files by a loose, possibly misspelled description of their path (e.g. 'provder typs' finds 'providers/types.rs'). Every word must fuzzily match the path; best matches come first".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "Words from the file's path or name"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Optional directory to search in (defaults to the project directory)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Optional maximum number of files to return (default 20)"
+                        }
+                    },
+                    "required": ["query"]
+                }),
+            },
+            RiskClass::Read,
+            execute_find_file,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "list_symbols".to_string(),
+                description: "List the functions, types, classes and imports defined in a source file (Rust, Python, JavaScript, TypeScript, Go) with their line ranges. Use this to navigate large files instead of reading them whole".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The path to the source file"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            RiskClass::Read,
+            execute_list_symbols,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "get_symbol_source".to_string(),
+                description: "Get the full source of a function, type or class in a source file by name".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The path to the source file"
+                        },
+                        "name": {
+                            "type": "string",
+                            "description": "The symbol name, optionally qualified by its parent (e.g. 'Parser::parse' or 'Parser.parse')"
+                        }
+                    },
+                    "required": ["path", "name"]
+                }),
+            },
+            RiskClass::Read,
+            execute_get_symbol_source,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "apply_patch".to_string(),
+                description: "Apply a unified diff to files in a directory. Hunks tolerate line offsets and small context mismatches; the result reports each hunk's outcome and includes the text of rejected hunks so they can be fixed and resubmitted".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "patch": {
+                            "type": "string",
+                            "description": "The unified diff to apply, with '---'/'+++' file headers and '@@' hunks"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "The directory the paths in the diff are relative to"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Check whether the patch applies without writing any files"
+                        }
+                    },
+                    "required": ["patch", "path"]
+                }),
+            },
+            RiskClass::Write,
+            execute_apply_patch,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "multi_edit".to_string(),
+                description: "Apply several find-and-replace edits across one or more files as a single transaction. All edits are validated before anything is written; if any edit fails, no file is changed".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "edits": {
+                            "type": "array",
+                            "description": "Edits to apply in order; later edits to the same file see the result of earlier ones",
+                            "items": {
+                                "type": "object",
+                                "properties": {
+                                    "path": {
+                                        "type": "string",
+                                        "description": "The path to the file to edit"
+                                    },
+                                    "old_string": {
+                                        "type": "string",
+                                        "description": "The exact text to replace. Must be unique in the file unless replace_all is set. Use an empty string to create a new file"
+                                    },
+                                    "new_string": {
+                                        "type": "string",
+                                        "description": "The replacement text"
+                                    },
+                                    "replace_all": {
+                                        "type": "boolean",
+                                        "description": "Replace every occurrence of old_string"
+                                    }
+                                },
+                                "required": ["path", "old_string", "new_string"]
+                            }
+                        }
+                    },
+                    "required": ["edits"]
+                }),
+            },
+            RiskClass::Write,
+            execute_multi_edit,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "replace_in_files".to_string(),
+                description: "Replace every match of a regex across the project's files, optionally limited to a glob. Returns the number of replacements per file and a unified diff. Set dry_run to preview the changes without writing them".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "pattern": {
+                            "type": "string",
+                            "description": "The regex to search for"
+                        },
+                        "replacement": {
+                            "type": "string",
+                            "description": "The replacement text; use $1 or ${name} to insert capture groups and $$ for a literal $"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Optional directory to search in (default: the project root)"
+                        },
+                        "file_pattern": {
+                            "type": "string",
+                            "description": "Optional glob of files to change, e.g. '*.rs' or 'src/**/*.ts'"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Preview the changes without writing them"
+                        }
+                    },
+                    "required": ["pattern", "replacement"]
+                }),
+            },
+            RiskClass::Write,
+            execute_replace_in_files,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "rename_symbol".to_string(),
+                description: "Rename an identifier across the project's source files (Rust, Python, JavaScript, TypeScript, Go). Only identifier tokens are renamed; strings, comments and longer names containing the old name are left alone. Scopes are not resolved, so unrelated symbols with the same name are renamed too: use dry_run to review the touched lines first".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "old_name": {
+                            "type": "string",
+                            "description": "The identifier to rename"
+                        },
+                        "new_name": {
+                            "type": "string",
+                            "description": "The new identifier"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Optional directory or file to rename in (default: the project root)"
+                        },
+                        "file_pattern": {
+                            "type": "string",
+                            "description": "Optional glob of files to change, e.g. 'src/**/*.rs'"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "List the occurrences without changing any file"
+                        }
+                    },
+                    "required": ["old_name", "new_name"]
+                }),
+            },
+            RiskClass::Write,
+            execute_rename_symbol,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "format_file".to_string(),
+                description: "Format a file in place with the standard formatter for its language (rustfmt, prettier, black or gofmt), using the project's formatter configuration".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The path to the file to format"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            RiskClass::Write,
+            execute_format_file,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "read_notebook".to_string(),
+                description: "Read a Jupyter notebook (.ipynb) as a list of cells with their indices, types and sources. Outputs are shown as text and truncated; images and other rich outputs are only named".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The path to the notebook"
+                        },
+                        "max_output_chars": {
+                            "type": "integer",
+                            "description": "Optional number of output characters shown per cell (default 2000, 0 to omit outputs)"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            RiskClass::Read,
+            execute_read_notebook,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "edit_notebook_cell".to_string(),
+                description: "Edit one cell of a Jupyter notebook (.ipynb) without touching the rest of it. Replace a cell's source or type, insert a new cell before an index, or delete a cell. Editing a code cell clears its outputs".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The path to the notebook"
+                        },
+                        "cell_index": {
+                            "type": "integer",
+                            "description": "Index of the cell to edit, or to insert before (use the cell count to append)"
+                        },
+                        "new_source": {
+                            "type": "string",
+                            "description": "The new source of the cell (for replace and insert)"
+                        },
+                        "cell_type": {
+                            "type": "string",
+                            "enum": ["code", "markdown", "raw"],
+                            "description": "Optional new cell type; inserted cells default to code"
+                        },
+                        "edit_mode": {
+                            "type": "string",
+                            "enum": ["replace", "insert", "delete"],
+                            "description": "Optional kind of edit (default replace)"
+                        }
+                    },
+                    "required": ["path", "cell_index"]
+                }),
+            },
+            RiskClass::Write,
+            execute_edit_notebook_cell,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "query_database".to_string(),
+                description: "Inspect a SQLite database file in the project. Without 'sql', returns the schema (tables, views, indexes and triggers with their CREATE statements). With 'sql', runs one read-only statement and returns the rows".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "The path to the SQLite database file"
+                        },
+                        "sql": {
+                            "type": "string",
+                            "description": "Optional read-only SQL statement, e.g. a SELECT or PRAGMA"
+                        },
+                        "max_rows": {
+                            "type": "integer",
+                            "description": "Optional maximum rows to return (default 100, max 1000)"
+                        }
+                    },
+                    "required": ["path"]
+                }),
+            },
+            RiskClass::Read,
+            execute_query_database,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "git_status".to_string(),
+                description: "Show the current branch and the staged, unstaged and untracked files of the project's git repository".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Optional repository directory (defaults to the project directory)"
+                        }
+                    }
+                }),
+            },
+            RiskClass::Read,
+            execute_git_status,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "git_diff".to_string(),
+                description: "Show uncommitted changes in the project's git repository as a unified diff".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Optional repository directory (defaults to the project directory)"
+                        },
+                        "staged": {
+                            "type": "boolean",
+                            "description": "Optional flag to show staged changes instead of unstaged ones (default false)"
+                        },
+                        "file": {
+                            "type": "string",
+                            "description": "Optional file to limit the diff to, relative to the repository"
+                        }
+                    }
+                }),
+            },
+            RiskClass::Read,
+            execute_git_diff,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "git_log".to_string(),
+                description: "Show recent commits of the project's git repository, newest first".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Optional repository directory (defaults to the project directory)"
+                        },
+                        "count": {
+                            "type": "integer",
+                            "description": "Optional number of commits to show (default 10, max 100)"
+                        },
+                        "skip": {
+                            "type": "integer",
+                            "description": "Optional number of matching commits to skip, to page through history"
+                        },
+                        "branch": {
+                            "type": "string",
+                            "description": "Optional branch or revision to list from (defaults to HEAD)"
+                        },
+                        "author": {
+                            "type": "string",
+                            "description": "Optional text to match in the author's name or email"
+                        },
+                        "grep": {
+                            "type": "string",
+                            "description": "Optional text to match in commit messages"
+                        },
+                        "file": {
+                            "type": "string",
+                            "description": "Optional file or directory; only commits changing it are shown"
+                        }
+                    }
+                }),
+            },
+            RiskClass::Read,
+            execute_git_log,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "git_search_commits".to_string(),
+                description: "Search the project's git history for commits that add or remove some text, like `git log -S`, e.g. to find when a function was introduced. Can search commit messages instead".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "The exact text to search for, e.g. a function name"
+                        },
+                        "messages": {
+                            "type": "boolean",
+                            "description": "Optional flag to search commit messages (ignoring case) instead of changes (default false)"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Optional repository directory (defaults to the project directory)"
+                        },
+                        "count": {
+                            "type": "integer",
+                            "description": "Optional number of commits to show (default 10, max 100)"
+                        }
+                    },
+                    "required": ["query"]
+                }),
+            },
+            RiskClass::Read,
+            execute_git_search_commits,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "git_check_ignore".to_string(),
+                description: "Check whether files are ignored by git, and which .gitignore rule decides it. Use this when a file is missing from git status".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "paths": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Files or directories to check, relative to the repository"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Optional repository directory (defaults to the project directory)"
+                        }
+                    },
+                    "required": ["paths"]
+                }),
+            },
+            RiskClass::Read,
+            execute_git_check_ignore,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "add_to_gitignore".to_string(),
+                description: "Add a pattern, such as a build output directory, to the .gitignore at the repository root".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "pattern": {
+                            "type": "string",
+                            "description": "The gitignore pattern, e.g. 'target/' or '*.log'"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Optional repository directory (defaults to the project directory)"
+                        }
+                    },
+                    "required": ["pattern"]
+                }),
+            },
+            RiskClass::Write,
+            execute_add_to_gitignore,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "git_commit".to_string(),
+                description: "Commit changes to the project's git repository. Stages the given files (or all changes) first, then commits everything staged".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "message": {
+                            "type": "string",
+                            "description": "The commit message"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Optional repository directory (defaults to the project directory)"
+                        },
+                        "files": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Optional files to stage before committing, relative to the repository"
+                        },
+                        "all": {
+                            "type": "boolean",
+                            "description": "Optional flag to stage all changes, including untracked files, before committing (default false)"
+                        },
+                        "no_verify": {
+                            "type": "boolean",
+                            "description": "Optional flag to skip the pre-commit and commit-msg hooks (default false). Only use this when the user asks"
+                        }
+                    },
+                    "required": ["message"]
+                }),
+            },
+            RiskClass::Write,
+            execute_git_commit,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "create_pull_request".to_string(),
+                description: "Open a pull request (a merge request on GitLab) on the forge hosting the project's origin remote. Push the branch first. Needs GITHUB_TOKEN or GITLAB_TOKEN to be set".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "title": {
+                            "type": "string",
+                            "description": "The pull request title"
+                        },
+                        "body": {
+                            "type": "string",
+                            "description": "The pull request description, in markdown"
+                        },
+                        "base": {
+                            "type": "string",
+                            "description": "The branch to merge into, e.g. main"
+                        },
+                        "head": {
+                            "type": "string",
+                            "description": "Optional branch with the changes (defaults to the current branch)"
+                        },
+                        "path": {
+                            "type": "string",
+                            "description": "Optional repository directory (defaults to the project directory)"
+                        }
+                    },
+                    "required": ["title", "body", "base"]
+                }),
+            },
+            RiskClass::Write,
+            execute_create_pull_request,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "get_diagnostics".to_string(),
+                description: "Check the project for compiler and linter errors (cargo check, tsc, go vet) and return them with file, line and message. Run this after editing code to verify it compiles".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "file": {
+                            "type": "string",
+                            "description": "Optional file to report diagnostics for, relative to the project; its whole project is still checked"
+                        },
+                        "timeout_secs": {
+                            "type": "integer",
+                            "description": "Optional time each checker may run (default 300, max 600)"
+                        }
+                    }
+                }),
+            },
+            RiskClass::Execute,
+            execute_get_diagnostics,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "run_command".to_string(),
+                description: "Run a shell command in the project directory and return its exit code, stdout and stderr. Use for builds, tests and linters. Long output is truncated in the middle; commands are killed after the timeout".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "command": {
+                            "type": "string",
+                            "description": "The command line to run through the shell"
+                        },
+                        "cwd": {
+                            "type": "string",
+                            "description": "Optional working directory, absolute or relative to the project directory"
+                        },
+                        "timeout_secs": {
+                            "type": "integer",
+                            "description": "Optional timeout in seconds (default 120, max 600)"
+                        }
+                    },
+                    "required": ["command"]
+                }),
+            },
+            RiskClass::Execute,
+            execute_run_command,
+        ),
+        builtin(
+            ToolDefinition {
+                name: "fetch_url".to_string(),
+                description: "Fetch a web page (e.g. documentation) over http or https and return its content as text. HTML is converted to markdown-style text; long content is truncated".to_string(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "url": {
+                            "type": "string",
+                            "description": "The http or https URL to fetch"
+                        },
+                        "max_chars": {
+                            "type": "integer",
+                            "description": "Optional maximum characters of content to return (default 50000)"
+                        }
+                    },
+                    "required": ["url"]
+                }),
+            },
+            RiskClass::Network,
+            execute_fetch_url,
+        ),
+    ]
+}
+
+/// Execute a tool call without a project context or path restrictions
+pub fn execute_tool(tool_call: &ToolCall) -> ToolResult<Value> {
+    execute_tool_with_context(tool_call, &ToolContext::default())
+}
+
+/// Execute a built-in tool call in the given context and return the result as JSON
+pub fn execute_tool_with_context(tool_call: &ToolCall, context: &ToolContext) -> ToolResult<Value> {
+    ToolRegistry::with_builtin_tools().execute(tool_call, context)
 }
 
 /// Execute read_file tool
-fn execute_read_file(args: &Value) -> ToolResult<Value> {
+fn execute_read_file(args: &Value, context: &ToolContext) -> ToolResult<Value> {
     let path = args
         .get("path")
         .and_then(|v| v.as_str())
         .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
 
-    let content = file_ops::read_file(path)?;
-
-    Ok(json!({
-        "success": true,
-        "content": content
-    }))
+    match file_ops::read_file_content(&path)? {
+        FileContent::Text {
+            content,
+            encoding,
+            line_ending,
+            bom,
+        } => Ok(json!({
+            "success": true,
+            "content": content,
+            "encoding": encoding,
+            "line_ending": line_ending,
+            "bom": bom,
+            "hash": file_ops::file_version(&path).ok().map(|v| v.hash)
+        })),
+        FileContent::Image { media_type, data, size } => Ok(json!({
+            "success": true,
+            "kind": "image",
+            "media_type": media_type,
+            "size": size,
+            TOOL_IMAGE_KEY: ContentBlock::Image {
+                source: ImageSource::Base64 { media_type, data },
+            }
+        })),
+        FileContent::Binary { media_type, size } => Ok(json!({
+            "success": true,
+            "kind": "binary",
+            "media_type": media_type,
+            "size": size,
+            "content": "Binary file; contents not shown"
+        })),
+    }
 }
 
 /// Execute write_file tool
-fn execute_write_file(args: &Value) -> ToolResult<Value> {
+fn execute_write_file(args: &Value, context: &ToolContext) -> ToolResult<Value> {
     let path = args
         .get("path")
         .and_then(|v| v.as_str())
         .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
 
     let content = args
         .get("content")
         .and_then(|v| v.as_str())
         .ok_or_else(|| ToolError::InvalidArgument("Missing 'content' argument".to_string()))?;
 
-    file_ops::write_file(path, content)?;
+    let expected_hash = args.get("expected_hash").and_then(|v| v.as_str());
+    file_ops::check_file_version(&path, expected_hash, None)?;
+
+    context.snapshot_file(Path::new(path.as_ref()))?;
+    file_ops::write_file(&path, content)?;
+    let formatted_with = context.format_written_file(Path::new(path.as_ref()));
 
     Ok(json!({
         "success": true,
-        "message": format!("File written successfully: {}", path)
+        "message": format!("File written successfully: {}", path),
+        "formatted_with": formatted_with
     }))
 }
 
 /// Execute list_directory tool
-fn execute_list_directory(args: &Value) -> ToolResult<Value> {
+fn execute_list_directory(args: &Value, context: &ToolContext) -> ToolResult<Value> {
     let path = args
         .get("path")
         .and_then(|v| v.as_str())
         .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
 
-    let entries = file_ops::list_directory(path)?;
+    let entries = file_ops::list_directory(&path)?;
 
     Ok(json!({
         "success": true,
@@ -70,8 +1113,100 @@ fn execute_list_directory(args: &Value) -> ToolResult<Value> {
     }))
 }
 
+/// Per-file match cap for grep tool calls, so one noisy file can't fill a page
+const DEFAULT_TOOL_MATCHES_PER_FILE: usize = 20;
+
+/// Read pagination arguments shared by the search tools
+fn search_limits(args: &Value, default_per_file: Option<usize>) -> SearchLimits {
+    let arg = |name: &str| args.get(name).and_then(|v| v.as_u64()).map(|n| n as usize);
+    SearchLimits {
+        max_results: arg("max_results").unwrap_or(DEFAULT_MAX_SEARCH_RESULTS).max(1),
+        offset: arg("offset").unwrap_or(0),
+        max_per_file: arg("max_per_file").or(default_per_file),
+    }
+}
+
+/// Execute directory_tree tool
+fn execute_directory_tree(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+    let path = resolve_path(context, path)?;
+
+    let defaults = TreeOptions::default();
+    let usize_arg = |name: &str, default: usize| {
+        args.get(name)
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(default)
+    };
+    let options = TreeOptions {
+        max_depth: usize_arg("max_depth", defaults.max_depth),
+        max_entries_per_dir: usize_arg("max_entries_per_dir", defaults.max_entries_per_dir).max(1),
+        include_hidden: args
+            .get("include_hidden")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.include_hidden),
+        respect_ignore: args
+            .get("respect_gitignore")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.respect_ignore),
+    };
+
+    let tree = tree::directory_tree(&path.to_string_lossy(), &options)?;
+
+    Ok(json!({
+        "success": true,
+        "tree": tree.tree,
+        "truncated": tree.truncated
+    }))
+}
+
+/// Execute environment_info tool
+fn execute_environment_info(_args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let info = environment::environment_info(context.working_dir.as_deref());
+
+    Ok(json!({
+        "success": true,
+        "environment": info
+    }))
+}
+
+/// Execute manage_todos tool
+fn execute_manage_todos(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let todos = context
+        .todos
+        .as_ref()
+        .ok_or_else(|| ToolError::ExecutionFailed("No task list is available".to_string()))?;
+    let action: TodoAction = serde_json::from_value(args.clone())
+        .map_err(|e| ToolError::InvalidArgument(format!("Invalid todo action: {}", e)))?;
+
+    let items = todos.apply(action)?;
+
+    Ok(json!({
+        "success": true,
+        "todos": items
+    }))
+}
+
+/// Execute save_memory tool
+fn execute_save_memory(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let content = args
+        .get("content")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'content' argument".to_string()))?;
+    let memory = context.memory.as_ref().ok_or_else(|| {
+        ToolError::ExecutionFailed("Memory is only available in a project".to_string())
+    })?;
+
+    let entry = memory.remember(content)?;
+
+    Ok(json!({
+        "success": true,
+        "memory": entry
+    }))
+}
+
 /// Execute search_files tool
-fn execute_search_files(args: &Value) -> ToolResult<Value> {
+fn execute_search_files(args: &Value, context: &ToolContext) -> ToolResult<Value> {
     let pattern = args
         .get("pattern")
         .and_then(|v| v.as_str())
@@ -81,17 +1216,21 @@ fn execute_search_files(args: &Value) -> ToolResult<Value> {
         .get("path")
         .and_then(|v| v.as_str())
         .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
 
-    let matches = search::search_files(pattern, path)?;
+    let page = search::search_files(pattern, &path, &search_limits(args, None))?;
 
     Ok(json!({
         "success": true,
-        "matches": matches
+        "matches": page.results,
+        "total": page.total,
+        "has_more": page.has_more
     }))
 }
 
 /// Execute grep_files tool
-fn execute_grep_files(args: &Value) -> ToolResult<Value> {
+fn execute_grep_files(args: &Value, context: &ToolContext) -> ToolResult<Value> {
     let query = args
         .get("query")
         .and_then(|v| v.as_str())
@@ -101,32 +1240,622 @@ fn execute_grep_files(args: &Value) -> ToolResult<Value> {
         .get("path")
         .and_then(|v| v.as_str())
         .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
 
     let file_pattern = args.get("file_pattern").and_then(|v| v.as_str());
 
-    let results = search::grep_files(query, path, file_pattern)?;
+    let page = search::grep_files(
+        query,
+        &path,
+        file_pattern,
+        &search_limits(args, Some(DEFAULT_TOOL_MATCHES_PER_FILE)),
+    )?;
+
+    Ok(json!({
+        "success": true,
+        "results": page.results,
+        "count": page.count,
+        "total": page.total,
+        "has_more": page.has_more,
+        "files_truncated": page.files_truncated
+    }))
+}
+
+/// Execute find_file tool
+fn execute_find_file(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let query = args
+        .get("query")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'query' argument".to_string()))?;
+
+    let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
+
+    let limit = args
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or(fuzzy::DEFAULT_FUZZY_RESULTS);
+
+    let matches = fuzzy::fuzzy_find_files(query, &path, limit)?;
+
+    Ok(json!({
+        "success": true,
+        "files": matches
+            .iter()
+            .map(|m| m.relative_path.as_str())
+            .collect::<Vec<_>>()
+    }))
+}
+
+/// Execute list_symbols tool
+fn execute_list_symbols(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let path = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
+
+    let (language, symbols) = ast::list_symbols(&path)?;
+
+    Ok(json!({
+        "success": true,
+        "language": language,
+        "symbols": symbols
+    }))
+}
+
+/// Execute get_symbol_source tool
+fn execute_get_symbol_source(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let path = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
+
+    let name = args
+        .get("name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'name' argument".to_string()))?;
+
+    let symbols = ast::get_symbol_source(&path, name)?;
 
     Ok(json!({
         "success": true,
-        "results": results,
-        "count": results.len()
+        "symbols": symbols
     }))
 }
 
-/// Execute a tool and return the result as a string (for tool result messages)
-pub fn execute_tool_as_string(tool_call: &ToolCall) -> String {
-    match execute_tool(tool_call) {
-        Ok(value) => serde_json::to_string_pretty(&value).unwrap_or_else(|e| {
-            format!("{{\"error\": \"Failed to serialize result: {}\"}}", e)
-        }),
-        Err(e) => {
-            json!({
-                "success": false,
-                "error": e.to_string()
-            })
-            .to_string()
+/// Execute apply_patch tool
+fn execute_apply_patch(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let patch_text = args
+        .get("patch")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'patch' argument".to_string()))?;
+
+    let path = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
+
+    let dry_run = args.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    // Every file must be in the jail, even through symlinked directories
+    let files = patch::patch_paths(patch_text)?;
+    for file in &files {
+        let target = Path::new(path.as_ref()).join(file);
+        resolve_path(context, &target.to_string_lossy())?;
+    }
+    if !dry_run {
+        for file in files {
+            context.snapshot_file(&Path::new(path.as_ref()).join(file))?;
         }
     }
+
+    let result = patch::apply_patch(patch_text, &path, dry_run)?;
+    let formatted: Vec<&str> = result
+        .files
+        .iter()
+        .filter(|f| !dry_run && f.success && f.action != "deleted")
+        .filter(|f| {
+            context
+                .format_written_file(&Path::new(path.as_ref()).join(&f.path))
+                .is_some()
+        })
+        .map(|f| f.path.as_str())
+        .collect();
+
+    Ok(json!({
+        "formatted": formatted,
+        "success": result.rejected_hunks == 0 && result.files.iter().all(|f| f.success),
+        "result": result
+    }))
+}
+
+/// Execute multi_edit tool
+fn execute_multi_edit(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let edits = args
+        .get("edits")
+        .cloned()
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'edits' argument".to_string()))?;
+    let edits: Vec<FileEdit> = serde_json::from_value(edits)?;
+
+    let edits = edits
+        .into_iter()
+        .map(|mut e| {
+            e.path = resolve_path(context, &e.path)?.to_string_lossy().to_string();
+            Ok(e)
+        })
+        .collect::<ToolResult<Vec<FileEdit>>>()?;
+
+    for e in &edits {
+        context.snapshot_file(Path::new(&e.path))?;
+    }
+
+    let files = edit::multi_edit(&edits)?;
+    let formatted: Vec<&str> = files
+        .iter()
+        .filter(|f| context.format_written_file(Path::new(&f.path)).is_some())
+        .map(|f| f.path.as_str())
+        .collect();
+
+    Ok(json!({
+        "success": true,
+        "files": files,
+        "formatted": formatted
+    }))
+}
+
+/// Execute replace_in_files tool
+fn execute_replace_in_files(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let pattern = args
+        .get("pattern")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'pattern' argument".to_string()))?;
+    let replacement = args
+        .get("replacement")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'replacement' argument".to_string()))?;
+
+    let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+    let path = resolve_path(context, path)?;
+    let base = path.to_string_lossy();
+
+    let file_pattern = args.get("file_pattern").and_then(|v| v.as_str());
+    let dry_run = args.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    if !dry_run && context.snapshots.is_some() {
+        // Find the files that will change so they can be snapshotted first
+        let preview = replace::replace_in_files(pattern, replacement, &base, file_pattern, true)?;
+        for file in &preview.files {
+            let file = if path.is_file() { path.clone() } else { path.join(&file.path) };
+            context.snapshot_file(&file)?;
+        }
+    }
+
+    let result = replace::replace_in_files(pattern, replacement, &base, file_pattern, dry_run)?;
+
+    Ok(json!({
+        "success": true,
+        "result": result
+    }))
+}
+
+/// Execute rename_symbol tool
+fn execute_rename_symbol(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let old_name = args
+        .get("old_name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'old_name' argument".to_string()))?;
+    let new_name = args
+        .get("new_name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'new_name' argument".to_string()))?;
+
+    let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+    let path = resolve_path(context, path)?;
+    let base = path.to_string_lossy();
+
+    let file_pattern = args.get("file_pattern").and_then(|v| v.as_str());
+    let dry_run = args.get("dry_run").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    if !dry_run && context.snapshots.is_some() {
+        let preview = rename::rename_symbol(&base, old_name, new_name, file_pattern, true)?;
+        for file in &preview.files {
+            let file = if path.is_file() { path.clone() } else { path.join(&file.path) };
+            context.snapshot_file(&file)?;
+        }
+    }
+
+    let result = rename::rename_symbol(&base, old_name, new_name, file_pattern, dry_run)?;
+
+    Ok(json!({
+        "success": true,
+        "result": result
+    }))
+}
+
+/// Execute format_file tool
+fn execute_format_file(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let path = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+
+    context.snapshot_file(&path)?;
+    let result = format::format_file(&path.to_string_lossy())?;
+
+    Ok(json!({
+        "success": true,
+        "formatter": result.formatter,
+        "changed": result.changed
+    }))
+}
+
+/// Execute read_notebook tool
+fn execute_read_notebook(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let path = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
+
+    let max_output_chars = args
+        .get("max_output_chars")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or(notebook::DEFAULT_NOTEBOOK_OUTPUT_CHARS);
+
+    let notebook = notebook::read_notebook(&path, max_output_chars)?;
+
+    Ok(json!({
+        "success": true,
+        "language": notebook.language,
+        "cells": notebook.cells
+    }))
+}
+
+/// Execute edit_notebook_cell tool
+fn execute_edit_notebook_cell(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let path = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
+
+    let index = args
+        .get("cell_index")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'cell_index' argument".to_string()))?
+        as usize;
+
+    let mode = match args.get("edit_mode") {
+        Some(mode) => serde_json::from_value(mode.clone())?,
+        None => CellEditMode::default(),
+    };
+
+    context.snapshot_file(Path::new(path.as_ref()))?;
+    let cell_count = notebook::edit_notebook_cell(
+        &path,
+        index,
+        args.get("new_source").and_then(|v| v.as_str()),
+        args.get("cell_type").and_then(|v| v.as_str()),
+        mode,
+    )?;
+
+    Ok(json!({
+        "success": true,
+        "path": path,
+        "cell_count": cell_count
+    }))
+}
+
+/// Execute query_database tool
+fn execute_query_database(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let path = args
+        .get("path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'path' argument".to_string()))?;
+    let path = resolve_path(context, path)?;
+    let path = path.to_string_lossy();
+
+    let Some(sql) = args.get("sql").and_then(|v| v.as_str()) else {
+        let schema = database::database_schema(&path)?;
+        return Ok(json!({
+            "success": true,
+            "schema": schema
+        }));
+    };
+
+    let max_rows = args
+        .get("max_rows")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or(DEFAULT_QUERY_ROWS);
+
+    // The tool doesn't require approval, so it may never write
+    let result = database::query_database(&path, sql, max_rows, true)?;
+
+    Ok(json!({
+        "success": true,
+        "result": result
+    }))
+}
+
+/// Default number of commits shown by the git_log tool
+const DEFAULT_GIT_LOG_COUNT: u32 = 10;
+
+/// Most commits the git_log tool shows at once
+const MAX_GIT_LOG_COUNT: u32 = 100;
+
+/// Resolve the repository directory of a git tool call
+fn git_repo_path(args: &Value, context: &ToolContext) -> ToolResult<String> {
+    let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
+    Ok(resolve_path(context, path)?.to_string_lossy().to_string())
+}
+
+/// Execute git_status tool
+fn execute_git_status(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let path = git_repo_path(args, context)?;
+    let status = git::status(&path)?;
+
+    Ok(json!({
+        "success": true,
+        "status": status
+    }))
+}
+
+/// Execute git_diff tool
+fn execute_git_diff(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let path = git_repo_path(args, context)?;
+    let staged = args.get("staged").and_then(|v| v.as_bool()).unwrap_or(false);
+    let file = args.get("file").and_then(|v| v.as_str());
+
+    let diff = git::diff(&path, staged, file)?;
+
+    Ok(json!({
+        "success": true,
+        "diff": diff
+    }))
+}
+
+/// Execute git_log tool
+fn execute_git_log(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let path = git_repo_path(args, context)?;
+    let count = args
+        .get("count")
+        .and_then(|v| v.as_u64())
+        .map(|n| n.min(MAX_GIT_LOG_COUNT as u64) as u32)
+        .unwrap_or(DEFAULT_GIT_LOG_COUNT);
+
+    let text = |name: &str| args.get(name).and_then(|v| v.as_str()).map(str::to_string);
+    let filter = git::LogFilter {
+        skip: args.get("skip").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+        branch: text("branch"),
+        author: text("author"),
+        grep: text("grep"),
+        path: text("file"),
+        ..Default::default()
+    };
+    let commits = git::log_filtered(&path, count, &filter)?;
+
+    Ok(json!({
+        "success": true,
+        "commits": commits
+    }))
+}
+
+/// Execute git_search_commits tool
+fn execute_git_search_commits(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let query = args
+        .get("query")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'query' argument".to_string()))?;
+    let messages = args.get("messages").and_then(|v| v.as_bool()).unwrap_or(false);
+    let count = args
+        .get("count")
+        .and_then(|v| v.as_u64())
+        .map(|n| n.min(MAX_GIT_LOG_COUNT as u64) as u32)
+        .unwrap_or(DEFAULT_GIT_LOG_COUNT);
+    let path = git_repo_path(args, context)?;
+    let commits = git::search_commits(&path, query, !messages, count)?;
+
+    Ok(json!({
+        "success": true,
+        "commits": commits
+    }))
+}
+
+/// Execute git_check_ignore tool
+fn execute_git_check_ignore(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let paths: Vec<String> = args
+        .get("paths")
+        .cloned()
+        .map(serde_json::from_value)
+        .transpose()?
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'paths' argument".to_string()))?;
+    let path = git_repo_path(args, context)?;
+    let checks = git::check_ignore(&path, &paths)?;
+
+    Ok(json!({
+        "success": true,
+        "checks": checks
+    }))
+}
+
+/// Execute add_to_gitignore tool
+fn execute_add_to_gitignore(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let pattern = args
+        .get("pattern")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'pattern' argument".to_string()))?;
+    let path = git_repo_path(args, context)?;
+    let added = git::add_to_gitignore(&path, pattern)?;
+
+    Ok(json!({
+        "success": true,
+        "added": added
+    }))
+}
+
+/// Execute git_commit tool
+fn execute_git_commit(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let message = args
+        .get("message")
+        .and_then(|v| v.as_str())
+        .filter(|m| !m.trim().is_empty())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'message' argument".to_string()))?;
+    let path = git_repo_path(args, context)?;
+
+    if args.get("all").and_then(|v| v.as_bool()).unwrap_or(false) {
+        git::stage_all(&path)?;
+    }
+    if let Some(files) = args.get("files").cloned() {
+        let files: Vec<String> = serde_json::from_value(files)?;
+        git::stage(&path, &files)?;
+    }
+
+    let no_verify = args.get("no_verify").and_then(|v| v.as_bool()).unwrap_or(false);
+    let commit = git_hooks::commit_with_hooks(
+        &path,
+        message,
+        no_verify,
+        context.pre_commit_command.as_deref(),
+        &mut |_, _| {},
+    )?;
+
+    Ok(json!({
+        "success": true,
+        "commit": commit
+    }))
+}
+
+/// Execute create_pull_request tool
+fn execute_create_pull_request(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let arg = |name: &str| {
+        args.get(name)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArgument(format!("Missing '{}' argument", name)))
+    };
+    let (title, body, base) = (arg("title")?, arg("body")?, arg("base")?);
+    let path = git_repo_path(args, context)?;
+    let head = match args.get("head").and_then(|v| v.as_str()) {
+        Some(head) => head.to_string(),
+        None => git::status(&path)?.branch,
+    };
+
+    let client = forge::ForgeClient::for_repository(&path, None)?;
+    let pull_request = client.create_pr(title, body, base, &head)?;
+
+    Ok(json!({
+        "success": true,
+        "pull_request": pull_request
+    }))
+}
+
+/// Execute get_diagnostics tool
+fn execute_get_diagnostics(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let root = context.working_dir.clone().ok_or_else(|| {
+        ToolError::InvalidArgument("No project directory is set".to_string())
+    })?;
+    let file = args
+        .get("file")
+        .and_then(|v| v.as_str())
+        .map(|f| resolve_path(context, f))
+        .transpose()?;
+    let file = file
+        .as_deref()
+        .map(|f| f.strip_prefix(&root).unwrap_or(f).to_string_lossy().to_string());
+
+    let timeout_secs = args
+        .get("timeout_secs")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_DIAGNOSTICS_TIMEOUT_SECS)
+        .min(command::MAX_COMMAND_TIMEOUT_SECS);
+
+    let report = diagnostics::get_diagnostics(
+        &root.to_string_lossy(),
+        file.as_deref(),
+        &diagnostics::builtin_diagnostics_runners(),
+        Duration::from_secs(timeout_secs),
+    )?;
+
+    if report.runners.is_empty() {
+        return Ok(json!({
+            "success": false,
+            "error": "No supported checker found (looked for Cargo.toml, tsconfig.json and go.mod)"
+        }));
+    }
+
+    Ok(json!({
+        "success": true,
+        "report": report
+    }))
+}
+
+/// Execute run_command tool
+fn execute_run_command(args: &Value, context: &ToolContext) -> ToolResult<Value> {
+    let command_line = args
+        .get("command")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'command' argument".to_string()))?;
+
+    let cwd = match (args.get("cwd").and_then(|v| v.as_str()), &context.working_dir) {
+        (Some(cwd), _) => resolve_path(context, cwd)?,
+        (None, Some(root)) => root.clone(),
+        (None, None) => {
+            return Err(ToolError::InvalidArgument(
+                "No project directory is set; pass 'cwd'".to_string(),
+            ))
+        }
+    };
+
+    let timeout_secs = args
+        .get("timeout_secs")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(command::DEFAULT_COMMAND_TIMEOUT_SECS)
+        .clamp(1, command::MAX_COMMAND_TIMEOUT_SECS);
+
+    let result = command::run_command(
+        command_line,
+        &cwd.to_string_lossy(),
+        Duration::from_secs(timeout_secs),
+    )?;
+
+    Ok(json!({
+        "success": result.exit_code == Some(0),
+        "result": result
+    }))
+}
+
+/// Execute fetch_url tool
+fn execute_fetch_url(args: &Value, _context: &ToolContext) -> ToolResult<Value> {
+    let url = args
+        .get("url")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidArgument("Missing 'url' argument".to_string()))?;
+
+    let max_chars = args
+        .get("max_chars")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or(web::DEFAULT_FETCH_MAX_CHARS);
+
+    let result = web::fetch_url(url, max_chars)?;
+
+    Ok(json!({
+        "success": (200..300).contains(&result.status),
+        "result": result
+    }))
 }
 
 /// Check if a tool call resulted in an error
@@ -204,6 +1933,71 @@ mod tests {
         assert_eq!(result["entries"].as_array().unwrap().len(), 2);
     }
 
+    #[test]
+    fn test_jailed_context_rejects_paths_outside_project() {
+        let project = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        fs::write(project.path().join("inside.txt"), "ok").unwrap();
+        fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+
+        let context = ToolContext::jailed(Some(project.path().to_path_buf()), Vec::new());
+        let read = |path: &str| {
+            execute_tool_with_context(
+                &ToolCall {
+                    id: "test-1".to_string(),
+                    name: "read_file".to_string(),
+                    arguments: json!({ "path": path }),
+                },
+                &context,
+            )
+        };
+
+        assert_eq!(read("inside.txt").unwrap()["content"], "ok");
+        let secret = outside.path().join("secret.txt");
+        assert!(matches!(
+            read(secret.to_str().unwrap()),
+            Err(ToolError::PermissionDenied(_))
+        ));
+        let escape = format!("../{}/secret.txt", outside.path().file_name().unwrap().to_str().unwrap());
+        assert!(matches!(read(&escape), Err(ToolError::PermissionDenied(_))));
+
+        // A patch can't write through a symlinked directory leading outside
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(outside.path(), project.path().join("link")).unwrap();
+            let patch = execute_tool_with_context(
+                &ToolCall {
+                    id: "test-2".to_string(),
+                    name: "apply_patch".to_string(),
+                    arguments: json!({
+                        "path": ".",
+                        "patch": "--- /dev/null\n+++ b/link/new.txt\n@@ -0,0 +1 @@\n+pwned\n"
+                    }),
+                },
+                &context,
+            );
+            assert!(matches!(patch, Err(ToolError::PermissionDenied(_))));
+            assert!(!outside.path().join("new.txt").exists());
+        }
+
+        let allowed = ToolContext::jailed(
+            Some(project.path().to_path_buf()),
+            vec![outside.path().to_path_buf()],
+        );
+        assert!(resolve_path(&allowed, secret.to_str().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_tool_risk() {
+        let registry = ToolRegistry::with_builtin_tools();
+        assert_eq!(registry.risk("read_file"), RiskClass::Read);
+        assert_eq!(registry.risk("multi_edit"), RiskClass::Write);
+        assert_eq!(registry.risk("save_memory"), RiskClass::Write);
+        assert_eq!(registry.risk("run_command"), RiskClass::Execute);
+        assert!(registry.risk("unknown_tool").requires_approval());
+        assert!(!RiskClass::Read.requires_approval());
+    }
+
     #[test]
     fn test_unknown_tool() {
         let tool_call = ToolCall {
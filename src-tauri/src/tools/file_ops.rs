@@ -3,20 +3,107 @@
 //! This module provides file reading, writing, and directory listing operations
 //! that can be used by AI assistants.
 
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::time::UNIX_EPOCH;
 
-use super::{FileEntry, ToolError, ToolResult};
+use base64::Engine;
+use serde::Serialize;
+
+use super::{
+    decode_text, encode_text, git, hash_file, search, FileContent, FileEntry, HashAlgorithm,
+    LineEnding, ToolError, ToolResult,
+};
+
+/// Suffix of the backup `write_file_atomic` keeps of a file's previous content
+pub const BACKUP_SUFFIX: &str = ".bak";
+
+/// Largest image returned to a model; bigger images are reported as binary
+pub const MAX_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Files larger than this are read in ranges rather than all at once
+pub const LARGE_FILE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Bytes returned by each range of a large file read in chunks
+pub const READ_CHUNK_BYTES: u64 = 1024 * 1024;
+
+/// Files and directories a recursive delete may remove without confirmation
+pub const LARGE_TREE_ENTRIES: usize = 1_000;
+
+/// Image formats vision models accept, by their magic bytes
+const IMAGE_SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+];
+
+/// Other common binary formats, reported by type so the model knows what it found
+const BINARY_SIGNATURES: &[(&[u8], &str)] = &[
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"\x7fELF", "application/x-elf"),
+    (b"\0asm", "application/wasm"),
+    (b"SQLite format 3\0", "application/vnd.sqlite3"),
+];
+
+/// Media types of common formats that can't be told apart by their bytes
+const EXTENSION_MEDIA_TYPES: &[(&str, &str)] = &[
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("jsx", "text/javascript"),
+    ("ts", "text/typescript"),
+    ("tsx", "text/typescript"),
+    ("json", "application/json"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("xml", "application/xml"),
+    ("svg", "image/svg+xml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("toml", "application/toml"),
+    ("rs", "text/x-rust"),
+    ("py", "text/x-python"),
+    ("go", "text/x-go"),
+    ("sh", "application/x-sh"),
+    ("mp3", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+    ("ico", "image/x-icon"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+];
+
+/// Identify an image format vision models accept from a file's first bytes
+fn sniff_image_type(bytes: &[u8]) -> Option<&'static str> {
+    // WebP is a RIFF container: "RIFF" <size> "WEBP"
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    IMAGE_SIGNATURES
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+        .map(|(_, media_type)| *media_type)
+}
 
-/// Read the contents of a file
+/// Read a file, detecting images and other binary files
 ///
 /// # Arguments
 /// * `path` - Path to the file to read
 ///
 /// # Returns
-/// The file contents as a string
-pub fn read_file(path: &str) -> ToolResult<String> {
+/// The text of a text file, decoded to UTF-8 from whatever encoding it
+/// uses, the base64 data of an image, or the type and size of any other
+/// binary file
+pub fn read_file_content(path: &str) -> ToolResult<FileContent> {
     let path = Path::new(path);
 
     if !path.exists() {
@@ -30,15 +117,72 @@ pub fn read_file(path: &str) -> ToolResult<String> {
         )));
     }
 
-    fs::read_to_string(path).map_err(|e| {
+    let bytes = fs::read(path).map_err(|e| {
         if e.kind() == std::io::ErrorKind::PermissionDenied {
             ToolError::PermissionDenied(path.display().to_string())
         } else {
             ToolError::IoError(e)
         }
+    })?;
+    let size = bytes.len() as u64;
+
+    if let Some(media_type) = sniff_image_type(&bytes) {
+        if size <= MAX_IMAGE_BYTES {
+            return Ok(FileContent::Image {
+                media_type: media_type.to_string(),
+                data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+                size,
+            });
+        }
+        return Ok(FileContent::Binary {
+            media_type: Some(media_type.to_string()),
+            size,
+        });
+    }
+
+    let media_type = BINARY_SIGNATURES
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+        .map(|(_, media_type)| media_type.to_string());
+    let decoded = media_type.is_none().then(|| decode_text(&bytes)).flatten();
+    let Some((content, format)) = decoded else {
+        return Ok(FileContent::Binary { media_type, size });
+    };
+
+    let plain_utf8 = format.encoding == encoding_rs::UTF_8 && !format.bom;
+    Ok(FileContent::Text {
+        content,
+        encoding: (!plain_utf8).then(|| format.encoding_name().to_string()),
+        line_ending: format.line_ending,
+        bom: format.bom,
     })
 }
 
+/// Read the contents of a text file
+///
+/// # Arguments
+/// * `path` - Path to the file to read
+///
+/// # Returns
+/// The file contents as a string, or an error if the file is binary
+pub fn read_file(path: &str) -> ToolResult<String> {
+    match read_file_content(path)? {
+        FileContent::Text { content, .. } => Ok(content),
+        FileContent::Image { media_type, size, .. }
+        | FileContent::Binary {
+            media_type: Some(media_type),
+            size,
+        } => Err(ToolError::InvalidArgument(format!(
+            "{} is a binary file ({}, {} bytes)",
+            path, media_type, size
+        ))),
+        FileContent::Binary { size, .. } => Err(ToolError::InvalidArgument(format!(
+            "{} is a binary file ({} bytes)",
+            path, size
+        ))),
+    }
+}
+
 /// Read the contents of a file with a line limit
 ///
 /// # Arguments
@@ -59,8 +203,132 @@ pub fn read_file_lines(path: &str, max_lines: usize) -> ToolResult<(String, bool
     }
 }
 
+/// A byte range of a file, decoded as UTF-8
+#[derive(Debug, Clone, Serialize)]
+pub struct FileRange {
+    pub content: String,
+    /// Offset of the first byte of `content` in the file
+    pub offset: u64,
+    /// Offset to read the next range from
+    pub next_offset: u64,
+    /// Size of the whole file in bytes
+    pub total_size: u64,
+    /// Whether the range reaches the end of the file
+    pub eof: bool,
+}
+
+/// Read a byte range of a file
+///
+/// The range is widened or narrowed by a few bytes so it never splits a
+/// UTF-8 character: a partial character at the start is skipped and one at
+/// the end is left for the next range. Invalid UTF-8 is replaced.
+///
+/// # Arguments
+/// * `path` - Path to the file to read
+/// * `offset` - Byte offset to start reading at
+/// * `length` - Most bytes to read
+pub fn read_file_range(path: &str, offset: u64, length: u64) -> ToolResult<FileRange> {
+    let path = Path::new(path);
+    if !path.is_file() {
+        return Err(ToolError::PathNotFound(path.display().to_string()));
+    }
+
+    let mut file = fs::File::open(path)?;
+    let total_size = file.metadata()?.len();
+    let offset = offset.min(total_size);
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.take(length).read_to_end(&mut bytes)?;
+    let mut end = bytes.len();
+
+    // Leave a character cut off at the end for the next range
+    if offset + (end as u64) < total_size {
+        let tail = bytes.len().saturating_sub(3);
+        if let Some(lead) = (tail..end).rev().find(|&i| bytes[i] & 0xc0 != 0x80) {
+            let char_len = match bytes[lead] {
+                b if b >= 0xf0 => 4,
+                b if b >= 0xe0 => 3,
+                b if b >= 0xc0 => 2,
+                _ => 1,
+            };
+            if lead + char_len > end {
+                end = lead;
+            }
+        }
+    }
+    // Skip the rest of a character the previous range ended inside
+    let start = bytes[..end]
+        .iter()
+        .take(3)
+        .take_while(|&&b| b & 0xc0 == 0x80)
+        .count();
+
+    let next_offset = offset + end as u64;
+    Ok(FileRange {
+        content: String::from_utf8_lossy(&bytes[start..end]).into_owned(),
+        offset: offset + start as u64,
+        next_offset,
+        total_size,
+        eof: next_offset >= total_size,
+    })
+}
+
+/// The version of a file on disk, used to tell whether it changed since it
+/// was read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileVersion {
+    /// SHA-256 of the file's bytes, in hex
+    pub hash: String,
+    /// Last modification time, in milliseconds since the Unix epoch
+    pub mtime: Option<u64>,
+}
+
+/// Get the current version of a file
+pub fn file_version(path: &str) -> ToolResult<FileVersion> {
+    let mtime = fs::metadata(path)?
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64);
+    Ok(FileVersion {
+        hash: hash_file(path, HashAlgorithm::Sha256)?,
+        mtime,
+    })
+}
+
+/// Check that a file is still the version it was read as
+///
+/// Passes if neither expectation is given. A file that was deleted since it
+/// was read fails the check.
+///
+/// # Arguments
+/// * `path` - Path to the file about to be written
+/// * `expected_hash` - SHA-256 of the file when it was read
+/// * `expected_mtime` - Modification time of the file when it was read, in
+///   milliseconds
+pub fn check_file_version(
+    path: &str,
+    expected_hash: Option<&str>,
+    expected_mtime: Option<u64>,
+) -> ToolResult<()> {
+    if expected_hash.is_none() && expected_mtime.is_none() {
+        return Ok(());
+    }
+    let Ok(current) = file_version(path) else {
+        return Err(ToolError::Conflict(format!("{} no longer exists", path)));
+    };
+    let hash_changed = expected_hash.is_some_and(|h| !h.eq_ignore_ascii_case(&current.hash));
+    let mtime_changed = expected_mtime.is_some_and(|m| Some(m) != current.mtime);
+    if hash_changed || mtime_changed {
+        return Err(ToolError::Conflict(path.to_string()));
+    }
+    Ok(())
+}
+
 /// Write content to a file
 ///
+/// The write is atomic; see `write_file_atomic`.
+///
 /// # Arguments
 /// * `path` - Path to the file to write
 /// * `content` - Content to write
@@ -68,24 +336,146 @@ pub fn read_file_lines(path: &str, max_lines: usize) -> ToolResult<(String, bool
 /// # Returns
 /// Success or error
 pub fn write_file(path: &str, content: &str) -> ToolResult<()> {
+    write_file_atomic(path, content, false)
+}
+
+/// Write content to a file without ever leaving it half-written
+///
+/// The content goes to a temporary file in the same directory, is flushed to
+/// disk and then renamed over the file, so a crash mid-write leaves either
+/// the old or the new content. An existing text file keeps its encoding, byte
+/// order mark and line endings, an existing file keeps its permissions, and a
+/// symlink is written through rather than replaced.
+///
+/// # Arguments
+/// * `path` - Path to the file to write
+/// * `content` - Content to write
+/// * `backup` - Keep the previous content as `<path>.bak`
+pub fn write_file_atomic(path: &str, content: &str, backup: bool) -> ToolResult<()> {
     let path = Path::new(path);
+    let permission_error = |e: std::io::Error| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            ToolError::PermissionDenied(path.display().to_string())
+        } else {
+            ToolError::IoError(e)
+        }
+    };
 
     // Create parent directories if they don't exist
     if let Some(parent) = path.parent() {
-        if !parent.exists() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
             fs::create_dir_all(parent)?;
         }
     }
 
-    fs::write(path, content).map_err(|e| {
-        if e.kind() == std::io::ErrorKind::PermissionDenied {
-            ToolError::PermissionDenied(path.display().to_string())
-        } else {
-            ToolError::IoError(e)
+    let target = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_symlink() => fs::canonicalize(path)?,
+        _ => path.to_path_buf(),
+    };
+    let existing = fs::metadata(&target).ok();
+    if existing.as_ref().is_some_and(|meta| meta.is_dir()) {
+        return Err(ToolError::InvalidArgument(format!(
+            "Path is a directory: {}",
+            path.display()
+        )));
+    }
+
+    // Save the content the way the file was saved before
+    let bytes = match fs::read(&target).ok().and_then(|old| decode_text(&old)) {
+        Some((_, format)) if !format.is_plain_utf8() => encode_text(content, &format)?,
+        _ => content.as_bytes().to_vec(),
+    };
+
+    let file_name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp = target.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name,
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    ));
+
+    let write_temp = || -> std::io::Result<()> {
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(&bytes)?;
+        if let Some(meta) = &existing {
+            file.set_permissions(meta.permissions())?;
         }
+        file.sync_all()
+    };
+    if let Err(e) = write_temp() {
+        let _ = fs::remove_file(&temp);
+        return Err(permission_error(e));
+    }
+
+    if backup && existing.is_some() {
+        let mut backup_path = target.clone().into_os_string();
+        backup_path.push(BACKUP_SUFFIX);
+        if let Err(e) = fs::copy(&target, &backup_path) {
+            let _ = fs::remove_file(&temp);
+            return Err(permission_error(e));
+        }
+    }
+
+    fs::rename(&temp, &target).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        permission_error(e)
     })
 }
 
+/// Replace a range of lines in a file
+///
+/// Lines are numbered from 1 and the range is inclusive. An empty range,
+/// where `end_line` is `start_line - 1`, inserts `new_content` before
+/// `start_line`; empty `new_content` deletes the lines. The file is written
+/// with `write_file_atomic`, so it keeps its encoding and line endings.
+///
+/// # Arguments
+/// * `path` - Path to the file to update
+/// * `start_line` - First line to replace
+/// * `end_line` - Last line to replace
+/// * `new_content` - Text to put in place of the lines
+pub fn write_file_lines(
+    path: &str,
+    start_line: usize,
+    end_line: usize,
+    new_content: &str,
+) -> ToolResult<()> {
+    let content = read_file(path)?;
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+
+    if start_line == 0
+        || start_line > lines.len() + 1
+        || end_line + 1 < start_line
+        || end_line > lines.len()
+    {
+        return Err(ToolError::InvalidArgument(format!(
+            "Invalid line range {}-{}: {} has {} lines",
+            start_line,
+            end_line,
+            path,
+            lines.len()
+        )));
+    }
+
+    // New lines end the way the file's lines do
+    let line_ending = LineEnding::detect(&content);
+    let new_content = line_ending.apply(new_content);
+    let mut updated = lines[..start_line - 1].concat();
+    updated.push_str(&new_content);
+    // Keep the following line on a line of its own
+    if !new_content.is_empty() && !new_content.ends_with('\n') && end_line < lines.len() {
+        updated.push_str(match line_ending {
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Lf => "\n",
+        });
+    }
+    updated.push_str(&lines[end_line..].concat());
+
+    write_file_atomic(path, &updated, false)
+}
+
 /// List the contents of a directory
 ///
 /// # Arguments
@@ -134,6 +524,9 @@ pub fn list_directory(path: &str) -> ToolResult<Vec<FileEntry>> {
             size: metadata.len(),
             modified,
             extension,
+            git_status: None,
+            mime_type: None,
+            line_count: None,
         });
     }
 
@@ -151,6 +544,9 @@ pub fn list_directory(path: &str) -> ToolResult<Vec<FileEntry>> {
 
 /// List the contents of a directory recursively
 ///
+/// Entries excluded by `.gitignore`, `.ignore` or the global git excludes
+/// file are skipped, as is the `.git` directory.
+///
 /// # Arguments
 /// * `path` - Path to the directory to list
 /// * `max_depth` - Maximum recursion depth (None for unlimited)
@@ -158,22 +554,17 @@ pub fn list_directory(path: &str) -> ToolResult<Vec<FileEntry>> {
 /// # Returns
 /// A vector of file entries
 pub fn list_directory_recursive(path: &str, max_depth: Option<usize>) -> ToolResult<Vec<FileEntry>> {
-    use walkdir::WalkDir;
-
     let path = Path::new(path);
 
     if !path.exists() {
         return Err(ToolError::PathNotFound(path.display().to_string()));
     }
 
-    let walker = match max_depth {
-        Some(depth) => WalkDir::new(path).max_depth(depth),
-        None => WalkDir::new(path),
-    };
+    let walker = search::project_walker(path, true).max_depth(max_depth).build();
 
     let mut entries = Vec::new();
 
-    for entry in walker.into_iter().filter_map(|e| e.ok()) {
+    for entry in walker.filter_map(|e| e.ok()) {
         // Skip the root directory itself
         if entry.path() == path {
             continue;
@@ -206,6 +597,9 @@ pub fn list_directory_recursive(path: &str, max_depth: Option<usize>) -> ToolRes
             size: metadata.len(),
             modified,
             extension,
+            git_status: None,
+            mime_type: None,
+            line_count: None,
         });
     }
 
@@ -260,9 +654,102 @@ pub fn get_file_info(path: &str) -> ToolResult<FileEntry> {
         size: metadata.len(),
         modified,
         extension,
+        git_status: None,
+        mime_type: None,
+        line_count: None,
     })
 }
 
+/// Detect a file's media type and count its lines, if it is text
+///
+/// Files larger than `LARGE_FILE_BYTES` only have their first bytes read, so
+/// their lines aren't counted.
+fn sniff_file(path: &Path, size: u64) -> (Option<String>, Option<usize>) {
+    let bytes = if size > LARGE_FILE_BYTES {
+        let mut head = Vec::new();
+        let read = fs::File::open(path).and_then(|f| f.take(READ_CHUNK_BYTES).read_to_end(&mut head));
+        read.map(|_| head)
+    } else {
+        fs::read(path)
+    };
+    let Ok(bytes) = bytes else {
+        return (None, None);
+    };
+
+    let sniffed = sniff_image_type(&bytes).or_else(|| {
+        BINARY_SIGNATURES
+            .iter()
+            .find(|(magic, _)| bytes.starts_with(magic))
+            .map(|(_, media_type)| *media_type)
+    });
+    let by_extension = || {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        EXTENSION_MEDIA_TYPES
+            .iter()
+            .find(|(e, _)| *e == ext)
+            .map(|(_, media_type)| *media_type)
+    };
+    if let Some(media_type) = sniffed {
+        return (Some(media_type.to_string()), None);
+    }
+
+    // A large file's head may end mid-character, so only look for NULs
+    let text = if size > LARGE_FILE_BYTES {
+        (!bytes.contains(&0)).then(String::new)
+    } else {
+        decode_text(&bytes).map(|(text, _)| text)
+    };
+    let line_count = (size <= LARGE_FILE_BYTES)
+        .then(|| text.as_ref().map(|t| t.lines().count()))
+        .flatten();
+    let media_type = by_extension().unwrap_or(match text {
+        Some(_) => "text/plain",
+        None => "application/octet-stream",
+    });
+    (Some(media_type.to_string()), line_count)
+}
+
+/// The git status of a path, given the statuses from `git::path_statuses`
+fn entry_git_status(statuses: &HashMap<PathBuf, String>, path: &Path, is_dir: bool) -> Option<String> {
+    if let Some(status) = statuses.get(path) {
+        return Some(status.clone());
+    }
+    // Inside an untracked or ignored directory
+    let inherited = path.ancestors().skip(1).find_map(|ancestor| {
+        statuses
+            .get(ancestor)
+            .filter(|s| *s == "untracked" || *s == "ignored")
+    });
+    if let Some(status) = inherited {
+        return Some(status.clone());
+    }
+    // A directory holding changes
+    (is_dir
+        && statuses
+            .iter()
+            .any(|(p, s)| p.starts_with(path) && s != "ignored"))
+    .then(|| "modified".to_string())
+}
+
+/// Fill in the git status, media type and line count of entries
+///
+/// These take a file read each and a git call, so listings leave them out
+/// unless asked. Git status is left empty outside a repository.
+///
+/// # Arguments
+/// * `dir` - A directory holding all the entries, where git is run
+/// * `entries` - The entries to fill in
+pub fn add_file_details(dir: &str, entries: &mut [FileEntry]) {
+    let statuses = git::path_statuses(dir).unwrap_or_default();
+    for entry in entries {
+        let path = Path::new(&entry.path);
+        entry.git_status = entry_git_status(&statuses, path, entry.is_dir);
+        if entry.is_file {
+            (entry.mime_type, entry.line_count) = sniff_file(path, entry.size);
+        }
+    }
+}
+
 /// Create a directory and all parent directories
 pub fn create_directory(path: &str) -> ToolResult<()> {
     fs::create_dir_all(path).map_err(|e| {
@@ -318,6 +805,123 @@ pub fn copy_file(from: &str, to: &str) -> ToolResult<()> {
     Ok(())
 }
 
+/// Count the files and directories under a directory, stopping once there
+/// are more than `limit`
+pub fn count_entries(path: &Path, limit: usize) -> usize {
+    let mut count = 0;
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            count += 1;
+            if count > limit {
+                return count;
+            }
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                pending.push(entry.path());
+            }
+        }
+    }
+    count
+}
+
+/// Delete a directory
+///
+/// Without `recursive`, only an empty directory is deleted. A symlink to a
+/// directory is refused rather than followed.
+pub fn delete_directory(path: &str, recursive: bool) -> ToolResult<()> {
+    let path = Path::new(path);
+    let metadata = fs::symlink_metadata(path)
+        .map_err(|_| ToolError::PathNotFound(path.display().to_string()))?;
+
+    if !metadata.is_dir() {
+        return Err(ToolError::InvalidArgument(format!(
+            "Path is not a directory: {}",
+            path.display()
+        )));
+    }
+    if path.parent().is_none() {
+        return Err(ToolError::InvalidArgument(
+            "Refusing to delete the filesystem root".to_string(),
+        ));
+    }
+
+    let result = if recursive {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_dir(path)
+    };
+    result.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            ToolError::PermissionDenied(path.display().to_string())
+        } else if !recursive && fs::read_dir(path).is_ok_and(|mut d| d.next().is_some()) {
+            ToolError::InvalidArgument(format!(
+                "Directory is not empty: {}; delete it recursively instead",
+                path.display()
+            ))
+        } else {
+            ToolError::IoError(e)
+        }
+    })
+}
+
+/// Copy a directory and everything in it
+///
+/// Symlinks to files are copied as files; symlinks to directories are
+/// skipped, so a link cycle can't make the copy run forever.
+///
+/// # Returns
+/// The number of files copied
+pub fn copy_directory(from: &str, to: &str) -> ToolResult<usize> {
+    let from_path = Path::new(from);
+    let to_path = Path::new(to);
+
+    if !from_path.is_dir() {
+        return Err(ToolError::PathNotFound(from.to_string()));
+    }
+    if to_path.exists() {
+        return Err(ToolError::InvalidArgument(format!(
+            "Destination already exists: {}",
+            to
+        )));
+    }
+    // Compare resolved paths so `to` can't be inside `from` through a symlink
+    let source = from_path.canonicalize()?;
+    let destination_parent = to_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map_or_else(std::env::current_dir, |p| {
+            fs::create_dir_all(p)?;
+            p.canonicalize()
+        })?;
+    if destination_parent.starts_with(&source) {
+        return Err(ToolError::InvalidArgument(format!(
+            "Can't copy {} into itself",
+            from
+        )));
+    }
+
+    let mut copied = 0;
+    let mut pending = vec![(from_path.to_path_buf(), to_path.to_path_buf())];
+    while let Some((source_dir, target_dir)) = pending.pop() {
+        fs::create_dir(&target_dir)?;
+        for entry in fs::read_dir(&source_dir)? {
+            let entry = entry?;
+            let target = target_dir.join(entry.file_name());
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push((entry.path(), target));
+            } else if entry.path().is_file() {
+                fs::copy(entry.path(), target)?;
+                copied += 1;
+            }
+        }
+    }
+    Ok(copied)
+}
+
 /// Move/rename a file
 pub fn move_file(from: &str, to: &str) -> ToolResult<()> {
     let from_path = Path::new(from);
@@ -358,6 +962,84 @@ mod tests {
         assert_eq!(content, "Hello, World!");
     }
 
+    #[test]
+    fn test_write_file_atomic_keeps_backup() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("main.rs");
+        let path_str = file_path.to_str().unwrap();
+
+        write_file_atomic(path_str, "old", true).unwrap();
+        assert!(!dir.path().join("main.rs.bak").exists());
+
+        write_file_atomic(path_str, "new", true).unwrap();
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "new");
+        assert_eq!(fs::read_to_string(dir.path().join("main.rs.bak")).unwrap(), "old");
+
+        // The previous encoding and line endings are kept
+        fs::write(&file_path, b"caf\xe9\r\n").unwrap();
+        write_file_atomic(path_str, "café\nthé\n", false).unwrap();
+        assert_eq!(fs::read(&file_path).unwrap(), b"caf\xe9\r\nth\xe9\r\n");
+        assert_eq!(read_file(path_str).unwrap(), "café\r\nthé\r\n");
+
+        // No temporary files are left behind
+        let names: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(write_file_atomic(dir.path().to_str().unwrap(), "x", false).is_err());
+    }
+
+    #[test]
+    fn test_check_file_version_detects_changes() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("notes.md");
+        let path_str = file_path.to_str().unwrap();
+
+        write_file(path_str, "draft").unwrap();
+        let read = file_version(path_str).unwrap();
+        check_file_version(path_str, None, None).unwrap();
+        check_file_version(path_str, Some(&read.hash.to_uppercase()), read.mtime).unwrap();
+
+        write_file(path_str, "edited elsewhere").unwrap();
+        assert!(matches!(
+            check_file_version(path_str, Some(&read.hash), None),
+            Err(ToolError::Conflict(_))
+        ));
+        fs::remove_file(&file_path).unwrap();
+        assert!(check_file_version(path_str, None, read.mtime).is_err());
+        check_file_version(path_str, None, None).unwrap();
+    }
+
+    #[test]
+    fn test_add_file_details() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let root_str = root.to_str().unwrap();
+        git::init(root_str).unwrap();
+        fs::write(root.join(".gitignore"), "build/\n").unwrap();
+        fs::create_dir_all(root.join("build")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("build/out.bin"), b"\x7fELF\x02\x01").unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {\n}\n").unwrap();
+
+        let mut entries = list_directory_recursive(root_str, None).unwrap();
+        entries.push(get_file_info(root.join("build/out.bin").to_str().unwrap()).unwrap());
+        add_file_details(root_str, &mut entries);
+        let find = |name: &str| entries.iter().find(|e| e.name == name).unwrap();
+
+        let main = find("main.rs");
+        assert_eq!(main.git_status.as_deref(), Some("untracked"));
+        assert_eq!(main.mime_type.as_deref(), Some("text/x-rust"));
+        assert_eq!(main.line_count, Some(2));
+        assert_eq!(find("src").git_status.as_deref(), Some("untracked"));
+
+        let binary = find("out.bin");
+        assert_eq!(binary.git_status.as_deref(), Some("ignored"));
+        assert_eq!(binary.mime_type.as_deref(), Some("application/x-elf"));
+        assert_eq!(binary.line_count, None);
+    }
+
     #[test]
     fn test_list_directory() {
         let dir = tempdir().unwrap();
@@ -375,6 +1057,103 @@ mod tests {
         assert_eq!(entries[0].name, "subdir");
     }
 
+    #[test]
+    fn test_read_file_content_detects_binary_and_images() {
+        let dir = tempdir().unwrap();
+        let png = dir.path().join("logo.png");
+        fs::write(&png, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        let blob = dir.path().join("data.bin");
+        fs::write(&blob, [0u8, 159, 146, 150]).unwrap();
+
+        match read_file_content(png.to_str().unwrap()).unwrap() {
+            FileContent::Image { media_type, data, size } => {
+                assert_eq!(media_type, "image/png");
+                assert_eq!(data, "iVBORw0KGgoAAAANSUhEUg==");
+                assert_eq!(size, 16);
+            }
+            other => panic!("expected an image, got {:?}", other),
+        }
+        assert!(matches!(
+            read_file_content(blob.to_str().unwrap()).unwrap(),
+            FileContent::Binary { media_type: None, size: 4 }
+        ));
+        assert!(matches!(
+            read_file(blob.to_str().unwrap()),
+            Err(ToolError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_file_range_keeps_characters_whole() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("app.log");
+        let path_str = file_path.to_str().unwrap();
+        // "é" is two bytes, at offsets 3 and 4
+        fs::write(&file_path, "caf\u{e9} ok\n").unwrap();
+
+        let first = read_file_range(path_str, 0, 4).unwrap();
+        assert_eq!((first.content.as_str(), first.next_offset), ("caf", 3));
+        assert!(!first.eof);
+        let rest = read_file_range(path_str, first.next_offset, 100).unwrap();
+        assert_eq!(rest.content, "\u{e9} ok\n");
+        assert!(rest.eof);
+
+        // A range starting inside a character skips it
+        let inside = read_file_range(path_str, 4, 3).unwrap();
+        assert_eq!((inside.content.as_str(), inside.offset), (" o", 5));
+        assert_eq!(read_file_range(path_str, 99, 10).unwrap().content, "");
+    }
+
+    #[test]
+    fn test_write_file_lines() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("lib.rs");
+        let path_str = file_path.to_str().unwrap();
+        fs::write(&file_path, "one\r\ntwo\r\nthree\r\n").unwrap();
+
+        write_file_lines(path_str, 2, 2, "2a\r\n2b").unwrap();
+        assert_eq!(read_file(path_str).unwrap(), "one\r\n2a\r\n2b\r\nthree\r\n");
+        // Insert before the first line, then delete the last two
+        write_file_lines(path_str, 1, 0, "zero\r\n").unwrap();
+        write_file_lines(path_str, 4, 5, "").unwrap();
+        assert_eq!(read_file(path_str).unwrap(), "zero\r\none\r\n2a\r\n");
+
+        assert!(matches!(
+            write_file_lines(path_str, 2, 9, "x"),
+            Err(ToolError::InvalidArgument(_))
+        ));
+
+        // LF lines written into a CRLF file take its line endings
+        write_file_lines(path_str, 2, 2, "1a\n1b").unwrap();
+        assert_eq!(read_file(path_str).unwrap(), "zero\r\n1a\r\n1b\r\n2a\r\n");
+    }
+
+    #[test]
+    fn test_copy_and_delete_directory() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("nested/deep")).unwrap();
+        fs::write(src.join("a.txt"), "a").unwrap();
+        fs::write(src.join("nested/deep/b.txt"), "b").unwrap();
+        let src_str = src.to_str().unwrap();
+        let copy = dir.path().join("copy");
+        let copy_str = copy.to_str().unwrap();
+
+        assert_eq!(copy_directory(src_str, copy_str).unwrap(), 2);
+        assert_eq!(fs::read_to_string(copy.join("nested/deep/b.txt")).unwrap(), "b");
+        assert_eq!(count_entries(&copy, 100), 4);
+        assert!(copy_directory(src_str, copy_str).is_err());
+        assert!(copy_directory(src_str, src.join("inner").to_str().unwrap()).is_err());
+
+        assert!(matches!(
+            delete_directory(copy_str, false),
+            Err(ToolError::InvalidArgument(_))
+        ));
+        delete_directory(copy_str, true).unwrap();
+        assert!(!copy.exists());
+        assert!(delete_directory(src.join("a.txt").to_str().unwrap(), true).is_err());
+    }
+
     #[test]
     fn test_path_not_found() {
         let result = read_file("/nonexistent/path/file.txt");
@@ -0,0 +1,462 @@
+//! GitHub and GitLab pull requests
+//!
+//! This module works out which forge hosts a repository from its remote URL
+//! and talks to that forge's REST API. GitHub pull requests and GitLab merge
+//! requests are both presented as [`PullRequest`]s. Requests authenticate with
+//! a token given by the caller, or else one from `GITHUB_TOKEN`/`GH_TOKEN` or
+//! `GITLAB_TOKEN`. Tokens from the environment are only sent to github.com,
+//! gitlab.com or the host named in `GH_HOST`/`GITLAB_HOST`, so a remote whose
+//! host merely looks like a forge can't collect them.
+
+use std::time::Duration;
+
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{git, ToolError, ToolResult};
+
+/// Environment variables holding a GitHub token, in order of preference
+pub const GITHUB_TOKEN_VARS: &[&str] = &["GITHUB_TOKEN", "GH_TOKEN"];
+
+/// Environment variables holding a GitLab token, in order of preference
+pub const GITLAB_TOKEN_VARS: &[&str] = &["GITLAB_TOKEN"];
+
+/// Environment variable naming a self-hosted GitHub the GitHub token is for
+pub const GITHUB_HOST_VAR: &str = "GH_HOST";
+
+/// Environment variable naming a self-hosted GitLab the GitLab token is for
+pub const GITLAB_HOST_VAR: &str = "GITLAB_HOST";
+
+/// Most pull requests listed at once
+const MAX_LISTED_PRS: usize = 50;
+
+/// Deadline for each API request
+const FORGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A code hosting service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+}
+
+/// A repository on a forge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeRepo {
+    pub kind: ForgeKind,
+    pub host: String,
+    /// `owner/name`, or a GitLab project path including its groups
+    pub path: String,
+}
+
+impl ForgeRepo {
+    /// Parse an HTTPS, SSH or scp-style (`git@host:owner/name.git`) remote URL
+    ///
+    /// The forge is recognized by name in the host, so self-hosted GitLab and
+    /// GitHub Enterprise work when their host says which they are.
+    pub fn from_remote_url(url: &str) -> ToolResult<Self> {
+        let unsupported =
+            || ToolError::InvalidArgument(format!("Not a GitHub or GitLab remote: {}", url));
+        let (authority, path) = match url.split_once("://") {
+            Some((_, rest)) => rest.split_once('/').ok_or_else(unsupported)?,
+            None => url.split_once(':').ok_or_else(unsupported)?,
+        };
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+        let host = host.split(':').next().unwrap_or(host).to_ascii_lowercase();
+        let path = path.trim_matches('/').trim_end_matches(".git").to_string();
+
+        let kind = if host.contains("github") {
+            ForgeKind::GitHub
+        } else if host.contains("gitlab") {
+            ForgeKind::GitLab
+        } else {
+            return Err(unsupported());
+        };
+        if host.is_empty() || !path.contains('/') {
+            return Err(unsupported());
+        }
+        Ok(Self { kind, host, path })
+    }
+
+    /// The root URL of the forge's REST API
+    pub fn api_base(&self) -> String {
+        match self.kind {
+            ForgeKind::GitHub if self.host == "github.com" => "https://api.github.com".to_string(),
+            ForgeKind::GitHub => format!("https://{}/api/v3", self.host),
+            ForgeKind::GitLab => format!("https://{}/api/v4", self.host),
+        }
+    }
+
+    /// The API path of the repository
+    fn api_path(&self) -> String {
+        match self.kind {
+            ForgeKind::GitHub => format!("/repos/{}", self.path),
+            ForgeKind::GitLab => format!("/projects/{}", self.path.replace('/', "%2F")),
+        }
+    }
+
+    /// Whether a token from the environment may be sent to this host
+    ///
+    /// # Arguments
+    /// * `configured_host` - The host from `GH_HOST` or `GITLAB_HOST`, if set
+    fn accepts_env_token(&self, configured_host: Option<&str>) -> bool {
+        let public_host = match self.kind {
+            ForgeKind::GitHub => "github.com",
+            ForgeKind::GitLab => "gitlab.com",
+        };
+        let configured_host = configured_host.map(|host| {
+            let host = host.split_once("://").map_or(host, |(_, rest)| rest);
+            host.trim_end_matches('/').to_ascii_lowercase()
+        });
+        self.host == public_host || configured_host.as_deref() == Some(self.host.as_str())
+    }
+}
+
+/// A GitHub pull request or GitLab merge request
+#[derive(Debug, Clone, Serialize)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub body: String,
+    /// "open", "closed" or "merged"
+    pub state: String,
+    /// The branch with the changes
+    pub head: String,
+    /// The branch the changes are merged into
+    pub base: String,
+    pub author: String,
+    pub url: String,
+    pub draft: bool,
+}
+
+fn text(value: &Value, pointer: &str) -> String {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn github_pr(value: &Value) -> PullRequest {
+    let state = if value["merged_at"].is_string() {
+        "merged".to_string()
+    } else {
+        text(value, "/state")
+    };
+    PullRequest {
+        number: value["number"].as_u64().unwrap_or_default(),
+        title: text(value, "/title"),
+        body: text(value, "/body"),
+        state,
+        head: text(value, "/head/ref"),
+        base: text(value, "/base/ref"),
+        author: text(value, "/user/login"),
+        url: text(value, "/html_url"),
+        draft: value["draft"].as_bool().unwrap_or(false),
+    }
+}
+
+fn gitlab_mr(value: &Value) -> PullRequest {
+    let state = match value["state"].as_str() {
+        Some("opened") => "open".to_string(),
+        Some("locked") | None => "closed".to_string(),
+        Some(state) => state.to_string(),
+    };
+    PullRequest {
+        number: value["iid"].as_u64().unwrap_or_default(),
+        title: text(value, "/title"),
+        body: text(value, "/description"),
+        state,
+        head: text(value, "/source_branch"),
+        base: text(value, "/target_branch"),
+        author: text(value, "/author/username"),
+        url: text(value, "/web_url"),
+        draft: value["draft"].as_bool().unwrap_or(false),
+    }
+}
+
+/// An authenticated client for one repository's pull requests
+pub struct ForgeClient {
+    repo: ForgeRepo,
+    token: String,
+    client: Client,
+}
+
+impl ForgeClient {
+    /// Create a client, taking the token from the environment if none is given
+    /// and the repository's host is one the environment's token is for
+    pub fn new(repo: ForgeRepo, token: Option<String>) -> ToolResult<Self> {
+        let (vars, host_var) = match repo.kind {
+            ForgeKind::GitHub => (GITHUB_TOKEN_VARS, GITHUB_HOST_VAR),
+            ForgeKind::GitLab => (GITLAB_TOKEN_VARS, GITLAB_HOST_VAR),
+        };
+        let configured_host = std::env::var(host_var).ok();
+        let env_token = || {
+            if !repo.accepts_env_token(configured_host.as_deref()) {
+                return None;
+            }
+            vars.iter().find_map(|var| std::env::var(var).ok())
+        };
+        let token = token
+            .or_else(env_token)
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| ToolError::AuthRequired {
+                url: repo.api_base(),
+                method: "token".to_string(),
+            })?;
+        let client = Client::builder()
+            .timeout(FORGE_TIMEOUT)
+            .user_agent(concat!("OpenSesh/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| {
+                ToolError::ExecutionFailed(format!("Failed to create HTTP client: {}", e))
+            })?;
+        Ok(Self {
+            repo,
+            token,
+            client,
+        })
+    }
+
+    /// Create a client for the repository `origin` points to
+    pub fn for_repository(path: &str, token: Option<String>) -> ToolResult<Self> {
+        let url = git::remote_url(path, "origin")?;
+        Self::new(ForgeRepo::from_remote_url(&url)?, token)
+    }
+
+    pub fn repo(&self) -> &ForgeRepo {
+        &self.repo
+    }
+
+    /// Start a request to a path under the repository
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.request_accepting(method, path, "application/vnd.github+json")
+    }
+
+    /// Start a request for a particular GitHub media type
+    fn request_accepting(&self, method: Method, path: &str, accept: &str) -> RequestBuilder {
+        let url = format!("{}{}{}", self.repo.api_base(), self.repo.api_path(), path);
+        let request = self.client.request(method, url);
+        match self.repo.kind {
+            ForgeKind::GitHub => request
+                .bearer_auth(&self.token)
+                .header("Accept", accept)
+                .header("X-GitHub-Api-Version", "2022-11-28"),
+            ForgeKind::GitLab => request.header("PRIVATE-TOKEN", &self.token),
+        }
+    }
+
+    /// Send a request, turning error responses into errors
+    fn send(&self, request: RequestBuilder) -> ToolResult<reqwest::blocking::Response> {
+        let response = request
+            .send()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Request failed: {}", e)))?;
+        let status = response.status();
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(ToolError::AuthRequired {
+                url: self.repo.api_base(),
+                method: "token".to_string(),
+            });
+        }
+        if !status.is_success() {
+            let body = response.text().unwrap_or_default();
+            let message = serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|v| {
+                    v.get("message").map(|m| {
+                        m.as_str()
+                            .map(str::to_string)
+                            .unwrap_or_else(|| m.to_string())
+                    })
+                })
+                .unwrap_or(body);
+            return Err(ToolError::ExecutionFailed(format!(
+                "{} returned {}: {}",
+                self.repo.host, status, message
+            )));
+        }
+        Ok(response)
+    }
+
+    fn send_json(&self, request: RequestBuilder) -> ToolResult<Value> {
+        self.send(request)?
+            .json()
+            .map_err(|e| ToolError::ExecutionFailed(format!("Invalid response: {}", e)))
+    }
+
+    fn pull_request(&self, value: &Value) -> PullRequest {
+        match self.repo.kind {
+            ForgeKind::GitHub => github_pr(value),
+            ForgeKind::GitLab => gitlab_mr(value),
+        }
+    }
+
+    /// List pull requests, newest first
+    ///
+    /// # Arguments
+    /// * `state` - "open" (the default), "closed", "merged" or "all"
+    pub fn list_prs(&self, state: Option<&str>) -> ToolResult<Vec<PullRequest>> {
+        let state = state.unwrap_or("open");
+        let per_page = MAX_LISTED_PRS.to_string();
+        let request = match self.repo.kind {
+            // GitHub counts merged pull requests as closed
+            ForgeKind::GitHub => self.request(Method::GET, "/pulls").query(&[
+                ("state", if state == "merged" { "closed" } else { state }),
+                ("per_page", &per_page),
+            ]),
+            ForgeKind::GitLab => self.request(Method::GET, "/merge_requests").query(&[
+                ("state", if state == "open" { "opened" } else { state }),
+                ("per_page", &per_page),
+            ]),
+        };
+        let values = self.send_json(request)?;
+        let prs = values.as_array().map(Vec::as_slice).unwrap_or_default();
+        Ok(prs
+            .iter()
+            .map(|value| self.pull_request(value))
+            .filter(|pr| state != "merged" || pr.state == "merged")
+            .collect())
+    }
+
+    /// Open a pull request from `head` into `base`
+    pub fn create_pr(
+        &self,
+        title: &str,
+        body: &str,
+        base: &str,
+        head: &str,
+    ) -> ToolResult<PullRequest> {
+        let request = match self.repo.kind {
+            ForgeKind::GitHub => self.request(Method::POST, "/pulls").json(&json!({
+                "title": title,
+                "body": body,
+                "base": base,
+                "head": head,
+            })),
+            ForgeKind::GitLab => self.request(Method::POST, "/merge_requests").json(&json!({
+                "title": title,
+                "description": body,
+                "target_branch": base,
+                "source_branch": head,
+            })),
+        };
+        Ok(self.pull_request(&self.send_json(request)?))
+    }
+
+    /// Get a pull request's changes as a unified diff
+    pub fn get_pr_diff(&self, number: u64) -> ToolResult<String> {
+        match self.repo.kind {
+            ForgeKind::GitHub => {
+                let path = format!("/pulls/{}", number);
+                let request =
+                    self.request_accepting(Method::GET, &path, "application/vnd.github.diff");
+                self.send(request)?
+                    .text()
+                    .map_err(|e| ToolError::ExecutionFailed(format!("Invalid response: {}", e)))
+            }
+            ForgeKind::GitLab => {
+                let request =
+                    self.request(Method::GET, &format!("/merge_requests/{}/changes", number));
+                let value = self.send_json(request)?;
+                let changes = value["changes"]
+                    .as_array()
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                Ok(changes
+                    .iter()
+                    .map(|change| {
+                        let (old, new) = (text(change, "/old_path"), text(change, "/new_path"));
+                        format!(
+                            "diff --git a/{old} b/{new}\n--- a/{old}\n+++ b/{new}\n{}",
+                            text(change, "/diff")
+                        )
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Add a comment to a pull request's conversation
+    pub fn comment_on_pr(&self, number: u64, body: &str) -> ToolResult<()> {
+        let path = match self.repo.kind {
+            ForgeKind::GitHub => format!("/issues/{}/comments", number),
+            ForgeKind::GitLab => format!("/merge_requests/{}/notes", number),
+        };
+        self.send(
+            self.request(Method::POST, &path)
+                .json(&json!({ "body": body })),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_urls() {
+        let repo = ForgeRepo::from_remote_url("git@github.com:JayefBuild/openSesh.git").unwrap();
+        assert_eq!(repo.kind, ForgeKind::GitHub);
+        assert_eq!(repo.path, "JayefBuild/openSesh");
+        assert_eq!(repo.api_base(), "https://api.github.com");
+
+        let repo =
+            ForgeRepo::from_remote_url("https://token@gitlab.example.com:8443/group/sub/app")
+                .unwrap();
+        assert_eq!(repo.kind, ForgeKind::GitLab);
+        assert_eq!(repo.host, "gitlab.example.com");
+        assert_eq!(repo.api_path(), "/projects/group%2Fsub%2Fapp");
+
+        let repo = ForgeRepo::from_remote_url("ssh://git@github.corp.com/team/tool.git").unwrap();
+        assert_eq!(repo.api_base(), "https://github.corp.com/api/v3");
+        assert!(!repo.accepts_env_token(None));
+        assert!(repo.accepts_env_token(Some("https://GitHub.corp.com/")));
+        assert!(!repo.accepts_env_token(Some("github.com")));
+        let repo = ForgeRepo::from_remote_url("https://github.com.evil.io/o/r.git").unwrap();
+        assert!(!repo.accepts_env_token(None));
+        let repo = ForgeRepo::from_remote_url("https://gitlab.com/o/r.git").unwrap();
+        assert!(repo.accepts_env_token(None));
+
+        assert!(ForgeRepo::from_remote_url("https://example.com/owner/repo.git").is_err());
+        assert!(ForgeRepo::from_remote_url("/srv/git/repo.git").is_err());
+    }
+
+    #[test]
+    fn test_pull_request_fields() {
+        let pr = github_pr(&json!({
+            "number": 7,
+            "title": "Add forge",
+            "body": null,
+            "state": "closed",
+            "merged_at": "2024-01-01T00:00:00Z",
+            "head": { "ref": "forge" },
+            "base": { "ref": "main" },
+            "user": { "login": "octocat" },
+            "html_url": "https://github.com/o/r/pull/7",
+            "draft": false
+        }));
+        assert_eq!(
+            (pr.number, pr.state.as_str(), pr.body.as_str()),
+            (7, "merged", "")
+        );
+        assert_eq!((pr.head.as_str(), pr.base.as_str()), ("forge", "main"));
+
+        let mr = gitlab_mr(&json!({
+            "iid": 3,
+            "title": "Draft: Add forge",
+            "description": "Body",
+            "state": "opened",
+            "source_branch": "forge",
+            "target_branch": "main",
+            "author": { "username": "tanuki" },
+            "web_url": "https://gitlab.com/o/r/-/merge_requests/3",
+            "draft": true
+        }));
+        assert_eq!((mr.number, mr.state.as_str(), mr.draft), (3, "open", true));
+        assert_eq!(mr.author, "tanuki");
+    }
+}
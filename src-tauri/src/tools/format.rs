@@ -0,0 +1,144 @@
+//! Code formatting for the tools system
+//!
+//! This module formats files in place with the standard formatter for their
+//! language (rustfmt, prettier, black, gofmt), so code written by an AI
+//! assistant matches the project's style.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use super::{ToolError, ToolResult};
+
+/// A formatter and the extensions it handles
+struct Formatter {
+    name: &'static str,
+    program: &'static str,
+    /// Arguments placed before the file path
+    args: &'static [&'static str],
+    extensions: &'static [&'static str],
+}
+
+const FORMATTERS: &[Formatter] = &[
+    Formatter {
+        name: "rustfmt",
+        program: "rustfmt",
+        args: &["--edition", "2021"],
+        extensions: &["rs"],
+    },
+    Formatter {
+        name: "prettier",
+        // --no-install: only use the project's own prettier
+        program: "npx",
+        args: &["--no-install", "prettier", "--write", "--log-level", "warn"],
+        extensions: &[
+            "js", "jsx", "mjs", "cjs", "ts", "tsx", "json", "css", "scss", "less", "html",
+            "vue", "svelte", "md", "yaml", "yml",
+        ],
+    },
+    Formatter {
+        name: "black",
+        program: "black",
+        args: &["--quiet"],
+        extensions: &["py", "pyi"],
+    },
+    Formatter {
+        name: "gofmt",
+        program: "gofmt",
+        args: &["-w"],
+        extensions: &["go"],
+    },
+];
+
+/// Result of formatting a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatResult {
+    pub path: String,
+    pub formatter: String,
+    /// Whether formatting changed the file
+    pub changed: bool,
+}
+
+/// Find the formatter for a file by its extension
+fn formatter_for(path: &Path) -> Option<&'static Formatter> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    FORMATTERS
+        .iter()
+        .find(|f| f.extensions.contains(&extension.as_str()))
+}
+
+/// Whether a file has a formatter
+pub fn can_format(path: &str) -> bool {
+    formatter_for(Path::new(path)).is_some()
+}
+
+/// Format a file in place with the formatter for its language
+///
+/// The formatter runs from the file's directory, so it picks up project
+/// configuration such as `rustfmt.toml` or `.prettierrc`.
+///
+/// # Arguments
+/// * `path` - The file to format
+///
+/// # Returns
+/// The formatter used and whether the file changed
+pub fn format_file(path: &str) -> ToolResult<FormatResult> {
+    let file = Path::new(path);
+    if !file.is_file() {
+        return Err(ToolError::PathNotFound(path.to_string()));
+    }
+    let formatter = formatter_for(file).ok_or_else(|| {
+        ToolError::InvalidArgument(format!("No formatter available for {}", path))
+    })?;
+
+    let before = fs::read(file)?;
+    let mut cmd = Command::new(formatter.program);
+    cmd.args(formatter.args).arg(file);
+    if let Some(dir) = file.parent().filter(|d| !d.as_os_str().is_empty()) {
+        cmd.current_dir(dir);
+    }
+
+    let output = cmd.output().map_err(|e| {
+        if e.kind() == ErrorKind::NotFound {
+            ToolError::ExecutionFailed(format!("{} is not installed", formatter.program))
+        } else {
+            ToolError::ExecutionFailed(format!("Failed to run {}: {}", formatter.name, e))
+        }
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let message = if stderr.trim().is_empty() { stdout } else { stderr };
+        return Err(ToolError::ExecutionFailed(format!(
+            "{} failed: {}",
+            formatter.name,
+            message.trim()
+        )));
+    }
+
+    Ok(FormatResult {
+        path: path.to_string(),
+        formatter: formatter.name.to_string(),
+        changed: fs::read(file)? != before,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formatter_selection() {
+        assert_eq!(formatter_for(Path::new("src/main.rs")).unwrap().name, "rustfmt");
+        assert_eq!(formatter_for(Path::new("App.TSX")).unwrap().name, "prettier");
+        assert_eq!(formatter_for(Path::new("setup.py")).unwrap().name, "black");
+        assert!(!can_format("Makefile"));
+        assert!(matches!(
+            format_file("/nonexistent/main.rs"),
+            Err(ToolError::PathNotFound(_))
+        ));
+    }
+}
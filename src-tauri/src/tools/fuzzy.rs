@@ -0,0 +1,155 @@
+//! Fuzzy file finding for the tools system
+//!
+//! This module matches a loose query against a project's file paths with
+//! fzf-style scoring, so "provder typs" finds `providers/types.rs`. It backs
+//! both the `find_file` tool and the frontend's quick-open palette.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Config, Matcher, Utf32Str};
+use serde::{Deserialize, Serialize};
+
+use super::{project_walker, ToolError, ToolResult};
+
+/// Default number of files returned by a fuzzy search
+pub const DEFAULT_FUZZY_RESULTS: usize = 20;
+
+/// Largest amount a file's frecency adds to its match score
+const MAX_FRECENCY_BOOST: f64 = 100.0;
+
+/// Words people type before the actual query ("open foo.rs"), which would
+/// otherwise have to match the path too
+const QUERY_VERBS: &[&str] = &["open", "find", "show", "goto", "edit"];
+
+/// A file matched by a fuzzy search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzyMatch {
+    /// Full path of the file
+    pub path: String,
+    /// Path relative to the search root, which the query was matched against
+    pub relative_path: String,
+    pub score: u32,
+    /// Character positions in `relative_path` that matched, for highlighting
+    pub indices: Vec<u32>,
+}
+
+/// Drop a leading verb from a multi-word query
+fn strip_query_verb(query: &str) -> &str {
+    let trimmed = query.trim_start();
+    match trimmed.split_once(char::is_whitespace) {
+        Some((first, rest)) if QUERY_VERBS.contains(&first.to_lowercase().as_str()) => rest.trim(),
+        _ => trimmed.trim_end(),
+    }
+}
+
+/// Find the files whose paths best match a fuzzy query
+///
+/// Each word of the query must match the path (fzf syntax: `^` anchors to
+/// the start, `$` to the end, `'` requires an exact substring, `!` negates).
+///
+/// # Arguments
+/// * `query` - The fuzzy query
+/// * `base_path` - The directory to search in
+/// * `limit` - Maximum number of files to return
+///
+/// # Returns
+/// The best matching files, best first
+pub fn fuzzy_find_files(query: &str, base_path: &str, limit: usize) -> ToolResult<Vec<FuzzyMatch>> {
+    fuzzy_find_files_ranked(query, base_path, limit, &HashMap::new())
+}
+
+/// Find the files whose paths best match a fuzzy query, favoring files used
+/// often and recently
+///
+/// A file's frecency, keyed by its full path, is added to its match score,
+/// up to `MAX_FRECENCY_BOOST`, so a recently edited file wins a close match.
+pub fn fuzzy_find_files_ranked(
+    query: &str,
+    base_path: &str,
+    limit: usize,
+    frecency: &HashMap<String, f64>,
+) -> ToolResult<Vec<FuzzyMatch>> {
+    let base = Path::new(base_path);
+    if !base.is_dir() {
+        return Err(ToolError::PathNotFound(base_path.to_string()));
+    }
+
+    let query = strip_query_verb(query);
+    if query.is_empty() {
+        return Err(ToolError::InvalidArgument("Query is empty".to_string()));
+    }
+
+    let pattern = Pattern::parse(query, CaseMatching::Smart, Normalization::Smart);
+    let mut matcher = Matcher::new(Config::DEFAULT.match_paths());
+    let mut buf = Vec::new();
+    let mut matches = Vec::new();
+
+    for entry in project_walker(base, false).build().flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(base) else {
+            continue;
+        };
+        let relative_path = relative.to_string_lossy().replace('\\', "/");
+
+        let mut indices = Vec::new();
+        let haystack = Utf32Str::new(&relative_path, &mut buf);
+        let Some(score) = pattern.indices(haystack, &mut matcher, &mut indices) else {
+            continue;
+        };
+        indices.sort_unstable();
+        indices.dedup();
+
+        let path = entry.path().to_string_lossy().to_string();
+        let boost = frecency.get(&path).map_or(0.0, |f| f.min(MAX_FRECENCY_BOOST));
+        matches.push(FuzzyMatch {
+            path,
+            relative_path,
+            score: score + boost as u32,
+            indices,
+        });
+    }
+
+    // Best score first; among equals prefer shorter paths
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.relative_path.len().cmp(&b.relative_path.len()))
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+    matches.truncate(limit);
+
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_fuzzy_find_files_tolerates_typos_and_verbs() {
+        let dir = tempdir().unwrap();
+        for file in [
+            "src/providers/types.rs",
+            "src/providers/openai.rs",
+            "src/tools/types.rs",
+            "src/providers/mod.rs",
+        ] {
+            let path = dir.path().join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let matches =
+            fuzzy_find_files("open provder typs", dir.path().to_str().unwrap(), 10).unwrap();
+
+        assert_eq!(matches[0].relative_path, "src/providers/types.rs");
+        assert!(matches.iter().all(|m| m.relative_path.contains("types")));
+        assert!(!matches[0].indices.is_empty());
+    }
+}
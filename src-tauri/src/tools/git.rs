@@ -0,0 +1,2832 @@
+//! Git operations for the tools system
+//!
+//! This module works with repositories through libgit2, so no `git` binary
+//! needs to be installed and results don't depend on parsing its output. It
+//! backs both the frontend's git commands and the git tools AI assistants can
+//! call.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use git2::build::CheckoutBuilder;
+use git2::{
+    AnnotatedCommit, BranchType, CherrypickOptions, Commit, Cred, CredentialType, Delta, Diff,
+    DiffFormat, DiffStatsFormat, FetchOptions, FetchPrune, IndexAddOption, IndexConflict,
+    IndexEntry, ObjectType, Oid, PushOptions, RemoteCallbacks, Repository, RepositoryState,
+    ResetType, RevparseMode, Sort, Status, StatusEntry, StatusOptions, WorktreeAddOptions,
+    WorktreeLockStatus, WorktreePruneOptions,
+};
+use ignore::gitignore::GitignoreBuilder;
+use ignore::Match;
+use serde::{Deserialize, Serialize};
+
+use super::{ToolError, ToolResult};
+
+/// Private keys tried, in order, when the SSH agent has none that work
+const DEFAULT_SSH_KEYS: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// File in the git directory holding the state of an interactive rebase
+const REBASE_STATE_FILE: &str = "opensesh-rebase.json";
+
+/// Confirmation a hard reset needs, since it discards uncommitted changes
+pub const HARD_RESET_CONFIRMATION: &str = "discard-uncommitted-changes";
+
+/// Git status result
+#[derive(Debug, Clone, Serialize)]
+pub struct GitStatus {
+    pub branch: String,
+    pub ahead: u32,
+    pub behind: u32,
+    pub staged: Vec<FileStatus>,
+    pub unstaged: Vec<FileStatus>,
+    pub untracked: Vec<String>,
+    pub is_clean: bool,
+    pub has_conflicts: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileStatus {
+    pub path: String,
+    pub status: String, // "modified", "added", "deleted", "renamed", "conflict"
+    pub old_path: Option<String>, // For renamed files
+}
+
+/// Git commit info
+#[derive(Debug, Serialize)]
+pub struct GitCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub email: String,
+    /// Author date in strict ISO 8601 format
+    pub date: String,
+    pub message: String,
+    pub body: String,
+}
+
+/// Whether a path is ignored, and the rule that decides it
+#[derive(Debug, Serialize)]
+pub struct IgnoreCheck {
+    /// The path, relative to the repository root
+    pub path: String,
+    pub ignored: bool,
+    /// Whether git tracks the file anyway; ignore rules don't apply to it then
+    pub tracked: bool,
+    /// The ignore file with the deciding rule, relative to the repository
+    /// root if it's inside it
+    pub source: Option<String>,
+    /// Line of the rule in `source`, counting from 1
+    pub line: Option<usize>,
+    /// The rule, such as "target/", or "!keep.log" for one that un-ignores
+    pub pattern: Option<String>,
+}
+
+/// A branch, tag or HEAD pointing at a commit
+#[derive(Debug, Serialize)]
+pub struct GitRefLabel {
+    /// Short name, like "main", "origin/main" or "v1.0"
+    pub name: String,
+    /// "head" (a detached HEAD), "branch", "remote" or "tag"
+    pub kind: String,
+    /// Whether this is the checked out branch
+    pub is_head: bool,
+}
+
+/// A commit with what the frontend needs to draw it in a branch graph
+#[derive(Debug, Serialize)]
+pub struct GraphCommit {
+    #[serde(flatten)]
+    pub commit: GitCommit,
+    /// Full hashes of the parents, first parent first
+    pub parents: Vec<String>,
+    pub refs: Vec<GitRefLabel>,
+}
+
+/// A local or remote-tracking branch
+#[derive(Debug, Serialize)]
+pub struct GitBranch {
+    pub name: String,
+    pub commit: String,
+    pub upstream: Option<String>,
+    pub is_current: bool,
+    pub is_remote: bool,
+}
+
+/// Outcome of merging a branch into the current one
+#[derive(Debug, Serialize)]
+pub struct MergeResult {
+    /// "up_to_date", "fast_forward", "merged" or "conflicts"
+    pub status: String,
+    /// The new HEAD commit, unless the merge stopped on conflicts
+    pub commit: Option<GitCommit>,
+    /// Conflicted paths, relative to the repository root
+    pub conflicts: Vec<String>,
+}
+
+/// A conflicted file with the content of each side
+///
+/// A side is `None` when it doesn't have the file (it was added or deleted
+/// there) or when the file is binary.
+#[derive(Debug, Serialize)]
+pub struct GitConflict {
+    pub path: String,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+    pub is_binary: bool,
+}
+
+/// How to resolve a conflicted file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", content = "content", rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// Keep the current branch's version
+    Ours,
+    /// Take the merged branch's version
+    Theirs,
+    /// Use hand-merged content
+    Custom(String),
+}
+
+/// Credentials for a remote, given by the user after an `AuthRequired` error
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GitCredentials {
+    pub username: Option<String>,
+    /// Password or personal access token for HTTPS remotes
+    pub token: Option<String>,
+    /// Private key file for SSH remotes
+    pub ssh_key_path: Option<String>,
+    pub ssh_passphrase: Option<String>,
+}
+
+/// A way of authenticating with a remote, tried once each
+enum CredentialSource {
+    KeyFile(PathBuf),
+    Agent,
+    Token(String),
+    /// The configured credential helper, which reads the system keychain
+    Helper,
+}
+
+/// Remembers the remote that ran out of credentials to try, so the failure
+/// can be reported as `AuthRequired`
+#[derive(Clone, Default)]
+struct AuthTracker(Arc<Mutex<Option<(String, &'static str)>>>);
+
+impl AuthTracker {
+    fn error(&self, error: git2::Error) -> ToolError {
+        let failed = self.0.lock().ok().and_then(|mut failed| failed.take());
+        match failed {
+            Some((url, method)) => ToolError::AuthRequired {
+                url,
+                method: method.to_string(),
+            },
+            None => error.into(),
+        }
+    }
+}
+
+/// Which commits a log lists
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LogFilter {
+    /// Matching commits to skip, for paging through history
+    pub skip: u32,
+    /// Branch or other revision to list from, instead of HEAD
+    pub branch: Option<String>,
+    /// Text in the author's name or email, ignoring case
+    pub author: Option<String>,
+    /// Text in the message, ignoring case
+    pub grep: Option<String>,
+    /// Only commits changing this file or directory, relative to the
+    /// repository directory given
+    pub path: Option<String>,
+    /// Only commits made at or after this time, in Unix seconds
+    pub since: Option<i64>,
+    /// Only commits made at or before this time, in Unix seconds
+    pub until: Option<i64>,
+}
+
+/// How much of a repository to clone
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CloneOptions {
+    /// Only fetch this many commits of history, like `git clone --depth`
+    pub depth: Option<u32>,
+    /// Branch to check out instead of the remote's default branch
+    pub branch: Option<String>,
+}
+
+/// A working tree of a repository
+#[derive(Debug, Serialize)]
+pub struct GitWorktree {
+    /// The linked worktree's name, or `None` for the main working tree
+    pub name: Option<String>,
+    pub path: String,
+    /// The checked-out branch, or `None` for a detached HEAD
+    pub branch: Option<String>,
+    pub is_main: bool,
+    pub is_locked: bool,
+}
+
+/// How much a reset puts back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetMode {
+    /// Move the branch only, keeping changes staged
+    Soft,
+    /// Also reset the index, keeping changes in the working tree
+    Mixed,
+    /// Also reset the working tree, discarding changes
+    Hard,
+}
+
+/// A move of HEAD recorded in the reflog
+#[derive(Debug, Serialize)]
+pub struct ReflogEntry {
+    /// Position in the reflog, as in `HEAD@{index}`
+    pub index: usize,
+    /// Where HEAD pointed after the operation
+    pub hash: String,
+    /// Where HEAD pointed before it; empty for the first commit
+    pub previous: String,
+    /// What moved HEAD, like "checkout: moving from main to feature"
+    pub message: String,
+    pub committer: String,
+    /// In strict ISO 8601 format
+    pub date: String,
+}
+
+/// What undoing the last HEAD move did
+#[derive(Debug, Serialize)]
+pub struct UndoResult {
+    /// The reflog message of the operation undone
+    pub undone: String,
+    /// "checkout" or "reset"
+    pub action: String,
+    /// The branch checked out again, or the commit HEAD was reset to
+    pub target: String,
+    /// Whether the working tree was left as it was rather than being reset
+    /// too
+    pub kept_changes: bool,
+    pub commit: GitCommit,
+}
+
+/// What to do with a commit during an interactive rebase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RebaseAction {
+    Pick,
+    /// Meld into the previous commit
+    Squash,
+    /// Pick with a new message
+    Reword,
+    Drop,
+}
+
+/// One line of an interactive rebase's todo list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RebaseStep {
+    pub action: RebaseAction,
+    pub commit: String,
+    /// Message for reworded commits, or for the combined commit when squashing
+    /// (the two messages are joined otherwise)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Progress of an interactive rebase
+#[derive(Debug, Serialize)]
+pub struct RebaseResult {
+    /// "completed" or "conflicts"
+    pub status: String,
+    /// The step that stopped on conflicts
+    pub current: Option<RebaseStep>,
+    /// Steps left after the current one
+    pub remaining: usize,
+    pub conflicts: Vec<String>,
+    /// The rebased branch's new head once completed
+    pub head: Option<GitCommit>,
+}
+
+/// Saved between the steps of an interactive rebase
+#[derive(Debug, Serialize, Deserialize)]
+struct RebaseState {
+    /// The branch being rebased, or `None` for a detached HEAD
+    head_name: Option<String>,
+    orig_head: String,
+    /// Steps still to do, starting with the one stopped on
+    todo: Vec<RebaseStep>,
+    /// Whether the first step has been applied and is waiting on conflicts
+    stopped: bool,
+}
+
+/// Open the repository containing `path`
+fn open(path: &str) -> ToolResult<Repository> {
+    Ok(Repository::discover(path)?)
+}
+
+/// A path given relative to `cwd`, made relative to the repository root
+fn repo_relative(repo: &Repository, cwd: &str, file: &str) -> String {
+    let prefix = repo.workdir().and_then(|workdir| {
+        let workdir = workdir.canonicalize().ok()?;
+        let cwd = Path::new(cwd).canonicalize().ok()?;
+        cwd.strip_prefix(&workdir).ok().map(Path::to_path_buf)
+    });
+    let path = match prefix {
+        Some(prefix) if !prefix.as_os_str().is_empty() => prefix.join(file),
+        _ => PathBuf::from(file),
+    };
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Format a commit time like `git log --format=%aI`
+fn format_time(time: git2::Time) -> String {
+    let offset = i64::from(time.offset_minutes());
+    let local = time.seconds() + offset * 60;
+    let (days, secs) = (local.div_euclid(86_400), local.rem_euclid(86_400));
+
+    // Civil date from days since the epoch
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+fn commit_info(commit: &Commit) -> ToolResult<GitCommit> {
+    let author = commit.author();
+    Ok(GitCommit {
+        hash: commit.id().to_string(),
+        short_hash: commit
+            .as_object()
+            .short_id()?
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        author: author.name().unwrap_or_default().to_string(),
+        email: author.email().unwrap_or_default().to_string(),
+        date: format_time(author.when()),
+        message: commit.summary().unwrap_or_default().to_string(),
+        body: commit.body().unwrap_or_default().trim().to_string(),
+    })
+}
+
+/// The current branch's name; empty when HEAD is detached
+fn current_branch(repo: &Repository) -> String {
+    match repo.head() {
+        Ok(head) if head.is_branch() => head.shorthand().unwrap_or_default().to_string(),
+        Ok(_) => String::new(),
+        // A new repository's branch has no commits yet
+        Err(_) => repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|head| {
+                head.symbolic_target()
+                    .map(|t| t.trim_start_matches("refs/heads/").to_string())
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// The path of a status entry, after any rename
+fn entry_path(entry: &StatusEntry) -> Option<String> {
+    entry
+        .head_to_index()
+        .and_then(|d| d.new_file().path())
+        .or_else(|| entry.index_to_workdir().and_then(|d| d.new_file().path()))
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .or_else(|| entry.path().map(str::to_string))
+}
+
+const INDEX_CHANGES: Status = Status::INDEX_NEW
+    .union(Status::INDEX_MODIFIED)
+    .union(Status::INDEX_DELETED)
+    .union(Status::INDEX_RENAMED)
+    .union(Status::INDEX_TYPECHANGE);
+
+/// Get the status of a repository
+pub fn status(path: &str) -> ToolResult<GitStatus> {
+    let repo = open(path)?;
+    let branch = current_branch(&repo);
+    let (ahead, behind) = get_ahead_behind(&repo).unwrap_or((0, 0));
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .renames_head_to_index(true);
+    let statuses = repo.statuses(Some(&mut options))?;
+
+    let mut staged = Vec::new();
+    let mut unstaged = Vec::new();
+    let mut untracked = Vec::new();
+    let mut has_conflicts = false;
+
+    for entry in statuses.iter() {
+        let Some(file_path) = entry_path(&entry) else {
+            continue;
+        };
+        let s = entry.status();
+
+        if s.is_conflicted() {
+            has_conflicts = true;
+            for list in [&mut staged, &mut unstaged] {
+                list.push(FileStatus {
+                    path: file_path.clone(),
+                    status: "conflict".to_string(),
+                    old_path: None,
+                });
+            }
+            continue;
+        }
+        if s.is_ignored() {
+            continue;
+        }
+        if s.is_wt_new() && !s.intersects(INDEX_CHANGES) {
+            untracked.push(file_path);
+            continue;
+        }
+
+        let index_status = if s.is_index_new() {
+            Some("added")
+        } else if s.is_index_deleted() {
+            Some("deleted")
+        } else if s.is_index_renamed() {
+            Some("renamed")
+        } else if s.is_index_modified() || s.is_index_typechange() {
+            Some("modified")
+        } else {
+            None
+        };
+        if let Some(status) = index_status {
+            let old_path = s
+                .is_index_renamed()
+                .then(|| entry.head_to_index().and_then(|d| d.old_file().path()))
+                .flatten()
+                .map(|p| p.to_string_lossy().replace('\\', "/"));
+            staged.push(FileStatus {
+                path: file_path.clone(),
+                status: status.to_string(),
+                old_path,
+            });
+        }
+
+        let worktree_status = if s.is_wt_deleted() {
+            Some("deleted")
+        } else if s.is_wt_modified() || s.is_wt_typechange() || s.is_wt_renamed() {
+            Some("modified")
+        } else {
+            None
+        };
+        if let Some(status) = worktree_status {
+            unstaged.push(FileStatus {
+                path: file_path,
+                status: status.to_string(),
+                old_path: None,
+            });
+        }
+    }
+
+    let is_clean = staged.is_empty() && unstaged.is_empty() && untracked.is_empty();
+
+    Ok(GitStatus {
+        branch,
+        ahead,
+        behind,
+        staged,
+        unstaged,
+        untracked,
+        is_clean,
+        has_conflicts,
+    })
+}
+
+/// Statuses of repositories whose files are being watched, so they are only
+/// worked out again once something changes
+///
+/// Entries are keyed by canonical path; whoever sees a change in a directory
+/// must call [`GitStatusCache::invalidate`] for it.
+#[derive(Debug, Default)]
+pub struct GitStatusCache {
+    statuses: Mutex<HashMap<PathBuf, GitStatus>>,
+}
+
+impl GitStatusCache {
+    pub fn get(&self, path: &Path) -> Option<GitStatus> {
+        self.statuses.lock().unwrap().get(path).cloned()
+    }
+
+    pub fn insert(&self, path: PathBuf, status: GitStatus) {
+        self.statuses.lock().unwrap().insert(path, status);
+    }
+
+    /// Forget the status of every repository path in, or containing, `path`
+    pub fn invalidate(&self, path: &Path) {
+        let mut statuses = self.statuses.lock().unwrap();
+        statuses.retain(|cached, _| !cached.starts_with(path) && !path.starts_with(cached));
+    }
+
+    pub fn clear(&self) {
+        self.statuses.lock().unwrap().clear();
+    }
+}
+
+/// Get the status of every changed, untracked or ignored path under a directory
+///
+/// Untracked and ignored directories are reported as a whole rather than
+/// file by file.
+///
+/// # Returns
+/// Statuses keyed by absolute path: "modified", "added", "deleted",
+/// "renamed", "conflict", "untracked" or "ignored"
+pub fn path_statuses(path: &str) -> ToolResult<HashMap<PathBuf, String>> {
+    let repo = open(path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| ToolError::InvalidArgument("Repository has no working tree".to_string()))?
+        .canonicalize()?;
+
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .include_ignored(true)
+        .recurse_ignored_dirs(false)
+        .renames_head_to_index(true);
+    let prefix = repo_relative(&repo, path, "");
+    if !prefix.is_empty() {
+        options.pathspec(&prefix);
+    }
+
+    let mut statuses = HashMap::new();
+    for entry in repo.statuses(Some(&mut options))?.iter() {
+        let Some(file_path) = entry_path(&entry) else {
+            continue;
+        };
+        let s = entry.status();
+        let status = if s.is_conflicted() {
+            "conflict"
+        } else if s.is_ignored() {
+            "ignored"
+        } else if s.is_wt_new() && !s.intersects(INDEX_CHANGES) {
+            "untracked"
+        } else if s.is_index_renamed() || s.is_wt_renamed() {
+            "renamed"
+        } else if s.is_index_deleted() || s.is_wt_deleted() {
+            "deleted"
+        } else if s.is_index_new() {
+            "added"
+        } else {
+            "modified"
+        };
+        statuses.insert(
+            workdir.join(file_path.trim_end_matches('/')),
+            status.to_string(),
+        );
+    }
+    Ok(statuses)
+}
+
+/// Get ahead/behind counts relative to upstream
+fn get_ahead_behind(repo: &Repository) -> ToolResult<(u32, u32)> {
+    let head = repo.head()?;
+    let (Some(name), Some(local)) = (head.shorthand(), head.target()) else {
+        return Ok((0, 0));
+    };
+    let upstream = repo.find_branch(name, BranchType::Local)?.upstream()?;
+    let Some(upstream) = upstream.get().target() else {
+        return Ok((0, 0));
+    };
+    let (ahead, behind) = repo.graph_ahead_behind(local, upstream)?;
+    Ok((ahead as u32, behind as u32))
+}
+
+/// The staged changes (index against HEAD) or unstaged changes (working
+/// tree against the index)
+fn changes<'r>(
+    repo: &'r Repository,
+    cwd: &str,
+    staged: bool,
+    file_path: Option<&str>,
+) -> ToolResult<Diff<'r>> {
+    let mut options = git2::DiffOptions::new();
+    if let Some(file_path) = file_path {
+        options.pathspec(repo_relative(repo, cwd, file_path));
+    }
+    let mut diff = if staged {
+        let head = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+        repo.diff_tree_to_index(head.as_ref(), None, Some(&mut options))?
+    } else {
+        repo.diff_index_to_workdir(None, Some(&mut options))?
+    };
+    diff.find_similar(None)?;
+    Ok(diff)
+}
+
+/// Render a diff as a unified patch, like `git diff`
+fn patch_text(diff: &Diff) -> ToolResult<String> {
+    let mut text = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            text.push(line.origin());
+        }
+        text.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+    Ok(text)
+}
+
+/// Get the diff of the working tree or the index, optionally for one file
+pub fn diff(path: &str, staged: bool, file_path: Option<&str>) -> ToolResult<String> {
+    let repo = open(path)?;
+    let diff = changes(&repo, path, staged, file_path)?;
+    patch_text(&diff)
+}
+
+/// Get a `git diff --stat` style summary of the working tree or index changes
+pub fn diff_stat(path: &str, staged: bool) -> ToolResult<String> {
+    let repo = open(path)?;
+    let stats = changes(&repo, path, staged, None)?.stats()?;
+    let buf = stats.to_buf(DiffStatsFormat::FULL, 80)?;
+    Ok(buf.as_str().unwrap_or_default().to_string())
+}
+
+/// Get the diff between two commits, given as a range such as `main..HEAD`
+/// or a single ref to compare the working tree against
+pub fn diff_range(path: &str, range: &str) -> ToolResult<String> {
+    if range.starts_with('-') {
+        return Err(ToolError::InvalidArgument(format!("Invalid ref range: {}", range)));
+    }
+    let repo = open(path)?;
+    let spec = repo.revparse(range)?;
+
+    let mut diff = match (spec.from(), spec.to()) {
+        (Some(from), Some(to)) => {
+            // `a...b` compares b with where it branched off a
+            let from_tree = if spec.mode().contains(RevparseMode::MERGE_BASE) {
+                let base = repo.merge_base(from.id(), to.id())?;
+                repo.find_commit(base)?.tree()?
+            } else {
+                from.peel_to_tree()?
+            };
+            repo.diff_tree_to_tree(Some(&from_tree), Some(&to.peel_to_tree()?), None)?
+        }
+        (Some(from), None) => {
+            repo.diff_tree_to_workdir_with_index(Some(&from.peel_to_tree()?), None)?
+        }
+        _ => return Err(ToolError::InvalidArgument(format!("Invalid ref range: {}", range))),
+    };
+    diff.find_similar(None)?;
+    patch_text(&diff)
+}
+
+/// The changes between two refs, or between a ref and the working tree
+fn ref_changes<'r>(
+    repo: &'r Repository,
+    cwd: &str,
+    from_ref: &str,
+    to_ref: &str,
+    file_path: Option<&str>,
+) -> ToolResult<Diff<'r>> {
+    let mut options = git2::DiffOptions::new();
+    if let Some(file_path) = file_path {
+        options.pathspec(repo_relative(repo, cwd, file_path));
+    }
+    let from_tree = repo.revparse_single(from_ref)?.peel_to_tree()?;
+    let mut diff = if to_ref.is_empty() {
+        repo.diff_tree_to_workdir_with_index(Some(&from_tree), Some(&mut options))?
+    } else {
+        let to_tree = repo.revparse_single(to_ref)?.peel_to_tree()?;
+        repo.diff_tree_to_tree(Some(&from_tree), Some(&to_tree), Some(&mut options))?
+    };
+    diff.find_similar(None)?;
+    Ok(diff)
+}
+
+/// Get the diff between two refs, optionally for one file
+///
+/// # Arguments
+/// * `from_ref` - A branch, tag or commit
+/// * `to_ref` - A branch, tag or commit, or an empty string for the working
+///   tree
+/// * `file_path` - Path relative to `path`
+pub fn diff_refs(
+    path: &str,
+    from_ref: &str,
+    to_ref: &str,
+    file_path: Option<&str>,
+) -> ToolResult<String> {
+    let repo = open(path)?;
+    let diff = ref_changes(&repo, path, from_ref, to_ref, file_path)?;
+    patch_text(&diff)
+}
+
+/// List the files changed between two refs, like `git diff --name-status`
+pub fn diff_refs_files(path: &str, from_ref: &str, to_ref: &str) -> ToolResult<Vec<FileStatus>> {
+    let repo = open(path)?;
+    let diff = ref_changes(&repo, path, from_ref, to_ref, None)?;
+    let file_path =
+        |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().replace('\\', "/"));
+
+    let mut files = Vec::new();
+    for delta in diff.deltas() {
+        let status = match delta.status() {
+            Delta::Added | Delta::Copied => "added",
+            Delta::Deleted => "deleted",
+            Delta::Renamed => "renamed",
+            Delta::Conflicted => "conflict",
+            _ => "modified",
+        };
+        let new_path = file_path(delta.new_file()).unwrap_or_default();
+        let old_path = file_path(delta.old_file()).filter(|old| *old != new_path);
+        files.push(FileStatus {
+            path: new_path,
+            status: status.to_string(),
+            old_path,
+        });
+    }
+    Ok(files)
+}
+
+/// Get the most recent commits
+pub fn log(path: &str, count: u32) -> ToolResult<Vec<GitCommit>> {
+    log_filtered(path, count, &LogFilter::default())
+}
+
+/// Branches, remote-tracking branches and tags by the commit they point at
+fn ref_labels(repo: &Repository) -> ToolResult<HashMap<Oid, Vec<GitRefLabel>>> {
+    let head = repo.head().ok();
+    let head_name = head.as_ref().and_then(|h| h.name()).map(str::to_string);
+    let mut labels: HashMap<Oid, Vec<GitRefLabel>> = HashMap::new();
+    if let Some(head) = head.filter(|h| !h.is_branch()) {
+        if let Some(oid) = head.target() {
+            labels.entry(oid).or_default().push(GitRefLabel {
+                name: "HEAD".to_string(),
+                kind: "head".to_string(),
+                is_head: true,
+            });
+        }
+    }
+    for reference in repo.references()? {
+        let reference = reference?;
+        let kind = if reference.is_branch() {
+            "branch"
+        } else if reference.is_remote() {
+            "remote"
+        } else if reference.is_tag() {
+            "tag"
+        } else {
+            continue;
+        };
+        let (Some(name), Ok(commit)) = (reference.shorthand(), reference.peel_to_commit()) else {
+            continue;
+        };
+        // Remotes' HEADs only repeat the branch they point at
+        if kind == "remote" && name.ends_with("/HEAD") {
+            continue;
+        }
+        labels.entry(commit.id()).or_default().push(GitRefLabel {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            is_head: reference.name().is_some() && reference.name() == head_name.as_deref(),
+        });
+    }
+    Ok(labels)
+}
+
+/// Get the most recent commits on all branches, with their parents and refs
+///
+/// Commits come in topological order, so each is listed before its parents.
+pub fn log_graph(path: &str, count: u32) -> ToolResult<Vec<GraphCommit>> {
+    let repo = open(path)?;
+    let mut walk = repo.revwalk()?;
+    // A new repository has no commits yet
+    let _ = walk.push_head();
+    walk.push_glob("refs/heads")?;
+    walk.push_glob("refs/remotes")?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+    let mut labels = ref_labels(&repo)?;
+    walk.take(count as usize)
+        .map(|oid| {
+            let commit = repo.find_commit(oid?)?;
+            Ok(GraphCommit {
+                commit: commit_info(&commit)?,
+                parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+                refs: labels.remove(&commit.id()).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// Whether a commit changed a path, compared with each of its parents
+///
+/// Like `git log -- <path>`, a merge only counts if it differs from all of
+/// its parents there.
+fn changes_path(commit: &Commit, path: &Path) -> ToolResult<bool> {
+    let entry_id = |tree: git2::Tree| tree.get_path(path).ok().map(|e| e.id());
+    let own = entry_id(commit.tree()?);
+    if commit.parent_count() == 0 {
+        return Ok(own.is_some());
+    }
+    for parent in commit.parents() {
+        if entry_id(parent.tree()?) == own {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Get commits matching a filter, newest first
+///
+/// # Arguments
+/// * `count` - Most commits to return, after skipping `filter.skip`
+pub fn log_filtered(path: &str, count: u32, filter: &LogFilter) -> ToolResult<Vec<GitCommit>> {
+    let repo = open(path)?;
+    let mut walk = repo.revwalk()?;
+    match &filter.branch {
+        Some(branch) => walk.push(repo.revparse_single(branch)?.peel_to_commit()?.id())?,
+        // A new repository has no commits yet
+        None if walk.push_head().is_err() => return Ok(Vec::new()),
+        None => {}
+    }
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+    let lowercase = |text: &Option<String>| text.as_ref().map(|t| t.to_lowercase());
+    let (author, grep) = (lowercase(&filter.author), lowercase(&filter.grep));
+    let file = filter.path.as_ref().map(|file| PathBuf::from(repo_relative(&repo, path, file)));
+
+    let mut commits = Vec::new();
+    let mut skipped = 0;
+    for oid in walk {
+        if commits.len() >= count as usize {
+            break;
+        }
+        let commit = repo.find_commit(oid?)?;
+        let time = commit.time().seconds();
+        if filter.since.is_some_and(|since| time < since)
+            || filter.until.is_some_and(|until| time > until)
+        {
+            continue;
+        }
+        if let Some(author) = &author {
+            let signature = commit.author();
+            let name = signature.name().unwrap_or_default().to_lowercase();
+            let email = signature.email().unwrap_or_default().to_lowercase();
+            if !name.contains(author.as_str()) && !email.contains(author.as_str()) {
+                continue;
+            }
+        }
+        if let Some(grep) = &grep {
+            let message = commit.message().unwrap_or_default().to_lowercase();
+            if !message.contains(grep.as_str()) {
+                continue;
+            }
+        }
+        if let Some(file) = &file {
+            if !changes_path(&commit, file)? {
+                continue;
+            }
+        }
+        if skipped < filter.skip {
+            skipped += 1;
+            continue;
+        }
+        commits.push(commit_info(&commit)?);
+    }
+    Ok(commits)
+}
+
+/// Whether a commit changes how many times `query` appears in any file,
+/// like `git log -S`
+///
+/// Merges aren't searched, as their changes come from the merged commits.
+fn changes_occurrences(repo: &Repository, commit: &Commit, query: &[u8]) -> ToolResult<bool> {
+    if commit.parent_count() > 1 {
+        return Ok(false);
+    }
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    let occurrences = |oid: Oid| -> ToolResult<usize> {
+        if oid.is_zero() {
+            return Ok(0);
+        }
+        let blob = repo.find_blob(oid)?;
+        if blob.is_binary() {
+            return Ok(0);
+        }
+        Ok(blob.content().windows(query.len()).filter(|w| *w == query).count())
+    };
+    for delta in diff.deltas() {
+        if occurrences(delta.old_file().id())? != occurrences(delta.new_file().id())? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Search history for commits mentioning some text, newest first
+///
+/// # Arguments
+/// * `search_patches` - Find commits that add or remove `query` in their
+///   changes, like `git log -S`, rather than mention it in their message, like
+///   `git log --grep` (ignoring case)
+/// * `count` - Most commits to return
+pub fn search_commits(
+    path: &str,
+    query: &str,
+    search_patches: bool,
+    count: u32,
+) -> ToolResult<Vec<GitCommit>> {
+    if query.is_empty() {
+        return Err(ToolError::InvalidArgument("The search text is empty".to_string()));
+    }
+    if !search_patches {
+        let filter = LogFilter {
+            grep: Some(query.to_string()),
+            ..Default::default()
+        };
+        return log_filtered(path, count, &filter);
+    }
+
+    let repo = open(path)?;
+    let mut walk = repo.revwalk()?;
+    // A new repository has no commits yet
+    if walk.push_head().is_err() {
+        return Ok(Vec::new());
+    }
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+
+    let mut commits = Vec::new();
+    for oid in walk {
+        if commits.len() >= count as usize {
+            break;
+        }
+        let commit = repo.find_commit(oid?)?;
+        if changes_occurrences(&repo, &commit, query.as_bytes())? {
+            commits.push(commit_info(&commit)?);
+        }
+    }
+    Ok(commits)
+}
+
+/// Stage files for commit
+///
+/// Paths may be files or directories; deleted files are staged as deleted.
+pub fn stage(path: &str, files: &[String]) -> ToolResult<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let repo = open(path)?;
+    let specs: Vec<String> = files.iter().map(|f| repo_relative(&repo, path, f)).collect();
+
+    let mut index = repo.index()?;
+    index.add_all(&specs, IndexAddOption::DEFAULT, None)?;
+    index.update_all(&specs, None)?;
+    index.write()?;
+    Ok(())
+}
+
+/// Stage every change in the working tree, like `git add -A`
+pub fn stage_all(path: &str) -> ToolResult<()> {
+    let repo = open(path)?;
+    let mut index = repo.index()?;
+    index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+    index.update_all(["*"], None)?;
+    index.write()?;
+    Ok(())
+}
+
+/// Unstage files, leaving their changes in the working tree
+pub fn unstage(path: &str, files: &[String]) -> ToolResult<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+    let repo = open(path)?;
+    let specs: Vec<String> = files.iter().map(|f| repo_relative(&repo, path, f)).collect();
+
+    // Without a commit to reset to, the files are removed from the index
+    let head = repo.head().ok().and_then(|h| h.peel(ObjectType::Commit).ok());
+    repo.reset_default(head.as_ref(), &specs)?;
+    Ok(())
+}
+
+/// Discard unstaged changes to a file, restoring it from the index
+pub fn discard(path: &str, file_path: &str) -> ToolResult<()> {
+    let repo = open(path)?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force().path(repo_relative(&repo, path, file_path));
+    repo.checkout_index(None, Some(&mut checkout))?;
+    Ok(())
+}
+
+/// Commit staged changes
+pub fn commit(path: &str, message: &str) -> ToolResult<GitCommit> {
+    let mut repo = open(path)?;
+    let signature = repo.signature()?;
+
+    // Finishing a merge records the merged commits as extra parents
+    let mut merge_heads = Vec::new();
+    if repo.state() == RepositoryState::Merge {
+        repo.mergehead_foreach(|oid| {
+            merge_heads.push(*oid);
+            true
+        })?;
+    }
+
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        return Err(ToolError::ExecutionFailed(
+            "Resolve the merge conflicts before committing".to_string(),
+        ));
+    }
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let merge_heads = merge_heads
+        .into_iter()
+        .map(|oid| repo.find_commit(oid))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let unchanged = match &parent {
+        _ if !merge_heads.is_empty() => false,
+        Some(parent) => parent.tree_id() == tree.id(),
+        None => index.is_empty(),
+    };
+    if unchanged {
+        return Err(ToolError::ExecutionFailed("Nothing to commit".to_string()));
+    }
+
+    let message = git2::message_prettify(message, None)?;
+    let parents: Vec<&Commit> = parent.iter().chain(&merge_heads).collect();
+    let oid = repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)?;
+    if !merge_heads.is_empty() {
+        repo.cleanup_state()?;
+    }
+    let commit = repo.find_commit(oid)?;
+    commit_info(&commit)
+}
+
+/// Point the current branch at a revision
+///
+/// # Arguments
+/// * `target` - The revision to reset to, such as `HEAD~1`
+/// * `confirmation` - Must be [`HARD_RESET_CONFIRMATION`] for a hard reset
+///
+/// # Returns
+/// The commit the branch now points at
+pub fn reset(
+    path: &str,
+    target: &str,
+    mode: ResetMode,
+    confirmation: Option<&str>,
+) -> ToolResult<GitCommit> {
+    if mode == ResetMode::Hard && confirmation != Some(HARD_RESET_CONFIRMATION) {
+        return Err(ToolError::PermissionDenied(
+            "A hard reset discards uncommitted changes and needs confirmation".to_string(),
+        ));
+    }
+    let repo = open(path)?;
+    if rebase_state_path(&repo).exists() {
+        return Err(ToolError::ExecutionFailed(
+            "Finish or abort the rebase before resetting".to_string(),
+        ));
+    }
+    let commit = repo.revparse_single(target)?.peel_to_commit()?;
+    let kind = match mode {
+        ResetMode::Soft => ResetType::Soft,
+        ResetMode::Mixed => ResetType::Mixed,
+        ResetMode::Hard => ResetType::Hard,
+    };
+    repo.reset(commit.as_object(), kind, None)?;
+    commit_info(&commit)
+}
+
+/// Get the most recent moves of HEAD, newest first
+pub fn reflog(path: &str, count: u32) -> ToolResult<Vec<ReflogEntry>> {
+    let repo = open(path)?;
+    let reflog = repo.reflog("HEAD")?;
+    let entries = reflog
+        .iter()
+        .take(count as usize)
+        .enumerate()
+        .map(|(index, entry)| {
+            let previous = entry.id_old();
+            ReflogEntry {
+                index,
+                hash: entry.id_new().to_string(),
+                previous: if previous.is_zero() {
+                    String::new()
+                } else {
+                    previous.to_string()
+                },
+                message: entry.message().unwrap_or_default().to_string(),
+                committer: entry.committer().name().unwrap_or_default().to_string(),
+                date: format_time(entry.committer().when()),
+            }
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// Undo the last move of HEAD recorded in the reflog
+///
+/// A checkout is undone by checking out the branch or commit it left. Anything
+/// else that moved the current branch (a reset, commit, merge or pull) is
+/// undone by a mixed reset to where it was, so the working tree keeps every
+/// change; undoing a commit leaves its changes uncommitted.
+///
+/// # Arguments
+/// * `confirmation` - [`HARD_RESET_CONFIRMATION`] to reset the working tree
+///   too, such as to bring back files a hard reset removed
+pub fn undo_last_operation(path: &str, confirmation: Option<&str>) -> ToolResult<UndoResult> {
+    let repo = open(path)?;
+    if repo.state() != RepositoryState::Clean || rebase_state_path(&repo).exists() {
+        return Err(ToolError::ExecutionFailed(
+            "Finish or abort the merge or rebase in progress first".to_string(),
+        ));
+    }
+    let reflog = repo.reflog("HEAD")?;
+    let Some(entry) = reflog.get(0) else {
+        return Err(ToolError::ExecutionFailed("There is nothing to undo".to_string()));
+    };
+    let undone = entry.message().unwrap_or_default().to_string();
+    let previous = entry.id_old();
+    if previous.is_zero() {
+        return Err(ToolError::ExecutionFailed(format!(
+            "\"{}\" has no earlier HEAD to go back to",
+            undone
+        )));
+    }
+
+    let left = undone
+        .strip_prefix("checkout: moving from ")
+        .and_then(|moved| moved.rsplit_once(" to "))
+        .map(|(from, _)| from.to_string());
+    let (action, target, kept_changes) = match left {
+        Some(from) => {
+            // Go back to the branch, or to the commit if it was detached
+            let target = if repo.find_branch(&from, BranchType::Local).is_ok() {
+                from
+            } else {
+                previous.to_string()
+            };
+            checkout(path, &target)?;
+            ("checkout", target, false)
+        }
+        None => {
+            let hard = confirmation == Some(HARD_RESET_CONFIRMATION);
+            let kind = if hard { ResetType::Hard } else { ResetType::Mixed };
+            repo.reset(&repo.find_object(previous, None)?, kind, None)?;
+            ("reset", previous.to_string(), !hard)
+        }
+    };
+
+    let head = repo.head()?.peel_to_commit()?;
+    Ok(UndoResult {
+        undone,
+        action: action.to_string(),
+        target,
+        kept_changes,
+        commit: commit_info(&head)?,
+    })
+}
+
+/// Resolve a branch name, remote-tracking branch or revision to a commit
+fn annotated_commit<'r>(repo: &'r Repository, target: &str) -> ToolResult<AnnotatedCommit<'r>> {
+    if let Ok(reference) = repo.resolve_reference_from_short_name(target) {
+        return Ok(repo.reference_to_annotated_commit(&reference)?);
+    }
+    let commit = repo.revparse_single(target)?.peel_to_commit()?;
+    Ok(repo.find_annotated_commit(commit.id())?)
+}
+
+/// Point HEAD, or the branch it's on, at `oid`
+fn move_head(repo: &Repository, oid: Oid, log_message: &str) -> ToolResult<()> {
+    let head = repo.find_reference("HEAD")?;
+    match head.symbolic_target() {
+        Some(refname) => {
+            repo.reference(refname, oid, true, log_message)?;
+        }
+        None => repo.set_head_detached(oid)?,
+    }
+    Ok(())
+}
+
+/// The path of a conflict, taken from whichever side has the file
+fn conflict_path(conflict: &IndexConflict) -> String {
+    [&conflict.our, &conflict.their, &conflict.ancestor]
+        .into_iter()
+        .flatten()
+        .next()
+        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+        .unwrap_or_default()
+}
+
+/// Paths in the index that still have conflicts
+fn conflicted_paths(repo: &Repository) -> ToolResult<Vec<String>> {
+    let mut paths = Vec::new();
+    for conflict in repo.index()?.conflicts()? {
+        paths.push(conflict_path(&conflict?));
+    }
+    Ok(paths)
+}
+
+/// Merge a branch into the current one
+///
+/// Fast-forwards when possible and otherwise creates a merge commit. When the
+/// merge conflicts, the conflicted files are left in the working tree and the
+/// repository stays mid-merge; resolve them with [`resolve_conflict`] and
+/// finish with [`commit`].
+///
+/// # Arguments
+/// * `branch` - A local or remote-tracking branch, or any revision
+pub fn merge(path: &str, branch: &str) -> ToolResult<MergeResult> {
+    let repo = open(path)?;
+    if repo.state() != RepositoryState::Clean {
+        return Err(ToolError::ExecutionFailed(
+            "Another merge or rebase is in progress".to_string(),
+        ));
+    }
+    let theirs = annotated_commit(&repo, branch)?;
+    let (analysis, _) = repo.merge_analysis(&[&theirs])?;
+
+    let result = |status: &str, commit: Option<GitCommit>, conflicts| MergeResult {
+        status: status.to_string(),
+        commit,
+        conflicts,
+    };
+    if analysis.is_up_to_date() {
+        return Ok(result("up_to_date", None, Vec::new()));
+    }
+    if analysis.is_fast_forward() {
+        let target = repo.find_object(theirs.id(), None)?;
+        repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))?;
+        move_head(&repo, theirs.id(), &format!("merge {}: Fast-forward", branch))?;
+        let commit = repo.find_commit(theirs.id())?;
+        return Ok(result("fast_forward", Some(commit_info(&commit)?), Vec::new()));
+    }
+
+    // Writes MERGE_HEAD and MERGE_MSG, and the merged files to the working tree
+    repo.merge(&[&theirs], None, Some(CheckoutBuilder::new().safe()))?;
+    let conflicts = conflicted_paths(&repo)?;
+    if !conflicts.is_empty() {
+        return Ok(result("conflicts", None, conflicts));
+    }
+    let message = repo.message()?;
+    let commit = commit(path, &message)?;
+    Ok(result("merged", Some(commit), Vec::new()))
+}
+
+/// The content of one side of a conflict
+fn conflict_side(repo: &Repository, entry: Option<&IndexEntry>) -> ToolResult<Option<Vec<u8>>> {
+    match entry {
+        Some(entry) => Ok(Some(repo.find_blob(entry.id)?.content().to_vec())),
+        None => Ok(None),
+    }
+}
+
+/// The base, ours and theirs content of a conflicted file
+fn conflict_versions(repo: &Repository, conflict: &IndexConflict) -> ToolResult<GitConflict> {
+    let sides = [&conflict.ancestor, &conflict.our, &conflict.their]
+        .map(|entry| conflict_side(repo, entry.as_ref()));
+    let [base, ours, theirs] = sides;
+    let (base, ours, theirs) = (base?, ours?, theirs?);
+    let is_binary = [&base, &ours, &theirs]
+        .into_iter()
+        .flatten()
+        .any(|content| content.contains(&0));
+    let text = |content: Option<Vec<u8>>| {
+        content
+            .filter(|_| !is_binary)
+            .map(|content| String::from_utf8_lossy(&content).to_string())
+    };
+    Ok(GitConflict {
+        path: conflict_path(conflict),
+        base: text(base),
+        ours: text(ours),
+        theirs: text(theirs),
+        is_binary,
+    })
+}
+
+/// List conflicted files with their base, ours and theirs content
+pub fn conflicts(path: &str) -> ToolResult<Vec<GitConflict>> {
+    let repo = open(path)?;
+    let mut result = Vec::new();
+    for conflict in repo.index()?.conflicts()? {
+        result.push(conflict_versions(&repo, &conflict?)?);
+    }
+    Ok(result)
+}
+
+/// Get the base, ours and theirs content of one conflicted file, for a
+/// three-way merge editor
+///
+/// # Arguments
+/// * `file_path` - Path relative to `path`
+pub fn merge_file_versions(path: &str, file_path: &str) -> ToolResult<GitConflict> {
+    let repo = open(path)?;
+    let relative = repo_relative(&repo, path, file_path);
+    for conflict in repo.index()?.conflicts()? {
+        let conflict = conflict?;
+        if conflict_path(&conflict) == relative {
+            return conflict_versions(&repo, &conflict);
+        }
+    }
+    Err(ToolError::InvalidArgument(format!("{} has no merge conflict", file_path)))
+}
+
+/// Resolve a conflicted file and mark it as resolved
+///
+/// Taking a side that deleted the file deletes it.
+///
+/// # Arguments
+/// * `file_path` - Path relative to `path`
+pub fn resolve_conflict(
+    path: &str,
+    file_path: &str,
+    resolution: ConflictResolution,
+) -> ToolResult<()> {
+    let repo = open(path)?;
+    let relative = repo_relative(&repo, path, file_path);
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| ToolError::ExecutionFailed("Repository has no working tree".to_string()))?;
+
+    let mut index = repo.index()?;
+    let mut conflict = None;
+    for entry in index.conflicts()? {
+        let entry = entry?;
+        if conflict_path(&entry) == relative {
+            conflict = Some(entry);
+            break;
+        }
+    }
+    let conflict = conflict
+        .ok_or_else(|| ToolError::InvalidArgument(format!("{} has no conflicts", relative)))?;
+    let content = match resolution {
+        ConflictResolution::Ours => conflict_side(&repo, conflict.our.as_ref())?,
+        ConflictResolution::Theirs => conflict_side(&repo, conflict.their.as_ref())?,
+        ConflictResolution::Custom(content) => Some(content.into_bytes()),
+    };
+
+    let full_path = workdir.join(&relative);
+    match content {
+        Some(content) => {
+            fs::write(&full_path, content)?;
+            index.add_path(Path::new(&relative))?;
+        }
+        None => {
+            if full_path.exists() {
+                fs::remove_file(&full_path)?;
+            }
+            index.remove_path(Path::new(&relative))?;
+        }
+    }
+    index.write()?;
+    Ok(())
+}
+
+/// List local and remote-tracking branches
+pub fn branches(path: &str) -> ToolResult<Vec<GitBranch>> {
+    let repo = open(path)?;
+    let mut branches = Vec::new();
+
+    for entry in repo.branches(None)? {
+        let (branch, kind) = entry?;
+        let reference = branch.get();
+        // Skip symbolic refs such as origin/HEAD
+        let Some(target) = reference.target() else {
+            continue;
+        };
+        let Some(name) = branch.name()? else {
+            continue;
+        };
+        let upstream = match kind {
+            BranchType::Local => branch
+                .upstream()
+                .ok()
+                .and_then(|u| u.name().ok().flatten().map(str::to_string)),
+            BranchType::Remote => None,
+        };
+        let commit = repo.find_object(target, None)?.short_id()?;
+
+        branches.push(GitBranch {
+            name: name.to_string(),
+            commit: commit.as_str().unwrap_or_default().to_string(),
+            upstream,
+            is_current: branch.is_head(),
+            is_remote: kind == BranchType::Remote,
+        });
+    }
+    Ok(branches)
+}
+
+/// Check out a branch or commit
+///
+/// A name that only exists as a remote branch, such as `feature` for
+/// `origin/feature`, gets a local branch tracking it. Anything else that
+/// names a commit is checked out with a detached HEAD. Local changes that
+/// would be overwritten make the checkout fail.
+pub fn checkout(path: &str, target: &str) -> ToolResult<()> {
+    let repo = open(path)?;
+    let switch = |object: &git2::Object, refname: Option<&str>| -> ToolResult<()> {
+        repo.checkout_tree(object, Some(CheckoutBuilder::new().safe()))?;
+        match refname {
+            Some(refname) => repo.set_head(refname)?,
+            None => repo.set_head_detached(object.id())?,
+        }
+        Ok(())
+    };
+
+    if let Ok(branch) = repo.find_branch(target, BranchType::Local) {
+        let refname = branch.get().name().unwrap_or_default().to_string();
+        return switch(&branch.get().peel(ObjectType::Commit)?, Some(&refname));
+    }
+
+    let remote_branch = repo
+        .branches(Some(BranchType::Remote))?
+        .flatten()
+        .map(|(branch, _)| branch)
+        .find(|branch| {
+            branch
+                .name()
+                .ok()
+                .flatten()
+                .and_then(|n| n.split_once('/'))
+                .is_some_and(|(_, name)| name == target)
+        });
+    if let Some(remote_branch) = remote_branch {
+        let commit = remote_branch.get().peel_to_commit()?;
+        let mut local = repo.branch(target, &commit, false)?;
+        local.set_upstream(remote_branch.name()?)?;
+        let refname = local.get().name().unwrap_or_default().to_string();
+        return switch(commit.as_object(), Some(&refname));
+    }
+
+    let commit = repo.revparse_single(target)?.peel(ObjectType::Commit)?;
+    switch(&commit, None)
+}
+
+/// Create a branch at HEAD, optionally checking it out
+pub fn create_branch(path: &str, name: &str, checkout_branch: bool) -> ToolResult<()> {
+    let repo = open(path)?;
+    let head = repo.head()?.peel_to_commit()?;
+    repo.branch(name, &head, false)?;
+    if checkout_branch {
+        checkout(path, name)?;
+    }
+    Ok(())
+}
+
+/// Delete a local branch
+///
+/// # Arguments
+/// * `force` - Delete it even if neither HEAD nor its upstream contains its
+///   commits, like `git branch -D`
+pub fn delete_branch(path: &str, name: &str, force: bool) -> ToolResult<()> {
+    let repo = open(path)?;
+    let mut branch = repo.find_branch(name, BranchType::Local)?;
+    if branch.is_head() {
+        return Err(ToolError::ExecutionFailed(format!(
+            "{} is checked out; check out another branch first",
+            name
+        )));
+    }
+    if !force {
+        let tip = branch.get().peel_to_commit()?.id();
+        let contains_tip = |oid: Option<Oid>| -> ToolResult<bool> {
+            Ok(match oid {
+                Some(oid) => oid == tip || repo.graph_descendant_of(oid, tip)?,
+                None => false,
+            })
+        };
+        let head = repo.head().ok().and_then(|head| head.target());
+        let upstream = branch.upstream().ok().and_then(|u| u.get().target());
+        if !contains_tip(head)? && !contains_tip(upstream)? {
+            return Err(ToolError::ExecutionFailed(format!(
+                "{} isn't fully merged; force to delete it anyway",
+                name
+            )));
+        }
+    }
+    branch.delete()?;
+    Ok(())
+}
+
+/// Rename a local branch, keeping its upstream and HEAD if it's checked out
+pub fn rename_branch(path: &str, old_name: &str, new_name: &str) -> ToolResult<()> {
+    let repo = open(path)?;
+    let mut branch = repo.find_branch(old_name, BranchType::Local)?;
+    branch.rename(new_name, false)?;
+    Ok(())
+}
+
+/// Set or clear the remote branch a local branch tracks
+///
+/// # Arguments
+/// * `branch` - The local branch; the current branch if `None`
+/// * `upstream` - A remote-tracking branch such as `origin/main`, or `None`
+///   to stop tracking
+pub fn set_upstream(path: &str, branch: Option<&str>, upstream: Option<&str>) -> ToolResult<()> {
+    let repo = open(path)?;
+    let name = match branch {
+        Some(branch) => branch.to_string(),
+        None => head_branch(&repo).ok_or_else(|| {
+            ToolError::ExecutionFailed("HEAD is detached; name a branch".to_string())
+        })?,
+    };
+    repo.find_branch(&name, BranchType::Local)?.set_upstream(upstream)?;
+    Ok(())
+}
+
+/// The ways to authenticate with a remote, in the order they're tried
+fn credential_sources(credentials: &GitCredentials) -> VecDeque<CredentialSource> {
+    let mut sources = VecDeque::new();
+    if let Some(key) = &credentials.ssh_key_path {
+        sources.push_back(CredentialSource::KeyFile(PathBuf::from(key)));
+    }
+    sources.push_back(CredentialSource::Agent);
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    if let Some(ssh_dir) = home.map(|home| PathBuf::from(home).join(".ssh")) {
+        sources.extend(
+            DEFAULT_SSH_KEYS
+                .iter()
+                .map(|name| ssh_dir.join(name))
+                .filter(|key| key.exists())
+                .map(CredentialSource::KeyFile),
+        );
+    }
+    if let Some(token) = &credentials.token {
+        sources.push_back(CredentialSource::Token(token.clone()));
+    }
+    sources.push_back(CredentialSource::Helper);
+    sources
+}
+
+/// Callbacks that try each credential source once, then give up instead of
+/// asking the remote again forever
+///
+/// # Arguments
+/// * `config` - Git config to find the credential helper in
+fn remote_callbacks(
+    config: git2::Config,
+    credentials: &GitCredentials,
+) -> ToolResult<(RemoteCallbacks<'static>, AuthTracker)> {
+    let mut sources = credential_sources(credentials);
+    let credentials = credentials.clone();
+    let tracker = AuthTracker::default();
+    let failed = tracker.clone();
+
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        let user = credentials.username.as_deref().or(username).unwrap_or("git");
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(user);
+        }
+        let ssh = allowed.contains(CredentialType::SSH_KEY);
+        let https = allowed.contains(CredentialType::USER_PASS_PLAINTEXT);
+        let passphrase = credentials.ssh_passphrase.as_deref();
+
+        while let Some(source) = sources.pop_front() {
+            let cred = match source {
+                CredentialSource::KeyFile(key) if ssh => {
+                    Cred::ssh_key(user, None, &key, passphrase)
+                }
+                CredentialSource::Agent if ssh => Cred::ssh_key_from_agent(user),
+                CredentialSource::Token(token) if https => Cred::userpass_plaintext(user, &token),
+                CredentialSource::Helper if https => {
+                    Cred::credential_helper(&config, url, username)
+                }
+                _ => continue,
+            };
+            if let Ok(cred) = cred {
+                return Ok(cred);
+            }
+        }
+
+        if let Ok(mut failed) = failed.0.lock() {
+            *failed = Some((url.to_string(), if ssh { "ssh" } else { "https" }));
+        }
+        Err(git2::Error::from_str("Authentication required"))
+    });
+    Ok((callbacks, tracker))
+}
+
+fn fetch_options(
+    config: git2::Config,
+    credentials: &GitCredentials,
+) -> ToolResult<(FetchOptions<'static>, AuthTracker)> {
+    let (callbacks, tracker) = remote_callbacks(config, credentials)?;
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks).prune(FetchPrune::On);
+    Ok((options, tracker))
+}
+
+/// The current branch and its upstream, as full ref names
+fn tracked_branch(repo: &Repository) -> ToolResult<(String, Option<String>)> {
+    let name = current_branch(repo);
+    if name.is_empty() || repo.head().is_err() {
+        return Err(ToolError::ExecutionFailed(
+            "Not on a branch with commits".to_string(),
+        ));
+    }
+    let upstream = repo
+        .find_branch(&name, BranchType::Local)?
+        .upstream()
+        .ok()
+        .and_then(|u| u.get().name().map(str::to_string));
+    Ok((format!("refs/heads/{}", name), upstream))
+}
+
+/// Fetch depth that fetches the rest of a shallow repository's history
+const UNSHALLOW_DEPTH: i32 = i32::MAX;
+
+/// libgit2's fetch depth for a limit on history
+fn history_depth(depth: u32) -> i32 {
+    // Anything larger would ask to unshallow
+    depth.min(UNSHALLOW_DEPTH as u32 - 1) as i32
+}
+
+/// Fetch from every remote with a history depth; 0 for no limit
+fn fetch_remotes(path: &str, credentials: &GitCredentials, depth: i32) -> ToolResult<String> {
+    let repo = open(path)?;
+    let mut fetched = Vec::new();
+    for name in repo.remotes()?.iter().flatten() {
+        let mut remote = repo.find_remote(name)?;
+        let (mut options, auth) = fetch_options(repo.config()?, credentials)?;
+        options.depth(depth);
+        remote
+            .fetch(&[] as &[&str], Some(&mut options), None)
+            .map_err(|e| auth.error(e))?;
+        fetched.push(format!("Fetched {}", name));
+    }
+    Ok(fetched.join("\n"))
+}
+
+/// Fetch from every remote, pruning deleted branches
+///
+/// Fails with `AuthRequired` when a remote rejects every credential tried;
+/// call again with `credentials` from the user.
+///
+/// # Arguments
+/// * `depth` - Only fetch this many commits of each branch's history, like
+///   `git fetch --depth`
+pub fn fetch(path: &str, credentials: &GitCredentials, depth: Option<u32>) -> ToolResult<String> {
+    fetch_remotes(path, credentials, depth.map_or(0, history_depth))
+}
+
+/// Fetch the full history of a shallow clone, like `git fetch --unshallow`
+pub fn unshallow(path: &str, credentials: &GitCredentials) -> ToolResult<String> {
+    if !open(path)?.is_shallow() {
+        return Err(ToolError::InvalidArgument(
+            "The repository already has its full history".to_string(),
+        ));
+    }
+    fetch_remotes(path, credentials, UNSHALLOW_DEPTH)
+}
+
+/// Clone a repository into a new directory
+///
+/// Partial clones (`--filter=blob:none`) aren't available, as libgit2 can't
+/// make them; a shallow clone with `depth` is the nearest option, and can be
+/// completed later with [`unshallow`].
+///
+/// Fails with `AuthRequired` when the remote rejects every credential tried.
+pub fn clone(
+    url: &str,
+    destination: &str,
+    options: &CloneOptions,
+    credentials: &GitCredentials,
+) -> ToolResult<()> {
+    let (mut fetch, auth) = fetch_options(git2::Config::open_default()?, credentials)?;
+    if let Some(depth) = options.depth {
+        fetch.depth(history_depth(depth));
+    }
+    let mut builder = git2::build::RepoBuilder::new();
+    builder.fetch_options(fetch);
+    if let Some(branch) = &options.branch {
+        builder.branch(branch);
+    }
+    builder
+        .clone(url, Path::new(destination))
+        .map_err(|e| auth.error(e))?;
+    Ok(())
+}
+
+/// Fetch the current branch's upstream and fast-forward to it
+///
+/// Branches that have diverged aren't merged; that is left to the user.
+pub fn pull(path: &str, credentials: &GitCredentials) -> ToolResult<String> {
+    let repo = open(path)?;
+    let (local_ref, upstream_ref) = tracked_branch(&repo)?;
+    let upstream_ref = upstream_ref.ok_or_else(|| {
+        ToolError::ExecutionFailed(format!("{} has no upstream branch", local_ref))
+    })?;
+    let remote_name = repo.branch_remote_name(&upstream_ref)?;
+    let mut remote = repo.find_remote(remote_name.as_str().unwrap_or("origin"))?;
+    let (mut options, auth) = fetch_options(repo.config()?, credentials)?;
+    remote
+        .fetch(&[] as &[&str], Some(&mut options), None)
+        .map_err(|e| auth.error(e))?;
+
+    let upstream = repo.refname_to_id(&upstream_ref)?;
+    let (analysis, _) = repo.merge_analysis(&[&repo.find_annotated_commit(upstream)?])?;
+    if analysis.is_up_to_date() {
+        return Ok("Already up to date.".to_string());
+    }
+    if !analysis.is_fast_forward() {
+        return Err(ToolError::ExecutionFailed(format!(
+            "{} and {} have diverged; merge or rebase them first",
+            local_ref.trim_start_matches("refs/heads/"),
+            upstream_ref.trim_start_matches("refs/remotes/")
+        )));
+    }
+
+    let target = repo.find_object(upstream, None)?;
+    repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))?;
+    repo.find_reference(&local_ref)?
+        .set_target(upstream, "pull: fast-forward")?;
+    Ok(format!(
+        "Fast-forwarded {} to {}",
+        local_ref.trim_start_matches("refs/heads/"),
+        target.short_id()?.as_str().unwrap_or_default()
+    ))
+}
+
+/// Push the current branch to its upstream
+///
+/// # Arguments
+/// * `set_upstream` - Push a branch without an upstream to `origin` and
+///   track it there
+pub fn push(path: &str, set_upstream: bool, credentials: &GitCredentials) -> ToolResult<String> {
+    let repo = open(path)?;
+    let (local_ref, upstream_ref) = tracked_branch(&repo)?;
+    let name = local_ref.trim_start_matches("refs/heads/").to_string();
+
+    let (remote_name, remote_ref) = match &upstream_ref {
+        Some(upstream_ref) => {
+            let remote = repo.branch_remote_name(upstream_ref)?;
+            let merge = repo.branch_upstream_merge(&local_ref)?;
+            (
+                remote.as_str().unwrap_or("origin").to_string(),
+                merge.as_str().unwrap_or(&local_ref).to_string(),
+            )
+        }
+        None if set_upstream => ("origin".to_string(), local_ref.clone()),
+        None => {
+            return Err(ToolError::ExecutionFailed(format!(
+                "{} has no upstream branch; push with set_upstream to create one",
+                name
+            )))
+        }
+    };
+
+    let mut rejected = None;
+    let (mut callbacks, auth) = remote_callbacks(repo.config()?, credentials)?;
+    callbacks.push_update_reference(|_, status| {
+        rejected = status.map(str::to_string);
+        Ok(())
+    });
+    let mut options = PushOptions::new();
+    options.remote_callbacks(callbacks);
+    let mut remote = repo.find_remote(&remote_name)?;
+    remote
+        .push(&[format!("{}:{}", local_ref, remote_ref)], Some(&mut options))
+        .map_err(|e| auth.error(e))?;
+    drop(options);
+    if let Some(reason) = rejected {
+        return Err(ToolError::ExecutionFailed(format!("Push rejected: {}", reason)));
+    }
+
+    if upstream_ref.is_none() {
+        repo.find_branch(&name, BranchType::Local)?
+            .set_upstream(Some(&format!("{}/{}", remote_name, name)))?;
+    }
+    Ok(format!("Pushed {} to {}", name, remote_name))
+}
+
+/// Get the URL of a remote
+pub fn remote_url(path: &str, name: &str) -> ToolResult<String> {
+    let repo = open(path)?;
+    let remote = repo.find_remote(name)?;
+    remote
+        .url()
+        .map(str::to_string)
+        .ok_or_else(|| ToolError::ExecutionFailed(format!("{} has no URL", name)))
+}
+
+/// Check if a directory is inside a git repository
+pub fn is_repository(path: &str) -> bool {
+    Repository::discover(path).is_ok()
+}
+
+/// Create a new repository
+pub fn init(path: &str) -> ToolResult<()> {
+    Repository::init(path)?;
+    Ok(())
+}
+
+/// Get a file's content at a ref
+///
+/// # Arguments
+/// * `git_ref` - A commit-ish such as `HEAD` or a hash, or `:0` (or an empty
+///   string) for the staged version; `:1` to `:3` are the base, ours and
+///   theirs versions of a conflicted file
+/// * `file_path` - Path relative to the repository root
+pub fn show_file(path: &str, git_ref: &str, file_path: &str) -> ToolResult<String> {
+    let repo = open(path)?;
+    let blob_id = if git_ref.is_empty() || git_ref.starts_with(':') {
+        let stage: i32 = match git_ref.trim_start_matches(':') {
+            "" => 0,
+            stage => stage
+                .parse()
+                .ok()
+                .filter(|stage| (0..=3).contains(stage))
+                .ok_or_else(|| ToolError::InvalidArgument(format!("Invalid stage: {}", git_ref)))?,
+        };
+        let index = repo.index()?;
+        let file = Path::new(file_path);
+        let entry = index.get_path(file, stage).ok_or_else(|| {
+            let conflicted = index.get_path(file, 2).is_some() || index.get_path(file, 3).is_some();
+            ToolError::PathNotFound(match stage {
+                0 if conflicted => format!(
+                    "{} has a merge conflict; use :1, :2 or :3 for its base, ours or theirs",
+                    file_path
+                ),
+                0 => format!("{} is not in the index", file_path),
+                // A side that deleted the file has no entry
+                _ => {
+                    let side = ["base", "ours", "theirs"][stage as usize - 1];
+                    format!("{} has no {} version", file_path, side)
+                }
+            })
+        })?;
+        entry.id
+    } else {
+        repo.revparse_single(git_ref)?
+            .peel_to_tree()?
+            .get_path(Path::new(file_path))?
+            .id()
+    };
+    let blob = repo.find_blob(blob_id)?;
+    Ok(String::from_utf8_lossy(blob.content()).to_string())
+}
+
+/// Ignore files that can apply to a path, from highest precedence to lowest,
+/// each with the directory its patterns are relative to
+fn ignore_files(repo: &Repository, workdir: &Path, relative: &Path) -> Vec<(PathBuf, PathBuf)> {
+    // A `.gitignore` in a deeper directory overrides those above it
+    let mut files: Vec<(PathBuf, PathBuf)> = relative
+        .ancestors()
+        .skip(1)
+        .map(|dir| {
+            let dir = workdir.join(dir);
+            (dir.join(".gitignore"), dir)
+        })
+        .collect();
+    files.push((repo.path().join("info").join("exclude"), workdir.to_path_buf()));
+    let global = repo
+        .config()
+        .and_then(|config| config.get_path("core.excludesFile"))
+        .ok()
+        .or_else(|| {
+            // Git's default when core.excludesFile isn't set
+            let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+                Some(dir) => PathBuf::from(dir),
+                None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+            };
+            Some(config_dir.join("git").join("ignore"))
+        });
+    if let Some(global) = global {
+        files.push((global, workdir.to_path_buf()));
+    }
+    files
+}
+
+/// The rule deciding whether a path is ignored
+struct IgnoreRule {
+    file: PathBuf,
+    /// Counting from 1; 0 if the rule couldn't be found in the file again
+    line: usize,
+    pattern: String,
+    /// Whether it ignores the path, rather than un-ignoring it
+    ignores: bool,
+}
+
+/// Find the ignore rule that decides whether a path is ignored
+///
+/// libgit2 can say whether a path is ignored, but not why, and it doesn't
+/// let a nested `.gitignore` un-ignore what a parent directory's ignores, as
+/// git does; so the ignore files are read here instead.
+fn ignore_rule(repo: &Repository, workdir: &Path, relative: &Path) -> Option<IgnoreRule> {
+    let full_path = workdir.join(relative);
+    let is_dir = full_path.is_dir();
+    for (file, base) in ignore_files(repo, workdir, relative) {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let mut builder = GitignoreBuilder::new(&base);
+        for line in content.lines() {
+            // Invalid patterns are skipped, as git does
+            let _ = builder.add_line(Some(file.clone()), line);
+        }
+        let Ok(matcher) = builder.build() else {
+            continue;
+        };
+        let (glob, ignores) = match matcher.matched_path_or_any_parents(&full_path, is_dir) {
+            Match::Ignore(glob) => (glob, true),
+            Match::Whitelist(glob) => (glob, false),
+            Match::None => continue,
+        };
+        // Later rules in a file override earlier ones
+        let line = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.trim_end() == glob.original())
+            .last()
+            .map_or(0, |(i, _)| i + 1);
+        return Some(IgnoreRule {
+            line,
+            pattern: glob.original().to_string(),
+            ignores,
+            file,
+        });
+    }
+    None
+}
+
+/// Check whether paths are ignored, and which rules ignore them
+///
+/// # Arguments
+/// * `files` - Paths relative to `path`
+pub fn check_ignore(path: &str, files: &[String]) -> ToolResult<Vec<IgnoreCheck>> {
+    let repo = open(path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| ToolError::InvalidArgument("Repository has no working tree".to_string()))?
+        .to_path_buf();
+    let index = repo.index()?;
+
+    files
+        .iter()
+        .map(|file| {
+            let relative = repo_relative(&repo, path, file);
+            let rule = ignore_rule(&repo, &workdir, Path::new(&relative));
+            let source = rule.as_ref().map(|rule| match rule.file.strip_prefix(&workdir) {
+                Ok(inside) => inside.to_string_lossy().replace('\\', "/"),
+                Err(_) => rule.file.display().to_string(),
+            });
+            Ok(IgnoreCheck {
+                tracked: index.get_path(Path::new(&relative), 0).is_some(),
+                path: relative,
+                ignored: rule.as_ref().is_some_and(|rule| rule.ignores),
+                source,
+                line: rule.as_ref().map(|rule| rule.line).filter(|&line| line > 0),
+                pattern: rule.map(|rule| rule.pattern),
+            })
+        })
+        .collect()
+}
+
+/// Add a pattern to the `.gitignore` at the repository root
+///
+/// # Returns
+/// Whether it was added; `false` if the file already had it
+pub fn add_to_gitignore(path: &str, pattern: &str) -> ToolResult<bool> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() || pattern.contains('\n') {
+        return Err(ToolError::InvalidArgument(
+            "The pattern must be a single non-empty line".to_string(),
+        ));
+    }
+    let repo = open(path)?;
+    let gitignore = repo
+        .workdir()
+        .ok_or_else(|| ToolError::InvalidArgument("Repository has no working tree".to_string()))?
+        .join(".gitignore");
+
+    let mut content = fs::read_to_string(&gitignore).unwrap_or_default();
+    if content.lines().any(|line| line.trim() == pattern) {
+        return Ok(false);
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(pattern);
+    content.push('\n');
+    fs::write(&gitignore, content)?;
+    Ok(true)
+}
+
+/// Open the main repository, even from inside a linked worktree
+fn open_main(path: &str) -> ToolResult<Repository> {
+    let repo = open(path)?;
+    if repo.is_worktree() {
+        Ok(Repository::open(repo.commondir())?)
+    } else {
+        Ok(repo)
+    }
+}
+
+/// A path without the trailing separator libgit2 gives directories
+fn display_path(path: &Path) -> String {
+    path.components()
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// The branch checked out in a repository, if HEAD isn't detached
+fn head_branch(repo: &Repository) -> Option<String> {
+    Some(current_branch(repo)).filter(|branch| !branch.is_empty())
+}
+
+fn linked_worktree(repo: &Repository, name: &str) -> ToolResult<GitWorktree> {
+    let worktree = repo.find_worktree(name)?;
+    let branch = Repository::open_from_worktree(&worktree)
+        .ok()
+        .and_then(|repo| head_branch(&repo));
+    Ok(GitWorktree {
+        name: Some(name.to_string()),
+        path: display_path(worktree.path()),
+        branch,
+        is_main: false,
+        is_locked: matches!(worktree.is_locked()?, WorktreeLockStatus::Locked(_)),
+    })
+}
+
+/// List the main working tree and every linked worktree
+pub fn worktrees(path: &str) -> ToolResult<Vec<GitWorktree>> {
+    let repo = open_main(path)?;
+    let mut list = Vec::new();
+    if let Some(workdir) = repo.workdir() {
+        list.push(GitWorktree {
+            name: None,
+            path: display_path(workdir),
+            branch: head_branch(&repo),
+            is_main: true,
+            is_locked: false,
+        });
+    }
+    for name in repo.worktrees()?.iter().flatten() {
+        list.push(linked_worktree(&repo, name)?);
+    }
+    Ok(list)
+}
+
+/// Create a linked worktree
+///
+/// # Arguments
+/// * `name` - The worktree's name
+/// * `worktree_path` - Where to create it; the directory must not exist yet
+/// * `branch` - The branch to check out, created from HEAD if it doesn't
+///   exist; defaults to `name`
+pub fn add_worktree(
+    path: &str,
+    name: &str,
+    worktree_path: &str,
+    branch: Option<&str>,
+) -> ToolResult<GitWorktree> {
+    let repo = open_main(path)?;
+    let branch_name = branch.unwrap_or(name);
+    let branch = match repo.find_branch(branch_name, BranchType::Local) {
+        Ok(branch) => branch,
+        Err(_) => repo.branch(branch_name, &repo.head()?.peel_to_commit()?, false)?,
+    };
+
+    let mut options = WorktreeAddOptions::new();
+    options.reference(Some(branch.get()));
+    repo.worktree(name, Path::new(worktree_path), Some(&options))?;
+    linked_worktree(&repo, name)
+}
+
+/// Delete a linked worktree and its directory
+///
+/// # Arguments
+/// * `force` - Remove it even if it has uncommitted changes or is locked
+pub fn remove_worktree(path: &str, name: &str, force: bool) -> ToolResult<()> {
+    let repo = open_main(path)?;
+    let worktree = repo.find_worktree(name)?;
+    if !force {
+        if let Ok(worktree_repo) = Repository::open_from_worktree(&worktree) {
+            let mut options = StatusOptions::new();
+            options.include_untracked(true).include_ignored(false);
+            if !worktree_repo.statuses(Some(&mut options))?.is_empty() {
+                return Err(ToolError::ExecutionFailed(format!(
+                    "{} has uncommitted changes; force to remove it anyway",
+                    name
+                )));
+            }
+        }
+    }
+
+    let mut options = WorktreePruneOptions::new();
+    options.valid(true).working_tree(true).locked(force);
+    worktree.prune(Some(&mut options))?;
+    Ok(())
+}
+
+fn rebase_state_path(repo: &Repository) -> PathBuf {
+    repo.path().join(REBASE_STATE_FILE)
+}
+
+fn load_rebase_state(repo: &Repository) -> ToolResult<RebaseState> {
+    match fs::read_to_string(rebase_state_path(repo)) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(_) => Err(ToolError::ExecutionFailed("No rebase in progress".to_string())),
+    }
+}
+
+fn save_rebase_state(repo: &Repository, state: &RebaseState) -> ToolResult<()> {
+    fs::write(rebase_state_path(repo), serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+/// Commit the changes a step applied to the index on top of HEAD
+fn commit_rebase_step(repo: &Repository, step: &RebaseStep, original: &Commit) -> ToolResult<()> {
+    let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+    let head = repo.head()?.peel_to_commit()?;
+    let committer = repo.signature()?;
+
+    let oid = if step.action == RebaseAction::Squash {
+        let message = step.message.clone().unwrap_or_else(|| {
+            format!(
+                "{}\n\n{}",
+                head.message().unwrap_or_default().trim_end(),
+                original.message().unwrap_or_default()
+            )
+        });
+        let message = git2::message_prettify(message, None)?;
+        let parents: Vec<Commit> = head.parents().collect();
+        let parents: Vec<&Commit> = parents.iter().collect();
+        repo.commit(None, &head.author(), &committer, &message, &tree, &parents)?
+    } else if tree.id() == head.tree_id() {
+        // The changes are already upstream; leave the commit out
+        head.id()
+    } else {
+        let message = match (&step.action, &step.message) {
+            (RebaseAction::Reword, Some(message)) => git2::message_prettify(message, None)?,
+            _ => original.message().unwrap_or_default().to_string(),
+        };
+        repo.commit(None, &original.author(), &committer, &message, &tree, &[&head])?
+    };
+    repo.set_head_detached(oid)?;
+    repo.cleanup_state()?;
+    Ok(())
+}
+
+/// Apply steps until the todo list is done or one conflicts
+fn run_rebase(repo: &Repository, mut state: RebaseState) -> ToolResult<RebaseResult> {
+    while let Some(step) = state.todo.first().cloned() {
+        if step.action != RebaseAction::Drop {
+            let original = repo.find_commit(Oid::from_str(&step.commit)?)?;
+            let head = repo.head()?.peel_to_commit()?;
+
+            if step.action == RebaseAction::Pick && original.parent_id(0).ok() == Some(head.id()) {
+                // Already on top of HEAD, so keep the commit as it is
+                repo.checkout_tree(original.as_object(), Some(CheckoutBuilder::new().safe()))?;
+                repo.set_head_detached(original.id())?;
+            } else {
+                let mut checkout = CheckoutBuilder::new();
+                checkout.safe();
+                let mut options = CherrypickOptions::new();
+                options.checkout_builder(checkout);
+                repo.cherrypick(&original, Some(&mut options))?;
+
+                let conflicts = conflicted_paths(repo)?;
+                if !conflicts.is_empty() {
+                    state.stopped = true;
+                    save_rebase_state(repo, &state)?;
+                    return Ok(RebaseResult {
+                        status: "conflicts".to_string(),
+                        current: Some(step),
+                        remaining: state.todo.len() - 1,
+                        conflicts,
+                        head: None,
+                    });
+                }
+                commit_rebase_step(repo, &step, &original)?;
+            }
+        }
+        state.todo.remove(0);
+        state.stopped = false;
+        save_rebase_state(repo, &state)?;
+    }
+
+    let head = repo.head()?.peel_to_commit()?;
+    if let Some(head_name) = &state.head_name {
+        repo.reference(head_name, head.id(), true, "rebase: finished")?;
+        repo.set_head(head_name)?;
+    }
+    fs::remove_file(rebase_state_path(repo))?;
+    Ok(RebaseResult {
+        status: "completed".to_string(),
+        current: None,
+        remaining: 0,
+        conflicts: Vec::new(),
+        head: Some(commit_info(&head)?),
+    })
+}
+
+/// Start an interactive rebase of the current branch
+///
+/// The steps run in order on top of `onto`. A step that conflicts stops the
+/// rebase with the conflicts in the working tree; resolve them with
+/// [`resolve_conflict`] and call [`rebase_continue`], or use [`rebase_skip`]
+/// or [`rebase_abort`].
+///
+/// # Arguments
+/// * `onto` - The revision to replay the commits on
+/// * `instructions` - The todo list, oldest commit first
+pub fn rebase_start(
+    path: &str,
+    onto: &str,
+    instructions: Vec<RebaseStep>,
+) -> ToolResult<RebaseResult> {
+    let repo = open(path)?;
+    if repo.state() != RepositoryState::Clean || rebase_state_path(&repo).exists() {
+        return Err(ToolError::ExecutionFailed(
+            "Another merge or rebase is in progress".to_string(),
+        ));
+    }
+    let first = instructions.iter().find(|step| step.action != RebaseAction::Drop);
+    if first.is_some_and(|step| step.action == RebaseAction::Squash) {
+        return Err(ToolError::InvalidArgument(
+            "The first commit can't be squashed into a previous one".to_string(),
+        ));
+    }
+
+    let mut options = StatusOptions::new();
+    options.include_untracked(false);
+    if !repo.statuses(Some(&mut options))?.is_empty() {
+        return Err(ToolError::ExecutionFailed(
+            "Commit or discard your changes before rebasing".to_string(),
+        ));
+    }
+
+    let mut todo = Vec::with_capacity(instructions.len());
+    for step in instructions {
+        let commit = repo.revparse_single(&step.commit)?.peel_to_commit()?;
+        if commit.parent_count() > 1 {
+            return Err(ToolError::InvalidArgument(format!(
+                "{} is a merge commit, which can't be rebased",
+                step.commit
+            )));
+        }
+        todo.push(RebaseStep {
+            commit: commit.id().to_string(),
+            ..step
+        });
+    }
+
+    let head = repo.head()?;
+    let state = RebaseState {
+        head_name: head.is_branch().then(|| head.name().map(str::to_string)).flatten(),
+        orig_head: head.peel_to_commit()?.id().to_string(),
+        todo,
+        stopped: false,
+    };
+    let onto = repo.revparse_single(onto)?.peel_to_commit()?;
+    repo.checkout_tree(onto.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    repo.set_head_detached(onto.id())?;
+    save_rebase_state(&repo, &state)?;
+    run_rebase(&repo, state)
+}
+
+/// Commit the resolved step and carry on with the rebase
+pub fn rebase_continue(path: &str) -> ToolResult<RebaseResult> {
+    let repo = open(path)?;
+    let mut state = load_rebase_state(&repo)?;
+    if state.stopped {
+        if repo.index()?.has_conflicts() {
+            return Err(ToolError::ExecutionFailed(
+                "Resolve the merge conflicts before continuing".to_string(),
+            ));
+        }
+        let step = state.todo.remove(0);
+        let original = repo.find_commit(Oid::from_str(&step.commit)?)?;
+        commit_rebase_step(&repo, &step, &original)?;
+        state.stopped = false;
+        save_rebase_state(&repo, &state)?;
+    }
+    run_rebase(&repo, state)
+}
+
+/// Leave out the step that stopped on conflicts and carry on with the rebase
+pub fn rebase_skip(path: &str) -> ToolResult<RebaseResult> {
+    let repo = open(path)?;
+    let mut state = load_rebase_state(&repo)?;
+    if state.stopped {
+        let head = repo.head()?.peel_to_commit()?;
+        repo.reset(head.as_object(), ResetType::Hard, None)?;
+        repo.cleanup_state()?;
+        state.todo.remove(0);
+        state.stopped = false;
+        save_rebase_state(&repo, &state)?;
+    }
+    run_rebase(&repo, state)
+}
+
+/// Stop the rebase and put the branch back where it was
+pub fn rebase_abort(path: &str) -> ToolResult<()> {
+    let repo = open(path)?;
+    let state = load_rebase_state(&repo)?;
+    let orig_head = repo.find_commit(Oid::from_str(&state.orig_head)?)?;
+    match &state.head_name {
+        Some(head_name) => repo.set_head(head_name)?,
+        None => repo.set_head_detached(orig_head.id())?,
+    }
+    repo.reset(orig_head.as_object(), ResetType::Hard, None)?;
+    repo.cleanup_state()?;
+    fs::remove_file(rebase_state_path(&repo))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn init_repo(path: &str) {
+        init(path).unwrap();
+        let mut config = Repository::open(path).unwrap().config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+    }
+
+    #[test]
+    fn test_status_commit_and_log() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        init_repo(path);
+
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        assert_eq!(status(path).unwrap().untracked, vec!["main.rs"]);
+        assert!(log(path, 5).unwrap().is_empty());
+
+        stage(path, &["main.rs".to_string()]).unwrap();
+        assert!(diff(path, true, None).unwrap().contains("+fn main() {}"));
+
+        let commit = commit(path, "Add main\n\nWith a body.\n").unwrap();
+        assert_eq!((commit.message.as_str(), commit.body.as_str()), ("Add main", "With a body."));
+        assert!(status(path).unwrap().is_clean);
+        assert_eq!(log(path, 5).unwrap().len(), 1);
+        assert!(super::commit(path, "Again").is_err());
+
+        // Unstaged edits, then staging, unstaging and discarding them
+        fs::write(dir.path().join("main.rs"), "fn main() { run() }\n").unwrap();
+        assert_eq!(status(path).unwrap().unstaged[0].status, "modified");
+        assert!(diff_stat(path, false).unwrap().contains("1 file changed"));
+        stage_all(path).unwrap();
+        assert_eq!(status(path).unwrap().staged[0].path, "main.rs");
+        unstage(path, &["main.rs".to_string()]).unwrap();
+        assert!(status(path).unwrap().staged.is_empty());
+        assert_eq!(show_file(path, "HEAD", "main.rs").unwrap(), "fn main() {}\n");
+        assert!(diff_refs(path, "HEAD", "", None).unwrap().contains("+fn main() { run() }"));
+        assert_eq!(diff_refs_files(path, "HEAD", "").unwrap()[0].status, "modified");
+        discard(path, "main.rs").unwrap();
+        assert!(status(path).unwrap().is_clean);
+
+        // Undoing a commit, keeping its changes and then discarding them
+        fs::write(dir.path().join("lib.rs"), "pub fn run() {}\n").unwrap();
+        stage_all(path).unwrap();
+        super::commit(path, "Add lib").unwrap();
+        assert_eq!(reset(path, "HEAD~1", ResetMode::Soft, None).unwrap().message, "Add main");
+        assert_eq!(status(path).unwrap().staged[0].path, "lib.rs");
+        reset(path, "HEAD", ResetMode::Mixed, None).unwrap();
+        assert_eq!(status(path).unwrap().untracked, vec!["lib.rs"]);
+        stage_all(path).unwrap();
+        assert!(reset(path, "HEAD", ResetMode::Hard, Some("yes")).is_err());
+        reset(path, "HEAD", ResetMode::Hard, Some(HARD_RESET_CONFIRMATION)).unwrap();
+        assert!(status(path).unwrap().is_clean);
+
+        // Undoing a commit keeps its changes in the working tree
+        fs::write(dir.path().join("lib.rs"), "pub fn run() {}\n").unwrap();
+        stage_all(path).unwrap();
+        super::commit(path, "Add lib").unwrap();
+        let undo = undo_last_operation(path, None).unwrap();
+        assert_eq!((undo.action.as_str(), undo.kept_changes), ("reset", true));
+        assert_eq!(undo.commit.message, "Add main");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("lib.rs")).unwrap(),
+            "pub fn run() {}\n"
+        );
+        assert_eq!(status(path).unwrap().untracked, vec!["lib.rs"]);
+
+        // Undoing an accidental reset and checkout from the reflog
+        stage_all(path).unwrap();
+        super::commit(path, "Add lib").unwrap();
+        reset(path, "HEAD~1", ResetMode::Hard, Some(HARD_RESET_CONFIRMATION)).unwrap();
+        assert!(reflog(path, 10).unwrap()[0].message.starts_with("reset: moving to"));
+        let undo = undo_last_operation(path, Some(HARD_RESET_CONFIRMATION)).unwrap();
+        assert_eq!((undo.action.as_str(), undo.kept_changes), ("reset", false));
+        assert_eq!(undo.commit.message, "Add lib");
+        assert!(dir.path().join("lib.rs").exists());
+        assert!(status(path).unwrap().is_clean);
+
+        let branch = status(path).unwrap().branch;
+        create_branch(path, "feature", true).unwrap();
+        assert_eq!(status(path).unwrap().branch, "feature");
+        let undo = undo_last_operation(path, None).unwrap();
+        assert_eq!((undo.action.as_str(), undo.target.as_str()), ("checkout", branch.as_str()));
+        assert_eq!(status(path).unwrap().branch, branch);
+        let entries = reflog(path, 10).unwrap();
+        assert_eq!(entries[0].index, 0);
+        assert!(entries.last().unwrap().previous.is_empty());
+        checkout(path, "feature").unwrap();
+        checkout(path, &branch).unwrap();
+        let names: Vec<String> = branches(path).unwrap().into_iter().map(|b| b.name).collect();
+        assert!(names.contains(&"feature".to_string()));
+
+        assert_eq!(format_time(git2::Time::new(0, 0)), "1970-01-01T00:00:00+00:00");
+        assert_eq!(
+            format_time(git2::Time::new(1_700_000_000, -90)),
+            "2023-11-14T20:43:20-01:30"
+        );
+    }
+
+    #[test]
+    fn test_check_ignore() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        init_repo(path);
+        fs::write(dir.path().join(".gitignore"), "# Build output\ntarget/\n*.log\n").unwrap();
+        fs::create_dir_all(dir.path().join("web")).unwrap();
+        fs::write(dir.path().join("web/.gitignore"), "!keep.log\n").unwrap();
+        fs::write(dir.path().join("tracked.log"), "kept\n").unwrap();
+        let repo = Repository::open(path).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("tracked.log")).unwrap();
+        index.write().unwrap();
+
+        let files: Vec<String> = ["target/debug/app", "web/keep.log", "tracked.log", "src/main.rs"]
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        let checks = check_ignore(path, &files).unwrap();
+        let target = &checks[0];
+        assert!(target.ignored && !target.tracked);
+        assert_eq!(
+            (target.source.as_deref(), target.line, target.pattern.as_deref()),
+            (Some(".gitignore"), Some(2), Some("target/"))
+        );
+        assert!(!checks[1].ignored);
+        assert_eq!(checks[1].source.as_deref(), Some("web/.gitignore"));
+        assert_eq!(checks[1].pattern.as_deref(), Some("!keep.log"));
+        assert!(checks[2].tracked);
+        assert_eq!(checks[2].pattern.as_deref(), Some("*.log"));
+        assert!(!checks[3].ignored && checks[3].pattern.is_none());
+
+        assert!(add_to_gitignore(path, "dist/").unwrap());
+        assert!(!add_to_gitignore(path, " dist/ ").unwrap());
+        assert!(add_to_gitignore(path, "").is_err());
+        let gitignore = fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        assert!(gitignore.ends_with("*.log\ndist/\n"));
+        let files = vec!["dist/app.js".to_string()];
+        assert!(check_ignore(path, &files).unwrap()[0].ignored);
+    }
+
+    #[test]
+    fn test_search_commits() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        init_repo(path);
+        let file = dir.path().join("lib.rs");
+        for (content, message) in [
+            ("fn helper() {}\n", "Add helper"),
+            ("fn helper() {}\nfn run() { helper() }\n", "Call the helper from run"),
+            ("fn run() {}\n", "Inline it"),
+        ] {
+            fs::write(&file, content).unwrap();
+            stage_all(path).unwrap();
+            commit(path, message).unwrap();
+        }
+
+        let messages = |search_patches| -> Vec<String> {
+            let commits = search_commits(path, "fn helper", search_patches, 10).unwrap();
+            commits.into_iter().map(|c| c.message).collect()
+        };
+        assert_eq!(messages(true), vec!["Inline it", "Add helper"]);
+        let found = search_commits(path, "HELPER", false, 10).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(search_commits(path, "helper()", true, 1).unwrap()[0].message, "Inline it");
+        assert!(search_commits(path, "", true, 10).is_err());
+    }
+
+    #[test]
+    fn test_branch_management() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        init_repo(path);
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        stage_all(path).unwrap();
+        commit(path, "Add main").unwrap();
+        let main = status(path).unwrap().branch;
+
+        create_branch(path, "feature", true).unwrap();
+        fs::write(dir.path().join("lib.rs"), "pub fn run() {}\n").unwrap();
+        stage_all(path).unwrap();
+        commit(path, "Add lib").unwrap();
+        assert!(delete_branch(path, "feature", true).is_err());
+        checkout(path, &main).unwrap();
+        assert!(delete_branch(path, "feature", false).is_err());
+        delete_branch(path, "feature", true).unwrap();
+
+        create_branch(path, "topic", false).unwrap();
+        delete_branch(path, "topic", false).unwrap();
+        create_branch(path, "old-name", true).unwrap();
+        rename_branch(path, "old-name", "new-name").unwrap();
+        assert_eq!(status(path).unwrap().branch, "new-name");
+
+        let repo = Repository::open(path).unwrap();
+        repo.remote("origin", "https://example.com/repo.git").unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.reference("refs/remotes/origin/main", head.id(), false, "test").unwrap();
+        set_upstream(path, None, Some("origin/main")).unwrap();
+        let upstream = |name: &str| {
+            let branches = branches(path).unwrap();
+            branches.into_iter().find(|b| b.name == name).unwrap().upstream
+        };
+        assert_eq!(upstream("new-name").as_deref(), Some("origin/main"));
+        set_upstream(path, Some("new-name"), None).unwrap();
+        assert_eq!(upstream("new-name"), None);
+    }
+
+    #[test]
+    fn test_merge_conflicts() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        init_repo(path);
+        let file = dir.path().join("notes.txt");
+
+        fs::write(&file, "base\n").unwrap();
+        stage_all(path).unwrap();
+        commit(path, "Base").unwrap();
+        let main = status(path).unwrap().branch;
+
+        create_branch(path, "feature", true).unwrap();
+        fs::write(&file, "theirs\n").unwrap();
+        stage_all(path).unwrap();
+        commit(path, "Feature").unwrap();
+        checkout(path, &main).unwrap();
+        assert_eq!(merge(path, &main).unwrap().status, "up_to_date");
+
+        fs::write(&file, "ours\n").unwrap();
+        stage_all(path).unwrap();
+        commit(path, "Main").unwrap();
+
+        let result = merge(path, "feature").unwrap();
+        assert_eq!(result.status, "conflicts");
+        assert_eq!(result.conflicts, vec!["notes.txt"]);
+        assert!(merge(path, "feature").is_err());
+        assert!(commit(path, "Merge").is_err());
+
+        let conflict = &conflicts(path).unwrap()[0];
+        assert_eq!(conflict.base.as_deref(), Some("base\n"));
+        let versions = merge_file_versions(path, "notes.txt").unwrap();
+        assert_eq!(versions.theirs.as_deref(), Some("theirs\n"));
+        assert!(merge_file_versions(path, "other.txt").is_err());
+        assert_eq!(show_file(path, ":1", "notes.txt").unwrap(), "base\n");
+        assert_eq!(show_file(path, ":2", "notes.txt").unwrap(), "ours\n");
+        assert_eq!(show_file(path, ":3", "notes.txt").unwrap(), "theirs\n");
+        assert!(show_file(path, ":4", "notes.txt").is_err());
+        assert!(show_file(path, ":0", "notes.txt").unwrap_err().to_string().contains(":2"));
+        assert_eq!(conflict.ours.as_deref(), Some("ours\n"));
+        assert_eq!(conflict.theirs.as_deref(), Some("theirs\n"));
+
+        resolve_conflict(path, "notes.txt", ConflictResolution::Theirs).unwrap();
+        assert!(conflicts(path).unwrap().is_empty());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "theirs\n");
+
+        let merged = commit(path, "Merge feature").unwrap();
+        let repo = Repository::open(path).unwrap();
+        assert_eq!(repo.head().unwrap().peel_to_commit().unwrap().parent_count(), 2);
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.tag_lightweight("v1", head.as_object(), false).unwrap();
+
+        let graph = log_graph(path, 10).unwrap();
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph[0].commit.hash, merged.hash);
+        assert_eq!(graph[0].parents.len(), 2);
+        let labels: Vec<(&str, &str, bool)> = graph[0]
+            .refs
+            .iter()
+            .map(|r| (r.name.as_str(), r.kind.as_str(), r.is_head))
+            .collect();
+        assert!(labels.contains(&(main.as_str(), "branch", true)));
+        assert!(labels.contains(&("v1", "tag", false)));
+        let feature = graph.iter().find(|c| c.commit.message == "Feature").unwrap();
+        assert_eq!(feature.refs[0].name, "feature");
+        assert!(graph.last().unwrap().parents.is_empty());
+        assert_eq!(repo.state(), RepositoryState::Clean);
+        assert!(status(path).unwrap().is_clean);
+    }
+
+    #[test]
+    fn test_interactive_rebase() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        init_repo(path);
+        let file = dir.path().join("notes.txt");
+        let commit_file = |content: &str, message: &str| {
+            fs::write(&file, content).unwrap();
+            stage_all(path).unwrap();
+            commit(path, message).unwrap().hash
+        };
+
+        let base = commit_file("base\n", "Base");
+        let main = status(path).unwrap().branch;
+        create_branch(path, "feature", true).unwrap();
+        let one = commit_file("base\none\n", "One");
+        let two = commit_file("base\none\ntwo\n", "Two");
+        let three = commit_file("base\none\ntwo\nthree\n", "Three");
+
+        let step = |action, commit: &str, message: Option<&str>| RebaseStep {
+            action,
+            commit: commit.to_string(),
+            message: message.map(str::to_string),
+        };
+        let result = rebase_start(
+            path,
+            &base,
+            vec![
+                step(RebaseAction::Reword, &one, Some("First")),
+                step(RebaseAction::Squash, &two, None),
+                step(RebaseAction::Drop, &three, None),
+            ],
+        )
+        .unwrap();
+        assert_eq!(result.status, "completed");
+        let messages: Vec<String> = log(path, 5).unwrap().into_iter().map(|c| c.message).collect();
+        assert_eq!(messages, vec!["First", "Base"]);
+        assert_eq!(log(path, 1).unwrap()[0].body, "Two");
+        assert_eq!(status(path).unwrap().branch, "feature");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "base\none\ntwo\n");
+
+        // Conflicting with a change on the main branch
+        checkout(path, &main).unwrap();
+        commit_file("base\nmain\n", "Main");
+        checkout(path, "feature").unwrap();
+        let feature = log(path, 1).unwrap()[0].hash.clone();
+        let changed = diff_refs_files(path, &main, "feature").unwrap();
+        assert_eq!(changed[0].path, "notes.txt");
+        assert_eq!(changed[0].status, "modified");
+        assert!(diff_refs(path, &main, "feature", Some("notes.txt")).unwrap().contains("-main"));
+        let result =
+            rebase_start(path, &main, vec![step(RebaseAction::Pick, &feature, None)]).unwrap();
+        assert_eq!(result.status, "conflicts");
+        assert_eq!(result.conflicts, vec!["notes.txt"]);
+        assert!(rebase_start(path, &main, Vec::new()).is_err());
+        assert!(rebase_continue(path).is_err());
+
+        rebase_abort(path).unwrap();
+        assert_eq!(log(path, 1).unwrap()[0].hash, feature);
+        assert!(status(path).unwrap().is_clean);
+
+        rebase_start(path, &main, vec![step(RebaseAction::Pick, &feature, None)]).unwrap();
+        resolve_conflict(path, "notes.txt", ConflictResolution::Custom("merged\n".into()))
+            .unwrap();
+        assert_eq!(rebase_continue(path).unwrap().status, "completed");
+        let messages: Vec<String> = log(path, 5).unwrap().into_iter().map(|c| c.message).collect();
+        assert_eq!(messages, vec!["First", "Main", "Base"]);
+
+        // Paging and filtering the history
+        let filtered = |filter: LogFilter| -> Vec<String> {
+            let commits = log_filtered(path, 5, &filter).unwrap();
+            commits.into_iter().map(|c| c.message).collect()
+        };
+        let page = |skip| LogFilter {
+            skip,
+            ..Default::default()
+        };
+        assert_eq!(filtered(page(1)), vec!["Main", "Base"]);
+        assert_eq!(log_filtered(path, 1, &page(1)).unwrap()[0].message, "Main");
+        let grep = LogFilter {
+            grep: Some("MAIN".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(filtered(grep), vec!["Main"]);
+        let author = LogFilter {
+            author: Some("nobody".to_string()),
+            ..Default::default()
+        };
+        assert!(filtered(author).is_empty());
+        let branch = LogFilter {
+            branch: Some(main.clone()),
+            path: Some("notes.txt".to_string()),
+            until: Some(i64::MAX),
+            ..Default::default()
+        };
+        assert_eq!(filtered(branch), vec!["Main", "Base"]);
+        let since = LogFilter {
+            since: Some(i64::MAX),
+            ..Default::default()
+        };
+        assert!(filtered(since).is_empty());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "merged\n");
+        assert!(status(path).unwrap().is_clean);
+    }
+
+    #[test]
+    fn test_clone() {
+        let dir = tempdir().unwrap();
+        let origin = dir.path().join("origin");
+        let origin_path = origin.to_str().unwrap();
+        init_repo(origin_path);
+        fs::write(origin.join("main.rs"), "fn main() {}\n").unwrap();
+        stage_all(origin_path).unwrap();
+        commit(origin_path, "Add main").unwrap();
+        create_branch(origin_path, "feature", false).unwrap();
+
+        let copy = dir.path().join("copy");
+        let copy_path = copy.to_str().unwrap();
+        let options = CloneOptions {
+            branch: Some("feature".to_string()),
+            ..Default::default()
+        };
+        clone(origin_path, copy_path, &options, &GitCredentials::default()).unwrap();
+        assert_eq!(status(copy_path).unwrap().branch, "feature");
+        assert!(copy.join("main.rs").exists());
+        assert!(fetch(copy_path, &GitCredentials::default(), None).unwrap().contains("origin"));
+        assert!(unshallow(copy_path, &GitCredentials::default()).is_err());
+        assert_eq!(history_depth(1), 1);
+        assert_eq!(history_depth(u32::MAX), UNSHALLOW_DEPTH - 1);
+    }
+
+    #[test]
+    fn test_worktrees() {
+        let dir = tempdir().unwrap();
+        let repo_dir = dir.path().join("repo");
+        fs::create_dir(&repo_dir).unwrap();
+        let path = repo_dir.to_str().unwrap();
+        init_repo(path);
+        fs::write(repo_dir.join("main.rs"), "fn main() {}\n").unwrap();
+        stage_all(path).unwrap();
+        commit(path, "Add main").unwrap();
+
+        let worktree_dir = dir.path().join("refactor");
+        let worktree_path = worktree_dir.to_str().unwrap();
+        let worktree = add_worktree(path, "refactor", worktree_path, None).unwrap();
+        assert_eq!(worktree.branch.as_deref(), Some("refactor"));
+        assert!(worktree_dir.join("main.rs").exists());
+
+        // Listing from inside the linked worktree finds the main one too
+        let list = worktrees(worktree_path).unwrap();
+        assert_eq!(list.len(), 2);
+        assert!(list[0].is_main);
+        assert_eq!(list[1].name.as_deref(), Some("refactor"));
+
+        fs::write(worktree_dir.join("main.rs"), "fn main() { risky() }\n").unwrap();
+        assert!(remove_worktree(path, "refactor", false).is_err());
+        remove_worktree(path, "refactor", true).unwrap();
+        assert!(!worktree_dir.exists());
+        assert_eq!(worktrees(path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_credential_sources() {
+        let credentials = GitCredentials {
+            token: Some("secret".to_string()),
+            ssh_key_path: Some("/keys/deploy".to_string()),
+            ..Default::default()
+        };
+        let sources = credential_sources(&credentials);
+        assert!(matches!(&sources[0], CredentialSource::KeyFile(key) if key.ends_with("deploy")));
+        assert!(matches!(&sources[1], CredentialSource::Agent));
+        assert!(matches!(sources.back(), Some(CredentialSource::Helper)));
+
+        // Running out of credentials is reported as needing them
+        let tracker = AuthTracker::default();
+        let error = git2::Error::from_str("Authentication required");
+        assert!(matches!(tracker.error(error), ToolError::Git(_)));
+        *tracker.0.lock().unwrap() = Some(("https://example.com/repo.git".to_string(), "https"));
+        let error = git2::Error::from_str("Authentication required");
+        assert!(matches!(
+            tracker.error(error),
+            ToolError::AuthRequired { method, .. } if method == "https"
+        ));
+    }
+}
@@ -0,0 +1,326 @@
+//! Git hooks for commits
+//!
+//! libgit2 doesn't run hooks, so commits made through [`super::git`] run them
+//! here: the repository's `pre-commit` and `commit-msg` hooks, with a
+//! configured command (such as `pre-commit run`) taking the place of the
+//! `pre-commit` hook when one is set. Hook output is passed on line by line as
+//! it's written, and a failing hook stops the commit with its full result.
+
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use git2::Repository;
+use serde::Serialize;
+
+use super::command::{kill_tree, shell_command, OUTPUT_DRAIN_TIMEOUT};
+use super::git::{self, GitCommit};
+use super::{ToolError, ToolResult};
+
+/// Time a hook may run before it is killed
+pub const HOOK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// File in the git directory the commit message is written to for hooks
+const COMMIT_MESSAGE_FILE: &str = "COMMIT_EDITMSG";
+
+/// Result of running a hook
+#[derive(Debug, Clone, Serialize)]
+pub struct HookResult {
+    /// "pre-commit" or "commit-msg"
+    pub hook: String,
+    /// The hook script or configured command that ran
+    pub command: String,
+    /// Exit code, or `None` if the hook was killed
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// stdout and stderr, interleaved as they were written
+    pub output: String,
+    pub duration_ms: u64,
+}
+
+impl HookResult {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Where to find the repository's hooks: `core.hooksPath`, or `.git/hooks`
+///
+/// Linked worktrees share the main repository's hooks.
+pub fn hooks_dir(repo: &Repository) -> ToolResult<PathBuf> {
+    Ok(match repo.config()?.get_path("core.hooksPath") {
+        Ok(dir) if dir.is_relative() => repo.workdir().unwrap_or(repo.path()).join(dir),
+        Ok(dir) => dir,
+        Err(_) => repo.commondir().join("hooks"),
+    })
+}
+
+/// A hook script, if the repository has one that can run
+fn find_hook(repo: &Repository, name: &str) -> ToolResult<Option<PathBuf>> {
+    let hook = hooks_dir(repo)?.join(name);
+    if !hook.is_file() {
+        return Ok(None);
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // Git skips hooks that aren't executable
+        if hook.metadata()?.permissions().mode() & 0o111 == 0 {
+            return Ok(None);
+        }
+    }
+    Ok(Some(hook))
+}
+
+/// Send each line a stream writes to a channel
+fn forward_lines<R: Read + Send + 'static>(stream: Option<R>, lines: mpsc::Sender<String>) {
+    if let Some(stream) = stream {
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                if lines.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// Run a hook, passing each line of its output to `on_line` as it's written
+fn run_hook(
+    hook: &str,
+    mut command: Command,
+    description: String,
+    cwd: &Path,
+    on_line: &mut dyn FnMut(&str, &str),
+) -> ToolResult<HookResult> {
+    let start = Instant::now();
+    let mut child = command
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to start {} hook: {}", hook, e)))?;
+
+    let (sender, lines) = mpsc::channel();
+    forward_lines(child.stdout.take(), sender.clone());
+    forward_lines(child.stderr.take(), sender);
+
+    let mut output = String::new();
+    let mut timed_out = false;
+    // The hook's exit status, and when it was seen
+    let mut exited = None;
+    loop {
+        match lines.recv_timeout(Duration::from_millis(50)) {
+            Ok(line) => {
+                on_line(hook, &line);
+                output.push_str(&line);
+                output.push('\n');
+            }
+            // Both streams closed; the hook has exited or is about to
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+        }
+        if exited.is_none() {
+            exited = child.try_wait()?.map(|status| (status, Instant::now()));
+        }
+        match exited {
+            // Processes the hook left in the background may hold its output
+            // open; they don't decide its result
+            Some((_, at)) if at.elapsed() >= OUTPUT_DRAIN_TIMEOUT => break,
+            None if start.elapsed() >= HOOK_TIMEOUT => {
+                kill_tree(&mut child);
+                let _ = child.wait();
+                timed_out = true;
+                break;
+            }
+            _ => {}
+        }
+    }
+    let status = match exited {
+        Some((status, _)) => Some(status),
+        None if timed_out => None,
+        None => Some(child.wait()?),
+    };
+
+    Ok(HookResult {
+        hook: hook.to_string(),
+        command: description,
+        exit_code: status.and_then(|s| s.code()),
+        timed_out,
+        output,
+        duration_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// The command that runs a hook script
+fn hook_command(hook: &Path, args: &[&str]) -> Command {
+    // Hooks are shell scripts, which Windows can't run directly
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("sh");
+        command.arg(hook);
+        command
+    } else {
+        Command::new(hook)
+    };
+    command.args(args);
+    command
+}
+
+/// Run the pre-commit hook, or `configured_command` in its place
+///
+/// # Returns
+/// The hook's result, or `None` if there is nothing to run
+pub fn run_pre_commit(
+    path: &str,
+    configured_command: Option<&str>,
+    on_line: &mut dyn FnMut(&str, &str),
+) -> ToolResult<Option<HookResult>> {
+    let repo = Repository::discover(path)?;
+    let workdir = repo.workdir().unwrap_or(repo.path()).to_path_buf();
+    let (command, description) = match configured_command.filter(|c| !c.trim().is_empty()) {
+        Some(configured) => (shell_command(configured), configured.to_string()),
+        None => match find_hook(&repo, "pre-commit")? {
+            Some(hook) => (hook_command(&hook, &[]), hook.display().to_string()),
+            None => return Ok(None),
+        },
+    };
+    run_hook("pre-commit", command, description, &workdir, on_line).map(Some)
+}
+
+/// Run the commit-msg hook, which may check or rewrite the message
+///
+/// # Returns
+/// The hook's result, if there is a hook, and the message to commit
+pub fn run_commit_msg(
+    path: &str,
+    message: &str,
+    on_line: &mut dyn FnMut(&str, &str),
+) -> ToolResult<(Option<HookResult>, String)> {
+    let repo = Repository::discover(path)?;
+    let Some(hook) = find_hook(&repo, "commit-msg")? else {
+        return Ok((None, message.to_string()));
+    };
+    let workdir = repo.workdir().unwrap_or(repo.path()).to_path_buf();
+    let message_file = repo.path().join(COMMIT_MESSAGE_FILE);
+    std::fs::write(&message_file, message)?;
+
+    let file_arg = message_file.to_string_lossy().to_string();
+    let command = hook_command(&hook, &[&file_arg]);
+    let result = run_hook(
+        "commit-msg",
+        command,
+        hook.display().to_string(),
+        &workdir,
+        on_line,
+    )?;
+    let message = std::fs::read_to_string(&message_file)?;
+    Ok((Some(result), message))
+}
+
+/// Commit staged changes, running the pre-commit and commit-msg hooks first
+///
+/// # Arguments
+/// * `no_verify` - Skip the hooks, like `git commit --no-verify`
+/// * `pre_commit_command` - Run this instead of the pre-commit hook
+/// * `on_line` - Called with the hook name and each line of hook output
+///
+/// # Returns
+/// The commit; a failing hook stops the commit with `ToolError::HookFailed`
+pub fn commit_with_hooks(
+    path: &str,
+    message: &str,
+    no_verify: bool,
+    pre_commit_command: Option<&str>,
+    on_line: &mut dyn FnMut(&str, &str),
+) -> ToolResult<GitCommit> {
+    if no_verify {
+        return git::commit(path, message);
+    }
+    if let Some(result) = run_pre_commit(path, pre_commit_command, on_line)? {
+        if !result.success() {
+            return Err(ToolError::HookFailed(Box::new(result)));
+        }
+    }
+    let (result, message) = run_commit_msg(path, message, on_line)?;
+    if let Some(result) = result.filter(|r| !r.success()) {
+        return Err(ToolError::HookFailed(Box::new(result)));
+    }
+    git::commit(path, &message)
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    fn write_hook(dir: &Path, name: &str, script: &str) {
+        let hook = dir.join(".git/hooks").join(name);
+        fs::write(&hook, script).unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn test_commit_runs_hooks() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        git::init(path).unwrap();
+        let mut config = Repository::open(path).unwrap().config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        git::stage_all(path).unwrap();
+
+        write_hook(
+            dir.path(),
+            "pre-commit",
+            "#!/bin/sh\necho checking\necho 'lint failed' >&2\nexit 1\n",
+        );
+        let mut lines = Vec::new();
+        let error = commit_with_hooks(path, "Add main", false, None, &mut |hook, line| {
+            lines.push(format!("{}: {}", hook, line))
+        })
+        .unwrap_err();
+        let ToolError::HookFailed(result) = error else {
+            panic!("expected a hook failure, got {}", error);
+        };
+        assert_eq!(
+            (result.hook.as_str(), result.exit_code),
+            ("pre-commit", Some(1))
+        );
+        assert!(result.output.contains("lint failed"));
+        assert!(lines.contains(&"pre-commit: checking".to_string()));
+
+        // A configured command replaces the hook
+        write_hook(
+            dir.path(),
+            "commit-msg",
+            "#!/bin/sh\necho 'Signed-off-by: Test' >> \"$1\"\n",
+        );
+        let commit =
+            commit_with_hooks(path, "Add main\n\n", false, Some("true"), &mut |_, _| {}).unwrap();
+        assert_eq!(commit.body, "Signed-off-by: Test");
+
+        fs::write(dir.path().join("main.rs"), "fn main() { run() }\n").unwrap();
+        git::stage_all(path).unwrap();
+        let commit = commit_with_hooks(path, "Skip hooks", true, None, &mut |_, _| {}).unwrap();
+        assert_eq!(
+            (commit.message.as_str(), commit.body.as_str()),
+            ("Skip hooks", "")
+        );
+
+        // A background process holding the output open doesn't hold up the hook
+        let start = Instant::now();
+        let result = run_pre_commit(path, Some("sleep 30 & echo done"), &mut |_, _| {})
+            .unwrap()
+            .unwrap();
+        assert!(result.success() && !result.timed_out);
+        assert_eq!(result.output, "done\n");
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,408 @@
+//! Lines of code statistics
+//!
+//! Counts code, comment and blank lines per language for the files in a
+//! directory, skipping those ignored by `.gitignore`. Comments are recognized
+//! by each language's comment markers; markers inside string literals aren't
+//! told apart, which is close enough for an overview of a codebase.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::{decode_text, project_walker, ToolResult};
+
+/// How a language writes comments
+struct LanguageSyntax {
+    name: &'static str,
+    extensions: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+}
+
+const C_STYLE: (&[&str], Option<(&str, &str)>) = (&["//"], Some(("/*", "*/")));
+
+const LANGUAGES: &[LanguageSyntax] = &[
+    LanguageSyntax {
+        name: "Rust",
+        extensions: &["rs"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "TypeScript",
+        extensions: &["ts", "tsx", "mts", "cts"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "JavaScript",
+        extensions: &["js", "jsx", "mjs", "cjs"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "Go",
+        extensions: &["go"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "C",
+        extensions: &["c", "h"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "C++",
+        extensions: &["cpp", "cc", "cxx", "hpp", "hh", "hxx"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "C#",
+        extensions: &["cs"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "Java",
+        extensions: &["java"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "Kotlin",
+        extensions: &["kt", "kts"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "Swift",
+        extensions: &["swift"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "Scala",
+        extensions: &["scala"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "Dart",
+        extensions: &["dart"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "PHP",
+        extensions: &["php"],
+        line_comments: &["//", "#"],
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "CSS",
+        extensions: &["css"],
+        line_comments: &[],
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "SCSS",
+        extensions: &["scss", "sass", "less"],
+        line_comments: C_STYLE.0,
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "Python",
+        extensions: &["py", "pyi"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    LanguageSyntax {
+        name: "Ruby",
+        extensions: &["rb"],
+        line_comments: &["#"],
+        block_comment: Some(("=begin", "=end")),
+    },
+    LanguageSyntax {
+        name: "Shell",
+        extensions: &["sh", "bash", "zsh", "fish"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    LanguageSyntax {
+        name: "Perl",
+        extensions: &["pl", "pm"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    LanguageSyntax {
+        name: "R",
+        extensions: &["r"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    LanguageSyntax {
+        name: "TOML",
+        extensions: &["toml"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    LanguageSyntax {
+        name: "YAML",
+        extensions: &["yml", "yaml"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    LanguageSyntax {
+        name: "Lua",
+        extensions: &["lua"],
+        line_comments: &["--"],
+        block_comment: Some(("--[[", "]]")),
+    },
+    LanguageSyntax {
+        name: "SQL",
+        extensions: &["sql"],
+        line_comments: &["--"],
+        block_comment: C_STYLE.1,
+    },
+    LanguageSyntax {
+        name: "Haskell",
+        extensions: &["hs"],
+        line_comments: &["--"],
+        block_comment: Some(("{-", "-}")),
+    },
+    LanguageSyntax {
+        name: "Elixir",
+        extensions: &["ex", "exs"],
+        line_comments: &["#"],
+        block_comment: None,
+    },
+    LanguageSyntax {
+        name: "HTML",
+        extensions: &["html", "htm"],
+        line_comments: &[],
+        block_comment: Some(("<!--", "-->")),
+    },
+    LanguageSyntax {
+        name: "XML",
+        extensions: &["xml", "svg"],
+        line_comments: &[],
+        block_comment: Some(("<!--", "-->")),
+    },
+    LanguageSyntax {
+        name: "Vue",
+        extensions: &["vue"],
+        line_comments: C_STYLE.0,
+        block_comment: Some(("<!--", "-->")),
+    },
+    LanguageSyntax {
+        name: "Svelte",
+        extensions: &["svelte"],
+        line_comments: C_STYLE.0,
+        block_comment: Some(("<!--", "-->")),
+    },
+    LanguageSyntax {
+        name: "Markdown",
+        extensions: &["md", "mdx"],
+        line_comments: &[],
+        block_comment: None,
+    },
+    LanguageSyntax {
+        name: "JSON",
+        extensions: &["json"],
+        line_comments: &[],
+        block_comment: None,
+    },
+];
+
+/// Line counts for one language
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LanguageStats {
+    pub language: String,
+    pub files: usize,
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+}
+
+impl LanguageStats {
+    /// All lines, of any kind
+    pub fn lines(&self) -> usize {
+        self.code + self.comments + self.blanks
+    }
+
+    fn add(&mut self, other: &LanguageStats) {
+        self.files += other.files;
+        self.code += other.code;
+        self.comments += other.comments;
+        self.blanks += other.blanks;
+    }
+}
+
+/// Line counts for a directory
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LocReport {
+    /// Per-language counts, the language with the most code first
+    pub languages: Vec<LanguageStats>,
+    /// Counts over all languages, with `language` set to "Total"
+    pub total: LanguageStats,
+}
+
+fn language_for(path: &Path) -> Option<&'static LanguageSyntax> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|l| l.extensions.contains(&ext.as_str()))
+}
+
+/// Count the code, comment and blank lines of a file's text
+fn count_lines(text: &str, syntax: &LanguageSyntax) -> LanguageStats {
+    let mut stats = LanguageStats {
+        files: 1,
+        ..Default::default()
+    };
+    let mut in_block = false;
+
+    for line in text.lines() {
+        let mut rest = line.trim();
+        if rest.is_empty() && !in_block {
+            stats.blanks += 1;
+            continue;
+        }
+
+        // A line is code if any of it is outside a comment
+        let mut has_code = false;
+        while !rest.is_empty() {
+            if in_block {
+                let end = syntax.block_comment.map_or("", |(_, end)| end);
+                match rest.find(end) {
+                    Some(i) => {
+                        in_block = false;
+                        rest = rest[i + end.len()..].trim_start();
+                    }
+                    None => rest = "",
+                }
+                continue;
+            }
+            let line_comment = syntax
+                .line_comments
+                .iter()
+                .filter_map(|marker| rest.find(marker))
+                .min();
+            let block_start = syntax
+                .block_comment
+                .and_then(|(start, _)| rest.find(start).map(|i| (i, start.len())));
+            match (line_comment, block_start) {
+                (Some(l), Some((b, _))) if l < b => {
+                    has_code |= l > 0;
+                    rest = "";
+                }
+                (_, Some((b, len))) => {
+                    has_code |= b > 0;
+                    in_block = true;
+                    rest = &rest[b + len..];
+                }
+                (Some(l), None) => {
+                    has_code |= l > 0;
+                    rest = "";
+                }
+                (None, None) => {
+                    has_code = true;
+                    rest = "";
+                }
+            }
+        }
+
+        if has_code {
+            stats.code += 1;
+        } else {
+            stats.comments += 1;
+        }
+    }
+    stats
+}
+
+/// Count lines of code per language under a directory
+///
+/// Files in languages that aren't recognized, and files that aren't text,
+/// are skipped.
+pub fn count_loc(dir: &Path) -> ToolResult<LocReport> {
+    let mut by_language: HashMap<&'static str, LanguageStats> = HashMap::new();
+
+    for entry in project_walker(dir, false).build().flatten() {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Some(syntax) = language_for(entry.path()) else {
+            continue;
+        };
+        let Some((text, _)) = fs::read(entry.path()).ok().and_then(|b| decode_text(&b)) else {
+            continue;
+        };
+        by_language
+            .entry(syntax.name)
+            .or_insert_with(|| LanguageStats {
+                language: syntax.name.to_string(),
+                ..Default::default()
+            })
+            .add(&count_lines(&text, syntax));
+    }
+
+    let mut languages: Vec<LanguageStats> = by_language.into_values().collect();
+    languages.sort_by(|a, b| b.code.cmp(&a.code).then(a.language.cmp(&b.language)));
+    let mut total = LanguageStats {
+        language: "Total".to_string(),
+        ..Default::default()
+    };
+    for stats in &languages {
+        total.add(stats);
+    }
+    Ok(LocReport { languages, total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_count_loc_by_language() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("target")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        fs::write(
+            dir.path().join("src/main.rs"),
+            "//! Docs\n\nfn main() { /* inline */\n    /* a\n       b */ run();\n}\n/* only\n comment */\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("target/gen.rs"), "fn x() {}\n").unwrap();
+        fs::write(dir.path().join("build.py"), "# build\nimport os\n\n").unwrap();
+        fs::write(dir.path().join("notes.bin"), "skipped\n").unwrap();
+
+        let report = count_loc(dir.path()).unwrap();
+        assert_eq!(
+            report.languages,
+            vec![
+                LanguageStats {
+                    language: "Rust".to_string(),
+                    files: 1,
+                    code: 3,
+                    comments: 4,
+                    blanks: 1,
+                },
+                LanguageStats {
+                    language: "Python".to_string(),
+                    files: 1,
+                    code: 1,
+                    comments: 1,
+                    blanks: 1,
+                },
+            ]
+        );
+        assert_eq!((report.total.files, report.total.lines()), (2, 11));
+    }
+}
@@ -0,0 +1,214 @@
+//! Long-term project memory
+//!
+//! The model saves durable facts about a project, such as "this repo uses
+//! pnpm, not npm", with the save_memory tool. They are kept per project in the
+//! app data directory and listed in the system prompt of later sessions.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{ToolError, ToolResult};
+
+/// Subdirectory of the app data directory holding project memories
+pub const MEMORY_DIR: &str = "memory";
+
+/// Most facts a project's memory may hold
+pub const MAX_MEMORIES: usize = 200;
+
+/// Longest fact that can be saved, in characters
+pub const MAX_MEMORY_CHARS: usize = 500;
+
+/// Most characters of memories included in the system prompt
+pub const MAX_MEMORY_PROMPT_CHARS: usize = 8_000;
+
+/// A fact remembered about a project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    pub id: String,
+    pub content: String,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+}
+
+/// A project's memory
+#[derive(Debug, Default)]
+pub struct ProjectMemory {
+    entries: Mutex<Vec<MemoryEntry>>,
+    /// Where the memory is saved; `None` keeps it in memory only
+    path: Option<PathBuf>,
+}
+
+/// Path of a project's memory under the app data directory
+///
+/// The file is named after a hash of the project's path.
+pub fn memory_path(data_dir: &Path, project_root: &Path) -> PathBuf {
+    let hash: String = Sha256::digest(project_root.to_string_lossy().as_bytes())
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    data_dir.join(MEMORY_DIR).join(format!("{}.json", hash))
+}
+
+/// List remembered facts for the system prompt
+///
+/// The newest facts are kept if they don't all fit in
+/// `MAX_MEMORY_PROMPT_CHARS`.
+///
+/// # Returns
+/// The section to add to the system prompt, or `None` if there are no facts
+pub fn memory_prompt(entries: &[MemoryEntry]) -> Option<String> {
+    let mut facts = Vec::new();
+    let mut total = 0;
+    for entry in entries.iter().rev() {
+        total += entry.content.len();
+        if total > MAX_MEMORY_PROMPT_CHARS {
+            break;
+        }
+        facts.push(format!("- {}", entry.content));
+    }
+    if facts.is_empty() {
+        return None;
+    }
+    facts.reverse();
+
+    Some(format!(
+        "Facts you saved about this project in earlier sessions:\n<project_memory>\n{}\n\
+         </project_memory>",
+        facts.join("\n")
+    ))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl ProjectMemory {
+    /// Create an empty, in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a project's memory from its file, starting empty if it doesn't exist or is invalid
+    pub fn load(path: PathBuf) -> Self {
+        let entries = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("Ignoring invalid project memory {}: {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self {
+            entries: Mutex::new(entries),
+            path: Some(path),
+        }
+    }
+
+    fn locked(&self) -> MutexGuard<'_, Vec<MemoryEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, entries: &[MemoryEntry]) -> ToolResult<()> {
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, serde_json::to_string_pretty(entries)?)?;
+        }
+        Ok(())
+    }
+
+    /// The remembered facts, oldest first
+    pub fn entries(&self) -> Vec<MemoryEntry> {
+        self.locked().clone()
+    }
+
+    /// Remember a fact
+    ///
+    /// A fact that is already remembered isn't added again. When the memory
+    /// is full, the oldest fact is forgotten.
+    pub fn remember(&self, content: &str) -> ToolResult<MemoryEntry> {
+        let content = content.trim();
+        if content.is_empty() {
+            return Err(ToolError::InvalidArgument(
+                "A memory can't be empty".to_string(),
+            ));
+        }
+        if content.chars().count() > MAX_MEMORY_CHARS {
+            return Err(ToolError::InvalidArgument(format!(
+                "A memory can be at most {} characters; save one short fact at a time",
+                MAX_MEMORY_CHARS
+            )));
+        }
+
+        let mut entries = self.locked();
+        if let Some(existing) = entries
+            .iter()
+            .find(|e| e.content.eq_ignore_ascii_case(content))
+        {
+            return Ok(existing.clone());
+        }
+        if entries.len() >= MAX_MEMORIES {
+            entries.remove(0);
+        }
+        let entry = MemoryEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: content.to_string(),
+            created_at: now(),
+        };
+        entries.push(entry.clone());
+        self.save(&entries)?;
+        Ok(entry)
+    }
+
+    /// Forget a fact
+    pub fn forget(&self, id: &str) -> ToolResult<()> {
+        let mut entries = self.locked();
+        let index = entries
+            .iter()
+            .position(|e| e.id == id)
+            .ok_or_else(|| ToolError::InvalidArgument(format!("No memory with id '{}'", id)))?;
+        entries.remove(index);
+        self.save(&entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_memory_persists_per_project() {
+        let dir = tempdir().unwrap();
+        let path = memory_path(dir.path(), Path::new("/work/app"));
+        assert_ne!(path, memory_path(dir.path(), Path::new("/work/other")));
+
+        let memory = ProjectMemory::load(path.clone());
+        let pnpm = memory.remember("This repo uses pnpm, not npm.").unwrap();
+        memory.remember("  this repo uses PNPM, not npm.").unwrap();
+        memory.remember("Tests run with `cargo nextest`").unwrap();
+        assert!(memory.remember(" ").is_err());
+
+        let reloaded = ProjectMemory::load(path);
+        assert_eq!(reloaded.entries().len(), 2);
+        assert_eq!(
+            memory_prompt(&reloaded.entries()).unwrap(),
+            "Facts you saved about this project in earlier sessions:\n<project_memory>\n\
+             - This repo uses pnpm, not npm.\n- Tests run with `cargo nextest`\n</project_memory>"
+        );
+
+        reloaded.forget(&pnpm.id).unwrap();
+        assert!(reloaded.forget(&pnpm.id).is_err());
+        assert_eq!(reloaded.entries().len(), 1);
+        assert_eq!(memory_prompt(&[]), None);
+    }
+}
@@ -4,12 +4,80 @@
 //! with the filesystem, search code, and execute operations.
 
 pub mod file_ops;
+pub mod archive;
+pub mod ast;
+pub mod audit;
+pub mod batch;
+pub mod checksum;
+pub mod clipboard;
+pub mod command;
+pub mod database;
+pub mod diagnostics;
+pub mod diff;
+pub mod edit;
+pub mod encoding;
+pub mod environment;
+pub mod forge;
+pub mod format;
+pub mod fuzzy;
+pub mod git;
+pub mod git_hooks;
+pub mod loc;
+pub mod memory;
+pub mod notebook;
+pub mod screenshot;
+pub mod schema;
 pub mod search;
+pub mod semantic;
+pub mod snapshot;
+pub mod text_index;
+pub mod todo;
+pub mod tree;
+pub mod truncation;
+pub mod patch;
+pub mod rename;
+pub mod replace;
+pub mod web;
 pub mod executor;
+pub mod registry;
 
 pub use file_ops::*;
+pub use archive::*;
+pub use ast::*;
+pub use audit::*;
+pub use batch::*;
+pub use checksum::*;
+pub use clipboard::*;
+pub use command::*;
+pub use database::*;
+pub use diagnostics::*;
+pub use diff::*;
+pub use edit::*;
+pub use encoding::*;
+pub use environment::*;
+pub use format::*;
+pub use fuzzy::*;
+pub use loc::*;
+pub use memory::*;
+pub use notebook::*;
+pub use screenshot::*;
+pub use schema::*;
 pub use search::*;
+pub use semantic::*;
+pub use snapshot::*;
+pub use text_index::*;
+pub use todo::*;
+pub use tree::*;
+pub use truncation::*;
+pub use patch::*;
+pub use rename::*;
+pub use replace::*;
+pub use web::*;
 pub use executor::*;
+pub use registry::*;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -29,6 +97,9 @@ pub enum ToolError {
     #[error("Invalid argument: {0}")]
     InvalidArgument(String),
 
+    #[error("Invalid arguments: {}", .0.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("; "))]
+    InvalidArguments(Vec<SchemaViolation>),
+
     #[error("Tool not found: {0}")]
     ToolNotFound(String),
 
@@ -38,13 +109,150 @@ pub enum ToolError {
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    #[error("Git error: {}", .0.message())]
+    Git(#[from] git2::Error),
+
+    #[error("{} hook failed:\n{}", .0.hook, .0.output)]
+    HookFailed(Box<git_hooks::HookResult>),
+
+    #[error("Authentication required for {url}")]
+    AuthRequired {
+        url: String,
+        /// "ssh" or "https"
+        method: String,
+    },
+
     #[error("Pattern error: {0}")]
     PatternError(String),
+
+    #[error("File changed on disk since it was read: {0}")]
+    Conflict(String),
 }
 
 /// Result type for tool operations
 pub type ToolResult<T> = Result<T, ToolError>;
 
+/// Context a tool call runs in
+#[derive(Debug, Clone, Default)]
+pub struct ToolContext {
+    /// The project root; commands run here and relative paths resolve against it
+    pub working_dir: Option<PathBuf>,
+
+    /// Restrict file access to `working_dir` and `allowed_paths`
+    pub jailed: bool,
+
+    /// Directories outside the project that jailed tools may still access
+    pub allowed_paths: Vec<PathBuf>,
+
+    /// Where write tools record files before changing them, for undo
+    pub snapshots: Option<Arc<SnapshotStore>>,
+
+    /// Run the language formatter on files after write tools change them
+    pub format_on_write: bool,
+
+    /// The session's task list, for the manage_todos tool
+    pub todos: Option<Arc<TodoList>>,
+
+    /// The project's long-term memory, for the save_memory tool
+    pub memory: Option<Arc<ProjectMemory>>,
+
+    /// Command the git_commit tool runs in place of the pre-commit hook
+    pub pre_commit_command: Option<String>,
+}
+
+impl ToolContext {
+    /// Context for tool calls made by the AI: file access is jailed to the project
+    pub fn jailed(working_dir: Option<PathBuf>, allowed_paths: Vec<PathBuf>) -> Self {
+        Self {
+            working_dir,
+            jailed: true,
+            allowed_paths,
+            snapshots: None,
+            format_on_write: false,
+            todos: None,
+            memory: None,
+            pre_commit_command: None,
+        }
+    }
+
+    /// Record files changed by write tools in a snapshot store
+    pub fn with_snapshots(mut self, snapshots: Arc<SnapshotStore>) -> Self {
+        self.snapshots = Some(snapshots);
+        self
+    }
+
+    /// Snapshot a file before a write tool changes it, if snapshots are enabled
+    pub fn snapshot_file(&self, path: &Path) -> ToolResult<()> {
+        match &self.snapshots {
+            Some(snapshots) => snapshots.snapshot(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Give the manage_todos tool a session's task list
+    pub fn with_todos(mut self, todos: Arc<TodoList>) -> Self {
+        self.todos = Some(todos);
+        self
+    }
+
+    /// Give the save_memory tool the project's memory
+    pub fn with_memory(mut self, memory: Arc<ProjectMemory>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Run `command` in place of the pre-commit hook when the AI commits
+    pub fn with_pre_commit_command(mut self, command: Option<String>) -> Self {
+        self.pre_commit_command = command;
+        self
+    }
+
+    /// Format files after a write tool changes them
+    pub fn with_format_on_write(mut self, enabled: bool) -> Self {
+        self.format_on_write = enabled;
+        self
+    }
+
+    /// Format a file a write tool just changed, if format-on-write is enabled
+    ///
+    /// Formatting is best effort: a missing formatter or a file it rejects
+    /// leaves the write as it was.
+    ///
+    /// # Returns
+    /// The name of the formatter, if it changed the file
+    pub fn format_written_file(&self, path: &Path) -> Option<String> {
+        let path = path.to_string_lossy();
+        if !self.format_on_write || !can_format(&path) {
+            return None;
+        }
+        match format_file(&path) {
+            Ok(result) if result.changed => Some(result.formatter),
+            Ok(_) => None,
+            Err(e) => {
+                log::warn!("Not formatting {}: {}", path, e);
+                None
+            }
+        }
+    }
+}
+
+/// File name stem for per-session data stored under the app data directory
+///
+/// Session IDs come from the frontend, so anything but letters, digits, `-`
+/// and `_` is replaced to keep them to safe file names.
+pub fn session_file_stem(session_id: &str) -> String {
+    session_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
 /// A file entry with metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileEntry {
@@ -58,6 +266,47 @@ pub struct FileEntry {
     pub modified: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extension: Option<String>,
+    /// Git status: "modified", "added", "deleted", "renamed", "conflict",
+    /// "untracked" or "ignored"; filled in by `add_file_details`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_status: Option<String>,
+    /// Detected media type; filled in by `add_file_details`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Number of lines of a text file; filled in by `add_file_details`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_count: Option<usize>,
+}
+
+/// The contents of a file, by what kind of file it turned out to be
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FileContent {
+    Text {
+        content: String,
+        /// The file's encoding, if it isn't UTF-8 without a byte order mark;
+        /// `content` has been decoded from it
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        encoding: Option<String>,
+        /// How the file's lines end; writes keep them this way
+        #[serde(default)]
+        line_ending: LineEnding,
+        /// Whether the file starts with a byte order mark
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        bom: bool,
+    },
+    /// An image a vision model can look at, base64 encoded
+    Image {
+        media_type: String,
+        data: String,
+        size: u64,
+    },
+    /// Any other binary file; only its metadata is returned
+    Binary {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        media_type: Option<String>,
+        size: u64,
+    },
 }
 
 /// A search result with context
@@ -66,10 +315,95 @@ pub struct SearchResult {
     pub path: String,
     pub line_number: u64,
     pub line_content: String,
+    /// Character range of the match within `line_content`, for highlighting
     #[serde(skip_serializing_if = "Option::is_none")]
     pub match_start: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub match_end: Option<usize>,
+    /// Byte range of the match within `line_content`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_byte_start: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_byte_end: Option<usize>,
+}
+
+/// Default maximum number of results returned by a search
+pub const DEFAULT_MAX_SEARCH_RESULTS: usize = 200;
+
+/// Limits applied to search results
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchLimits {
+    /// Maximum number of results to return
+    pub max_results: usize,
+    /// Number of results to skip, for fetching later pages
+    pub offset: usize,
+    /// Maximum matches reported per file (grep only)
+    pub max_per_file: Option<usize>,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self {
+            max_results: DEFAULT_MAX_SEARCH_RESULTS,
+            offset: 0,
+            max_per_file: None,
+        }
+    }
+}
+
+/// One page of search results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchPage<T> {
+    pub results: Vec<T>,
+    /// Number of results in this page
+    pub count: usize,
+    /// Number of results across all pages
+    pub total: usize,
+    pub offset: usize,
+    /// Whether results exist beyond this page
+    pub has_more: bool,
+    /// Number of files whose matches were cut by `max_per_file`
+    #[serde(default)]
+    pub files_truncated: usize,
+}
+
+impl<T> SearchPage<T> {
+    /// Cut one page out of the complete result list
+    pub fn paginate(all: Vec<T>, limits: &SearchLimits) -> Self {
+        let total = all.len();
+        let results: Vec<T> = all
+            .into_iter()
+            .skip(limits.offset)
+            .take(limits.max_results)
+            .collect();
+
+        Self {
+            count: results.len(),
+            total,
+            offset: limits.offset,
+            has_more: limits.offset + results.len() < total,
+            files_truncated: 0,
+            results,
+        }
+    }
+
+    /// Combine the first pages of several searches into one page
+    ///
+    /// Each search must have started at offset 0 and returned up to
+    /// `limits.offset + limits.max_results` results, so the combined page
+    /// holds the same results as one search over everything would.
+    pub fn merge(pages: Vec<Self>, limits: &SearchLimits) -> Self {
+        let total = pages.iter().map(|p| p.total).sum();
+        let files_truncated = pages.iter().map(|p| p.files_truncated).sum();
+        let all: Vec<T> = pages.into_iter().flat_map(|p| p.results).collect();
+
+        let mut page = Self::paginate(all, limits);
+        page.total = total;
+        page.has_more = limits.offset + page.count < total;
+        page.files_truncated = files_truncated;
+        page
+    }
 }
 
 /// File match from glob search
@@ -86,95 +420,3 @@ pub struct ToolDefinition {
     pub description: String,
     pub parameters: serde_json::Value,
 }
-
-/// Get all available tool definitions
-pub fn get_tool_definitions() -> Vec<ToolDefinition> {
-    vec![
-        ToolDefinition {
-            name: "read_file".to_string(),
-            description: "Read the contents of a file at the given path".to_string(),
-            parameters: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "path": {
-                        "type": "string",
-                        "description": "The path to the file to read"
-                    }
-                },
-                "required": ["path"]
-            }),
-        },
-        ToolDefinition {
-            name: "write_file".to_string(),
-            description: "Write content to a file at the given path".to_string(),
-            parameters: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "path": {
-                        "type": "string",
-                        "description": "The path to the file to write"
-                    },
-                    "content": {
-                        "type": "string",
-                        "description": "The content to write to the file"
-                    }
-                },
-                "required": ["path", "content"]
-            }),
-        },
-        ToolDefinition {
-            name: "list_directory".to_string(),
-            description: "List the contents of a directory".to_string(),
-            parameters: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "path": {
-                        "type": "string",
-                        "description": "The path to the directory to list"
-                    }
-                },
-                "required": ["path"]
-            }),
-        },
-        ToolDefinition {
-            name: "search_files".to_string(),
-            description: "Search for files matching a glob pattern".to_string(),
-            parameters: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "pattern": {
-                        "type": "string",
-                        "description": "The glob pattern to match (e.g., '**/*.rs')"
-                    },
-                    "path": {
-                        "type": "string",
-                        "description": "The base directory to search in"
-                    }
-                },
-                "required": ["pattern", "path"]
-            }),
-        },
-        ToolDefinition {
-            name: "grep_files".to_string(),
-            description: "Search for text in files using a regex pattern".to_string(),
-            parameters: serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "query": {
-                        "type": "string",
-                        "description": "The regex pattern to search for"
-                    },
-                    "path": {
-                        "type": "string",
-                        "description": "The directory to search in"
-                    },
-                    "file_pattern": {
-                        "type": "string",
-                        "description": "Optional glob pattern to filter files (e.g., '*.rs')"
-                    }
-                },
-                "required": ["query", "path"]
-            }),
-        },
-    ]
-}
@@ -0,0 +1,364 @@
+//! Jupyter notebook support for the tools system
+//!
+//! This module presents `.ipynb` files cell by cell, with bulky outputs
+//! summarized, and edits single cells while leaving the rest of the notebook
+//! JSON untouched. Notebooks are written back the way Jupyter writes them
+//! (sorted keys, one-space indent) so edits produce minimal diffs.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use super::{ToolError, ToolResult};
+
+/// Default number of output characters shown per cell
+pub const DEFAULT_NOTEBOOK_OUTPUT_CHARS: usize = 2_000;
+
+/// A notebook cell as presented to the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotebookCell {
+    pub index: usize,
+    /// "code", "markdown" or "raw"
+    pub cell_type: String,
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub execution_count: Option<i64>,
+    /// Text of the cell's outputs, with rich outputs summarized
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<String>,
+    pub outputs_truncated: bool,
+}
+
+/// A notebook as presented to the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notebook {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    pub cells: Vec<NotebookCell>,
+}
+
+/// How `edit_notebook_cell` changes the notebook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellEditMode {
+    /// Replace the source (and optionally the type) of an existing cell
+    #[default]
+    Replace,
+    /// Insert a new cell before the given index (or at the end)
+    Insert,
+    /// Delete the cell at the given index
+    Delete,
+}
+
+/// Join a multiline notebook string, stored either as a string or a list of lines
+fn join_source(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Split a string into the list-of-lines form Jupyter writes
+fn split_source(source: &str) -> Value {
+    Value::Array(
+        source
+            .split_inclusive('\n')
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    )
+}
+
+/// Render a cell's outputs as text, summarizing anything that isn't text
+fn render_outputs(outputs: &[Value]) -> String {
+    let mut rendered = String::new();
+    for output in outputs {
+        match output.get("output_type").and_then(Value::as_str) {
+            Some("stream") => rendered.push_str(&join_source(output.get("text"))),
+            Some("error") => {
+                let name = output.get("ename").and_then(Value::as_str).unwrap_or("Error");
+                let value = output.get("evalue").and_then(Value::as_str).unwrap_or("");
+                rendered.push_str(&format!("{}: {}\n", name, value));
+            }
+            Some("execute_result") | Some("display_data") => {
+                let Some(data) = output.get("data").and_then(Value::as_object) else {
+                    continue;
+                };
+                if let Some(text) = data.get("text/plain") {
+                    rendered.push_str(&join_source(Some(text)));
+                    rendered.push('\n');
+                }
+                for mime in data.keys().filter(|m| *m != "text/plain") {
+                    rendered.push_str(&format!("[{} output]\n", mime));
+                }
+            }
+            _ => {}
+        }
+    }
+    rendered
+}
+
+/// Load a notebook's JSON
+fn load_notebook(path: &Path) -> ToolResult<Value> {
+    if !path.is_file() {
+        return Err(ToolError::PathNotFound(path.display().to_string()));
+    }
+    let content = fs::read_to_string(path)?;
+    let notebook: Value = serde_json::from_str(&content).map_err(|e| {
+        ToolError::InvalidArgument(format!("{} is not a valid notebook: {}", path.display(), e))
+    })?;
+    if !notebook.get("cells").is_some_and(Value::is_array) {
+        return Err(ToolError::InvalidArgument(format!(
+            "{} has no cells list",
+            path.display()
+        )));
+    }
+    Ok(notebook)
+}
+
+/// Write a notebook the way Jupyter does: sorted keys, one-space indent
+fn save_notebook(path: &Path, notebook: &Value) -> ToolResult<()> {
+    let mut buf = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+    notebook.serialize(&mut serializer)?;
+    buf.push(b'\n');
+    fs::write(path, buf)?;
+    Ok(())
+}
+
+/// Read a notebook's cells
+///
+/// # Arguments
+/// * `path` - Path to the `.ipynb` file
+/// * `max_output_chars` - Output characters shown per cell (0 to omit outputs)
+///
+/// # Returns
+/// The notebook's language and its cells, with indices
+pub fn read_notebook(path: &str, max_output_chars: usize) -> ToolResult<Notebook> {
+    let notebook = load_notebook(Path::new(path))?;
+
+    let language = notebook
+        .pointer("/metadata/kernelspec/language")
+        .or_else(|| notebook.pointer("/metadata/language_info/name"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let cells = notebook["cells"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, cell)| {
+            let mut outputs = cell
+                .get("outputs")
+                .and_then(Value::as_array)
+                .filter(|outputs| !outputs.is_empty() && max_output_chars > 0)
+                .map(|outputs| render_outputs(outputs));
+            let mut outputs_truncated = false;
+            if let Some(text) = outputs.as_mut() {
+                if let Some((cut, _)) = text.char_indices().nth(max_output_chars) {
+                    text.truncate(cut);
+                    outputs_truncated = true;
+                }
+            }
+
+            NotebookCell {
+                index,
+                cell_type: cell
+                    .get("cell_type")
+                    .and_then(Value::as_str)
+                    .unwrap_or("code")
+                    .to_string(),
+                source: join_source(cell.get("source")),
+                execution_count: cell.get("execution_count").and_then(Value::as_i64),
+                outputs,
+                outputs_truncated,
+            }
+        })
+        .collect();
+
+    Ok(Notebook { language, cells })
+}
+
+/// Build an empty cell of the given type
+fn new_cell(cell_type: &str, with_id: bool) -> Value {
+    let mut cell = Map::new();
+    cell.insert("cell_type".to_string(), json!(cell_type));
+    cell.insert("metadata".to_string(), json!({}));
+    cell.insert("source".to_string(), json!([]));
+    if with_id {
+        // nbformat 4.5+ requires a unique id per cell
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        cell.insert("id".to_string(), json!(&id[..8]));
+    }
+    if cell_type == "code" {
+        cell.insert("execution_count".to_string(), Value::Null);
+        cell.insert("outputs".to_string(), json!([]));
+    }
+    Value::Object(cell)
+}
+
+/// Set a cell's type, adding or removing the fields only code cells have
+fn set_cell_type(cell: &mut Map<String, Value>, cell_type: &str) {
+    cell.insert("cell_type".to_string(), json!(cell_type));
+    if cell_type == "code" {
+        cell.entry("execution_count").or_insert(Value::Null);
+        cell.entry("outputs").or_insert_with(|| json!([]));
+    } else {
+        cell.remove("execution_count");
+        cell.remove("outputs");
+    }
+}
+
+/// Edit a single notebook cell
+///
+/// Replacing a code cell's source clears its outputs, which no longer match.
+///
+/// # Arguments
+/// * `path` - Path to the `.ipynb` file
+/// * `index` - Index of the cell to edit, or to insert before
+/// * `source` - New source for replaced or inserted cells
+/// * `cell_type` - New cell type ("code", "markdown" or "raw"); inserted cells default to code
+/// * `mode` - Whether to replace, insert or delete
+///
+/// # Returns
+/// The number of cells in the notebook after the edit
+pub fn edit_notebook_cell(
+    path: &str,
+    index: usize,
+    source: Option<&str>,
+    cell_type: Option<&str>,
+    mode: CellEditMode,
+) -> ToolResult<usize> {
+    if let Some(cell_type) = cell_type {
+        if !["code", "markdown", "raw"].contains(&cell_type) {
+            return Err(ToolError::InvalidArgument(format!(
+                "Unknown cell type '{}'; expected code, markdown or raw",
+                cell_type
+            )));
+        }
+    }
+
+    let path = Path::new(path);
+    let mut notebook = load_notebook(path)?;
+    let with_id = notebook.get("nbformat").and_then(Value::as_u64) == Some(4)
+        && notebook.get("nbformat_minor").and_then(Value::as_u64) >= Some(5);
+    let Some(cells) = notebook.get_mut("cells").and_then(Value::as_array_mut) else {
+        return Err(ToolError::InvalidArgument("Notebook has no cells list".to_string()));
+    };
+
+    let out_of_range = || {
+        ToolError::InvalidArgument(format!(
+            "Cell index {} is out of range (notebook has {} cells)",
+            index,
+            cells.len()
+        ))
+    };
+
+    match mode {
+        CellEditMode::Insert => {
+            if index > cells.len() {
+                return Err(out_of_range());
+            }
+            let mut cell = new_cell(cell_type.unwrap_or("code"), with_id);
+            cell["source"] = split_source(source.unwrap_or(""));
+            cells.insert(index, cell);
+        }
+        CellEditMode::Delete => {
+            if index >= cells.len() {
+                return Err(out_of_range());
+            }
+            cells.remove(index);
+        }
+        CellEditMode::Replace => {
+            if index >= cells.len() {
+                return Err(out_of_range());
+            }
+            if source.is_none() && cell_type.is_none() {
+                return Err(ToolError::InvalidArgument(
+                    "Replacing a cell needs a new source or cell type".to_string(),
+                ));
+            }
+            let Some(cell) = cells[index].as_object_mut() else {
+                return Err(ToolError::InvalidArgument(format!("Cell {} is malformed", index)));
+            };
+            if let Some(cell_type) = cell_type {
+                set_cell_type(cell, cell_type);
+            }
+            if let Some(source) = source {
+                cell.insert("source".to_string(), split_source(source));
+                if cell.contains_key("outputs") {
+                    cell.insert("outputs".to_string(), json!([]));
+                    cell.insert("execution_count".to_string(), Value::Null);
+                }
+            }
+        }
+    }
+
+    let count = cells.len();
+    save_notebook(path, &notebook)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": ["# Title\n", "Intro"]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 3,
+   "metadata": {"tags": ["keep"]},
+   "outputs": [
+    {"name": "stdout", "output_type": "stream", "text": ["hello\n"]},
+    {"data": {"image/png": "iVBOR...", "text/plain": ["<Figure>"]}, "metadata": {}, "output_type": "display_data"}
+   ],
+   "source": ["print('hello')"]
+  }
+ ],
+ "metadata": {"kernelspec": {"language": "python", "name": "python3"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"##;
+
+    #[test]
+    fn test_read_and_edit_notebook() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("analysis.ipynb");
+        fs::write(&path, NOTEBOOK).unwrap();
+        let path = path.to_str().unwrap();
+
+        let notebook = read_notebook(path, DEFAULT_NOTEBOOK_OUTPUT_CHARS).unwrap();
+        assert_eq!(notebook.language.as_deref(), Some("python"));
+        assert_eq!(notebook.cells[0].source, "# Title\nIntro");
+        assert_eq!(
+            notebook.cells[1].outputs.as_deref(),
+            Some("hello\n<Figure>\n[image/png output]\n")
+        );
+
+        edit_notebook_cell(path, 1, Some("x = 1\nx"), None, CellEditMode::Replace).unwrap();
+        let count =
+            edit_notebook_cell(path, 0, Some("import os"), None, CellEditMode::Insert).unwrap();
+        assert_eq!(count, 3);
+
+        let raw: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let edited = &raw["cells"][2];
+        assert_eq!(edited["source"], json!(["x = 1\n", "x"]));
+        assert_eq!(edited["outputs"], json!([]));
+        assert_eq!(edited["execution_count"], Value::Null);
+        assert_eq!(edited["metadata"]["tags"], json!(["keep"]));
+        assert_eq!(raw["cells"][0]["id"].as_str().map(str::len), Some(8));
+
+        assert!(edit_notebook_cell(path, 5, None, None, CellEditMode::Delete).is_err());
+    }
+}
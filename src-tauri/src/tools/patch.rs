@@ -0,0 +1,592 @@
+//! Unified diff parsing and application
+//!
+//! This module lets AI assistants edit files by submitting a unified diff.
+//! Hunks are located with offset and fuzz tolerance, similar to `patch(1)`,
+//! and the outcome of every hunk is reported so rejected hunks can be repaired.
+
+use std::fs;
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::{ToolError, ToolResult};
+
+/// Maximum number of context lines that may be ignored at each end of a hunk
+const MAX_FUZZ: usize = 2;
+
+/// A single line of a hunk
+#[derive(Debug, Clone, PartialEq)]
+enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+/// A hunk parsed from a unified diff
+#[derive(Debug, Clone)]
+struct Hunk {
+    old_start: usize,
+    header: String,
+    lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Lines the hunk expects to find, with `fuzz` context lines ignored at each end
+    fn old_lines(&self, fuzz: usize) -> Vec<&str> {
+        self.trimmed(fuzz)
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    /// Lines the hunk produces, with `fuzz` context lines ignored at each end
+    fn new_lines(&self, fuzz: usize) -> Vec<String> {
+        self.trimmed(fuzz)
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Add(s) => Some(s.clone()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+
+    /// Number of context lines that can be dropped from each end
+    fn max_fuzz(&self) -> usize {
+        let leading = self
+            .lines
+            .iter()
+            .take_while(|l| matches!(l, HunkLine::Context(_)))
+            .count();
+        let trailing = self
+            .lines
+            .iter()
+            .rev()
+            .take_while(|l| matches!(l, HunkLine::Context(_)))
+            .count();
+        leading.min(trailing).min(MAX_FUZZ)
+    }
+
+    fn trimmed(&self, fuzz: usize) -> &[HunkLine] {
+        &self.lines[fuzz..self.lines.len() - fuzz]
+    }
+
+    /// Render the hunk back to unified diff text
+    fn to_text(&self) -> String {
+        let mut text = self.header.clone();
+        for line in &self.lines {
+            text.push('\n');
+            match line {
+                HunkLine::Context(s) => text.push_str(&format!(" {}", s)),
+                HunkLine::Remove(s) => text.push_str(&format!("-{}", s)),
+                HunkLine::Add(s) => text.push_str(&format!("+{}", s)),
+            }
+        }
+        text
+    }
+}
+
+/// All hunks for a single file
+#[derive(Debug, Clone)]
+struct FilePatch {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+}
+
+/// Outcome of applying a single hunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HunkResult {
+    pub index: usize,
+    pub applied: bool,
+    /// Line offset from the position stated in the hunk header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<isize>,
+    /// Number of context lines ignored at each end to find a match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuzz: Option<usize>,
+    /// The hunk text, included for rejected hunks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected_hunk: Option<String>,
+}
+
+/// Outcome of applying a patch to a single file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePatchResult {
+    pub path: String,
+    /// "modified", "created" or "deleted"
+    pub action: String,
+    pub hunks: Vec<HunkResult>,
+    /// Whether every hunk applied
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of applying a patch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchResult {
+    pub files: Vec<FilePatchResult>,
+    pub applied_hunks: usize,
+    pub rejected_hunks: usize,
+    pub dry_run: bool,
+}
+
+/// Strip the `a/` or `b/` prefix git adds to diff paths; `/dev/null` means no file
+fn parse_diff_path(raw: &str) -> Option<String> {
+    // Drop any trailing timestamp separated by a tab
+    let path = raw.split('\t').next().unwrap_or(raw).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// Line numbers and counts from a hunk header like `@@ -12,5 +12,7 @@`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HunkRange {
+    old_start: usize,
+    old_count: usize,
+    new_count: usize,
+}
+
+/// Parse a hunk header; a range without a count, like `-12`, has one line
+fn parse_hunk_header(line: &str) -> ToolResult<HunkRange> {
+    let invalid = || ToolError::InvalidArgument(format!("Invalid hunk header: {}", line));
+    let mut ranges = line.trim_start_matches('@').split_whitespace();
+    let mut range = |prefix: char| -> ToolResult<(usize, usize)> {
+        let range = ranges
+            .next()
+            .and_then(|s| s.strip_prefix(prefix))
+            .ok_or_else(invalid)?;
+        let (start, count) = range.split_once(',').unwrap_or((range, "1"));
+        Ok((
+            start.parse().map_err(|_| invalid())?,
+            count.parse().map_err(|_| invalid())?,
+        ))
+    };
+    let (old_start, old_count) = range('-')?;
+    let (_, new_count) = range('+')?;
+    Ok(HunkRange {
+        old_start,
+        old_count,
+        new_count,
+    })
+}
+
+/// Check whether line `i` starts a file header (`---` followed by `+++`)
+fn is_file_header(lines: &[&str], i: usize) -> bool {
+    lines[i].starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ "))
+}
+
+/// Error for a hunk whose lines don't add up to the counts in its header
+fn hunk_length_error(header: &str) -> ToolError {
+    ToolError::InvalidArgument(format!(
+        "Hunk lines don't match the line counts in its header: {}",
+        header
+    ))
+}
+
+/// Parse a unified diff into per-file patches
+fn parse_patch(patch: &str) -> ToolResult<Vec<FilePatch>> {
+    let lines: Vec<&str> = patch.lines().collect();
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+
+        if is_file_header(&lines, i - 1) {
+            files.push(FilePatch {
+                old_path: parse_diff_path(&line[4..]),
+                new_path: parse_diff_path(&lines[i][4..]),
+                hunks: Vec::new(),
+            });
+            i += 1;
+        } else if line.starts_with("@@") {
+            let file = files.last_mut().ok_or_else(|| {
+                ToolError::InvalidArgument("Hunk found before any file header".to_string())
+            })?;
+            let range = parse_hunk_header(line)?;
+            let mut hunk = Hunk {
+                old_start: range.old_start,
+                header: line.to_string(),
+                lines: Vec::new(),
+            };
+
+            // The header's counts say where the hunk ends, so its lines may
+            // look like file headers and may end in blank context lines
+            let (mut old_left, mut new_left) = (range.old_count, range.new_count);
+            while old_left > 0 || new_left > 0 {
+                let Some(&next) = lines.get(i) else {
+                    return Err(hunk_length_error(line));
+                };
+                i += 1;
+                let hunk_line = if let Some(s) = next.strip_prefix('+') {
+                    HunkLine::Add(s.to_string())
+                } else if let Some(s) = next.strip_prefix('-') {
+                    HunkLine::Remove(s.to_string())
+                } else if let Some(s) = next.strip_prefix(' ') {
+                    HunkLine::Context(s.to_string())
+                } else if next.is_empty() {
+                    // Some tools strip the leading space from blank context lines
+                    HunkLine::Context(String::new())
+                } else if next.starts_with('\\') {
+                    // "\ No newline at end of file"
+                    continue;
+                } else {
+                    return Err(hunk_length_error(line));
+                };
+                let (old, new) = match hunk_line {
+                    HunkLine::Context(_) => (1, 1),
+                    HunkLine::Remove(_) => (1, 0),
+                    HunkLine::Add(_) => (0, 1),
+                };
+                if old > old_left || new > new_left {
+                    return Err(hunk_length_error(line));
+                }
+                old_left -= old;
+                new_left -= new;
+                hunk.lines.push(hunk_line);
+            }
+
+            // Lines the counts leave out mean the header is wrong
+            while let Some(&next) = lines.get(i) {
+                if next.starts_with('\\') {
+                    i += 1;
+                    continue;
+                }
+                let changes = next.starts_with('+') || next.starts_with('-');
+                if changes && !is_file_header(&lines, i) && next != "-- " {
+                    return Err(hunk_length_error(line));
+                }
+                break;
+            }
+            file.hunks.push(hunk);
+        }
+    }
+
+    if files.is_empty() {
+        return Err(ToolError::InvalidArgument(
+            "Patch contains no file headers ('---'/'+++' lines)".to_string(),
+        ));
+    }
+
+    Ok(files)
+}
+
+/// Check whether `expected` matches `lines` at `pos`, ignoring trailing whitespace
+fn matches_at(lines: &[String], expected: &[&str], pos: usize) -> bool {
+    pos + expected.len() <= lines.len()
+        && expected
+            .iter()
+            .zip(&lines[pos..])
+            .all(|(e, l)| e.trim_end() == l.trim_end())
+}
+
+/// Find where a hunk applies, searching outward from the expected position
+///
+/// Returns the position of the (fuzz-trimmed) hunk and the fuzz used.
+fn locate_hunk(lines: &[String], hunk: &Hunk, expected: usize) -> Option<(usize, usize)> {
+    for fuzz in 0..=hunk.max_fuzz() {
+        let old = hunk.old_lines(fuzz);
+        let expected = (expected + fuzz).min(lines.len());
+
+        if old.is_empty() {
+            return Some((expected, fuzz));
+        }
+
+        let max_distance = lines.len().max(expected);
+        for distance in 0..=max_distance {
+            if let Some(pos) = expected.checked_add(distance) {
+                if matches_at(lines, &old, pos) {
+                    return Some((pos, fuzz));
+                }
+            }
+            if let Some(pos) = expected.checked_sub(distance) {
+                if distance > 0 && matches_at(lines, &old, pos) {
+                    return Some((pos, fuzz));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Apply hunks to file lines in place, returning per-hunk results
+fn apply_hunks(lines: &mut Vec<String>, hunks: &[Hunk]) -> Vec<HunkResult> {
+    let mut results = Vec::new();
+    // Net lines added by previously applied hunks
+    let mut delta: isize = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let stated = hunk.old_start.saturating_sub(1);
+        let expected = (stated as isize + delta).max(0) as usize;
+
+        match locate_hunk(lines, hunk, expected) {
+            Some((pos, fuzz)) => {
+                let old_len = hunk.old_lines(fuzz).len();
+                let new_lines = hunk.new_lines(fuzz);
+                let new_len = new_lines.len();
+                lines.splice(pos..pos + old_len, new_lines);
+
+                results.push(HunkResult {
+                    index,
+                    applied: true,
+                    offset: Some(pos as isize - fuzz as isize - stated as isize),
+                    fuzz: Some(fuzz),
+                    rejected_hunk: None,
+                });
+                delta += new_len as isize - old_len as isize;
+            }
+            None => results.push(HunkResult {
+                index,
+                applied: false,
+                offset: None,
+                fuzz: None,
+                rejected_hunk: Some(hunk.to_text()),
+            }),
+        }
+    }
+
+    results
+}
+
+/// Apply one file's hunks under `base`
+fn apply_file_patch(base: &Path, file: &FilePatch, dry_run: bool) -> ToolResult<FilePatchResult> {
+    // Paths must stay under the base directory
+    for path in file.old_path.iter().chain(file.new_path.iter()) {
+        let escapes = Path::new(path)
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+        if escapes {
+            return Err(ToolError::PermissionDenied(format!(
+                "Patch paths must be relative to the base directory: {}",
+                path
+            )));
+        }
+    }
+
+    let (rel_path, action) = match (&file.old_path, &file.new_path) {
+        (_, Some(new)) if file.old_path.is_none() => (new.clone(), "created"),
+        (Some(old), None) => (old.clone(), "deleted"),
+        (_, Some(new)) => (new.clone(), "modified"),
+        (None, None) => {
+            return Err(ToolError::InvalidArgument(
+                "File patch has neither an old nor a new path".to_string(),
+            ))
+        }
+    };
+
+    let source = base.join(file.old_path.as_deref().unwrap_or(&rel_path));
+    let target = base.join(&rel_path);
+
+    let original = if file.old_path.is_some() {
+        if !source.is_file() {
+            return Err(ToolError::PathNotFound(source.display().to_string()));
+        }
+        fs::read_to_string(&source)?
+    } else if target.exists() {
+        return Err(ToolError::InvalidArgument(format!(
+            "Patch creates {}, which already exists",
+            rel_path
+        )));
+    } else {
+        String::new()
+    };
+
+    let eol = if original.contains("\r\n") { "\r\n" } else { "\n" };
+    let trailing_newline = original.is_empty() || original.ends_with('\n');
+    let mut lines: Vec<String> = original
+        .lines()
+        .map(|l| l.trim_end_matches('\r').to_string())
+        .collect();
+
+    let hunks = apply_hunks(&mut lines, &file.hunks);
+    let success = hunks.iter().all(|h| h.applied);
+    let any_applied = hunks.iter().any(|h| h.applied);
+
+    if !dry_run && any_applied {
+        if action == "deleted" && success {
+            fs::remove_file(&source)?;
+        } else {
+            let mut content = lines.join(eol);
+            if trailing_newline && !lines.is_empty() {
+                content.push_str(eol);
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, content)?;
+            // A rename moves the file rather than copying it
+            if action == "modified" && source != target && source.exists() {
+                fs::remove_file(&source)?;
+            }
+        }
+    }
+
+    Ok(FilePatchResult {
+        path: rel_path,
+        action: action.to_string(),
+        hunks,
+        success,
+        error: None,
+    })
+}
+
+/// List the files a unified diff touches, relative to its base directory
+pub fn patch_paths(patch: &str) -> ToolResult<Vec<String>> {
+    let mut paths: Vec<String> = Vec::new();
+    for file in parse_patch(patch)? {
+        for path in file.old_path.into_iter().chain(file.new_path) {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    Ok(paths)
+}
+
+/// Apply a unified diff to files under a base directory
+///
+/// Hunks are matched at the stated line first, then at the nearest offset,
+/// then with up to two context lines ignored at each end. Hunks that apply
+/// are written even if others in the same file are rejected, like `patch(1)`.
+///
+/// # Arguments
+/// * `patch` - Unified diff text
+/// * `base` - Directory the paths in the diff are relative to
+/// * `dry_run` - Report what would happen without writing any files
+///
+/// # Returns
+/// Per-file and per-hunk results
+pub fn apply_patch(patch: &str, base: &str, dry_run: bool) -> ToolResult<PatchResult> {
+    let base = Path::new(base);
+    if !base.is_dir() {
+        return Err(ToolError::PathNotFound(base.display().to_string()));
+    }
+
+    let mut result = PatchResult {
+        files: Vec::new(),
+        applied_hunks: 0,
+        rejected_hunks: 0,
+        dry_run,
+    };
+
+    for file in parse_patch(patch)? {
+        let file_result = match apply_file_patch(base, &file, dry_run) {
+            Ok(r) => r,
+            Err(e) => FilePatchResult {
+                path: file
+                    .new_path
+                    .clone()
+                    .or_else(|| file.old_path.clone())
+                    .unwrap_or_default(),
+                action: "failed".to_string(),
+                hunks: Vec::new(),
+                success: false,
+                error: Some(e.to_string()),
+            },
+        };
+
+        result.applied_hunks += file_result.hunks.iter().filter(|h| h.applied).count();
+        result.rejected_hunks += file_result.hunks.iter().filter(|h| !h.applied).count();
+        result.files.push(file_result);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_apply_patch_with_offset() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("main.rs"),
+            "// header\n// added later\nfn main() {\n    println!(\"hello\");\n}\n",
+        )
+        .unwrap();
+
+        let patch = "--- a/main.rs\n+++ b/main.rs\n@@ -1,3 +1,3 @@\n fn main() {\n-    println!(\"hello\");\n+    println!(\"world\");\n }\n";
+        let result = apply_patch(patch, dir.path().to_str().unwrap(), false).unwrap();
+
+        assert_eq!(result.applied_hunks, 1);
+        assert_eq!(result.files[0].hunks[0].offset, Some(2));
+        let content = fs::read_to_string(dir.path().join("main.rs")).unwrap();
+        assert!(content.contains("world"));
+        assert!(content.starts_with("// header\n"));
+    }
+
+    #[test]
+    fn test_apply_patch_reports_rejected_hunk() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+
+        let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n-one\n+uno\n two\n@@ -3,1 +3,1 @@\n-missing\n+gone\n";
+        let result = apply_patch(patch, dir.path().to_str().unwrap(), false).unwrap();
+
+        assert_eq!(result.applied_hunks, 1);
+        assert_eq!(result.rejected_hunks, 1);
+        assert!(!result.files[0].success);
+        assert!(result.files[0].hunks[1].rejected_hunk.is_some());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+            "uno\ntwo\nthree\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_patch_creates_file_and_dry_run() {
+        let dir = tempdir().unwrap();
+        let patch = "--- /dev/null\n+++ b/new/file.txt\n@@ -0,0 +1,2 @@\n+hello\n+world\n";
+
+        let result = apply_patch(patch, dir.path().to_str().unwrap(), true).unwrap();
+        assert_eq!(result.files[0].action, "created");
+        assert!(!dir.path().join("new/file.txt").exists());
+
+        apply_patch(patch, dir.path().to_str().unwrap(), false).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("new/file.txt")).unwrap(),
+            "hello\nworld\n"
+        );
+
+        // Creating a file that exists would overwrite it
+        let result = apply_patch(patch, dir.path().to_str().unwrap(), false).unwrap();
+        assert!(result.files[0].error.is_some());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("new/file.txt")).unwrap(),
+            "hello\nworld\n"
+        );
+    }
+
+    #[test]
+    fn test_hunks_end_at_their_header_counts() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("notes.md"), "# Notes\n-- old\n\n\nend\n").unwrap();
+
+        // Content lines that look like file headers, and blank context at the end
+        let patch = "--- a/notes.md\n+++ b/notes.md\n@@ -1,4 +1,4 @@\n # Notes\n--- old\n+++ new\n \n \n";
+        let result = apply_patch(patch, dir.path().to_str().unwrap(), false).unwrap();
+        assert_eq!((result.applied_hunks, result.files.len()), (1, 1));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("notes.md")).unwrap(),
+            "# Notes\n++ new\n\n\nend\n"
+        );
+
+        let short = "--- a/notes.md\n+++ b/notes.md\n@@ -1,3 +1,3 @@\n # Notes\n";
+        assert!(apply_patch(short, dir.path().to_str().unwrap(), true).is_err());
+        let long = "--- a/notes.md\n+++ b/notes.md\n@@ -1 +1 @@\n-# Notes\n+# Todo\n+extra\n";
+        assert!(apply_patch(long, dir.path().to_str().unwrap(), true).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+//! Tool registry
+//!
+//! This module defines the `Tool` trait implemented by every tool the AI can
+//! call, and the `ToolRegistry` that dispatches tool calls by name. The
+//! registry lives in `AppState`, so tools (including plugins) can be added at
+//! runtime without touching the dispatcher.
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::{
+    validate_arguments, ReadToolResultTool, RiskClass, ToolContext, ToolDefinition, ToolError,
+    ToolResult, ToolResultCache,
+};
+use crate::providers::{ContentBlock, ToolCall};
+
+/// Key under which a tool returns an image (a serialized `ContentBlock::Image`)
+pub const TOOL_IMAGE_KEY: &str = "image";
+
+/// A tool result ready to send back to the model
+#[derive(Debug, Clone)]
+pub struct ToolOutput {
+    /// The JSON result, as text
+    pub content: String,
+    /// Images the tool returned, as image content blocks
+    pub images: Vec<ContentBlock>,
+}
+
+/// Tool calls of each risk class that may run at once, across all sessions
+const DISK_CONCURRENCY: usize = 8;
+const NETWORK_CONCURRENCY: usize = 4;
+const SUBPROCESS_CONCURRENCY: usize = 2;
+const WRITE_CONCURRENCY: usize = 1;
+
+/// Limits how many tool calls use each kind of resource at once
+#[derive(Debug, Clone)]
+pub struct ToolConcurrency {
+    disk: Arc<Semaphore>,
+    network: Arc<Semaphore>,
+    subprocess: Arc<Semaphore>,
+    writes: Arc<Semaphore>,
+}
+
+impl Default for ToolConcurrency {
+    fn default() -> Self {
+        Self {
+            disk: Arc::new(Semaphore::new(DISK_CONCURRENCY)),
+            network: Arc::new(Semaphore::new(NETWORK_CONCURRENCY)),
+            subprocess: Arc::new(Semaphore::new(SUBPROCESS_CONCURRENCY)),
+            writes: Arc::new(Semaphore::new(WRITE_CONCURRENCY)),
+        }
+    }
+}
+
+impl ToolConcurrency {
+    /// Wait for a slot to run a call of the given risk class
+    pub async fn acquire(&self, risk: RiskClass) -> OwnedSemaphorePermit {
+        let semaphore = match risk {
+            RiskClass::Read => &self.disk,
+            RiskClass::Write => &self.writes,
+            RiskClass::Network => &self.network,
+            RiskClass::Execute => &self.subprocess,
+        };
+        semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("tool semaphores are never closed")
+    }
+}
+
+/// A tool that AI assistants can call
+pub trait Tool: Send + Sync {
+    /// Unique name the model uses to call the tool
+    fn name(&self) -> &str;
+
+    /// Definition sent to providers, including the JSON schema of the arguments
+    fn definition(&self) -> ToolDefinition;
+
+    /// Risk class used to decide whether a call needs the user's approval
+    fn risk(&self) -> RiskClass {
+        RiskClass::Execute
+    }
+
+    /// Whether calls may run alongside the other calls in a response
+    fn runs_concurrently(&self) -> bool {
+        self.risk().runs_concurrently()
+    }
+
+    /// Run the tool with the given arguments
+    fn execute(&self, args: &Value, context: &ToolContext) -> ToolResult<Value>;
+}
+
+/// Registered tools, in registration order
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<Arc<dyn Tool>>,
+    /// Full text of results that were truncated, shared by clones of the registry
+    results: Arc<ToolResultCache>,
+}
+
+impl ToolRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with all built-in tools registered
+    pub fn with_builtin_tools() -> Self {
+        let mut registry = Self::new();
+        for tool in super::builtin_tools() {
+            registry.register(tool);
+        }
+        registry.register(Arc::new(ReadToolResultTool::new(registry.results.clone())));
+        registry
+    }
+
+    /// Register a tool, replacing any existing tool with the same name
+    pub fn register(&mut self, tool: Arc<dyn Tool>) {
+        match self.tools.iter().position(|t| t.name() == tool.name()) {
+            Some(index) => self.tools[index] = tool,
+            None => self.tools.push(tool),
+        }
+    }
+
+    /// Remove a tool by name
+    pub fn unregister(&mut self, name: &str) -> Option<Arc<dyn Tool>> {
+        let index = self.tools.iter().position(|t| t.name() == name)?;
+        Some(self.tools.remove(index))
+    }
+
+    /// Get a tool by name
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.iter().find(|t| t.name() == name).cloned()
+    }
+
+    /// Get the definitions of all registered tools
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        self.tools.iter().map(|t| t.definition()).collect()
+    }
+
+    /// Get the risk class of a tool; unknown tools are treated as the riskiest
+    pub fn risk(&self, name: &str) -> RiskClass {
+        self.get(name)
+            .map(|t| t.risk())
+            .unwrap_or(RiskClass::Execute)
+    }
+
+    /// Whether calls to a tool may run concurrently; unknown tools run alone
+    pub fn runs_concurrently(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|t| t.runs_concurrently())
+    }
+
+    /// Execute a tool call and return the result as JSON
+    ///
+    /// The arguments are validated against the tool's parameter schema first.
+    pub fn execute(&self, tool_call: &ToolCall, context: &ToolContext) -> ToolResult<Value> {
+        let tool = self
+            .get(&tool_call.name)
+            .ok_or_else(|| ToolError::ToolNotFound(tool_call.name.clone()))?;
+
+        let violations = validate_arguments(&tool.definition().parameters, &tool_call.arguments);
+        if !violations.is_empty() {
+            return Err(ToolError::InvalidArguments(violations));
+        }
+
+        tool.execute(&tool_call.arguments, context)
+    }
+
+    /// Execute a tool call and return the result as a string (for tool result messages)
+    ///
+    /// Images in the result are dropped; use `execute_for_message` to keep them.
+    pub fn execute_as_string(&self, tool_call: &ToolCall, context: &ToolContext) -> String {
+        self.execute_for_message(tool_call, context).content
+    }
+
+    /// Execute a tool call on the blocking thread pool, once its resource allows
+    ///
+    /// Tools block on disk, network and subprocesses, so they are kept off
+    /// the async runtime; `limits` bounds how many run at once.
+    pub async fn execute_async(
+        &self,
+        tool_call: ToolCall,
+        context: ToolContext,
+        limits: &ToolConcurrency,
+    ) -> ToolOutput {
+        let _permit = limits.acquire(self.risk(&tool_call.name)).await;
+        let registry = self.clone();
+        let name = tool_call.name.clone();
+
+        tokio::task::spawn_blocking(move || registry.execute_for_message(&tool_call, &context))
+            .await
+            .unwrap_or_else(|e| ToolOutput {
+                content: json!({
+                    "success": false,
+                    "error": format!("Tool {} panicked: {}", name, e)
+                })
+                .to_string(),
+                images: Vec::new(),
+            })
+    }
+
+    /// Execute a tool call and prepare the result for a tool result message
+    ///
+    /// An image returned under `TOOL_IMAGE_KEY` is moved out of the JSON, so
+    /// it can be sent as an image block rather than as base64 text. Results
+    /// longer than `MAX_TOOL_RESULT_CHARS` are truncated, with a continuation
+    /// token for reading the rest.
+    pub fn execute_for_message(&self, tool_call: &ToolCall, context: &ToolContext) -> ToolOutput {
+        match self.execute(tool_call, context) {
+            Ok(mut value) => {
+                let images = value
+                    .as_object_mut()
+                    .and_then(|object| object.remove(TOOL_IMAGE_KEY))
+                    .and_then(|image| serde_json::from_value::<ContentBlock>(image).ok())
+                    .into_iter()
+                    .collect();
+                let content = serde_json::to_string_pretty(&value).unwrap_or_else(|e| {
+                    format!("{{\"error\": \"Failed to serialize result: {}\"}}", e)
+                });
+                ToolOutput {
+                    content: self.results.truncate(content),
+                    images,
+                }
+            }
+            Err(ToolError::InvalidArguments(violations)) => ToolOutput {
+                content: json!({
+                    "success": false,
+                    "error": format!(
+                        "Invalid arguments for {}; fix them and call the tool again",
+                        tool_call.name
+                    ),
+                    "validation_errors": violations
+                })
+                .to_string(),
+                images: Vec::new(),
+            },
+            Err(e) => ToolOutput {
+                content: json!({
+                    "success": false,
+                    "error": e.to_string()
+                })
+                .to_string(),
+                images: Vec::new(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoTool;
+
+    impl Tool for EchoTool {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "echo".to_string(),
+                description: "Echo the arguments back".to_string(),
+                parameters: json!({ "type": "object" }),
+            }
+        }
+
+        fn risk(&self) -> RiskClass {
+            RiskClass::Read
+        }
+
+        fn execute(&self, args: &Value, _context: &ToolContext) -> ToolResult<Value> {
+            Ok(json!({ "success": true, "echo": args }))
+        }
+    }
+
+    #[test]
+    fn test_register_custom_tool() {
+        let mut registry = ToolRegistry::with_builtin_tools();
+        let builtin_count = registry.definitions().len();
+        registry.register(Arc::new(EchoTool));
+
+        assert_eq!(registry.definitions().len(), builtin_count + 1);
+        assert_eq!(registry.risk("echo"), RiskClass::Read);
+
+        let tool_call = ToolCall {
+            id: "test-1".to_string(),
+            name: "echo".to_string(),
+            arguments: json!({ "value": 1 }),
+        };
+        let result = registry
+            .execute(&tool_call, &ToolContext::default())
+            .unwrap();
+        assert_eq!(result["echo"]["value"], 1);
+
+        let invalid_call = ToolCall {
+            id: "test-2".to_string(),
+            name: "read_file".to_string(),
+            arguments: json!({ "path": 42 }),
+        };
+        let output = registry.execute_for_message(&invalid_call, &ToolContext::default());
+        let output: Value = serde_json::from_str(&output.content).unwrap();
+        assert_eq!(output["validation_errors"][0]["path"], "/path");
+
+        assert!(registry.unregister("echo").is_some());
+        assert!(matches!(
+            registry.execute(&tool_call, &ToolContext::default()),
+            Err(ToolError::ToolNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_execute_async_respects_limits() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(EchoTool));
+        let limits = ToolConcurrency::default();
+
+        let tool_call = ToolCall {
+            id: "test-1".to_string(),
+            name: "echo".to_string(),
+            arguments: json!({ "value": 1 }),
+        };
+        let output = registry
+            .execute_async(tool_call, ToolContext::default(), &limits)
+            .await;
+        assert!(output.content.contains("\"value\": 1"));
+
+        let _write = limits.acquire(RiskClass::Write).await;
+        assert_eq!(limits.writes.available_permits(), 0);
+        assert_eq!(limits.disk.available_permits(), DISK_CONCURRENCY);
+    }
+}
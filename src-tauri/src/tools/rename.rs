@@ -0,0 +1,217 @@
+//! Syntax-aware symbol renaming for the tools system
+//!
+//! This module renames an identifier across a project by parsing each source
+//! file with tree-sitter and rewriting only identifier tokens. Unlike a text
+//! replace, it leaves strings, comments and longer names that merely contain
+//! the old name alone. It does not resolve scopes, so unrelated symbols that
+//! share the name are renamed too; the result lists every touched line.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tree_sitter::Node;
+
+use super::{ast, search, SourceLanguage, ToolError, ToolResult};
+
+/// Occurrences renamed in one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenamedFile {
+    /// Path relative to the searched directory
+    pub path: String,
+    pub occurrences: usize,
+    /// 1-based lines containing an occurrence
+    pub lines: Vec<usize>,
+}
+
+/// Result of renaming a symbol
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameResult {
+    pub files: Vec<RenamedFile>,
+    pub total_occurrences: usize,
+    /// Files that already used the new name, where the rename may collide
+    pub conflicts: Vec<String>,
+    pub dry_run: bool,
+}
+
+/// Whether a name is a plain identifier in every supported language
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Collect the byte ranges of identifier tokens spelled `name`
+///
+/// Grammars name their identifier tokens `identifier`, `type_identifier`,
+/// `field_identifier`, `property_identifier` and so on.
+fn collect_identifiers(node: Node, source: &str, name: &str, ranges: &mut Vec<(usize, usize)>) {
+    if node.child_count() == 0 {
+        if node.kind().ends_with("identifier") && &source[node.byte_range()] == name {
+            ranges.push((node.start_byte(), node.end_byte()));
+        }
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_identifiers(child, source, name, ranges);
+    }
+}
+
+/// Find the identifier occurrences of `name` in a source file
+fn find_identifiers(source: &str, language: SourceLanguage, name: &str) -> ToolResult<Vec<(usize, usize)>> {
+    if !source.contains(name) {
+        return Ok(Vec::new());
+    }
+    let tree = ast::parse_source(source, language)?;
+    let mut ranges = Vec::new();
+    collect_identifiers(tree.root_node(), source, name, &mut ranges);
+    Ok(ranges)
+}
+
+/// Rename an identifier in every supported source file under a path
+///
+/// # Arguments
+/// * `path` - The directory (or single file) to rename in
+/// * `old_name` - The identifier to rename
+/// * `new_name` - Its new name
+/// * `file_pattern` - Optional glob limiting the files changed
+/// * `dry_run` - Report the occurrences without changing any file
+///
+/// # Returns
+/// The touched files with their occurrence lines, and any files that already
+/// used the new name
+pub fn rename_symbol(
+    path: &str,
+    old_name: &str,
+    new_name: &str,
+    file_pattern: Option<&str>,
+    dry_run: bool,
+) -> ToolResult<RenameResult> {
+    for name in [old_name, new_name] {
+        if !is_identifier(name) {
+            return Err(ToolError::InvalidArgument(format!(
+                "'{}' is not a valid identifier",
+                name
+            )));
+        }
+    }
+    let base = Path::new(path);
+    if !base.exists() {
+        return Err(ToolError::PathNotFound(path.to_string()));
+    }
+
+    let mut candidates: Vec<_> = search::walk_builder(base, file_pattern)?
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .collect();
+    candidates.sort();
+
+    let mut result = RenameResult {
+        files: Vec::new(),
+        total_occurrences: 0,
+        conflicts: Vec::new(),
+        dry_run,
+    };
+    // Compute every file's new contents before writing any of them
+    let mut pending = Vec::new();
+
+    for file in candidates {
+        let Some(language) = SourceLanguage::from_path(&file) else {
+            continue;
+        };
+        let Ok(source) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let ranges = find_identifiers(&source, language, old_name)?;
+        if ranges.is_empty() {
+            continue;
+        }
+
+        let relative = file
+            .strip_prefix(base)
+            .ok()
+            .filter(|p| !p.as_os_str().is_empty())
+            .or_else(|| file.file_name().map(Path::new))
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        if !find_identifiers(&source, language, new_name)?.is_empty() {
+            result.conflicts.push(relative.clone());
+        }
+
+        let mut renamed = source.clone();
+        for &(start, end) in ranges.iter().rev() {
+            renamed.replace_range(start..end, new_name);
+        }
+        let mut lines: Vec<usize> = ranges
+            .iter()
+            .map(|&(start, _)| source[..start].matches('\n').count() + 1)
+            .collect();
+        lines.dedup();
+
+        result.total_occurrences += ranges.len();
+        result.files.push(RenamedFile {
+            path: relative,
+            occurrences: ranges.len(),
+            lines,
+        });
+        pending.push((file, renamed));
+    }
+
+    if !dry_run {
+        for (file, renamed) in pending {
+            fs::write(file, renamed)?;
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rename_skips_strings_comments_and_longer_names() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("lib.rs"),
+            "// parse the input\npub fn parse(s: &str) -> usize { parse_inner(s) }\nfn parse_inner(s: &str) -> usize { s.len() }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("app.ts"),
+            "import { parse } from './lib';\nconst label = \"parse\";\nparse(label);\nobj.parse();\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("README.md"), "Call parse()\n").unwrap();
+        let path = dir.path().to_str().unwrap();
+
+        let preview = rename_symbol(path, "parse", "parse_str", None, true).unwrap();
+        assert_eq!(preview.total_occurrences, 4);
+        assert_eq!(preview.files[0].path, "app.ts");
+        assert_eq!(preview.files[0].lines, vec![1, 3, 4]);
+        assert!(preview.conflicts.is_empty());
+
+        rename_symbol(path, "parse", "parse_str", None, false).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("lib.rs")).unwrap(),
+            "// parse the input\npub fn parse_str(s: &str) -> usize { parse_inner(s) }\nfn parse_inner(s: &str) -> usize { s.len() }\n"
+        );
+        assert!(fs::read_to_string(dir.path().join("app.ts"))
+            .unwrap()
+            .contains("const label = \"parse\";\nparse_str(label);\nobj.parse_str();"));
+        assert_eq!(fs::read_to_string(dir.path().join("README.md")).unwrap(), "Call parse()\n");
+
+        let conflict = rename_symbol(path, "parse_inner", "parse_str", None, true).unwrap();
+        assert_eq!(conflict.conflicts, vec!["lib.rs"]);
+        assert!(rename_symbol(path, "parse", "not valid", None, true).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+//! Regex search and replace across files for the tools system
+//!
+//! This module applies one regex replacement to every matching file under a
+//! directory, reporting how many replacements each file received and a
+//! unified diff of the changes. A dry run produces the same report without
+//! writing anything, so the changes can be reviewed first.
+
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{file_ops, search, ToolError, ToolResult};
+
+/// Lines of unchanged context around each change in the diff
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Longest diff returned; the per-file counts stay complete
+const MAX_DIFF_CHARS: usize = 50_000;
+
+/// Replacements made in one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReplacement {
+    /// Path relative to the searched directory
+    pub path: String,
+    pub replacements: usize,
+}
+
+/// Result of a replace across files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceResult {
+    pub files: Vec<FileReplacement>,
+    pub total_replacements: usize,
+    /// Unified diff of every change
+    pub diff: String,
+    pub diff_truncated: bool,
+    pub dry_run: bool,
+}
+
+/// A run of changed lines: old lines `start..start + old.len()` become `new`
+struct LineChange {
+    start: usize,
+    old: Vec<String>,
+    new: Vec<String>,
+}
+
+/// A match and the text replacing it
+struct Replacement {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+/// Split text into lines without their line endings
+fn split_lines(text: &str) -> Vec<String> {
+    text.lines().map(str::to_string).collect()
+}
+
+/// Replace every match in `content`
+///
+/// # Returns
+/// The new content, the number of replacements and the changed line ranges
+fn replace_content(regex: &Regex, replacement: &str, content: &str) -> (String, usize, Vec<LineChange>) {
+    let replacements: Vec<Replacement> = regex
+        .captures_iter(content)
+        .map(|caps| {
+            let m = caps.get(0).expect("group 0 always matches");
+            let mut text = String::new();
+            caps.expand(replacement, &mut text);
+            Replacement {
+                start: m.start(),
+                end: m.end(),
+                text,
+            }
+        })
+        .collect();
+
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset) - 1;
+    let line_end = |line: usize| line_starts.get(line + 1).copied().unwrap_or(content.len());
+
+    // Rebuild the content, and group matches that share lines into changes
+    let mut output = String::with_capacity(content.len());
+    let mut copied_to = 0;
+    let mut groups: Vec<(usize, usize, Vec<&Replacement>)> = Vec::new();
+
+    for r in &replacements {
+        output.push_str(&content[copied_to..r.start]);
+        output.push_str(&r.text);
+        copied_to = r.end;
+
+        let first = line_of(r.start);
+        let last = line_of(r.end.saturating_sub(1).max(r.start));
+        match groups.last_mut() {
+            Some(group) if group.1 >= first => {
+                group.1 = group.1.max(last);
+                group.2.push(r);
+            }
+            _ => groups.push((first, last, vec![r])),
+        }
+    }
+    output.push_str(&content[copied_to..]);
+
+    let changes = groups
+        .into_iter()
+        .filter_map(|(first, last, group)| {
+            let (from, to) = (line_starts[first], line_end(last));
+            let mut new_text = String::new();
+            let mut copied_to = from;
+            for r in group {
+                new_text.push_str(&content[copied_to..r.start]);
+                new_text.push_str(&r.text);
+                copied_to = r.end;
+            }
+            new_text.push_str(&content[copied_to..to]);
+
+            let change = LineChange {
+                start: first,
+                old: split_lines(&content[from..to]),
+                new: split_lines(&new_text),
+            };
+            (change.old != change.new).then_some(change)
+        })
+        .collect();
+
+    (output, replacements.len(), changes)
+}
+
+/// Render changes to one file as a unified diff
+fn unified_diff(path: &str, old_lines: &[&str], changes: &[LineChange]) -> String {
+    let mut diff = format!("--- a/{}\n+++ b/{}\n", path, path);
+    // Difference between new and old line numbers so far
+    let mut offset: isize = 0;
+    let mut i = 0;
+
+    while i < changes.len() {
+        // Merge changes whose context would overlap into one hunk
+        let mut j = i;
+        while j + 1 < changes.len()
+            && changes[j + 1].start <= changes[j].start + changes[j].old.len() + 2 * DIFF_CONTEXT_LINES
+        {
+            j += 1;
+        }
+
+        let hunk_start = changes[i].start.saturating_sub(DIFF_CONTEXT_LINES);
+        let hunk_end = (changes[j].start + changes[j].old.len() + DIFF_CONTEXT_LINES).min(old_lines.len());
+
+        let mut body = String::new();
+        let mut old_count = 0;
+        let mut new_count = 0;
+        let mut line = hunk_start;
+        for change in &changes[i..=j] {
+            for context in &old_lines[line..change.start] {
+                body.push_str(&format!(" {}\n", context));
+            }
+            old_count += change.start - line;
+            new_count += change.start - line;
+            for old in &change.old {
+                body.push_str(&format!("-{}\n", old));
+            }
+            for new in &change.new {
+                body.push_str(&format!("+{}\n", new));
+            }
+            old_count += change.old.len();
+            new_count += change.new.len();
+            line = change.start + change.old.len();
+        }
+        for context in &old_lines[line.min(hunk_end)..hunk_end] {
+            body.push_str(&format!(" {}\n", context));
+        }
+        old_count += hunk_end.saturating_sub(line);
+        new_count += hunk_end.saturating_sub(line);
+
+        let new_start = hunk_start as isize + offset;
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n{}",
+            hunk_start + 1,
+            old_count,
+            new_start + 1,
+            new_count,
+            body
+        ));
+        offset += new_count as isize - old_count as isize;
+        i = j + 1;
+    }
+
+    diff
+}
+
+/// Replace regex matches in every file under a directory
+///
+/// Files are found like `grep_files` finds them: .gitignore is honored and
+/// hidden, binary and non-UTF-8 files are skipped.
+///
+/// # Arguments
+/// * `pattern` - The regex to search for
+/// * `replacement` - Replacement text; `$1` or `${name}` insert capture groups
+/// * `path` - The directory (or file) to search in
+/// * `file_pattern` - Optional glob filter, e.g. "*.rs" or "src/**/*.ts"
+/// * `dry_run` - Report the changes without writing them
+///
+/// # Returns
+/// Per-file replacement counts and a diff of the changes
+pub fn replace_in_files(
+    pattern: &str,
+    replacement: &str,
+    path: &str,
+    file_pattern: Option<&str>,
+    dry_run: bool,
+) -> ToolResult<ReplaceResult> {
+    let regex = Regex::new(pattern)
+        .map_err(|e| ToolError::PatternError(format!("Invalid regex: {}", e)))?;
+    let base = Path::new(path);
+    if !base.exists() {
+        return Err(ToolError::PathNotFound(path.to_string()));
+    }
+
+    let mut candidates: Vec<_> = search::walk_builder(base, file_pattern)?
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .map(|entry| entry.into_path())
+        .collect();
+    candidates.sort();
+
+    // Compute every change before writing, so an unreadable file can't leave a
+    // partial replace; a failed write rolls back the files already written
+    let mut pending = Vec::new();
+    for file in candidates {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        if content.contains('\0') || !regex.is_match(&content) {
+            continue;
+        }
+        let (new_content, count, changes) = replace_content(&regex, replacement, &content);
+        if changes.is_empty() {
+            continue;
+        }
+        let relative = file
+            .strip_prefix(base)
+            .ok()
+            .filter(|p| !p.as_os_str().is_empty())
+            .or_else(|| file.file_name().map(Path::new))
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let old_lines: Vec<&str> = content.lines().collect();
+        let diff = unified_diff(&relative, &old_lines, &changes);
+        pending.push((file, relative, content, new_content, count, diff));
+    }
+
+    if !dry_run {
+        for (index, (file, _, _, new_content, ..)) in pending.iter().enumerate() {
+            if let Err(e) = file_ops::write_file_atomic(&file.to_string_lossy(), new_content, false) {
+                for (written, _, original, ..) in &pending[..index] {
+                    if let Err(e) = fs::write(written, original) {
+                        log::error!("Failed to roll back {}: {}", written.display(), e);
+                    }
+                }
+                return Err(ToolError::ExecutionFailed(format!(
+                    "Failed to write {}: {}; all replacements were rolled back",
+                    file.display(),
+                    e
+                )));
+            }
+        }
+    }
+
+    let mut result = ReplaceResult {
+        files: Vec::new(),
+        total_replacements: 0,
+        diff: String::new(),
+        diff_truncated: false,
+        dry_run,
+    };
+    for (_, relative, _, _, count, diff) in pending {
+        if result.diff.len() + diff.len() <= MAX_DIFF_CHARS {
+            result.diff.push_str(&diff);
+        } else {
+            result.diff_truncated = true;
+        }
+        result.total_replacements += count;
+        result.files.push(FileReplacement {
+            path: relative,
+            replacements: count,
+        });
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_replace_in_files() {
+        let dir = tempdir().unwrap();
+        let lib = dir.path().join("lib.rs");
+        let content = "fn old_name() {}\n\nfn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\nfn e() {}\nfn f() {}\n\nfn main() { old_name(); }\n";
+        fs::write(&lib, content).unwrap();
+        fs::write(dir.path().join("notes.md"), "old_name\n").unwrap();
+        let path = dir.path().to_str().unwrap();
+
+        let preview = replace_in_files(r"old_(\w+)", "new_$1", path, Some("*.rs"), true).unwrap();
+        assert_eq!(preview.total_replacements, 2);
+        assert_eq!(preview.files[0].path, "lib.rs");
+        assert_eq!(fs::read_to_string(&lib).unwrap(), content);
+        assert!(preview.diff.contains("@@ -1,4 +1,4 @@\n-fn old_name() {}\n+fn new_name() {}\n"));
+        assert!(preview.diff.contains("@@ -7,4 +7,4 @@\n fn e() {}\n fn f() {}\n \n-fn main() { old_name(); }\n+fn main() { new_name(); }\n"));
+
+        let applied = replace_in_files(r"old_(\w+)", "new_$1", path, Some("*.rs"), false).unwrap();
+        assert_eq!(applied.diff, preview.diff);
+        assert_eq!(fs::read_to_string(&lib).unwrap(), content.replace("old_", "new_"));
+        assert_eq!(fs::read_to_string(dir.path().join("notes.md")).unwrap(), "old_name\n");
+    }
+}
@@ -0,0 +1,222 @@
+//! Tool argument validation against JSON Schema
+//!
+//! Tool calls are checked against the `parameters` schema each tool
+//! declares before they run. Violations are reported with the JSON pointer
+//! of the offending value, so the model can correct its call. The subset of
+//! JSON Schema that tool definitions use is supported: `type`, `properties`,
+//! `required`, `additionalProperties`, `items`, `enum`, `minimum`, `maximum`,
+//! `minItems` and `maxItems`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// One way in which arguments don't match a tool's schema
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value, e.g. "/edits/0/path"; "" for the root
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Name of a value's JSON type, as used by the schema `type` keyword
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        // Integers are numbers, and 2.0 is an integer
+        "number" => value.is_number(),
+        "integer" => {
+            type_name(value) == "integer" || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        other => type_name(value) == other,
+    }
+}
+
+/// Escape a property name for use in a JSON pointer
+fn pointer_segment(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str, violations: &mut Vec<SchemaViolation>) {
+    let mut violation = |message: String| {
+        violations.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| matches_type(value, t)) {
+            violation(format!("expected {}, got {}", types.join(" or "), type_name(value)));
+            // Further keywords would only repeat the type error
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|v| v.as_array()) {
+        if !allowed.contains(value) {
+            let options: Vec<String> = allowed.iter().map(|v| v.to_string()).collect();
+            violation(format!("must be one of {}", options.join(", ")));
+        }
+    }
+
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(|v| v.as_f64()) {
+            if n < min {
+                violation(format!("must be at least {}", min));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(|v| v.as_f64()) {
+            if n > max {
+                violation(format!("must be at most {}", max));
+            }
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            let properties = schema.get("properties").and_then(|v| v.as_object());
+            let required: Vec<&str> = schema
+                .get("required")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str())
+                .collect();
+
+            for name in &required {
+                if !object.contains_key(*name) {
+                    violation(format!("missing required property '{}'", name));
+                }
+            }
+
+            for (key, item) in object {
+                // Models often send null for optional arguments they don't use
+                if item.is_null() && !required.contains(&key.as_str()) {
+                    continue;
+                }
+                let item_path = format!("{}/{}", path, pointer_segment(key));
+                match properties.and_then(|p| p.get(key)) {
+                    Some(property) => validate_at(property, item, &item_path, violations),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => violations.push(SchemaViolation {
+                            path: item_path,
+                            message: "unknown property".to_string(),
+                        }),
+                        Some(extra @ Value::Object(_)) => {
+                            validate_at(extra, item, &item_path, violations)
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(|v| v.as_u64()) {
+                if (items.len() as u64) < min {
+                    violation(format!("must have at least {} items", min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(|v| v.as_u64()) {
+                if items.len() as u64 > max {
+                    violation(format!("must have at most {} items", max));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item_schema, item, &format!("{}/{}", path, i), violations);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Check a value against a JSON schema
+///
+/// # Returns
+/// Every violation found; empty if the value is valid
+pub fn validate_arguments(schema: &Value, value: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    validate_at(schema, value, "", &mut violations);
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_arguments() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string" },
+                "max_results": { "type": "integer", "minimum": 1 },
+                "mode": { "type": "string", "enum": ["replace", "insert"] },
+                "edits": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "old_string": { "type": "string" } },
+                        "required": ["old_string"]
+                    }
+                }
+            },
+            "required": ["path"]
+        });
+
+        let valid = json!({ "path": "src", "max_results": 5.0, "mode": null, "edits": [{ "old_string": "" }] });
+        assert!(validate_arguments(&schema, &valid).is_empty());
+
+        let invalid = json!({ "max_results": 0, "mode": "append", "edits": [{ "old_string": 1 }, {}] });
+        let violations: Vec<String> = validate_arguments(&schema, &invalid)
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                "missing required property 'path'",
+                "/edits/0/old_string: expected string, got integer",
+                "/edits/1: missing required property 'old_string'",
+                "/max_results: must be at least 1",
+                "/mode: must be one of \"replace\", \"insert\"",
+            ]
+        );
+
+        assert_eq!(
+            validate_arguments(&schema, &json!("src")),
+            vec![SchemaViolation {
+                path: String::new(),
+                message: "expected object, got string".to_string()
+            }]
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Screenshot capture
+//!
+//! This module captures the screen or a window as a PNG through the
+//! platform's own utilities (`screencapture`, `grim`, `gnome-screenshot`,
+//! `scrot`, ImageMagick `import`, or PowerShell on Windows), so a screenshot
+//! can be handed to a vision model without the user saving and uploading it.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use super::{ToolError, ToolResult};
+
+/// What to capture
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenshotTarget {
+    /// Every display
+    #[default]
+    Screen,
+    /// A single window: the focused one on Linux, or one the user clicks on macOS
+    Window,
+}
+
+/// Windows has no built-in screenshot utility, so draw the screen with .NET
+#[cfg(target_os = "windows")]
+const POWERSHELL_CAPTURE: &str = "Add-Type -AssemblyName System.Windows.Forms,System.Drawing; \
+    $b = [System.Windows.Forms.SystemInformation]::VirtualScreen; \
+    $bmp = New-Object System.Drawing.Bitmap $b.Width, $b.Height; \
+    $g = [System.Drawing.Graphics]::FromImage($bmp); \
+    $g.CopyFromScreen($b.Left, $b.Top, 0, 0, $bmp.Size); \
+    $bmp.Save($env:OPENSESH_SCREENSHOT, [System.Drawing.Imaging.ImageFormat]::Png)";
+
+/// Commands that write a capture to the given PNG path, in order of preference
+fn capture_commands(target: ScreenshotTarget, output: &str) -> Vec<Vec<String>> {
+    let command = |args: &[&str]| -> Vec<String> {
+        args.iter()
+            .map(|a| a.to_string())
+            .chain(std::iter::once(output.to_string()))
+            .collect()
+    };
+
+    #[cfg(target_os = "macos")]
+    {
+        match target {
+            // -x: no shutter sound
+            ScreenshotTarget::Screen => vec![command(&["screencapture", "-x"])],
+            ScreenshotTarget::Window => vec![command(&["screencapture", "-x", "-o", "-i", "-W"])],
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let _ = command;
+        match target {
+            ScreenshotTarget::Screen => vec![vec![
+                "powershell".to_string(),
+                "-NoProfile".to_string(),
+                "-Command".to_string(),
+                POWERSHELL_CAPTURE.to_string(),
+            ]],
+            ScreenshotTarget::Window => Vec::new(),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        match target {
+            ScreenshotTarget::Screen => vec![
+                command(&["grim"]),
+                command(&["gnome-screenshot", "-f"]),
+                command(&["scrot", "-o"]),
+                command(&["import", "-window", "root"]),
+            ],
+            ScreenshotTarget::Window => vec![
+                command(&["gnome-screenshot", "-w", "-f"]),
+                command(&["scrot", "-o", "-u"]),
+            ],
+        }
+    }
+}
+
+/// Capture the screen or a window
+///
+/// # Returns
+/// The PNG image bytes
+pub fn capture_screenshot(target: ScreenshotTarget) -> ToolResult<Vec<u8>> {
+    let path = std::env::temp_dir().join(format!("opensesh-screenshot-{}.png", uuid::Uuid::new_v4()));
+    let output = path.to_string_lossy().to_string();
+
+    let commands = capture_commands(target, &output);
+    if commands.is_empty() {
+        return Err(ToolError::InvalidArgument(
+            "Capturing a single window is not supported on this platform".to_string(),
+        ));
+    }
+
+    let mut last_error = None;
+    for command in commands {
+        let result = Command::new(&command[0])
+            .args(&command[1..])
+            .env("OPENSESH_SCREENSHOT", &output)
+            .output();
+        match result {
+            Ok(result) if result.status.success() && is_png(&path) => {
+                let image = fs::read(&path);
+                let _ = fs::remove_file(&path);
+                return Ok(image?);
+            }
+            // The macOS window picker exits cleanly without a file when cancelled
+            Ok(result) if result.status.success() => {
+                last_error = Some(format!("{} produced no image", command[0]));
+            }
+            Ok(result) => {
+                last_error = Some(format!(
+                    "{} failed: {}",
+                    command[0],
+                    String::from_utf8_lossy(&result.stderr).trim()
+                ));
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => last_error = Some(format!("Failed to run {}: {}", command[0], e)),
+        }
+        let _ = fs::remove_file(&path);
+    }
+
+    Err(ToolError::ExecutionFailed(last_error.unwrap_or_else(|| {
+        "No screenshot utility found (install grim, gnome-screenshot, scrot or ImageMagick)"
+            .to_string()
+    })))
+}
+
+/// Whether a capture produced a PNG file
+fn is_png(path: &Path) -> bool {
+    fs::read(path)
+        .map(|bytes| bytes.starts_with(b"\x89PNG\r\n\x1a\n"))
+        .unwrap_or(false)
+}
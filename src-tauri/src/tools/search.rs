@@ -3,25 +3,48 @@
 //! This module provides glob-based file searching and grep-like text searching
 //! capabilities that can be used by AI assistants.
 
-use std::path::Path;
+use std::collections::HashMap;
 use std::fs;
-use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 
-use glob::glob;
+use globset::GlobBuilder;
+use grep_matcher::Matcher;
 use grep_regex::RegexMatcher;
-use grep_searcher::{Searcher, Sink, SinkMatch};
+use grep_searcher::{BinaryDetection, Searcher, SearcherBuilder, Sink, SinkMatch};
+use ignore::overrides::OverrideBuilder;
+use ignore::{WalkBuilder, WalkState};
 
-use super::{GlobMatch, SearchResult, ToolError, ToolResult};
+use super::{GlobMatch, SearchLimits, SearchPage, SearchResult, ToolError, ToolResult};
 
 /// Search for files matching a glob pattern
 ///
 /// # Arguments
 /// * `pattern` - The glob pattern to match (e.g., "**/*.rs")
 /// * `base_path` - The base directory to search in
+/// * `limits` - Page of results to return
 ///
 /// # Returns
-/// A vector of matching file paths
-pub fn search_files(pattern: &str, base_path: &str) -> ToolResult<Vec<GlobMatch>> {
+/// A page of matching file paths
+pub fn search_files(
+    pattern: &str,
+    base_path: &str,
+    limits: &SearchLimits,
+) -> ToolResult<SearchPage<GlobMatch>> {
+    search_files_ranked(pattern, base_path, limits, &HashMap::new())
+}
+
+/// Search for files matching a glob pattern, files used often and recently
+/// first
+///
+/// Files are ordered by their frecency, keyed by full path, then by path.
+pub fn search_files_ranked(
+    pattern: &str,
+    base_path: &str,
+    limits: &SearchLimits,
+    frecency: &HashMap<String, f64>,
+) -> ToolResult<SearchPage<GlobMatch>> {
     let base = Path::new(base_path);
 
     if !base.exists() {
@@ -35,221 +58,281 @@ pub fn search_files(pattern: &str, base_path: &str) -> ToolResult<Vec<GlobMatch>
         )));
     }
 
-    // Construct the full pattern
-    let full_pattern = base.join(pattern);
-    let pattern_str = full_pattern.to_string_lossy();
+    // `*` stays within one path component, like shell globs
+    let matcher = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| ToolError::PatternError(e.to_string()))?
+        .compile_matcher();
 
     let mut matches = Vec::new();
 
-    for entry in glob(&pattern_str).map_err(|e| ToolError::PatternError(e.to_string()))? {
-        match entry {
-            Ok(path) => {
-                let is_dir = path.is_dir();
-                matches.push(GlobMatch {
-                    path: path.to_string_lossy().to_string(),
-                    is_dir,
-                });
-            }
+    for entry in project_walker(base, false).build() {
+        let entry = match entry {
+            Ok(entry) => entry,
             Err(e) => {
-                log::warn!("Glob error for entry: {}", e);
+                log::warn!("Walk error for entry: {}", e);
+                continue;
             }
+        };
+        let Ok(relative) = entry.path().strip_prefix(base) else {
+            continue;
+        };
+        if relative.as_os_str().is_empty() || !matcher.is_match(relative) {
+            continue;
         }
+
+        matches.push(GlobMatch {
+            path: entry.path().to_string_lossy().to_string(),
+            is_dir: entry.file_type().is_some_and(|t| t.is_dir()),
+        });
+    }
+
+    // Most frecent first, then by path
+    let score = |m: &GlobMatch| frecency.get(&m.path).copied().unwrap_or(0.0);
+    matches.sort_by(|a, b| score(b).total_cmp(&score(a)).then_with(|| a.path.cmp(&b.path)));
+
+    Ok(SearchPage::paginate(matches, limits))
+}
+
+/// Build a directory walker that honors `.gitignore`, `.ignore` and the
+/// global git excludes file, whether or not the directory is a git repository
+///
+/// Hidden files are skipped unless `include_hidden` is set; the `.git`
+/// directory itself is always skipped.
+pub fn project_walker(base: &Path, include_hidden: bool) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(base);
+    builder
+        .hidden(!include_hidden)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git");
+    builder
+}
+
+/// Build a walker for grep with an optional glob filter (e.g. "*.rs" or
+/// "src/**/*.ts")
+pub(crate) fn walk_builder(base: &Path, file_pattern: Option<&str>) -> ToolResult<WalkBuilder> {
+    let mut builder = project_walker(base, false);
+
+    if let Some(pattern) = file_pattern {
+        let overrides = OverrideBuilder::new(base)
+            .add(pattern)
+            .and_then(|b| b.build())
+            .map_err(|e| ToolError::PatternError(format!("Invalid file pattern: {}", e)))?;
+        builder.overrides(overrides);
     }
 
-    // Sort by path
-    matches.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(builder)
+}
+
+/// Run `search_file` over every file under `base` on parallel threads,
+/// returning the results ordered by path
+fn search_parallel<T, F>(
+    base: &Path,
+    file_pattern: Option<&str>,
+    search_file: F,
+) -> ToolResult<Vec<T>>
+where
+    T: Send,
+    F: Fn(&Path) -> Vec<T> + Sync,
+{
+    let per_file: Mutex<Vec<(PathBuf, Vec<T>)>> = Mutex::new(Vec::new());
+
+    walk_builder(base, file_pattern)?.build_parallel().run(|| {
+        let per_file = &per_file;
+        let search_file = &search_file;
+        Box::new(move |entry| {
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
+            if entry.file_type().is_some_and(|t| t.is_file()) {
+                let results = search_file(entry.path());
+                if !results.is_empty() {
+                    per_file
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push((entry.into_path(), results));
+                }
+            }
+            WalkState::Continue
+        })
+    });
+
+    let mut per_file = per_file.into_inner().unwrap_or_else(|e| e.into_inner());
+    per_file.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(per_file.into_iter().flat_map(|(_, results)| results).collect())
+}
+
+/// Searcher that reports line numbers and skips binary files
+fn new_searcher() -> Searcher {
+    SearcherBuilder::new()
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .line_number(true)
+        .build()
+}
 
-    Ok(matches)
+/// Create a regex matcher from a query
+fn new_matcher(query: &str) -> ToolResult<RegexMatcher> {
+    RegexMatcher::new(query).map_err(|e| ToolError::PatternError(format!("Invalid regex: {}", e)))
 }
 
 /// Search for text in files using a regex pattern
 ///
+/// Files are searched in parallel, honoring .gitignore and skipping hidden
+/// and binary files.
+///
 /// # Arguments
 /// * `query` - The regex pattern to search for
-/// * `path` - The directory to search in
+/// * `path` - The directory (or file) to search in
 /// * `file_pattern` - Optional glob pattern to filter files
+/// * `limits` - Page of results to return and the per-file match cap
 ///
 /// # Returns
-/// A vector of search results with line numbers and content
+/// A page of search results with line numbers and content
 pub fn grep_files(
     query: &str,
     path: &str,
     file_pattern: Option<&str>,
-) -> ToolResult<Vec<SearchResult>> {
+    limits: &SearchLimits,
+) -> ToolResult<SearchPage<SearchResult>> {
     let base = Path::new(path);
 
     if !base.exists() {
         return Err(ToolError::PathNotFound(path.to_string()));
     }
 
-    // Create the regex matcher
-    let matcher = RegexMatcher::new(query)
-        .map_err(|e| ToolError::PatternError(format!("Invalid regex: {}", e)))?;
-
-    let mut results = Vec::new();
+    let matcher = new_matcher(query)?;
+    let files_truncated = AtomicUsize::new(0);
 
-    // Get files to search
-    let files: Vec<String> = if let Some(pattern) = file_pattern {
-        search_files(pattern, path)?
-            .into_iter()
-            .filter(|m| !m.is_dir)
-            .map(|m| m.path)
-            .collect()
-    } else {
-        // Search all files recursively
-        collect_files_recursive(base)?
-    };
-
-    for file_path in files {
-        let file_results = search_in_file(&matcher, &file_path)?;
-        results.extend(file_results);
-    }
+    let results = search_parallel(base, file_pattern, |file_path| {
+        capped_matches(&matcher, file_path, limits, &files_truncated)
+    })?;
 
-    Ok(results)
+    let mut page = SearchPage::paginate(results, limits);
+    page.files_truncated = files_truncated.into_inner();
+    Ok(page)
 }
 
-/// Collect all files recursively from a directory
-fn collect_files_recursive(path: &Path) -> ToolResult<Vec<String>> {
-    use walkdir::WalkDir;
-
-    let mut files = Vec::new();
-
-    for entry in WalkDir::new(path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-    {
-        // Skip binary files and hidden directories
-        let entry_path = entry.path();
-
-        // Skip hidden files and directories
-        if entry_path
-            .components()
-            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
-        {
-            continue;
-        }
-
-        // Skip common binary/generated directories
-        let path_str = entry_path.to_string_lossy();
-        if path_str.contains("/target/")
-            || path_str.contains("/node_modules/")
-            || path_str.contains("/.git/")
-            || path_str.contains("/dist/")
-            || path_str.contains("/build/")
-        {
-            continue;
-        }
-
-        // Check if file is likely text
-        if is_likely_text_file(entry_path) {
-            files.push(entry_path.to_string_lossy().to_string());
-        }
-    }
-
-    Ok(files)
+/// Search for text in a given list of files using a regex pattern
+///
+/// Used when the files that could match are already known, e.g. from a text
+/// index. Results follow the order of `files`.
+pub fn grep_paths(
+    query: &str,
+    files: &[PathBuf],
+    limits: &SearchLimits,
+) -> ToolResult<SearchPage<SearchResult>> {
+    let matcher = new_matcher(query)?;
+    let files_truncated = AtomicUsize::new(0);
+
+    let results: Vec<SearchResult> = files
+        .iter()
+        .flat_map(|file_path| capped_matches(&matcher, file_path, limits, &files_truncated))
+        .collect();
+
+    let mut page = SearchPage::paginate(results, limits);
+    page.files_truncated = files_truncated.into_inner();
+    Ok(page)
 }
 
-/// Check if a file is likely a text file based on extension
-fn is_likely_text_file(path: &Path) -> bool {
-    let text_extensions = [
-        "rs", "js", "ts", "jsx", "tsx", "py", "rb", "go", "java", "c", "cpp", "h", "hpp",
-        "cs", "php", "swift", "kt", "scala", "clj", "ex", "exs", "erl", "hrl",
-        "html", "htm", "css", "scss", "sass", "less", "xml", "json", "yaml", "yml",
-        "toml", "ini", "cfg", "conf", "md", "txt", "rst", "adoc",
-        "sh", "bash", "zsh", "fish", "ps1", "bat", "cmd",
-        "sql", "graphql", "prisma",
-        "dockerfile", "makefile", "cmake",
-        "gitignore", "gitattributes", "editorconfig",
-        "env", "env.local", "env.example",
-        "lock", // Cargo.lock, package-lock.json, etc.
-    ];
-
-    // Check extension
-    if let Some(ext) = path.extension() {
-        let ext_lower = ext.to_string_lossy().to_lowercase();
-        if text_extensions.iter().any(|&e| e == ext_lower) {
-            return true;
-        }
-    }
-
-    // Check for extensionless files that are commonly text
-    if let Some(name) = path.file_name() {
-        let name_lower = name.to_string_lossy().to_lowercase();
-        let text_filenames = [
-            "makefile", "dockerfile", "jenkinsfile", "rakefile",
-            "gemfile", "brewfile", "procfile", "vagrantfile",
-            "cargo.toml", "cargo.lock", "package.json", "package-lock.json",
-            "tsconfig.json", "jsconfig.json", ".gitignore", ".gitattributes",
-            ".editorconfig", ".prettierrc", ".eslintrc", "readme", "license",
-            "changelog", "contributing", "authors", "maintainers",
-        ];
-        if text_filenames.iter().any(|&f| name_lower == f || name_lower.starts_with(f)) {
-            return true;
+/// Matches in a file, cut to `limits.max_per_file`
+///
+/// Files that can't be searched are skipped.
+fn capped_matches(
+    matcher: &RegexMatcher,
+    file_path: &Path,
+    limits: &SearchLimits,
+    files_truncated: &AtomicUsize,
+) -> Vec<SearchResult> {
+    let mut results = search_in_file(matcher, file_path).unwrap_or_else(|e| {
+        log::debug!("Skipping {}: {}", file_path.display(), e);
+        Vec::new()
+    });
+    if let Some(max) = limits.max_per_file {
+        if results.len() > max {
+            results.truncate(max);
+            files_truncated.fetch_add(1, Ordering::Relaxed);
         }
     }
-
-    false
+    results
 }
 
 /// Search for matches in a single file
-fn search_in_file(matcher: &RegexMatcher, file_path: &str) -> ToolResult<Vec<SearchResult>> {
-    let path = Path::new(file_path);
-
-    if !path.exists() {
-        return Ok(Vec::new());
-    }
+///
+/// Each match produces its own result, so a line with several matches is
+/// reported several times with different offsets.
+fn search_in_file(matcher: &RegexMatcher, file_path: &Path) -> ToolResult<Vec<SearchResult>> {
+    let mut sink = LineSink::new(matcher);
+    new_searcher().search_path(matcher, file_path, &mut sink)?;
 
+    let path = file_path.to_string_lossy().to_string();
     let mut results = Vec::new();
-
-    // Use a simple line-by-line search for better control
-    let file = fs::File::open(path)?;
-    let reader = std::io::BufReader::new(file);
-
-    for (line_num, line_result) in reader.lines().enumerate() {
-        let line = match line_result {
-            Ok(l) => l,
-            Err(_) => continue, // Skip lines that can't be read as UTF-8
-        };
-
-        // Check if line matches
-        let mut sink = MatchSink::new();
-        let result = Searcher::new().search_slice(matcher, line.as_bytes(), &mut sink);
-
-        if result.is_ok() && !sink.matches.is_empty() {
-            for (start, end) in sink.matches {
-                results.push(SearchResult {
-                    path: file_path.to_string(),
-                    line_number: (line_num + 1) as u64,
-                    line_content: line.clone(),
-                    match_start: Some(start),
-                    match_end: Some(end),
-                });
-            }
+    for line in sink.lines {
+        for &(byte_start, byte_end) in &line.matches {
+            results.push(SearchResult {
+                path: path.clone(),
+                line_number: line.line_number,
+                line_content: line.content.clone(),
+                match_start: Some(line.content[..byte_start].chars().count()),
+                match_end: Some(line.content[..byte_end].chars().count()),
+                match_byte_start: Some(byte_start),
+                match_byte_end: Some(byte_end),
+            });
         }
     }
-
     Ok(results)
 }
 
-/// Sink for collecting match positions
-struct MatchSink {
+/// A matching line and the byte ranges of the matches in it
+struct MatchedLine {
+    line_number: u64,
+    content: String,
     matches: Vec<(usize, usize)>,
 }
 
-impl MatchSink {
-    fn new() -> Self {
-        Self { matches: Vec::new() }
+/// Sink collecting matching lines with their line numbers and match offsets
+struct LineSink<'m> {
+    matcher: &'m RegexMatcher,
+    lines: Vec<MatchedLine>,
+}
+
+impl<'m> LineSink<'m> {
+    fn new(matcher: &'m RegexMatcher) -> Self {
+        Self {
+            matcher,
+            lines: Vec::new(),
+        }
     }
 }
 
-impl Sink for MatchSink {
+impl Sink for LineSink<'_> {
     type Error = std::io::Error;
 
     fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
-        // For now, just record that there was a match
-        // The actual match position is in the bytes
-        self.matches.push((0, mat.bytes().len()));
+        let content = String::from_utf8_lossy(mat.bytes())
+            .trim_end_matches(['\r', '\n'])
+            .to_string();
+
+        // Find every match in the line, ignoring any that fall in the
+        // terminator or would split a character after lossy decoding
+        let mut matches = Vec::new();
+        self.matcher
+            .find_iter(content.as_bytes(), |m| {
+                if m.end() <= content.len()
+                    && content.is_char_boundary(m.start())
+                    && content.is_char_boundary(m.end())
+                {
+                    matches.push((m.start(), m.end()));
+                }
+                true
+            })
+            .map_err(std::io::Error::other)?;
+
+        self.lines.push(MatchedLine {
+            line_number: mat.line_number().unwrap_or(0),
+            content,
+            matches,
+        });
         Ok(true)
     }
 }
@@ -267,30 +350,11 @@ pub fn grep_files_with_context(
         return Err(ToolError::PathNotFound(path.to_string()));
     }
 
-    // Create the regex matcher
-    let matcher = RegexMatcher::new(query)
-        .map_err(|e| ToolError::PatternError(format!("Invalid regex: {}", e)))?;
+    let matcher = new_matcher(query)?;
 
-    let mut results = Vec::new();
-
-    // Get files to search
-    let files: Vec<String> = if let Some(pattern) = file_pattern {
-        search_files(pattern, path)?
-            .into_iter()
-            .filter(|m| !m.is_dir)
-            .map(|m| m.path)
-            .collect()
-    } else {
-        collect_files_recursive(base)?
-    };
-
-    for file_path in files {
-        if let Ok(file_results) = search_in_file_with_context(&matcher, &file_path, context_lines) {
-            results.extend(file_results);
-        }
-    }
-
-    Ok(results)
+    search_parallel(base, file_pattern, |file_path| {
+        search_in_file_with_context(&matcher, file_path, context_lines).unwrap_or_default()
+    })
 }
 
 /// Search result with context lines
@@ -306,50 +370,43 @@ pub struct SearchResultWithContext {
 /// Search in a file with context lines
 fn search_in_file_with_context(
     matcher: &RegexMatcher,
-    file_path: &str,
+    file_path: &Path,
     context_lines: usize,
 ) -> ToolResult<Vec<SearchResultWithContext>> {
-    let path = Path::new(file_path);
-
-    if !path.exists() {
-        return Ok(Vec::new());
-    }
-
-    let content = fs::read_to_string(path)?;
+    let content = fs::read_to_string(file_path)?;
     let lines: Vec<&str> = content.lines().collect();
-    let mut results = Vec::new();
 
-    for (line_num, line) in lines.iter().enumerate() {
-        let mut sink = MatchSink::new();
-        let result = Searcher::new().search_slice(matcher, line.as_bytes(), &mut sink);
+    let mut sink = LineSink::new(matcher);
+    new_searcher().search_slice(matcher, content.as_bytes(), &mut sink)?;
 
-        if result.is_ok() && !sink.matches.is_empty() {
+    let path = file_path.to_string_lossy().to_string();
+    let results = sink
+        .lines
+        .into_iter()
+        .filter_map(|line| {
+            let line_number = line.line_number;
+            let line_content = line.content;
+            let line_num = (line_number as usize).checked_sub(1)?;
             let start = line_num.saturating_sub(context_lines);
             let end = (line_num + context_lines + 1).min(lines.len());
 
-            let before_context: Vec<String> = lines[start..line_num]
-                .iter()
-                .map(|s| s.to_string())
-                .collect();
-
-            let after_context: Vec<String> = if line_num + 1 < end {
-                lines[line_num + 1..end]
+            Some(SearchResultWithContext {
+                path: path.clone(),
+                line_number,
+                line_content,
+                before_context: lines[start..line_num.min(lines.len())]
                     .iter()
                     .map(|s| s.to_string())
-                    .collect()
-            } else {
-                Vec::new()
-            };
-
-            results.push(SearchResultWithContext {
-                path: file_path.to_string(),
-                line_number: (line_num + 1) as u64,
-                line_content: line.to_string(),
-                before_context,
-                after_context,
-            });
-        }
-    }
+                    .collect(),
+                after_context: lines
+                    .get(line_num + 1..end)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            })
+        })
+        .collect();
 
     Ok(results)
 }
@@ -369,8 +426,31 @@ mod tests {
         fs::write(dir.path().join("test2.rs"), "fn test() {}").unwrap();
         fs::write(dir.path().join("other.txt"), "hello").unwrap();
 
-        let results = search_files("*.rs", dir.path().to_str().unwrap()).unwrap();
-        assert_eq!(results.len(), 2);
+        let results = search_files("*.rs", dir.path().to_str().unwrap(), &SearchLimits::default()).unwrap();
+        assert_eq!(results.count, 2);
+    }
+
+    #[test]
+    fn test_search_files_respects_ignore_files() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/gen")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        fs::write(dir.path().join(".ignore"), "gen/\n").unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+        fs::write(dir.path().join("src/gen/out.rs"), "").unwrap();
+        fs::write(dir.path().join("src/debug.log"), "").unwrap();
+
+        let results = search_files("**/*", dir.path().to_str().unwrap(), &SearchLimits::default())
+            .unwrap()
+            .results;
+        let paths: Vec<String> = results
+            .iter()
+            .map(|m| {
+                let path = Path::new(&m.path).strip_prefix(dir.path()).unwrap();
+                path.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+        assert_eq!(paths, vec!["src", "src/lib.rs"]);
     }
 
     #[test]
@@ -381,7 +461,77 @@ mod tests {
         fs::write(dir.path().join("test1.rs"), "fn main() {\n    println!(\"hello\");\n}").unwrap();
         fs::write(dir.path().join("test2.rs"), "fn test() {\n    println!(\"world\");\n}").unwrap();
 
-        let results = grep_files("println", dir.path().to_str().unwrap(), Some("*.rs")).unwrap();
-        assert_eq!(results.len(), 2);
+        let results = grep_files(
+            "println",
+            dir.path().to_str().unwrap(),
+            Some("*.rs"),
+            &SearchLimits::default(),
+        )
+        .unwrap();
+        assert_eq!(results.count, 2);
+    }
+
+    #[test]
+    fn test_grep_files_respects_gitignore_and_skips_binary() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("generated")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "generated/\n").unwrap();
+        fs::write(dir.path().join("generated/out.rs"), "needle\n").unwrap();
+        fs::write(dir.path().join("data.bin"), b"needle\x00\x01").unwrap();
+        fs::write(dir.path().join("b.rs"), "a\nneedle\n").unwrap();
+        fs::write(dir.path().join("a.rs"), "needle\n").unwrap();
+
+        let results = grep_files("needle", dir.path().to_str().unwrap(), None, &SearchLimits::default())
+            .unwrap()
+            .results;
+        let found: Vec<(String, u64)> = results
+            .iter()
+            .map(|r| {
+                let name = Path::new(&r.path).file_name().unwrap().to_string_lossy();
+                (name.to_string(), r.line_number)
+            })
+            .collect();
+        assert_eq!(found, vec![("a.rs".to_string(), 1), ("b.rs".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_grep_files_reports_every_match_offset() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "héllo foo, foo\n").unwrap();
+
+        let results = grep_files("foo", dir.path().to_str().unwrap(), None, &SearchLimits::default())
+            .unwrap()
+            .results;
+        let offsets: Vec<(Option<usize>, Option<usize>, Option<usize>)> = results
+            .iter()
+            .map(|r| (r.match_start, r.match_end, r.match_byte_start))
+            .collect();
+
+        assert_eq!(
+            offsets,
+            vec![(Some(6), Some(9), Some(7)), (Some(11), Some(14), Some(12))]
+        );
+        assert_eq!(results[0].line_content, "héllo foo, foo");
+    }
+
+    #[test]
+    fn test_grep_files_pagination_and_per_file_cap() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "x\nx\nx\nx\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "x\n").unwrap();
+        let path = dir.path().to_str().unwrap();
+
+        let limits = SearchLimits {
+            max_results: 2,
+            offset: 1,
+            max_per_file: Some(3),
+        };
+        let page = grep_files("x", path, None, &limits).unwrap();
+
+        assert_eq!(page.total, 4);
+        assert_eq!(page.count, 2);
+        assert!(page.has_more);
+        assert_eq!(page.files_truncated, 1);
+        assert_eq!(page.results[0].line_number, 2);
     }
 }
@@ -0,0 +1,91 @@
+//! Semantic code search tool
+//!
+//! This module exposes a project's semantic index to AI assistants. The tool
+//! is registered by the `reindex_project` command once an index exists.
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use super::{RiskClass, Tool, ToolContext, ToolDefinition, ToolError, ToolResult};
+use crate::index::SemanticIndex;
+use crate::providers::embeddings::EmbeddingProvider;
+
+/// Name of the semantic search tool
+pub const SEMANTIC_SEARCH_TOOL: &str = "semantic_search";
+
+/// Default number of results returned by a search
+const DEFAULT_SEMANTIC_RESULTS: usize = 10;
+
+/// Upper bound for a caller-supplied result limit
+const MAX_SEMANTIC_RESULTS: usize = 50;
+
+/// Searches a project's semantic index
+pub struct SemanticSearchTool {
+    index: Arc<SemanticIndex>,
+    provider: Arc<dyn EmbeddingProvider>,
+}
+
+impl SemanticSearchTool {
+    pub fn new(index: Arc<SemanticIndex>, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        Self { index, provider }
+    }
+}
+
+impl Tool for SemanticSearchTool {
+    fn name(&self) -> &str {
+        SEMANTIC_SEARCH_TOOL
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: SEMANTIC_SEARCH_TOOL.to_string(),
+            description: "Search the project by meaning rather than exact text. Describe what the code does (e.g. 'where are API keys loaded') and get the most relevant code chunks with their file paths and line ranges".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "A natural language description of the code to find"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Optional maximum number of results (default 10, max 50)"
+                    }
+                },
+                "required": ["query"]
+            }),
+        }
+    }
+
+    fn risk(&self) -> RiskClass {
+        RiskClass::Read
+    }
+
+    fn execute(&self, args: &Value, _context: &ToolContext) -> ToolResult<Value> {
+        let query = args
+            .get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidArgument("Missing 'query' argument".to_string()))?;
+
+        let limit = args
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_SEMANTIC_RESULTS)
+            .clamp(1, MAX_SEMANTIC_RESULTS);
+
+        // Tools run on blocking threads, so the query can be embedded in place
+        let runtime = tokio::runtime::Handle::try_current().map_err(|_| {
+            ToolError::ExecutionFailed("Semantic search needs an async runtime".to_string())
+        })?;
+        let results = runtime
+            .block_on(self.index.search(self.provider.as_ref(), query, limit))
+            .map_err(|e| ToolError::ExecutionFailed(e.to_string()))?;
+
+        Ok(json!({
+            "success": true,
+            "results": results
+        }))
+    }
+}
@@ -0,0 +1,425 @@
+//! File snapshots for undoing AI edits
+//!
+//! Before a write tool changes a file, it records the file's original
+//! contents in the session's `SnapshotStore`. Each tool call becomes one
+//! undoable edit, so the user can roll back an AI's changes without git.
+//!
+//! The edits of each agent turn are grouped into a checkpoint, so the
+//! workspace can be rewound to how it was after any turn.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::{ToolError, ToolResult};
+
+/// Files larger than this are not snapshotted (and so cannot be undone)
+pub const MAX_SNAPSHOT_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Edits kept per session; the oldest are forgotten first
+pub const MAX_SESSION_EDITS: usize = 200;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The state of a file before an edit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    pub path: PathBuf,
+    /// Original contents; `None` if the edit created the file
+    #[serde(skip)]
+    original: Option<Vec<u8>>,
+    pub created: bool,
+}
+
+impl FileSnapshot {
+    /// Whether the file differs from the snapshot
+    fn changed(&self) -> bool {
+        match &self.original {
+            Some(content) => fs::read(&self.path).ok().as_ref() != Some(content),
+            None => self.path.exists(),
+        }
+    }
+
+    /// Put the file back the way it was
+    fn restore(&self) -> ToolResult<()> {
+        match &self.original {
+            Some(content) => {
+                if let Some(parent) = self.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&self.path, content)?;
+            }
+            None if self.path.exists() => fs::remove_file(&self.path)?,
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// One tool call's changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditRecord {
+    /// ID of the tool call that made the edit
+    pub id: String,
+    pub tool: String,
+    /// Unix timestamp (seconds)
+    pub timestamp: u64,
+    pub files: Vec<FileSnapshot>,
+}
+
+/// The workspace after an agent turn that changed files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub id: String,
+    /// IDs of the turn's tool calls that changed files, in order
+    pub edits: Vec<String>,
+    /// Unix timestamp (seconds)
+    pub timestamp: u64,
+    /// Files the turn changed
+    pub files: Vec<PathBuf>,
+}
+
+impl Checkpoint {
+    /// Whether the turn's last edit is still recorded, so the edits made
+    /// after it can be undone
+    fn restorable(&self, records: &[EditRecord]) -> bool {
+        self.edits
+            .last()
+            .is_some_and(|id| records.iter().any(|r| &r.id == id))
+    }
+}
+
+/// Undoable edits made during one session, oldest first
+#[derive(Debug, Default)]
+pub struct SnapshotStore {
+    records: Mutex<Vec<EditRecord>>,
+    checkpoints: Mutex<Vec<Checkpoint>>,
+}
+
+impl SnapshotStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn records(&self) -> MutexGuard<'_, Vec<EditRecord>> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Checkpoints that can still be restored; the rest are forgotten
+    fn checkpoints_for(&self, records: &[EditRecord]) -> MutexGuard<'_, Vec<Checkpoint>> {
+        let mut checkpoints = self.checkpoints.lock().unwrap_or_else(|e| e.into_inner());
+        checkpoints.retain(|c| c.restorable(records));
+        checkpoints
+    }
+
+    /// Start recording the edit made by a tool call
+    pub fn begin(&self, id: &str, tool: &str) {
+        let mut records = self.records();
+        records.push(EditRecord {
+            id: id.to_string(),
+            tool: tool.to_string(),
+            timestamp: now(),
+            files: Vec::new(),
+        });
+        if records.len() > MAX_SESSION_EDITS {
+            records.remove(0);
+        }
+    }
+
+    /// Finish the current edit, forgetting it if the tool changed no files
+    ///
+    /// Tools snapshot files before validating their edits, so files a failed
+    /// call left untouched are dropped here.
+    pub fn finish(&self) {
+        let mut records = self.records();
+        let Some(record) = records.last_mut() else {
+            return;
+        };
+        record.files.retain(FileSnapshot::changed);
+        if record.files.is_empty() {
+            records.pop();
+        }
+    }
+
+    /// Record a file's contents before the current edit changes it
+    ///
+    /// Only the first snapshot of a file within an edit is kept, so undo
+    /// restores the state from before the tool call.
+    pub fn snapshot(&self, path: &Path) -> ToolResult<()> {
+        let mut records = self.records();
+        let Some(record) = records.last_mut() else {
+            return Ok(());
+        };
+        if record.files.iter().any(|f| f.path == path) {
+            return Ok(());
+        }
+
+        let original = match fs::metadata(path) {
+            Ok(metadata) if metadata.len() > MAX_SNAPSHOT_FILE_BYTES => {
+                log::warn!(
+                    "Not snapshotting {} ({} bytes); this edit cannot be undone",
+                    path.display(),
+                    metadata.len()
+                );
+                return Ok(());
+            }
+            Ok(metadata) if metadata.is_file() => Some(fs::read(path)?),
+            Ok(_) => {
+                return Err(ToolError::InvalidArgument(format!(
+                    "Path is not a file: {}",
+                    path.display()
+                )))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        record.files.push(FileSnapshot {
+            path: path.to_path_buf(),
+            created: original.is_none(),
+            original,
+        });
+        Ok(())
+    }
+
+    /// The recorded edits, oldest first
+    pub fn edits(&self) -> Vec<EditRecord> {
+        self.records().clone()
+    }
+
+    /// Group the edits made since the last checkpoint into a new one
+    ///
+    /// # Returns
+    /// The new checkpoint, or `None` if no files changed since the last one
+    pub fn checkpoint(&self) -> Option<Checkpoint> {
+        let records = self.records();
+        let mut checkpoints = self.checkpoints_for(&records);
+
+        let start = checkpoints
+            .last()
+            .and_then(|c| c.edits.last())
+            .and_then(|id| records.iter().position(|r| &r.id == id))
+            .map_or(0, |i| i + 1);
+        let turn = &records[start..];
+        if turn.is_empty() {
+            return None;
+        }
+
+        let mut files: Vec<PathBuf> = Vec::new();
+        for file in turn.iter().flat_map(|r| &r.files) {
+            if !files.contains(&file.path) {
+                files.push(file.path.clone());
+            }
+        }
+        let checkpoint = Checkpoint {
+            id: uuid::Uuid::new_v4().to_string(),
+            edits: turn.iter().map(|r| r.id.clone()).collect(),
+            timestamp: now(),
+            files,
+        };
+        checkpoints.push(checkpoint.clone());
+        Some(checkpoint)
+    }
+
+    /// The checkpoints that can be restored, oldest first
+    pub fn checkpoints(&self) -> Vec<Checkpoint> {
+        let records = self.records();
+        let checkpoints = self.checkpoints_for(&records);
+        checkpoints.clone()
+    }
+
+    /// Undo every edit made after a checkpoint, newest first
+    ///
+    /// Later checkpoints are forgotten, since their edits no longer apply.
+    ///
+    /// # Returns
+    /// The paths that were restored
+    pub fn restore_checkpoint(&self, checkpoint_id: &str) -> ToolResult<Vec<PathBuf>> {
+        let mut records = self.records();
+        let mut checkpoints = self.checkpoints_for(&records);
+
+        let index = checkpoints
+            .iter()
+            .position(|c| c.id == checkpoint_id)
+            .ok_or_else(|| {
+                ToolError::InvalidArgument(format!("Unknown checkpoint: {}", checkpoint_id))
+            })?;
+        let last_edit = checkpoints[index].edits.last();
+        let keep = records
+            .iter()
+            .position(|r| Some(&r.id) == last_edit)
+            .map_or(0, |i| i + 1);
+
+        let mut restored = Vec::new();
+        while records.len() > keep {
+            let record = records.pop().expect("records is longer than keep");
+            for file in &record.files {
+                file.restore()?;
+                if !restored.contains(&file.path) {
+                    restored.push(file.path.clone());
+                }
+            }
+        }
+        checkpoints.truncate(index + 1);
+        Ok(restored)
+    }
+
+    /// Undo the most recent edit
+    ///
+    /// The edit is kept if restoring it fails, so it can be undone again.
+    ///
+    /// # Returns
+    /// The edit that was undone, or `None` if there was nothing to undo
+    pub fn undo_last(&self) -> ToolResult<Option<EditRecord>> {
+        let mut records = self.records();
+        let Some(record) = records.last() else {
+            return Ok(None);
+        };
+        for file in &record.files {
+            file.restore()?;
+        }
+        Ok(records.pop())
+    }
+
+    /// Undo every edit in the session, newest first
+    ///
+    /// # Returns
+    /// The paths that were restored
+    pub fn undo_all(&self) -> ToolResult<Vec<PathBuf>> {
+        let records = std::mem::take(&mut *self.records());
+        let mut restored = Vec::new();
+        let mut seen = HashSet::new();
+
+        for record in records.iter().rev() {
+            for file in &record.files {
+                file.restore()?;
+                if seen.insert(file.path.clone()) {
+                    restored.push(file.path.clone());
+                }
+            }
+        }
+        Ok(restored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_undo_restores_and_removes_files() {
+        let dir = tempdir().unwrap();
+        let existing = dir.path().join("main.rs");
+        let created = dir.path().join("new.rs");
+        fs::write(&existing, "v1").unwrap();
+
+        let store = SnapshotStore::new();
+        store.begin("call-1", "write_file");
+        store.snapshot(&existing).unwrap();
+        fs::write(&existing, "v2").unwrap();
+        store.finish();
+
+        store.begin("call-2", "multi_edit");
+        store.snapshot(&existing).unwrap();
+        store.snapshot(&created).unwrap();
+        fs::write(&existing, "v3").unwrap();
+        fs::write(&created, "new").unwrap();
+        store.finish();
+
+        store.begin("call-3", "read_file");
+        store.finish();
+        assert_eq!(store.edits().len(), 2);
+
+        let undone = store.undo_last().unwrap().unwrap();
+        assert_eq!(undone.id, "call-2");
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "v2");
+        assert!(!created.exists());
+
+        fs::write(&existing, "v4").unwrap();
+        store.begin("call-4", "write_file");
+        store.snapshot(&existing).unwrap();
+        fs::write(&existing, "v5").unwrap();
+        store.finish();
+
+        assert_eq!(store.undo_all().unwrap(), vec![existing.clone()]);
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "v1");
+        assert!(store.undo_last().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_restore_checkpoint() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("lib.rs");
+        let created = dir.path().join("util.rs");
+        fs::write(&file, "v1").unwrap();
+
+        let store = SnapshotStore::new();
+        let write = |id: &str, path: &Path, content: &str| {
+            store.begin(id, "write_file");
+            store.snapshot(path).unwrap();
+            fs::write(path, content).unwrap();
+            store.finish();
+        };
+
+        write("call-1", &file, "v2");
+        let first = store.checkpoint().unwrap();
+        assert!(store.checkpoint().is_none());
+
+        write("call-2", &file, "v3");
+        write("call-3", &created, "new");
+        let second = store.checkpoint().unwrap();
+        assert_eq!(second.edits, vec!["call-2", "call-3"]);
+        assert_eq!(second.files, vec![file.clone(), created.clone()]);
+
+        write("call-4", &file, "v4");
+        store.checkpoint().unwrap();
+
+        let restored = store.restore_checkpoint(&first.id).unwrap();
+        assert_eq!(restored, vec![file.clone(), created.clone()]);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v2");
+        assert!(!created.exists());
+        assert_eq!(store.edits().len(), 1);
+        assert_eq!(store.checkpoints().len(), 1);
+        assert!(store.restore_checkpoint(&second.id).is_err());
+
+        // Undoing a turn's edits forgets its checkpoint
+        store.undo_last().unwrap();
+        assert!(store.checkpoints().is_empty());
+    }
+
+    #[test]
+    fn test_failed_undo_keeps_edit() {
+        let dir = tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        let file = sub.join("lib.rs");
+        fs::create_dir(&sub).unwrap();
+        fs::write(&file, "v1").unwrap();
+
+        let store = SnapshotStore::new();
+        store.begin("call-1", "write_file");
+        store.snapshot(&file).unwrap();
+        fs::write(&file, "v2").unwrap();
+        store.finish();
+
+        // A file where the directory was stops the restore
+        fs::remove_dir_all(&sub).unwrap();
+        fs::write(&sub, "blocker").unwrap();
+        assert!(store.undo_last().is_err());
+        assert_eq!(store.edits().len(), 1);
+
+        fs::remove_file(&sub).unwrap();
+        assert_eq!(store.undo_last().unwrap().unwrap().id, "call-1");
+        assert_eq!(fs::read_to_string(&file).unwrap(), "v1");
+    }
+}
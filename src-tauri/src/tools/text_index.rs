@@ -0,0 +1,355 @@
+//! Trigram index for fast text search
+//!
+//! Records which files contain each three-byte sequence, so a grep only has
+//! to read the files that could match instead of walking the whole project.
+//! Literal text the regex requires is split into trigrams; files containing
+//! all of them are the candidates. Queries with no required literals, such
+//! as case-insensitive ones, fall back to searching every indexed file.
+//!
+//! The index is kept current by the project watcher, which passes it the
+//! paths that changed.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use ignore::overrides::OverrideBuilder;
+use regex_syntax::hir::{Hir, HirKind};
+
+use super::{
+    project_walker, search, SearchLimits, SearchPage, SearchResult, ToolError, ToolResult,
+};
+
+/// Files larger than this aren't split into trigrams; they're searched by
+/// every query instead
+pub const MAX_INDEXED_FILE_BYTES: u64 = 4 * 1024 * 1024;
+
+type Trigram = [u8; 3];
+
+/// A file known to the index
+struct IndexedFile {
+    path: PathBuf,
+    /// The file's trigrams, or `None` if it wasn't indexed and must always
+    /// be searched
+    trigrams: Option<Vec<Trigram>>,
+}
+
+#[derive(Default)]
+struct IndexData {
+    /// Files by ID; removed files leave an empty slot
+    files: Vec<Option<IndexedFile>>,
+    ids: HashMap<PathBuf, u32>,
+    postings: HashMap<Trigram, HashSet<u32>>,
+}
+
+impl IndexData {
+    fn remove(&mut self, path: &Path) {
+        let Some(id) = self.ids.remove(path) else {
+            return;
+        };
+        let Some(file) = self.files[id as usize].take() else {
+            return;
+        };
+        for trigram in file.trigrams.into_iter().flatten() {
+            if let Some(ids) = self.postings.get_mut(&trigram) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.postings.remove(&trigram);
+                }
+            }
+        }
+    }
+
+    fn insert(&mut self, path: PathBuf) {
+        self.remove(&path);
+        let Ok(metadata) = fs::metadata(&path) else {
+            return;
+        };
+        let trigrams = if metadata.len() > MAX_INDEXED_FILE_BYTES {
+            None
+        } else {
+            let Ok(bytes) = fs::read(&path) else {
+                return;
+            };
+            file_trigrams(&bytes)
+        };
+
+        let id = self.files.len() as u32;
+        for trigram in trigrams.iter().flatten() {
+            self.postings.entry(*trigram).or_default().insert(id);
+        }
+        self.ids.insert(path.clone(), id);
+        self.files.push(Some(IndexedFile { path, trigrams }));
+    }
+}
+
+/// The distinct trigrams of a file's content
+///
+/// Content that isn't plain UTF-8 text gets `None`: grep may transcode it or
+/// stop at a NUL byte, so its bytes can't be trusted to rule the file out.
+fn file_trigrams(bytes: &[u8]) -> Option<Vec<Trigram>> {
+    if bytes.contains(&0) || std::str::from_utf8(bytes).is_err() {
+        return None;
+    }
+    let trigrams: HashSet<Trigram> = bytes.windows(3).map(|w| [w[0], w[1], w[2]]).collect();
+    Some(trigrams.into_iter().collect())
+}
+
+/// Literal byte strings every match of a regex must contain
+fn required_literals(hir: &Hir, literals: &mut Vec<Vec<u8>>) {
+    match hir.kind() {
+        HirKind::Literal(literal) => literals.push(literal.0.to_vec()),
+        HirKind::Capture(capture) => required_literals(&capture.sub, literals),
+        HirKind::Repetition(repetition) if repetition.min > 0 => {
+            required_literals(&repetition.sub, literals)
+        }
+        HirKind::Concat(parts) => {
+            // Adjacent literals form one longer literal
+            let mut run = Vec::new();
+            for part in parts {
+                if let HirKind::Literal(literal) = part.kind() {
+                    run.extend_from_slice(&literal.0);
+                    continue;
+                }
+                if !run.is_empty() {
+                    literals.push(std::mem::take(&mut run));
+                }
+                required_literals(part, literals);
+            }
+            if !run.is_empty() {
+                literals.push(run);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Trigrams every match of a query must contain
+///
+/// # Returns
+/// The trigrams, or `None` if the query doesn't require any
+fn query_trigrams(query: &str) -> ToolResult<Option<HashSet<Trigram>>> {
+    let hir = regex_syntax::Parser::new()
+        .parse(query)
+        .map_err(|e| ToolError::PatternError(format!("Invalid regex: {}", e)))?;
+    let mut literals = Vec::new();
+    required_literals(&hir, &mut literals);
+    let trigrams: HashSet<Trigram> = literals
+        .iter()
+        .flat_map(|literal| literal.windows(3).map(|w| [w[0], w[1], w[2]]))
+        .collect();
+    Ok((!trigrams.is_empty()).then_some(trigrams))
+}
+
+/// A trigram index of the text files in a directory
+pub struct TextIndex {
+    root: PathBuf,
+    data: RwLock<IndexData>,
+}
+
+impl TextIndex {
+    /// Index every file under `root`, skipping those ignored by `.gitignore`
+    pub fn build(root: &Path) -> ToolResult<Self> {
+        let root = root.canonicalize()?;
+        let mut data = IndexData::default();
+        for entry in project_walker(&root, false).build().flatten() {
+            if entry.file_type().is_some_and(|t| t.is_file()) {
+                data.insert(entry.into_path());
+            }
+        }
+        Ok(Self {
+            root,
+            data: RwLock::new(data),
+        })
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, IndexData> {
+        self.data.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, IndexData> {
+        self.data.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The indexed directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Number of files in the index
+    pub fn len(&self) -> usize {
+        self.read().ids.len()
+    }
+
+    /// Whether the index holds no files
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Re-index files and directories that changed
+    ///
+    /// # Arguments
+    /// * `paths` - Changed or removed paths, relative to the root
+    pub fn update(&self, paths: &[String]) {
+        let mut data = self.write();
+        for relative in paths {
+            let path = self.root.join(relative);
+            if path.is_dir() {
+                for entry in project_walker(&path, false).build().flatten() {
+                    if entry.file_type().is_some_and(|t| t.is_file()) {
+                        data.insert(entry.into_path());
+                    }
+                }
+            } else if path.is_file() {
+                data.insert(path);
+            } else {
+                // Removed: drop the file, or everything under the directory
+                let removed: Vec<PathBuf> = data
+                    .ids
+                    .keys()
+                    .filter(|p| p.starts_with(&path))
+                    .cloned()
+                    .collect();
+                for path in removed {
+                    data.remove(&path);
+                }
+            }
+        }
+    }
+
+    /// Indexed files under `base` that could match a query, sorted by path
+    fn candidates(&self, query: &str, base: &Path) -> ToolResult<Vec<PathBuf>> {
+        let trigrams = query_trigrams(query)?;
+        let data = self.read();
+
+        let mut paths: Vec<PathBuf> = match &trigrams {
+            Some(trigrams) => {
+                let lists: Option<Vec<&HashSet<u32>>> =
+                    trigrams.iter().map(|t| data.postings.get(t)).collect();
+                let mut ids: HashSet<u32> = match lists {
+                    Some(mut lists) => {
+                        lists.sort_by_key(|ids| ids.len());
+                        lists[0]
+                            .iter()
+                            .copied()
+                            .filter(|id| lists[1..].iter().all(|ids| ids.contains(id)))
+                            .collect()
+                    }
+                    // Some trigram appears in no file
+                    None => HashSet::new(),
+                };
+                // Files that weren't split into trigrams can't be ruled out
+                ids.extend(data.files.iter().enumerate().filter_map(|(id, f)| {
+                    f.as_ref()
+                        .filter(|f| f.trigrams.is_none())
+                        .map(|_| id as u32)
+                }));
+                ids.iter()
+                    .filter_map(|id| data.files[*id as usize].as_ref())
+                    .map(|f| f.path.clone())
+                    .collect()
+            }
+            None => data.ids.keys().cloned().collect(),
+        };
+        paths.retain(|p| p.starts_with(base));
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Whether this index can answer a search of `path`
+    pub fn covers(&self, path: &Path) -> bool {
+        path.canonicalize().is_ok_and(|p| p.starts_with(&self.root))
+    }
+
+    /// Search for text in indexed files using a regex pattern
+    ///
+    /// Behaves like `search::grep_files`, but only reads the files that could
+    /// match. Results are reported under `path` as given.
+    pub fn grep(
+        &self,
+        query: &str,
+        path: &str,
+        file_pattern: Option<&str>,
+        limits: &SearchLimits,
+    ) -> ToolResult<SearchPage<SearchResult>> {
+        let base = Path::new(path);
+        let canonical_base = base
+            .canonicalize()
+            .map_err(|_| ToolError::PathNotFound(path.to_string()))?;
+
+        let mut files = self.candidates(query, &canonical_base)?;
+        if let Some(pattern) = file_pattern {
+            let overrides = OverrideBuilder::new(&canonical_base)
+                .add(pattern)
+                .and_then(|b| b.build())
+                .map_err(|e| ToolError::PatternError(format!("Invalid file pattern: {}", e)))?;
+            files.retain(|f| overrides.matched(f, false).is_whitelist());
+        }
+        let files: Vec<PathBuf> = files
+            .into_iter()
+            .map(|f| match f.strip_prefix(&canonical_base) {
+                Ok(relative) if !relative.as_os_str().is_empty() => base.join(relative),
+                _ => base.to_path_buf(),
+            })
+            .collect();
+        search::grep_paths(query, &files, limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_index_narrows_and_follows_changes() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::write(root.join("src/a.rs"), "fn parse_config() {}\n").unwrap();
+        fs::write(root.join("src/b.rs"), "fn render() {}\n").unwrap();
+        fs::write(root.join("notes.md"), "parse_config is documented here\n").unwrap();
+
+        let index = TextIndex::build(root).unwrap();
+        let base = root.canonicalize().unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(
+            index.candidates(r"fn parse_\w+\(", &base).unwrap(),
+            vec![base.join("src/a.rs")]
+        );
+        // No required literal, so every file is a candidate
+        assert_eq!(index.candidates("(?i)RENDER", &base).unwrap().len(), 3);
+
+        let path = root.to_string_lossy();
+        let page = index
+            .grep(
+                "parse_config",
+                &path,
+                Some("*.rs"),
+                &SearchLimits::default(),
+            )
+            .unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(
+            page.results[0].path,
+            root.join("src/a.rs").to_string_lossy()
+        );
+
+        fs::write(root.join("src/b.rs"), "fn parse_config_v2() {}\n").unwrap();
+        fs::remove_file(root.join("notes.md")).unwrap();
+        index.update(&["src/b.rs".to_string(), "notes.md".to_string()]);
+        assert_eq!(
+            index.candidates("parse_config", &base).unwrap(),
+            vec![base.join("src/a.rs"), base.join("src/b.rs")]
+        );
+        assert_eq!(
+            index
+                .grep("parse_config", &path, None, &SearchLimits::default())
+                .unwrap()
+                .total,
+            2
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Task list for planning agent work
+//!
+//! The model keeps a per-session list of tasks it creates, updates and
+//! checks off as it works, so the frontend can show the agent's progress.
+//! Each change is saved to the session's todo file in the app data directory.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+
+use super::{session_file_stem, ToolError, ToolResult};
+
+/// Subdirectory of the app data directory holding todo lists
+pub const TODO_DIR: &str = "todos";
+
+/// Most tasks a list may hold
+pub const MAX_TODOS: usize = 100;
+
+/// Progress of a task
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Completed,
+}
+
+/// One task in the list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TodoItem {
+    pub id: String,
+    pub content: String,
+    pub status: TodoStatus,
+}
+
+/// A change to the task list
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum TodoAction {
+    /// Show the list without changing it
+    List,
+    /// Append tasks
+    Add { items: Vec<String> },
+    /// Change a task's text or status
+    Update {
+        id: String,
+        #[serde(default)]
+        content: Option<String>,
+        #[serde(default)]
+        status: Option<TodoStatus>,
+    },
+    /// Mark a task completed
+    Complete { id: String },
+    Remove { id: String },
+    /// Remove every task
+    Clear,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TodoData {
+    next_id: u64,
+    items: Vec<TodoItem>,
+}
+
+/// A session's task list
+#[derive(Debug, Default)]
+pub struct TodoList {
+    data: Mutex<TodoData>,
+    /// Where the list is saved; `None` keeps it in memory only
+    path: Option<PathBuf>,
+}
+
+/// Path of a session's todo list under the app data directory
+pub fn todo_list_path(data_dir: &Path, session_id: &str) -> PathBuf {
+    data_dir
+        .join(TODO_DIR)
+        .join(format!("{}.json", session_file_stem(session_id)))
+}
+
+impl TodoList {
+    /// Create an empty, in-memory list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a list from its file, starting empty if it doesn't exist or is invalid
+    pub fn load(path: PathBuf) -> Self {
+        let data = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("Ignoring invalid todo list {}: {}", path.display(), e);
+                TodoData::default()
+            }),
+            Err(_) => TodoData::default(),
+        };
+        Self {
+            data: Mutex::new(data),
+            path: Some(path),
+        }
+    }
+
+    fn data(&self) -> MutexGuard<'_, TodoData> {
+        self.data.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The tasks, in the order they were added
+    pub fn items(&self) -> Vec<TodoItem> {
+        self.data().items.clone()
+    }
+
+    /// Apply a change and save the list
+    ///
+    /// # Returns
+    /// The tasks after the change
+    pub fn apply(&self, action: TodoAction) -> ToolResult<Vec<TodoItem>> {
+        let mut data = self.data();
+        let find = |items: &mut Vec<TodoItem>, id: &str| {
+            items
+                .iter()
+                .position(|item| item.id == id)
+                .ok_or_else(|| ToolError::InvalidArgument(format!("No task with id '{}'", id)))
+        };
+
+        match action {
+            TodoAction::List => return Ok(data.items.clone()),
+            TodoAction::Add { items } => {
+                if data.items.len() + items.len() > MAX_TODOS {
+                    return Err(ToolError::InvalidArgument(format!(
+                        "A task list holds at most {} tasks",
+                        MAX_TODOS
+                    )));
+                }
+                for content in items {
+                    data.next_id += 1;
+                    let id = data.next_id.to_string();
+                    data.items.push(TodoItem {
+                        id,
+                        content,
+                        status: TodoStatus::Pending,
+                    });
+                }
+            }
+            TodoAction::Update {
+                id,
+                content,
+                status,
+            } => {
+                let index = find(&mut data.items, &id)?;
+                let item = &mut data.items[index];
+                if let Some(content) = content {
+                    item.content = content;
+                }
+                if let Some(status) = status {
+                    item.status = status;
+                }
+            }
+            TodoAction::Complete { id } => {
+                let index = find(&mut data.items, &id)?;
+                data.items[index].status = TodoStatus::Completed;
+            }
+            TodoAction::Remove { id } => {
+                let index = find(&mut data.items, &id)?;
+                data.items.remove(index);
+            }
+            TodoAction::Clear => data.items.clear(),
+        }
+
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, serde_json::to_string_pretty(&*data)?)?;
+        }
+        Ok(data.items.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn action(value: serde_json::Value) -> TodoAction {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_todo_actions_persist() {
+        let dir = tempdir().unwrap();
+        let path = todo_list_path(dir.path(), "session-1");
+        let todos = TodoList::load(path.clone());
+
+        todos
+            .apply(action(json!({ "action": "add", "items": ["Write parser", "Add tests"] })))
+            .unwrap();
+        todos
+            .apply(action(json!({ "action": "update", "id": "1", "status": "in_progress" })))
+            .unwrap();
+        todos.apply(action(json!({ "action": "complete", "id": "2" }))).unwrap();
+        assert!(todos.apply(action(json!({ "action": "remove", "id": "9" }))).is_err());
+
+        let reloaded = TodoList::load(path);
+        let statuses: Vec<TodoStatus> = reloaded.items().iter().map(|t| t.status).collect();
+        assert_eq!(statuses, vec![TodoStatus::InProgress, TodoStatus::Completed]);
+
+        reloaded.apply(action(json!({ "action": "remove", "id": "1" }))).unwrap();
+        let items = reloaded.apply(action(json!({ "action": "add", "items": ["Ship it"] }))).unwrap();
+        assert_eq!(items.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec!["2", "3"]);
+    }
+}
@@ -0,0 +1,255 @@
+//! Directory tree rendering for the tools system
+//!
+//! This module renders a directory as an indented text tree (like `tree -L 3`),
+//! which orients an AI assistant in a project for far fewer tokens than a
+//! recursive JSON listing.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{project_walker, ToolError, ToolResult};
+
+/// Default depth rendered below the root
+pub const DEFAULT_TREE_DEPTH: usize = 3;
+
+/// Default number of entries shown per directory before truncating
+pub const DEFAULT_TREE_ENTRIES_PER_DIR: usize = 50;
+
+/// Total entries rendered before the whole tree is cut off
+const MAX_TREE_ENTRIES: usize = 2_000;
+
+/// Options controlling how much of a tree is rendered
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TreeOptions {
+    /// Levels rendered below the root
+    pub max_depth: usize,
+    /// Entries shown per directory; the rest are summarized
+    pub max_entries_per_dir: usize,
+    pub include_hidden: bool,
+    /// Skip entries excluded by `.gitignore`, `.ignore` and global excludes
+    pub respect_ignore: bool,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_TREE_DEPTH,
+            max_entries_per_dir: DEFAULT_TREE_ENTRIES_PER_DIR,
+            include_hidden: false,
+            respect_ignore: true,
+        }
+    }
+}
+
+/// A rendered directory tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryTree {
+    pub tree: String,
+    /// Directories and files shown in the tree
+    pub directories: usize,
+    pub files: usize,
+    /// Whether any directory was truncated or left unexpanded
+    pub truncated: bool,
+}
+
+struct TreeEntry {
+    name: String,
+    path: PathBuf,
+    is_dir: bool,
+}
+
+struct TreeRenderer<'a> {
+    children: HashMap<PathBuf, Vec<TreeEntry>>,
+    options: &'a TreeOptions,
+    output: String,
+    directories: usize,
+    files: usize,
+    rendered: usize,
+    truncated: bool,
+}
+
+impl TreeRenderer<'_> {
+    fn child_count(&self, dir: &Path) -> usize {
+        self.children.get(dir).map_or(0, Vec::len)
+    }
+
+    fn render(&mut self, dir: &Path, prefix: &str, depth: usize) {
+        let Some(entries) = self.children.remove(dir) else {
+            return;
+        };
+        let shown = entries.len().min(self.options.max_entries_per_dir);
+        let hidden = entries.len() - shown;
+
+        for (i, entry) in entries.iter().take(shown).enumerate() {
+            if self.rendered >= MAX_TREE_ENTRIES {
+                self.output
+                    .push_str(&format!("{}└── ... (tree truncated)\n", prefix));
+                self.truncated = true;
+                return;
+            }
+            self.rendered += 1;
+
+            let last = i + 1 == shown && hidden == 0;
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+
+            if entry.is_dir {
+                self.directories += 1;
+                let count = self.child_count(&entry.path);
+                if depth + 1 >= self.options.max_depth && count > 0 {
+                    // Not expanded: say how much is inside
+                    self.output.push_str(&format!(
+                        "{}{}{}/ ({} entries)\n",
+                        prefix, branch, entry.name, count
+                    ));
+                    self.truncated = true;
+                } else {
+                    self.output
+                        .push_str(&format!("{}{}{}/\n", prefix, branch, entry.name));
+                    self.render(&entry.path, &format!("{}{}", prefix, indent), depth + 1);
+                }
+            } else {
+                self.files += 1;
+                self.output
+                    .push_str(&format!("{}{}{}\n", prefix, branch, entry.name));
+            }
+        }
+
+        if hidden > 0 {
+            self.output
+                .push_str(&format!("{}└── ... {} more\n", prefix, hidden));
+            self.truncated = true;
+        }
+    }
+}
+
+/// Render a directory as an indented tree
+///
+/// # Arguments
+/// * `path` - The directory to render
+/// * `options` - Depth, truncation and filtering options
+///
+/// # Returns
+/// The rendered tree with counts of what it shows
+pub fn directory_tree(path: &str, options: &TreeOptions) -> ToolResult<DirectoryTree> {
+    let root = Path::new(path);
+    if !root.is_dir() {
+        return Err(ToolError::PathNotFound(path.to_string()));
+    }
+
+    let mut builder = project_walker(root, options.include_hidden);
+    builder
+        .git_ignore(options.respect_ignore)
+        .git_global(options.respect_ignore)
+        .git_exclude(options.respect_ignore)
+        .ignore(options.respect_ignore)
+        // One level deeper than rendered, to count what unexpanded directories hold
+        .max_depth(Some(options.max_depth.max(1) + 1));
+
+    let mut children: HashMap<PathBuf, Vec<TreeEntry>> = HashMap::new();
+    for entry in builder.build().flatten() {
+        if entry.depth() == 0 {
+            continue;
+        }
+        let Some(parent) = entry.path().parent() else {
+            continue;
+        };
+        children
+            .entry(parent.to_path_buf())
+            .or_default()
+            .push(TreeEntry {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path: entry.path().to_path_buf(),
+                is_dir: entry.file_type().is_some_and(|t| t.is_dir()),
+            });
+    }
+
+    // Directories first, then files, both alphabetically
+    for entries in children.values_mut() {
+        entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        });
+    }
+
+    let root_name = root
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+
+    let mut renderer = TreeRenderer {
+        children,
+        options,
+        output: format!("{}/\n", root_name),
+        directories: 0,
+        files: 0,
+        rendered: 0,
+        truncated: false,
+    };
+    renderer.render(root, "", 0);
+
+    let summary = format!(
+        "\n{} directories, {} files",
+        renderer.directories, renderer.files
+    );
+    renderer.output.push_str(&summary);
+
+    Ok(DirectoryTree {
+        tree: renderer.output,
+        directories: renderer.directories,
+        files: renderer.files,
+        truncated: renderer.truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_directory_tree_depth_and_truncation() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("project");
+        fs::create_dir_all(root.join("src/tools/deep")).unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("Cargo.toml"), "").unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        fs::write(root.join("src/tools/deep/a.rs"), "").unwrap();
+        for i in 0..3 {
+            fs::write(root.join(format!("src/tools/m{}.rs", i)), "").unwrap();
+        }
+
+        let options = TreeOptions {
+            max_depth: 3,
+            max_entries_per_dir: 3,
+            ..TreeOptions::default()
+        };
+        let tree = directory_tree(root.to_str().unwrap(), &options).unwrap();
+
+        let expected = "\
+project/
+├── src/
+│   ├── tools/
+│   │   ├── deep/ (1 entries)
+│   │   ├── m0.rs
+│   │   ├── m1.rs
+│   │   └── ... 1 more
+│   └── lib.rs
+└── Cargo.toml
+
+3 directories, 4 files";
+        assert_eq!(tree.tree, expected);
+        assert!(tree.truncated);
+    }
+}
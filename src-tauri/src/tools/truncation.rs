@@ -0,0 +1,205 @@
+//! Size limits for tool results
+//!
+//! A tool result bigger than `MAX_TOOL_RESULT_CHARS` is cut down to its head
+//! and tail before it reaches the model, so one huge listing can't fill the
+//! context window. The full result is kept in a `ToolResultCache` under a
+//! continuation token, and the `read_tool_result` tool pages through it.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde_json::{json, Value};
+
+use super::{RiskClass, Tool, ToolContext, ToolDefinition, ToolError, ToolResult};
+
+/// Name of the tool that reads the rest of a truncated result
+pub const READ_TOOL_RESULT_TOOL: &str = "read_tool_result";
+
+/// Longest tool result sent to the model, in characters
+pub const MAX_TOOL_RESULT_CHARS: usize = 50_000;
+
+/// Longest page `read_tool_result` returns, leaving room for its JSON wrapper
+const MAX_RESULT_PAGE_CHARS: usize = MAX_TOOL_RESULT_CHARS / 2;
+
+/// Full results kept for continuation; the oldest are dropped first
+const MAX_CACHED_RESULTS: usize = 20;
+
+/// Full text of recently truncated tool results, keyed by continuation token
+#[derive(Debug, Default)]
+pub struct ToolResultCache {
+    entries: Mutex<VecDeque<(String, Arc<str>)>>,
+}
+
+impl ToolResultCache {
+    fn entries(&self) -> MutexGuard<'_, VecDeque<(String, Arc<str>)>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Keep a full result, returning its continuation token
+    fn insert(&self, content: String) -> String {
+        let token = uuid::Uuid::new_v4().to_string();
+        let mut entries = self.entries();
+        entries.push_back((token.clone(), content.into()));
+        if entries.len() > MAX_CACHED_RESULTS {
+            entries.pop_front();
+        }
+        token
+    }
+
+    fn get(&self, token: &str) -> Option<Arc<str>> {
+        self.entries()
+            .iter()
+            .find(|(t, _)| t == token)
+            .map(|(_, content)| content.clone())
+    }
+
+    /// Truncate a tool result that is too long for the model
+    ///
+    /// The head and tail are kept, since that's where errors and summaries
+    /// usually are. The omitted part is described with its size and the
+    /// token and offset to pass to `read_tool_result`.
+    pub fn truncate(&self, content: String) -> String {
+        let char_count = content.chars().count();
+        if char_count <= MAX_TOOL_RESULT_CHARS {
+            return content;
+        }
+
+        let head_chars = MAX_TOOL_RESULT_CHARS / 2;
+        let tail_chars = MAX_TOOL_RESULT_CHARS / 4;
+        let head: String = content.chars().take(head_chars).collect();
+        let tail: String = content.chars().skip(char_count - tail_chars).collect();
+        // Counted in characters, like the offset
+        let omitted = char_count - head_chars - tail_chars;
+        let token = self.insert(content);
+
+        format!(
+            "{}\n[... {} characters omitted; call {} with continuation_token \"{}\" and offset {} to read them ...]\n{}",
+            head, omitted, READ_TOOL_RESULT_TOOL, token, head_chars, tail
+        )
+    }
+}
+
+/// Pages through tool results that were too long to send whole
+pub struct ReadToolResultTool {
+    cache: Arc<ToolResultCache>,
+}
+
+impl ReadToolResultTool {
+    pub fn new(cache: Arc<ToolResultCache>) -> Self {
+        Self { cache }
+    }
+}
+
+impl Tool for ReadToolResultTool {
+    fn name(&self) -> &str {
+        READ_TOOL_RESULT_TOOL
+    }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: READ_TOOL_RESULT_TOOL.to_string(),
+            description: "Read part of a tool result that was too long and was truncated, using the continuation token and offset given in the truncated result".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "continuation_token": {
+                        "type": "string",
+                        "description": "The token from the truncated result"
+                    },
+                    "offset": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "Character offset to start reading at"
+                    },
+                    "max_chars": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Optional maximum characters to return (default and max 25000)"
+                    }
+                },
+                "required": ["continuation_token", "offset"]
+            }),
+        }
+    }
+
+    fn risk(&self) -> RiskClass {
+        RiskClass::Read
+    }
+
+    fn execute(&self, args: &Value, _context: &ToolContext) -> ToolResult<Value> {
+        let token = args
+            .get("continuation_token")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                ToolError::InvalidArgument("Missing 'continuation_token' argument".to_string())
+            })?;
+        let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        let max_chars = args
+            .get("max_chars")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(MAX_RESULT_PAGE_CHARS)
+            .min(MAX_RESULT_PAGE_CHARS);
+
+        let content = self.cache.get(token).ok_or_else(|| {
+            ToolError::InvalidArgument(format!(
+                "Unknown or expired continuation token '{}'; run the original tool again",
+                token
+            ))
+        })?;
+
+        let total_chars = content.chars().count();
+        let page: String = content.chars().skip(offset).take(max_chars).collect();
+        let end = offset + page.chars().count();
+
+        Ok(json!({
+            "success": true,
+            "content": page,
+            "offset": offset,
+            "next_offset": (end < total_chars).then_some(end),
+            "total_chars": total_chars
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_and_continue() {
+        let cache = Arc::new(ToolResultCache::default());
+        assert_eq!(cache.truncate("short".to_string()), "short");
+
+        let content: String = (0..MAX_TOOL_RESULT_CHARS * 2)
+            .map(|i| char::from(b'a' + (i % 26) as u8))
+            .collect();
+        let truncated = cache.truncate(content.clone());
+        assert!(truncated.len() < MAX_TOOL_RESULT_CHARS);
+        assert!(truncated.starts_with(&content[..100]));
+        assert!(truncated.ends_with(&content[content.len() - 100..]));
+
+        let token = truncated
+            .split("continuation_token \"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap();
+        let tool = ReadToolResultTool::new(cache.clone());
+        let page = tool
+            .execute(
+                &json!({ "continuation_token": token, "offset": MAX_TOOL_RESULT_CHARS / 2 }),
+                &ToolContext::default(),
+            )
+            .unwrap();
+        let start = MAX_TOOL_RESULT_CHARS / 2;
+        assert_eq!(page["content"], content[start..start + MAX_RESULT_PAGE_CHARS]);
+        assert_eq!(page["next_offset"], start + MAX_RESULT_PAGE_CHARS);
+
+        let wide = cache.truncate("é".repeat(MAX_TOOL_RESULT_CHARS * 2));
+        let omitted = MAX_TOOL_RESULT_CHARS * 2 - start - MAX_TOOL_RESULT_CHARS / 4;
+        assert!(wide.contains(&format!("[... {} characters omitted;", omitted)));
+
+        let expired = json!({ "continuation_token": "missing", "offset": 0 });
+        assert!(tool.execute(&expired, &ToolContext::default()).is_err());
+    }
+}
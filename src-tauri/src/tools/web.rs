@@ -0,0 +1,165 @@
+//! Web fetching for the tools system
+//!
+//! This module lets AI assistants read documentation pages. Only http(s)
+//! URLs are allowed, downloads are capped in size, and HTML is converted to
+//! readable markdown-style text.
+
+use std::io::Read;
+use std::time::Duration;
+
+use reqwest::blocking::Client;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use super::{ToolError, ToolResult};
+
+/// URL schemes the fetch tool may request
+pub const ALLOWED_URL_SCHEMES: &[&str] = &["http", "https"];
+
+/// Maximum number of bytes downloaded from a URL
+pub const MAX_FETCH_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Default maximum characters of content returned to the model
+pub const DEFAULT_FETCH_MAX_CHARS: usize = 50_000;
+
+/// Overall deadline for a fetch, including redirects
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Line width used when rendering HTML as text
+const TEXT_WIDTH: usize = 100;
+
+/// Result of fetching a URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchResult {
+    /// Final URL after redirects
+    pub url: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    pub content: String,
+    /// Whether the download or the returned content was cut short
+    pub truncated: bool,
+}
+
+/// Parse a URL and check it against the allowed schemes
+pub fn validate_url(url: &str) -> ToolResult<Url> {
+    let parsed = Url::parse(url)
+        .map_err(|e| ToolError::InvalidArgument(format!("Invalid URL '{}': {}", url, e)))?;
+
+    if !ALLOWED_URL_SCHEMES.contains(&parsed.scheme()) {
+        return Err(ToolError::PermissionDenied(format!(
+            "URL scheme '{}' is not allowed; use one of: {}",
+            parsed.scheme(),
+            ALLOWED_URL_SCHEMES.join(", ")
+        )));
+    }
+
+    Ok(parsed)
+}
+
+/// Convert an HTML document to readable text with markdown-style markup
+pub fn html_to_text(html: &[u8]) -> ToolResult<String> {
+    html2text::from_read(html, TEXT_WIDTH)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to convert HTML: {}", e)))
+}
+
+/// Whether a content type is text the model can read as-is
+fn is_plain_text(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || ["json", "xml", "javascript", "yaml", "toml"]
+            .iter()
+            .any(|kind| content_type.contains(kind))
+}
+
+/// Fetch a URL and return its content as text
+///
+/// # Arguments
+/// * `url` - The http(s) URL to fetch
+/// * `max_chars` - Maximum characters of content to return
+///
+/// # Returns
+/// The response status and content; HTML is converted to text
+pub fn fetch_url(url: &str, max_chars: usize) -> ToolResult<FetchResult> {
+    let parsed = validate_url(url)?;
+
+    let client = Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent(concat!("OpenSesh/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to create HTTP client: {}", e)))?;
+
+    let response = client
+        .get(parsed)
+        .send()
+        .map_err(|e| ToolError::ExecutionFailed(format!("Request failed: {}", e)))?;
+
+    // Redirects may have led somewhere we don't allow
+    let final_url = validate_url(response.url().as_str())?;
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_ascii_lowercase());
+
+    let mut body = Vec::new();
+    response
+        .take(MAX_FETCH_BYTES + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| ToolError::ExecutionFailed(format!("Failed to read response: {}", e)))?;
+    let mut truncated = body.len() as u64 > MAX_FETCH_BYTES;
+    body.truncate(MAX_FETCH_BYTES as usize);
+
+    let content = match content_type.as_deref() {
+        Some(ct) if ct.contains("html") => html_to_text(&body)?,
+        Some(ct) if is_plain_text(ct) => String::from_utf8_lossy(&body).to_string(),
+        Some(ct) => {
+            return Err(ToolError::InvalidArgument(format!(
+                "Unsupported content type: {}",
+                ct
+            )))
+        }
+        None if body.trim_ascii_start().starts_with(b"<") => html_to_text(&body)?,
+        None => String::from_utf8_lossy(&body).to_string(),
+    };
+
+    let content = if content.chars().count() > max_chars {
+        truncated = true;
+        content.chars().take(max_chars).collect()
+    } else {
+        content
+    };
+
+    Ok(FetchResult {
+        url: final_url.to_string(),
+        status,
+        content_type,
+        content,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_url_scheme_policy() {
+        assert!(validate_url("https://docs.rs/serde").is_ok());
+        assert!(matches!(
+            validate_url("file:///etc/passwd"),
+            Err(ToolError::PermissionDenied(_))
+        ));
+        assert!(matches!(
+            validate_url("not a url"),
+            Err(ToolError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let text = html_to_text(b"<h1>Title</h1><p>Some <b>bold</b> text</p>").unwrap();
+        assert!(text.contains("# Title"));
+        assert!(text.contains("**bold**"));
+    }
+}
@@ -0,0 +1,253 @@
+//! Project file watcher
+//!
+//! Watches the project root for changes made outside the app, such as edits
+//! in another editor or a `git checkout`, so the frontend can refresh and the
+//! semantic index can drop stale content. Events are debounced and paths
+//! ignored by `.gitignore` files, or inside `.git`, are left out; changes to
+//! the repository's index, HEAD or refs are only flagged, since they change
+//! `git status`.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde::Serialize;
+
+use crate::tools::project_walker;
+
+/// Event emitted to the frontend when files in the project change
+pub const FS_CHANGE_EVENT: &str = "fs-change";
+
+/// Files and directories in `.git` whose changes can change `git status`
+const GIT_STATE_PATHS: &[&str] = &["HEAD", "index", "refs", "packed-refs", "MERGE_HEAD", "info"];
+
+/// How long the project must be quiet before changes are reported
+pub const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Files that changed in the project, relative to its root
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FsChange {
+    /// The watched root the files are in
+    pub root: String,
+    /// Files and directories that were created or modified
+    pub changed: Vec<String>,
+    /// Files and directories that no longer exist
+    pub removed: Vec<String>,
+    /// Whether the repository's index, HEAD or refs changed
+    pub git: bool,
+}
+
+impl FsChange {
+    /// Whether any files outside `.git` changed
+    pub fn has_files(&self) -> bool {
+        !self.changed.is_empty() || !self.removed.is_empty()
+    }
+}
+
+/// The ignore rules of a project: one matcher per `.gitignore` or `.ignore`
+/// file, each relative to its own directory
+struct IgnoreRules {
+    matchers: Vec<Gitignore>,
+}
+
+impl IgnoreRules {
+    /// Read every ignore file in the project, plus `.git/info/exclude`
+    fn load(root: &Path) -> Self {
+        let mut files: Vec<PathBuf> = project_walker(root, true)
+            .build()
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name();
+                (name == ".gitignore" || name == ".ignore")
+                    && entry.file_type().is_some_and(|t| t.is_file())
+            })
+            .map(|entry| entry.into_path())
+            .collect();
+        files.push(root.join(".git").join("info").join("exclude"));
+
+        let matchers = files
+            .iter()
+            .filter_map(|file| {
+                let dir = file.parent()?;
+                // `.git/info/exclude` applies to the whole repository
+                let dir = if dir.ends_with(".git/info") {
+                    root
+                } else {
+                    dir
+                };
+                let mut builder = GitignoreBuilder::new(dir);
+                if let Some(e) = builder.add(file) {
+                    if file.exists() {
+                        log::warn!("Invalid ignore file {}: {}", file.display(), e);
+                    }
+                }
+                builder.build().ok()
+            })
+            .collect();
+        Self { matchers }
+    }
+
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.matchers.iter().any(|matcher| {
+            path.starts_with(matcher.path())
+                && matcher
+                    .matched_path_or_any_parents(path, is_dir)
+                    .is_ignore()
+        })
+    }
+}
+
+/// Sort changed paths into an `FsChange`, leaving out ignored ones
+///
+/// # Returns
+/// The change, or `None` if every path was ignored and git state didn't change
+fn classify_changes(root: &Path, rules: &IgnoreRules, paths: &[PathBuf]) -> Option<FsChange> {
+    let mut change = FsChange::default();
+
+    for path in paths {
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        if let Ok(git_path) = relative.strip_prefix(".git") {
+            change.git |= GIT_STATE_PATHS.iter().any(|p| git_path.starts_with(p));
+            continue;
+        }
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        if rules.is_ignored(path, path.is_dir()) {
+            continue;
+        }
+
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        let list = if path.exists() {
+            &mut change.changed
+        } else {
+            &mut change.removed
+        };
+        if !list.contains(&relative) {
+            list.push(relative);
+        }
+    }
+
+    change.changed.sort();
+    change.removed.sort();
+    (change.has_files() || change.git).then_some(change)
+}
+
+/// Watches a project for changes until dropped
+pub struct ProjectWatcher {
+    root: PathBuf,
+    _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+impl ProjectWatcher {
+    /// Start watching a project
+    ///
+    /// # Arguments
+    /// * `root` - The project root, watched recursively
+    /// * `on_change` - Called on the watcher's thread with each debounced change
+    pub fn start<F>(
+        root: &Path,
+        mut on_change: F,
+    ) -> Result<Self, notify_debouncer_mini::notify::Error>
+    where
+        F: FnMut(FsChange) + Send + 'static,
+    {
+        // Events carry canonical paths, e.g. under /private on macOS
+        let root = root.canonicalize()?;
+        let watched_root = root.clone();
+        let mut rules = IgnoreRules::load(&root);
+
+        let mut debouncer =
+            new_debouncer(DEBOUNCE_INTERVAL, move |result: DebounceEventResult| {
+                let events = match result {
+                    Ok(events) => events,
+                    Err(e) => {
+                        log::warn!("File watcher error: {}", e);
+                        return;
+                    }
+                };
+                let paths: Vec<PathBuf> = events.into_iter().map(|event| event.path).collect();
+
+                // Pick up edited ignore rules before deciding what to report
+                if paths.iter().any(|p| {
+                    p.ends_with(".gitignore")
+                        || p.ends_with(".ignore")
+                        || p.ends_with(".git/info/exclude")
+                }) {
+                    rules = IgnoreRules::load(&watched_root);
+                }
+                if let Some(mut change) = classify_changes(&watched_root, &rules, &paths) {
+                    change.root = watched_root.to_string_lossy().to_string();
+                    on_change(change);
+                }
+            })?;
+        debouncer.watcher().watch(&root, RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            root,
+            _debouncer: debouncer,
+        })
+    }
+
+    /// The directory being watched
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_classify_changes_skips_ignored_paths() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::create_dir_all(root.join("web")).unwrap();
+        fs::write(root.join("web/.gitignore"), "/dist\n").unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+
+        let rules = IgnoreRules::load(root);
+        let paths: Vec<PathBuf> = [
+            "src/main.rs",
+            "src/old.rs",
+            "target/debug/app",
+            "debug.log",
+            "web/dist/app.js",
+            "dist/notes.md",
+            ".git/index",
+            "src/main.rs",
+        ]
+        .iter()
+        .map(|p| root.join(p))
+        .collect();
+
+        assert_eq!(
+            classify_changes(root, &rules, &paths),
+            Some(FsChange {
+                changed: vec!["src/main.rs".to_string()],
+                removed: vec!["dist/notes.md".to_string(), "src/old.rs".to_string()],
+                git: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            classify_changes(root, &rules, &[root.join(".git/objects/ab/cdef")]),
+            None
+        );
+        let refs = classify_changes(root, &rules, &[root.join(".git/refs/heads/main")]).unwrap();
+        assert!(refs.git && !refs.has_files());
+        assert_eq!(
+            classify_changes(root, &rules, &[root.join("debug.log")]),
+            None
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Workspaces with several project roots
+//!
+//! A workspace is a list of directories opened together, such as a frontend
+//! and a backend repository. The first root is the primary one: project
+//! instructions, memory and the semantic index belong to it, and relative
+//! paths resolve against it. Search, listing and file watching span every
+//! root.
+
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+/// Errors that can occur while changing a workspace's roots
+#[derive(Debug, Error, PartialEq)]
+pub enum WorkspaceError {
+    #[error("Path is not a directory: {0}")]
+    NotADirectory(String),
+
+    #[error("{0} is already in the workspace")]
+    AlreadyAdded(String),
+
+    #[error("{0} overlaps the workspace root {1}")]
+    Overlaps(String, String),
+
+    #[error("{0} is not a workspace root")]
+    NotFound(String),
+}
+
+/// The directories open in the app
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Workspace {
+    roots: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// The primary root, if any directory is open
+    pub fn primary(&self) -> Option<&Path> {
+        self.roots.first().map(PathBuf::as_path)
+    }
+
+    /// Every root, primary first
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Open a single directory, replacing every root
+    pub fn set_primary(&mut self, root: PathBuf) {
+        self.roots = vec![root];
+    }
+
+    /// Add a root
+    ///
+    /// A directory inside, or containing, an existing root is refused, since
+    /// its files would be searched and watched twice.
+    pub fn add_root(&mut self, root: PathBuf) -> Result<(), WorkspaceError> {
+        if !root.is_dir() {
+            return Err(WorkspaceError::NotADirectory(root.display().to_string()));
+        }
+        if self.roots.contains(&root) {
+            return Err(WorkspaceError::AlreadyAdded(root.display().to_string()));
+        }
+        if let Some(existing) = self
+            .roots
+            .iter()
+            .find(|r| root.starts_with(r) || r.starts_with(&root))
+        {
+            return Err(WorkspaceError::Overlaps(
+                root.display().to_string(),
+                existing.display().to_string(),
+            ));
+        }
+        self.roots.push(root);
+        Ok(())
+    }
+
+    /// Remove a root; removing the primary root makes the next one primary
+    pub fn remove_root(&mut self, root: &Path) -> Result<(), WorkspaceError> {
+        let index = self
+            .roots
+            .iter()
+            .position(|r| r == root)
+            .ok_or_else(|| WorkspaceError::NotFound(root.display().to_string()))?;
+        self.roots.remove(index);
+        Ok(())
+    }
+
+    /// The root a path is in, if any
+    pub fn root_for(&self, path: &Path) -> Option<&Path> {
+        self.roots
+            .iter()
+            .find(|root| path.starts_with(root))
+            .map(PathBuf::as_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_workspace_roots() {
+        let dir = tempdir().unwrap();
+        let web = dir.path().join("web");
+        let api = dir.path().join("api");
+        fs::create_dir_all(web.join("src")).unwrap();
+        fs::create_dir_all(&api).unwrap();
+
+        let mut workspace = Workspace::default();
+        workspace.set_primary(web.clone());
+        workspace.add_root(api.clone()).unwrap();
+        assert_eq!(
+            workspace.add_root(api.clone()),
+            Err(WorkspaceError::AlreadyAdded(api.display().to_string()))
+        );
+        assert!(matches!(
+            workspace.add_root(web.join("src")),
+            Err(WorkspaceError::Overlaps(..))
+        ));
+        assert!(workspace.add_root(dir.path().join("missing")).is_err());
+        assert_eq!(
+            workspace.root_for(&api.join("main.rs")),
+            Some(api.as_path())
+        );
+
+        workspace.remove_root(&web).unwrap();
+        assert_eq!(workspace.primary(), Some(api.as_path()));
+        assert!(workspace.remove_root(&web).is_err());
+    }
+}